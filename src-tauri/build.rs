@@ -1,3 +1,39 @@
+use std::process::Command;
+
 fn main() {
+    // Commit the app was built from, for the About panel; CI building from a
+    // source tarball can pass it in `QWIK_ASK_GIT_COMMIT` instead
+    println!("cargo:rerun-if-env-changed=QWIK_ASK_GIT_COMMIT");
+    let commit = std::env::var("QWIK_ASK_GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.trim().is_empty())
+        .or_else(git_commit)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=QWIK_ASK_GIT_COMMIT={}", commit.trim());
+    println!(
+        "cargo:rustc-env=QWIK_ASK_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+
     tauri_build::build()
 }
+
+/// Short hash of `HEAD`, if building from a git checkout.
+fn git_commit() -> Option<String> {
+    let git_dir = git(&["rev-parse", "--git-dir"])?;
+    // Rebuild when a commit is made or another branch is checked out
+    println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+    if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+        println!("cargo:rerun-if-changed={}/{}", git_dir, branch);
+    }
+    git(&["rev-parse", "--short=10", "HEAD"])
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
@@ -0,0 +1,179 @@
+//! Version, build and runtime information.
+//!
+//! Shown in the settings "About" panel and included in the diagnostics
+//! report. The commit hash and target triple are embedded by `build.rs`.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const info = await invoke<AppInfo>('get_app_info');
+//! // { version: '1.4.0', profile: 'release', commit: '1a2b3c4d5e',
+//! //   tauri_version: '2.9.5', webview_version: '131.0.2903.86',
+//! //   target: 'x86_64-pc-windows-msvc', data_dir: 'C:\\Users\\...',
+//! //   portable: false, log_dir: '...', database_path: '...', uptime_secs: 3600 }
+//! ```
+
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{db, logging, paths};
+
+/// Short hash of the commit the app was built from, or `"unknown"` when
+/// built outside a git checkout.
+pub const GIT_COMMIT: &str = env!("QWIK_ASK_GIT_COMMIT");
+
+/// Target triple the app was built for, e.g. `aarch64-apple-darwin`.
+pub const BUILD_TARGET: &str = env!("QWIK_ASK_TARGET");
+
+/// When the app started, managed as Tauri state.
+#[derive(Debug)]
+pub struct LaunchTime(Instant);
+
+impl Default for LaunchTime {
+    fn default() -> Self {
+        Self(Instant::now())
+    }
+}
+
+impl LaunchTime {
+    /// Whole seconds since launch.
+    pub fn uptime_secs(&self) -> u64 {
+        self.0.elapsed().as_secs()
+    }
+}
+
+/// Version, build and runtime information.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppInfo {
+    /// App version from `Cargo.toml`
+    pub version: String,
+    /// `"debug"` or `"release"`
+    pub profile: String,
+    /// See [`GIT_COMMIT`]
+    pub commit: String,
+    pub tauri_version: String,
+    /// `None` if the webview runtime couldn't be queried
+    pub webview_version: Option<String>,
+    /// See [`BUILD_TARGET`]
+    pub target: String,
+    /// App data directory (logs, backups, screenshots)
+    pub data_dir: Option<String>,
+    /// Whether the data lives next to the executable (portable mode)
+    pub portable: bool,
+    /// Directory of the log files
+    pub log_dir: Option<String>,
+    /// History database file
+    pub database_path: Option<String>,
+    /// Seconds since the app started
+    pub uptime_secs: u64,
+}
+
+/// Collect the app info.
+///
+/// Paths that can't be resolved are `None`.
+pub fn app_info(app: &AppHandle) -> AppInfo {
+    let path = |path: Result<std::path::PathBuf, String>| {
+        path.ok().map(|path| path.to_string_lossy().to_string())
+    };
+    AppInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        profile: build_profile().to_string(),
+        commit: GIT_COMMIT.to_string(),
+        tauri_version: tauri::VERSION.to_string(),
+        webview_version: tauri::webview_version().ok(),
+        target: BUILD_TARGET.to_string(),
+        data_dir: path(paths::data_dir(app)),
+        portable: paths::is_portable(),
+        log_dir: path(logging::logs_dir(app)),
+        database_path: path(db::database_path(app)),
+        uptime_secs: app
+            .try_state::<LaunchTime>()
+            .map_or(0, |launch| launch.uptime_secs()),
+    }
+}
+
+/// The Cargo profile the app was built with.
+pub fn build_profile() -> &'static str {
+    if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    }
+}
+
+/// Get version, build and runtime information for the About panel.
+#[tauri::command]
+pub fn get_app_info(app: AppHandle) -> AppInfo {
+    app_info(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> AppInfo {
+        AppInfo {
+            version: "1.4.0".to_string(),
+            profile: "release".to_string(),
+            commit: "1a2b3c4d5e".to_string(),
+            tauri_version: "2.9.5".to_string(),
+            webview_version: None,
+            target: "x86_64-unknown-linux-gnu".to_string(),
+            data_dir: Some("/home/me/.local/share/com.qwikask.app".to_string()),
+            portable: false,
+            log_dir: None,
+            database_path: None,
+            uptime_secs: 42,
+        }
+    }
+
+    // ===== Serialization =====
+
+    #[test]
+    fn test_app_info_serialization() {
+        let json = serde_json::to_value(sample()).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "version": "1.4.0",
+                "profile": "release",
+                "commit": "1a2b3c4d5e",
+                "tauri_version": "2.9.5",
+                "webview_version": null,
+                "target": "x86_64-unknown-linux-gnu",
+                "data_dir": "/home/me/.local/share/com.qwikask.app",
+                "portable": false,
+                "log_dir": null,
+                "database_path": null,
+                "uptime_secs": 42
+            })
+        );
+    }
+
+    // ===== Build =====
+
+    #[test]
+    fn test_commit_is_embedded() {
+        assert!(
+            GIT_COMMIT == "unknown"
+                || (GIT_COMMIT.len() >= 7 && GIT_COMMIT.chars().all(|c| c.is_ascii_hexdigit())),
+            "unexpected commit {:?}",
+            GIT_COMMIT
+        );
+    }
+
+    #[test]
+    fn test_target_is_embedded() {
+        assert!(BUILD_TARGET.contains(std::env::consts::ARCH));
+    }
+
+    #[test]
+    fn test_uptime_counts_from_launch() {
+        let launch = LaunchTime(Instant::now() - std::time::Duration::from_secs(90));
+
+        assert_eq!(launch.uptime_secs(), 90);
+    }
+}
@@ -0,0 +1,569 @@
+//! Command-line arguments.
+//!
+//! ```text
+//! qwik-ask [--query <text>] [--submit] [--hidden] [--settings [<section>]]
+//!          [--conversation <id>] [--portable] [qwikask://...]
+//! qwik-ask --version | --help
+//! ```
+//!
+//! [`parse`] turns the arguments into a [`LaunchIntent`] without touching
+//! the app; [`execute`] carries it out once setup has finished. Launching
+//! again while the app runs doesn't start a second app: the single-instance
+//! plugin hands the new process's arguments to [`forwarded`], which carries
+//! them out in the running one. Values can
+//! follow their flag or be joined with `=`; use `--query=<text>` for text
+//! that itself starts with `--`. `qwikask://` links among the arguments
+//! are opened with [`crate::deeplink::open`].
+//!
+//! Unknown flags and missing values print the usage to stderr and are
+//! otherwise ignored, so a bad argument never keeps the app from starting.
+//! `--version` and `--help` print and exit before the Tauri builder runs.
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::clipboard::{self, PrefillSource};
+use crate::deeplink;
+use crate::notifications::OpenConversation;
+use crate::settings::SettingsManager;
+use crate::window::{self, visibility::VisibilityReason};
+
+/// Printed for `--help` and after a bad argument.
+pub const USAGE: &str = "Usage: qwik-ask [options] [qwikask://...]
+
+Options:
+  -q, --query <text>       Open the launcher with <text> as the prompt
+      --submit             Send the --query prompt right away
+      --hidden             Don't show the launcher for --query or --conversation
+      --settings [section] Open the settings window, at a section if given
+      --conversation <id>  Open a conversation in the launcher
+      --portable           Keep settings and history in data/ next to the executable
+  -V, --version            Print the version and exit
+  -h, --help               Print this help and exit";
+
+/// What the app was launched to do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchIntent {
+    /// Prompt to prefill
+    pub query: Option<String>,
+    /// Send the prompt without waiting for Enter
+    pub submit: bool,
+    /// Deliver the query or conversation without showing the launcher
+    pub hidden: bool,
+    /// Open the settings window
+    pub open_settings: bool,
+    /// Section to open the settings window at
+    pub settings_section: Option<String>,
+    /// Conversation to open
+    pub conversation: Option<String>,
+    /// `qwikask://` links to open
+    pub links: Vec<String>,
+    /// Keep the data beside the executable (see [`crate::paths`])
+    pub portable: bool,
+}
+
+/// What to do with the arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    /// Start the app
+    Launch(LaunchIntent),
+    /// Print the version and exit
+    Version,
+    /// Print the usage and exit
+    Help,
+}
+
+/// Parsed arguments, with the problems found in them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedArgs {
+    pub command: CliCommand,
+    /// One message per ignored argument
+    pub warnings: Vec<String>,
+}
+
+/// Parse command-line arguments.
+///
+/// # Arguments
+///
+/// * `args` - Arguments after the program name
+///
+/// # Returns
+///
+/// The command, plus a warning for each argument that was ignored.
+/// Repeated flags keep the last value.
+pub fn parse<I, S>(args: I) -> ParsedArgs
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut intent = LaunchIntent::default();
+    let mut warnings = Vec::new();
+    let mut args = args.into_iter().map(Into::into).peekable();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        // The flag's value: after `=`, or the next argument unless that's a flag
+        let mut value = |required: bool| -> Option<String> {
+            if let Some(value) = inline {
+                return Some(value.to_string());
+            }
+            match args.peek() {
+                Some(next) if !next.starts_with("--") && !deeplink::is_deep_link(next) => {
+                    args.next()
+                }
+                _ => {
+                    if required {
+                        warnings.push(format!("{} needs a value", flag));
+                    }
+                    None
+                }
+            }
+        };
+
+        match flag.as_str() {
+            "-V" | "--version" => {
+                return ParsedArgs {
+                    command: CliCommand::Version,
+                    warnings,
+                }
+            }
+            "-h" | "--help" => {
+                return ParsedArgs {
+                    command: CliCommand::Help,
+                    warnings,
+                }
+            }
+            "-q" | "--query" => {
+                if let Some(query) = value(true) {
+                    intent.query = Some(query);
+                }
+            }
+            "--conversation" => match value(true) {
+                Some(id) if deeplink::is_conversation_id(&id) => intent.conversation = Some(id),
+                Some(id) => warnings.push(format!("Invalid conversation ID '{}'", id)),
+                None => {}
+            },
+            "--settings" => {
+                intent.open_settings = true;
+                match value(false).map(|s| s.to_ascii_lowercase()) {
+                    Some(section) if deeplink::is_section_name(&section) => {
+                        intent.settings_section = Some(section)
+                    }
+                    Some(section) => {
+                        warnings.push(format!("Invalid settings section '{}'", section))
+                    }
+                    None => intent.settings_section = None,
+                }
+            }
+            "--submit" | "--hidden" | "--portable" if inline.is_some() => {
+                warnings.push(format!("{} doesn't take a value", flag));
+            }
+            "--submit" => intent.submit = true,
+            "--hidden" => intent.hidden = true,
+            "--portable" => intent.portable = true,
+            _ if deeplink::is_deep_link(&arg) => intent.links.push(arg),
+            _ if arg.starts_with('-') => warnings.push(format!("Unknown option '{}'", flag)),
+            _ => warnings.push(format!("Unexpected argument '{}'", arg)),
+        }
+    }
+
+    if intent.submit && intent.query.is_none() {
+        warnings.push("--submit needs --query".to_string());
+        intent.submit = false;
+    }
+    ParsedArgs {
+        command: CliCommand::Launch(intent),
+        warnings,
+    }
+}
+
+/// Parse the process's arguments, handling `--version` and `--help`.
+///
+/// Problems are printed to stderr along with the usage.
+///
+/// # Returns
+///
+/// The intent to carry out, or `None` if the process should exit.
+pub fn from_env() -> Option<LaunchIntent> {
+    let parsed = parse(std::env::args().skip(1));
+    for warning in &parsed.warnings {
+        eprintln!("qwik-ask: {}", warning);
+    }
+    if !parsed.warnings.is_empty() {
+        eprintln!("{}", USAGE);
+    }
+    match parsed.command {
+        CliCommand::Launch(intent) => Some(intent),
+        CliCommand::Version => {
+            println!("qwik-ask {}", env!("CARGO_PKG_VERSION"));
+            None
+        }
+        CliCommand::Help => {
+            println!("{}", USAGE);
+            None
+        }
+    }
+}
+
+/// The intent of a second launch's arguments.
+///
+/// `--portable` is dropped, since the running app's data can't move.
+///
+/// # Arguments
+///
+/// * `argv` - The second process's arguments, program name first
+///
+/// # Returns
+///
+/// `None` for `--version` and `--help`, which the second process has
+/// already handled.
+fn forwarded_intent(argv: Vec<String>) -> Option<LaunchIntent> {
+    let parsed = parse(argv.into_iter().skip(1));
+    for warning in &parsed.warnings {
+        tracing::warn!("Ignoring forwarded argument: {}", warning);
+    }
+    match parsed.command {
+        CliCommand::Launch(intent) => Some(LaunchIntent {
+            portable: false,
+            ..intent
+        }),
+        CliCommand::Version | CliCommand::Help => None,
+    }
+}
+
+/// Carry out the arguments of a second launch in the running app.
+///
+/// Without arguments the launcher is shown, as if the shortcut was pressed.
+pub fn forwarded(app: &AppHandle, argv: Vec<String>) {
+    match forwarded_intent(argv) {
+        Some(intent) if intent == LaunchIntent::default() => {
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || {
+                window::show_launcher(&handle, VisibilityReason::CommandLine);
+            });
+        }
+        Some(intent) => execute(app, intent),
+        None => {}
+    }
+}
+
+/// Carry out a launch intent once the event loop is running.
+pub fn execute(app: &AppHandle, intent: LaunchIntent) {
+    if intent == LaunchIntent::default() {
+        return;
+    }
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || run_intent(&handle, intent));
+}
+
+fn run_intent(app: &AppHandle, intent: LaunchIntent) {
+    if intent.open_settings {
+        if let Err(e) = window::open_settings_window(app, intent.settings_section) {
+            tracing::warn!(error = %e, "Failed to open settings");
+        }
+    }
+
+    if let Some(conversation_id) = intent.conversation {
+        if !intent.hidden {
+            window::show_launcher(app, VisibilityReason::CommandLine);
+        }
+        let _ = app.emit("open-conversation", OpenConversation { conversation_id });
+    }
+
+    if let Some(query) = intent.query {
+        let max_chars = app
+            .state::<SettingsManager>()
+            .snapshot()
+            .unwrap_or_default()
+            .launcher
+            .clipboard_max_chars;
+        let prompt = clipboard::prefill_prompt(Some(&query), max_chars, PrefillSource::CommandLine)
+            .map(|prompt| clipboard::PrefillPrompt {
+                submit: intent.submit,
+                ..prompt
+            });
+        if intent.hidden {
+            if let Some(prompt) = prompt {
+                let _ = app.emit("prefill-prompt", prompt);
+            }
+        } else {
+            clipboard::show_prefilled(app, VisibilityReason::CommandLine, prompt);
+        }
+    }
+
+    for link in &intent.links {
+        deeplink::open(app, link);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(args: &[&str]) -> LaunchIntent {
+        let parsed = parse(args.iter().copied());
+        assert_eq!(parsed.warnings, Vec::<String>::new());
+        match parsed.command {
+            CliCommand::Launch(intent) => intent,
+            other => panic!("expected a launch, got {:?}", other),
+        }
+    }
+
+    fn warnings(args: &[&str]) -> Vec<String> {
+        parse(args.iter().copied()).warnings
+    }
+
+    // ===== Flags =====
+
+    #[test]
+    fn test_no_arguments() {
+        assert_eq!(intent(&[]), LaunchIntent::default());
+    }
+
+    #[test]
+    fn test_query_and_submit() {
+        let intent = intent(&["--query", "explain awk", "--submit"]);
+
+        assert_eq!(intent.query.as_deref(), Some("explain awk"));
+        assert!(intent.submit);
+        assert!(!intent.hidden);
+    }
+
+    #[test]
+    fn test_short_query_flag() {
+        assert_eq!(intent(&["-q", "hi"]).query.as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn test_hidden() {
+        assert!(intent(&["--hidden"]).hidden);
+    }
+
+    #[test]
+    fn test_portable() {
+        assert!(intent(&["--portable"]).portable);
+        assert!(!intent(&[]).portable);
+    }
+
+    #[test]
+    fn test_settings_with_and_without_section() {
+        let with_section = intent(&["--settings", "LLM"]);
+        let without = intent(&["--settings"]);
+
+        assert!(with_section.open_settings);
+        assert_eq!(with_section.settings_section.as_deref(), Some("llm"));
+        assert!(without.open_settings);
+        assert_eq!(without.settings_section, None);
+    }
+
+    #[test]
+    fn test_conversation() {
+        assert_eq!(
+            intent(&["--conversation", "0190a1b2-c3d4"])
+                .conversation
+                .as_deref(),
+            Some("0190a1b2-c3d4")
+        );
+    }
+
+    #[test]
+    fn test_combined_flags() {
+        let intent = intent(&[
+            "--hidden",
+            "--settings",
+            "--query=what is rust",
+            "--submit",
+            "--conversation",
+            "abc",
+            "qwikask://ask?q=hi",
+        ]);
+
+        assert_eq!(
+            intent,
+            LaunchIntent {
+                query: Some("what is rust".to_string()),
+                submit: true,
+                hidden: true,
+                open_settings: true,
+                settings_section: None,
+                conversation: Some("abc".to_string()),
+                links: vec!["qwikask://ask?q=hi".to_string()],
+                portable: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_repeated_flag_keeps_last() {
+        assert_eq!(
+            intent(&["--query", "one", "--query", "two"])
+                .query
+                .as_deref(),
+            Some("two")
+        );
+    }
+
+    #[test]
+    fn test_version_and_help() {
+        assert_eq!(parse(["--version"]).command, CliCommand::Version);
+        assert_eq!(parse(["-V"]).command, CliCommand::Version);
+        assert_eq!(parse(["--hidden", "--help"]).command, CliCommand::Help);
+        assert_eq!(parse(["-h", "--version"]).command, CliCommand::Help);
+    }
+
+    // ===== Quoting =====
+
+    #[test]
+    fn test_inline_values() {
+        let intent = intent(&["--query=a=b", "--settings=general"]);
+
+        assert_eq!(intent.query.as_deref(), Some("a=b"));
+        assert_eq!(intent.settings_section.as_deref(), Some("general"));
+    }
+
+    #[test]
+    fn test_query_keeps_quotes_and_spaces() {
+        let query = "  say \"hi\" --like 'this'  ";
+
+        assert_eq!(intent(&["--query", query]).query.as_deref(), Some(query));
+    }
+
+    #[test]
+    fn test_query_starting_with_dashes_needs_equals() {
+        assert_eq!(
+            intent(&["--query=--verbose means what?"]).query.as_deref(),
+            Some("--verbose means what?")
+        );
+        assert_eq!(
+            intent(&["--query", "-5 degrees in F"]).query.as_deref(),
+            Some("-5 degrees in F")
+        );
+    }
+
+    #[test]
+    fn test_empty_inline_value() {
+        assert_eq!(intent(&["--query="]).query.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_unicode_query() {
+        assert_eq!(
+            intent(&["--query", "ça va? 日本語"]).query.as_deref(),
+            Some("ça va? 日本語")
+        );
+    }
+
+    // ===== Bad arguments =====
+
+    #[test]
+    fn test_unknown_flags_are_ignored() {
+        let parsed = parse(["--frobnicate", "--hidden", "-x"]);
+
+        assert_eq!(
+            parsed.warnings,
+            vec!["Unknown option '--frobnicate'", "Unknown option '-x'"]
+        );
+        assert!(matches!(parsed.command, CliCommand::Launch(i) if i.hidden));
+    }
+
+    #[test]
+    fn test_unknown_flag_with_value() {
+        assert_eq!(
+            warnings(&["--theme=dark"]),
+            vec!["Unknown option '--theme'"]
+        );
+    }
+
+    #[test]
+    fn test_missing_values() {
+        assert_eq!(warnings(&["--query"]), vec!["--query needs a value"]);
+        assert_eq!(
+            warnings(&["--conversation", "--hidden"]),
+            vec!["--conversation needs a value"]
+        );
+        let parsed = parse(["--query", "--submit"]);
+        assert_eq!(
+            parsed.warnings,
+            vec!["--query needs a value", "--submit needs --query"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        assert_eq!(
+            warnings(&["--conversation", "a b"]),
+            vec!["Invalid conversation ID 'a b'"]
+        );
+        assert_eq!(
+            warnings(&["--settings", "../etc"]),
+            vec!["Invalid settings section '../etc'"]
+        );
+    }
+
+    #[test]
+    fn test_flags_without_values_reject_them() {
+        assert_eq!(
+            warnings(&["--submit=yes", "--hidden=1"]),
+            vec![
+                "--submit doesn't take a value",
+                "--hidden doesn't take a value"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stray_arguments() {
+        assert_eq!(
+            warnings(&["explain", "awk"]),
+            vec!["Unexpected argument 'explain'", "Unexpected argument 'awk'"]
+        );
+    }
+
+    #[test]
+    fn test_settings_does_not_take_a_link() {
+        let intent = intent(&["--settings", "qwikask://settings/llm"]);
+
+        assert_eq!(intent.settings_section, None);
+        assert_eq!(intent.links, vec!["qwikask://settings/llm"]);
+    }
+
+    // ===== Second launch =====
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        std::iter::once("qwik-ask")
+            .chain(args.iter().copied())
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_forwarded_skips_program_name() {
+        let intent = forwarded_intent(argv(&["--query", "explain awk", "--hidden"])).unwrap();
+
+        assert_eq!(intent.query.as_deref(), Some("explain awk"));
+        assert!(intent.hidden);
+    }
+
+    #[test]
+    fn test_forwarded_link() {
+        let intent = forwarded_intent(argv(&["qwikask://ask?q=hi"])).unwrap();
+
+        assert_eq!(intent.links, vec!["qwikask://ask?q=hi"]);
+    }
+
+    #[test]
+    fn test_forwarded_plain_launch_is_empty() {
+        assert_eq!(forwarded_intent(argv(&[])), Some(LaunchIntent::default()));
+        assert_eq!(
+            forwarded_intent(argv(&["--portable"])),
+            Some(LaunchIntent::default())
+        );
+    }
+
+    #[test]
+    fn test_forwarded_version_is_ignored() {
+        assert_eq!(forwarded_intent(argv(&["--version"])), None);
+    }
+}
@@ -0,0 +1,253 @@
+//! Capturing a screen region as an image for the prompt.
+//!
+//! `capture_screen_region` hides the Qwik Ask windows, captures a region
+//! and returns it as a PNG with its dimensions. Without a region the OS
+//! tool lets the user select one; with a region (drawn on a selection
+//! overlay, see [`region::ScreenRegion`]) that part of the screen is
+//! captured directly.
+//!
+//! # Platforms
+//!
+//! - **macOS**: `screencapture`, after checking Screen Recording access
+//! - **Linux**: region capture through GDK on X11; interactive selection
+//!   through the first installed of gnome-screenshot, spectacle, maim or
+//!   grim with slurp
+//! - **Windows**: interactive selection through the Snipping Tool, which
+//!   copies the region to the clipboard; region capture by copying from the
+//!   screen through PowerShell
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! try {
+//!   const image = await invoke<CapturedImage>('capture_screen_region', { region: null });
+//!   preview.src = `data:${image.mime};base64,${image.data}`;
+//!   // Record the screenshot, bytes included, with the message it's sent in
+//!   await invoke('append_message', { conversationId, role: 'user', content, attachments: [image] });
+//!   const answer = await invoke<LlmResponse>('ask_llm_with_image', { messages, image, conversationId });
+//! } catch (e) {
+//!   if (e.kind === 'permission_denied') showScreenRecordingHelp();
+//!   else if (e.kind !== 'cancelled') showError(e.detail);
+//! }
+//! ```
+
+mod native;
+pub mod region;
+
+use std::time::Duration;
+
+use base64::Engine;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+
+use crate::window;
+use region::ScreenRegion;
+
+/// Time for the compositor to remove hidden windows from the screen.
+const HIDE_DELAY: Duration = Duration::from_millis(200);
+
+/// A captured screen region.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CapturedImage {
+    /// Name used when the image is recorded as an attachment
+    pub name: String,
+    /// PNG size in bytes
+    pub size: u64,
+    /// Dimensions in physical pixels
+    pub width: u32,
+    pub height: u32,
+    pub mime: &'static str,
+    /// The PNG, base64-encoded
+    pub data: String,
+}
+
+/// Why a capture failed.
+///
+/// Serializes with a `kind` tag like [`crate::llm::LlmError`].
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum CaptureError {
+    /// The user dismissed the selection
+    #[error("Screen capture was cancelled")]
+    Cancelled,
+    /// Screen Recording access hasn't been granted (macOS)
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    #[error("Screen recording permission is required")]
+    PermissionDenied,
+    /// The region is too small or on a monitor that isn't connected
+    #[error("Invalid screen region")]
+    InvalidRegion,
+    /// No way to capture on this platform or desktop
+    #[error("Screen capture is not supported: {0}")]
+    Unsupported(String),
+    /// The capture or its image couldn't be read
+    #[error("Screen capture failed: {0}")]
+    Failed(String),
+}
+
+/// Width and height from a PNG's header.
+pub fn png_dimensions(png: &[u8]) -> Option<(u32, u32)> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if png.len() < 24 || !png.starts_with(SIGNATURE) || &png[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(png[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(png[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+/// Wrap PNG bytes for the frontend.
+fn captured_image(png: Vec<u8>, name: String) -> Result<CapturedImage, CaptureError> {
+    let (width, height) = png_dimensions(&png)
+        .ok_or_else(|| CaptureError::Failed("the capture is not a PNG image".to_string()))?;
+    Ok(CapturedImage {
+        name,
+        size: png.len() as u64,
+        width,
+        height,
+        mime: "image/png",
+        data: base64::engine::general_purpose::STANDARD.encode(&png),
+    })
+}
+
+/// Hide the visible Qwik Ask windows, returning their labels.
+fn hide_windows(app: &AppHandle) -> Vec<String> {
+    app.webview_windows()
+        .into_iter()
+        .filter(|(_, window)| window.is_visible().unwrap_or(false))
+        .filter_map(|(label, window)| window.hide().ok().map(|_| label))
+        .collect()
+}
+
+/// Show the windows hidden by [`hide_windows`] again.
+fn restore_windows(app: &AppHandle, labels: &[String]) {
+    for label in labels {
+        if let Some(window) = app.get_webview_window(label) {
+            let _ = window.show();
+        }
+    }
+    if let Some(main) = labels
+        .iter()
+        .find(|label| *label == "main")
+        .and_then(|label| app.get_webview_window(label))
+    {
+        let _ = main.set_focus();
+    }
+}
+
+/// Capture a region of the screen as a PNG.
+///
+/// The Qwik Ask windows are hidden during the capture and shown again
+/// afterwards.
+///
+/// # Arguments
+///
+/// * `region` - Region selected on the overlay, or `None` to let the user
+///   select one with the OS tool
+///
+/// # Returns
+///
+/// * `Ok(CapturedImage)` - The PNG and its dimensions
+/// * `Err(CaptureError)` - Cancelled, not permitted, or not possible here
+#[tauri::command]
+pub async fn capture_screen_region(
+    app: AppHandle,
+    region: Option<ScreenRegion>,
+) -> Result<CapturedImage, CaptureError> {
+    let target = match region {
+        Some(region) => {
+            let monitors = window::monitor_areas(&app);
+            let rect =
+                region::physical_rect(&monitors, &region).ok_or(CaptureError::InvalidRegion)?;
+            let scale_factor = monitors
+                .iter()
+                .find(|m| m.bounds.x == region.monitor_x && m.bounds.y == region.monitor_y)
+                .map_or(1.0, |m| m.scale_factor);
+            Some((rect, scale_factor))
+        }
+        None => None,
+    };
+    native::check_permission()?;
+
+    let hidden = hide_windows(&app);
+    tokio::time::sleep(HIDE_DELAY).await;
+    let png = match target {
+        Some((rect, scale_factor)) => native::capture_rect(&app, rect, scale_factor).await,
+        None => tauri::async_runtime::spawn_blocking(native::capture_interactive)
+            .await
+            .unwrap_or_else(|e| Err(CaptureError::Failed(e.to_string()))),
+    };
+    restore_windows(&app, &hidden);
+
+    let name = format!("Screenshot {}.png", crate::db::now_ms());
+    captured_image(png?, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The start of a PNG, enough for [`png_dimensions`].
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend(width.to_be_bytes());
+        png.extend(height.to_be_bytes());
+        png.extend([8, 6, 0, 0, 0]);
+        png
+    }
+
+    #[test]
+    fn test_png_dimensions() {
+        assert_eq!(png_dimensions(&png_header(3840, 2160)), Some((3840, 2160)));
+        assert_eq!(png_dimensions(b"GIF89a"), None);
+        assert_eq!(png_dimensions(&png_header(1, 1)[..20]), None);
+    }
+
+    #[test]
+    fn test_captured_image_metadata() {
+        let png = png_header(3, 2);
+
+        let image = captured_image(png.clone(), "shot.png".to_string()).unwrap();
+
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image.size, png.len() as u64);
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD
+                .decode(&image.data)
+                .unwrap(),
+            png
+        );
+    }
+
+    #[test]
+    fn test_captured_image_is_an_attachment() {
+        let image = captured_image(png_header(3, 2), "shot.png".to_string()).unwrap();
+
+        let attachment: crate::history::NewAttachment =
+            serde_json::from_value(serde_json::to_value(&image).unwrap()).unwrap();
+
+        assert_eq!(attachment.name, "shot.png");
+        assert_eq!(attachment.line_count, 0);
+        assert_eq!(attachment.mime.as_deref(), Some("image/png"));
+        assert_eq!(attachment.data, Some(image.data));
+    }
+
+    #[test]
+    fn test_captured_image_is_an_image_part() {
+        let image = captured_image(png_header(3, 2), "shot.png".to_string()).unwrap();
+
+        let part: crate::llm::types::ImagePart =
+            serde_json::from_value(serde_json::to_value(&image).unwrap()).unwrap();
+
+        assert_eq!(part.mime, "image/png");
+        assert_eq!(part.data, image.data);
+    }
+
+    #[test]
+    fn test_error_serialization() {
+        let json = serde_json::to_value(CaptureError::PermissionDenied).unwrap();
+
+        assert_eq!(json["kind"], "permission_denied");
+    }
+}
@@ -0,0 +1,317 @@
+//! Capturing the screen with each platform's tools.
+//!
+//! `screencapture` on macOS; GDK on X11 for a given region and the
+//! desktop's screenshot tool for interactive selection on Linux. On
+//! Windows the Snipping Tool selects a region and copies it to the
+//! clipboard, and a given region is copied from the screen through
+//! PowerShell. Other platforms return `CaptureError::Unsupported`.
+
+use tauri::AppHandle;
+
+use super::CaptureError;
+use crate::window::placement::Rect;
+
+/// A temporary file for a capture tool to write to.
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+fn temp_png() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("qwik-ask-capture-{}.png", uuid::Uuid::new_v4()))
+}
+
+/// Read and delete the file a capture tool wrote.
+///
+/// Tools exit without writing it when the selection is dismissed.
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+fn take_png(path: &std::path::Path) -> Result<Vec<u8>, CaptureError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(CaptureError::Cancelled),
+        Err(e) => return Err(CaptureError::Failed(e.to_string())),
+    };
+    let _ = std::fs::remove_file(path);
+    if bytes.is_empty() {
+        return Err(CaptureError::Cancelled);
+    }
+    Ok(bytes)
+}
+
+/// Fail with `PermissionDenied` unless the app may record the screen.
+///
+/// Asks for access the first time, which opens System Settings; the app
+/// has to be restarted once it is granted.
+#[cfg(target_os = "macos")]
+pub fn check_permission() -> Result<(), CaptureError> {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    // SAFETY: both functions take no arguments and only query or request
+    // the Screen Recording permission.
+    unsafe {
+        if CGPreflightScreenCaptureAccess() {
+            return Ok(());
+        }
+        CGRequestScreenCaptureAccess();
+    }
+    Err(CaptureError::PermissionDenied)
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn check_permission() -> Result<(), CaptureError> {
+    Ok(())
+}
+
+/// Let the user select a region with the OS tool. Blocks until done.
+#[cfg(target_os = "macos")]
+pub fn capture_interactive() -> Result<Vec<u8>, CaptureError> {
+    let path = temp_png();
+    std::process::Command::new("screencapture")
+        .args(["-i", "-x", "-t", "png"])
+        .arg(&path)
+        .status()
+        .map_err(|e| CaptureError::Failed(format!("Failed to run screencapture: {}", e)))?;
+    take_png(&path)
+}
+
+/// Let the user select a region with the desktop's screenshot tool.
+/// Blocks until done.
+#[cfg(target_os = "linux")]
+pub fn capture_interactive() -> Result<Vec<u8>, CaptureError> {
+    use std::io::ErrorKind;
+    use std::process::Command;
+
+    /// Region-selection commands, each followed by the output path.
+    const TOOLS: &[(&str, &[&str])] = &[
+        ("gnome-screenshot", &["--area", "--file"]),
+        (
+            "spectacle",
+            &["--background", "--nonotify", "--region", "--output"],
+        ),
+        ("maim", &["--select"]),
+    ];
+
+    let path = temp_png();
+    for (tool, args) in TOOLS {
+        match Command::new(tool).args(*args).arg(&path).status() {
+            Ok(_) => return take_png(&path),
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(CaptureError::Failed(format!(
+                    "Failed to run {}: {}",
+                    tool, e
+                )))
+            }
+        }
+    }
+
+    // wlroots compositors: slurp selects, grim captures
+    let geometry = match Command::new("slurp").output() {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+        Ok(_) => return Err(CaptureError::Cancelled),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(CaptureError::Unsupported(
+                "install gnome-screenshot, spectacle, maim, or grim and slurp".to_string(),
+            ))
+        }
+        Err(e) => return Err(CaptureError::Failed(format!("Failed to run slurp: {}", e))),
+    };
+    Command::new("grim")
+        .args(["-g", &geometry])
+        .arg(&path)
+        .status()
+        .map_err(|e| CaptureError::Failed(format!("Failed to run grim: {}", e)))?;
+    take_png(&path)
+}
+
+/// How long the Snipping Tool may take before the capture counts as
+/// dismissed; it doesn't report that.
+#[cfg(target_os = "windows")]
+const SNIP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
+/// Run a PowerShell script with `path` as its only argument.
+#[cfg(target_os = "windows")]
+fn powershell(script: &str, path: &std::path::Path) -> Result<(), CaptureError> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let status = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-STA", "-Command"])
+        .arg(format!("& {{ {} }}", script))
+        .arg(path)
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()
+        .map_err(|e| CaptureError::Failed(format!("Failed to run PowerShell: {}", e)))?;
+    if !status.success() {
+        return Err(CaptureError::Failed(format!(
+            "PowerShell exited with {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// Let the user select a region with the Snipping Tool. Blocks until done.
+///
+/// The tool copies the selection to the clipboard, which is saved as a
+/// PNG once its contents change.
+#[cfg(target_os = "windows")]
+pub fn capture_interactive() -> Result<Vec<u8>, CaptureError> {
+    use windows_sys::Win32::System::DataExchange::GetClipboardSequenceNumber;
+
+    // SAFETY: takes no arguments and only reads a counter
+    let before = unsafe { GetClipboardSequenceNumber() };
+    std::process::Command::new("explorer")
+        .arg("ms-screenclip:")
+        .spawn()
+        .map_err(|e| CaptureError::Failed(format!("Failed to open the Snipping Tool: {}", e)))?;
+
+    let deadline = std::time::Instant::now() + SNIP_TIMEOUT;
+    // SAFETY: as above
+    while unsafe { GetClipboardSequenceNumber() } == before {
+        if std::time::Instant::now() > deadline {
+            return Err(CaptureError::Cancelled);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+
+    let path = temp_png();
+    powershell(
+        "param($path)
+         Add-Type -AssemblyName System.Windows.Forms, System.Drawing
+         $image = [System.Windows.Forms.Clipboard]::GetImage()
+         if ($image) { $image.Save($path, [System.Drawing.Imaging.ImageFormat]::Png) }",
+        &path,
+    )?;
+    // No file if something other than an image was copied meanwhile
+    take_png(&path)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+pub fn capture_interactive() -> Result<Vec<u8>, CaptureError> {
+    Err(CaptureError::Unsupported(
+        "screen capture isn't available on this platform yet".to_string(),
+    ))
+}
+
+/// Capture `rect` (physical desktop pixels).
+#[cfg(target_os = "macos")]
+pub async fn capture_rect(
+    _app: &AppHandle,
+    rect: Rect,
+    scale_factor: f64,
+) -> Result<Vec<u8>, CaptureError> {
+    // screencapture takes points, not pixels
+    let region = format!(
+        "{},{},{},{}",
+        rect.x as f64 / scale_factor,
+        rect.y as f64 / scale_factor,
+        rect.width as f64 / scale_factor,
+        rect.height as f64 / scale_factor
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = temp_png();
+        std::process::Command::new("screencapture")
+            .args(["-x", "-t", "png", "-R", &region])
+            .arg(&path)
+            .status()
+            .map_err(|e| CaptureError::Failed(format!("Failed to run screencapture: {}", e)))?;
+        take_png(&path)
+    })
+    .await
+    .unwrap_or_else(|e| Err(CaptureError::Failed(e.to_string())))
+}
+
+/// Capture `rect` (physical desktop pixels) from the X11 root window.
+///
+/// GDK may only be used on the main thread.
+#[cfg(target_os = "linux")]
+pub async fn capture_rect(
+    app: &AppHandle,
+    rect: Rect,
+    _scale_factor: f64,
+) -> Result<Vec<u8>, CaptureError> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app.run_on_main_thread(move || {
+        let _ = sender.send(capture_root_window(rect));
+    })
+    .map_err(|e| CaptureError::Failed(e.to_string()))?;
+    receiver
+        .await
+        .map_err(|e| CaptureError::Failed(e.to_string()))?
+}
+
+#[cfg(target_os = "linux")]
+fn capture_root_window(rect: Rect) -> Result<Vec<u8>, CaptureError> {
+    use gtk::gdk;
+    use gtk::gdk::prelude::*;
+
+    let display =
+        gdk::Display::default().ok_or_else(|| CaptureError::Failed("no display".to_string()))?;
+    // Wayland doesn't let clients read the screen
+    if !display.type_().name().contains("X11") {
+        return Err(CaptureError::Unsupported(
+            "capturing a drawn region needs X11; capture without a region to use the desktop's screenshot tool"
+                .to_string(),
+        ));
+    }
+
+    let root = gdk::Window::default_root_window();
+    // Root window coordinates are in GDK's scaled units
+    let scale = root.scale_factor().max(1);
+    let pixbuf = root
+        .pixbuf(
+            rect.x / scale,
+            rect.y / scale,
+            rect.width as i32 / scale,
+            rect.height as i32 / scale,
+        )
+        .ok_or_else(|| CaptureError::Failed("the screen couldn't be read".to_string()))?;
+    pixbuf
+        .save_to_bufferv("png", &[])
+        .map_err(|e| CaptureError::Failed(format!("Failed to encode PNG: {}", e)))
+}
+
+/// Capture `rect` (physical desktop pixels) by copying it from the screen.
+#[cfg(target_os = "windows")]
+pub async fn capture_rect(
+    _app: &AppHandle,
+    rect: Rect,
+    _scale_factor: f64,
+) -> Result<Vec<u8>, CaptureError> {
+    // Without DPI awareness the screen would be read in scaled units
+    let script = format!(
+        "param($path)
+         Add-Type -AssemblyName System.Drawing
+         Add-Type -Name Dpi -Namespace Native -MemberDefinition '[DllImport(\"user32.dll\")] public static extern bool SetProcessDPIAware();'
+         [Native.Dpi]::SetProcessDPIAware() | Out-Null
+         $bitmap = New-Object System.Drawing.Bitmap {width}, {height}
+         $graphics = [System.Drawing.Graphics]::FromImage($bitmap)
+         $graphics.CopyFromScreen({x}, {y}, 0, 0, $bitmap.Size)
+         $bitmap.Save($path, [System.Drawing.Imaging.ImageFormat]::Png)",
+        x = rect.x,
+        y = rect.y,
+        width = rect.width,
+        height = rect.height,
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = temp_png();
+        powershell(&script, &path)?;
+        take_png(&path)
+    })
+    .await
+    .unwrap_or_else(|e| Err(CaptureError::Failed(e.to_string())))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+pub async fn capture_rect(
+    _app: &AppHandle,
+    _rect: Rect,
+    _scale_factor: f64,
+) -> Result<Vec<u8>, CaptureError> {
+    Err(CaptureError::Unsupported(
+        "screen capture isn't available on this platform yet".to_string(),
+    ))
+}
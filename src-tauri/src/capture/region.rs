@@ -0,0 +1,199 @@
+//! Screen region math.
+//!
+//! A selection is drawn on one monitor in logical pixels relative to that
+//! monitor's top-left corner, in whichever direction the user dragged.
+//! Capturing needs it in physical pixels in the desktop coordinate space
+//! (see [`crate::window::placement`]), rounded outwards so no selected pixel
+//! is lost, and kept on the monitor it was drawn on.
+
+use serde::Deserialize;
+
+use crate::window::placement::{MonitorArea, Rect};
+
+/// Smallest selection captured, in logical pixels per side.
+pub const MIN_SIZE: f64 = 4.0;
+
+/// A region selected on one monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ScreenRegion {
+    /// Physical position of the monitor the selection was drawn on
+    pub monitor_x: i32,
+    pub monitor_y: i32,
+    /// Where the drag started, in logical pixels from the monitor's corner
+    pub x: f64,
+    pub y: f64,
+    /// Drag distance in logical pixels; negative when dragged up or left
+    pub width: f64,
+    pub height: f64,
+}
+
+/// The physical desktop rectangle of `region`.
+///
+/// # Returns
+///
+/// `None` when the monitor isn't connected or the selection is smaller
+/// than [`MIN_SIZE`] once clipped to the monitor.
+pub fn physical_rect(monitors: &[MonitorArea], region: &ScreenRegion) -> Option<Rect> {
+    let monitor = monitors
+        .iter()
+        .find(|m| m.bounds.x == region.monitor_x && m.bounds.y == region.monitor_y)?;
+    let scale = monitor.scale_factor;
+    let bounds = monitor.bounds;
+
+    // Normalize the drag direction, then clip to the monitor
+    let (left, right) = ordered(region.x, region.x + region.width);
+    let (top, bottom) = ordered(region.y, region.y + region.height);
+    let logical_width = bounds.width as f64 / scale;
+    let logical_height = bounds.height as f64 / scale;
+    let (left, right) = (left.max(0.0), right.min(logical_width));
+    let (top, bottom) = (top.max(0.0), bottom.min(logical_height));
+    if right - left < MIN_SIZE || bottom - top < MIN_SIZE {
+        return None;
+    }
+
+    let left = (left * scale).floor() as u32;
+    let top = (top * scale).floor() as u32;
+    let right = ((right * scale).ceil() as u32).min(bounds.width);
+    let bottom = ((bottom * scale).ceil() as u32).min(bounds.height);
+    Some(Rect::new(
+        bounds.x + left as i32,
+        bounds.y + top as i32,
+        right - left,
+        bottom - top,
+    ))
+}
+
+fn ordered(a: f64, b: f64) -> (f64, f64) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(x: i32, y: i32, width: u32, height: u32, scale_factor: f64) -> MonitorArea {
+        let bounds = Rect::new(x, y, width, height);
+        MonitorArea {
+            bounds,
+            work_area: bounds,
+            scale_factor,
+        }
+    }
+
+    fn region(monitor: (i32, i32), x: f64, y: f64, width: f64, height: f64) -> ScreenRegion {
+        ScreenRegion {
+            monitor_x: monitor.0,
+            monitor_y: monitor.1,
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    // ===== Scaling =====
+
+    #[test]
+    fn test_standard_dpi_is_unchanged() {
+        let monitors = [monitor(0, 0, 1920, 1080, 1.0)];
+
+        let rect = physical_rect(&monitors, &region((0, 0), 100.0, 50.0, 300.0, 200.0));
+
+        assert_eq!(rect, Some(Rect::new(100, 50, 300, 200)));
+    }
+
+    #[test]
+    fn test_hidpi_scales_to_pixels() {
+        let monitors = [monitor(0, 0, 2880, 1800, 2.0)];
+
+        let rect = physical_rect(&monitors, &region((0, 0), 10.0, 20.0, 100.0, 50.0));
+
+        assert_eq!(rect, Some(Rect::new(20, 40, 200, 100)));
+    }
+
+    #[test]
+    fn test_fractional_scale_rounds_outwards() {
+        let monitors = [monitor(0, 0, 2400, 1350, 1.25)];
+
+        let rect = physical_rect(&monitors, &region((0, 0), 10.5, 10.5, 20.0, 20.0)).unwrap();
+
+        // 13.125..38.125 physical
+        assert_eq!((rect.x, rect.width), (13, 26));
+        assert_eq!((rect.y, rect.height), (13, 26));
+    }
+
+    // ===== Monitors =====
+
+    #[test]
+    fn test_secondary_monitor_offsets() {
+        let monitors = [
+            monitor(0, 0, 1920, 1080, 1.0),
+            monitor(1920, -200, 3840, 2160, 2.0),
+        ];
+
+        let rect = physical_rect(&monitors, &region((1920, -200), 0.0, 0.0, 100.0, 100.0));
+
+        assert_eq!(rect, Some(Rect::new(1920, -200, 200, 200)));
+    }
+
+    #[test]
+    fn test_monitor_left_of_primary() {
+        let monitors = [
+            monitor(-1280, 0, 1280, 1024, 1.0),
+            monitor(0, 0, 1920, 1080, 1.0),
+        ];
+
+        let rect = physical_rect(&monitors, &region((-1280, 0), 1180.0, 0.0, 100.0, 100.0));
+
+        assert_eq!(rect, Some(Rect::new(-100, 0, 100, 100)));
+    }
+
+    #[test]
+    fn test_unknown_monitor() {
+        let monitors = [monitor(0, 0, 1920, 1080, 1.0)];
+
+        assert_eq!(
+            physical_rect(&monitors, &region((1920, 0), 0.0, 0.0, 100.0, 100.0)),
+            None
+        );
+    }
+
+    // ===== Selections =====
+
+    #[test]
+    fn test_reverse_drag_is_normalized() {
+        let monitors = [monitor(0, 0, 1920, 1080, 1.0)];
+
+        let rect = physical_rect(&monitors, &region((0, 0), 400.0, 300.0, -300.0, -200.0));
+
+        assert_eq!(rect, Some(Rect::new(100, 100, 300, 200)));
+    }
+
+    #[test]
+    fn test_selection_clipped_to_monitor() {
+        let monitors = [monitor(0, 0, 2880, 1800, 2.0)];
+
+        let rect = physical_rect(&monitors, &region((0, 0), 1400.0, -50.0, 200.0, 100.0));
+
+        assert_eq!(rect, Some(Rect::new(2800, 0, 80, 100)));
+    }
+
+    #[test]
+    fn test_tiny_selection_rejected() {
+        let monitors = [monitor(0, 0, 1920, 1080, 1.0)];
+
+        assert_eq!(
+            physical_rect(&monitors, &region((0, 0), 10.0, 10.0, 2.0, 200.0)),
+            None
+        );
+        // Clipped down to nothing
+        assert_eq!(
+            physical_rect(&monitors, &region((0, 0), 1919.0, 10.0, 200.0, 200.0)),
+            None
+        );
+    }
+}
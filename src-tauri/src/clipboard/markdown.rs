@@ -0,0 +1,457 @@
+//! Markdown rendering for copying answers.
+//!
+//! Parsed with `pulldown-cmark`, with GFM tables and strikethrough. Raw
+//! HTML in the source is escaped, not passed through, so copied HTML can't
+//! carry markup the answer didn't render.
+//!
+//! The same parse renders to HTML ([`to_html`]), to plain text with the
+//! markup removed ([`to_plain_text`]), and to text for reading aloud
+//! ([`to_speech`]).
+
+use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+
+/// Spoken in place of a code block.
+pub const CODE_OMITTED: &str = "(code omitted)";
+
+fn parse(markdown: &str) -> Parser<'_> {
+    Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    )
+}
+
+/// Render markdown to an HTML fragment.
+pub fn to_html(markdown: &str) -> String {
+    let events = parse(markdown).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        event => event,
+    });
+    let mut html = String::new();
+    html::push_html(&mut html, events);
+    html
+}
+
+/// Render markdown to plain text, dropping the markup.
+///
+/// Code keeps its content, list items keep their bullets or numbers, table
+/// cells are separated by tabs, and links keep their URL in parentheses.
+pub fn to_plain_text(markdown: &str) -> String {
+    TextWriter::new(Mode::Plain).render(parse(markdown))
+}
+
+/// Render markdown as text to be read aloud.
+///
+/// Like [`to_plain_text`], but code blocks are replaced by
+/// [`CODE_OMITTED`], links keep only their text, and list bullets, table
+/// separators and rules are dropped.
+pub fn to_speech(markdown: &str) -> String {
+    TextWriter::new(Mode::Speech).render(parse(markdown))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Plain,
+    Speech,
+}
+
+/// Writes the text of markdown events.
+///
+/// Blocks are separated by a blank line and list items and table rows by a
+/// line break. Separators are only written before the next text, so empty
+/// blocks and rules leave no trace.
+struct TextWriter {
+    mode: Mode,
+    out: String,
+    /// Separator owed before the next text
+    pending: &'static str,
+    /// An item was started and nothing written in it yet
+    item_start: bool,
+    /// Next number of each open list; `None` for bullets
+    lists: Vec<Option<u64>>,
+    /// Continuation indent of the open list items (plain text)
+    indent: String,
+    indents: Vec<usize>,
+    /// Link URLs, with where their text starts
+    links: Vec<(String, usize)>,
+    /// Text of the code block being read
+    code: Option<String>,
+    /// Cells written in the current table row
+    cells: usize,
+}
+
+impl TextWriter {
+    fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            out: String::new(),
+            pending: "",
+            item_start: false,
+            lists: Vec::new(),
+            indent: String::new(),
+            indents: Vec::new(),
+            links: Vec::new(),
+            code: None,
+            cells: 0,
+        }
+    }
+
+    fn render<'a>(mut self, events: impl Iterator<Item = Event<'a>>) -> String {
+        for event in events {
+            self.event(event);
+        }
+        self.out
+    }
+
+    fn event(&mut self, event: Event<'_>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) | Event::Code(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                match &mut self.code {
+                    Some(code) => code.push_str(&text),
+                    None => self.write(&text),
+                }
+            }
+            Event::SoftBreak if self.mode == Mode::Speech => self.write(" "),
+            Event::SoftBreak | Event::HardBreak => self.write("\n"),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph | Tag::Heading { .. } | Tag::Table(_) => self.block(),
+            Tag::CodeBlock(_) if self.mode == Mode::Speech => {
+                self.block();
+                self.write(CODE_OMITTED);
+                self.code = Some(String::new());
+            }
+            Tag::CodeBlock(_) => {
+                self.block();
+                self.code = Some(String::new());
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.block();
+                } else {
+                    self.separate("\n");
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.separate("\n");
+                if self.mode == Mode::Plain {
+                    let marker = match self.lists.last_mut() {
+                        Some(Some(number)) => {
+                            *number += 1;
+                            format!("{}. ", *number - 1)
+                        }
+                        _ => "- ".to_string(),
+                    };
+                    self.write(&marker);
+                    self.indents.push(marker.len());
+                    self.indent.push_str(&" ".repeat(marker.len()));
+                }
+                self.item_start = true;
+            }
+            Tag::TableHead | Tag::TableRow => {
+                self.separate("\n");
+                self.cells = 0;
+            }
+            Tag::TableCell => {
+                if self.cells > 0 {
+                    self.write(if self.mode == Mode::Speech {
+                        ", "
+                    } else {
+                        "\t"
+                    });
+                }
+                self.cells += 1;
+            }
+            Tag::Link { dest_url, .. } => self.links.push((dest_url.to_string(), self.out.len())),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::CodeBlock => {
+                if let Some(code) = self.code.take() {
+                    if self.mode == Mode::Plain {
+                        self.write(code.trim_end_matches('\n'));
+                    }
+                }
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+            }
+            TagEnd::Item if self.mode == Mode::Plain => {
+                let width = self.indents.pop().unwrap_or(0);
+                self.indent.truncate(self.indent.len() - width);
+            }
+            TagEnd::Link => {
+                let Some((url, start)) = self.links.pop() else {
+                    return;
+                };
+                if self.mode == Mode::Plain && !url.is_empty() && self.out[start..] != url {
+                    self.write(&format!(" ({})", url));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Start a block, unless it is the first one of a list item.
+    fn block(&mut self) {
+        if !self.item_start {
+            self.separate("\n\n");
+        }
+    }
+
+    /// Owe `separator` before the next text, unless a longer one is owed.
+    fn separate(&mut self, separator: &'static str) {
+        if separator.len() > self.pending.len() {
+            self.pending = separator;
+        }
+    }
+
+    /// Write `text`, after the separator owed, indenting lines inside list
+    /// items.
+    fn write(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if !self.out.is_empty() {
+            self.out.push_str(self.pending);
+            if !self.pending.is_empty() {
+                self.out.push_str(&self.indent);
+            }
+        }
+        self.pending = "";
+        self.item_start = false;
+
+        let mut lines = text.split('\n');
+        self.out.push_str(lines.next().unwrap_or(""));
+        for line in lines {
+            self.out.push('\n');
+            if !line.is_empty() {
+                self.out.push_str(&self.indent);
+            }
+            self.out.push_str(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===== Code =====
+
+    #[test]
+    fn test_code_fence_with_language() {
+        let html = to_html("```rust\nfn main() {\n    println!(\"<hi>\");\n}\n```");
+
+        assert_eq!(
+            html,
+            "<pre><code class=\"language-rust\">fn main() {\n    println!(\"&lt;hi&gt;\");\n}\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_code_fence_keeps_markdown_literal() {
+        let html = to_html("~~~\n# not a heading\n**not bold**\n~~~");
+
+        assert_eq!(
+            html,
+            "<pre><code># not a heading\n**not bold**\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_unclosed_code_fence_runs_to_end() {
+        let html = to_html("```py\nprint(1)");
+
+        assert_eq!(
+            html,
+            "<pre><code class=\"language-py\">print(1)</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_code() {
+        assert_eq!(
+            to_html("Run `cargo test` or `` a`b ``."),
+            "<p>Run <code>cargo test</code> or <code>a`b</code>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_code_is_not_formatted() {
+        assert_eq!(
+            to_html("`**x** <b>`"),
+            "<p><code>**x** &lt;b&gt;</code></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_unmatched_backtick_is_literal() {
+        assert_eq!(to_html("a ` b"), "<p>a ` b</p>\n");
+    }
+
+    // ===== Tables =====
+
+    #[test]
+    fn test_table_with_alignment() {
+        let markdown =
+            "| Name | Size | Note |\n|:-----|-----:|:----:|\n| a | 1 | `x\\|y` |\n| b | 22 |";
+
+        assert_eq!(
+            to_html(markdown),
+            "<table><thead>\
+             <tr><th style=\"text-align: left\">Name</th><th style=\"text-align: right\">Size</th><th style=\"text-align: center\">Note</th></tr>\
+             </thead><tbody>\n\
+             <tr><td style=\"text-align: left\">a</td><td style=\"text-align: right\">1</td><td style=\"text-align: center\"><code>x|y</code></td></tr>\n\
+             <tr><td style=\"text-align: left\">b</td><td style=\"text-align: right\">22</td><td style=\"text-align: center\"></td></tr>\n\
+             </tbody></table>\n"
+        );
+    }
+
+    #[test]
+    fn test_table_without_delimiter_is_paragraph() {
+        assert_eq!(to_html("a | b\nc | d"), "<p>a | b\nc | d</p>\n");
+    }
+
+    #[test]
+    fn test_table_as_plain_text() {
+        let markdown = "| Key | Value |\n| --- | --- |\n| **a** | 1 |";
+
+        assert_eq!(to_plain_text(markdown), "Key\tValue\na\t1");
+    }
+
+    // ===== Blocks =====
+
+    #[test]
+    fn test_headings_and_paragraphs() {
+        assert_eq!(
+            to_html("# Title ##\n\nSome *text*\nwrapped.\n\n###### Small"),
+            "<h1>Title</h1>\n<p>Some <em>text</em>\nwrapped.</p>\n<h6>Small</h6>\n"
+        );
+    }
+
+    #[test]
+    fn test_tight_and_nested_lists() {
+        let markdown = "- one\n- two\n  1. nested\n  2. list\n- three";
+
+        assert_eq!(
+            to_html(markdown),
+            "<ul>\n<li>one</li>\n<li>two\n<ol>\n<li>nested</li>\n<li>list</li>\n</ol>\n</li>\n<li>three</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn test_loose_ordered_list() {
+        assert_eq!(
+            to_html("3. a\n\n4. b"),
+            "<ol start=\"3\">\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n</li>\n</ol>\n"
+        );
+    }
+
+    #[test]
+    fn test_code_in_list_item() {
+        let markdown = "1. Install:\n   ```sh\n   cargo add x\n   ```\n2. Done";
+
+        assert_eq!(
+            to_html(markdown),
+            "<ol>\n<li>Install:\n<pre><code class=\"language-sh\">cargo add x\n</code></pre>\n</li>\n<li>Done</li>\n</ol>\n"
+        );
+    }
+
+    #[test]
+    fn test_quote_and_rule() {
+        assert_eq!(
+            to_html("> quoted\n> **text**\n\n---"),
+            "<blockquote>\n<p>quoted\n<strong>text</strong></p>\n</blockquote>\n<hr />\n"
+        );
+    }
+
+    // ===== Inlines =====
+
+    #[test]
+    fn test_emphasis_variants() {
+        assert_eq!(
+            to_html("**bold** _em_ ***both*** ~~gone~~ snake_case_name 2 * 3"),
+            "<p><strong>bold</strong> <em>em</em> <em><strong>both</strong></em> <del>gone</del> snake_case_name 2 * 3</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_links_and_images() {
+        assert_eq!(
+            to_html("[docs](https://example.com \"Docs\") ![logo](a.png) <https://x.dev>"),
+            "<p><a href=\"https://example.com\" title=\"Docs\">docs</a> <img src=\"a.png\" alt=\"logo\" /> <a href=\"https://x.dev\">https://x.dev</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_raw_html_is_escaped() {
+        assert_eq!(
+            to_html("<script>alert(1)</script> & more"),
+            "&lt;script&gt;alert(1)&lt;/script&gt; &amp; more"
+        );
+    }
+
+    #[test]
+    fn test_escapes_and_hard_breaks() {
+        assert_eq!(
+            to_html("\\*literal\\* line  \nbreak"),
+            "<p>*literal* line<br />\nbreak</p>\n"
+        );
+    }
+
+    // ===== Speech =====
+
+    #[test]
+    fn test_speech_omits_code_blocks() {
+        let markdown = "Run this:\n\n```bash\nrm -rf build\n```\n\nThen rebuild.";
+
+        assert_eq!(
+            to_speech(markdown),
+            "Run this:\n\n(code omitted)\n\nThen rebuild."
+        );
+    }
+
+    #[test]
+    fn test_speech_drops_markup_and_urls() {
+        let markdown = "# Setup\n\n- Open **Settings**\n- See [the docs](https://example.com)\n\n---\n\nUse `cargo build`.";
+
+        assert_eq!(
+            to_speech(markdown),
+            "Setup\n\nOpen Settings\nSee the docs\n\nUse cargo build."
+        );
+    }
+
+    #[test]
+    fn test_speech_reads_tables_by_row() {
+        let markdown = "| Flag | Meaning |\n|---|---|\n| -x | extract |\n| -z | gzip |";
+
+        assert_eq!(to_speech(markdown), "Flag, Meaning\n-x, extract\n-z, gzip");
+    }
+
+    #[test]
+    fn test_speech_joins_soft_breaks() {
+        assert_eq!(to_speech("one\ntwo\n\n> quoted"), "one two\n\nquoted");
+    }
+
+    // ===== Plain text =====
+
+    #[test]
+    fn test_plain_text_drops_markup() {
+        let markdown = "## Steps\n\n1. Open **Settings**\n2. See [docs](https://example.com)\n\n```\nlet x = 1;\n```";
+
+        assert_eq!(
+            to_plain_text(markdown),
+            "Steps\n\n1. Open Settings\n2. See docs (https://example.com)\n\nlet x = 1;"
+        );
+    }
+}
@@ -0,0 +1,453 @@
+//! Clipboard text, copying answers and the "ask about clipboard" shortcut.
+//!
+//! `ask_clipboard` (and the `shortcuts.ask_clipboard` hotkey) reads the
+//! clipboard, shows the launcher and emits `prefill-prompt` so the copied
+//! text becomes the prompt. Text longer than `launcher.clipboard_max_chars`
+//! is cut off and flagged as truncated.
+//!
+//! Only text is read: images, files and other formats give `None`, as does
+//! a clipboard that can't be opened (e.g. held by another app).
+//!
+//! The `shortcuts.ask_selection` hotkey does the same with the text
+//! selected in the foreground app, copied through the clipboard and then
+//! restored (see [`selection`]). The payload's `source` tells the two apart.
+//!
+//! # Copying
+//!
+//! `copy_to_clipboard` (and `copy_message`, which reads the text from
+//! history) copies markdown as one of the [`CopyFormat`]s. `html` renders
+//! the markdown and puts both the HTML and a plain text version on the
+//! clipboard, so pasting into rich editors keeps the formatting. Copying
+//! happens natively, so it works while the window doesn't have focus.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const text = await invoke<string | null>('get_clipboard_text');
+//!
+//! await listen<PrefillPrompt>('prefill-prompt', ({ payload }) => {
+//!   prompt.value = payload.text;
+//!   if (payload.truncated) showHint(`${payload.source === 'selection' ? 'Selection' : 'Clipboard'} cut to ${payload.text.length} of ${payload.original_chars} characters`);
+//! });
+//! await invoke('ask_clipboard');
+//!
+//! await invoke('copy_to_clipboard', { content: answer, format: 'html' });
+//! await invoke('copy_message', { messageId: message.id, format: 'markdown' });
+//! ```
+
+pub mod markdown;
+mod native;
+pub mod selection;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::settings::SettingsManager;
+use crate::window::{self, visibility::VisibilityReason};
+use selection::{NativeCopyKeystroke, SelectionClipboard};
+
+/// How copied markdown is put on the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyFormat {
+    /// Rendered text without markup
+    PlainText,
+    /// The markdown source
+    Markdown,
+    /// Rendered HTML, with plain text for apps that don't take HTML
+    Html,
+}
+
+/// What is written to the clipboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardContents {
+    pub text: String,
+    /// HTML version, for apps that paste rich text
+    pub html: Option<String>,
+}
+
+impl ClipboardContents {
+    /// Render markdown in a copy format.
+    pub fn render(markdown: &str, format: CopyFormat) -> Self {
+        match format {
+            CopyFormat::PlainText => Self {
+                text: markdown::to_plain_text(markdown),
+                html: None,
+            },
+            CopyFormat::Markdown => Self {
+                text: markdown.to_string(),
+                html: None,
+            },
+            CopyFormat::Html => Self {
+                text: markdown::to_plain_text(markdown),
+                html: Some(markdown::to_html(markdown)),
+            },
+        }
+    }
+}
+
+/// Where a prefilled prompt came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefillSource {
+    /// The clipboard text
+    Clipboard,
+    /// The text selected in the foreground app
+    Selection,
+    /// The `q` of a `qwikask://ask` link
+    DeepLink,
+    /// The `--query` command-line argument
+    CommandLine,
+}
+
+/// Payload of the `prefill-prompt` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrefillPrompt {
+    /// Prompt text, possibly truncated
+    pub text: String,
+    /// Whether `text` was cut off
+    pub truncated: bool,
+    /// Length of the source text in characters
+    pub original_chars: usize,
+    pub source: PrefillSource,
+    /// Send the prompt right away instead of waiting for Enter
+    pub submit: bool,
+}
+
+/// Cut text to at most `max_chars` characters.
+///
+/// # Returns
+///
+/// The text (on a character boundary) and whether it was cut off.
+pub fn truncate_chars(text: &str, max_chars: usize) -> (&str, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => (&text[..end], true),
+        None => (text, false),
+    }
+}
+
+/// Build the `prefill-prompt` payload.
+///
+/// # Arguments
+///
+/// * `text` - Clipboard or selected text, if any
+/// * `max_chars` - The `launcher.clipboard_max_chars` setting
+/// * `source` - Where the text came from
+///
+/// # Returns
+///
+/// `None` when there's no text or only whitespace.
+pub fn prefill_prompt(
+    text: Option<&str>,
+    max_chars: u32,
+    source: PrefillSource,
+) -> Option<PrefillPrompt> {
+    let text = text?.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let (prefix, truncated) = truncate_chars(text, max_chars as usize);
+    Some(PrefillPrompt {
+        text: prefix.to_string(),
+        truncated,
+        original_chars: text.chars().count(),
+        source,
+        submit: false,
+    })
+}
+
+/// Read the clipboard as text.
+///
+/// # Returns
+///
+/// `None` if the clipboard is empty, holds something other than text, or
+/// can't be read.
+#[tauri::command]
+pub fn get_clipboard_text() -> Option<String> {
+    native::read_text()
+}
+
+/// Copy markdown to the clipboard.
+///
+/// # Arguments
+///
+/// * `content` - Markdown to copy
+/// * `format` - How to put it on the clipboard
+///
+/// # Returns
+///
+/// * `Ok(())` - Copied
+/// * `Err(String)` - The clipboard couldn't be written
+#[tauri::command]
+pub async fn copy_to_clipboard(content: String, format: CopyFormat) -> Result<(), String> {
+    copy(&content, format).await
+}
+
+/// Copy markdown to the clipboard in `format`.
+pub async fn copy(markdown: &str, format: CopyFormat) -> Result<(), String> {
+    let contents = ClipboardContents::render(markdown, format);
+    tauri::async_runtime::spawn_blocking(move || native::write(&contents))
+        .await
+        .map_err(|e| format!("Failed to write to the clipboard: {}", e))?
+}
+
+/// Read the clipboard as text from an async context.
+pub async fn read() -> Option<String> {
+    tauri::async_runtime::spawn_blocking(native::read_text)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Copy markdown as HTML and paste it into the foreground app.
+///
+/// The pasted text stays on the clipboard: the app reads it some time after
+/// the keystroke, so it can't be restored safely.
+pub async fn paste(markdown: &str) -> Result<(), String> {
+    copy(markdown, CopyFormat::Html).await?;
+    selection::send_paste()
+}
+
+/// Show the launcher with the clipboard text as the prompt.
+///
+/// Emits `prefill-prompt` after showing the launcher. Without clipboard
+/// text the launcher is shown as usual.
+///
+/// # Returns
+///
+/// The emitted payload, or `None` if there was no text.
+#[tauri::command]
+pub fn ask_clipboard(app: AppHandle) -> Option<PrefillPrompt> {
+    ask(&app, VisibilityReason::Command)
+}
+
+/// Handle the `shortcuts.ask_clipboard` hotkey.
+pub fn ask_from_shortcut(app: &AppHandle) {
+    ask(app, VisibilityReason::Hotkey);
+}
+
+/// Handle the `shortcuts.ask_selection` hotkey.
+///
+/// The selection is captured on a background thread, since copying waits
+/// for the foreground app, then the launcher is shown on the main thread
+/// with it as the prompt. Without a selection the launcher is shown as
+/// usual.
+pub fn ask_selection_from_shortcut(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let selection = capture_selection();
+        let prompt = prefill_prompt(
+            selection.as_deref(),
+            max_chars(&app),
+            PrefillSource::Selection,
+        );
+        let handle = app.clone();
+        let _ = app.run_on_main_thread(move || {
+            show_prefilled(&handle, VisibilityReason::Hotkey, prompt);
+        });
+    });
+}
+
+/// Copy the text selected in the foreground app, restoring the clipboard.
+///
+/// Blocks while the copy happens, so it must not run on the main thread.
+///
+/// # Returns
+///
+/// The selected text, or `None` if nothing was selected or it couldn't be
+/// copied.
+pub fn capture_selection() -> Option<String> {
+    selection::capture_with(
+        &NativeClipboard,
+        &NativeCopyKeystroke,
+        selection::COPY_TIMEOUT,
+    )
+}
+
+fn ask(app: &AppHandle, reason: VisibilityReason) -> Option<PrefillPrompt> {
+    let prompt = prefill_prompt(
+        native::read_text().as_deref(),
+        max_chars(app),
+        PrefillSource::Clipboard,
+    );
+    show_prefilled(app, reason, prompt.clone());
+    prompt
+}
+
+fn max_chars(app: &AppHandle) -> u32 {
+    app.state::<SettingsManager>()
+        .snapshot()
+        .unwrap_or_default()
+        .launcher
+        .clipboard_max_chars
+}
+
+/// Show the launcher, then emit `prefill-prompt` if there is a prompt.
+pub fn show_prefilled(app: &AppHandle, reason: VisibilityReason, prompt: Option<PrefillPrompt>) {
+    window::show_launcher(app, reason);
+    if let Some(prompt) = &prompt {
+        let _ = app.emit("prefill-prompt", prompt);
+    }
+}
+
+/// The system clipboard.
+struct NativeClipboard;
+
+impl SelectionClipboard for NativeClipboard {
+    fn text(&self) -> Option<String> {
+        native::read_text()
+    }
+
+    fn has_content(&self) -> bool {
+        native::has_content()
+    }
+
+    fn change_count(&self) -> Option<u64> {
+        native::change_count()
+    }
+
+    fn set_text(&self, text: &str) -> Result<(), String> {
+        native::write(&ClipboardContents {
+            text: text.to_string(),
+            html: None,
+        })
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        native::clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===== Truncation =====
+
+    #[test]
+    fn test_short_text_is_kept() {
+        assert_eq!(truncate_chars("hello", 10), ("hello", false));
+        assert_eq!(truncate_chars("hello", 5), ("hello", false));
+    }
+
+    #[test]
+    fn test_long_text_is_cut() {
+        assert_eq!(truncate_chars("hello world", 5), ("hello", true));
+    }
+
+    #[test]
+    fn test_truncation_counts_characters_not_bytes() {
+        // Multi-byte characters must not be split
+        assert_eq!(truncate_chars("héllo wörld", 7), ("héllo w", true));
+        assert_eq!(truncate_chars("日本語テキスト", 3), ("日本語", true));
+        assert_eq!(truncate_chars("🦀🦀🦀", 3), ("🦀🦀🦀", false));
+    }
+
+    #[test]
+    fn test_zero_limit() {
+        assert_eq!(truncate_chars("abc", 0), ("", true));
+        assert_eq!(truncate_chars("", 0), ("", false));
+    }
+
+    // ===== Copy formats =====
+
+    #[test]
+    fn test_copy_formats() {
+        let answer = "Use `cargo test`:\n\n- **fast**";
+
+        assert_eq!(
+            ClipboardContents::render(answer, CopyFormat::Markdown),
+            ClipboardContents {
+                text: answer.to_string(),
+                html: None,
+            }
+        );
+        assert_eq!(
+            ClipboardContents::render(answer, CopyFormat::PlainText),
+            ClipboardContents {
+                text: "Use cargo test:\n\n- fast".to_string(),
+                html: None,
+            }
+        );
+        assert_eq!(
+            ClipboardContents::render(answer, CopyFormat::Html),
+            ClipboardContents {
+                text: "Use cargo test:\n\n- fast".to_string(),
+                html: Some(
+                    "<p>Use <code>cargo test</code>:</p>\n<ul>\n<li><strong>fast</strong></li>\n</ul>\n"
+                        .to_string()
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn test_copy_format_serialization() {
+        assert_eq!(
+            serde_json::from_str::<CopyFormat>("\"plain_text\"").unwrap(),
+            CopyFormat::PlainText
+        );
+        assert_eq!(serde_json::to_value(CopyFormat::Html).unwrap(), "html");
+    }
+
+    // ===== Payload =====
+
+    #[test]
+    fn test_prefill_payload() {
+        let prompt = prefill_prompt(
+            Some("  error: index out of bounds\n"),
+            100,
+            PrefillSource::Clipboard,
+        )
+        .unwrap();
+
+        assert_eq!(
+            prompt,
+            PrefillPrompt {
+                text: "error: index out of bounds".to_string(),
+                truncated: false,
+                original_chars: 26,
+                source: PrefillSource::Clipboard,
+                submit: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_prefill_payload_truncated() {
+        let clipboard = "x".repeat(10_000);
+
+        let prompt = prefill_prompt(Some(&clipboard), 8000, PrefillSource::Clipboard).unwrap();
+
+        assert_eq!(prompt.text.len(), 8000);
+        assert!(prompt.truncated);
+        assert_eq!(prompt.original_chars, 10_000);
+    }
+
+    #[test]
+    fn test_no_payload_without_text() {
+        assert_eq!(prefill_prompt(None, 100, PrefillSource::Clipboard), None);
+        assert_eq!(
+            prefill_prompt(Some(" \n\t"), 100, PrefillSource::Selection),
+            None
+        );
+    }
+
+    #[test]
+    fn test_payload_serialization() {
+        let prompt = prefill_prompt(Some("abcdef"), 3, PrefillSource::Selection).unwrap();
+
+        let json = serde_json::to_value(&prompt).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "text": "abc",
+                "truncated": true,
+                "original_chars": 6,
+                "source": "selection",
+                "submit": false
+            })
+        );
+    }
+}
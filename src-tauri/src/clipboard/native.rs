@@ -0,0 +1,79 @@
+//! Reading and writing the system clipboard through `arboard`.
+//!
+//! Writes put the plain text and, if given, an HTML version on the
+//! clipboard together, so rich editors paste the HTML and others the text.
+//!
+//! One `arboard::Clipboard` is kept for the life of the app: on X11 the
+//! copied contents are served by it and would be lost once it's dropped.
+//!
+//! `has_content`, `change_count` and `clear` exist for capturing the
+//! selection, which has to save and restore the clipboard around a copy.
+
+use std::sync::Mutex;
+
+use arboard::Clipboard;
+
+use super::ClipboardContents;
+
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+/// Run `f` with the app's clipboard, opening it the first time.
+fn with_clipboard<T>(
+    f: impl FnOnce(&mut Clipboard) -> Result<T, arboard::Error>,
+) -> Result<T, arboard::Error> {
+    let mut clipboard = CLIPBOARD.lock().unwrap_or_else(|e| e.into_inner());
+    let clipboard = match &mut *clipboard {
+        Some(clipboard) => clipboard,
+        None => clipboard.insert(Clipboard::new()?),
+    };
+    f(clipboard)
+}
+
+/// The clipboard as text, or `None` if it holds no text.
+pub fn read_text() -> Option<String> {
+    with_clipboard(|clipboard| clipboard.get_text()).ok()
+}
+
+/// Replace the clipboard contents.
+pub fn write(contents: &ClipboardContents) -> Result<(), String> {
+    with_clipboard(|clipboard| match &contents.html {
+        Some(html) => clipboard.set_html(html.as_str(), Some(contents.text.as_str())),
+        None => clipboard.set_text(contents.text.as_str()),
+    })
+    .map_err(|e| format!("Failed to write to the clipboard: {}", e))
+}
+
+/// Whether the clipboard holds anything: text, an image or files.
+pub fn has_content() -> bool {
+    with_clipboard(|clipboard| {
+        Ok(clipboard.get().text().is_ok()
+            || clipboard.get().image().is_ok()
+            || clipboard
+                .get()
+                .file_list()
+                .is_ok_and(|files| !files.is_empty()))
+    })
+    .unwrap_or(false)
+}
+
+/// The clipboard's sequence number, which changes with every change.
+#[cfg(target_os = "windows")]
+pub fn change_count() -> Option<u64> {
+    use windows_sys::Win32::System::DataExchange::GetClipboardSequenceNumber;
+
+    // SAFETY: no arguments; doesn't need the clipboard to be open.
+    let sequence = unsafe { GetClipboardSequenceNumber() };
+    (sequence != 0).then_some(sequence.into())
+}
+
+/// No clipboard change counter; changes are noticed by comparing text.
+#[cfg(not(target_os = "windows"))]
+pub fn change_count() -> Option<u64> {
+    None
+}
+
+/// Empty the clipboard.
+pub fn clear() -> Result<(), String> {
+    with_clipboard(|clipboard| clipboard.clear())
+        .map_err(|e| format!("Failed to clear the clipboard: {}", e))
+}
@@ -0,0 +1,404 @@
+//! Capturing the text selected in the foreground app.
+//!
+//! There's no cross-platform way to read another app's selection, so the
+//! copy shortcut is sent to it (Ctrl+C, or Cmd+C on macOS) and the copied
+//! text is read back from the clipboard. The clipboard the user had before
+//! is put back afterwards.
+//!
+//! # No selection
+//!
+//! If nothing is selected the copy doesn't change the clipboard. That's
+//! detected with the clipboard's change counter on Windows or by comparing
+//! the text elsewhere, and the clipboard is then left untouched. Without
+//! the counter, selecting exactly the text that is already on the
+//! clipboard isn't noticed.
+//!
+//! # Restoring
+//!
+//! Only text can be put back. A clipboard holding only something else
+//! (an image, files) is never touched: the capture gives `None` without
+//! sending the shortcut. Rich text is put back as its plain text.
+//!
+//! # Platforms
+//!
+//! [`send_paste`] sends the paste shortcut the same way, for putting text
+//! back into the foreground app.
+//!
+//! The shortcut is sent with `SendInput` on Windows and Quartz events on
+//! macOS (which needs the Accessibility permission). Linux uses `xdotool`,
+//! so it only works on X11 with `xdotool` installed; otherwise the capture
+//! gives `None`.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the foreground app to copy its selection.
+pub const COPY_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// How often the clipboard is checked while waiting.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Sends the copy shortcut to the foreground app.
+pub trait CopyKeystroke {
+    fn send_copy(&self) -> Result<(), String>;
+}
+
+/// The clipboard operations a capture needs.
+pub trait SelectionClipboard {
+    /// The clipboard as text, or `None` if it holds no text.
+    fn text(&self) -> Option<String>;
+    /// Whether the clipboard holds something, text or not.
+    fn has_content(&self) -> bool;
+    /// A counter that changes whenever the clipboard does, if the platform
+    /// has one.
+    fn change_count(&self) -> Option<u64>;
+    fn set_text(&self, text: &str) -> Result<(), String>;
+    fn clear(&self) -> Result<(), String>;
+}
+
+/// Copy the foreground app's selection and restore the clipboard.
+///
+/// # Arguments
+///
+/// * `clipboard` - The clipboard to copy through
+/// * `keys` - Sends the copy shortcut
+/// * `timeout` - How long to wait for the copy
+///
+/// # Returns
+///
+/// The selected text, or `None` if nothing (or only whitespace) was
+/// selected, the selection isn't text, or the clipboard couldn't be saved.
+pub fn capture_with(
+    clipboard: &impl SelectionClipboard,
+    keys: &impl CopyKeystroke,
+    timeout: Duration,
+) -> Option<String> {
+    let saved = clipboard.text();
+    if saved.is_none() && clipboard.has_content() {
+        // Couldn't be put back
+        return None;
+    }
+    let before = clipboard.change_count();
+
+    if let Err(e) = keys.send_copy() {
+        tracing::warn!(error = %e, "Failed to send the copy shortcut");
+        return None;
+    }
+
+    let started = Instant::now();
+    let selection = loop {
+        let changed = match before {
+            Some(before) => clipboard.change_count() != Some(before),
+            None => clipboard.text() != saved,
+        };
+        if changed {
+            break clipboard.text();
+        }
+        if started.elapsed() >= timeout {
+            // Nothing was copied, so there's nothing to restore
+            return None;
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let restored = match &saved {
+        Some(text) => clipboard.set_text(text),
+        None => clipboard.clear(),
+    };
+    if let Err(e) = restored {
+        tracing::warn!(error = %e, "Failed to restore the clipboard");
+    }
+    selection.filter(|text| !text.trim().is_empty())
+}
+
+/// The platform's copy shortcut.
+pub struct NativeCopyKeystroke;
+
+impl CopyKeystroke for NativeCopyKeystroke {
+    fn send_copy(&self) -> Result<(), String> {
+        send_edit_shortcut(EditKey::Copy)
+    }
+}
+
+/// Send the paste shortcut (Ctrl+V, or Cmd+V on macOS) to the foreground
+/// app.
+pub fn send_paste() -> Result<(), String> {
+    send_edit_shortcut(EditKey::Paste)
+}
+
+/// The letter of an edit shortcut.
+#[derive(Debug, Clone, Copy)]
+enum EditKey {
+    Copy,
+    Paste,
+}
+
+#[cfg(target_os = "windows")]
+fn send_edit_shortcut(key: EditKey) -> Result<(), String> {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        VIRTUAL_KEY, VK_C, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT, VK_V,
+    };
+
+    let letter = match key {
+        EditKey::Copy => VK_C,
+        EditKey::Paste => VK_V,
+    };
+    let input = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    // Release modifiers still held from the hotkey, so the app sees
+    // Ctrl+C rather than e.g. Ctrl+Alt+C
+    let inputs = [
+        input(VK_SHIFT, KEYEVENTF_KEYUP),
+        input(VK_MENU, KEYEVENTF_KEYUP),
+        input(VK_LWIN, KEYEVENTF_KEYUP),
+        input(VK_RWIN, KEYEVENTF_KEYUP),
+        input(VK_CONTROL, 0),
+        input(letter, 0),
+        input(letter, KEYEVENTF_KEYUP),
+        input(VK_CONTROL, KEYEVENTF_KEYUP),
+    ];
+
+    // SAFETY: `inputs` is a valid array of the given length and size.
+    let sent = unsafe {
+        SendInput(
+            inputs.len() as u32,
+            inputs.as_ptr(),
+            std::mem::size_of::<INPUT>() as i32,
+        )
+    };
+    if sent as usize == inputs.len() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to send the {:?} shortcut (blocked by another app?)",
+            key
+        ))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn send_edit_shortcut(key: EditKey) -> Result<(), String> {
+    use objc2_core_graphics::{CGEvent, CGEventFlags, CGEventTapLocation};
+
+    // `kVK_ANSI_C` and `kVK_ANSI_V`
+    let letter = match key {
+        EditKey::Copy => 8,
+        EditKey::Paste => 9,
+    };
+    for key_down in [true, false] {
+        let event = CGEvent::new_keyboard_event(None, letter, key_down).ok_or(format!(
+            "Failed to send the {:?} shortcut (is Accessibility access granted?)",
+            key
+        ))?;
+        // Only Command, whatever modifiers are still held from the hotkey
+        CGEvent::set_flags(Some(&event), CGEventFlags::MaskCommand);
+        CGEvent::post(CGEventTapLocation::HIDEventTap, Some(&event));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn send_edit_shortcut(key: EditKey) -> Result<(), String> {
+    let keys = match key {
+        EditKey::Copy => "ctrl+c",
+        EditKey::Paste => "ctrl+v",
+    };
+    // --clearmodifiers releases modifiers still held from the hotkey
+    let status = std::process::Command::new("xdotool")
+        .args(["key", "--clearmodifiers", keys])
+        .status()
+        .map_err(|e| format!("Failed to run xdotool: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("xdotool failed: {}", status))
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn send_edit_shortcut(_key: EditKey) -> Result<(), String> {
+    Err("Sending keystrokes isn't supported on this platform".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    /// In-memory clipboard; the fake keystroke copies `selection` into it.
+    #[derive(Default)]
+    struct FakeClipboard {
+        text: RefCell<Option<String>>,
+        other_content: Cell<bool>,
+        count: Cell<u64>,
+        has_counter: bool,
+        writes: Cell<usize>,
+    }
+
+    impl FakeClipboard {
+        fn with_text(text: Option<&str>, has_counter: bool) -> Self {
+            Self {
+                text: RefCell::new(text.map(str::to_string)),
+                has_counter,
+                ..Self::default()
+            }
+        }
+
+        fn copy(&self, text: &str) {
+            *self.text.borrow_mut() = Some(text.to_string());
+            self.other_content.set(false);
+            self.count.set(self.count.get() + 1);
+        }
+    }
+
+    impl SelectionClipboard for FakeClipboard {
+        fn text(&self) -> Option<String> {
+            self.text.borrow().clone()
+        }
+
+        fn has_content(&self) -> bool {
+            self.text.borrow().is_some() || self.other_content.get()
+        }
+
+        fn change_count(&self) -> Option<u64> {
+            self.has_counter.then(|| self.count.get())
+        }
+
+        fn set_text(&self, text: &str) -> Result<(), String> {
+            self.writes.set(self.writes.get() + 1);
+            self.copy(text);
+            Ok(())
+        }
+
+        fn clear(&self) -> Result<(), String> {
+            self.writes.set(self.writes.get() + 1);
+            *self.text.borrow_mut() = None;
+            self.count.set(self.count.get() + 1);
+            Ok(())
+        }
+    }
+
+    /// Copies `selection` (if any) when the shortcut is sent.
+    struct FakeKeys<'a> {
+        clipboard: &'a FakeClipboard,
+        selection: Option<&'a str>,
+        sent: Cell<bool>,
+    }
+
+    impl<'a> FakeKeys<'a> {
+        fn new(clipboard: &'a FakeClipboard, selection: Option<&'a str>) -> Self {
+            Self {
+                clipboard,
+                selection,
+                sent: Cell::new(false),
+            }
+        }
+    }
+
+    impl CopyKeystroke for FakeKeys<'_> {
+        fn send_copy(&self) -> Result<(), String> {
+            self.sent.set(true);
+            if let Some(selection) = self.selection {
+                self.clipboard.copy(selection);
+            }
+            Ok(())
+        }
+    }
+
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn test_selection_is_captured_and_clipboard_restored() {
+        for has_counter in [true, false] {
+            let clipboard = FakeClipboard::with_text(Some("copied earlier"), has_counter);
+            let keys = FakeKeys::new(&clipboard, Some("selected text"));
+
+            let selection = capture_with(&clipboard, &keys, TIMEOUT);
+
+            assert_eq!(selection.as_deref(), Some("selected text"));
+            assert_eq!(clipboard.text().as_deref(), Some("copied earlier"));
+        }
+    }
+
+    #[test]
+    fn test_empty_clipboard_is_cleared_again() {
+        let clipboard = FakeClipboard::with_text(None, true);
+        let keys = FakeKeys::new(&clipboard, Some("selected text"));
+
+        let selection = capture_with(&clipboard, &keys, TIMEOUT);
+
+        assert_eq!(selection.as_deref(), Some("selected text"));
+        assert_eq!(clipboard.text(), None);
+    }
+
+    #[test]
+    fn test_no_selection_leaves_clipboard_untouched() {
+        for has_counter in [true, false] {
+            let clipboard = FakeClipboard::with_text(Some("copied earlier"), has_counter);
+            let keys = FakeKeys::new(&clipboard, None);
+
+            let selection = capture_with(&clipboard, &keys, TIMEOUT);
+
+            assert_eq!(selection, None);
+            assert!(keys.sent.get());
+            assert_eq!(clipboard.writes.get(), 0);
+            assert_eq!(clipboard.text().as_deref(), Some("copied earlier"));
+        }
+    }
+
+    #[test]
+    fn test_same_text_selected_is_detected_with_counter() {
+        let clipboard = FakeClipboard::with_text(Some("same"), true);
+        let keys = FakeKeys::new(&clipboard, Some("same"));
+
+        assert_eq!(
+            capture_with(&clipboard, &keys, TIMEOUT).as_deref(),
+            Some("same")
+        );
+    }
+
+    #[test]
+    fn test_blank_selection_is_none_but_restored() {
+        let clipboard = FakeClipboard::with_text(Some("copied earlier"), true);
+        let keys = FakeKeys::new(&clipboard, Some("  \n"));
+
+        assert_eq!(capture_with(&clipboard, &keys, TIMEOUT), None);
+        assert_eq!(clipboard.text().as_deref(), Some("copied earlier"));
+    }
+
+    #[test]
+    fn test_non_text_clipboard_is_never_touched() {
+        let clipboard = FakeClipboard::with_text(None, true);
+        clipboard.other_content.set(true);
+        let keys = FakeKeys::new(&clipboard, Some("selected text"));
+
+        assert_eq!(capture_with(&clipboard, &keys, TIMEOUT), None);
+        assert!(!keys.sent.get());
+        assert!(clipboard.other_content.get());
+    }
+
+    #[test]
+    fn test_failed_keystroke_gives_none() {
+        struct Blocked;
+        impl CopyKeystroke for Blocked {
+            fn send_copy(&self) -> Result<(), String> {
+                Err("blocked".to_string())
+            }
+        }
+        let clipboard = FakeClipboard::with_text(Some("copied earlier"), true);
+
+        assert_eq!(capture_with(&clipboard, &Blocked, TIMEOUT), None);
+        assert_eq!(clipboard.writes.get(), 0);
+    }
+}
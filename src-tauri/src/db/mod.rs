@@ -0,0 +1,341 @@
+//! Rust-side access to the chat history database.
+//!
+//! The frontend reads and writes `history.db` through `tauri-plugin-sql`.
+//! Backend features that need the same database (e.g. the LLM response cache)
+//! go through the [`Db`] pool defined here, which points at the same file
+//! and applies the same migrations from [`crate::migrations`].
+//!
+//! # Migrations
+//!
+//! Both the plugin and this module use sqlx's migrator with identical
+//! version/SQL pairs, so whichever side opens the database first applies
+//! pending migrations and the other sees them as already applied.
+//!
+//! A database migrated by a newer version of the app isn't opened at all;
+//! see [`schema`].
+//!
+//! # Connection Settings
+//!
+//! Every pooled connection is opened with [`connect_options`]:
+//!
+//! - `journal_mode=WAL` - readers never block the writer; persisted in the
+//!   file, so the frontend's plugin connection uses WAL too
+//! - `foreign_keys=ON` - `ON DELETE CASCADE` removes a conversation's messages
+//! - `busy_timeout=5000` - wait up to 5 s for a lock instead of failing with
+//!   "database is locked"
+//! - `synchronous=NORMAL` - safe with WAL and avoids an fsync per commit
+//!
+//! Transactions that write messages start with [`begin_write`].
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sqlx::error::BoxDynError;
+use sqlx::migrate::{Migration, MigrationSource, MigrationType, Migrator};
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
+};
+use sqlx::{Sqlite, Transaction};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_sql::MigrationKind;
+
+use crate::migrations::get_migrations;
+use crate::paths;
+use crate::settings::SettingsManager;
+
+pub mod schema;
+
+/// File name of the history database, relative to the app config directory.
+///
+/// Must match the `sqlite:history.db` URL registered with `tauri-plugin-sql`.
+/// Not used once `history.database_path` moves the database elsewhere.
+pub const DATABASE_FILE: &str = "history.db";
+
+/// How long a connection waits for a lock held by another connection.
+pub const BUSY_TIMEOUT: Duration = Duration::from_millis(5000);
+
+/// Pooled connection to the history database.
+///
+/// Managed as Tauri state and shared by all backend modules that need
+/// SQLite access. See the module docs for the per-connection settings.
+pub struct Db {
+    pool: SqlitePool,
+}
+
+impl Db {
+    /// Open (creating if necessary) the database at `path` and run migrations.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Absolute path of the SQLite file
+    pub async fn open(path: &Path) -> Result<Self, String> {
+        let pool = SqlitePoolOptions::new()
+            .connect_with(connect_options(path))
+            .await
+            .map_err(|e| format!("Failed to open history database: {}", e))?;
+
+        let db = Self { pool };
+        db.migrate().await?;
+        Ok(db)
+    }
+
+    /// Open a private in-memory database with all migrations applied.
+    ///
+    /// Used in tests, and in place of a database refused by
+    /// [`schema::check`]. The pool is limited to a single connection that is
+    /// never closed because every SQLite in-memory connection would
+    /// otherwise get its own empty database.
+    pub async fn in_memory() -> Result<Self, String> {
+        let options = "sqlite::memory:"
+            .parse::<SqliteConnectOptions>()
+            .map_err(|e| format!("Invalid database URL: {}", e))?
+            .foreign_keys(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await
+            .map_err(|e| format!("Failed to open in-memory database: {}", e))?;
+
+        let db = Self { pool };
+        db.migrate().await?;
+        Ok(db)
+    }
+
+    /// Apply any pending migrations.
+    pub async fn migrate(&self) -> Result<(), String> {
+        let migrator = Migrator::new(HistoryMigrations)
+            .await
+            .map_err(|e| format!("Failed to load migrations: {}", e))?;
+
+        migrator
+            .run(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to run migrations: {}", e))
+    }
+
+    /// Borrow the underlying connection pool.
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+}
+
+/// Begin a transaction holding the write lock from the start
+/// (`BEGIN IMMEDIATE`).
+///
+/// Used by transactions that write messages: the search triggers make those
+/// writes read the FTS index too, and a deferred transaction that read first
+/// fails with "database is locked" when another connection commits before
+/// it, instead of waiting out [`BUSY_TIMEOUT`].
+pub async fn begin_write(pool: &SqlitePool) -> Result<Transaction<'static, Sqlite>, sqlx::Error> {
+    pool.begin_with("BEGIN IMMEDIATE").await
+}
+
+/// Connection options for the history database file at `path`.
+///
+/// Creates the file if it doesn't exist and applies the settings listed in
+/// the module docs.
+pub fn connect_options(path: &Path) -> SqliteConnectOptions {
+    SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .foreign_keys(true)
+        .busy_timeout(BUSY_TIMEOUT)
+        .synchronous(SqliteSynchronous::Normal)
+}
+
+/// Resolve the on-disk location of the history database.
+///
+/// `history.database_path` when set, otherwise the app config directory,
+/// where `tauri-plugin-sql` stores SQLite files (see
+/// [`paths::database_file`]).
+pub fn database_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let settings = app.state::<SettingsManager>().snapshot()?;
+    paths::database_file(app, settings.history.database_path.as_deref())
+}
+
+/// Current Unix timestamp in milliseconds, the unit used by every
+/// timestamp column in the history database.
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Adapts [`get_migrations`] to sqlx's migration source.
+///
+/// Mirrors the conversion done inside `tauri-plugin-sql` so checksums match.
+#[derive(Debug)]
+struct HistoryMigrations;
+
+impl MigrationSource<'static> for HistoryMigrations {
+    fn resolve(
+        self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Migration>, BoxDynError>> + Send + 'static>> {
+        Box::pin(async move {
+            let migrations = get_migrations()
+                .into_iter()
+                .filter(|m| matches!(m.kind, MigrationKind::Up))
+                .map(|m| {
+                    Migration::new(
+                        m.version,
+                        m.description.into(),
+                        MigrationType::ReversibleUp,
+                        m.sql.into(),
+                        false,
+                    )
+                })
+                .collect();
+            Ok(migrations)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::store;
+    use crate::history::types::{HistoryError, MessageMetadata};
+
+    #[tokio::test]
+    async fn test_in_memory_database_has_history_tables() {
+        let db = Db::in_memory().await.unwrap();
+
+        let tables: Vec<(String,)> =
+            sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table'")
+                .fetch_all(db.pool())
+                .await
+                .unwrap();
+        let names: Vec<&str> = tables.iter().map(|t| t.0.as_str()).collect();
+
+        assert!(names.contains(&"conversations"));
+        assert!(names.contains(&"messages"));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_is_idempotent() {
+        let db = Db::in_memory().await.unwrap();
+        assert!(db.migrate().await.is_ok());
+    }
+
+    // ===== Connection settings =====
+
+    #[tokio::test]
+    async fn test_file_database_connection_pragmas() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Db::open(&dir.path().join(DATABASE_FILE)).await.unwrap();
+        let mut conn = db.pool().acquire().await.unwrap();
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+
+        assert_eq!(journal_mode, "wal");
+        assert_eq!(foreign_keys, 1);
+        assert_eq!(busy_timeout, 5000);
+        assert_eq!(synchronous, 1); // NORMAL
+    }
+
+    #[tokio::test]
+    async fn test_deleting_conversation_cascades_to_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Db::open(&dir.path().join(DATABASE_FILE)).await.unwrap();
+        let pool = db.pool();
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        store::append_message(
+            pool,
+            &conversation.id,
+            "user",
+            "hi",
+            &MessageMetadata::default(),
+            &[],
+            1,
+        )
+        .await
+        .unwrap();
+
+        sqlx::query("DELETE FROM conversations")
+            .execute(pool)
+            .await
+            .unwrap();
+
+        let messages: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(messages, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_writes_and_reads_do_not_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Db::open(&dir.path().join(DATABASE_FILE)).await.unwrap();
+        let conversation = store::create_conversation(db.pool(), None, 0)
+            .await
+            .unwrap();
+
+        let mut tasks = Vec::new();
+        for writer in 0..8 {
+            let pool = db.pool().clone();
+            let id = conversation.id.clone();
+            tasks.push(tokio::spawn(async move {
+                for i in 0..25 {
+                    store::append_message(
+                        &pool,
+                        &id,
+                        "user",
+                        &format!("{}-{}", writer, i),
+                        &MessageMetadata::default(),
+                        &[],
+                        writer * 100 + i,
+                    )
+                    .await
+                    .map(|_| ())?;
+                }
+                Ok::<(), HistoryError>(())
+            }));
+        }
+        for _ in 0..2 {
+            let pool = db.pool().clone();
+            tasks.push(tokio::spawn(async move {
+                for _ in 0..50 {
+                    store::list_conversations(&pool, 50, 0, true, None).await?;
+                }
+                Ok(())
+            }));
+        }
+
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        let messages: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(messages, 200);
+    }
+
+    #[test]
+    fn test_now_ms_is_after_2020() {
+        assert!(now_ms() > 1_577_836_800_000);
+    }
+}
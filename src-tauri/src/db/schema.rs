@@ -0,0 +1,435 @@
+//! Refusing a history database migrated by a newer version of the app.
+//!
+//! After a downgrade, `history.db` may carry migrations this build doesn't
+//! know. sqlx refuses to migrate such a database, and writing to it with an
+//! older idea of the schema could corrupt it, so setup checks the highest
+//! version in `_sqlx_migrations` before opening the database. When it is
+//! newer than [`latest_version`]:
+//!
+//! - the file is left untouched, and the backend runs on an empty in-memory
+//!   database ([`crate::db::Db::in_memory`]) so the app still starts;
+//!   nothing written there is kept
+//! - history backups and maintenance don't run this session
+//! - `db-schema-too-new` is emitted and [`get_db_schema_status`] returns the
+//!   same payload, for a frontend that wasn't listening yet
+//!
+//! The user can then upgrade again, or call [`recreate_history_db`]: it
+//! copies the database beside itself as `<file>.v<version>-<timestamp>.bak`,
+//! empties it, applies this build's migrations and points the backend at
+//! it. The frontend reloads its `tauri-plugin-sql` connection afterwards.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const tooNew = await invoke<SchemaTooNew | null>('get_db_schema_status');
+//! await listen<SchemaTooNew>('db-schema-too-new', ({ payload }) => showDowngradeDialog(payload));
+//! // { path: '/…/history.db', database_version: 19, supported_version: 17 }
+//! const backup = await invoke<string>('recreate_history_db');
+//! await Database.load('sqlite:history.db');
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Connection, SqliteConnection};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::{now_ms, Db, BUSY_TIMEOUT};
+use crate::history::backup::ChangeTracker;
+use crate::history::relocate;
+use crate::migrations::latest_version;
+
+/// Event sent when setup refused a database newer than this build.
+pub const TOO_NEW_EVENT: &str = "db-schema-too-new";
+
+/// Payload of the `db-schema-too-new` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaTooNew {
+    /// Absolute path of the refused database file
+    pub path: String,
+    /// Highest migration applied to it
+    pub database_version: i64,
+    /// Highest migration this build knows
+    pub supported_version: i64,
+}
+
+/// The database setup refused, if any. Managed as Tauri state.
+#[derive(Debug, Default)]
+pub struct SchemaGuard {
+    refused: Mutex<Option<SchemaTooNew>>,
+}
+
+impl SchemaGuard {
+    /// Remember that the database was refused.
+    pub fn refuse(&self, status: SchemaTooNew) {
+        *self.refused.lock().unwrap() = Some(status);
+    }
+
+    /// The refused database, or `None` when the backend uses the real one.
+    pub fn status(&self) -> Option<SchemaTooNew> {
+        self.refused.lock().unwrap().clone()
+    }
+
+    /// Forget the refusal once the database was recreated.
+    pub fn clear(&self) {
+        *self.refused.lock().unwrap() = None;
+    }
+}
+
+/// Highest migration applied to the database at `path`.
+///
+/// Neither creates nor migrates the file.
+///
+/// # Returns
+///
+/// * `Ok(None)` - The file doesn't exist or was never migrated
+/// * `Ok(Some(version))` - Highest version in `_sqlx_migrations`
+/// * `Err(String)` - The file couldn't be read
+pub async fn applied_version(path: &Path) -> Result<Option<i64>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut conn = connect(path).await?;
+    let migrated: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_one(&mut conn)
+    .await
+    .map_err(|e| format!("Failed to read database schema: {}", e))?;
+    let version = if migrated == 0 {
+        None
+    } else {
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations")
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|e| format!("Failed to read database schema: {}", e))?
+    };
+    let _ = conn.close().await;
+    Ok(version)
+}
+
+/// Check whether the database at `path` is newer than this build.
+///
+/// # Returns
+///
+/// * `Ok(Some(status))` - It must not be opened
+/// * `Ok(None)` - It can be opened and migrated
+pub async fn check(path: &Path) -> Result<Option<SchemaTooNew>, String> {
+    let supported_version = latest_version();
+    Ok(applied_version(path)
+        .await?
+        .filter(|version| *version > supported_version)
+        .map(|database_version| SchemaTooNew {
+            path: path.to_string_lossy().into_owned(),
+            database_version,
+            supported_version,
+        }))
+}
+
+/// Record a refused database and tell the frontend.
+pub fn report(app: &AppHandle, status: SchemaTooNew) {
+    tracing::error!(
+        path = %status.path,
+        database_version = status.database_version,
+        supported_version = status.supported_version,
+        "History database is newer than this version; not opening it"
+    );
+    app.state::<SchemaGuard>().refuse(status.clone());
+    let _ = app.emit(TOO_NEW_EVENT, status);
+}
+
+/// Where [`recreate`] copies a database of schema `version`.
+pub fn backup_file(path: &Path, version: i64, now_ms: i64) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".v{}-{}.bak", version, now_ms));
+    PathBuf::from(name)
+}
+
+/// Copy the database at `path` to its [`backup_file`], then drop
+/// everything in it, `_sqlx_migrations` included, so it can be migrated
+/// from scratch.
+///
+/// The file is emptied in place rather than replaced, so connections that
+/// still have it open (the backup tracker, the frontend's) don't keep a
+/// deleted file alive. Nothing is dropped unless the copy succeeded.
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - The copy
+/// * `Err(String)` - The copy couldn't be written, or emptying failed
+pub async fn recreate(path: &Path, version: i64, now_ms: i64) -> Result<PathBuf, String> {
+    let backup = backup_file(path, version, now_ms);
+    let mut conn = connect(path).await?;
+    sqlx::query("VACUUM INTO ?")
+        .bind(backup.to_string_lossy())
+        .execute(&mut conn)
+        .await
+        .map_err(|e| format!("Failed to back up history database: {}", e))?;
+
+    // Virtual tables first: dropping one also drops its shadow tables
+    let tables: Vec<(String, String)> = sqlx::query_as(
+        "SELECT type, name FROM sqlite_master
+         WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%'
+         ORDER BY type = 'view' DESC, sql LIKE 'CREATE VIRTUAL%' DESC",
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|e| format!("Failed to read database schema: {}", e))?;
+    let mut tx = conn
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to clear history database: {}", e))?;
+    for (kind, name) in tables {
+        sqlx::query(&format!(
+            "DROP {} IF EXISTS \"{}\"",
+            kind.to_uppercase(),
+            name.replace('"', "\"\"")
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to clear history database: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to clear history database: {}", e))?;
+    let _ = sqlx::query("VACUUM").execute(&mut conn).await;
+    let _ = conn.close().await;
+    Ok(backup)
+}
+
+/// Open a single connection to an existing database, without the pool's
+/// settings: `journal_mode` is left as the file has it, and foreign keys
+/// aren't enforced so tables can be dropped in any order.
+async fn connect(path: &Path) -> Result<SqliteConnection, String> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .foreign_keys(false)
+        .busy_timeout(BUSY_TIMEOUT);
+    SqliteConnection::connect_with(&options)
+        .await
+        .map_err(|e| format!("Failed to open history database: {}", e))
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Get the database setup refused for being newer than this build.
+///
+/// # Returns
+///
+/// The `db-schema-too-new` payload, or `null` when history works normally.
+#[tauri::command]
+pub fn get_db_schema_status(guard: State<'_, SchemaGuard>) -> Option<SchemaTooNew> {
+    guard.status()
+}
+
+/// Back up the refused database and start over with an empty one.
+///
+/// # Returns
+///
+/// * `Ok(String)` - Path of the backup copy
+/// * `Err(String)` - No database was refused, or backing up, emptying or
+///   migrating failed; the file is untouched unless the backup was written
+#[tauri::command]
+pub async fn recreate_history_db(
+    db: State<'_, Db>,
+    guard: State<'_, SchemaGuard>,
+    tracker: State<'_, ChangeTracker>,
+) -> Result<String, String> {
+    let status = guard
+        .status()
+        .ok_or_else(|| "History database is not newer than this version".to_string())?;
+    let path = PathBuf::from(&status.path);
+
+    let backup = recreate(&path, status.database_version, now_ms()).await?;
+    relocate::point_at(db.pool(), &path)
+        .await
+        .map_err(|e| e.to_string())?;
+    db.migrate().await?;
+    if let Err(e) = tracker.switch_to(&path).await {
+        tracing::warn!(error = %e, "Failed to reset backup change tracker");
+    }
+    guard.clear();
+    tracing::warn!(
+        path = %path.display(),
+        backup = %backup.display(),
+        "Recreated history database"
+    );
+    Ok(backup.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DATABASE_FILE;
+    use crate::history::store;
+
+    /// A migrated database, with a conversation, that a newer build
+    /// migrated to `latest_version() + 2`.
+    async fn newer_database(path: &Path) -> String {
+        let db = Db::open(path).await.unwrap();
+        let conversation = store::create_conversation(db.pool(), Some("Kept".to_string()), 0)
+            .await
+            .unwrap();
+        for version in latest_version() + 1..=latest_version() + 2 {
+            sqlx::query(
+                "INSERT INTO _sqlx_migrations
+                 (version, description, success, checksum, execution_time)
+                 VALUES (?, 'from_the_future', 1, x'00', 0)",
+            )
+            .bind(version)
+            .execute(db.pool())
+            .await
+            .unwrap();
+        }
+        sqlx::query("CREATE TABLE future (id INTEGER PRIMARY KEY)")
+            .execute(db.pool())
+            .await
+            .unwrap();
+        db.pool().close().await;
+        conversation.id
+    }
+
+    // ===== Check =====
+
+    #[tokio::test]
+    async fn test_missing_file_is_not_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+
+        assert_eq!(check(&path).await, Ok(None));
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_current_database_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        Db::open(&path).await.unwrap().pool().close().await;
+
+        assert_eq!(applied_version(&path).await, Ok(Some(latest_version())));
+        assert_eq!(check(&path).await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn test_unmigrated_database_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        std::fs::write(&path, b"").unwrap();
+
+        assert_eq!(applied_version(&path).await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn test_newer_database_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        newer_database(&path).await;
+
+        let status = check(&path).await.unwrap().unwrap();
+
+        assert_eq!(status.database_version, latest_version() + 2);
+        assert_eq!(status.supported_version, latest_version());
+        assert_eq!(status.path, path.to_string_lossy());
+        // What the check protects against
+        assert!(Db::open(&path).await.is_err());
+    }
+
+    #[test]
+    fn test_too_new_shape() {
+        let status = SchemaTooNew {
+            path: "/data/history.db".to_string(),
+            database_version: 19,
+            supported_version: 17,
+        };
+
+        assert_eq!(
+            serde_json::to_value(status).unwrap(),
+            serde_json::json!({
+                "path": "/data/history.db",
+                "database_version": 19,
+                "supported_version": 17,
+            })
+        );
+    }
+
+    #[test]
+    fn test_guard() {
+        let guard = SchemaGuard::default();
+        assert_eq!(guard.status(), None);
+
+        let status = SchemaTooNew {
+            path: "history.db".to_string(),
+            database_version: 19,
+            supported_version: 17,
+        };
+        guard.refuse(status.clone());
+        assert_eq!(guard.status(), Some(status));
+        guard.clear();
+        assert_eq!(guard.status(), None);
+    }
+
+    // ===== Recreate =====
+
+    #[test]
+    fn test_backup_file_name() {
+        assert_eq!(
+            backup_file(Path::new("/data/history.db"), 19, 1_700_000_000_000),
+            PathBuf::from("/data/history.db.v19-1700000000000.bak")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recreate_backs_up_then_starts_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        let id = newer_database(&path).await;
+
+        let backup = recreate(&path, latest_version() + 2, 5).await.unwrap();
+
+        assert_eq!(applied_version(&path).await, Ok(None));
+        assert_eq!(
+            applied_version(&backup).await,
+            Ok(Some(latest_version() + 2))
+        );
+        let mut conn = connect(&backup).await.unwrap();
+        let title: String = sqlx::query_scalar("SELECT title FROM conversations WHERE id = ?")
+            .bind(&id)
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(title, "Kept");
+        let _ = conn.close().await;
+
+        let db = Db::open(&path).await.unwrap();
+        assert!(store::list_conversations(db.pool(), 50, 0, true, None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_fallback_follows_recreated_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        newer_database(&path).await;
+        let db = Db::in_memory().await.unwrap();
+        store::create_conversation(db.pool(), Some("Scratch".to_string()), 0)
+            .await
+            .unwrap();
+
+        recreate(&path, latest_version() + 2, 5).await.unwrap();
+        relocate::point_at(db.pool(), &path).await.unwrap();
+        db.migrate().await.unwrap();
+
+        assert_eq!(relocate::current_file(db.pool()), path);
+        assert_eq!(applied_version(&path).await, Ok(Some(latest_version())));
+        assert!(store::list_conversations(db.pool(), 50, 0, true, None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
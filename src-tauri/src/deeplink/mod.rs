@@ -0,0 +1,477 @@
+//! `qwikask://` links for opening Qwik Ask from other apps.
+//!
+//! Browser bookmarklets and scripts can open the launcher with a question:
+//!
+//! | Link | Opens |
+//! |------|-------|
+//! | `qwikask://ask?q=how+do+i+...` | The launcher with the question as the prompt |
+//! | `qwikask://ask?q=...&action=summarize` | The same, filled into a quick action's template |
+//! | `qwikask://ask?q=...&submit=1` | The same, sent right away |
+//! | `qwikask://conversation/<id>` | The launcher with that thread open |
+//! | `qwikask://settings/<section>` | The settings window at that section |
+//!
+//! [`parse`] turns a link into a [`DeepLink`] without touching the app;
+//! [`open`] routes it through the launcher and settings helpers. Prompts
+//! arrive as `prefill-prompt` (source `deep_link`), cut to
+//! `launcher.clipboard_max_chars` and flagged as truncated like clipboard
+//! text. `action` matches a quick action by ID or name.
+//!
+//! Links that can't be followed still show the launcher, with a
+//! `deep-link-error` explaining why.
+//!
+//! # Receiving links
+//!
+//! The scheme is declared under `plugins.deep-link` in `tauri.conf.json`,
+//! which the bundler registers for every platform; on Linux it is also
+//! registered at startup, for AppImages and development builds.
+//!
+//! - **macOS**: links arrive through the deep-link plugin ([`listen`])
+//! - **Windows and Linux**: links are passed as command-line arguments and
+//!   opened once the app has started (see [`crate::args`]). A link opened
+//!   while the app runs starts a second process, which hands its arguments
+//!   to this one through the single-instance plugin and exits
+//!   (see [`crate::args::forwarded`])
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! await listen<PrefillPrompt>('prefill-prompt', ({ payload }) => {
+//!   prompt.value = payload.text;
+//!   if (payload.submit) send();
+//! });
+//! await listen<DeepLinkFailed>('deep-link-error', ({ payload }) => showToast(payload.message));
+//! ```
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use thiserror::Error;
+
+use crate::clipboard::{self, PrefillPrompt, PrefillSource};
+use crate::notifications::OpenConversation;
+use crate::quick_actions;
+use crate::settings::{QuickAction, SettingsManager};
+use crate::window::{self, visibility::VisibilityReason};
+
+/// The URL scheme Qwik Ask handles.
+pub const SCHEME: &str = "qwikask";
+
+/// Where a link leads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    /// Show the launcher, optionally with a prompt
+    Ask {
+        /// The `q` parameter, truncated; `None` when absent or blank
+        query: Option<PrefillPrompt>,
+        /// Quick action ID or name to fill the query into
+        action: Option<String>,
+        /// Send the prompt without waiting for Enter
+        submit: bool,
+    },
+    /// Open a conversation in the launcher
+    Conversation { id: String },
+    /// Open the settings window, at a section if given
+    Settings { section: Option<String> },
+}
+
+/// Why a link couldn't be followed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DeepLinkError {
+    #[error("Not a valid link: {0}")]
+    InvalidUrl(String),
+    #[error("Not a {SCHEME}:// link")]
+    WrongScheme,
+    #[error("Unknown link '{0}'")]
+    UnknownRoute(String),
+    #[error("Invalid conversation ID '{0}'")]
+    InvalidConversationId(String),
+}
+
+/// Payload of the `deep-link-error` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeepLinkFailed {
+    pub url: String,
+    pub message: String,
+}
+
+/// Whether a command-line argument is a `qwikask:` link.
+pub fn is_deep_link(arg: &str) -> bool {
+    arg.get(..SCHEME.len() + 1)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{}:", SCHEME)))
+}
+
+/// Whether `id` looks like a conversation ID (letters, digits and dashes).
+pub fn is_conversation_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Whether `section` can name a settings section (letters, digits, `-`, `_`).
+pub fn is_section_name(section: &str) -> bool {
+    !section.is_empty()
+        && section
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parse a `qwikask://` link.
+///
+/// # Arguments
+///
+/// * `url` - The link, e.g. `qwikask://ask?q=what+is+rust`
+/// * `max_chars` - The `launcher.clipboard_max_chars` setting
+///
+/// # Returns
+///
+/// * `Ok(DeepLink)` - Where the link leads
+/// * `Err(DeepLinkError)` - Not a link, another scheme, an unknown path, or
+///   a malformed ID
+pub fn parse(url: &str, max_chars: u32) -> Result<DeepLink, DeepLinkError> {
+    let url = Url::parse(url.trim()).map_err(|e| DeepLinkError::InvalidUrl(e.to_string()))?;
+    if url.scheme() != SCHEME {
+        return Err(DeepLinkError::WrongScheme);
+    }
+
+    // `qwikask://ask/` has the route as its host, `qwikask:ask` as its path
+    let route: Vec<String> = url
+        .host_str()
+        .into_iter()
+        .chain(url.path().split('/'))
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect();
+    let unknown = || DeepLinkError::UnknownRoute(route.join("/"));
+
+    match route.first().map(|s| s.to_ascii_lowercase()).as_deref() {
+        Some("ask") if route.len() == 1 => {
+            let query = param(&url, "q");
+            Ok(DeepLink::Ask {
+                query: clipboard::prefill_prompt(
+                    query.as_deref(),
+                    max_chars,
+                    PrefillSource::DeepLink,
+                ),
+                action: param(&url, "action")
+                    .map(|action| action.trim().to_string())
+                    .filter(|action| !action.is_empty()),
+                submit: param(&url, "submit").is_some_and(|value| is_true(&value)),
+            })
+        }
+        Some("conversation") if route.len() <= 2 => {
+            let id = route.get(1).cloned().unwrap_or_default();
+            if !is_conversation_id(&id) {
+                return Err(DeepLinkError::InvalidConversationId(id));
+            }
+            Ok(DeepLink::Conversation { id })
+        }
+        Some("settings") if route.len() <= 2 => {
+            let section = route.get(1).map(|s| s.to_ascii_lowercase());
+            if section.as_deref().is_some_and(|s| !is_section_name(s)) {
+                return Err(unknown());
+            }
+            Ok(DeepLink::Settings { section })
+        }
+        _ => Err(unknown()),
+    }
+}
+
+/// The first value of a query parameter, decoded.
+fn param(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+fn is_true(value: &str) -> bool {
+    matches!(
+        value.to_ascii_lowercase().as_str(),
+        "" | "1" | "true" | "yes"
+    )
+}
+
+/// The prompt an `ask` link prefills.
+///
+/// # Arguments
+///
+/// * `query` - The link's query
+/// * `template` - Template of the link's quick action, if any
+/// * `submit` - The link's `submit` flag
+///
+/// # Returns
+///
+/// `None` when there is neither a query nor a quick action.
+pub fn ask_prompt(
+    query: Option<PrefillPrompt>,
+    template: Option<&str>,
+    submit: bool,
+) -> Option<PrefillPrompt> {
+    let prompt = match (query, template) {
+        (Some(mut query), Some(template)) => {
+            query.text = quick_actions::expand(template, Some(&query.text));
+            query
+        }
+        (None, Some(template)) => PrefillPrompt {
+            text: quick_actions::expand(template, None),
+            truncated: false,
+            original_chars: 0,
+            source: PrefillSource::DeepLink,
+            submit: false,
+        },
+        (query, None) => query?,
+    };
+    Some(PrefillPrompt { submit, ..prompt })
+}
+
+/// Find a quick action by ID, or by name ignoring case.
+fn find_action<'a>(actions: &'a [QuickAction], action: &str) -> Option<&'a QuickAction> {
+    actions
+        .iter()
+        .find(|a| a.id == action)
+        .or_else(|| actions.iter().find(|a| a.name.eq_ignore_ascii_case(action)))
+}
+
+/// Open links delivered by the deep-link plugin while the app runs.
+///
+/// Call once during setup.
+pub fn listen(app: &AppHandle) {
+    #[cfg(any(target_os = "linux", all(debug_assertions, target_os = "windows")))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::warn!(error = %e, "Failed to register the {} scheme", SCHEME);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            open(&handle, url.as_str());
+        }
+    });
+}
+
+/// Follow a `qwikask://` link. Must be called on the main thread.
+///
+/// Links that can't be followed show the launcher and emit
+/// `deep-link-error`.
+pub fn open(app: &AppHandle, url: &str) {
+    let settings = app
+        .state::<SettingsManager>()
+        .snapshot()
+        .unwrap_or_default();
+    let link = match parse(url, settings.launcher.clipboard_max_chars) {
+        Ok(link) => link,
+        Err(e) => {
+            fail(app, url, e.to_string());
+            return;
+        }
+    };
+
+    match link {
+        DeepLink::Ask {
+            query,
+            action,
+            submit,
+        } => {
+            let template = match action.as_deref() {
+                Some(action) => match find_action(&settings.quick_actions, action) {
+                    Some(found) => Some(found.prompt_template.as_str()),
+                    None => {
+                        fail(app, url, format!("Quick action '{}' not found", action));
+                        None
+                    }
+                },
+                None => None,
+            };
+            let prompt = ask_prompt(query, template, submit);
+            clipboard::show_prefilled(app, VisibilityReason::DeepLink, prompt);
+        }
+        DeepLink::Conversation { id } => {
+            window::show_launcher(app, VisibilityReason::DeepLink);
+            let _ = app.emit(
+                "open-conversation",
+                OpenConversation {
+                    conversation_id: id,
+                },
+            );
+        }
+        DeepLink::Settings { section } => {
+            if let Err(e) = window::open_settings_window(app, section) {
+                tracing::warn!(error = %e, "Failed to open settings from a deep link");
+            }
+        }
+    }
+}
+
+/// Log a link that can't be followed and tell the launcher why.
+fn fail(app: &AppHandle, url: &str, message: String) {
+    tracing::warn!(url, error = %message, "Deep link failed");
+    window::show_launcher(app, VisibilityReason::DeepLink);
+    let _ = app.emit(
+        "deep-link-error",
+        DeepLinkFailed {
+            url: url.to_string(),
+            message,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ask(url: &str) -> (Option<PrefillPrompt>, Option<String>, bool) {
+        match parse(url, 100).unwrap() {
+            DeepLink::Ask {
+                query,
+                action,
+                submit,
+            } => (query, action, submit),
+            other => panic!("expected an ask link, got {:?}", other),
+        }
+    }
+
+    // ===== Ask =====
+
+    #[test]
+    fn test_ask_decodes_query() {
+        let (query, action, submit) = ask("qwikask://ask?q=how+do+i+undo%20a%20commit%3F");
+
+        let query = query.unwrap();
+        assert_eq!(query.text, "how do i undo a commit?");
+        assert_eq!(query.source, PrefillSource::DeepLink);
+        assert!(!query.truncated);
+        assert_eq!(action, None);
+        assert!(!submit);
+    }
+
+    #[test]
+    fn test_ask_with_action_and_submit() {
+        let (query, action, submit) = ask("qwikask://ask?q=text&action=summarize&submit=1");
+
+        assert_eq!(query.unwrap().text, "text");
+        assert_eq!(action.as_deref(), Some("summarize"));
+        assert!(submit);
+        assert!(!ask("qwikask://ask?q=text&submit=no").2);
+    }
+
+    #[test]
+    fn test_ask_without_query() {
+        assert_eq!(ask("qwikask://ask").0, None);
+        assert_eq!(ask("qwikask://ask?q=+++").0, None);
+        assert_eq!(ask("qwikask:ask?action=").1, None);
+    }
+
+    #[test]
+    fn test_long_query_is_truncated() {
+        let url = format!("qwikask://ask?q={}", "é".repeat(500));
+
+        let query = ask(&url).0.unwrap();
+
+        assert_eq!(query.text.chars().count(), 100);
+        assert!(query.truncated);
+        assert_eq!(query.original_chars, 500);
+    }
+
+    #[test]
+    fn test_route_is_case_insensitive() {
+        assert!(ask("QwikAsk://ASK/?q=hi").0.is_some());
+    }
+
+    // ===== Conversation and settings =====
+
+    #[test]
+    fn test_conversation_route() {
+        assert_eq!(
+            parse("qwikask://conversation/0190a1b2-c3d4", 100),
+            Ok(DeepLink::Conversation {
+                id: "0190a1b2-c3d4".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_conversation_id_is_validated() {
+        assert_eq!(
+            parse("qwikask://conversation", 100),
+            Err(DeepLinkError::InvalidConversationId(String::new()))
+        );
+        assert_eq!(
+            parse("qwikask://conversation/a%20b", 100),
+            Err(DeepLinkError::InvalidConversationId("a%20b".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_settings_route() {
+        assert_eq!(
+            parse("qwikask://settings/LLM", 100),
+            Ok(DeepLink::Settings {
+                section: Some("llm".to_string())
+            })
+        );
+        assert_eq!(
+            parse("qwikask://settings", 100),
+            Ok(DeepLink::Settings { section: None })
+        );
+    }
+
+    // ===== Malformed links =====
+
+    #[test]
+    fn test_unknown_routes() {
+        assert_eq!(
+            parse("qwikask://launch", 100),
+            Err(DeepLinkError::UnknownRoute("launch".to_string()))
+        );
+        assert_eq!(
+            parse("qwikask://ask/extra", 100),
+            Err(DeepLinkError::UnknownRoute("ask/extra".to_string()))
+        );
+        assert_eq!(
+            parse("qwikask://settings/a/b", 100),
+            Err(DeepLinkError::UnknownRoute("settings/a/b".to_string()))
+        );
+        assert_eq!(
+            parse("qwikask://", 100),
+            Err(DeepLinkError::UnknownRoute(String::new()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_links() {
+        assert!(matches!(
+            parse("not a link", 100),
+            Err(DeepLinkError::InvalidUrl(_))
+        ));
+        assert_eq!(
+            parse("https://example.com/ask?q=hi", 100),
+            Err(DeepLinkError::WrongScheme)
+        );
+    }
+
+    #[test]
+    fn test_is_deep_link() {
+        assert!(is_deep_link("qwikask://ask?q=hi"));
+        assert!(is_deep_link("QWIKASK:ask"));
+        assert!(!is_deep_link("--hidden"));
+        assert!(!is_deep_link("qwik"));
+    }
+
+    // ===== Prompt =====
+
+    #[test]
+    fn test_ask_prompt_fills_action_template() {
+        let (query, _, _) = ask("qwikask://ask?q=some+text");
+
+        let prompt = ask_prompt(query, Some("Summarize:\n\n{{input}}"), true).unwrap();
+
+        assert_eq!(prompt.text, "Summarize:\n\nsome text");
+        assert!(prompt.submit);
+    }
+
+    #[test]
+    fn test_ask_prompt_without_query() {
+        assert_eq!(ask_prompt(None, None, true), None);
+        assert_eq!(
+            ask_prompt(None, Some("Tell me a joke"), false).map(|p| p.text),
+            Some("Tell me a joke".to_string())
+        );
+    }
+}
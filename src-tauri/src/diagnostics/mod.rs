@@ -0,0 +1,144 @@
+//! Diagnostics report for bug reports.
+//!
+//! `generate_diagnostics_report` collects what is usually asked for in an
+//! issue — versions and build (see [`crate::app_info`]), platform,
+//! settings, shortcut and autostart state, history size, update status,
+//! connectivity, the latest settings changes (see
+//! [`crate::settings::audit`]) and the end of the log — and returns it as markdown and as
+//! JSON (see [`report`]).
+//!
+//! API keys, prompts and credentials are masked with the same rules as the
+//! log file (see [`crate::logging::redact`]).
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! // Settings → Diagnostics → Copy report
+//! const report = await invoke<DiagnosticsReport>('generate_diagnostics_report');
+//! await writeText(report.markdown);
+//!
+//! // Save report…
+//! const path = await save({ defaultPath: 'qwik-ask-diagnostics.md' });
+//! if (path) await invoke('save_diagnostics_report', { path });
+//! ```
+
+pub mod report;
+
+use std::path::Path;
+
+use tauri::{AppHandle, Manager};
+
+use crate::app_info;
+use crate::db::{now_ms, Db};
+use crate::history::maintenance;
+use crate::llm::{self, connectivity::CACHE_TTL_MS, Connectivity};
+use crate::logging;
+use crate::settings::audit;
+use crate::settings::SettingsManager;
+use crate::updater::UpdateScheduler;
+pub use report::DiagnosticsReport;
+use report::{Diagnostics, ShortcutStatus, UpdateStatus};
+
+/// Log lines included in the report.
+pub const LOG_LINES: usize = 50;
+
+/// Settings audit entries included in the report.
+pub const AUDIT_ENTRIES: u32 = 5;
+
+/// Collect the diagnostics.
+///
+/// Probes the configured provider unless it was checked within
+/// [`CACHE_TTL_MS`]. Parts that can't be read are left out rather than
+/// failing the report.
+async fn collect(app: &AppHandle) -> Result<Diagnostics, String> {
+    let settings_manager = app.state::<SettingsManager>();
+    let settings = settings_manager.snapshot()?;
+
+    let connectivity = app.state::<Connectivity>();
+    llm::probe_provider(app, &connectivity, &settings.llm, CACHE_TTL_MS).await;
+
+    let (history, settings_audit) = match app.try_state::<Db>() {
+        Some(db) => (
+            maintenance::stats(db.pool())
+                .await
+                .map_err(|e| tracing::warn!(error = %e, "Failed to read history stats"))
+                .ok(),
+            audit::list(db.pool(), AUDIT_ENTRIES)
+                .await
+                .map_err(|e| tracing::warn!(error = %e, "Failed to read settings audit log"))
+                .unwrap_or_default(),
+        ),
+        None => (None, Vec::new()),
+    };
+    let recent_logs = match logging::logs_dir(app) {
+        Ok(dir) => {
+            tauri::async_runtime::spawn_blocking(move || logging::recent_lines(&dir, LOG_LINES))
+                .await
+                .unwrap_or_default()
+        }
+        Err(_) => Vec::new(),
+    };
+
+    Ok(Diagnostics {
+        generated_at: now_ms(),
+        app: app_info::app_info(app),
+        os: std::env::consts::OS.to_string(),
+        shortcut: ShortcutStatus {
+            registered: settings_manager
+                .is_shortcut_registered(&settings.shortcuts.toggle_launcher),
+            paused: settings_manager.shortcuts_paused(),
+            configured: settings.shortcuts.toggle_launcher.clone(),
+        },
+        autostart: settings_manager.get_auto_startup_status().ok(),
+        history,
+        updates: UpdateStatus {
+            channel: settings.updates.channel,
+            auto_check: settings.updates.auto_check,
+            last_check: app
+                .try_state::<UpdateScheduler>()
+                .and_then(|scheduler| scheduler.last_check()),
+        },
+        connectivity: connectivity.results(),
+        recent_logs,
+        settings_audit,
+        settings: serde_json::to_value(&*settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?,
+    })
+}
+
+/// Generate the diagnostics report.
+///
+/// # Returns
+///
+/// * `Ok(DiagnosticsReport)` - The redacted report as markdown and JSON
+/// * `Err(String)` - The settings couldn't be loaded
+#[tauri::command]
+pub async fn generate_diagnostics_report(app: AppHandle) -> Result<DiagnosticsReport, String> {
+    Ok(DiagnosticsReport::new(collect(&app).await?))
+}
+
+/// Generate the diagnostics report and write it to `path`.
+///
+/// # Arguments
+///
+/// * `path` - File to write; a `.json` extension writes the JSON form,
+///   anything else the markdown
+///
+/// # Returns
+///
+/// * `Ok(())` - Report written
+/// * `Err(String)` - The report couldn't be generated or written
+#[tauri::command]
+pub async fn save_diagnostics_report(app: AppHandle, path: String) -> Result<(), String> {
+    let report = DiagnosticsReport::new(collect(&app).await?);
+    let path = Path::new(&path);
+    let contents = if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+    {
+        report.json
+    } else {
+        report.markdown
+    };
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write report: {}", e))
+}
@@ -0,0 +1,395 @@
+//! `.env` file in the data directory, for provider credentials.
+//!
+//! Instead of exporting variables system-wide, `KEY=VALUE` lines can be put
+//! in `.env` next to `settings.json`. The file is read at startup and by
+//! `reload_env`, and `get_environment_variable` looks its variables up
+//! before the process environment. The app only ever reads the file.
+//!
+//! The format follows the usual dotenv conventions:
+//!
+//! ```text
+//! # comment
+//! OPENAI_API_KEY=sk-...            # trailing comment
+//! export GROQ_API_KEY=gsk_...
+//! PROXY_PASSWORD='literal $value, no escapes'
+//! GREETING="line one\nline two"    # \n \r \t \" \\ escapes
+//! ```
+//!
+//! A UTF-8 byte order mark and CRLF line endings are accepted, and a key
+//! set twice takes the last value. Lines that can't be parsed are skipped
+//! and reported by line number in `env-file-error`; the report never
+//! includes the line itself. Every value is masked in the log and the
+//! diagnostics report (see [`redact::set_known_secrets`]).
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! await listen<EnvFileStatus>('env-file-error', ({ payload }) => {
+//!   // { path: '.../.env', variables: 3, errors: [{ line: 4, message: 'Unterminated double quote' }] }
+//!   showWarning(payload.errors.map((e) => `line ${e.line}: ${e.message}`));
+//! });
+//!
+//! const status = await invoke<EnvFileStatus>('reload_env');
+//! const key = await invoke<string | null>('get_environment_variable', { envName: 'OPENAI_API_KEY' });
+//! ```
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::logging::redact;
+use crate::paths;
+
+/// Name of the file in the data directory.
+pub const FILE_NAME: &str = ".env";
+
+/// Event sent when lines of the file couldn't be parsed.
+pub const ERROR_EVENT: &str = "env-file-error";
+
+/// A line of the file that couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvFileError {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+/// Outcome of reading the file; the payload of `env-file-error`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvFileStatus {
+    pub path: PathBuf,
+    /// Number of variables read; `0` if there is no file
+    pub variables: usize,
+    pub errors: Vec<EnvFileError>,
+}
+
+/// The variables read from the file, managed as Tauri state.
+#[derive(Default)]
+pub struct EnvFile(RwLock<HashMap<String, String>>);
+
+impl EnvFile {
+    /// The value of `name` in the file, if set there.
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.0.read().unwrap().get(name).cloned()
+    }
+
+    fn replace(&self, variables: HashMap<String, String>) {
+        *self.0.write().unwrap() = variables;
+    }
+}
+
+/// Parse the contents of a `.env` file.
+///
+/// # Returns
+///
+/// The variables, and the lines that were skipped because they couldn't be
+/// parsed.
+pub fn parse(text: &str) -> (HashMap<String, String>, Vec<EnvFileError>) {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut variables = HashMap::new();
+    let mut errors = Vec::new();
+    for (i, line) in text.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match parse_line(line) {
+            Ok(Some((name, value))) => {
+                variables.insert(name, value);
+            }
+            Ok(None) => {}
+            Err(message) => errors.push(EnvFileError {
+                line: i + 1,
+                message: message.to_string(),
+            }),
+        }
+    }
+    (variables, errors)
+}
+
+/// A variable from one line; `None` for blank lines and comments.
+fn parse_line(line: &str) -> Result<Option<(String, String)>, &'static str> {
+    let line = line.trim_start();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let line = line
+        .strip_prefix("export")
+        .filter(|rest| rest.starts_with([' ', '\t']))
+        .map_or(line, str::trim_start);
+    let (name, value) = line.split_once('=').ok_or("Expected KEY=VALUE")?;
+    let name = name.trim_end();
+    if !is_valid_name(name) {
+        return Err("Invalid variable name");
+    }
+    Ok(Some((name.to_string(), parse_value(value)?)))
+}
+
+/// Letters, digits, `_` and `.`, not starting with a digit.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// The value after `=`: quoted, or up to a ` #` comment.
+fn parse_value(raw: &str) -> Result<String, &'static str> {
+    let trimmed = raw.trim_start();
+    let (value, rest) = if let Some(quoted) = trimmed.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        let end = loop {
+            match chars.next() {
+                None => return Err("Unterminated double quote"),
+                Some((i, '"')) => break i + 1,
+                Some((_, '\\')) => match chars.next() {
+                    None => return Err("Unterminated double quote"),
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 'r')) => value.push('\r'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, c @ ('"' | '\\' | '$'))) => value.push(c),
+                    Some((_, c)) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                },
+                Some((_, c)) => value.push(c),
+            }
+        };
+        (value, &quoted[end..])
+    } else if let Some(quoted) = trimmed.strip_prefix('\'') {
+        let end = quoted.find('\'').ok_or("Unterminated single quote")?;
+        (quoted[..end].to_string(), &quoted[end + 1..])
+    } else {
+        let end = raw
+            .char_indices()
+            .find(|&(i, c)| c == '#' && raw[..i].ends_with([' ', '\t']))
+            .map_or(raw.len(), |(i, _)| i);
+        return Ok(raw[..end].trim().to_string());
+    };
+
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(value)
+    } else {
+        Err("Unexpected text after the closing quote")
+    }
+}
+
+/// Path of the file.
+pub fn env_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(paths::data_dir(app)?.join(FILE_NAME))
+}
+
+/// Read the file into [`EnvFile`], replacing what was read before.
+///
+/// A missing file counts as empty. Lines that can't be parsed are logged
+/// by number and reported in `env-file-error`.
+pub fn load(app: &AppHandle) -> Result<EnvFileStatus, String> {
+    let path = env_file_path(app)?;
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let (variables, errors) = parse(&text);
+
+    redact::set_known_secrets(variables.values().cloned());
+    let status = EnvFileStatus {
+        path,
+        variables: variables.len(),
+        errors,
+    };
+    if let Some(env_file) = app.try_state::<EnvFile>() {
+        env_file.replace(variables);
+    }
+    if !status.errors.is_empty() {
+        let lines: Vec<usize> = status.errors.iter().map(|error| error.line).collect();
+        tracing::warn!(?lines, path = %status.path.display(), "Skipped lines of the .env file");
+        let _ = app.emit(ERROR_EVENT, &status);
+    }
+    Ok(status)
+}
+
+/// Read `.env` again, e.g. after editing it.
+///
+/// # Returns
+///
+/// * `Ok(EnvFileStatus)` - Number of variables and any skipped lines
+/// * `Err(String)` - The file exists but couldn't be read
+#[tauri::command]
+pub fn reload_env(app: AppHandle) -> Result<EnvFileStatus, String> {
+    load(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(text: &str) -> HashMap<String, String> {
+        let (variables, errors) = parse(text);
+        assert_eq!(errors, [], "unexpected errors in {:?}", text);
+        variables
+    }
+
+    fn value(text: &str) -> String {
+        parsed(text).remove("KEY").expect("KEY not set")
+    }
+
+    fn error_lines(text: &str) -> Vec<usize> {
+        parse(text).1.iter().map(|error| error.line).collect()
+    }
+
+    // ===== Lines =====
+
+    #[test]
+    fn test_plain_pairs() {
+        let variables = parsed("OPENAI_API_KEY=sk-abc\nGROQ_API_KEY = gsk_123\n");
+
+        assert_eq!(variables.len(), 2);
+        assert_eq!(variables["OPENAI_API_KEY"], "sk-abc");
+        assert_eq!(variables["GROQ_API_KEY"], "gsk_123");
+    }
+
+    #[test]
+    fn test_comments_and_blank_lines() {
+        let variables = parsed("# credentials\n\n   # indented\nKEY=value\n\t\n");
+
+        assert_eq!(variables.len(), 1);
+        assert_eq!(variables["KEY"], "value");
+    }
+
+    #[test]
+    fn test_export_prefix() {
+        assert_eq!(value("export KEY=value"), "value");
+        assert_eq!(value("export\tKEY=value"), "value");
+        assert_eq!(parsed("exported=1")["exported"], "1");
+    }
+
+    #[test]
+    fn test_empty_value() {
+        assert_eq!(value("KEY="), "");
+        assert_eq!(value("KEY=   "), "");
+        assert_eq!(value("KEY=\"\""), "");
+    }
+
+    #[test]
+    fn test_names() {
+        assert!(parsed("_PRIVATE=1").contains_key("_PRIVATE"));
+        assert!(parsed("app.key=1").contains_key("app.key"));
+        assert_eq!(error_lines("1KEY=x\nMY-KEY=x\n=x"), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_duplicate_keys_last_wins() {
+        assert_eq!(value("KEY=first\nOTHER=x\nKEY=second"), "second");
+    }
+
+    // ===== Values =====
+
+    #[test]
+    fn test_unquoted_trailing_comment() {
+        assert_eq!(value("KEY=value # comment"), "value");
+        assert_eq!(value("KEY=value\t# comment"), "value");
+        assert_eq!(value("KEY=a#b"), "a#b");
+        assert_eq!(value("KEY=#not-a-comment"), "#not-a-comment");
+        assert_eq!(value("KEY= # only a comment"), "");
+        assert_eq!(value("KEY=  spaced value  "), "spaced value");
+    }
+
+    #[test]
+    fn test_double_quotes_and_escapes() {
+        assert_eq!(value(r#"KEY="a b  c""#), "a b  c");
+        assert_eq!(value(r#"KEY="line\nnext\ttab\rcr""#), "line\nnext\ttab\rcr");
+        assert_eq!(
+            value(r#"KEY="say \"hi\" \\ \$HOME""#),
+            r#"say "hi" \ $HOME"#
+        );
+        assert_eq!(value(r#"KEY="C:\path""#), r"C:\path");
+        assert_eq!(value(r#"KEY="has # hash" # comment"#), "has # hash");
+    }
+
+    #[test]
+    fn test_single_quotes_are_literal() {
+        assert_eq!(value(r"KEY='raw \n $VAR'"), r"raw \n $VAR");
+        assert_eq!(value("KEY='with \"double\"' # note"), "with \"double\"");
+    }
+
+    #[test]
+    fn test_unicode_values() {
+        assert_eq!(value("KEY=\"päss wörd 🔑\""), "päss wörd 🔑");
+        assert_eq!(value("KEY=日本語"), "日本語");
+    }
+
+    #[test]
+    fn test_quoted_value_errors() {
+        assert_eq!(
+            error_lines("KEY=\"open\nB='open\nC=\"x\" y\nD='x'y\nE=\"x\\"),
+            [1, 2, 3, 4, 5]
+        );
+        let (_, errors) = parse("KEY=\"open");
+        assert_eq!(errors[0].message, "Unterminated double quote");
+    }
+
+    // ===== File =====
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let variables = parsed("A=one\r\nB=\"two\"\r\n# c\r\nC='three'\r\n");
+
+        assert_eq!(variables["A"], "one");
+        assert_eq!(variables["B"], "two");
+        assert_eq!(variables["C"], "three");
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        assert_eq!(parsed("\u{feff}KEY=value")["KEY"], "value");
+    }
+
+    #[test]
+    fn test_bad_lines_are_skipped_with_line_numbers() {
+        let (variables, errors) = parse("A=1\njust some text\nB=2\r\nC=\"open\n");
+
+        assert_eq!(variables.len(), 2);
+        assert_eq!(
+            errors,
+            [
+                EnvFileError {
+                    line: 2,
+                    message: "Expected KEY=VALUE".to_string(),
+                },
+                EnvFileError {
+                    line: 4,
+                    message: "Unterminated double quote".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_never_include_the_line() {
+        let (_, errors) = parse("sk-proj-0123456789abcdef\nKEY=\"sk-proj-0123456789abcdef");
+
+        for error in errors {
+            assert!(!error.message.contains("0123456789"));
+        }
+    }
+
+    #[test]
+    fn test_empty_file() {
+        assert_eq!(parse(""), (HashMap::new(), Vec::new()));
+        assert_eq!(parse("\u{feff}"), (HashMap::new(), Vec::new()));
+    }
+
+    #[test]
+    fn test_env_file_state() {
+        let env_file = EnvFile::default();
+        env_file.replace(parsed("KEY=from-file"));
+
+        assert_eq!(env_file.get("KEY").as_deref(), Some("from-file"));
+        assert_eq!(env_file.get("MISSING"), None);
+    }
+}
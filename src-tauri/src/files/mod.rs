@@ -0,0 +1,618 @@
+//! Reading dropped or picked files into the prompt.
+//!
+//! `read_file_for_prompt` returns a file's text with what the launcher
+//! shows about it (name, size, lines). Only text files are read: the
+//! extension must be on the allowlist (plain text, code, JSON, CSV or
+//! Markdown) and content that looks binary is refused even then.
+//!
+//! # Encoding
+//!
+//! UTF-8 (with or without BOM) and UTF-16 with a BOM are converted to
+//! UTF-8. Anything else is read as UTF-8 with invalid bytes replaced, and
+//! flagged with `lossy: true`.
+//!
+//! # Size Limit
+//!
+//! Files larger than `launcher.file_max_kb` aren't refused: the first and
+//! last half of the limit are kept, cut at line boundaries, with a marker
+//! in between saying how much was left out (`truncated: true`).
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const file = await invoke<FileContent>('read_file_for_prompt', { path });
+//! if (file.truncated) showHint(`${file.name} is large; only its start and end were added`);
+//! prompt.value += `\n\n${file.name}:\n\`\`\`\n${file.content}\n\`\`\``;
+//!
+//! // Record the file with the message it was sent in
+//! await invoke('append_message', { conversationId, role: 'user', content, attachments: [file] });
+//! ```
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::settings::SettingsManager;
+
+/// Bytes checked for binary content.
+const SNIFF_LEN: usize = 8192;
+
+/// Extensions accepted per kind of file.
+const EXTENSIONS: &[(FileKind, &[&str])] = &[
+    (FileKind::Text, &["txt", "text", "log", "out", "rst", "tex"]),
+    (FileKind::Markdown, &["md", "markdown", "mdx"]),
+    (FileKind::Json, &["json", "jsonl", "ndjson", "geojson"]),
+    (FileKind::Csv, &["csv", "tsv"]),
+    (
+        FileKind::Code,
+        &[
+            "rs",
+            "py",
+            "js",
+            "mjs",
+            "cjs",
+            "ts",
+            "tsx",
+            "jsx",
+            "vue",
+            "svelte",
+            "go",
+            "java",
+            "kt",
+            "kts",
+            "c",
+            "h",
+            "cpp",
+            "cc",
+            "hpp",
+            "cs",
+            "rb",
+            "php",
+            "swift",
+            "scala",
+            "lua",
+            "r",
+            "sh",
+            "bash",
+            "zsh",
+            "fish",
+            "ps1",
+            "bat",
+            "cmd",
+            "pl",
+            "dart",
+            "ex",
+            "exs",
+            "hs",
+            "sql",
+            "html",
+            "htm",
+            "css",
+            "scss",
+            "xml",
+            "yaml",
+            "yml",
+            "toml",
+            "ini",
+            "cfg",
+            "conf",
+            "env",
+            "properties",
+            "gradle",
+            "diff",
+            "patch",
+        ],
+    ),
+];
+
+/// Extensionless file names accepted as code.
+const CODE_FILE_NAMES: &[&str] = &[
+    "Dockerfile",
+    "Makefile",
+    "Justfile",
+    "Gemfile",
+    "Procfile",
+    ".gitignore",
+    ".env",
+    ".editorconfig",
+];
+
+/// What kind of text file was read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    Text,
+    Markdown,
+    Json,
+    Csv,
+    Code,
+}
+
+/// A file's text and metadata, as added to the prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileContent {
+    /// File name without its directory
+    pub name: String,
+    /// Size on disk in bytes
+    pub size: u64,
+    pub kind: FileKind,
+    /// Encoding the file was read as (`utf-8`, `utf-16le` or `utf-16be`)
+    pub encoding: &'static str,
+    /// Lines of text included (without the truncation marker)
+    pub line_count: usize,
+    /// Whether the middle of the file was left out
+    pub truncated: bool,
+    /// Whether invalid characters were replaced while converting to UTF-8
+    pub lossy: bool,
+    /// The text, as UTF-8
+    pub content: String,
+}
+
+/// Text encodings recognized by their BOM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// The encoding and BOM length of a file starting with `bytes`.
+    fn detect(bytes: &[u8]) -> (Self, usize) {
+        match bytes {
+            [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+            [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+            [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+            _ => (Encoding::Utf8, 0),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+        }
+    }
+
+    /// Convert to UTF-8; the flag is set if anything was replaced.
+    fn decode(self, bytes: &[u8]) -> (String, bool) {
+        let unit = |pair: &[u8]| match self {
+            Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+            _ => u16::from_be_bytes([pair[0], pair[1]]),
+        };
+        match self {
+            Encoding::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(text) => (text.to_string(), false),
+                Err(_) => (String::from_utf8_lossy(bytes).into_owned(), true),
+            },
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let mut lossy = !bytes.len().is_multiple_of(2);
+                let text = char::decode_utf16(bytes.chunks_exact(2).map(unit))
+                    .map(|c| {
+                        c.unwrap_or_else(|_| {
+                            lossy = true;
+                            char::REPLACEMENT_CHARACTER
+                        })
+                    })
+                    .collect();
+                (text, lossy)
+            }
+        }
+    }
+
+    /// Drop a character cut in half at the start and/or end of a chunk.
+    fn trim_partial(self, mut bytes: &[u8], start: bool, end: bool) -> &[u8] {
+        match self {
+            Encoding::Utf8 => {
+                if start {
+                    // Continuation bytes of a character begun before the chunk
+                    let skip = bytes
+                        .iter()
+                        .take(3)
+                        .take_while(|&&b| b & 0xC0 == 0x80)
+                        .count();
+                    bytes = &bytes[skip..];
+                }
+                if end {
+                    if let Err(e) = std::str::from_utf8(bytes) {
+                        if e.error_len().is_none() {
+                            bytes = &bytes[..e.valid_up_to()];
+                        }
+                    }
+                }
+                bytes
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let surrogate = |pair: &[u8]| {
+                    let hi = if self == Encoding::Utf16Le {
+                        pair[1]
+                    } else {
+                        pair[0]
+                    };
+                    hi & 0xFC
+                };
+                bytes = &bytes[..bytes.len() - bytes.len() % 2];
+                if start && bytes.len() >= 2 && surrogate(&bytes[..2]) == 0xDC {
+                    bytes = &bytes[2..];
+                }
+                if end && bytes.len() >= 2 && surrogate(&bytes[bytes.len() - 2..]) == 0xD8 {
+                    bytes = &bytes[..bytes.len() - 2];
+                }
+                bytes
+            }
+        }
+    }
+
+    /// Encoded length of `text` in bytes.
+    fn encoded_len(self, text: &str) -> usize {
+        match self {
+            Encoding::Utf8 => text.len(),
+            Encoding::Utf16Le | Encoding::Utf16Be => text.encode_utf16().count() * 2,
+        }
+    }
+}
+
+/// The kind of file `name` is, if it's on the allowlist.
+pub fn file_kind(name: &str) -> Option<FileKind> {
+    if CODE_FILE_NAMES.contains(&name) {
+        return Some(FileKind::Code);
+    }
+    let extension = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|(_, extensions)| extensions.contains(&extension.as_str()))
+        .map(|(kind, _)| *kind)
+}
+
+/// Whether the start of a file looks like binary data.
+///
+/// NUL bytes (outside UTF-16) or many control characters give it away.
+pub fn looks_binary(sample: &[u8]) -> bool {
+    let sample = &sample[..sample.len().min(SNIFF_LEN)];
+    if Encoding::detect(sample).0 != Encoding::Utf8 {
+        return false;
+    }
+    if sample.contains(&0) {
+        return true;
+    }
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    control * 10 > sample.len()
+}
+
+/// Build the prompt content from a file's bytes.
+///
+/// # Arguments
+///
+/// * `name` - File name
+/// * `kind` - Kind of file (see [`file_kind`])
+/// * `size` - Size of the whole file in bytes
+/// * `head` - The whole file, or its first bytes when it's too large
+/// * `tail` - The file's last bytes when it's too large
+///
+/// # Returns
+///
+/// * `Ok(FileContent)` - The text, with the middle left out if `tail` is given
+/// * `Err(String)` - The file looks binary
+pub fn text_content(
+    name: &str,
+    kind: FileKind,
+    size: u64,
+    head: &[u8],
+    tail: Option<&[u8]>,
+) -> Result<FileContent, String> {
+    if looks_binary(head) {
+        return Err(format!("Can't add '{}': it looks like a binary file", name));
+    }
+    let (encoding, bom_len) = Encoding::detect(head);
+    let body = &head[bom_len..];
+
+    let Some(tail) = tail else {
+        let (content, lossy) = encoding.decode(body);
+        return Ok(FileContent {
+            name: name.to_string(),
+            size,
+            kind,
+            encoding: encoding.name(),
+            line_count: content.lines().count(),
+            truncated: false,
+            lossy,
+            content,
+        });
+    };
+
+    // Keep whole lines on both sides of the cut
+    let (head_text, head_lossy) = encoding.decode(encoding.trim_partial(body, false, true));
+    let (tail_text, tail_lossy) = encoding.decode(encoding.trim_partial(tail, true, false));
+    let head_text = match head_text.rfind('\n') {
+        Some(end) => &head_text[..=end],
+        None => &head_text[..],
+    };
+    let tail_text = match tail_text.find('\n') {
+        Some(start) => &tail_text[start + 1..],
+        None => &tail_text[..],
+    };
+    let kept = bom_len + encoding.encoded_len(head_text) + encoding.encoded_len(tail_text);
+    let omitted = size.saturating_sub(kept as u64);
+
+    Ok(FileContent {
+        name: name.to_string(),
+        size,
+        kind,
+        encoding: encoding.name(),
+        line_count: head_text.lines().count() + tail_text.lines().count(),
+        truncated: true,
+        lossy: head_lossy || tail_lossy,
+        content: format!(
+            "{}\n[… {} omitted …]\n\n{}",
+            head_text,
+            format_size(omitted),
+            tail_text
+        ),
+    })
+}
+
+/// Read a text file for the prompt.
+///
+/// # Arguments
+///
+/// * `path` - File to read
+/// * `max_bytes` - Size above which only the start and end are read
+///
+/// # Returns
+///
+/// * `Ok(FileContent)` - The file's text
+/// * `Err(String)` - Not an allowed type, binary, or unreadable
+pub fn read_for_prompt(path: &Path, max_bytes: u64) -> Result<FileContent, String> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("'{}' is not a file", path.display()))?;
+    let kind = file_kind(&name).ok_or_else(|| {
+        format!(
+            "Can't add '{}': only text, code, JSON, CSV and Markdown files are supported",
+            name
+        )
+    })?;
+
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let metadata = file
+        .metadata()
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("'{}' is not a file", path.display()));
+    }
+    let size = metadata.len();
+    let read_error = |e: std::io::Error| format!("Failed to read '{}': {}", path.display(), e);
+
+    if size <= max_bytes {
+        let mut bytes = Vec::with_capacity(size as usize);
+        file.read_to_end(&mut bytes).map_err(read_error)?;
+        return text_content(&name, kind, size, &bytes, None);
+    }
+
+    // Even halves keep UTF-16 code units aligned
+    let half = (max_bytes / 2) & !1;
+    let mut head = vec![0; half as usize];
+    file.read_exact(&mut head).map_err(read_error)?;
+    let mut tail = Vec::with_capacity(half as usize);
+    file.seek(SeekFrom::End(-(half as i64)))
+        .map_err(read_error)?;
+    file.read_to_end(&mut tail).map_err(read_error)?;
+    text_content(&name, kind, size, &head, Some(&tail))
+}
+
+/// A byte count for people, e.g. `"1.5 MB"`.
+pub fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let bytes_f = bytes as f64;
+    if bytes_f >= KB * KB {
+        format!("{:.1} MB", bytes_f / (KB * KB))
+    } else if bytes_f >= KB {
+        format!("{:.1} KB", bytes_f / KB)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// Read a dropped or picked file for the prompt.
+///
+/// Files over `launcher.file_max_kb` are cut to their start and end.
+///
+/// # Arguments
+///
+/// * `path` - Absolute path of the file
+///
+/// # Returns
+///
+/// * `Ok(FileContent)` - The file's text and metadata
+/// * `Err(String)` - The type isn't allowed, the content is binary, or the
+///   file couldn't be read
+#[tauri::command]
+pub async fn read_file_for_prompt(
+    settings_manager: State<'_, SettingsManager>,
+    path: String,
+) -> Result<FileContent, String> {
+    let max_bytes = u64::from(settings_manager.snapshot()?.launcher.file_max_kb) * 1024;
+    tauri::async_runtime::spawn_blocking(move || read_for_prompt(Path::new(&path), max_bytes))
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_file(dir: &tempfile::TempDir, name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = dir.path().join(name);
+        File::create(&path).unwrap().write_all(bytes).unwrap();
+        path
+    }
+
+    // ===== File Types =====
+
+    #[test]
+    fn test_allowed_kinds() {
+        assert_eq!(file_kind("server.log"), Some(FileKind::Text));
+        assert_eq!(file_kind("README.MD"), Some(FileKind::Markdown));
+        assert_eq!(file_kind("data.json"), Some(FileKind::Json));
+        assert_eq!(file_kind("export.csv"), Some(FileKind::Csv));
+        assert_eq!(file_kind("main.rs"), Some(FileKind::Code));
+        assert_eq!(file_kind("Dockerfile"), Some(FileKind::Code));
+    }
+
+    #[test]
+    fn test_other_types_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(&dir, "setup.exe", b"MZ");
+
+        assert_eq!(file_kind("photo.png"), None);
+        assert_eq!(file_kind("notes"), None);
+        assert!(read_for_prompt(&path, 1024)
+            .unwrap_err()
+            .contains("only text, code, JSON, CSV and Markdown"));
+    }
+
+    // ===== Binary Detection =====
+
+    #[test]
+    fn test_nul_bytes_are_binary() {
+        assert!(looks_binary(b"SQLite format 3\0\x10\0"));
+        assert!(!looks_binary(b"plain text\twith tabs\r\n"));
+    }
+
+    #[test]
+    fn test_control_characters_are_binary() {
+        let noise: Vec<u8> = (1..20).cycle().take(200).collect();
+
+        assert!(looks_binary(&noise));
+        // Colored log output is text
+        assert!(!looks_binary(b"\x1b[31mERROR\x1b[0m disk full\n"));
+    }
+
+    #[test]
+    fn test_binary_with_text_extension_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(&dir, "dump.log", b"\x7fELF\x02\x01\x01\0\0\0\0");
+
+        let error = read_for_prompt(&path, 1024).unwrap_err();
+
+        assert!(error.contains("binary"));
+    }
+
+    // ===== Encoding =====
+
+    #[test]
+    fn test_utf8_read_as_is() {
+        let content = text_content("a.txt", FileKind::Text, 9, "héllo\n".as_bytes(), None).unwrap();
+
+        assert_eq!(content.content, "héllo\n");
+        assert_eq!(content.encoding, "utf-8");
+        assert!(!content.lossy);
+    }
+
+    #[test]
+    fn test_utf8_bom_is_stripped() {
+        let content =
+            text_content("a.csv", FileKind::Csv, 8, b"\xEF\xBB\xBFa,b\n1,2", None).unwrap();
+
+        assert_eq!(content.content, "a,b\n1,2");
+        assert_eq!(content.line_count, 2);
+    }
+
+    #[test]
+    fn test_utf16_is_converted() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("naïve 🦀\n".encode_utf16().flat_map(u16::to_le_bytes));
+        let mut be = vec![0xFE, 0xFF];
+        be.extend("ok".encode_utf16().flat_map(u16::to_be_bytes));
+
+        let le = text_content("a.txt", FileKind::Text, 0, &bytes, None).unwrap();
+        let be = text_content("b.txt", FileKind::Text, 0, &be, None).unwrap();
+
+        assert_eq!(le.content, "naïve 🦀\n");
+        assert_eq!(le.encoding, "utf-16le");
+        assert!(!le.lossy);
+        assert_eq!(be.content, "ok");
+        assert_eq!(be.encoding, "utf-16be");
+    }
+
+    #[test]
+    fn test_non_utf8_is_flagged_lossy() {
+        // "café" in Latin-1
+        let content = text_content("a.txt", FileKind::Text, 4, b"caf\xE9", None).unwrap();
+
+        assert_eq!(content.content, "caf\u{FFFD}");
+        assert!(content.lossy);
+    }
+
+    // ===== Size Limit =====
+
+    #[test]
+    fn test_file_within_cap_is_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(&dir, "small.log", b"line 1\nline 2\n");
+
+        let content = read_for_prompt(&path, 14).unwrap();
+
+        assert!(!content.truncated);
+        assert_eq!(content.size, 14);
+        assert_eq!(content.line_count, 2);
+        assert_eq!(content.name, "small.log");
+    }
+
+    #[test]
+    fn test_oversized_file_keeps_head_and_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let text: String = (1..=1000).map(|i| format!("line {}\n", i)).collect();
+        let path = write_file(&dir, "big.log", text.as_bytes());
+
+        let content = read_for_prompt(&path, 200).unwrap();
+
+        assert!(content.truncated);
+        assert_eq!(content.size, text.len() as u64);
+        assert!(content.content.starts_with("line 1\nline 2\n"));
+        assert!(content.content.ends_with("line 999\nline 1000\n"));
+        assert!(content.content.contains("omitted …]"));
+        // Only whole lines are kept
+        for line in content.content.lines().filter(|l| !l.starts_with('[')) {
+            assert!(line.is_empty() || line.starts_with("line "), "{:?}", line);
+        }
+        assert!(content.content.len() < 250);
+        assert_eq!(
+            content.line_count,
+            content
+                .content
+                .lines()
+                .filter(|l| l.starts_with("line "))
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_truncation_does_not_split_characters() {
+        let head = "ééé\néé".as_bytes();
+        let tail = &"ü\nüü\n".as_bytes()[1..];
+
+        let content = text_content("a.txt", FileKind::Text, 100, head, Some(tail)).unwrap();
+
+        assert!(!content.lossy);
+        assert!(content.content.starts_with("ééé\n\n[…"));
+        assert!(content.content.ends_with("…]\n\nüü\n"));
+    }
+
+    #[test]
+    fn test_size_formatting() {
+        assert_eq!(format_size(512), "512 bytes");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+}
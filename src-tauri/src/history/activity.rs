@@ -0,0 +1,252 @@
+//! Per-day activity statistics for the settings window chart.
+//!
+//! Everything is aggregated in SQL. A recursive CTE generates one row per
+//! calendar day (UTC) in the window, so days without messages still appear
+//! with zero counts. Latency percentiles use the nearest-rank method over
+//! the `duration_ms` of that day's replies.
+
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use super::types::HistoryError;
+
+/// Longest window [`activity_stats`] accepts, in days.
+pub const MAX_ACTIVITY_DAYS: u32 = 366;
+
+/// Message and latency totals for one UTC day.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyActivity {
+    /// UTC day as `YYYY-MM-DD`
+    pub day: String,
+    /// Messages with role `user`
+    pub user_messages: i64,
+    /// Messages with role `assistant`
+    pub assistant_messages: i64,
+    /// Sum of prompt and completion tokens
+    pub total_tokens: i64,
+    /// Mean response time, `None` when no reply recorded a duration
+    pub avg_latency_ms: Option<f64>,
+    /// Median response time
+    pub p50_latency_ms: Option<i64>,
+    /// 95th percentile response time
+    pub p95_latency_ms: Option<i64>,
+}
+
+/// Result of `get_activity_stats`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActivityStats {
+    /// Number of days covered, ending today (UTC)
+    pub range_days: u32,
+    /// One entry per day, oldest first; empty when history is disabled
+    pub days: Vec<DailyActivity>,
+}
+
+impl ActivityStats {
+    /// Stats with no days, returned when history is disabled.
+    pub fn empty(range_days: u32) -> Self {
+        Self {
+            range_days: range_days.clamp(1, MAX_ACTIVITY_DAYS),
+            days: Vec::new(),
+        }
+    }
+}
+
+/// Aggregate messages per day for the last `range_days` days (including
+/// today, UTC).
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `range_days` - Window size, clamped to `1..=`[`MAX_ACTIVITY_DAYS`]
+/// * `now_ms` - Current Unix timestamp (ms)
+pub async fn activity_stats(
+    pool: &SqlitePool,
+    range_days: u32,
+    now_ms: i64,
+) -> Result<ActivityStats, HistoryError> {
+    let range_days = range_days.clamp(1, MAX_ACTIVITY_DAYS);
+    let modifier = format!("-{} days", range_days - 1);
+
+    let rows = sqlx::query(
+        "WITH RECURSIVE
+         calendar(day) AS (
+             SELECT date(?1 / 1000, 'unixepoch', ?2)
+             UNION ALL
+             SELECT date(day, '+1 day') FROM calendar
+             WHERE day < date(?1 / 1000, 'unixepoch')
+         ),
+         windowed AS (
+             SELECT date(created_at / 1000, 'unixepoch') AS day, role, duration_ms,
+                    COALESCE(prompt_tokens, 0) + COALESCE(completion_tokens, 0) AS tokens
+             FROM messages
+             WHERE created_at >= unixepoch(date(?1 / 1000, 'unixepoch', ?2)) * 1000
+         ),
+         counts AS (
+             SELECT day,
+                    SUM(role = 'user') AS user_messages,
+                    SUM(role = 'assistant') AS assistant_messages,
+                    SUM(tokens) AS total_tokens,
+                    AVG(CASE WHEN role = 'assistant' THEN duration_ms END) AS avg_latency_ms
+             FROM windowed
+             GROUP BY day
+         ),
+         ranked AS (
+             SELECT day, duration_ms,
+                    ROW_NUMBER() OVER (PARTITION BY day ORDER BY duration_ms) AS rank,
+                    COUNT(*) OVER (PARTITION BY day) AS n
+             FROM windowed
+             WHERE role = 'assistant' AND duration_ms IS NOT NULL
+         ),
+         latency AS (
+             SELECT day,
+                    MIN(CASE WHEN rank >= (n * 50 + 99) / 100 THEN duration_ms END) AS p50,
+                    MIN(CASE WHEN rank >= (n * 95 + 99) / 100 THEN duration_ms END) AS p95
+             FROM ranked
+             GROUP BY day
+         )
+         SELECT calendar.day,
+                COALESCE(counts.user_messages, 0) AS user_messages,
+                COALESCE(counts.assistant_messages, 0) AS assistant_messages,
+                COALESCE(counts.total_tokens, 0) AS total_tokens,
+                counts.avg_latency_ms,
+                latency.p50,
+                latency.p95
+         FROM calendar
+         LEFT JOIN counts ON counts.day = calendar.day
+         LEFT JOIN latency ON latency.day = calendar.day
+         ORDER BY calendar.day ASC",
+    )
+    .bind(now_ms)
+    .bind(modifier)
+    .fetch_all(pool)
+    .await?;
+
+    let days = rows
+        .iter()
+        .map(|row| DailyActivity {
+            day: row.get("day"),
+            user_messages: row.get("user_messages"),
+            assistant_messages: row.get("assistant_messages"),
+            total_tokens: row.get("total_tokens"),
+            avg_latency_ms: row.get("avg_latency_ms"),
+            p50_latency_ms: row.get("p50"),
+            p95_latency_ms: row.get("p95"),
+        })
+        .collect();
+
+    Ok(ActivityStats { range_days, days })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::store;
+    use crate::history::types::MessageMetadata;
+
+    /// 2025-01-10T12:00:00Z
+    const DAY_10: i64 = 1_736_510_400_000;
+    const DAY_MS: i64 = 86_400_000;
+
+    async fn add(
+        pool: &SqlitePool,
+        conversation_id: &str,
+        role: &str,
+        at: i64,
+        tokens: u32,
+        duration_ms: Option<u32>,
+    ) {
+        let metadata = MessageMetadata {
+            prompt_tokens: Some(tokens),
+            completion_tokens: Some(1),
+            duration_ms,
+            ..MessageMetadata::default()
+        };
+        store::append_message(pool, conversation_id, role, "text", &metadata, &[], at)
+            .await
+            .unwrap();
+    }
+
+    /// Day 7 (outside a 3-day window), day 8, nothing on day 9, day 10.
+    async fn seed(pool: &SqlitePool) {
+        let id = store::create_conversation(pool, None, 0).await.unwrap().id;
+        add(pool, &id, "user", DAY_10 - 3 * DAY_MS, 50, None).await;
+
+        add(pool, &id, "user", DAY_10 - 2 * DAY_MS, 9, None).await;
+        add(pool, &id, "assistant", DAY_10 - 2 * DAY_MS, 9, Some(100)).await;
+
+        add(pool, &id, "user", DAY_10 - 1_000, 0, None).await;
+        add(pool, &id, "user", DAY_10, 0, None).await;
+        for (i, duration) in [400, 100, 300, 200].into_iter().enumerate() {
+            add(pool, &id, "assistant", DAY_10 + i as i64, 0, Some(duration)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_activity_fills_empty_days() {
+        let db = Db::in_memory().await.unwrap();
+
+        let stats = activity_stats(db.pool(), 3, DAY_10).await.unwrap();
+
+        let days: Vec<&str> = stats.days.iter().map(|d| d.day.as_str()).collect();
+        assert_eq!(days, ["2025-01-08", "2025-01-09", "2025-01-10"]);
+        assert!(stats
+            .days
+            .iter()
+            .all(|d| d.user_messages == 0 && d.avg_latency_ms.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_activity_aggregates_per_day() {
+        let db = Db::in_memory().await.unwrap();
+        seed(db.pool()).await;
+
+        let stats = activity_stats(db.pool(), 3, DAY_10).await.unwrap();
+
+        assert_eq!(stats.range_days, 3);
+        assert_eq!(
+            stats.days,
+            [
+                DailyActivity {
+                    day: "2025-01-08".to_string(),
+                    user_messages: 1,
+                    assistant_messages: 1,
+                    total_tokens: 20,
+                    avg_latency_ms: Some(100.0),
+                    p50_latency_ms: Some(100),
+                    p95_latency_ms: Some(100),
+                },
+                DailyActivity {
+                    day: "2025-01-09".to_string(),
+                    user_messages: 0,
+                    assistant_messages: 0,
+                    total_tokens: 0,
+                    avg_latency_ms: None,
+                    p50_latency_ms: None,
+                    p95_latency_ms: None,
+                },
+                DailyActivity {
+                    day: "2025-01-10".to_string(),
+                    user_messages: 2,
+                    assistant_messages: 4,
+                    total_tokens: 6,
+                    avg_latency_ms: Some(250.0),
+                    p50_latency_ms: Some(200),
+                    p95_latency_ms: Some(400),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_activity_range_is_clamped() {
+        let db = Db::in_memory().await.unwrap();
+
+        let today = activity_stats(db.pool(), 0, DAY_10).await.unwrap();
+        let max = activity_stats(db.pool(), 10_000, DAY_10).await.unwrap();
+
+        assert_eq!(today.days.len(), 1);
+        assert_eq!(today.days[0].day, "2025-01-10");
+        assert_eq!(max.days.len(), MAX_ACTIVITY_DAYS as usize);
+    }
+}
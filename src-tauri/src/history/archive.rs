@@ -0,0 +1,577 @@
+//! Zip export and import of the whole history.
+//!
+//! # Archive Layout
+//!
+//! ```text
+//! manifest.json                  ExportManifest
+//! conversations/<id>.json        ConversationWithMessages, one per conversation
+//! ```
+//!
+//! Import parses and validates the entire archive before touching the
+//! database, then writes everything in a single transaction, so a
+//! malformed archive leaves the history unchanged. The search index is
+//! optimized afterwards.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use super::crypto;
+use super::search;
+use super::store;
+use super::types::{ConversationWithMessages, HistoryError, MESSAGE_ROLES};
+use crate::db;
+use crate::migrations;
+
+/// Name of the manifest entry.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Directory holding one JSON file per conversation.
+pub const CONVERSATIONS_DIR: &str = "conversations/";
+
+/// Describes an export archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    /// Version of the app that wrote the archive
+    pub app_version: String,
+    /// Latest migration applied to the exported database
+    pub schema_version: i64,
+    /// Unix timestamp (ms) of the export
+    pub exported_at: i64,
+    /// Number of conversation files in the archive
+    pub conversation_count: usize,
+}
+
+/// What to do with imported conversations whose ID already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeStrategy {
+    /// Keep the local conversation and ignore the imported one
+    Skip,
+    /// Replace the local conversation (and its messages) with the imported one
+    Overwrite,
+    /// Import as a new conversation with fresh IDs
+    Duplicate,
+}
+
+/// Result of an import.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportSummary {
+    /// Conversations written to the database
+    pub imported: usize,
+    /// Conversations left alone because of [`MergeStrategy::Skip`]
+    pub skipped: usize,
+    /// Messages written to the database
+    pub messages: usize,
+}
+
+/// Payload of the `history-export-progress` / `history-import-progress` events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HistoryProgress {
+    /// Conversations processed so far
+    pub done: usize,
+    /// Total conversations
+    pub total: usize,
+}
+
+/// Latest schema version known to this build.
+pub fn schema_version() -> i64 {
+    migrations::latest_version()
+}
+
+/// Write every conversation to a zip archive at `path`.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `path` - Destination file (overwritten if it exists)
+/// * `now_ms` - Export timestamp for the manifest
+/// * `on_progress` - Called after each conversation is written
+pub async fn export_all(
+    pool: &SqlitePool,
+    path: &Path,
+    now_ms: i64,
+    mut on_progress: impl FnMut(HistoryProgress),
+) -> Result<ExportManifest, HistoryError> {
+    let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM conversations ORDER BY created_at")
+        .fetch_all(pool)
+        .await?;
+
+    let file = File::create(path).map_err(|e| {
+        HistoryError::Archive(format!("Failed to create {}: {}", path.display(), e))
+    })?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    let manifest = ExportManifest {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: schema_version(),
+        exported_at: now_ms,
+        conversation_count: ids.len(),
+    };
+    write_json(&mut zip, MANIFEST_FILE, &manifest, options)?;
+
+    for (i, id) in ids.iter().enumerate() {
+        let conversation = store::get_conversation(pool, id).await?;
+        let name = format!("{}{}.json", CONVERSATIONS_DIR, id);
+        write_json(&mut zip, &name, &conversation, options)?;
+        on_progress(HistoryProgress {
+            done: i + 1,
+            total: ids.len(),
+        });
+    }
+
+    zip.finish().map_err(archive_error)?;
+    Ok(manifest)
+}
+
+/// Import an archive written by [`export_all`].
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `path` - Archive to read
+/// * `strategy` - How to handle conversations that already exist
+/// * `on_progress` - Called after each conversation is processed
+///
+/// # Returns
+///
+/// * `Err(HistoryError::Archive)` - The archive is unreadable, malformed,
+///   or from a newer schema; nothing was imported
+pub async fn import_all(
+    pool: &SqlitePool,
+    path: &Path,
+    strategy: MergeStrategy,
+    mut on_progress: impl FnMut(HistoryProgress),
+) -> Result<ImportSummary, HistoryError> {
+    let conversations = read_archive(path)?;
+    let total = conversations.len();
+    let mut summary = ImportSummary::default();
+
+    let mut tx = db::begin_write(pool).await?;
+
+    for (i, mut imported) in conversations.into_iter().enumerate() {
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM conversations WHERE id = ?)")
+                .bind(&imported.conversation.id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        let write = match (exists, strategy) {
+            (false, _) => true,
+            (true, MergeStrategy::Skip) => false,
+            (true, MergeStrategy::Overwrite) => {
+                sqlx::query("DELETE FROM conversations WHERE id = ?")
+                    .bind(&imported.conversation.id)
+                    .execute(&mut *tx)
+                    .await?;
+                true
+            }
+            (true, MergeStrategy::Duplicate) => {
+                imported.conversation.id = uuid::Uuid::now_v7().to_string();
+                let ids: HashMap<String, String> = imported
+                    .messages
+                    .iter()
+                    .map(|m| (m.id.clone(), uuid::Uuid::now_v7().to_string()))
+                    .collect();
+                for message in &mut imported.messages {
+                    message.id = ids[&message.id].clone();
+                    // Keep regeneration lineage pointing at the new IDs
+                    for link in [&mut message.parent_message_id, &mut message.superseded_by] {
+                        *link = link.as_ref().and_then(|id| ids.get(id).cloned());
+                    }
+                }
+                true
+            }
+        };
+
+        if write {
+            insert_conversation(&mut tx, &imported).await?;
+            summary.imported += 1;
+            summary.messages += imported.messages.len();
+        } else {
+            summary.skipped += 1;
+        }
+
+        on_progress(HistoryProgress { done: i + 1, total });
+    }
+
+    tx.commit().await?;
+    // The triggers indexed every message one by one
+    search::optimize_index(pool).await?;
+    Ok(summary)
+}
+
+async fn insert_conversation(
+    tx: &mut sqlx::SqliteConnection,
+    imported: &ConversationWithMessages,
+) -> Result<(), HistoryError> {
+    let conversation = &imported.conversation;
+    sqlx::query(
+        "INSERT INTO conversations (id, title, created_at, updated_at, pinned, archived, deleted_at, branched_from_conversation_id, branched_from_message_id, system_prompt_override)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&conversation.id)
+    .bind(&conversation.title)
+    .bind(conversation.created_at)
+    .bind(conversation.updated_at)
+    .bind(conversation.pinned)
+    .bind(conversation.archived)
+    .bind(conversation.deleted_at)
+    .bind(&conversation.branched_from_conversation_id)
+    .bind(&conversation.branched_from_message_id)
+    .bind(&conversation.system_prompt_override)
+    .execute(&mut *tx)
+    .await?;
+
+    for message in &imported.messages {
+        let metadata = &message.metadata;
+        let stored = crypto::seal_new(&message.content)?;
+        sqlx::query(
+            "INSERT INTO messages (id, conversation_id, role, content, created_at, starred, parent_message_id, superseded_by, provider, model, prompt_tokens, completion_tokens, duration_ms)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&message.id)
+        .bind(&conversation.id)
+        .bind(&message.role)
+        .bind(&stored)
+        .bind(message.created_at)
+        .bind(message.starred)
+        .bind(&message.parent_message_id)
+        .bind(&message.superseded_by)
+        .bind(&metadata.provider)
+        .bind(&metadata.model)
+        .bind(metadata.prompt_tokens.map(i64::from))
+        .bind(metadata.completion_tokens.map(i64::from))
+        .bind(metadata.duration_ms.map(i64::from))
+        .execute(&mut *tx)
+        .await?;
+        search::index_sealed(tx, &message.id, &stored, &message.content).await?;
+    }
+
+    Ok(())
+}
+
+/// Parse and validate the whole archive.
+fn read_archive(path: &Path) -> Result<Vec<ConversationWithMessages>, HistoryError> {
+    let file = File::open(path)
+        .map_err(|e| HistoryError::Archive(format!("Failed to open {}: {}", path.display(), e)))?;
+    let mut zip = ZipArchive::new(file).map_err(archive_error)?;
+
+    let manifest: ExportManifest = read_json(&mut zip, MANIFEST_FILE)?;
+    if manifest.schema_version > schema_version() {
+        return Err(HistoryError::Archive(format!(
+            "Archive schema version {} is newer than supported version {}",
+            manifest.schema_version,
+            schema_version()
+        )));
+    }
+
+    let names: Vec<String> = zip
+        .file_names()
+        .filter(|name| name.starts_with(CONVERSATIONS_DIR) && name.ends_with(".json"))
+        .map(str::to_string)
+        .collect();
+    if names.len() != manifest.conversation_count {
+        return Err(HistoryError::Archive(format!(
+            "Manifest lists {} conversations but archive contains {}",
+            manifest.conversation_count,
+            names.len()
+        )));
+    }
+
+    let mut conversations = Vec::with_capacity(names.len());
+    for name in names {
+        let conversation: ConversationWithMessages = read_json(&mut zip, &name)?;
+        if let Some(message) = conversation
+            .messages
+            .iter()
+            .find(|m| !MESSAGE_ROLES.contains(&m.role.as_str()))
+        {
+            return Err(HistoryError::Archive(format!(
+                "{}: invalid message role: {}",
+                name, message.role
+            )));
+        }
+        conversations.push(conversation);
+    }
+
+    conversations.sort_by_key(|c| c.conversation.created_at);
+    Ok(conversations)
+}
+
+fn write_json<T: Serialize>(
+    zip: &mut ZipWriter<File>,
+    name: &str,
+    value: &T,
+    options: SimpleFileOptions,
+) -> Result<(), HistoryError> {
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| HistoryError::Archive(format!("Failed to serialize {}: {}", name, e)))?;
+    zip.start_file(name, options).map_err(archive_error)?;
+    zip.write_all(&json)
+        .map_err(|e| HistoryError::Archive(format!("Failed to write {}: {}", name, e)))
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(
+    zip: &mut ZipArchive<File>,
+    name: &str,
+) -> Result<T, HistoryError> {
+    let mut entry = zip
+        .by_name(name)
+        .map_err(|e| HistoryError::Archive(format!("{}: {}", name, e)))?;
+    let mut json = String::new();
+    entry
+        .read_to_string(&mut json)
+        .map_err(|e| HistoryError::Archive(format!("Failed to read {}: {}", name, e)))?;
+    serde_json::from_str(&json).map_err(|e| HistoryError::Archive(format!("{}: {}", name, e)))
+}
+
+fn archive_error(error: zip::result::ZipError) -> HistoryError {
+    HistoryError::Archive(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::types::{MessageMetadata, DEFAULT_TITLE};
+
+    async fn seed(pool: &SqlitePool) {
+        let first = store::create_conversation(pool, Some("Rust".to_string()), 1_000)
+            .await
+            .unwrap();
+        let second = store::create_conversation(pool, None, 2_000).await.unwrap();
+        store::set_pinned(pool, &first.id, true).await.unwrap();
+        let metadata = MessageMetadata {
+            provider: Some("gemini".to_string()),
+            model: Some("gemini-2.0-flash".to_string()),
+            prompt_tokens: Some(10),
+            completion_tokens: Some(20),
+            duration_ms: Some(300),
+        };
+        for (id, at) in [(&first.id, 1_100), (&first.id, 1_200), (&second.id, 2_100)] {
+            store::append_message(
+                pool,
+                id,
+                "user",
+                "question",
+                &MessageMetadata::default(),
+                &[],
+                at,
+            )
+            .await
+            .unwrap();
+            store::append_message(pool, id, "assistant", "answer", &metadata, &[], at + 1)
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn snapshot(pool: &SqlitePool) -> Vec<ConversationWithMessages> {
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM conversations ORDER BY id")
+            .fetch_all(pool)
+            .await
+            .unwrap();
+        let mut conversations = Vec::new();
+        for id in ids {
+            conversations.push(store::get_conversation(pool, &id).await.unwrap());
+        }
+        conversations
+    }
+
+    async fn counts(pool: &SqlitePool) -> (i64, i64) {
+        sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM conversations), (SELECT COUNT(*) FROM messages)",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn wipe(pool: &SqlitePool) {
+        sqlx::query("DELETE FROM conversations")
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_wipe_import_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.zip");
+        let db = Db::in_memory().await.unwrap();
+        seed(db.pool()).await;
+        let before = snapshot(db.pool()).await;
+
+        let mut progress = Vec::new();
+        let manifest = export_all(db.pool(), &path, 9_000, |p| progress.push(p))
+            .await
+            .unwrap();
+        wipe(db.pool()).await;
+        assert_eq!(counts(db.pool()).await, (0, 0));
+
+        let summary = import_all(db.pool(), &path, MergeStrategy::Skip, |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(manifest.conversation_count, 2);
+        assert_eq!(manifest.schema_version, schema_version());
+        assert_eq!(manifest.exported_at, 9_000);
+        assert_eq!(
+            progress.last(),
+            Some(&HistoryProgress { done: 2, total: 2 })
+        );
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 2,
+                skipped: 0,
+                messages: 6
+            }
+        );
+        assert_eq!(counts(db.pool()).await, (2, 6));
+        assert_eq!(snapshot(db.pool()).await, before);
+    }
+
+    #[tokio::test]
+    async fn test_import_existing_with_skip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.zip");
+        let db = Db::in_memory().await.unwrap();
+        seed(db.pool()).await;
+        export_all(db.pool(), &path, 0, |_| {}).await.unwrap();
+
+        let summary = import_all(db.pool(), &path, MergeStrategy::Skip, |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(summary.imported, 0);
+        assert_eq!(summary.skipped, 2);
+        assert_eq!(counts(db.pool()).await, (2, 6));
+    }
+
+    #[tokio::test]
+    async fn test_import_existing_with_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.zip");
+        let db = Db::in_memory().await.unwrap();
+        seed(db.pool()).await;
+        let before = snapshot(db.pool()).await;
+        export_all(db.pool(), &path, 0, |_| {}).await.unwrap();
+
+        let id = &before[0].conversation.id;
+        store::rename_conversation(db.pool(), id, "Local edit")
+            .await
+            .unwrap();
+        store::append_message(
+            db.pool(),
+            id,
+            "user",
+            "extra",
+            &MessageMetadata::default(),
+            &[],
+            5_000,
+        )
+        .await
+        .unwrap();
+
+        let summary = import_all(db.pool(), &path, MergeStrategy::Overwrite, |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(summary.imported, 2);
+        assert_eq!(snapshot(db.pool()).await, before);
+    }
+
+    #[tokio::test]
+    async fn test_import_existing_with_duplicate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.zip");
+        let db = Db::in_memory().await.unwrap();
+        seed(db.pool()).await;
+        export_all(db.pool(), &path, 0, |_| {}).await.unwrap();
+
+        import_all(db.pool(), &path, MergeStrategy::Duplicate, |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(counts(db.pool()).await, (4, 12));
+        let untitled: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM conversations WHERE title = ?")
+                .bind(DEFAULT_TITLE)
+                .fetch_one(db.pool())
+                .await
+                .unwrap();
+        assert_eq!(untitled, 2);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_archive_imports_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let manifest = ExportManifest {
+            app_version: "0.0.0".to_string(),
+            schema_version: 1,
+            exported_at: 0,
+            conversation_count: 2,
+        };
+        let options = SimpleFileOptions::default();
+        write_json(&mut zip, MANIFEST_FILE, &manifest, options).unwrap();
+        zip.start_file("conversations/a.json", options).unwrap();
+        zip.write_all(br#"{"id":"a","title":"ok","created_at":1,"updated_at":1,"messages":[]}"#)
+            .unwrap();
+        zip.start_file("conversations/b.json", options).unwrap();
+        zip.write_all(b"{ not json").unwrap();
+        zip.finish().unwrap();
+        let db = Db::in_memory().await.unwrap();
+
+        let result = import_all(db.pool(), &path, MergeStrategy::Skip, |_| {}).await;
+
+        assert!(matches!(result, Err(HistoryError::Archive(_))));
+        assert_eq!(counts(db.pool()).await, (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_newer_schema_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("future.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let manifest = ExportManifest {
+            app_version: "99.0.0".to_string(),
+            schema_version: schema_version() + 1,
+            exported_at: 0,
+            conversation_count: 0,
+        };
+        write_json(
+            &mut zip,
+            MANIFEST_FILE,
+            &manifest,
+            SimpleFileOptions::default(),
+        )
+        .unwrap();
+        zip.finish().unwrap();
+        let db = Db::in_memory().await.unwrap();
+
+        let result = import_all(db.pool(), &path, MergeStrategy::Skip, |_| {}).await;
+
+        assert!(matches!(result, Err(HistoryError::Archive(_))));
+    }
+
+    #[test]
+    fn test_merge_strategy_serialization() {
+        assert_eq!(
+            serde_json::to_string(&MergeStrategy::Overwrite).unwrap(),
+            "\"overwrite\""
+        );
+        let parsed: MergeStrategy = serde_json::from_str("\"duplicate\"").unwrap();
+        assert_eq!(parsed, MergeStrategy::Duplicate);
+    }
+}
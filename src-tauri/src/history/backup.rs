@@ -0,0 +1,617 @@
+//! Backups of the history database file.
+//!
+//! Backups are full SQLite copies written with `VACUUM INTO` to
+//! `{app_data_dir}/backups/history-<unix ms>.db`. The copy runs inside a
+//! single read transaction, so other queries are only held up while pages
+//! are being copied, never by a long-lived lock.
+//!
+//! The scheduler started in `lib.rs` wakes up every [`CHECK_INTERVAL`] and
+//! writes a backup once `history.auto_backup_interval_days` have passed since
+//! the newest one, then deletes all but the newest
+//! `history.backup_keep_count`. It skips the backup when nothing was
+//! committed since the last one, which [`ChangeTracker`] detects with
+//! `PRAGMA data_version`.
+//!
+//! Restoring copies the history tables back from a backup inside one
+//! transaction, so the pool and the frontend's `tauri-plugin-sql`
+//! connection stay valid.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{Acquire, SqliteConnection, SqlitePool};
+use tauri::AppHandle;
+
+use super::archive::schema_version;
+use super::types::HistoryError;
+use crate::db::connect_options;
+use crate::paths;
+use crate::settings::HistorySettings;
+
+/// Directory under the app data dir that holds backups.
+pub const BACKUPS_DIR: &str = "backups";
+
+/// How often the scheduler checks whether a backup is due.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const FILE_PREFIX: &str = "history-";
+const FILE_EXTENSION: &str = ".db";
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// How long restore waits for connections still being returned to the pool.
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+
+/// Tables copied back on restore, parents before children.
+const RESTORED_TABLES: [&str; 5] = [
+    "conversations",
+    "messages",
+    "tags",
+    "conversation_tags",
+    "usage_daily",
+];
+
+/// Tables every history backup has; later tables may be missing from
+/// backups taken by older versions.
+const REQUIRED_TABLES: [&str; 3] = ["conversations", "messages", "usage_daily"];
+
+/// A backup file on disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BackupInfo {
+    /// Absolute path of the file
+    pub path: String,
+    /// When the backup was taken (Unix ms, from the file name)
+    pub created_at: i64,
+    /// File size in bytes
+    pub size_bytes: u64,
+}
+
+/// Detects commits to the history database between backups.
+///
+/// `PRAGMA data_version` only changes when *another* connection commits, so
+/// the tracker keeps its own connection open for the lifetime of the app.
+/// Writes from the [`crate::db::Db`] pool and from `tauri-plugin-sql` both
+/// count as changes.
+pub struct ChangeTracker {
+    /// Single long-lived connection used only for `PRAGMA data_version`
+    conn: SqlitePool,
+    /// `data_version` when the last backup was taken
+    backed_up: Mutex<Option<i64>>,
+}
+
+impl ChangeTracker {
+    /// Open a tracking connection to the database at `path`.
+    pub async fn open(path: &Path) -> Result<Self, String> {
+        let conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(connect_options(path))
+            .await
+            .map_err(|e| format!("Failed to open backup tracker: {}", e))?;
+
+        Ok(Self {
+            conn,
+            backed_up: Mutex::new(None),
+        })
+    }
+
+    /// Whether anything was committed since [`ChangeTracker::mark_backed_up`].
+    ///
+    /// Always `true` before the first backup of this session.
+    pub async fn has_changed(&self) -> Result<bool, HistoryError> {
+        let current = self.data_version().await?;
+        Ok(*self.backed_up.lock().unwrap() != Some(current))
+    }
+
+    /// Follow the database to the file it was moved to.
+    ///
+    /// The next check counts as a change, so the moved database gets a
+    /// backup of its own.
+    pub async fn switch_to(&self, path: &Path) -> Result<(), HistoryError> {
+        self.conn.set_connect_options(connect_options(path));
+        self.conn.acquire().await?.close().await?;
+        *self.backed_up.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// Record the current state as backed up.
+    pub async fn mark_backed_up(&self) -> Result<(), HistoryError> {
+        let current = self.data_version().await?;
+        *self.backed_up.lock().unwrap() = Some(current);
+        Ok(())
+    }
+
+    async fn data_version(&self) -> Result<i64, HistoryError> {
+        Ok(sqlx::query_scalar("PRAGMA data_version")
+            .fetch_one(&self.conn)
+            .await?)
+    }
+}
+
+/// Resolve (and create) the backups directory.
+pub fn backups_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = paths::data_dir(app)?.join(BACKUPS_DIR);
+
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backups dir: {}", e))?;
+
+    Ok(dir)
+}
+
+/// Path of the backup taken at `now_ms` inside `dir`.
+pub fn backup_path(dir: &Path, now_ms: i64) -> PathBuf {
+    dir.join(format!("{}{}{}", FILE_PREFIX, now_ms, FILE_EXTENSION))
+}
+
+/// Copy the database to `path` with `VACUUM INTO`.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::Backup)` - `path` already exists
+pub async fn backup_to(pool: &SqlitePool, path: &Path) -> Result<(), HistoryError> {
+    if path.exists() {
+        return Err(HistoryError::Backup(format!(
+            "{} already exists",
+            path.display()
+        )));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| HistoryError::Internal(format!("Failed to create backup dir: {}", e)))?;
+    }
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Back up into `dir` and apply the keep-last-N rotation.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `tracker` - Marked as backed up before the copy starts
+/// * `dir` - Backups directory
+/// * `keep` - Number of newest backups to keep (at least 1)
+/// * `now_ms` - Current Unix timestamp (ms), used in the file name
+pub async fn backup_now(
+    pool: &SqlitePool,
+    tracker: &ChangeTracker,
+    dir: &Path,
+    keep: u32,
+    now_ms: i64,
+) -> Result<PathBuf, HistoryError> {
+    let path = backup_path(dir, now_ms);
+    tracker.mark_backed_up().await?;
+    backup_to(pool, &path).await?;
+    rotate(dir, keep)?;
+    Ok(path)
+}
+
+/// Take a scheduled backup if one is due.
+///
+/// # Returns
+///
+/// * `Ok(None)` - Automatic backups are off, the newest backup is younger
+///   than the interval, or nothing changed since the last backup
+/// * `Ok(Some(path))` - The backup that was written
+pub async fn backup_if_due(
+    pool: &SqlitePool,
+    tracker: &ChangeTracker,
+    dir: &Path,
+    settings: &HistorySettings,
+    now_ms: i64,
+) -> Result<Option<PathBuf>, HistoryError> {
+    if settings.auto_backup_interval_days == 0 {
+        return Ok(None);
+    }
+
+    let interval_ms = i64::from(settings.auto_backup_interval_days) * DAY_MS;
+    if let Some(newest) = list_backups(dir)?.first() {
+        if now_ms - newest.created_at < interval_ms {
+            return Ok(None);
+        }
+    }
+    if !tracker.has_changed().await? {
+        return Ok(None);
+    }
+
+    backup_now(pool, tracker, dir, settings.backup_keep_count, now_ms)
+        .await
+        .map(Some)
+}
+
+/// List backups in `dir`, newest first.
+///
+/// Files not named like a backup are ignored; a missing directory has no
+/// backups.
+pub fn list_backups(dir: &Path) -> Result<Vec<BackupInfo>, HistoryError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(HistoryError::Internal(format!(
+                "Failed to read backups dir: {}",
+                e
+            )))
+        }
+    };
+
+    let mut backups: Vec<BackupInfo> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let created_at = name
+                .strip_prefix(FILE_PREFIX)?
+                .strip_suffix(FILE_EXTENSION)?
+                .parse()
+                .ok()?;
+            Some(BackupInfo {
+                path: entry.path().to_string_lossy().into_owned(),
+                created_at,
+                size_bytes: entry.metadata().ok()?.len(),
+            })
+        })
+        .collect();
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    Ok(backups)
+}
+
+/// Delete all but the newest `keep` backups (at least one is kept).
+///
+/// # Returns
+///
+/// Number of files deleted.
+pub fn rotate(dir: &Path, keep: u32) -> Result<usize, HistoryError> {
+    let stale: Vec<BackupInfo> = list_backups(dir)?
+        .into_iter()
+        .skip(keep.max(1) as usize)
+        .collect();
+
+    for backup in &stale {
+        std::fs::remove_file(&backup.path)
+            .map_err(|e| HistoryError::Internal(format!("Failed to delete old backup: {}", e)))?;
+    }
+    Ok(stale.len())
+}
+
+/// Replace the current history with the contents of a backup.
+///
+/// Conversations, messages, tags, and usage totals are copied back in a single
+/// transaction; the response cache is left alone. Columns added by
+/// migrations newer than the backup keep their defaults.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::Busy)` - Other queries are running on the pool
+/// * `Err(HistoryError::Backup)` - Not a history database, or written by a
+///   newer version of the app
+pub async fn restore_from(pool: &SqlitePool, path: &Path) -> Result<(), HistoryError> {
+    if !wait_for_idle(pool).await {
+        return Err(HistoryError::Busy);
+    }
+    if !path.is_file() {
+        return Err(HistoryError::Backup(format!(
+            "{} does not exist",
+            path.display()
+        )));
+    }
+
+    let mut conn = pool.acquire().await?;
+    sqlx::query("ATTACH DATABASE ? AS backup")
+        .bind(path.to_string_lossy())
+        .execute(&mut *conn)
+        .await?;
+
+    let result = copy_from_backup(&mut conn).await;
+
+    // Detach even when the copy failed so the pooled connection stays clean
+    sqlx::query("DETACH DATABASE backup")
+        .execute(&mut *conn)
+        .await?;
+    result
+}
+
+/// Whether every pooled connection is idle, allowing [`IDLE_WAIT`] for
+/// connections released just before the call.
+async fn wait_for_idle(pool: &SqlitePool) -> bool {
+    let deadline = Instant::now() + IDLE_WAIT;
+    while pool.num_idle() < pool.size() as usize {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    true
+}
+
+async fn copy_from_backup(conn: &mut SqliteConnection) -> Result<(), HistoryError> {
+    let tables: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM backup.sqlite_master WHERE type = 'table' AND name IN (?, ?, ?)",
+    )
+    .bind(REQUIRED_TABLES[0])
+    .bind(REQUIRED_TABLES[1])
+    .bind(REQUIRED_TABLES[2])
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| HistoryError::Backup(e.to_string()))?;
+    if tables != REQUIRED_TABLES.len() as i64 {
+        return Err(HistoryError::Backup("not a history database".to_string()));
+    }
+
+    let version: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM backup._sqlx_migrations")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| HistoryError::Backup(e.to_string()))?;
+    if version.unwrap_or(0) > schema_version() {
+        return Err(HistoryError::Backup(format!(
+            "schema version {} is newer than supported version {}",
+            version.unwrap_or(0),
+            schema_version()
+        )));
+    }
+
+    let mut tx = conn.begin().await?;
+    for table in RESTORED_TABLES.iter().rev() {
+        sqlx::query(&format!("DELETE FROM main.{}", table))
+            .execute(&mut *tx)
+            .await?;
+    }
+    for table in RESTORED_TABLES {
+        let columns = shared_columns(&mut tx, table).await?;
+        if columns.is_empty() {
+            // Table was added after the backup was taken
+            continue;
+        }
+        let columns = columns.join(", ");
+        sqlx::query(&format!(
+            "INSERT INTO main.{table} ({columns}) SELECT {columns} FROM backup.{table}"
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Columns of `table` present in both the live database and the backup.
+async fn shared_columns(
+    conn: &mut SqliteConnection,
+    table: &str,
+) -> Result<Vec<String>, HistoryError> {
+    let main: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT name FROM pragma_table_info('{}', 'main')",
+        table
+    ))
+    .fetch_all(&mut *conn)
+    .await?;
+    let backup: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT name FROM pragma_table_info('{}', 'backup')",
+        table
+    ))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(main.into_iter().filter(|c| backup.contains(c)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::store;
+    use crate::history::types::MessageMetadata;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use tempfile::TempDir;
+
+    const NOW: i64 = 1_736_510_400_000;
+
+    /// File-backed database, its tracker, and a backups dir in one temp dir.
+    async fn setup() -> (TempDir, Db, ChangeTracker, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("history.db");
+        let db = Db::open(&db_path).await.unwrap();
+        let tracker = ChangeTracker::open(&db_path).await.unwrap();
+        let backups = dir.path().join(BACKUPS_DIR);
+        (dir, db, tracker, backups)
+    }
+
+    async fn add_conversation(pool: &SqlitePool, title: &str) -> String {
+        let conversation = store::create_conversation(pool, Some(title.to_string()), 0)
+            .await
+            .unwrap();
+        store::append_message(
+            pool,
+            &conversation.id,
+            "user",
+            "hello",
+            &MessageMetadata::default(),
+            &[],
+            1,
+        )
+        .await
+        .unwrap();
+        conversation.id
+    }
+
+    fn settings(interval_days: u32, keep: u32) -> HistorySettings {
+        HistorySettings {
+            enabled: true,
+            auto_backup_interval_days: interval_days,
+            backup_keep_count: keep,
+            ..HistorySettings::default()
+        }
+    }
+
+    // ===== Backups =====
+
+    #[tokio::test]
+    async fn test_backup_is_a_readable_copy() {
+        let (_dir, db, tracker, backups) = setup().await;
+        add_conversation(db.pool(), "Saved").await;
+
+        let path = backup_now(db.pool(), &tracker, &backups, 5, NOW)
+            .await
+            .unwrap();
+
+        assert_eq!(path, backup_path(&backups, NOW));
+        let copy = Db::open(&path).await.unwrap();
+        let titles = store::list_conversations(copy.pool(), 10, 0, true, None)
+            .await
+            .unwrap();
+        assert_eq!(titles[0].conversation.title, "Saved");
+    }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_newest() {
+        let (_dir, db, tracker, backups) = setup().await;
+        for i in 0..4 {
+            backup_now(db.pool(), &tracker, &backups, 2, NOW + i)
+                .await
+                .unwrap();
+        }
+        std::fs::write(backups.join("notes.txt"), "keep me").unwrap();
+
+        let remaining: Vec<i64> = list_backups(&backups)
+            .unwrap()
+            .iter()
+            .map(|b| b.created_at)
+            .collect();
+
+        assert_eq!(remaining, [NOW + 3, NOW + 2]);
+        assert!(backups.join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_missing_backups_dir_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(list_backups(&dir.path().join("nope")).unwrap(), []);
+    }
+
+    // ===== Scheduling =====
+
+    #[tokio::test]
+    async fn test_scheduled_backup_respects_interval() {
+        let (_dir, db, tracker, backups) = setup().await;
+        let settings = settings(1, 5);
+        backup_if_due(db.pool(), &tracker, &backups, &settings, NOW)
+            .await
+            .unwrap()
+            .unwrap();
+        add_conversation(db.pool(), "New").await;
+
+        let early = backup_if_due(db.pool(), &tracker, &backups, &settings, NOW + DAY_MS - 1)
+            .await
+            .unwrap();
+        let due = backup_if_due(db.pool(), &tracker, &backups, &settings, NOW + DAY_MS)
+            .await
+            .unwrap();
+
+        assert_eq!(early, None);
+        assert!(due.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_backup_skips_when_unchanged() {
+        let (_dir, db, tracker, backups) = setup().await;
+        let settings = settings(1, 5);
+        backup_if_due(db.pool(), &tracker, &backups, &settings, NOW)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let unchanged = backup_if_due(db.pool(), &tracker, &backups, &settings, NOW + DAY_MS)
+            .await
+            .unwrap();
+        add_conversation(db.pool(), "New").await;
+        let changed = backup_if_due(db.pool(), &tracker, &backups, &settings, NOW + 2 * DAY_MS)
+            .await
+            .unwrap();
+
+        assert_eq!(unchanged, None);
+        assert_eq!(changed, Some(backup_path(&backups, NOW + 2 * DAY_MS)));
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_backup_disabled() {
+        let (_dir, db, tracker, backups) = setup().await;
+
+        let result = backup_if_due(db.pool(), &tracker, &backups, &settings(0, 5), NOW)
+            .await
+            .unwrap();
+
+        assert_eq!(result, None);
+        assert!(list_backups(&backups).unwrap().is_empty());
+    }
+
+    // ===== Restore =====
+
+    #[tokio::test]
+    async fn test_restore_replaces_history() {
+        let (_dir, db, tracker, backups) = setup().await;
+        add_conversation(db.pool(), "Before").await;
+        let path = backup_now(db.pool(), &tracker, &backups, 5, NOW)
+            .await
+            .unwrap();
+        add_conversation(db.pool(), "After").await;
+
+        restore_from(db.pool(), &path).await.unwrap();
+
+        let titles: Vec<String> = store::list_conversations(db.pool(), 10, 0, true, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.conversation.title)
+            .collect();
+        assert_eq!(titles, ["Before"]);
+        let messages: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(messages, 1);
+    }
+
+    #[tokio::test]
+    async fn test_restore_refused_while_busy() {
+        let (_dir, db, tracker, backups) = setup().await;
+        let path = backup_now(db.pool(), &tracker, &backups, 5, NOW)
+            .await
+            .unwrap();
+        let _in_flight = db.pool().acquire().await.unwrap();
+
+        assert_eq!(
+            restore_from(db.pool(), &path).await,
+            Err(HistoryError::Busy)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_foreign_database() {
+        let (dir, db, _tracker, _backups) = setup().await;
+        let other = dir.path().join("other.db");
+        let pool = SqlitePool::connect_with(
+            SqliteConnectOptions::new()
+                .filename(&other)
+                .create_if_missing(true),
+        )
+        .await
+        .unwrap();
+        sqlx::query("CREATE TABLE notes (body TEXT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+
+        let result = restore_from(db.pool(), &other).await;
+
+        assert!(matches!(result, Err(HistoryError::Backup(_))));
+    }
+}
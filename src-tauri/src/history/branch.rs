@@ -0,0 +1,462 @@
+//! Branching a conversation from an earlier message.
+//!
+//! A branch is a new conversation holding copies of the source's messages up
+//! to and including the chosen one, so a thread can take another direction
+//! without losing the original. Copies get new IDs but keep their
+//! timestamps, metadata and attachments, and the branch keeps the source's
+//! system prompt override. Regeneration lineage between copied messages is
+//! kept, and links to messages left behind are dropped.
+
+use std::collections::HashMap;
+
+use sqlx::{Row, SqlitePool};
+
+use super::types::{Conversation, HistoryError};
+use crate::db;
+
+/// Suffix added to the title of a branch.
+pub const BRANCH_SUFFIX: &str = "(branch)";
+
+/// Copy a conversation up to `from_message_id` into a new conversation.
+///
+/// Runs in one transaction: either the branch and all of its messages are
+/// created, or nothing is.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `conversation_id` - Conversation to branch
+/// * `from_message_id` - Last message to copy; must belong to the conversation
+/// * `now_ms` - Current Unix timestamp (ms), used for the branch's own
+///   timestamps
+///
+/// # Returns
+///
+/// * `Ok(Conversation)` - The branch, with its lineage set
+/// * `Err(HistoryError::NotFound)` - The conversation does not exist
+/// * `Err(HistoryError::MessageNotFound)` - The message does not exist or
+///   belongs to another conversation
+pub async fn branch_conversation(
+    pool: &SqlitePool,
+    conversation_id: &str,
+    from_message_id: &str,
+    now_ms: i64,
+) -> Result<Conversation, HistoryError> {
+    let mut tx = db::begin_write(pool).await?;
+
+    let (title, system_prompt_override): (String, Option<String>) =
+        sqlx::query_as("SELECT title, system_prompt_override FROM conversations WHERE id = ?")
+            .bind(conversation_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| HistoryError::NotFound(conversation_id.to_string()))?;
+
+    let boundary: i64 =
+        sqlx::query_scalar("SELECT created_at FROM messages WHERE id = ? AND conversation_id = ?")
+            .bind(from_message_id)
+            .bind(conversation_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| HistoryError::MessageNotFound(from_message_id.to_string()))?;
+
+    let branch = Conversation {
+        id: uuid::Uuid::now_v7().to_string(),
+        title: format!("{} {}", title, BRANCH_SUFFIX),
+        created_at: now_ms,
+        updated_at: now_ms,
+        pinned: false,
+        archived: false,
+        deleted_at: None,
+        branched_from_conversation_id: Some(conversation_id.to_string()),
+        branched_from_message_id: Some(from_message_id.to_string()),
+        system_prompt_override,
+    };
+
+    // The title was chosen by copying, so automatic titles leave it alone
+    sqlx::query(
+        "INSERT INTO conversations (id, title, created_at, updated_at, auto_title, branched_from_conversation_id, branched_from_message_id, system_prompt_override)
+         VALUES (?, ?, ?, ?, 0, ?, ?, ?)",
+    )
+    .bind(&branch.id)
+    .bind(&branch.title)
+    .bind(branch.created_at)
+    .bind(branch.updated_at)
+    .bind(&branch.branched_from_conversation_id)
+    .bind(&branch.branched_from_message_id)
+    .bind(&branch.system_prompt_override)
+    .execute(&mut *tx)
+    .await?;
+
+    // Same order as `get_conversation`; a regenerated reply shares its
+    // predecessor's `created_at` and sorts after it by ID
+    let rows = sqlx::query(
+        "SELECT id, role, content, created_at, starred, parent_message_id, superseded_by,
+                provider, model, prompt_tokens, completion_tokens, duration_ms
+         FROM messages
+         WHERE conversation_id = ? AND (created_at < ? OR (created_at = ? AND id <= ?))
+         ORDER BY created_at ASC, id ASC",
+    )
+    .bind(conversation_id)
+    .bind(boundary)
+    .bind(boundary)
+    .bind(from_message_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let new_ids: HashMap<String, String> = rows
+        .iter()
+        .map(|row| (row.get("id"), uuid::Uuid::now_v7().to_string()))
+        .collect();
+    let remap = |column: &str, row: &sqlx::sqlite::SqliteRow| {
+        row.get::<Option<String>, _>(column)
+            .and_then(|old| new_ids.get(&old).cloned())
+    };
+
+    for row in &rows {
+        let old_id: String = row.get("id");
+        let new_id = &new_ids[&old_id];
+        sqlx::query(
+            "INSERT INTO messages (id, conversation_id, role, content, created_at, starred, parent_message_id, superseded_by, provider, model, prompt_tokens, completion_tokens, duration_ms)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(new_id)
+        .bind(&branch.id)
+        .bind(row.get::<String, _>("role"))
+        .bind(row.get::<String, _>("content"))
+        .bind(row.get::<i64, _>("created_at"))
+        .bind(row.get::<bool, _>("starred"))
+        .bind(remap("parent_message_id", row))
+        .bind(remap("superseded_by", row))
+        .bind(row.get::<Option<String>, _>("provider"))
+        .bind(row.get::<Option<String>, _>("model"))
+        .bind(row.get::<Option<i64>, _>("prompt_tokens"))
+        .bind(row.get::<Option<i64>, _>("completion_tokens"))
+        .bind(row.get::<Option<i64>, _>("duration_ms"))
+        .execute(&mut *tx)
+        .await?;
+        // The triggers index plaintext copies; this carries over the
+        // indexed plaintext of sealed ones
+        sqlx::query(
+            "INSERT OR IGNORE INTO message_search (message_id, content)
+             SELECT ?, content FROM message_search WHERE message_id = ?",
+        )
+        .bind(new_id)
+        .bind(&old_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let attachment_ids: Vec<String> =
+            sqlx::query_scalar("SELECT id FROM attachments WHERE message_id = ? ORDER BY rowid")
+                .bind(&old_id)
+                .fetch_all(&mut *tx)
+                .await?;
+        for attachment_id in attachment_ids {
+            sqlx::query(
+                "INSERT INTO attachments (id, message_id, name, size, line_count, truncated, lossy, mime, data, created_at)
+                 SELECT ?, ?, name, size, line_count, truncated, lossy, mime, data, created_at
+                 FROM attachments WHERE id = ?",
+            )
+            .bind(uuid::Uuid::now_v7().to_string())
+            .bind(new_id)
+            .bind(&attachment_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(branch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::search::{self, SearchFilters};
+    use crate::history::store;
+    use crate::history::types::{MessageMetadata, NewAttachment};
+
+    /// Conversation "Trip ideas" with alternating user/assistant messages,
+    /// one ms apart.
+    async fn conversation(pool: &SqlitePool, count: usize) -> (String, Vec<String>) {
+        let conversation = store::create_conversation(pool, Some("Trip ideas".to_string()), 0)
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for i in 0..count {
+            let role = if i % 2 == 0 { "user" } else { "assistant" };
+            let message = store::append_message(
+                pool,
+                &conversation.id,
+                role,
+                &format!("message {}", i),
+                &MessageMetadata::default(),
+                &[],
+                i as i64 + 1,
+            )
+            .await
+            .unwrap();
+            ids.push(message.id);
+        }
+        (conversation.id, ids)
+    }
+
+    // ===== Copying =====
+
+    #[tokio::test]
+    async fn test_copies_up_to_and_including_message() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let (source, ids) = conversation(pool, 6).await;
+
+        let branch = branch_conversation(pool, &source, &ids[3], 100)
+            .await
+            .unwrap();
+        let copied = store::get_conversation(pool, &branch.id).await.unwrap();
+
+        let contents: Vec<&str> = copied.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec!["message 0", "message 1", "message 2", "message 3"]
+        );
+        let timestamps: Vec<i64> = copied.messages.iter().map(|m| m.created_at).collect();
+        assert_eq!(timestamps, vec![1, 2, 3, 4]);
+        assert!(copied.messages.iter().all(|m| !ids.contains(&m.id)));
+        assert_eq!(
+            store::get_conversation(pool, &source)
+                .await
+                .unwrap()
+                .messages
+                .len(),
+            6
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copies_are_searchable() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let (source, ids) = conversation(pool, 4).await;
+
+        let branch = branch_conversation(pool, &source, &ids[1], 100)
+            .await
+            .unwrap();
+        let results = search::search(
+            pool,
+            Some("message 1"),
+            &SearchFilters {
+                conversation_id: Some(branch.id.clone()),
+                ..SearchFilters::default()
+            },
+            10,
+            0,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(results.total, 1);
+        assert_eq!(results.hits[0].message.conversation_id, branch.id);
+    }
+
+    #[tokio::test]
+    async fn test_parent_links_point_at_copies() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let (source, ids) = conversation(pool, 4).await;
+
+        let branch = branch_conversation(pool, &source, &ids[3], 100)
+            .await
+            .unwrap();
+        let copied = store::get_conversation(pool, &branch.id).await.unwrap();
+
+        assert_eq!(
+            copied.messages[1].parent_message_id.as_deref(),
+            Some(copied.messages[0].id.as_str())
+        );
+        assert_eq!(
+            copied.messages[3].parent_message_id.as_deref(),
+            Some(copied.messages[2].id.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_superseded_link_outside_branch_is_dropped() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let (source, ids) = conversation(pool, 2).await;
+        // A regenerated reply: same created_at, newer ID
+        let replacement = uuid::Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO messages (id, conversation_id, role, content, created_at, parent_message_id)
+             VALUES (?, ?, 'assistant', 'better', 2, ?)",
+        )
+        .bind(&replacement)
+        .bind(&source)
+        .bind(&ids[0])
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE messages SET superseded_by = ? WHERE id = ?")
+            .bind(&replacement)
+            .bind(&ids[1])
+            .execute(pool)
+            .await
+            .unwrap();
+
+        let from_old = branch_conversation(pool, &source, &ids[1], 100)
+            .await
+            .unwrap();
+        let from_new = branch_conversation(pool, &source, &replacement, 100)
+            .await
+            .unwrap();
+
+        let old = store::get_conversation(pool, &from_old.id).await.unwrap();
+        assert_eq!(old.messages.len(), 2);
+        assert_eq!(old.messages[1].content, "message 1");
+        assert_eq!(old.messages[1].superseded_by, None);
+
+        let new = store::get_conversation(pool, &from_new.id).await.unwrap();
+        assert_eq!(new.messages.len(), 3);
+        assert_eq!(
+            new.messages[1].superseded_by.as_deref(),
+            Some(new.messages[2].id.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_attachments_are_copied() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        let file = NewAttachment {
+            name: "notes.txt".to_string(),
+            size: 42,
+            line_count: 3,
+            truncated: false,
+            lossy: false,
+            mime: None,
+            data: None,
+        };
+        let message = store::append_message(
+            pool,
+            &conversation.id,
+            "user",
+            "Summarize",
+            &MessageMetadata::default(),
+            &[file],
+            1,
+        )
+        .await
+        .unwrap();
+
+        let branch = branch_conversation(pool, &conversation.id, &message.id, 100)
+            .await
+            .unwrap();
+        let copied = store::get_conversation(pool, &branch.id).await.unwrap();
+        let attachments = store::list_attachments(pool, &copied.messages[0].id)
+            .await
+            .unwrap();
+
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].name, "notes.txt");
+        assert_eq!(attachments[0].created_at, 1);
+    }
+
+    // ===== Lineage =====
+
+    #[tokio::test]
+    async fn test_lineage_fields() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let (source, ids) = conversation(pool, 4).await;
+
+        let branch = branch_conversation(pool, &source, &ids[1], 100)
+            .await
+            .unwrap();
+        let loaded = store::get_conversation(pool, &branch.id).await.unwrap();
+
+        assert_eq!(loaded.conversation, branch);
+        assert_eq!(branch.title, "Trip ideas (branch)");
+        assert_eq!(branch.created_at, 100);
+        assert_eq!(
+            branch.branched_from_conversation_id.as_deref(),
+            Some(source.as_str())
+        );
+        assert_eq!(
+            branch.branched_from_message_id.as_deref(),
+            Some(ids[1].as_str())
+        );
+        let original = store::find_conversation(pool, &source).await.unwrap();
+        assert_eq!(original.branched_from_conversation_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_purging_source_clears_lineage() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let (source, ids) = conversation(pool, 2).await;
+        let branch = branch_conversation(pool, &source, &ids[1], 100)
+            .await
+            .unwrap();
+
+        store::purge_conversation(pool, &source).await.unwrap();
+        let loaded = store::find_conversation(pool, &branch.id).await.unwrap();
+
+        assert_eq!(loaded.branched_from_conversation_id, None);
+        assert_eq!(loaded.branched_from_message_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_branch_keeps_title_from_auto_titling() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let (source, ids) = conversation(pool, 2).await;
+
+        let branch = branch_conversation(pool, &source, &ids[1], 100)
+            .await
+            .unwrap();
+        let auto_title: bool =
+            sqlx::query_scalar("SELECT auto_title FROM conversations WHERE id = ?")
+                .bind(&branch.id)
+                .fetch_one(pool)
+                .await
+                .unwrap();
+
+        assert!(!auto_title);
+    }
+
+    // ===== Errors =====
+
+    #[tokio::test]
+    async fn test_missing_message_creates_nothing() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let (source, _) = conversation(pool, 2).await;
+        let (other, other_ids) = conversation(pool, 2).await;
+
+        assert_eq!(
+            branch_conversation(pool, &source, "missing", 100).await,
+            Err(HistoryError::MessageNotFound("missing".to_string()))
+        );
+        assert_eq!(
+            branch_conversation(pool, &source, &other_ids[0], 100).await,
+            Err(HistoryError::MessageNotFound(other_ids[0].clone()))
+        );
+        assert_ne!(source, other);
+        let conversations: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM conversations")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(conversations, 2);
+    }
+
+    #[tokio::test]
+    async fn test_missing_conversation_is_not_found() {
+        let db = Db::in_memory().await.unwrap();
+
+        assert_eq!(
+            branch_conversation(db.pool(), "missing", "also-missing", 100).await,
+            Err(HistoryError::NotFound("missing".to_string()))
+        );
+    }
+}
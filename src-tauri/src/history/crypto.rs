@@ -0,0 +1,311 @@
+//! Encryption of stored message content (`history.encrypt_content`).
+//!
+//! Content is sealed with AES-256-GCM under a key kept in the OS keychain
+//! (see [`super::keychain`]) and stored as a versioned envelope,
+//! `enc:v1:<nonce>:<ciphertext>`, both parts base64 and the ciphertext
+//! ending in the GCM tag. Anything that doesn't parse as an envelope is
+//! plaintext and read as is, so a database can hold both while
+//! [`super::encryption`] migrates it.
+//!
+//! The key in use is process-wide, like the locale in [`crate::i18n`]:
+//! queries seal new content with [`seal_new`] and open what they read with
+//! [`open_stored`], without the key being passed through every call.
+
+use std::sync::{Arc, RwLock};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use thiserror::Error;
+
+use super::types::HistoryError;
+
+/// Prefix of every envelope, whatever its version.
+pub const ENVELOPE_PREFIX: &str = "enc:";
+
+/// Envelope version written by [`Cipher::seal`].
+pub const CURRENT_VERSION: &str = "v1";
+
+/// Length of a content key in bytes (AES-256).
+pub const KEY_LEN: usize = 32;
+
+/// Shown instead of content that can't be decrypted.
+pub const UNREADABLE: &str = "[Encrypted message: the key is unavailable]";
+
+/// Why content couldn't be sealed or opened.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CryptoError {
+    /// The key isn't [`KEY_LEN`] bytes
+    #[error("Invalid content key")]
+    InvalidKey,
+    /// The envelope was written by a newer version of the app
+    #[error("Unsupported envelope version '{0}'")]
+    UnsupportedVersion(String),
+    /// Wrong key, or the ciphertext was changed
+    #[error("Failed to decrypt content")]
+    Decrypt,
+    /// The system random number generator failed
+    #[error("Failed to encrypt content")]
+    Encrypt,
+}
+
+impl From<CryptoError> for HistoryError {
+    fn from(error: CryptoError) -> Self {
+        Self::Internal(error.to_string())
+    }
+}
+
+/// The parts of an envelope.
+struct Envelope<'a> {
+    version: &'a str,
+    nonce: [u8; NONCE_LEN],
+    ciphertext: Vec<u8>,
+}
+
+/// Split `stored` into an envelope, or `None` if it's plaintext.
+fn parse(stored: &str) -> Option<Envelope<'_>> {
+    let mut parts = stored.strip_prefix(ENVELOPE_PREFIX)?.split(':');
+    let (version, nonce, ciphertext) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() || version.is_empty() {
+        return None;
+    }
+    Some(Envelope {
+        version,
+        nonce: STANDARD.decode(nonce).ok()?.try_into().ok()?,
+        ciphertext: STANDARD.decode(ciphertext).ok()?,
+    })
+}
+
+/// Whether `stored` is an envelope rather than plaintext.
+pub fn is_envelope(stored: &str) -> bool {
+    parse(stored).is_some()
+}
+
+/// An AES-256-GCM content key.
+pub struct Cipher {
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl Cipher {
+    /// Use `key`, which must be [`KEY_LEN`] bytes.
+    pub fn new(key: &[u8]) -> Result<Self, CryptoError> {
+        if key.len() != KEY_LEN {
+            return Err(CryptoError::InvalidKey);
+        }
+        let key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| CryptoError::InvalidKey)?;
+        Ok(Self {
+            key: LessSafeKey::new(key),
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// A new random key.
+    pub fn generate_key() -> Result<Vec<u8>, CryptoError> {
+        let mut key = vec![0; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut key)
+            .map_err(|_| CryptoError::Encrypt)?;
+        Ok(key)
+    }
+
+    /// Encrypt `plaintext` into a [`CURRENT_VERSION`] envelope under a fresh
+    /// random nonce.
+    pub fn seal(&self, plaintext: &str) -> Result<String, CryptoError> {
+        let mut nonce = [0; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| CryptoError::Encrypt)?;
+        let mut data = plaintext.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .map_err(|_| CryptoError::Encrypt)?;
+        Ok(format!(
+            "{}{}:{}:{}",
+            ENVELOPE_PREFIX,
+            CURRENT_VERSION,
+            STANDARD.encode(nonce),
+            STANDARD.encode(data)
+        ))
+    }
+
+    /// Decrypt an envelope; plaintext is returned unchanged.
+    pub fn open(&self, stored: &str) -> Result<String, CryptoError> {
+        let Some(envelope) = parse(stored) else {
+            return Ok(stored.to_string());
+        };
+        if envelope.version != CURRENT_VERSION {
+            return Err(CryptoError::UnsupportedVersion(
+                envelope.version.to_string(),
+            ));
+        }
+        let mut data = envelope.ciphertext;
+        let plaintext = self
+            .key
+            .open_in_place(
+                Nonce::assume_unique_for_key(envelope.nonce),
+                Aad::empty(),
+                &mut data,
+            )
+            .map_err(|_| CryptoError::Decrypt)?;
+        String::from_utf8(plaintext.to_vec()).map_err(|_| CryptoError::Decrypt)
+    }
+}
+
+/// The key in use and whether new content is sealed with it.
+struct Active {
+    cipher: Option<Arc<Cipher>>,
+    encrypt: bool,
+}
+
+static ACTIVE: RwLock<Active> = RwLock::new(Active {
+    cipher: None,
+    encrypt: false,
+});
+
+/// Set the process-wide key.
+///
+/// # Arguments
+///
+/// * `cipher` - Key for opening envelopes (and sealing, if `encrypt`)
+/// * `encrypt` - Whether content written from now on is sealed
+pub fn install(cipher: Option<Arc<Cipher>>, encrypt: bool) {
+    *ACTIVE.write().unwrap() = Active { cipher, encrypt };
+}
+
+/// The key in use, if one is loaded.
+pub fn active() -> Option<Arc<Cipher>> {
+    ACTIVE.read().unwrap().cipher.clone()
+}
+
+/// Content as it should be written: sealed while encryption is on,
+/// otherwise unchanged.
+pub fn seal_new(content: &str) -> Result<String, HistoryError> {
+    let active = ACTIVE.read().unwrap();
+    match &active.cipher {
+        Some(cipher) if active.encrypt => Ok(cipher.seal(content)?),
+        _ => Ok(content.to_string()),
+    }
+}
+
+/// Content as it should be shown: envelopes opened with the key in use.
+///
+/// Content that can't be opened (no key loaded, the wrong key, a newer
+/// envelope version) reads as [`UNREADABLE`].
+pub fn open_stored(stored: String) -> String {
+    if !stored.starts_with(ENVELOPE_PREFIX) || !is_envelope(&stored) {
+        return stored;
+    }
+    match active().map(|cipher| cipher.open(&stored)) {
+        Some(Ok(plaintext)) => plaintext,
+        Some(Err(e)) => {
+            tracing::warn!(error = %e, "Failed to open stored content");
+            UNREADABLE.to_string()
+        }
+        None => UNREADABLE.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher() -> Cipher {
+        Cipher::new(&Cipher::generate_key().unwrap()).unwrap()
+    }
+
+    // ===== Round trip =====
+
+    #[test]
+    fn test_seal_and_open() {
+        let cipher = cipher();
+
+        let sealed = cipher.seal("My salary is 42k 💸").unwrap();
+
+        assert!(sealed.starts_with("enc:v1:"));
+        assert!(!sealed.contains("salary"));
+        assert_eq!(cipher.open(&sealed).unwrap(), "My salary is 42k 💸");
+    }
+
+    #[test]
+    fn test_nonce_differs_per_seal() {
+        let cipher = cipher();
+
+        assert_ne!(cipher.seal("same").unwrap(), cipher.seal("same").unwrap());
+    }
+
+    #[test]
+    fn test_empty_content_round_trips() {
+        let cipher = cipher();
+
+        assert_eq!(cipher.open(&cipher.seal("").unwrap()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_wrong_key_fails() {
+        let sealed = cipher().seal("secret").unwrap();
+
+        assert_eq!(cipher().open(&sealed), Err(CryptoError::Decrypt));
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_fails() {
+        let cipher = cipher();
+        let sealed = cipher.seal("secret").unwrap();
+        let (head, ciphertext) = sealed.rsplit_once(':').unwrap();
+        let mut bytes = STANDARD.decode(ciphertext).unwrap();
+        bytes[0] ^= 1;
+
+        let tampered = format!("{}:{}", head, STANDARD.encode(bytes));
+
+        assert_eq!(cipher.open(&tampered), Err(CryptoError::Decrypt));
+    }
+
+    #[test]
+    fn test_invalid_key_length() {
+        assert!(matches!(
+            Cipher::new(&[0; 16]),
+            Err(CryptoError::InvalidKey)
+        ));
+    }
+
+    // ===== Envelope =====
+
+    #[test]
+    fn test_plaintext_passes_through() {
+        let cipher = cipher();
+
+        for text in [
+            "hello",
+            "enc: not really",
+            "enc:v1:only-two",
+            "enc:v1:!!:??",
+        ] {
+            assert!(!is_envelope(text), "{}", text);
+            assert_eq!(cipher.open(text).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_unknown_version_is_rejected() {
+        let cipher = cipher();
+        let sealed = cipher.seal("secret").unwrap();
+        let future = sealed.replacen("enc:v1:", "enc:v2:", 1);
+
+        assert!(is_envelope(&future));
+        assert_eq!(
+            cipher.open(&future),
+            Err(CryptoError::UnsupportedVersion("v2".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_open_stored_without_key_is_unreadable() {
+        // Nothing installs a key in tests, so envelopes can't be opened
+        let sealed = cipher().seal("secret").unwrap();
+
+        assert_eq!(open_stored(sealed), UNREADABLE);
+        assert_eq!(open_stored("plain".to_string()), "plain");
+    }
+}
@@ -0,0 +1,363 @@
+//! Unsent prompt text kept across hiding the launcher.
+//!
+//! The frontend saves what's in the input on `launcher-hidden`; when the
+//! launcher is shown again the draft it saved last is sent back as
+//! `draft-restored`, right after `launcher-shown`. There's one draft per
+//! conversation, plus one with no conversation for a prompt that would
+//! start a new one. Drafts go away when their prompt is submitted, when
+//! their conversation is purged, and after `history.draft_retention_days`.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::types::HistoryError;
+use crate::db::Db;
+
+/// Event sent with the restored draft after the launcher is shown.
+pub const RESTORED_EVENT: &str = "draft-restored";
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Unsent prompt text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Draft {
+    /// Conversation the prompt was typed into; `None` for a new conversation
+    pub conversation_id: Option<String>,
+    pub text: String,
+    /// Unix timestamp (ms)
+    pub updated_at: i64,
+}
+
+/// Which draft was saved last, i.e. what the launcher showed when it was
+/// hidden.
+#[derive(Default)]
+pub struct DraftTracker {
+    last: Mutex<Option<Option<String>>>,
+}
+
+impl DraftTracker {
+    /// Remember that `conversation_id`'s draft was just saved.
+    pub fn record(&self, conversation_id: Option<&str>) {
+        *self.last.lock().unwrap() = Some(conversation_id.map(str::to_string));
+    }
+
+    fn last(&self) -> Option<Option<String>> {
+        self.last.lock().unwrap().clone()
+    }
+}
+
+/// Save a conversation's draft, replacing the previous one.
+///
+/// Blank text deletes the draft instead.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::NotFound)` - The conversation does not exist
+pub async fn save_draft(
+    pool: &SqlitePool,
+    conversation_id: Option<&str>,
+    text: &str,
+    now_ms: i64,
+) -> Result<(), HistoryError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM drafts WHERE conversation_id IS ?")
+        .bind(conversation_id)
+        .execute(&mut *tx)
+        .await?;
+    if !text.trim().is_empty() {
+        sqlx::query("INSERT INTO drafts (conversation_id, text, updated_at) VALUES (?, ?, ?)")
+            .bind(conversation_id)
+            .bind(text)
+            .bind(now_ms)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| match e.as_database_error() {
+                Some(db) if db.is_foreign_key_violation() => {
+                    HistoryError::NotFound(conversation_id.unwrap_or_default().to_string())
+                }
+                _ => HistoryError::from(e),
+            })?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Load a conversation's draft, if it has one.
+pub async fn get_draft(
+    pool: &SqlitePool,
+    conversation_id: Option<&str>,
+) -> Result<Option<Draft>, HistoryError> {
+    let row: Option<(Option<String>, String, i64)> = sqlx::query_as(
+        "SELECT conversation_id, text, updated_at FROM drafts WHERE conversation_id IS ?",
+    )
+    .bind(conversation_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(conversation_id, text, updated_at)| Draft {
+        conversation_id,
+        text,
+        updated_at,
+    }))
+}
+
+/// Delete the draft of a prompt that was just submitted.
+///
+/// The new-conversation draft goes too when the prompt started the
+/// conversation, i.e. it has at most one user message.
+pub async fn clear_submitted(
+    pool: &SqlitePool,
+    conversation_id: Option<&str>,
+) -> Result<(), HistoryError> {
+    sqlx::query(
+        "DELETE FROM drafts
+         WHERE conversation_id IS ?
+            OR (conversation_id IS NULL AND (
+                SELECT COUNT(*) FROM messages WHERE conversation_id IS ? AND role = 'user') <= 1)",
+    )
+    .bind(conversation_id)
+    .bind(conversation_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Delete drafts not updated for `retention_days` days.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `retention_days` - `history.draft_retention_days`; `0` keeps drafts
+///   forever
+/// * `now_ms` - Current Unix timestamp (ms)
+///
+/// # Returns
+///
+/// Number of drafts deleted
+pub async fn purge_expired_drafts(
+    pool: &SqlitePool,
+    retention_days: u32,
+    now_ms: i64,
+) -> Result<u64, HistoryError> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let result = sqlx::query("DELETE FROM drafts WHERE updated_at <= ?")
+        .bind(now_ms - i64::from(retention_days) * DAY_MS)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Send the draft saved when the launcher was last hidden as
+/// `draft-restored`, if there is one.
+///
+/// Called after the launcher is shown; runs in the background.
+pub fn emit_restored(app: &AppHandle) {
+    let Some(conversation_id) = app.state::<DraftTracker>().last() else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match get_draft(app.state::<Db>().pool(), conversation_id.as_deref()).await {
+            Ok(Some(draft)) => {
+                let _ = app.emit(RESTORED_EVENT, draft);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, "Failed to load draft"),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::store;
+    use crate::history::types::MessageMetadata;
+
+    async fn draft_count(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM drafts")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn ask(pool: &SqlitePool, conversation_id: &str, at: i64) {
+        store::append_message(
+            pool,
+            conversation_id,
+            "user",
+            "question",
+            &MessageMetadata::default(),
+            &[],
+            at,
+        )
+        .await
+        .unwrap();
+    }
+
+    // ===== Saving =====
+
+    #[tokio::test]
+    async fn test_save_replaces_previous_draft() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+
+        save_draft(pool, Some(&conversation.id), "How do I", 1)
+            .await
+            .unwrap();
+        save_draft(pool, Some(&conversation.id), "How do I rebase", 2)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            get_draft(pool, Some(&conversation.id)).await.unwrap(),
+            Some(Draft {
+                conversation_id: Some(conversation.id.clone()),
+                text: "How do I rebase".to_string(),
+                updated_at: 2,
+            })
+        );
+        assert_eq!(draft_count(pool).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_new_conversation_draft_is_separate() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+
+        save_draft(pool, None, "first", 1).await.unwrap();
+        save_draft(pool, None, "second", 2).await.unwrap();
+        save_draft(pool, Some(&conversation.id), "other", 3)
+            .await
+            .unwrap();
+
+        let draft = get_draft(pool, None).await.unwrap().unwrap();
+        assert_eq!(draft.conversation_id, None);
+        assert_eq!(draft.text, "second");
+        assert_eq!(draft_count(pool).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_blank_text_deletes_draft() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+
+        save_draft(pool, None, "half a thought", 1).await.unwrap();
+        save_draft(pool, None, "  \n", 2).await.unwrap();
+
+        assert_eq!(get_draft(pool, None).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_conversation_is_not_found() {
+        let db = Db::in_memory().await.unwrap();
+
+        assert_eq!(
+            save_draft(db.pool(), Some("missing"), "text", 1).await,
+            Err(HistoryError::NotFound("missing".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_purging_conversation_deletes_draft() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        save_draft(pool, Some(&conversation.id), "text", 1)
+            .await
+            .unwrap();
+
+        store::purge_conversation(pool, &conversation.id)
+            .await
+            .unwrap();
+
+        assert_eq!(draft_count(pool).await, 0);
+    }
+
+    // ===== Submitting =====
+
+    #[tokio::test]
+    async fn test_first_prompt_clears_new_conversation_draft() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        save_draft(pool, None, "question", 1).await.unwrap();
+        let conversation = store::create_conversation(pool, None, 2).await.unwrap();
+        ask(pool, &conversation.id, 3).await;
+
+        clear_submitted(pool, Some(&conversation.id)).await.unwrap();
+
+        assert_eq!(draft_count(pool).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_follow_up_keeps_new_conversation_draft() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        ask(pool, &conversation.id, 1).await;
+        save_draft(pool, None, "for later", 2).await.unwrap();
+        save_draft(pool, Some(&conversation.id), "follow-up", 3)
+            .await
+            .unwrap();
+        ask(pool, &conversation.id, 4).await;
+
+        clear_submitted(pool, Some(&conversation.id)).await.unwrap();
+
+        assert_eq!(get_draft(pool, Some(&conversation.id)).await.unwrap(), None);
+        assert!(get_draft(pool, None).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_unstored_prompt_clears_new_conversation_draft() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        save_draft(pool, None, "question", 1).await.unwrap();
+
+        clear_submitted(pool, None).await.unwrap();
+
+        assert_eq!(draft_count(pool).await, 0);
+    }
+
+    // ===== Retention =====
+
+    #[tokio::test]
+    async fn test_purge_cutoff() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let now = 100 * DAY_MS;
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        save_draft(pool, None, "expired", now - 7 * DAY_MS)
+            .await
+            .unwrap();
+        save_draft(pool, Some(&conversation.id), "kept", now - 7 * DAY_MS + 1)
+            .await
+            .unwrap();
+
+        let purged = purge_expired_drafts(pool, 7, now).await.unwrap();
+
+        assert_eq!(purged, 1);
+        assert_eq!(get_draft(pool, None).await.unwrap(), None);
+        assert!(get_draft(pool, Some(&conversation.id))
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_zero_retention_keeps_drafts() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        save_draft(pool, None, "old", 0).await.unwrap();
+
+        assert_eq!(
+            purge_expired_drafts(pool, 0, 1_000 * DAY_MS).await.unwrap(),
+            0
+        );
+        assert_eq!(draft_count(pool).await, 1);
+    }
+}
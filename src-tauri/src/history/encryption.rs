@@ -0,0 +1,426 @@
+//! Turning `history.encrypt_content` on and off.
+//!
+//! Existing messages are re-encrypted (or decrypted) in the background, in
+//! batches of [`BATCH_SIZE`], with `history-encryption-progress` sent after
+//! each batch. Every row is checked on its own, so a migration that was
+//! interrupted (by quitting, or by the setting flipping back) picks up where
+//! it left off the next time it runs; the next start runs it again when the
+//! database doesn't match the setting yet. Rows that can't be decrypted are
+//! left as they are.
+//!
+//! With `history.search_encrypted` on, the plaintext of encrypted messages
+//! is kept in the search index (see [`super::search`]); each migration
+//! ends by indexing or dropping it to match the setting.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::crypto::{self, Cipher};
+use super::keychain::{self, NativeKeyStore};
+use super::search;
+use super::types::HistoryError;
+use crate::db::{self, Db};
+use crate::settings::{HistorySettings, SettingsManager};
+
+/// Event sent as existing messages are encrypted or decrypted.
+pub const PROGRESS_EVENT: &str = "history-encryption-progress";
+
+/// Messages updated per transaction.
+pub const BATCH_SIZE: i64 = 200;
+
+/// Payload of the `history-encryption-progress` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EncryptionProgress {
+    /// `true` while encrypting, `false` while decrypting
+    pub encrypting: bool,
+    /// Messages checked so far
+    pub done: u64,
+    /// Messages in the database
+    pub total: u64,
+    /// Whether this is the last event of the migration
+    pub finished: bool,
+    /// Why the migration stopped early, on the last event
+    pub error: Option<String>,
+}
+
+/// What the history UI can offer, given the encryption settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HistoryCapabilities {
+    /// `history.encrypt_content`
+    pub content_encrypted: bool,
+    /// Whether to show search; off while content is encrypted, unless
+    /// `history.search_encrypted` keeps its plaintext indexed
+    pub search_available: bool,
+}
+
+impl HistoryCapabilities {
+    pub fn from_settings(settings: &HistorySettings) -> Self {
+        Self {
+            content_encrypted: settings.encrypt_content,
+            search_available: !settings.encrypt_content || settings.search_encrypted,
+        }
+    }
+}
+
+/// Bumped by every [`apply`], so a migration stops once a newer one starts.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Encrypt (or decrypt) every message that isn't yet, in batches.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `cipher` - Content key
+/// * `encrypt` - `true` to seal plaintext, `false` to open envelopes
+/// * `on_batch` - Called with `(done, total)` after each batch; returning
+///   `false` stops the migration there
+///
+/// # Returns
+///
+/// Number of messages changed
+pub async fn migrate(
+    pool: &SqlitePool,
+    cipher: &Cipher,
+    encrypt: bool,
+    mut on_batch: impl FnMut(u64, u64) -> bool,
+) -> Result<u64, HistoryError> {
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+        .fetch_one(pool)
+        .await?;
+    let (mut done, mut changed, mut after) = (0u64, 0u64, 0i64);
+
+    loop {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT rowid, content FROM messages WHERE rowid > ? ORDER BY rowid LIMIT ?",
+        )
+        .bind(after)
+        .bind(BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+        let Some(&(last, _)) = rows.last() else {
+            break;
+        };
+
+        let mut tx = db::begin_write(pool).await?;
+        for (rowid, content) in &rows {
+            let Some(updated) = convert(cipher, encrypt, content)? else {
+                continue;
+            };
+            sqlx::query("UPDATE messages SET content = ? WHERE rowid = ?")
+                .bind(updated)
+                .bind(rowid)
+                .execute(&mut *tx)
+                .await?;
+            changed += 1;
+        }
+        tx.commit().await?;
+
+        after = last;
+        done += rows.len() as u64;
+        if !on_batch(done, total.max(0) as u64) {
+            break;
+        }
+    }
+    Ok(changed)
+}
+
+/// The new content of a message, or `None` if it's already as wanted (or
+/// can't be decrypted).
+fn convert(cipher: &Cipher, encrypt: bool, content: &str) -> Result<Option<String>, HistoryError> {
+    match (encrypt, crypto::is_envelope(content)) {
+        (true, false) => Ok(Some(cipher.seal(content)?)),
+        (false, true) => match cipher.open(content) {
+            Ok(plaintext) => Ok(Some(plaintext)),
+            Err(e) => {
+                tracing::warn!(error = %e, "Skipping message that can't be decrypted");
+                Ok(None)
+            }
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Whether any message is stored encrypted.
+pub async fn has_encrypted(pool: &SqlitePool) -> Result<bool, HistoryError> {
+    Ok(sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM messages WHERE substr(content, 1, 4) = 'enc:')",
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Apply `history.encrypt_content` and `history.search_encrypted`: load
+/// the key and start migrating existing messages, and then the search
+/// index, to match. Cached answers aren't migrated; turning encryption on
+/// drops the plaintext ones.
+///
+/// When turning encryption off the key is only loaded if one exists, to
+/// decrypt what was encrypted before. A migration still running in the
+/// other direction stops after its current batch.
+///
+/// # Returns
+///
+/// * `Err(String)` - The keychain is unavailable or locked; nothing changed
+pub fn apply(app: &AppHandle, settings: &HistorySettings) -> Result<(), String> {
+    let encrypt = settings.encrypt_content;
+    let index = settings.search_encrypted;
+    search::set_index_sealed(index);
+    let cipher = if encrypt {
+        Some(keychain::load_or_create(&NativeKeyStore)?)
+    } else {
+        keychain::load_existing(&NativeKeyStore)?
+    };
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let Some(cipher) = cipher.map(Arc::new) else {
+        crypto::install(None, false);
+        return Ok(());
+    };
+    crypto::install(Some(cipher.clone()), encrypt);
+
+    let Some(db) = app.try_state::<Db>() else {
+        return Ok(());
+    };
+    let pool = db.pool().clone();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let emit = |done, total, finished, error| {
+            let _ = app.emit(
+                PROGRESS_EVENT,
+                EncryptionProgress {
+                    encrypting: encrypt,
+                    done,
+                    total,
+                    finished,
+                    error,
+                },
+            );
+        };
+        // Cached answers are disposable; drop the ones written in plaintext
+        if encrypt {
+            if let Err(e) = sqlx::query("DELETE FROM llm_cache WHERE substr(content, 1, 4) != ?")
+                .bind(crypto::ENVELOPE_PREFIX)
+                .execute(&pool)
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to drop plaintext cached answers");
+            }
+        }
+        let mut progress = (0, 0);
+        let result = migrate(&pool, &cipher, encrypt, |done, total| {
+            progress = (done, total);
+            emit(done, total, false, None);
+            GENERATION.load(Ordering::SeqCst) == generation
+        })
+        .await;
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let result = match result {
+            Ok(changed) => search::sync_sealed(&pool, &cipher, index)
+                .await
+                .map(|indexed| (changed, indexed)),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok((changed, indexed)) => {
+                tracing::info!(
+                    encrypt,
+                    changed,
+                    index,
+                    indexed,
+                    "Finished migrating message content"
+                );
+                emit(progress.0, progress.1, true, None);
+            }
+            Err(e) => {
+                tracing::error!(encrypt, error = %e, "Failed to migrate message content");
+                emit(progress.0, progress.1, true, Some(e.to_string()));
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Load the key at startup and finish a migration that was interrupted.
+///
+/// The keychain is only touched when encryption is on or encrypted
+/// messages are left over from when it was.
+pub fn resume(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Ok(settings) = app.state::<SettingsManager>().snapshot() else {
+            return;
+        };
+        let pool = app.state::<Db>().pool().clone();
+        if !settings.history.encrypt_content && !has_encrypted(&pool).await.unwrap_or(false) {
+            return;
+        }
+        let history = settings.history.clone();
+        // The keychain calls block
+        let applied = tauri::async_runtime::spawn_blocking(move || apply(&app, &history)).await;
+        if let Ok(Err(e)) = applied {
+            tracing::error!(error = %e, "Failed to load the history content key");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::store;
+    use crate::history::types::MessageMetadata;
+
+    fn cipher() -> Cipher {
+        Cipher::new(&Cipher::generate_key().unwrap()).unwrap()
+    }
+
+    async fn seeded(count: usize) -> Db {
+        let db = Db::in_memory().await.unwrap();
+        let conversation = store::create_conversation(db.pool(), None, 0)
+            .await
+            .unwrap();
+        for i in 0..count {
+            store::append_message(
+                db.pool(),
+                &conversation.id,
+                "user",
+                &format!("message {}", i),
+                &MessageMetadata::default(),
+                &[],
+                i as i64,
+            )
+            .await
+            .unwrap();
+        }
+        db
+    }
+
+    async fn stored(pool: &SqlitePool) -> Vec<String> {
+        sqlx::query_scalar("SELECT content FROM messages ORDER BY rowid")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    // ===== Migration =====
+
+    #[tokio::test]
+    async fn test_encrypt_then_decrypt() {
+        let db = seeded(3).await;
+        let cipher = cipher();
+
+        let encrypted = migrate(db.pool(), &cipher, true, |_, _| true)
+            .await
+            .unwrap();
+
+        assert_eq!(encrypted, 3);
+        let contents = stored(db.pool()).await;
+        assert!(contents.iter().all(|c| crypto::is_envelope(c)));
+        assert!(has_encrypted(db.pool()).await.unwrap());
+
+        let decrypted = migrate(db.pool(), &cipher, false, |_, _| true)
+            .await
+            .unwrap();
+
+        assert_eq!(decrypted, 3);
+        assert_eq!(
+            stored(db.pool()).await,
+            ["message 0", "message 1", "message 2"]
+        );
+        assert!(!has_encrypted(db.pool()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_progress_per_batch() {
+        let db = seeded(BATCH_SIZE as usize + 5).await;
+        let mut batches = Vec::new();
+
+        migrate(db.pool(), &cipher(), true, |done, total| {
+            batches.push((done, total));
+            true
+        })
+        .await
+        .unwrap();
+
+        let total = BATCH_SIZE as u64 + 5;
+        assert_eq!(batches, [(BATCH_SIZE as u64, total), (total, total)]);
+    }
+
+    #[tokio::test]
+    async fn test_stopped_migration_resumes() {
+        let db = seeded(BATCH_SIZE as usize + 5).await;
+        let cipher = cipher();
+
+        let first = migrate(db.pool(), &cipher, true, |_, _| false)
+            .await
+            .unwrap();
+        let second = migrate(db.pool(), &cipher, true, |_, _| true)
+            .await
+            .unwrap();
+
+        assert_eq!(first, BATCH_SIZE as u64);
+        assert_eq!(second, 5);
+        assert!(stored(db.pool())
+            .await
+            .iter()
+            .all(|c| crypto::is_envelope(c)));
+    }
+
+    #[tokio::test]
+    async fn test_undecryptable_rows_are_skipped() {
+        let db = seeded(2).await;
+        migrate(db.pool(), &cipher(), true, |_, _| true)
+            .await
+            .unwrap();
+
+        let decrypted = migrate(db.pool(), &cipher(), false, |_, _| true)
+            .await
+            .unwrap();
+
+        assert_eq!(decrypted, 0);
+        assert!(stored(db.pool())
+            .await
+            .iter()
+            .all(|c| crypto::is_envelope(c)));
+    }
+
+    #[tokio::test]
+    async fn test_empty_database() {
+        let db = Db::in_memory().await.unwrap();
+        let mut called = false;
+
+        let changed = migrate(db.pool(), &cipher(), true, |_, _| {
+            called = true;
+            true
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(changed, 0);
+        assert!(!called);
+    }
+
+    // ===== Capabilities =====
+
+    #[test]
+    fn test_search_hidden_while_encrypted() {
+        let mut settings = HistorySettings::default();
+        assert!(HistoryCapabilities::from_settings(&settings).search_available);
+
+        settings.encrypt_content = true;
+        assert_eq!(
+            HistoryCapabilities::from_settings(&settings),
+            HistoryCapabilities {
+                content_encrypted: true,
+                search_available: false,
+            }
+        );
+
+        settings.search_encrypted = true;
+        assert!(HistoryCapabilities::from_settings(&settings).search_available);
+    }
+}
@@ -0,0 +1,393 @@
+//! The content key's home in the OS keychain.
+//!
+//! The key is kept as base64 text under the service name `qwik-ask`:
+//!
+//! - **Linux**: the Secret Service (GNOME Keyring, KWallet) default
+//!   collection, found by the `application` and `account` attributes
+//! - **macOS**: a generic password in the login keychain, through the
+//!   `security` tool
+//! - **Windows**: a generic credential in the Credential Manager
+//!
+//! It's never written to `settings.json` or the database, so copying
+//! `history.db` (or a backup of it) elsewhere doesn't copy the key.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use super::crypto::{Cipher, KEY_LEN};
+
+/// Service name the key is stored under.
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+const SERVICE: &str = "qwik-ask";
+
+/// Account name the key is stored under.
+const ACCOUNT: &str = "history-content-key";
+
+/// Where the content key is kept.
+pub trait KeyStore: Send + Sync {
+    /// The stored key, or `None` if there isn't one yet.
+    fn load(&self) -> Result<Option<String>, String>;
+
+    /// Store `key`, replacing any previous one.
+    fn save(&self, key: &str) -> Result<(), String>;
+}
+
+/// The content key from `store`, generating and saving one the first time.
+///
+/// # Returns
+///
+/// * `Ok(Cipher)` - The stored (or new) key
+/// * `Err(String)` - The keychain is unavailable or locked, or holds
+///   something that isn't a key
+pub fn load_or_create(store: &dyn KeyStore) -> Result<Cipher, String> {
+    let key = match store.load()? {
+        Some(stored) => STANDARD
+            .decode(stored.trim())
+            .ok()
+            .filter(|key| key.len() == KEY_LEN)
+            .ok_or("The stored content key is invalid")?,
+        None => {
+            let key = Cipher::generate_key().map_err(|e| e.to_string())?;
+            store.save(&STANDARD.encode(&key))?;
+            tracing::info!("Generated a new history content key");
+            key
+        }
+    };
+    Cipher::new(&key).map_err(|e| e.to_string())
+}
+
+/// The key only if one is stored, for reading content that was encrypted
+/// before `history.encrypt_content` was turned off.
+pub fn load_existing(store: &dyn KeyStore) -> Result<Option<Cipher>, String> {
+    match store.load()? {
+        Some(_) => load_or_create(store).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// The OS keychain.
+pub struct NativeKeyStore;
+
+impl KeyStore for NativeKeyStore {
+    fn load(&self) -> Result<Option<String>, String> {
+        native::load()
+    }
+
+    fn save(&self, key: &str) -> Result<(), String> {
+        native::save(key)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod native {
+    use std::collections::HashMap;
+
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+    use super::ACCOUNT;
+
+    const DESTINATION: &str = "org.freedesktop.secrets";
+    const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+    const DEFAULT_COLLECTION: &str = "/org/freedesktop/secrets/aliases/default";
+
+    /// A secret as the Secret Service passes it: session, parameters,
+    /// value, content type.
+    type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+    fn error(e: zbus::Error) -> String {
+        format!("Secret Service failed: {}", e)
+    }
+
+    fn attributes() -> HashMap<&'static str, &'static str> {
+        HashMap::from([("application", "qwik-ask"), ("account", ACCOUNT)])
+    }
+
+    /// A `plain` session: the secret travels unencrypted over the session
+    /// bus, which only this user's processes can read.
+    fn open_session(connection: &Connection) -> Result<(Proxy<'_>, OwnedObjectPath), String> {
+        let service = Proxy::new(
+            connection,
+            DESTINATION,
+            SERVICE_PATH,
+            "org.freedesktop.Secret.Service",
+        )
+        .map_err(error)?;
+        let (_output, session): (OwnedValue, OwnedObjectPath) = service
+            .call("OpenSession", &("plain", Value::from("")))
+            .map_err(error)?;
+        Ok((service, session))
+    }
+
+    pub fn load() -> Result<Option<String>, String> {
+        let connection = Connection::session().map_err(error)?;
+        let (service, session) = open_session(&connection)?;
+        let (unlocked, locked): (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) = service
+            .call("SearchItems", &(attributes(),))
+            .map_err(error)?;
+        if unlocked.is_empty() {
+            return match locked.is_empty() {
+                true => Ok(None),
+                false => Err("The keyring holding the content key is locked".to_string()),
+            };
+        }
+        let secrets: HashMap<OwnedObjectPath, Secret> = service
+            .call("GetSecrets", &(unlocked, session))
+            .map_err(error)?;
+        let Some((_, _, value, _)) = secrets.into_values().next() else {
+            return Ok(None);
+        };
+        String::from_utf8(value)
+            .map(Some)
+            .map_err(|_| "The stored content key is invalid".to_string())
+    }
+
+    pub fn save(key: &str) -> Result<(), String> {
+        let connection = Connection::session().map_err(error)?;
+        let (_service, session) = open_session(&connection)?;
+        let collection = Proxy::new(
+            &connection,
+            DESTINATION,
+            DEFAULT_COLLECTION,
+            "org.freedesktop.Secret.Collection",
+        )
+        .map_err(error)?;
+        let properties: HashMap<&str, Value> = HashMap::from([
+            (
+                "org.freedesktop.Secret.Item.Label",
+                Value::from("Qwik Ask history key"),
+            ),
+            (
+                "org.freedesktop.Secret.Item.Attributes",
+                Value::from(attributes()),
+            ),
+        ]);
+        let secret: Secret = (
+            session,
+            Vec::new(),
+            key.as_bytes().to_vec(),
+            "text/plain".to_string(),
+        );
+        let (_item, prompt): (OwnedObjectPath, OwnedObjectPath) = collection
+            .call("CreateItem", &(properties, secret, true))
+            .map_err(error)?;
+        // A prompt means the collection is locked and the desktop wants the
+        // user to unlock it first
+        if prompt.as_ref() != ObjectPath::from_static_str_unchecked("/") {
+            return Err("The default keyring is locked".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod native {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    use super::{ACCOUNT, SERVICE};
+
+    /// `security`'s exit code when no such item exists.
+    const ITEM_NOT_FOUND: i32 = 44;
+
+    pub fn load() -> Result<Option<String>, String> {
+        let output = Command::new("security")
+            .args(["find-generic-password", "-s", SERVICE, "-a", ACCOUNT, "-w"])
+            .output()
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+        match output.status.code() {
+            Some(0) => Ok(Some(
+                String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            )),
+            Some(ITEM_NOT_FOUND) => Ok(None),
+            _ => Err(format!(
+                "Failed to read the content key from the keychain: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+
+    pub fn save(key: &str) -> Result<(), String> {
+        // Passed on stdin in interactive mode so the key doesn't show up in
+        // the process list
+        let mut child = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(
+                stdin,
+                "add-generic-password -U -s {} -a {} -w {}",
+                SERVICE, ACCOUNT, key
+            )
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+        if output.status.success() && output.stderr.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Failed to save the content key to the keychain: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod native {
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_NOT_FOUND};
+    use windows_sys::Win32::Security::Credentials::{
+        CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    use super::{ACCOUNT, SERVICE};
+
+    fn target() -> Vec<u16> {
+        format!("{}/{}", SERVICE, ACCOUNT)
+            .encode_utf16()
+            .chain(Some(0))
+            .collect()
+    }
+
+    pub fn load() -> Result<Option<String>, String> {
+        let target = target();
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        // SAFETY: `target` is NUL-terminated and `credential` is freed below
+        unsafe {
+            if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) == 0 {
+                return match GetLastError() {
+                    ERROR_NOT_FOUND => Ok(None),
+                    code => Err(format!(
+                        "Failed to read the content key from the Credential Manager (error {})",
+                        code
+                    )),
+                };
+            }
+            let blob = std::slice::from_raw_parts(
+                (*credential).CredentialBlob,
+                (*credential).CredentialBlobSize as usize,
+            );
+            let key = String::from_utf8(blob.to_vec());
+            CredFree(credential as *const _);
+            key.map(Some)
+                .map_err(|_| "The stored content key is invalid".to_string())
+        }
+    }
+
+    pub fn save(key: &str) -> Result<(), String> {
+        let target = target();
+        let user: Vec<u16> = ACCOUNT.encode_utf16().chain(Some(0)).collect();
+        let mut blob = key.as_bytes().to_vec();
+        // SAFETY: every pointer outlives the call; the struct is zeroed
+        // apart from the fields set here
+        unsafe {
+            let mut credential: CREDENTIALW = std::mem::zeroed();
+            credential.Type = CRED_TYPE_GENERIC;
+            credential.TargetName = target.as_ptr() as *mut _;
+            credential.UserName = user.as_ptr() as *mut _;
+            credential.CredentialBlobSize = blob.len() as u32;
+            credential.CredentialBlob = blob.as_mut_ptr();
+            credential.Persist = CRED_PERSIST_LOCAL_MACHINE;
+            if CredWriteW(&credential, 0) == 0 {
+                return Err(format!(
+                    "Failed to save the content key to the Credential Manager (error {})",
+                    GetLastError()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod native {
+    pub fn load() -> Result<Option<String>, String> {
+        Err("No keychain is supported on this platform".to_string())
+    }
+
+    pub fn save(_key: &str) -> Result<(), String> {
+        Err("No keychain is supported on this platform".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        key: Mutex<Option<String>>,
+        saves: Mutex<u32>,
+    }
+
+    impl KeyStore for MemoryStore {
+        fn load(&self) -> Result<Option<String>, String> {
+            Ok(self.key.lock().unwrap().clone())
+        }
+
+        fn save(&self, key: &str) -> Result<(), String> {
+            *self.saves.lock().unwrap() += 1;
+            *self.key.lock().unwrap() = Some(key.to_string());
+            Ok(())
+        }
+    }
+
+    struct LockedStore;
+
+    impl KeyStore for LockedStore {
+        fn load(&self) -> Result<Option<String>, String> {
+            Err("The default keyring is locked".to_string())
+        }
+
+        fn save(&self, _key: &str) -> Result<(), String> {
+            Err("The default keyring is locked".to_string())
+        }
+    }
+
+    #[test]
+    fn test_key_is_created_once() {
+        let store = MemoryStore::default();
+
+        let first = load_or_create(&store).unwrap();
+        let second = load_or_create(&store).unwrap();
+
+        assert_eq!(*store.saves.lock().unwrap(), 1);
+        let sealed = first.seal("secret").unwrap();
+        assert_eq!(second.open(&sealed).unwrap(), "secret");
+    }
+
+    #[test]
+    fn test_load_existing_does_not_create() {
+        let store = MemoryStore::default();
+
+        assert!(load_existing(&store).unwrap().is_none());
+        assert_eq!(*store.saves.lock().unwrap(), 0);
+
+        load_or_create(&store).unwrap();
+        assert!(load_existing(&store).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_invalid_stored_key() {
+        let store = MemoryStore::default();
+        store.save(&STANDARD.encode([0u8; 16])).unwrap();
+
+        assert!(load_or_create(&store).is_err());
+    }
+
+    #[test]
+    fn test_locked_keychain_is_an_error() {
+        assert_eq!(
+            load_or_create(&LockedStore).err(),
+            Some("The default keyring is locked".to_string())
+        );
+    }
+}
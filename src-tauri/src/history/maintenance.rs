@@ -0,0 +1,184 @@
+//! History database statistics and maintenance.
+//!
+//! Stats are computed with aggregate queries, SQLite pragmas and `dbstat`,
+//! so no rows are loaded into memory regardless of how large the history is.
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use super::types::HistoryError;
+
+/// `(phase, sql)` pairs, in the order [`optimize`] runs them.
+const MAINTENANCE_STEPS: [(&str, &str); 3] = [
+    ("optimize", "PRAGMA optimize"),
+    ("reindex", "REINDEX"),
+    ("vacuum", "VACUUM"),
+];
+
+/// Size and content summary of the history database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryStats {
+    /// Number of conversations
+    pub conversation_count: i64,
+    /// Number of messages
+    pub message_count: i64,
+    /// Creation time of the oldest conversation (Unix ms)
+    pub oldest_at: Option<i64>,
+    /// Last update of the most recent conversation (Unix ms)
+    pub newest_at: Option<i64>,
+    /// Size of the database file in bytes (`page_count * page_size`)
+    pub file_size_bytes: i64,
+    /// Size of the full-text search index in bytes: `message_search`, its
+    /// index and the `messages_fts` tables
+    pub fts_index_bytes: i64,
+}
+
+/// Payload of the `db-maintenance-progress` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MaintenanceProgress {
+    /// Phase that is about to run: `"optimize"`, `"reindex"`, or `"vacuum"`
+    pub phase: &'static str,
+    /// 1-based index of the phase
+    pub step: usize,
+    /// Number of phases
+    pub total: usize,
+}
+
+/// Collect [`HistoryStats`].
+pub async fn stats(pool: &SqlitePool) -> Result<HistoryStats, HistoryError> {
+    let (conversation_count, message_count, oldest_at, newest_at): (
+        i64,
+        i64,
+        Option<i64>,
+        Option<i64>,
+    ) = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM conversations),
+                (SELECT COUNT(*) FROM messages),
+                (SELECT MIN(created_at) FROM conversations),
+                (SELECT MAX(updated_at) FROM conversations)",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+        .fetch_one(pool)
+        .await?;
+    let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+        .fetch_one(pool)
+        .await?;
+
+    let fts_index_bytes: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(pgsize), 0) FROM dbstat WHERE name IN (
+             SELECT name FROM sqlite_master
+             WHERE tbl_name = 'message_search' OR tbl_name LIKE 'messages\\_fts%' ESCAPE '\\')",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(HistoryStats {
+        conversation_count,
+        message_count,
+        oldest_at,
+        newest_at,
+        file_size_bytes: page_count * page_size,
+        fts_index_bytes,
+    })
+}
+
+/// Run `PRAGMA optimize`, `REINDEX` and `VACUUM`.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `on_phase` - Called before each phase starts
+pub async fn optimize(
+    pool: &SqlitePool,
+    mut on_phase: impl FnMut(MaintenanceProgress),
+) -> Result<(), HistoryError> {
+    for (i, (phase, sql)) in MAINTENANCE_STEPS.iter().enumerate() {
+        on_phase(MaintenanceProgress {
+            phase,
+            step: i + 1,
+            total: MAINTENANCE_STEPS.len(),
+        });
+        sqlx::query(sql).execute(pool).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::store;
+    use crate::history::types::MessageMetadata;
+
+    /// 3 conversations with 100 messages each.
+    async fn seed(pool: &SqlitePool) {
+        for c in 0..3 {
+            let conversation = store::create_conversation(pool, None, 1_000 + c)
+                .await
+                .unwrap();
+            for m in 0..100 {
+                let role = if m % 2 == 0 { "user" } else { "assistant" };
+                store::append_message(
+                    pool,
+                    &conversation.id,
+                    role,
+                    &"lorem ipsum ".repeat(20),
+                    &MessageMetadata::default(),
+                    &[],
+                    10_000 + c * 1_000 + m,
+                )
+                .await
+                .unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stats_on_empty_database() {
+        let db = Db::in_memory().await.unwrap();
+
+        let stats = stats(db.pool()).await.unwrap();
+
+        assert_eq!(stats.conversation_count, 0);
+        assert_eq!(stats.message_count, 0);
+        assert_eq!(stats.oldest_at, None);
+        assert_eq!(stats.newest_at, None);
+        assert!(stats.file_size_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn test_stats_on_seeded_database() {
+        let db = Db::in_memory().await.unwrap();
+        let empty_size = stats(db.pool()).await.unwrap().file_size_bytes;
+        seed(db.pool()).await;
+
+        let stats = stats(db.pool()).await.unwrap();
+
+        assert_eq!(stats.conversation_count, 3);
+        assert_eq!(stats.message_count, 300);
+        assert_eq!(stats.oldest_at, Some(1_000));
+        assert_eq!(stats.newest_at, Some(12_099));
+        assert!(stats.file_size_bytes > empty_size);
+        assert!(stats.fts_index_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn test_optimize_reports_phases_and_keeps_data() {
+        let db = Db::in_memory().await.unwrap();
+        seed(db.pool()).await;
+
+        let mut phases = Vec::new();
+        optimize(db.pool(), |progress| phases.push(progress))
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = phases.iter().map(|p| p.phase).collect();
+        assert_eq!(names, ["optimize", "reindex", "vacuum"]);
+        assert_eq!(phases[2].step, 3);
+        assert_eq!(phases[2].total, 3);
+        assert_eq!(stats(db.pool()).await.unwrap().message_count, 300);
+    }
+}
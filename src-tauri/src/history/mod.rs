@@ -0,0 +1,1165 @@
+//! Chat history feature module.
+//!
+//! Conversation and message access as Tauri commands, so the frontend no
+//! longer builds SQL strings against `history.db`. IDs (UUIDv7) and
+//! timestamps are generated here rather than trusted from the caller.
+//!
+//! # Architecture
+//!
+//! - [`types`] - Conversation/message rows and `HistoryError`
+//! - [`store`] - SQL queries over the shared [`Db`] pool
+//! - [`archive`] - Zip export/import of the whole history
+//! - [`titles`] - LLM-generated conversation titles
+//! - [`regenerate`] - Regenerated replies and their lineage
+//! - [`branch`] - Copying a conversation up to a message into a new one
+//! - [`tags`] - Conversation tags
+//! - [`drafts`] - Unsent prompt text kept across hiding the launcher
+//! - [`prompt_history`] - Submitted prompts for up-arrow recall
+//! - [`wipe`] - Two-step "delete all history"
+//! - [`maintenance`] - Database statistics and optimization
+//! - [`activity`] - Per-day message counts and response latency
+//! - [`backup`] - Database file backups, rotation, and restore
+//! - [`relocate`] - Moving the database file elsewhere
+//! - [`search`] - Message search with filters
+//! - [`crypto`] - Sealing message content with the content key
+//! - [`keychain`] - The content key in the OS keychain
+//! - [`encryption`] - Encrypting and decrypting existing messages
+//! - [`size`] - The `history.max_db_size_mb` warning and pruning
+//! - [`retention`] - The daily maintenance pass (`history.retention_days`, ...)
+//! - This file - Tauri commands
+//!
+//! # Frontend Integration
+//!
+//! ```typescript
+//! const conversation = await invoke<Conversation>('create_conversation', { title: null });
+//! const page = await invoke<ConversationSummary[]>('list_conversations', {
+//!   limit: 50,
+//!   offset: 0,
+//!   includeArchived: false,
+//!   tag: null, // e.g. 'work'
+//! });
+//! const full = await invoke<ConversationWithMessages>('get_conversation', { id: conversation.id });
+//! // "Continue" button; null when there is no active conversation
+//! const last = await invoke<ConversationWithMessages | null>('get_last_conversation', {
+//!   messageLimit: 20,
+//! });
+//! // With `general.restore_last_conversation` on, sent each time the launcher is shown
+//! await listen<RestoreConversation>('restore-conversation', ({ payload }) => {
+//!   openConversation(payload.conversation_id);
+//! });
+//! await invoke('rename_conversation', { id: conversation.id, title: 'Rust lifetimes' });
+//! await invoke('set_conversation_pinned', { id: conversation.id, pinned: true });
+//! await invoke('set_conversation_archived', { id: conversation.id, archived: true });
+//! // Persona for this conversation only; null goes back to the global prompt
+//! await invoke('set_conversation_system_prompt', { id: conversation.id, prompt: 'You are a regex golfer.' });
+//!
+//! // Tags are normalized ("Rust " and "rust" are one tag) and disappear when unused
+//! await invoke<string>('add_tag', { conversationId: conversation.id, tag: 'Rust' });
+//! await invoke('remove_tag', { conversationId: conversation.id, tag: 'rust' });
+//! const tags = await invoke<TagWithCount[]>('list_tags');
+//!
+//! // Trash: delete is reversible for 30 days
+//! await invoke('delete_conversation', { id: conversation.id });
+//! const trash = await invoke<ConversationSummary[]>('list_trashed_conversations');
+//! await invoke('restore_conversation', { id: conversation.id });
+//! await invoke('purge_conversation', { id: conversation.id }); // permanent
+//!
+//! const message = await invoke<Message>('append_message', {
+//!   conversationId: conversation.id,
+//!   role: 'user',
+//!   content: 'What is a lifetime?',
+//!   metadata: null,
+//!   attachments: droppedFiles, // FileContent[] from read_file_for_prompt, or null
+//! });
+//! const files = await invoke<Attachment[]>('get_message_attachments', { messageId: message.id });
+//! // Infinite scroll: newest first, then pass the oldest created_at as `before`
+//! const older = await invoke<Message[]>('get_messages', {
+//!   conversationId: conversation.id,
+//!   before: oldest?.created_at ?? null,
+//!   limit: 50,
+//!   includeSuperseded: false, // true returns every regenerated version
+//! });
+//!
+//! // "Regenerate" button: the old reply gets `superseded_by` set
+//! const reply = await invoke<Message>('regenerate_message', { assistantMessageId: message.id });
+//!
+//! // "Branch from here": a copy up to and including the message, titled "... (branch)";
+//! // `branched_from_conversation_id` / `branched_from_message_id` link back to the source
+//! const branch = await invoke<Conversation>('branch_conversation', {
+//!   conversationId: conversation.id,
+//!   fromMessageId: message.id,
+//! });
+//!
+//! // Drafts: save on hide (also when empty), restored after `launcher-shown`
+//! await listen('launcher-hidden', () =>
+//!   invoke('save_draft', { conversationId: current?.id ?? null, text: input.value }));
+//! await listen<Draft>('draft-restored', ({ payload }) => {
+//!   // { conversation_id: '0192...' | null, text: 'How do I', updated_at: 1717... }
+//!   input.value = payload.text;
+//! });
+//! const draft = await invoke<Draft | null>('get_draft', { conversationId: null });
+//!
+//! // Up arrow: the newest submitted prompt starting with what's typed so far
+//! const [previous] = await invoke<PromptHistoryEntry[]>('get_prompt_history', {
+//!   limit: 1,
+//!   prefixFilter: input.value, // or null
+//! });
+//! // { id: 42, text: 'git status', source: 'typed', created_at: 1717... }
+//! await invoke<number>('clear_prompt_history');
+//!
+//! // Search; no text with filters lists what the filters match
+//! const results = await invoke<SearchResults>('search_history', {
+//!   query: 'docker',
+//!   filters: { after: Date.now() - 14 * DAY_MS, model: 'gpt-4o' }, // or null
+//!   limit: 20,
+//!   offset: 0,
+//! });
+//! // { hits: [{ ...message, conversation_title }], total: 3, applied_filters: ['query', 'after', 'model'] }
+//!
+//! // "Saved" view
+//! await invoke('set_message_starred', { messageId: message.id, starred: true });
+//! const saved = await invoke<StarredMessage[]>('list_starred_messages', { limit: 50, offset: 0 });
+//!
+//! // After the first reply; skipped once the user has renamed the conversation
+//! await listen<TitleUpdated>('conversation-title-updated', ({ payload }) => {
+//!   sidebar.rename(payload.conversation_id, payload.title);
+//! });
+//! await invoke<string | null>('generate_conversation_title', { conversationId: conversation.id });
+//!
+//! // Backup and restore; progress arrives as { done, total }
+//! await listen<HistoryProgress>('history-import-progress', ({ payload }) => {
+//!   progressBar.value = payload.done / payload.total;
+//! });
+//! await invoke<ExportManifest>('export_all_history', { path: '/backups/qwik-ask.zip' });
+//! await invoke<ImportSummary>('import_history', { path: '/backups/qwik-ask.zip', strategy: 'skip' });
+//!
+//! // Delete everything: the token is valid for 30 seconds and single-use
+//! const token = await invoke<string>('request_history_wipe');
+//! if (await confirm('Delete all conversations?')) {
+//!   await invoke('confirm_history_wipe', { token });
+//! }
+//!
+//! // Settings "Storage" tab
+//! const stats = await invoke<HistoryStats>('get_history_stats');
+//! await listen<MaintenanceProgress>('db-maintenance-progress', ({ payload }) => {
+//!   status.text = `${payload.phase} (${payload.step}/${payload.total})`;
+//! });
+//! await invoke('optimize_history_db');
+//!
+//! // Usage chart: one entry per UTC day, oldest first, including empty days
+//! const activity = await invoke<ActivityStats>('get_activity_stats', { days: 30 });
+//!
+//! // Backups; also taken automatically every `history.auto_backup_interval_days`
+//! const file = await invoke<string>('backup_history_db', { path: null });
+//! const backups = await invoke<BackupInfo[]>('list_backups');
+//! await invoke('restore_backup', { path: backups[0].path }); // emits `history-restored`
+//!
+//! // Move the database to another drive; reload the plugin connection after
+//! await listen<DatabaseMoved>('history-db-moved', ({ payload }) => reloadDb(payload.database_url));
+//! const path = await invoke<string>('move_history_db', { newPath: 'D:\\QwikAsk' });
+//!
+//! // Saving `history.encrypt_content` migrates existing messages in the background
+//! await listen<EncryptionProgress>('history-encryption-progress', ({ payload }) => {
+//!   // { encrypting: true, done: 200, total: 1500, finished: false, error: null }
+//!   progressBar.value = payload.done / payload.total;
+//! });
+//! const { search_available } = await invoke<HistoryCapabilities>('get_history_capabilities');
+//!
+//! // Checked hourly against `history.max_db_size_mb`
+//! await listen<SizeWarning>('history-size-warning', ({ payload }) => {
+//!   // { size_bytes, limit_bytes, pruned_conversations: 0,
+//!   //   suggested_actions: ['empty_trash', 'enable_auto_prune', 'export_and_delete', 'raise_limit'] }
+//!   showStorageBanner(payload);
+//! });
+//!
+//! // Daily, and right away with `enforce_history_retention`
+//! await listen<MaintenanceSummary>('maintenance-completed', ({ payload }) => {
+//!   // { trigger: 'scheduled', expired_conversations: 12, purged_conversations: 3,
+//!   //   deleted_drafts: 1, pruned_conversations: 0, failed_steps: [], finished_at }
+//! });
+//! const summary = await invoke<MaintenanceSummary>('enforce_history_retention');
+//!
+//! // Errors are tagged: { kind: 'not_found', detail: '<id>' }
+//! ```
+
+pub mod activity;
+pub mod archive;
+pub mod backup;
+pub mod branch;
+pub mod crypto;
+pub mod drafts;
+pub mod encryption;
+pub mod keychain;
+pub mod maintenance;
+pub mod prompt_history;
+pub mod regenerate;
+pub mod relocate;
+pub mod retention;
+pub mod search;
+pub mod size;
+pub mod store;
+pub mod tags;
+pub mod titles;
+pub mod types;
+pub mod wipe;
+
+pub use types::{
+    Attachment, Conversation, ConversationSummary, ConversationWithMessages, HistoryError, Message,
+    MessageMetadata, NewAttachment, RestoreConversation, StarredMessage, TagWithCount,
+};
+
+use std::path::Path;
+
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use activity::ActivityStats;
+use archive::{ExportManifest, ImportSummary, MergeStrategy};
+use backup::{BackupInfo, ChangeTracker};
+use drafts::{Draft, DraftTracker};
+use encryption::HistoryCapabilities;
+use maintenance::HistoryStats;
+use prompt_history::{PromptHistoryEntry, PromptSource};
+use relocate::DatabaseMoved;
+use retention::{
+    DbSteps, MaintenanceScheduler, MaintenanceSummary, MaintenanceTrigger, SystemClock,
+};
+use search::{SearchFilters, SearchResults};
+
+use crate::clipboard::{self, CopyFormat};
+use crate::db::{now_ms, Db};
+use crate::llm::client::HttpClient;
+use crate::llm::{self, ResponseCache};
+use crate::paths;
+use crate::prompts;
+use crate::settings::SettingsManager;
+use crate::snooze;
+use titles::TitleUpdated;
+use wipe::WipeGuard;
+
+/// Create a new, empty conversation.
+///
+/// # Arguments
+///
+/// * `title` - Optional title; defaults to "New conversation"
+#[tauri::command]
+pub async fn create_conversation(
+    db: State<'_, Db>,
+    title: Option<String>,
+) -> Result<Conversation, HistoryError> {
+    store::create_conversation(db.pool(), title, now_ms()).await
+}
+
+/// List conversations, pinned first and then most recently updated, with
+/// message count and a snippet of the last message.
+///
+/// # Arguments
+///
+/// * `limit` - Maximum number of conversations to return
+/// * `offset` - Number of conversations to skip
+/// * `include_archived` - Also return archived conversations (default `false`)
+/// * `tag` - Only return conversations with this tag
+#[tauri::command]
+pub async fn list_conversations(
+    db: State<'_, Db>,
+    limit: u32,
+    offset: u32,
+    include_archived: Option<bool>,
+    tag: Option<String>,
+) -> Result<Vec<ConversationSummary>, HistoryError> {
+    store::list_conversations(
+        db.pool(),
+        limit,
+        offset,
+        include_archived.unwrap_or(false),
+        tag.as_deref(),
+    )
+    .await
+}
+
+/// Tag a conversation.
+///
+/// # Returns
+///
+/// The normalized tag name (trimmed, lowercase).
+///
+/// * `Err(HistoryError::InvalidTag)` - Empty or too long
+#[tauri::command]
+pub async fn add_tag(
+    db: State<'_, Db>,
+    conversation_id: String,
+    tag: String,
+) -> Result<String, HistoryError> {
+    tags::add_tag(db.pool(), &conversation_id, &tag).await
+}
+
+/// Remove a tag from a conversation; unused tags are deleted.
+#[tauri::command]
+pub async fn remove_tag(
+    db: State<'_, Db>,
+    conversation_id: String,
+    tag: String,
+) -> Result<(), HistoryError> {
+    tags::remove_tag(db.pool(), &conversation_id, &tag).await
+}
+
+/// List tags with the number of conversations (outside the trash) using them.
+#[tauri::command]
+pub async fn list_tags(db: State<'_, Db>) -> Result<Vec<TagWithCount>, HistoryError> {
+    tags::list_tags(db.pool()).await
+}
+
+/// Get a conversation with all of its messages.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::NotFound)` - No conversation with this ID
+#[tauri::command]
+pub async fn get_conversation(
+    db: State<'_, Db>,
+    id: String,
+) -> Result<ConversationWithMessages, HistoryError> {
+    store::get_conversation(db.pool(), &id).await
+}
+
+/// Start a new conversation from a copy of another one, up to and including
+/// a message.
+///
+/// # Arguments
+///
+/// * `conversation_id` - Conversation to branch
+/// * `from_message_id` - Last message to copy
+///
+/// # Returns
+///
+/// * `Ok(Conversation)` - The branch, with `branched_from_*` set
+/// * `Err(HistoryError::NotFound)` - No conversation with this ID
+/// * `Err(HistoryError::MessageNotFound)` - No such message in the conversation
+#[tauri::command]
+pub async fn branch_conversation(
+    db: State<'_, Db>,
+    conversation_id: String,
+    from_message_id: String,
+) -> Result<Conversation, HistoryError> {
+    branch::branch_conversation(db.pool(), &conversation_id, &from_message_id, now_ms()).await
+}
+
+/// Load the most recently updated conversation with its last messages.
+///
+/// Archived and trashed conversations are skipped.
+///
+/// # Arguments
+///
+/// * `message_limit` - How many of the newest messages to include
+///
+/// # Returns
+///
+/// * `None` - There is no active conversation
+#[tauri::command]
+pub async fn get_last_conversation(
+    db: State<'_, Db>,
+    message_limit: u32,
+) -> Result<Option<ConversationWithMessages>, HistoryError> {
+    store::get_last_conversation(db.pool(), message_limit).await
+}
+
+/// Emit `restore-conversation` for the most recent conversation.
+///
+/// Called whenever the launcher is shown. Does nothing when
+/// `general.restore_last_conversation` is off or there is no active
+/// conversation. The lookup runs in the background so showing the window
+/// is never delayed.
+pub fn restore_last_conversation(app: &AppHandle) {
+    let enabled = app
+        .state::<SettingsManager>()
+        .snapshot()
+        .map(|settings| settings.general.restore_last_conversation)
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let db = app.state::<Db>();
+        match store::get_last_conversation(db.pool(), 0).await {
+            Ok(Some(last)) => {
+                let _ = app.emit(
+                    "restore-conversation",
+                    RestoreConversation {
+                        conversation_id: last.conversation.id,
+                    },
+                );
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, "Failed to load last conversation"),
+        }
+    });
+}
+
+/// Generate a short title for a conversation with the configured LLM.
+///
+/// Uses `llm.title_model` (or a small model for the provider) and falls
+/// back to truncating the first prompt if the call fails. Emits
+/// `conversation-title-updated` when the title changes.
+///
+/// # Returns
+///
+/// * `Some(title)` - The new title
+/// * `None` - Skipped because the user renamed the conversation or it has
+///   no messages yet
+#[tauri::command]
+pub async fn generate_conversation_title(
+    app: AppHandle,
+    settings_manager: State<'_, SettingsManager>,
+    db: State<'_, Db>,
+    conversation_id: String,
+) -> Result<Option<String>, HistoryError> {
+    let settings = settings_manager.snapshot()?;
+    let title = titles::generate_title(
+        &llm::limited(&app, &HttpClient::new(&settings.network)?),
+        db.pool(),
+        &settings.llm,
+        &conversation_id,
+    )
+    .await?;
+
+    if let Some(title) = &title {
+        let _ = app.emit(
+            "conversation-title-updated",
+            TitleUpdated {
+                conversation_id,
+                title: title.clone(),
+            },
+        );
+    }
+    Ok(title)
+}
+
+/// Rename a conversation.
+///
+/// Renamed conversations keep their title; automatic titles are skipped.
+#[tauri::command]
+pub async fn rename_conversation(
+    db: State<'_, Db>,
+    id: String,
+    title: String,
+) -> Result<(), HistoryError> {
+    store::rename_conversation(db.pool(), &id, &title).await
+}
+
+/// Give a conversation its own system prompt, used instead of the active
+/// library prompt and `llm.system_prompt`.
+///
+/// # Arguments
+///
+/// * `id` - Conversation to change
+/// * `prompt` - The system prompt; `None` (or blank) reverts to the global one
+#[tauri::command]
+pub async fn set_conversation_system_prompt(
+    db: State<'_, Db>,
+    id: String,
+    prompt: Option<String>,
+) -> Result<(), HistoryError> {
+    store::set_system_prompt(db.pool(), &id, prompt.as_deref()).await
+}
+
+/// Save the unsent prompt text of a conversation, replacing its draft.
+///
+/// Call on `launcher-hidden`, also with empty text, so the next
+/// `draft-restored` matches what was on screen.
+///
+/// # Arguments
+///
+/// * `conversation_id` - Conversation being typed into; `None` for a new one
+/// * `text` - The input's content; blank deletes the draft
+#[tauri::command]
+pub async fn save_draft(
+    db: State<'_, Db>,
+    tracker: State<'_, DraftTracker>,
+    conversation_id: Option<String>,
+    text: String,
+) -> Result<(), HistoryError> {
+    drafts::save_draft(db.pool(), conversation_id.as_deref(), &text, now_ms()).await?;
+    tracker.record(conversation_id.as_deref());
+    Ok(())
+}
+
+/// Get the unsent prompt text of a conversation.
+///
+/// # Arguments
+///
+/// * `conversation_id` - Conversation to look up; `None` for a new one
+#[tauri::command]
+pub async fn get_draft(
+    db: State<'_, Db>,
+    conversation_id: Option<String>,
+) -> Result<Option<Draft>, HistoryError> {
+    drafts::get_draft(db.pool(), conversation_id.as_deref()).await
+}
+
+/// Get submitted prompts, newest first, for up-arrow recall.
+///
+/// Empty while `history.enabled` is off.
+///
+/// # Arguments
+///
+/// * `limit` - Maximum number of prompts to return
+/// * `prefix_filter` - Only return prompts starting with this text
+#[tauri::command]
+pub async fn get_prompt_history(
+    db: State<'_, Db>,
+    settings_manager: State<'_, SettingsManager>,
+    limit: u32,
+    prefix_filter: Option<String>,
+) -> Result<Vec<PromptHistoryEntry>, HistoryError> {
+    if !settings_manager.snapshot()?.history.enabled {
+        return Ok(Vec::new());
+    }
+    prompt_history::list(db.pool(), limit, prefix_filter.as_deref()).await
+}
+
+/// Delete every recorded prompt.
+///
+/// # Returns
+///
+/// Number of prompts deleted.
+#[tauri::command]
+pub async fn clear_prompt_history(db: State<'_, Db>) -> Result<u64, HistoryError> {
+    prompt_history::clear(db.pool()).await
+}
+
+/// Record a submitted prompt for up-arrow recall (see [`prompt_history`]).
+///
+/// Failures are only logged; they shouldn't fail the request.
+pub async fn record_prompt(app: &AppHandle, text: &str, source: PromptSource) {
+    let settings = match app.state::<SettingsManager>().snapshot() {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to record prompt");
+            return;
+        }
+    };
+    let pool = app.state::<Db>().pool().clone();
+    if let Err(e) = prompt_history::record(&pool, &settings.history, text, source, now_ms()).await {
+        tracing::warn!(error = %e, "Failed to record prompt");
+    }
+}
+
+/// Pin or unpin a conversation.
+#[tauri::command]
+pub async fn set_conversation_pinned(
+    db: State<'_, Db>,
+    id: String,
+    pinned: bool,
+) -> Result<(), HistoryError> {
+    store::set_pinned(db.pool(), &id, pinned).await
+}
+
+/// Archive or unarchive a conversation.
+///
+/// Archived conversations are hidden from `list_conversations` unless
+/// `include_archived` is set, and are unarchived when a message is added.
+#[tauri::command]
+pub async fn set_conversation_archived(
+    db: State<'_, Db>,
+    id: String,
+    archived: bool,
+) -> Result<(), HistoryError> {
+    store::set_archived(db.pool(), &id, archived).await
+}
+
+/// Move a conversation to the trash.
+///
+/// Trashed conversations are hidden from `list_conversations` and purged
+/// automatically after 30 days; until then they can be restored.
+#[tauri::command]
+pub async fn delete_conversation(db: State<'_, Db>, id: String) -> Result<(), HistoryError> {
+    store::delete_conversation(db.pool(), &id, now_ms()).await
+}
+
+/// List conversations in the trash, most recently trashed first.
+#[tauri::command]
+pub async fn list_trashed_conversations(
+    db: State<'_, Db>,
+) -> Result<Vec<ConversationSummary>, HistoryError> {
+    store::list_trashed_conversations(db.pool()).await
+}
+
+/// Restore a conversation from the trash.
+#[tauri::command]
+pub async fn restore_conversation(db: State<'_, Db>, id: String) -> Result<(), HistoryError> {
+    store::restore_conversation(db.pool(), &id).await
+}
+
+/// Permanently delete a conversation and its messages.
+#[tauri::command]
+pub async fn purge_conversation(db: State<'_, Db>, id: String) -> Result<(), HistoryError> {
+    store::purge_conversation(db.pool(), &id).await
+}
+
+/// Append a message to a conversation.
+///
+/// # Arguments
+///
+/// * `conversation_id` - Parent conversation
+/// * `role` - `"user"` or `"assistant"`
+/// * `content` - Message text
+/// * `metadata` - Optional provider/model/token details
+/// * `attachments` - Files added to the prompt, recorded with the message
+///
+/// # Returns
+///
+/// * `Err(HistoryError::InvalidRole)` - Unknown role
+/// * `Err(HistoryError::NotFound)` - The conversation does not exist
+#[tauri::command]
+pub async fn append_message(
+    db: State<'_, Db>,
+    conversation_id: String,
+    role: String,
+    content: String,
+    metadata: Option<MessageMetadata>,
+    attachments: Option<Vec<NewAttachment>>,
+) -> Result<Message, HistoryError> {
+    store::append_message(
+        db.pool(),
+        &conversation_id,
+        &role,
+        &content,
+        &metadata.unwrap_or_default(),
+        &attachments.unwrap_or_default(),
+        now_ms(),
+    )
+    .await
+}
+
+/// List the files recorded with a message, in the order they were added.
+///
+/// # Arguments
+///
+/// * `message_id` - Message to look up
+#[tauri::command]
+pub async fn get_message_attachments(
+    db: State<'_, Db>,
+    message_id: String,
+) -> Result<Vec<Attachment>, HistoryError> {
+    store::list_attachments(db.pool(), &message_id).await
+}
+
+/// Get a page of messages, newest first.
+///
+/// # Arguments
+///
+/// * `conversation_id` - Conversation to read
+/// * `before` - Return messages created before this timestamp (ms);
+///   `None` for the newest page
+/// * `limit` - Page size
+/// * `include_superseded` - Also return regenerated-away replies
+///   (default `false`)
+#[tauri::command]
+pub async fn get_messages(
+    db: State<'_, Db>,
+    conversation_id: String,
+    before: Option<i64>,
+    limit: u32,
+    include_superseded: Option<bool>,
+) -> Result<Vec<Message>, HistoryError> {
+    store::get_messages(
+        db.pool(),
+        &conversation_id,
+        before,
+        limit,
+        include_superseded.unwrap_or(false),
+    )
+    .await
+}
+
+/// Copy a message to the clipboard.
+///
+/// Reads the content from history, so the frontend doesn't send it back.
+///
+/// # Arguments
+///
+/// * `message_id` - Message to copy
+/// * `format` - How to put it on the clipboard
+#[tauri::command]
+pub async fn copy_message(
+    db: State<'_, Db>,
+    message_id: String,
+    format: CopyFormat,
+) -> Result<(), HistoryError> {
+    let message = store::get_message(db.pool(), &message_id).await?;
+    clipboard::copy(&message.content, format).await?;
+    Ok(())
+}
+
+/// Ask the LLM again for an assistant reply.
+///
+/// Sends the conversation up to the reply's user message, stores the new
+/// reply in its place, and marks the old one as superseded.
+///
+/// # Arguments
+///
+/// * `assistant_message_id` - Reply to regenerate; must not already be
+///   superseded
+#[tauri::command]
+pub async fn regenerate_message(
+    app: AppHandle,
+    settings_manager: State<'_, SettingsManager>,
+    cache: State<'_, ResponseCache>,
+    db: State<'_, Db>,
+    assistant_message_id: String,
+) -> Result<Message, HistoryError> {
+    let mut settings = settings_manager.load()?;
+    let conversation_id = store::get_message(db.pool(), &assistant_message_id)
+        .await?
+        .conversation_id;
+    settings.llm.system_prompt =
+        prompts::resolve_system_prompt(db.pool(), &settings.llm, Some(&conversation_id)).await;
+    regenerate::regenerate_message(
+        &llm::limited(&app, &HttpClient::new(&settings.network)?),
+        &cache,
+        db.pool(),
+        &settings.llm,
+        &assistant_message_id,
+        now_ms(),
+    )
+    .await
+}
+
+/// Export every conversation to a zip archive.
+///
+/// Emits `history-export-progress` after each conversation.
+///
+/// # Arguments
+///
+/// * `path` - Destination `.zip` file
+#[tauri::command]
+pub async fn export_all_history(
+    app: AppHandle,
+    db: State<'_, Db>,
+    path: String,
+) -> Result<ExportManifest, HistoryError> {
+    archive::export_all(db.pool(), Path::new(&path), now_ms(), |progress| {
+        let _ = app.emit("history-export-progress", progress);
+    })
+    .await
+}
+
+/// Import a zip archive written by `export_all_history`.
+///
+/// The import is all-or-nothing. Emits `history-import-progress` after
+/// each conversation.
+///
+/// # Arguments
+///
+/// * `path` - Archive to import
+/// * `strategy` - `"skip"`, `"overwrite"`, or `"duplicate"` for
+///   conversations that already exist
+#[tauri::command]
+pub async fn import_history(
+    app: AppHandle,
+    db: State<'_, Db>,
+    path: String,
+    strategy: MergeStrategy,
+) -> Result<ImportSummary, HistoryError> {
+    archive::import_all(db.pool(), Path::new(&path), strategy, |progress| {
+        let _ = app.emit("history-import-progress", progress);
+    })
+    .await
+}
+
+/// Start a history wipe.
+///
+/// # Returns
+///
+/// A confirmation token for `confirm_history_wipe`, valid for 30 seconds.
+#[tauri::command]
+pub fn request_history_wipe(guard: State<'_, WipeGuard>) -> String {
+    guard.issue(now_ms())
+}
+
+/// Delete all conversations and messages, and the cached answers.
+///
+/// Only runs with the token from the latest `request_history_wipe` call
+/// (single-use, 30 second lifetime). Emits `history-wiped` on success.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::InvalidToken)` - Wrong, reused, or expired token
+#[tauri::command]
+pub async fn confirm_history_wipe(
+    app: AppHandle,
+    guard: State<'_, WipeGuard>,
+    db: State<'_, Db>,
+    cache: State<'_, ResponseCache>,
+    token: String,
+) -> Result<(), HistoryError> {
+    guard.consume(&token, now_ms())?;
+    wipe::wipe_all(db.pool(), &cache).await?;
+    let _ = app.emit("history-wiped", ());
+    Ok(())
+}
+
+/// Get conversation/message counts and database size.
+#[tauri::command]
+pub async fn get_history_stats(db: State<'_, Db>) -> Result<HistoryStats, HistoryError> {
+    maintenance::stats(db.pool()).await
+}
+
+/// Get per-day message counts and response latency for the last `days`
+/// days (UTC, including today).
+///
+/// # Returns
+///
+/// One entry per day, or no days at all when `history.enabled` is off.
+#[tauri::command]
+pub async fn get_activity_stats(
+    settings_manager: State<'_, SettingsManager>,
+    db: State<'_, Db>,
+    days: u32,
+) -> Result<ActivityStats, HistoryError> {
+    if !settings_manager.snapshot()?.history.enabled {
+        return Ok(ActivityStats::empty(days));
+    }
+    activity::activity_stats(db.pool(), days, now_ms()).await
+}
+
+/// Optimize and compact the history database.
+///
+/// Runs `PRAGMA optimize`, `REINDEX` and `VACUUM`, emitting
+/// `db-maintenance-progress` before each phase.
+#[tauri::command]
+pub async fn optimize_history_db(app: AppHandle, db: State<'_, Db>) -> Result<(), HistoryError> {
+    maintenance::optimize(db.pool(), |progress| {
+        let _ = app.emit("db-maintenance-progress", progress);
+    })
+    .await
+}
+
+/// Star or unstar a message.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::MessageNotFound)` - No message with this ID
+#[tauri::command]
+pub async fn set_message_starred(
+    db: State<'_, Db>,
+    message_id: String,
+    starred: bool,
+) -> Result<(), HistoryError> {
+    store::set_message_starred(db.pool(), &message_id, starred).await
+}
+
+/// List starred messages, newest first, with their conversation titles.
+///
+/// # Arguments
+///
+/// * `limit` - Page size
+/// * `offset` - Number of messages to skip
+#[tauri::command]
+pub async fn list_starred_messages(
+    db: State<'_, Db>,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<StarredMessage>, HistoryError> {
+    store::list_starred_messages(db.pool(), limit, offset).await
+}
+
+/// Search messages, newest first, with the titles of their conversations.
+///
+/// # Arguments
+///
+/// * `query` - Text to find anywhere in a message; `None` or blank lists
+///   every message the filters match
+/// * `filters` - Time range, role, provider, model, tag or conversation
+///   to narrow the search to
+/// * `limit` - Page size
+/// * `offset` - Number of messages to skip
+///
+/// # Returns
+///
+/// The page, the number of matches on every page and the filters applied.
+#[tauri::command]
+pub async fn search_history(
+    db: State<'_, Db>,
+    query: Option<String>,
+    filters: Option<SearchFilters>,
+    limit: u32,
+    offset: u32,
+) -> Result<SearchResults, HistoryError> {
+    search::search(
+        db.pool(),
+        query.as_deref(),
+        &filters.unwrap_or_default(),
+        limit,
+        offset,
+    )
+    .await
+}
+
+/// Back up the history database.
+///
+/// Without a `path` the backup goes to the backups directory and older
+/// backups are rotated out according to `history.backup_keep_count`.
+///
+/// # Arguments
+///
+/// * `path` - Destination file, which must not exist yet
+///
+/// # Returns
+///
+/// Path of the written backup.
+#[tauri::command]
+pub async fn backup_history_db(
+    app: AppHandle,
+    settings_manager: State<'_, SettingsManager>,
+    tracker: State<'_, ChangeTracker>,
+    db: State<'_, Db>,
+    path: Option<String>,
+) -> Result<String, HistoryError> {
+    let path = match path {
+        Some(path) => {
+            backup::backup_to(db.pool(), Path::new(&path)).await?;
+            path.into()
+        }
+        None => {
+            let settings = settings_manager.snapshot()?;
+            let dir = backup::backups_dir(&app)?;
+            backup::backup_now(
+                db.pool(),
+                &tracker,
+                &dir,
+                settings.history.backup_keep_count,
+                now_ms(),
+            )
+            .await?
+        }
+    };
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// List backups in the backups directory, newest first.
+#[tauri::command]
+pub fn list_backups(app: AppHandle) -> Result<Vec<BackupInfo>, HistoryError> {
+    backup::list_backups(&backup::backups_dir(&app)?)
+}
+
+/// Replace the current history with a backup.
+///
+/// Emits `history-restored` on success.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::Busy)` - Other database operations are in flight
+/// * `Err(HistoryError::Backup)` - Missing, foreign, or too new backup file
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    db: State<'_, Db>,
+    path: String,
+) -> Result<(), HistoryError> {
+    backup::restore_from(db.pool(), Path::new(&path)).await?;
+    let _ = app.emit("history-restored", ());
+    Ok(())
+}
+
+/// Move the history database to another file, e.g. on a bigger drive.
+///
+/// Copies and checks the database, switches every backend connection to
+/// the copy and records it as `history.database_path` (see [`relocate`]).
+/// Emits `history-db-moved`; the frontend reloads its `tauri-plugin-sql`
+/// connection with the new URL. The old file is deleted at the next start,
+/// since the plugin's connection to it stays open until then.
+///
+/// # Arguments
+///
+/// * `new_path` - Absolute path of the new file, or of an existing
+///   directory to put `history.db` in
+///
+/// # Returns
+///
+/// * `Ok(String)` - Path of the database file now in use
+/// * `Err(HistoryError::Busy)` - Other database operations didn't finish
+/// * `Err(HistoryError::Relocate)` - The target is unusable or the copy
+///   couldn't be written or verified; nothing changed
+#[tauri::command]
+pub async fn move_history_db(
+    app: AppHandle,
+    db: State<'_, Db>,
+    settings_manager: State<'_, SettingsManager>,
+    tracker: State<'_, ChangeTracker>,
+    new_path: String,
+) -> Result<String, HistoryError> {
+    let target = relocate::target_file(&new_path)?;
+    let old = relocate::current_file(db.pool());
+    relocate::relocate(db.pool(), &target).await?;
+    let path = target.to_string_lossy().into_owned();
+
+    let saved = settings_manager
+        .update(|settings| {
+            settings.history.database_path = Some(path.clone());
+            Ok(())
+        })
+        .await;
+    if let Err(e) = saved {
+        // The next start would open the old file, so keep using it
+        relocate::point_at(db.pool(), &old).await?;
+        relocate::remove_database_files(&target);
+        return Err(e.into());
+    }
+    if let Err(e) = tracker.switch_to(&target).await {
+        tracing::warn!(error = %e, "Failed to move backup change tracker");
+    }
+    if let Err(e) = relocate::defer_removal(&settings_manager, &old) {
+        tracing::warn!(error = %e, "Failed to record old database file for removal");
+    }
+    tracing::info!(from = %old.display(), to = %path, "Moved history database");
+
+    let _ = app.emit(
+        relocate::MOVED_EVENT,
+        DatabaseMoved {
+            path: path.clone(),
+            database_url: paths::database_url(paths::mode(), Some(&path)),
+        },
+    );
+    Ok(path)
+}
+
+/// Get which history features are available with the current encryption
+/// settings.
+///
+/// Search is hidden while message content is encrypted, unless
+/// `history.search_encrypted` is on.
+#[tauri::command]
+pub fn get_history_capabilities(
+    settings_manager: State<'_, SettingsManager>,
+) -> Result<HistoryCapabilities, HistoryError> {
+    Ok(HistoryCapabilities::from_settings(
+        &settings_manager.snapshot()?.history,
+    ))
+}
+
+/// Run the history maintenance pass now: retention, trash purge, draft
+/// cleanup, size cap and `PRAGMA optimize` (see [`retention`]).
+///
+/// Waits for a scheduled pass in progress. Emits `maintenance-completed`.
+#[tauri::command]
+pub async fn enforce_history_retention(
+    app: AppHandle,
+    db: State<'_, Db>,
+    settings_manager: State<'_, SettingsManager>,
+    scheduler: State<'_, MaintenanceScheduler>,
+) -> Result<MaintenanceSummary, HistoryError> {
+    let settings = settings_manager.snapshot()?;
+    let steps = DbSteps {
+        pool: db.pool().clone(),
+    };
+    let summary = scheduler
+        .run(
+            &steps,
+            &SystemClock,
+            &settings.history,
+            MaintenanceTrigger::Manual,
+        )
+        .await;
+    let _ = app.emit(retention::COMPLETED_EVENT, summary.clone());
+    Ok(summary)
+}
+
+/// Run the history maintenance pass shortly after startup and then daily.
+///
+/// Started once from `lib.rs` setup; settings are re-read before every
+/// pass, and passes are skipped while snoozed.
+///
+/// # Returns
+///
+/// The task, to abort on shutdown.
+pub fn start_maintenance(app: &AppHandle) -> JoinHandle<()> {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let steps = DbSteps {
+            pool: app.state::<Db>().pool().clone(),
+        };
+        let settings = || {
+            if snooze::is_snoozed(&app) {
+                return None;
+            }
+            let settings = app.state::<SettingsManager>().snapshot().ok()?;
+            Some(settings.history.clone())
+        };
+        app.state::<MaintenanceScheduler>()
+            .run_schedule(&steps, &SystemClock, settings, |summary| {
+                let _ = app.emit(retention::COMPLETED_EVENT, summary);
+            })
+            .await;
+    })
+}
+
+/// Restart the wait for the next scheduled maintenance pass, after the
+/// settings it follows changed.
+pub fn reschedule_maintenance(app: &AppHandle) {
+    if let Some(scheduler) = app.try_state::<MaintenanceScheduler>() {
+        scheduler.reschedule();
+    }
+}
+
+/// Run the automatic backup check, then the database size check, every
+/// [`backup::CHECK_INTERVAL`].
+///
+/// Started once from `lib.rs` setup; settings are re-read on every check so
+/// changes apply without a restart.
+///
+/// # Returns
+///
+/// The task, to abort on shutdown.
+pub fn start_backup_scheduler(app: &AppHandle) -> JoinHandle<()> {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = scheduled_backup(&app).await {
+                tracing::error!(error = %e, "Automatic backup failed");
+            }
+            if let Err(e) = scheduled_size_check(&app).await {
+                tracing::warn!(error = %e, "History size check failed");
+            }
+            tokio::time::sleep(backup::CHECK_INTERVAL).await;
+        }
+    })
+}
+
+async fn scheduled_backup(app: &AppHandle) -> Result<(), HistoryError> {
+    if snooze::is_snoozed(app) {
+        return Ok(());
+    }
+    let settings = app.state::<SettingsManager>().snapshot()?;
+    let dir = backup::backups_dir(app)?;
+    backup::backup_if_due(
+        app.state::<Db>().pool(),
+        &app.state::<ChangeTracker>(),
+        &dir,
+        &settings.history,
+        now_ms(),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn scheduled_size_check(app: &AppHandle) -> Result<(), HistoryError> {
+    if snooze::is_snoozed(app) {
+        return Ok(());
+    }
+    let settings = app.state::<SettingsManager>().snapshot()?;
+    let pool = app.state::<Db>().pool().clone();
+    let path = relocate::current_file(&pool);
+    if let Some(warning) = size::check(&pool, &path, &settings.history).await? {
+        tracing::warn!(
+            size_bytes = warning.size_bytes,
+            limit_bytes = warning.limit_bytes,
+            pruned = warning.pruned_conversations,
+            "History database is over its size limit"
+        );
+        let _ = app.emit(size::WARNING_EVENT, warning);
+    }
+    Ok(())
+}
@@ -0,0 +1,351 @@
+//! Submitted prompts, recalled with the up arrow like a shell history.
+//!
+//! Every prompt sent with `ask_llm`, and every quick action answered
+//! outside the launcher, is recorded with where it came from. Sending the
+//! same prompt twice in a row keeps one entry, moved to the top. Only the
+//! newest `history.prompt_history_limit` entries are kept.
+//!
+//! Nothing is recorded while `history.enabled` is off, and, as with
+//! `HISTCONTROL=ignorespace`, a prompt starting with a space is never
+//! recorded. Prompts are sealed like message content when
+//! `history.encrypt_content` is on, so prefix matching happens after
+//! reading rather than in SQL.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use super::crypto;
+use super::types::HistoryError;
+use crate::settings::HistorySettings;
+
+/// Where a submitted prompt came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptSource {
+    /// Typed into the launcher
+    #[default]
+    Typed,
+    /// Prefilled from the clipboard or the selection
+    Clipboard,
+    /// Expanded from a quick action template
+    QuickAction,
+}
+
+impl PromptSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Typed => "typed",
+            Self::Clipboard => "clipboard",
+            Self::QuickAction => "quick_action",
+        }
+    }
+
+    fn from_stored(stored: &str) -> Self {
+        match stored {
+            "clipboard" => Self::Clipboard,
+            "quick_action" => Self::QuickAction,
+            _ => Self::Typed,
+        }
+    }
+}
+
+/// A recorded prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PromptHistoryEntry {
+    pub id: i64,
+    pub text: String,
+    pub source: PromptSource,
+    /// Unix timestamp (ms) of the last time it was submitted
+    pub created_at: i64,
+}
+
+/// Whether `text` may be recorded: not blank, and not marked sensitive
+/// with a leading space.
+pub fn is_recordable(text: &str) -> bool {
+    !text.trim().is_empty() && !text.starts_with(' ')
+}
+
+/// Record a submitted prompt.
+///
+/// # Returns
+///
+/// * `Ok(true)` - Recorded, or an identical newest entry moved to the top
+/// * `Ok(false)` - History is off, the limit is `0`, or the prompt is blank
+///   or starts with a space
+pub async fn record(
+    pool: &SqlitePool,
+    settings: &HistorySettings,
+    text: &str,
+    source: PromptSource,
+    now_ms: i64,
+) -> Result<bool, HistoryError> {
+    if !settings.enabled || settings.prompt_history_limit == 0 || !is_recordable(text) {
+        return Ok(false);
+    }
+
+    let mut tx = pool.begin().await?;
+    let newest = sqlx::query(
+        "SELECT id, text FROM prompt_history ORDER BY created_at DESC, id DESC LIMIT 1",
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    match newest {
+        Some(row) if crypto::open_stored(row.get("text")) == text => {
+            sqlx::query("UPDATE prompt_history SET source = ?, created_at = ? WHERE id = ?")
+                .bind(source.as_str())
+                .bind(now_ms)
+                .bind(row.get::<i64, _>("id"))
+                .execute(&mut *tx)
+                .await?;
+        }
+        _ => {
+            sqlx::query("INSERT INTO prompt_history (text, source, created_at) VALUES (?, ?, ?)")
+                .bind(crypto::seal_new(text)?)
+                .bind(source.as_str())
+                .bind(now_ms)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+    sqlx::query(
+        "DELETE FROM prompt_history WHERE id NOT IN (
+            SELECT id FROM prompt_history ORDER BY created_at DESC, id DESC LIMIT ?)",
+    )
+    .bind(settings.prompt_history_limit)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Recorded prompts, newest first.
+///
+/// # Arguments
+///
+/// * `limit` - Maximum number of entries to return
+/// * `prefix` - Only return prompts starting with this text (case-sensitive,
+///   like a shell); blank matches everything
+pub async fn list(
+    pool: &SqlitePool,
+    limit: u32,
+    prefix: Option<&str>,
+) -> Result<Vec<PromptHistoryEntry>, HistoryError> {
+    let prefix = prefix.unwrap_or_default();
+    let rows = sqlx::query(
+        "SELECT id, text, source, created_at FROM prompt_history
+         ORDER BY created_at DESC, id DESC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| PromptHistoryEntry {
+            id: row.get("id"),
+            text: crypto::open_stored(row.get("text")),
+            source: PromptSource::from_stored(row.get("source")),
+            created_at: row.get("created_at"),
+        })
+        .filter(|entry| entry.text.starts_with(prefix))
+        .take(limit as usize)
+        .collect())
+}
+
+/// Delete every recorded prompt.
+///
+/// # Returns
+///
+/// Number of entries deleted.
+pub async fn clear(pool: &SqlitePool) -> Result<u64, HistoryError> {
+    let result = sqlx::query("DELETE FROM prompt_history")
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+
+    fn settings(limit: u32) -> HistorySettings {
+        HistorySettings {
+            prompt_history_limit: limit,
+            ..HistorySettings::default()
+        }
+    }
+
+    fn texts(entries: &[PromptHistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.text.as_str()).collect()
+    }
+
+    async fn recorded(db: &Db, prompts: &[&str], limit: u32) {
+        for (i, prompt) in prompts.iter().enumerate() {
+            record(
+                db.pool(),
+                &settings(limit),
+                prompt,
+                PromptSource::Typed,
+                i as i64,
+            )
+            .await
+            .unwrap();
+        }
+    }
+
+    // ===== Recording =====
+
+    #[tokio::test]
+    async fn test_consecutive_repeats_are_deduplicated() {
+        let db = Db::in_memory().await.unwrap();
+        recorded(&db, &["ls -la", "git status", "git status"], 10).await;
+
+        record(
+            db.pool(),
+            &settings(10),
+            "git status",
+            PromptSource::Clipboard,
+            99,
+        )
+        .await
+        .unwrap();
+        let entries = list(db.pool(), 10, None).await.unwrap();
+
+        assert_eq!(texts(&entries), ["git status", "ls -la"]);
+        assert_eq!(entries[0].source, PromptSource::Clipboard);
+        assert_eq!(entries[0].created_at, 99);
+    }
+
+    #[tokio::test]
+    async fn test_non_consecutive_repeats_are_kept() {
+        let db = Db::in_memory().await.unwrap();
+        recorded(&db, &["a", "b", "a"], 10).await;
+
+        let entries = list(db.pool(), 10, None).await.unwrap();
+
+        assert_eq!(texts(&entries), ["a", "b", "a"]);
+    }
+
+    #[tokio::test]
+    async fn test_cap_evicts_oldest_first() {
+        let db = Db::in_memory().await.unwrap();
+        recorded(&db, &["one", "two", "three", "four", "five"], 3).await;
+
+        let entries = list(db.pool(), 10, None).await.unwrap();
+
+        assert_eq!(texts(&entries), ["five", "four", "three"]);
+    }
+
+    #[tokio::test]
+    async fn test_cap_keeps_bumped_repeat() {
+        let db = Db::in_memory().await.unwrap();
+        recorded(&db, &["one", "two", "two", "three"], 2).await;
+
+        let entries = list(db.pool(), 10, None).await.unwrap();
+
+        assert_eq!(texts(&entries), ["three", "two"]);
+    }
+
+    // ===== Privacy =====
+
+    #[tokio::test]
+    async fn test_nothing_recorded_while_history_is_off() {
+        let db = Db::in_memory().await.unwrap();
+        let off = HistorySettings {
+            enabled: false,
+            ..settings(10)
+        };
+
+        let recorded = record(db.pool(), &off, "secret", PromptSource::Typed, 1)
+            .await
+            .unwrap();
+
+        assert!(!recorded);
+        assert!(list(db.pool(), 10, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_leading_space_is_not_recorded() {
+        let db = Db::in_memory().await.unwrap();
+
+        let recorded = record(
+            db.pool(),
+            &settings(10),
+            " my password is hunter2",
+            PromptSource::Typed,
+            1,
+        )
+        .await
+        .unwrap();
+
+        assert!(!recorded);
+        assert!(list(db.pool(), 10, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_zero_limit_records_nothing() {
+        let db = Db::in_memory().await.unwrap();
+        recorded(&db, &["a"], 0).await;
+
+        assert!(list(db.pool(), 10, None).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_is_recordable() {
+        assert!(is_recordable("what is rust"));
+        assert!(is_recordable("\tindented"));
+        assert!(!is_recordable(" sensitive"));
+        assert!(!is_recordable(""));
+        assert!(!is_recordable("\n  "));
+    }
+
+    // ===== Listing =====
+
+    #[tokio::test]
+    async fn test_prefix_filter_matches_shell_style() {
+        let db = Db::in_memory().await.unwrap();
+        recorded(
+            &db,
+            &["git status", "Git log", "grep -r foo", "git diff"],
+            10,
+        )
+        .await;
+
+        let git = list(db.pool(), 10, Some("git ")).await.unwrap();
+        let newest = list(db.pool(), 1, Some("g")).await.unwrap();
+        let all = list(db.pool(), 10, Some("")).await.unwrap();
+
+        assert_eq!(texts(&git), ["git diff", "git status"]);
+        assert_eq!(texts(&newest), ["git diff"]);
+        assert_eq!(all.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_clear() {
+        let db = Db::in_memory().await.unwrap();
+        recorded(&db, &["a", "b"], 10).await;
+
+        assert_eq!(clear(db.pool()).await.unwrap(), 2);
+        assert!(list(db.pool(), 10, None).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_entry_shape() {
+        let entry = PromptHistoryEntry {
+            id: 7,
+            text: "tar extract flags".to_string(),
+            source: PromptSource::QuickAction,
+            created_at: 1,
+        };
+
+        assert_eq!(
+            serde_json::to_value(entry).unwrap(),
+            serde_json::json!({
+                "id": 7,
+                "text": "tar extract flags",
+                "source": "quick_action",
+                "created_at": 1,
+            })
+        );
+    }
+}
@@ -0,0 +1,464 @@
+//! Regenerating assistant replies.
+//!
+//! A regenerated reply never overwrites the old one. The new reply is
+//! inserted with the same parent user message and `created_at`, so it takes
+//! the old reply's place in the conversation, and the old reply gets
+//! `superseded_by` pointing at it. `get_messages` hides superseded replies
+//! unless asked for the full lineage.
+
+use sqlx::SqlitePool;
+
+use super::crypto;
+use super::search;
+use super::store;
+use super::types::{HistoryError, Message, MessageMetadata};
+use crate::db;
+use crate::llm::client::LlmClient;
+use crate::llm::connectivity::AssumeOnline;
+use crate::llm::types::{ChatMessage, ChatRole, ImagePart, LlmRequest};
+use crate::llm::usage::add_daily_usage;
+use crate::llm::{self, ResponseCache};
+use crate::settings::LlmSettings;
+
+/// Ask the model again for an assistant reply and store the new answer.
+///
+/// The request contains the conversation up to and including the parent
+/// user message, without superseded replies. The response cache is
+/// bypassed, since an identical answer would defeat the purpose.
+///
+/// # Arguments
+///
+/// * `client` - Client used for the model call
+/// * `cache` - Response cache (bypassed, but required by the fallback chain)
+/// * `pool` - History database pool
+/// * `settings` - LLM settings (provider, model, fallback profiles)
+/// * `message_id` - Assistant reply to regenerate
+/// * `now_ms` - Current Unix timestamp (ms)
+///
+/// # Returns
+///
+/// * `Ok(Message)` - The new reply
+/// * `Err(HistoryError::NotRegenerable)` - Not an assistant reply, already
+///   superseded, or no user message precedes it
+/// * `Err(HistoryError::Llm)` - Every profile failed
+pub async fn regenerate_message(
+    client: &impl LlmClient,
+    cache: &ResponseCache,
+    pool: &SqlitePool,
+    settings: &LlmSettings,
+    message_id: &str,
+    now_ms: i64,
+) -> Result<Message, HistoryError> {
+    let old = store::get_message(pool, message_id).await?;
+    if old.role != "assistant" || old.superseded_by.is_some() {
+        return Err(HistoryError::NotRegenerable(message_id.to_string()));
+    }
+    let parent = find_parent(pool, &old)
+        .await?
+        .ok_or_else(|| HistoryError::NotRegenerable(message_id.to_string()))?;
+
+    let history = context_up_to(pool, &parent).await?;
+    let chain = LlmRequest::chain_from_settings(settings, history)?;
+    let started = std::time::Instant::now();
+    let response = llm::ask_with_fallback(client, &AssumeOnline, cache, &chain, 0, now_ms).await?;
+    let duration_ms = started.elapsed().as_millis() as u32;
+
+    let reply = Message {
+        id: uuid::Uuid::now_v7().to_string(),
+        conversation_id: old.conversation_id.clone(),
+        role: old.role.clone(),
+        content: response.content.clone(),
+        created_at: old.created_at,
+        starred: false,
+        parent_message_id: Some(parent.id.clone()),
+        superseded_by: None,
+        metadata: MessageMetadata {
+            provider: Some(response.provider.as_str().to_string()),
+            model: Some(response.model.clone()),
+            prompt_tokens: response.usage.map(|u| u.prompt_tokens),
+            completion_tokens: response.usage.map(|u| u.completion_tokens),
+            duration_ms: Some(duration_ms),
+        },
+    };
+
+    let mut tx = db::begin_write(pool).await?;
+
+    let stored = crypto::seal_new(&reply.content)?;
+    sqlx::query(
+        "INSERT INTO messages (id, conversation_id, role, content, created_at, parent_message_id, provider, model, prompt_tokens, completion_tokens, duration_ms)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&reply.id)
+    .bind(&reply.conversation_id)
+    .bind(&reply.role)
+    .bind(&stored)
+    .bind(reply.created_at)
+    .bind(&reply.parent_message_id)
+    .bind(&reply.metadata.provider)
+    .bind(&reply.metadata.model)
+    .bind(reply.metadata.prompt_tokens.map(i64::from))
+    .bind(reply.metadata.completion_tokens.map(i64::from))
+    .bind(reply.metadata.duration_ms.map(i64::from))
+    .execute(&mut *tx)
+    .await?;
+    search::index_sealed(&mut tx, &reply.id, &stored, &reply.content).await?;
+
+    // Guard against a concurrent regeneration of the same reply
+    let result =
+        sqlx::query("UPDATE messages SET superseded_by = ? WHERE id = ? AND superseded_by IS NULL")
+            .bind(&reply.id)
+            .bind(&old.id)
+            .execute(&mut *tx)
+            .await?;
+    if result.rows_affected() == 0 {
+        return Err(HistoryError::NotRegenerable(message_id.to_string()));
+    }
+
+    sqlx::query("UPDATE conversations SET updated_at = ?, archived = 0 WHERE id = ?")
+        .bind(now_ms)
+        .bind(&reply.conversation_id)
+        .execute(&mut *tx)
+        .await?;
+
+    add_daily_usage(&mut tx, &response, now_ms).await?;
+
+    tx.commit().await?;
+    Ok(reply)
+}
+
+/// The user message `reply` answers.
+///
+/// Rows written before lineage tracking have no `parent_message_id`; for
+/// those the closest earlier user message is used.
+async fn find_parent(pool: &SqlitePool, reply: &Message) -> Result<Option<Message>, HistoryError> {
+    if let Some(parent_id) = &reply.parent_message_id {
+        return store::get_message(pool, parent_id).await.map(Some);
+    }
+
+    let parent_id: Option<String> = sqlx::query_scalar(
+        "SELECT id FROM messages
+         WHERE conversation_id = ? AND role = 'user'
+           AND (created_at < ? OR (created_at = ? AND id < ?))
+         ORDER BY created_at DESC, id DESC
+         LIMIT 1",
+    )
+    .bind(&reply.conversation_id)
+    .bind(reply.created_at)
+    .bind(reply.created_at)
+    .bind(&reply.id)
+    .fetch_optional(pool)
+    .await?;
+
+    match parent_id {
+        Some(id) => store::get_message(pool, &id).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Current messages of the conversation, oldest first, ending with `parent`.
+///
+/// Images recorded with a message are sent with it again.
+async fn context_up_to(
+    pool: &SqlitePool,
+    parent: &Message,
+) -> Result<Vec<ChatMessage>, HistoryError> {
+    let rows: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT id, role, content FROM messages
+         WHERE conversation_id = ? AND superseded_by IS NULL
+           AND (created_at < ? OR (created_at = ? AND id <= ?))
+         ORDER BY created_at ASC, id ASC",
+    )
+    .bind(&parent.conversation_id)
+    .bind(parent.created_at)
+    .bind(parent.created_at)
+    .bind(&parent.id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut messages = Vec::with_capacity(rows.len());
+    for (id, role, content) in rows {
+        let images = store::list_attachments(pool, &id)
+            .await?
+            .into_iter()
+            .filter_map(|attachment| {
+                Some(ImagePart {
+                    mime: attachment.mime?,
+                    data: attachment.data?,
+                })
+            })
+            .collect();
+        messages.push(ChatMessage {
+            role: if role == "user" {
+                ChatRole::User
+            } else {
+                ChatRole::Assistant
+            },
+            content: crypto::open_stored(content),
+            images,
+        });
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::NewAttachment;
+    use crate::llm::types::{Completion, TokenUsage};
+    use crate::llm::LlmError;
+    use crate::settings::AppSettings;
+    use std::sync::Mutex;
+
+    /// Client answering with a fixed reply and recording requests.
+    struct ReplyClient {
+        reply: Result<String, LlmError>,
+        requests: Mutex<Vec<LlmRequest>>,
+    }
+
+    impl ReplyClient {
+        fn new(reply: Result<&str, LlmError>) -> Self {
+            Self {
+                reply: reply.map(str::to_string),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl LlmClient for ReplyClient {
+        async fn complete(&self, request: &LlmRequest) -> Result<Completion, LlmError> {
+            self.requests.lock().unwrap().push(request.clone());
+            self.reply.clone().map(|content| Completion {
+                content,
+                usage: Some(TokenUsage {
+                    prompt_tokens: 10,
+                    completion_tokens: 5,
+                }),
+                cost_usd: None,
+            })
+        }
+    }
+
+    /// Conversation of alternating user/assistant messages, one ms apart.
+    async fn conversation_with(pool: &SqlitePool, contents: &[&str]) -> (String, Vec<Message>) {
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        let mut messages = Vec::new();
+        for (i, content) in contents.iter().enumerate() {
+            let role = if i % 2 == 0 { "user" } else { "assistant" };
+            let message = store::append_message(
+                pool,
+                &conversation.id,
+                role,
+                content,
+                &MessageMetadata::default(),
+                &[],
+                i as i64 + 1,
+            )
+            .await
+            .unwrap();
+            messages.push(message);
+        }
+        (conversation.id, messages)
+    }
+
+    async fn regenerate(
+        db: &Db,
+        client: &ReplyClient,
+        message_id: &str,
+    ) -> Result<Message, HistoryError> {
+        let cache = ResponseCache::new(db.pool().clone());
+        let settings = AppSettings::default().llm;
+        regenerate_message(client, &cache, db.pool(), &settings, message_id, 1_000).await
+    }
+
+    async fn contents(pool: &SqlitePool, conversation_id: &str, all: bool) -> Vec<String> {
+        let mut messages = store::get_messages(pool, conversation_id, None, 100, all)
+            .await
+            .unwrap();
+        messages.reverse();
+        messages.into_iter().map(|m| m.content).collect()
+    }
+
+    // ===== Linkage =====
+
+    #[tokio::test]
+    async fn test_assistant_reply_links_to_user_message() {
+        let db = Db::in_memory().await.unwrap();
+        let (_, messages) = conversation_with(db.pool(), &["q1", "a1", "q2", "a2"]).await;
+
+        assert_eq!(messages[0].parent_message_id, None);
+        assert_eq!(
+            messages[1].parent_message_id.as_ref(),
+            Some(&messages[0].id)
+        );
+        assert_eq!(
+            messages[3].parent_message_id.as_ref(),
+            Some(&messages[2].id)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_links_old_and_new_reply() {
+        let db = Db::in_memory().await.unwrap();
+        let (_, messages) = conversation_with(db.pool(), &["q1", "a1"]).await;
+        let client = ReplyClient::new(Ok("a1 again"));
+
+        let reply = regenerate(&db, &client, &messages[1].id).await.unwrap();
+
+        assert_eq!(reply.content, "a1 again");
+        assert_eq!(reply.parent_message_id.as_ref(), Some(&messages[0].id));
+        assert_eq!(reply.created_at, messages[1].created_at);
+        assert_eq!(reply.metadata.prompt_tokens, Some(10));
+        assert!(reply.metadata.duration_ms.is_some());
+        let old = store::get_message(db.pool(), &messages[1].id)
+            .await
+            .unwrap();
+        assert_eq!(old.superseded_by, Some(reply.id.clone()));
+        let stored = store::get_message(db.pool(), &reply.id).await.unwrap();
+        assert_eq!(stored, reply);
+    }
+
+    // ===== Filtering =====
+
+    #[tokio::test]
+    async fn test_get_messages_hides_superseded_by_default() {
+        let db = Db::in_memory().await.unwrap();
+        let (id, messages) = conversation_with(db.pool(), &["q1", "a1"]).await;
+        let client = ReplyClient::new(Ok("a1 again"));
+        regenerate(&db, &client, &messages[1].id).await.unwrap();
+
+        assert_eq!(contents(db.pool(), &id, false).await, ["q1", "a1 again"]);
+        let all = contents(db.pool(), &id, true).await;
+        assert_eq!(all.len(), 3);
+        assert!(all.contains(&"a1".to_string()));
+    }
+
+    // ===== Regeneration =====
+
+    #[tokio::test]
+    async fn test_regenerate_mid_conversation() {
+        let db = Db::in_memory().await.unwrap();
+        let (id, messages) = conversation_with(db.pool(), &["q1", "a1", "q2", "a2"]).await;
+        let client = ReplyClient::new(Ok("a1 again"));
+
+        regenerate(&db, &client, &messages[1].id).await.unwrap();
+
+        // Only the conversation up to the parent is sent
+        let sent: Vec<String> = client.requests.lock().unwrap()[0]
+            .messages
+            .iter()
+            .map(|m| m.content.clone())
+            .collect();
+        assert_eq!(sent, ["q1"]);
+        // The new reply keeps the old one's position
+        assert_eq!(
+            contents(db.pool(), &id, false).await,
+            ["q1", "a1 again", "q2", "a2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_sends_images_again() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        let screenshot = NewAttachment {
+            name: "Screenshot 1.png".to_string(),
+            size: 6,
+            line_count: 0,
+            truncated: false,
+            lossy: false,
+            mime: Some("image/png".to_string()),
+            data: Some("iVBORw0K".to_string()),
+        };
+        for (role, content, attachments) in [
+            ("user", "What does this chart mean?", vec![screenshot]),
+            ("assistant", "Sales doubled.", Vec::new()),
+        ] {
+            store::append_message(
+                pool,
+                &conversation.id,
+                role,
+                content,
+                &MessageMetadata::default(),
+                &attachments,
+                1,
+            )
+            .await
+            .unwrap();
+        }
+        let reply = store::get_messages(pool, &conversation.id, None, 1, false)
+            .await
+            .unwrap()
+            .remove(0);
+        let client = ReplyClient::new(Ok("Sales doubled in March."));
+
+        regenerate(&db, &client, &reply.id).await.unwrap();
+
+        let sent = client.requests.lock().unwrap()[0].messages.clone();
+        assert_eq!(
+            sent[0].images,
+            [ImagePart {
+                mime: "image/png".to_string(),
+                data: "iVBORw0K".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_twice_follows_latest_reply() {
+        let db = Db::in_memory().await.unwrap();
+        let (id, messages) = conversation_with(db.pool(), &["q1", "a1"]).await;
+        let first = regenerate(&db, &ReplyClient::new(Ok("a1 v2")), &messages[1].id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            regenerate(&db, &ReplyClient::new(Ok("a1 v3")), &messages[1].id).await,
+            Err(HistoryError::NotRegenerable(messages[1].id.clone()))
+        );
+        regenerate(&db, &ReplyClient::new(Ok("a1 v3")), &first.id)
+            .await
+            .unwrap();
+
+        assert_eq!(contents(db.pool(), &id, false).await, ["q1", "a1 v3"]);
+        assert_eq!(contents(db.pool(), &id, true).await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_rejects_user_message() {
+        let db = Db::in_memory().await.unwrap();
+        let (_, messages) = conversation_with(db.pool(), &["q1", "a1"]).await;
+
+        let result = regenerate(&db, &ReplyClient::new(Ok("x")), &messages[0].id).await;
+
+        assert_eq!(
+            result,
+            Err(HistoryError::NotRegenerable(messages[0].id.clone()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_unknown_message() {
+        let db = Db::in_memory().await.unwrap();
+
+        let result = regenerate(&db, &ReplyClient::new(Ok("x")), "missing").await;
+
+        assert_eq!(
+            result,
+            Err(HistoryError::MessageNotFound("missing".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_regeneration_keeps_old_reply() {
+        let db = Db::in_memory().await.unwrap();
+        let (id, messages) = conversation_with(db.pool(), &["q1", "a1"]).await;
+        let client = ReplyClient::new(Err(LlmError::BadRequest("bad request".to_string())));
+
+        let result = regenerate(&db, &client, &messages[1].id).await;
+
+        assert!(matches!(result, Err(HistoryError::Llm(_))));
+        assert_eq!(contents(db.pool(), &id, true).await, ["q1", "a1"]);
+    }
+}
@@ -0,0 +1,462 @@
+//! Moving the history database file (`move_history_db`).
+//!
+//! The move runs with every pooled connection held, so nothing on the pool
+//! writes while it happens:
+//!
+//! 1. `PRAGMA wal_checkpoint(TRUNCATE)` folds the WAL into the main file
+//! 2. `VACUUM INTO` writes a copy beside the target, as `<name>.moving`
+//! 3. `PRAGMA integrity_check` runs on the copy
+//! 4. the copy is renamed into place
+//! 5. the pool's connect options are pointed at the new file and the held
+//!    connections closed, so every later connection opens the new file
+//!
+//! A failure before step 5 (no write permission, a full disk, a corrupt
+//! copy) removes the copy and leaves the live database as it was.
+//!
+//! The old file outlives the move: the frontend's `tauri-plugin-sql`
+//! connection keeps it and its `-wal`/`-shm` files open until the app
+//! exits, and the plugin can't close a connection for the backend. Once
+//! `history.database_path` records the new location the caller passes the
+//! old file to [`defer_removal`], and [`remove_stale_files`] deletes it at
+//! the next start, before anything opens a database.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::pool::PoolConnection;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Connection, Sqlite, SqliteConnection, SqlitePool};
+
+use super::types::HistoryError;
+use crate::db::{connect_options, DATABASE_FILE};
+use crate::settings::SettingsManager;
+
+/// Event sent after the database was moved.
+pub const MOVED_EVENT: &str = "history-db-moved";
+
+/// Payload of the `history-db-moved` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DatabaseMoved {
+    /// Absolute path of the database file
+    pub path: String,
+    /// URL to reload the frontend's `tauri-plugin-sql` connection with
+    pub database_url: String,
+}
+
+/// How long the move waits for queries in flight to finish.
+const HOLD_WAIT: Duration = Duration::from_secs(5);
+
+/// Suffix of the copy while it's being written and checked.
+const PARTIAL_SUFFIX: &str = ".moving";
+
+/// Settings store key of the old database files left for the next start
+/// to delete.
+pub const STALE_FILES_KEY: &str = "stale_database_files";
+
+/// Resolve the file `move_history_db` moves the database to.
+///
+/// An existing directory gets [`DATABASE_FILE`] inside it.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::Relocate)` - The path is relative or the file
+///   already exists
+pub fn target_file(new_path: &str) -> Result<PathBuf, HistoryError> {
+    let path = PathBuf::from(new_path.trim());
+    if !path.is_absolute() {
+        return Err(HistoryError::Relocate(format!(
+            "'{}' is not an absolute path",
+            new_path
+        )));
+    }
+    let path = if path.is_dir() {
+        path.join(DATABASE_FILE)
+    } else {
+        path
+    };
+    if path.exists() {
+        return Err(HistoryError::Relocate(format!(
+            "{} already exists",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+/// The file the pool currently opens.
+pub fn current_file(pool: &SqlitePool) -> PathBuf {
+    pool.connect_options().get_filename().to_path_buf()
+}
+
+/// Copy the database behind `pool` to `target`, check the copy and point
+/// the pool at it.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::Busy)` - Queries didn't finish within 5 s
+/// * `Err(HistoryError::Relocate)` - The copy couldn't be written or failed
+///   its integrity check; the live database is untouched
+pub async fn relocate(pool: &SqlitePool, target: &Path) -> Result<(), HistoryError> {
+    let partial = partial_file(target);
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            HistoryError::Relocate(format!("Failed to create {}: {}", parent.display(), e))
+        })?;
+    }
+
+    let mut held = hold_all(pool).await?;
+    let copied = match copy(&mut held[0], &partial).await {
+        Ok(()) => verify(&partial).await,
+        Err(e) => Err(e),
+    };
+    let renamed = copied.and_then(|()| {
+        std::fs::rename(&partial, target)
+            .map_err(|e| HistoryError::Relocate(format!("Failed to move copy into place: {}", e)))
+    });
+    if let Err(e) = renamed {
+        remove_database_files(&partial);
+        return Err(e);
+    }
+
+    switch(pool, held, target).await;
+    Ok(())
+}
+
+/// Point `pool` at the database file `path`, e.g. back at the old file
+/// when recording the move failed.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::Busy)` - Queries didn't finish within 5 s
+pub async fn point_at(pool: &SqlitePool, path: &Path) -> Result<(), HistoryError> {
+    let held = hold_all(pool).await?;
+    switch(pool, held, path).await;
+    Ok(())
+}
+
+/// Delete a database file with its `-wal` and `-shm` files.
+///
+/// Failures are only logged, e.g. a file still open elsewhere on Windows.
+///
+/// # Returns
+///
+/// * `true` - None of the files is left
+pub fn remove_database_files(path: &Path) -> bool {
+    let mut removed = true;
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = path.as_os_str().to_owned();
+        file.push(suffix);
+        let file = PathBuf::from(file);
+        if file.exists() {
+            if let Err(e) = std::fs::remove_file(&file) {
+                tracing::warn!(path = %file.display(), error = %e, "Failed to delete old database file");
+                removed = false;
+            }
+        }
+    }
+    removed
+}
+
+/// Leave the database file `path` for [`remove_stale_files`] to delete at
+/// the next start.
+pub fn defer_removal(settings_manager: &SettingsManager, path: &Path) -> Result<(), String> {
+    let mut stale: Vec<String> = settings_manager
+        .load_state(STALE_FILES_KEY)?
+        .unwrap_or_default();
+    let path = path.to_string_lossy().into_owned();
+    if !stale.contains(&path) {
+        stale.push(path);
+    }
+    settings_manager.save_state(STALE_FILES_KEY, &stale)
+}
+
+/// Delete the files left by earlier moves; call at startup, before the
+/// database is opened.
+///
+/// # Arguments
+///
+/// * `current` - The database file in use, which is never deleted
+pub fn remove_stale_files(settings_manager: &SettingsManager, current: &Path) {
+    let stale: Vec<String> = match settings_manager.load_state(STALE_FILES_KEY) {
+        Ok(stale) => stale.unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to read old database files");
+            return;
+        }
+    };
+    if stale.is_empty() {
+        return;
+    }
+    let left = remove_stale(stale, current);
+    if let Err(e) = settings_manager.save_state(STALE_FILES_KEY, &left) {
+        tracing::warn!(error = %e, "Failed to record old database files");
+    }
+}
+
+/// Delete each of `stale` except `current`, returning the ones that could
+/// not be deleted to retry at the next start.
+fn remove_stale(stale: Vec<String>, current: &Path) -> Vec<String> {
+    stale
+        .into_iter()
+        .filter(|path| Path::new(path) != current && !remove_database_files(Path::new(path)))
+        .collect()
+}
+
+fn partial_file(target: &Path) -> PathBuf {
+    let mut name = target.as_os_str().to_owned();
+    name.push(PARTIAL_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Acquire every connection the pool may open, waiting for ones in use.
+async fn hold_all(pool: &SqlitePool) -> Result<Vec<PoolConnection<Sqlite>>, HistoryError> {
+    let max = pool.options().get_max_connections() as usize;
+    let mut held = Vec::with_capacity(max);
+    for _ in 0..max {
+        match tokio::time::timeout(HOLD_WAIT, pool.acquire()).await {
+            Ok(conn) => held.push(conn?),
+            Err(_) => return Err(HistoryError::Busy),
+        }
+    }
+    Ok(held)
+}
+
+async fn copy(conn: &mut SqliteConnection, partial: &Path) -> Result<(), HistoryError> {
+    if partial.exists() {
+        remove_database_files(partial);
+    }
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("VACUUM INTO ?")
+        .bind(partial.to_string_lossy())
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| HistoryError::Relocate(format!("Failed to copy database: {}", e)))?;
+    Ok(())
+}
+
+async fn verify(path: &Path) -> Result<(), HistoryError> {
+    let options = SqliteConnectOptions::new().filename(path).read_only(true);
+    let mut conn = SqliteConnection::connect_with(&options).await?;
+    let result: String = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(&mut conn)
+        .await?;
+    let _ = conn.close().await;
+    if result != "ok" {
+        return Err(HistoryError::Relocate(format!(
+            "Copy failed the integrity check: {}",
+            result
+        )));
+    }
+    Ok(())
+}
+
+/// Point the pool at `path` and close the held connections, which are
+/// still open on the old file.
+async fn switch(pool: &SqlitePool, held: Vec<PoolConnection<Sqlite>>, path: &Path) {
+    pool.set_connect_options(connect_options(path));
+    for conn in held {
+        let _ = conn.close().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::store;
+    use crate::history::types::MessageMetadata;
+
+    async fn seeded(dir: &Path) -> Db {
+        let db = Db::open(&dir.join(DATABASE_FILE)).await.unwrap();
+        let conversation = store::create_conversation(db.pool(), None, 0)
+            .await
+            .unwrap();
+        store::append_message(
+            db.pool(),
+            &conversation.id,
+            "user",
+            "kept",
+            &MessageMetadata::default(),
+            &[],
+            1,
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    async fn message_count(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    // ===== Target =====
+
+    #[test]
+    fn test_target_file_in_directory() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            target_file(dir.path().to_str().unwrap()),
+            Ok(dir.path().join(DATABASE_FILE))
+        );
+    }
+
+    #[test]
+    fn test_target_file_rejects_relative_and_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("taken.db");
+        std::fs::write(&existing, "").unwrap();
+
+        assert!(matches!(
+            target_file("history.db"),
+            Err(HistoryError::Relocate(_))
+        ));
+        assert!(matches!(
+            target_file(existing.to_str().unwrap()),
+            Err(HistoryError::Relocate(_))
+        ));
+    }
+
+    // ===== Moving =====
+
+    #[tokio::test]
+    async fn test_relocate_copies_verifies_and_switches() {
+        let old_dir = tempfile::tempdir().unwrap();
+        let new_dir = tempfile::tempdir().unwrap();
+        let db = seeded(old_dir.path()).await;
+        let old = current_file(db.pool());
+        let target = new_dir.path().join("nested").join(DATABASE_FILE);
+
+        relocate(db.pool(), &target).await.unwrap();
+
+        assert_eq!(current_file(db.pool()), target);
+        assert!(!partial_file(&target).exists());
+        assert_eq!(message_count(db.pool()).await, 1);
+
+        // New writes land in the new file only
+        store::create_conversation(db.pool(), None, 2)
+            .await
+            .unwrap();
+        let fresh = Db::open(&target).await.unwrap();
+        let conversations: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM conversations")
+            .fetch_one(fresh.pool())
+            .await
+            .unwrap();
+        assert_eq!(conversations, 2);
+
+        remove_database_files(&old);
+        assert!(!old.exists());
+        assert_eq!(message_count(db.pool()).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_target_leaves_live_database() {
+        let old_dir = tempfile::tempdir().unwrap();
+        let new_dir = tempfile::tempdir().unwrap();
+        let db = seeded(old_dir.path()).await;
+        let old = current_file(db.pool());
+        // A file where a directory is needed can't be written to, even as root
+        let blocker = new_dir.path().join("blocker");
+        std::fs::write(&blocker, "").unwrap();
+        let target = blocker.join(DATABASE_FILE);
+
+        let result = relocate(db.pool(), &target).await;
+
+        assert!(matches!(result, Err(HistoryError::Relocate(_))));
+        assert_eq!(current_file(db.pool()), old);
+        assert_eq!(message_count(db.pool()).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_copy_removes_partial_file() {
+        let old_dir = tempfile::tempdir().unwrap();
+        let new_dir = tempfile::tempdir().unwrap();
+        let db = seeded(old_dir.path()).await;
+        // VACUUM INTO refuses a target that's a directory
+        let target = new_dir.path().join(DATABASE_FILE);
+        std::fs::create_dir(partial_file(&target)).unwrap();
+        std::fs::write(partial_file(&target).join("keep"), "").unwrap();
+
+        let result = relocate(db.pool(), &target).await;
+
+        assert!(result.is_err());
+        assert!(!target.exists());
+        assert_eq!(current_file(db.pool()), old_dir.path().join(DATABASE_FILE));
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_copy_fails_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("corrupt.db");
+        let mut bytes = b"SQLite format 3\0".to_vec();
+        bytes.resize(4096, 0xAB);
+        std::fs::write(&path, bytes).unwrap();
+
+        assert!(verify(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_point_at_switches_back() {
+        let old_dir = tempfile::tempdir().unwrap();
+        let new_dir = tempfile::tempdir().unwrap();
+        let db = seeded(old_dir.path()).await;
+        let old = current_file(db.pool());
+        let target = new_dir.path().join(DATABASE_FILE);
+        relocate(db.pool(), &target).await.unwrap();
+
+        point_at(db.pool(), &old).await.unwrap();
+
+        assert_eq!(current_file(db.pool()), old);
+        assert_eq!(message_count(db.pool()).await, 1);
+    }
+
+    // ===== Stale files =====
+
+    #[test]
+    fn test_remove_stale_keeps_current_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.db");
+        let current = dir.path().join(DATABASE_FILE);
+        for file in [&old, &dir.path().join("old.db-wal"), &current] {
+            std::fs::write(file, "").unwrap();
+        }
+
+        let left = remove_stale(
+            vec![
+                old.to_string_lossy().into_owned(),
+                current.to_string_lossy().into_owned(),
+            ],
+            &current,
+        );
+
+        assert!(left.is_empty());
+        assert!(!old.exists());
+        assert!(!dir.path().join("old.db-wal").exists());
+        assert!(current.exists());
+    }
+
+    #[test]
+    fn test_remove_stale_retries_files_it_could_not_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        // remove_file refuses a directory, like a file still open on Windows
+        let stuck = dir.path().join("stuck.db");
+        std::fs::create_dir(&stuck).unwrap();
+        let gone = dir.path().join("gone.db");
+
+        let left = remove_stale(
+            vec![
+                stuck.to_string_lossy().into_owned(),
+                gone.to_string_lossy().into_owned(),
+            ],
+            &dir.path().join(DATABASE_FILE),
+        );
+
+        assert_eq!(left, vec![stuck.to_string_lossy().into_owned()]);
+    }
+}
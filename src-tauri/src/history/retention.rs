@@ -0,0 +1,651 @@
+//! Daily history maintenance.
+//!
+//! A background task runs the maintenance pass shortly after startup
+//! ([`STARTUP_DELAY`]) and then every [`INTERVAL`]. The steps run in
+//! [`STEPS`] order:
+//!
+//! 1. Retention: delete conversations not updated for
+//!    `history.retention_days` (pinned, archived and trashed ones, and
+//!    ones with a starred message, are kept)
+//! 2. Trash: purge conversations trashed more than 30 days ago
+//! 3. Drafts: delete drafts older than `history.draft_retention_days`
+//! 4. Size cap: the `history.max_db_size_mb` check, pruning with
+//!    `history.auto_prune`
+//! 5. `PRAGMA optimize`
+//!
+//! A failing step is logged and the pass goes on with the next one. Each
+//! pass ends with `maintenance-completed`, summarizing what was removed.
+//!
+//! Passes never overlap: the scheduled one and `enforce_history_retention`
+//! share the lock in [`MaintenanceScheduler`]. Changing
+//! `history.auto_maintenance` or `history.retention_days` restarts the
+//! wait, so the next pass runs [`STARTUP_DELAY`] after the change.
+//!
+//! Steps and time sit behind [`StepRunner`] and [`Clock`] so the
+//! orchestration can be tested without a database or real waiting.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::sync::{Mutex, Notify};
+
+use super::types::HistoryError;
+use super::{drafts, relocate, size, store};
+use crate::settings::HistorySettings;
+
+/// Event sent after every maintenance pass.
+pub const COMPLETED_EVENT: &str = "maintenance-completed";
+
+/// Wait before the first pass, so startup isn't slowed down.
+pub const STARTUP_DELAY: Duration = Duration::from_secs(2 * 60);
+
+/// Time between passes.
+pub const INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// One step of a maintenance pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceStep {
+    /// Delete conversations older than `history.retention_days`
+    Retention,
+    /// Purge conversations trashed more than 30 days ago
+    Trash,
+    /// Delete stale drafts
+    Drafts,
+    /// Enforce `history.max_db_size_mb`
+    SizeCap,
+    /// `PRAGMA optimize`
+    Optimize,
+}
+
+/// Every step, in the order a pass runs them.
+pub const STEPS: [MaintenanceStep; 5] = [
+    MaintenanceStep::Retention,
+    MaintenanceStep::Trash,
+    MaintenanceStep::Drafts,
+    MaintenanceStep::SizeCap,
+    MaintenanceStep::Optimize,
+];
+
+/// What started a pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceTrigger {
+    /// The daily schedule
+    Scheduled,
+    /// `enforce_history_retention`
+    Manual,
+}
+
+/// Payload of the `maintenance-completed` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaintenanceSummary {
+    pub trigger: MaintenanceTrigger,
+    /// Conversations deleted by `history.retention_days`
+    pub expired_conversations: u64,
+    /// Trashed conversations purged
+    pub purged_conversations: u64,
+    /// Drafts deleted
+    pub deleted_drafts: u64,
+    /// Conversations deleted to get under `history.max_db_size_mb`
+    pub pruned_conversations: u64,
+    /// Steps that failed; the others still ran
+    pub failed_steps: Vec<MaintenanceStep>,
+    /// When the pass finished (Unix ms)
+    pub finished_at: i64,
+}
+
+/// Runs a single step; the history database in the app.
+pub trait StepRunner {
+    /// Run `step`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(u64)` - Rows removed (`0` for `Optimize`)
+    /// * `Err(HistoryError)` - The step failed
+    async fn run(
+        &self,
+        step: MaintenanceStep,
+        settings: &HistorySettings,
+        now_ms: i64,
+    ) -> Result<u64, HistoryError>;
+}
+
+/// Current time and waiting; [`SystemClock`] in the app.
+pub trait Clock {
+    fn now_ms(&self) -> i64;
+
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The wall clock and tokio's timer.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> i64 {
+        crate::db::now_ms()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// The steps run against the history database.
+pub struct DbSteps {
+    pub pool: SqlitePool,
+}
+
+impl StepRunner for DbSteps {
+    async fn run(
+        &self,
+        step: MaintenanceStep,
+        settings: &HistorySettings,
+        now_ms: i64,
+    ) -> Result<u64, HistoryError> {
+        let pool = &self.pool;
+        match step {
+            MaintenanceStep::Retention => {
+                prune_expired(pool, settings.retention_days, now_ms).await
+            }
+            MaintenanceStep::Trash => store::purge_expired_trash(pool, now_ms).await,
+            MaintenanceStep::Drafts => {
+                drafts::purge_expired_drafts(pool, settings.draft_retention_days, now_ms).await
+            }
+            MaintenanceStep::SizeCap => {
+                let warning = size::check(pool, &relocate::current_file(pool), settings).await?;
+                Ok(warning.map_or(0, |warning| warning.pruned_conversations))
+            }
+            MaintenanceStep::Optimize => {
+                sqlx::query("PRAGMA optimize").execute(pool).await?;
+                Ok(0)
+            }
+        }
+    }
+}
+
+/// Delete conversations not updated for `retention_days` days.
+///
+/// Pinned, archived and trashed conversations, and ones with a starred
+/// message, are kept.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `retention_days` - `history.retention_days`; `0` keeps everything
+/// * `now_ms` - Current Unix timestamp (ms)
+///
+/// # Returns
+///
+/// Number of conversations deleted
+pub async fn prune_expired(
+    pool: &SqlitePool,
+    retention_days: u32,
+    now_ms: i64,
+) -> Result<u64, HistoryError> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let result = sqlx::query(
+        "DELETE FROM conversations
+         WHERE updated_at <= ? AND pinned = 0 AND archived = 0 AND deleted_at IS NULL
+           AND NOT EXISTS (
+               SELECT 1 FROM messages m WHERE m.conversation_id = conversations.id AND m.starred = 1)",
+    )
+    .bind(now_ms - i64::from(retention_days) * DAY_MS)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// The maintenance lock and schedule, managed as Tauri state.
+#[derive(Default)]
+pub struct MaintenanceScheduler {
+    /// Held for a whole pass
+    running: Mutex<()>,
+    /// Restarts the wait before the next scheduled pass
+    reschedule: Notify,
+}
+
+impl MaintenanceScheduler {
+    /// Run every step, after any pass in progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `runner` - Runs the steps
+    /// * `clock` - Timestamps the pass
+    /// * `settings` - History settings the steps follow
+    /// * `trigger` - What started the pass
+    pub async fn run(
+        &self,
+        runner: &impl StepRunner,
+        clock: &impl Clock,
+        settings: &HistorySettings,
+        trigger: MaintenanceTrigger,
+    ) -> MaintenanceSummary {
+        let _running = self.running.lock().await;
+        let mut summary = MaintenanceSummary {
+            trigger,
+            expired_conversations: 0,
+            purged_conversations: 0,
+            deleted_drafts: 0,
+            pruned_conversations: 0,
+            failed_steps: Vec::new(),
+            finished_at: 0,
+        };
+        for step in STEPS {
+            match runner.run(step, settings, clock.now_ms()).await {
+                Ok(removed) => {
+                    tracing::info!(?step, removed, "History maintenance step finished");
+                    match step {
+                        MaintenanceStep::Retention => summary.expired_conversations = removed,
+                        MaintenanceStep::Trash => summary.purged_conversations = removed,
+                        MaintenanceStep::Drafts => summary.deleted_drafts = removed,
+                        MaintenanceStep::SizeCap => summary.pruned_conversations = removed,
+                        MaintenanceStep::Optimize => {}
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(?step, error = %e, "History maintenance step failed");
+                    summary.failed_steps.push(step);
+                }
+            }
+        }
+        summary.finished_at = clock.now_ms();
+        summary
+    }
+
+    /// Start waiting for the next scheduled pass again, from
+    /// [`STARTUP_DELAY`].
+    pub fn reschedule(&self) {
+        self.reschedule.notify_one();
+    }
+
+    /// Run passes on the schedule, until the task is aborted.
+    ///
+    /// # Arguments
+    ///
+    /// * `runner` - Runs the steps
+    /// * `clock` - Waits between passes
+    /// * `settings` - Read before each pass; `None` skips it (e.g. while
+    ///   snoozed)
+    /// * `completed` - Called with the summary of each pass
+    pub async fn run_schedule(
+        &self,
+        runner: &impl StepRunner,
+        clock: &impl Clock,
+        settings: impl Fn() -> Option<HistorySettings>,
+        mut completed: impl FnMut(MaintenanceSummary),
+    ) {
+        loop {
+            let mut wait = STARTUP_DELAY;
+            loop {
+                tokio::select! {
+                    () = clock.sleep(wait) => {}
+                    () = self.reschedule.notified() => break,
+                }
+                wait = INTERVAL;
+                let Some(settings) = settings().filter(|settings| settings.auto_maintenance) else {
+                    continue;
+                };
+                completed(
+                    self.run(runner, clock, &settings, MaintenanceTrigger::Scheduled)
+                        .await,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::types::MessageMetadata;
+    use std::sync::atomic::{AtomicI64, AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    /// Records the steps it runs and how many passes overlap.
+    #[derive(Default)]
+    struct FakeSteps {
+        ran: StdMutex<Vec<(MaintenanceStep, i64)>>,
+        active: AtomicU32,
+        max_active: AtomicU32,
+        failing: Option<MaintenanceStep>,
+    }
+
+    impl StepRunner for FakeSteps {
+        async fn run(
+            &self,
+            step: MaintenanceStep,
+            _settings: &HistorySettings,
+            now_ms: i64,
+        ) -> Result<u64, HistoryError> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(2)).await;
+            self.ran.lock().unwrap().push((step, now_ms));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            if self.failing == Some(step) {
+                return Err(HistoryError::Busy);
+            }
+            Ok(STEPS.iter().position(|s| *s == step).unwrap() as u64 + 1)
+        }
+    }
+
+    /// Time that only moves when slept through; after `sleeps` sleeps it
+    /// never wakes again.
+    struct FakeClock {
+        now: AtomicI64,
+        slept: StdMutex<Vec<Duration>>,
+        sleeps: usize,
+    }
+
+    impl FakeClock {
+        fn new(sleeps: usize) -> Self {
+            Self {
+                now: AtomicI64::new(0),
+                slept: StdMutex::new(Vec::new()),
+                sleeps,
+            }
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now_ms(&self) -> i64 {
+            self.now.load(Ordering::SeqCst)
+        }
+
+        async fn sleep(&self, duration: Duration) {
+            let count = {
+                let mut slept = self.slept.lock().unwrap();
+                slept.push(duration);
+                slept.len()
+            };
+            if count > self.sleeps {
+                std::future::pending::<()>().await;
+            }
+            self.now
+                .fetch_add(duration.as_millis() as i64, Ordering::SeqCst);
+        }
+    }
+
+    fn history() -> HistorySettings {
+        HistorySettings::default()
+    }
+
+    // ===== Orchestration =====
+
+    #[tokio::test]
+    async fn test_steps_run_in_order_and_are_summarized() {
+        let scheduler = MaintenanceScheduler::default();
+        let steps = FakeSteps::default();
+
+        let summary = scheduler
+            .run(
+                &steps,
+                &FakeClock::new(0),
+                &history(),
+                MaintenanceTrigger::Manual,
+            )
+            .await;
+
+        let ran: Vec<_> = steps.ran.lock().unwrap().iter().map(|(s, _)| *s).collect();
+        assert_eq!(ran, STEPS);
+        assert_eq!(
+            summary,
+            MaintenanceSummary {
+                trigger: MaintenanceTrigger::Manual,
+                expired_conversations: 1,
+                purged_conversations: 2,
+                deleted_drafts: 3,
+                pruned_conversations: 4,
+                failed_steps: Vec::new(),
+                finished_at: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_step_does_not_stop_the_pass() {
+        let scheduler = MaintenanceScheduler::default();
+        let steps = FakeSteps {
+            failing: Some(MaintenanceStep::Trash),
+            ..FakeSteps::default()
+        };
+
+        let summary = scheduler
+            .run(
+                &steps,
+                &FakeClock::new(0),
+                &history(),
+                MaintenanceTrigger::Manual,
+            )
+            .await;
+
+        assert_eq!(steps.ran.lock().unwrap().len(), STEPS.len());
+        assert_eq!(summary.failed_steps, [MaintenanceStep::Trash]);
+        assert_eq!(summary.purged_conversations, 0);
+        assert_eq!(summary.deleted_drafts, 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_passes_never_overlap() {
+        let scheduler = Arc::new(MaintenanceScheduler::default());
+        let steps = Arc::new(FakeSteps::default());
+
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let (scheduler, steps) = (Arc::clone(&scheduler), Arc::clone(&steps));
+                tokio::spawn(async move {
+                    scheduler
+                        .run(
+                            &*steps,
+                            &FakeClock::new(0),
+                            &history(),
+                            MaintenanceTrigger::Manual,
+                        )
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(steps.max_active.load(Ordering::SeqCst), 1);
+        let ran: Vec<_> = steps.ran.lock().unwrap().iter().map(|(s, _)| *s).collect();
+        assert_eq!(ran, STEPS.repeat(4));
+    }
+
+    // ===== Schedule =====
+
+    #[tokio::test]
+    async fn test_schedule_runs_after_delay_then_daily() {
+        let scheduler = MaintenanceScheduler::default();
+        let steps = FakeSteps::default();
+        let clock = FakeClock::new(3);
+        let mut passes = Vec::new();
+
+        let schedule = scheduler.run_schedule(
+            &steps,
+            &clock,
+            || Some(history()),
+            |summary| passes.push(summary),
+        );
+        let _ = tokio::time::timeout(Duration::from_millis(200), schedule).await;
+
+        let delay = STARTUP_DELAY.as_millis() as i64;
+        let day = INTERVAL.as_millis() as i64;
+        assert_eq!(
+            passes.iter().map(|p| p.finished_at).collect::<Vec<_>>(),
+            [delay, delay + day, delay + 2 * day]
+        );
+        assert!(passes
+            .iter()
+            .all(|p| p.trigger == MaintenanceTrigger::Scheduled));
+        let ran = steps.ran.lock().unwrap();
+        assert_eq!(ran.len(), 3 * STEPS.len());
+        assert_eq!(ran[STEPS.len()], (MaintenanceStep::Retention, delay + day));
+    }
+
+    #[tokio::test]
+    async fn test_schedule_skips_when_disabled_or_snoozed() {
+        let scheduler = MaintenanceScheduler::default();
+        let steps = FakeSteps::default();
+        let clock = FakeClock::new(2);
+        let calls = AtomicU32::new(0);
+        let mut passes = 0;
+
+        let settings = || {
+            // First pass snoozed, second with automatic maintenance off
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => None,
+                _ => Some(HistorySettings {
+                    auto_maintenance: false,
+                    ..history()
+                }),
+            }
+        };
+        let schedule = scheduler.run_schedule(&steps, &clock, settings, |_| passes += 1);
+        let _ = tokio::time::timeout(Duration::from_millis(100), schedule).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(passes, 0);
+        assert!(steps.ran.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_restarts_the_wait() {
+        let scheduler = MaintenanceScheduler::default();
+        let steps = FakeSteps::default();
+        // Only the first wait ends; the daily one after it never does
+        let clock = FakeClock::new(1);
+        let ran = Notify::new();
+
+        let schedule =
+            scheduler.run_schedule(&steps, &clock, || Some(history()), |_| ran.notify_one());
+        let settings_changed = async {
+            ran.notified().await;
+            scheduler.reschedule();
+            std::future::pending::<()>().await;
+        };
+        let _ = tokio::time::timeout(Duration::from_millis(100), async {
+            tokio::join!(schedule, settings_changed)
+        })
+        .await;
+
+        assert_eq!(
+            *clock.slept.lock().unwrap(),
+            [STARTUP_DELAY, INTERVAL, STARTUP_DELAY]
+        );
+    }
+
+    // ===== Retention =====
+
+    async fn conversation(pool: &SqlitePool, at: i64) -> String {
+        let conversation = store::create_conversation(pool, None, at).await.unwrap();
+        store::append_message(
+            pool,
+            &conversation.id,
+            "user",
+            "hello",
+            &MessageMetadata::default(),
+            &[],
+            at,
+        )
+        .await
+        .unwrap();
+        conversation.id
+    }
+
+    async fn remaining(pool: &SqlitePool) -> usize {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM conversations")
+            .fetch_one(pool)
+            .await
+            .unwrap() as usize
+    }
+
+    #[tokio::test]
+    async fn test_prune_expired_cutoff() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let now = 100 * DAY_MS;
+        conversation(pool, now - 30 * DAY_MS - 1).await;
+        conversation(pool, now - 30 * DAY_MS).await;
+        let recent = conversation(pool, now - 30 * DAY_MS + 1).await;
+
+        let deleted = prune_expired(pool, 30, now).await.unwrap();
+
+        assert_eq!(deleted, 2);
+        assert_eq!(remaining(pool).await, 1);
+        assert!(store::get_conversation(pool, &recent).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_prune_expired_keeps_protected_conversations() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let pinned = conversation(pool, 1).await;
+        store::set_pinned(pool, &pinned, true).await.unwrap();
+        let archived = conversation(pool, 2).await;
+        store::set_archived(pool, &archived, true).await.unwrap();
+        let trashed = conversation(pool, 3).await;
+        store::delete_conversation(pool, &trashed, 4).await.unwrap();
+        let starred = conversation(pool, 5).await;
+        let message = store::get_conversation(pool, &starred)
+            .await
+            .unwrap()
+            .messages[0]
+            .id
+            .clone();
+        store::set_message_starred(pool, &message, true)
+            .await
+            .unwrap();
+        conversation(pool, 6).await;
+
+        let deleted = prune_expired(pool, 1, 10 * DAY_MS).await.unwrap();
+
+        assert_eq!(deleted, 1);
+        assert_eq!(remaining(pool).await, 4);
+    }
+
+    #[tokio::test]
+    async fn test_zero_retention_keeps_everything() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        conversation(pool, 0).await;
+
+        assert_eq!(prune_expired(pool, 0, 1_000 * DAY_MS).await.unwrap(), 0);
+        assert_eq!(remaining(pool).await, 1);
+    }
+
+    #[test]
+    fn test_summary_shape() {
+        let summary = MaintenanceSummary {
+            trigger: MaintenanceTrigger::Scheduled,
+            expired_conversations: 3,
+            purged_conversations: 1,
+            deleted_drafts: 2,
+            pruned_conversations: 0,
+            failed_steps: vec![MaintenanceStep::SizeCap],
+            finished_at: 42,
+        };
+
+        assert_eq!(
+            serde_json::to_value(summary).unwrap(),
+            serde_json::json!({
+                "trigger": "scheduled",
+                "expired_conversations": 3,
+                "purged_conversations": 1,
+                "deleted_drafts": 2,
+                "pruned_conversations": 0,
+                "failed_steps": ["size_cap"],
+                "finished_at": 42,
+            })
+        );
+    }
+}
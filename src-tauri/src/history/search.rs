@@ -0,0 +1,882 @@
+//! Searching messages (`search_history`).
+//!
+//! The text is matched case-insensitively anywhere in a message's content
+//! through the `messages_fts` trigram index (see [`crate::migrations`]),
+//! and can be narrowed by [`SearchFilters`]: a time range, role, provider,
+//! model, tag or conversation. Every filter is a bound parameter of one
+//! fixed query, so any combination works, including none, and no text
+//! with filters, which lists the matching messages. Messages in trashed
+//! conversations and replies replaced by a regeneration are left out.
+//!
+//! Text shorter than a trigram can't use the index and is matched with
+//! LIKE against `message_search`, the index's content.
+//!
+//! Encrypted content (`history.encrypt_content`) is only indexed with
+//! `history.search_encrypted` on: the triggers skip envelopes, so the
+//! plaintext is indexed by the code that seals it ([`index_sealed`]) and
+//! by [`sync_sealed`] when the setting changes. Otherwise encrypted
+//! messages are only found by their filters.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use sqlx::query::Query;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Row, Sqlite, SqliteConnection, SqlitePool};
+
+use super::crypto::{self, Cipher};
+use super::encryption::BATCH_SIZE;
+use super::store::{message_from_row, MESSAGE_COLUMNS};
+use super::tags::normalize_tag;
+use super::types::{HistoryError, Message, MESSAGE_ROLES};
+use crate::db;
+
+/// Shortest text the trigram index can match.
+const MIN_MATCH_CHARS: usize = 3;
+
+/// Conditions shared by the count and the page, over `messages m` joined
+/// to `conversations c`; `?1` is the FTS phrase, `?9` the LIKE pattern for
+/// shorter text, `?2`-`?8` the filters.
+const SEARCH_FROM: &str = "FROM messages m JOIN conversations c ON c.id = m.conversation_id
+ WHERE c.deleted_at IS NULL AND m.superseded_by IS NULL
+   AND (?1 IS NULL OR m.id IN (
+        SELECT s.message_id FROM message_search s
+        WHERE s.id IN (SELECT rowid FROM messages_fts WHERE messages_fts MATCH ?1)))
+   AND (?9 IS NULL OR m.id IN (
+        SELECT message_id FROM message_search WHERE content LIKE ?9 ESCAPE '\\'))
+   AND (?2 IS NULL OR m.created_at > ?2)
+   AND (?3 IS NULL OR m.created_at < ?3)
+   AND (?4 IS NULL OR m.role = ?4)
+   AND (?5 IS NULL OR m.provider = ?5 COLLATE NOCASE)
+   AND (?6 IS NULL OR m.model = ?6 COLLATE NOCASE)
+   AND (?7 IS NULL OR m.conversation_id IN (
+        SELECT ct.conversation_id FROM conversation_tags ct
+        JOIN tags t ON t.id = ct.tag_id
+        WHERE t.name = ?7))
+   AND (?8 IS NULL OR m.conversation_id = ?8)";
+
+/// What to narrow a search to; every field is optional.
+///
+/// Blank strings count as unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Only messages created after this Unix timestamp (ms)
+    pub after: Option<i64>,
+    /// Only messages created before this Unix timestamp (ms)
+    pub before: Option<i64>,
+    /// `"user"` or `"assistant"`
+    pub role: Option<String>,
+    /// Provider that generated the message, e.g. `"openai"`
+    pub provider: Option<String>,
+    /// Model that generated the message, e.g. `"gpt-4o"`
+    pub model: Option<String>,
+    /// Only conversations with this tag (normalized first)
+    pub tag: Option<String>,
+    /// Only this conversation
+    pub conversation_id: Option<String>,
+}
+
+/// A message found by `search_history`, with the title of its conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchHit {
+    /// Message row
+    #[serde(flatten)]
+    pub message: Message,
+    /// Title of the parent conversation
+    pub conversation_title: String,
+}
+
+/// Result of the `search_history` command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchResults {
+    /// Matching messages on this page, newest first
+    pub hits: Vec<SearchHit>,
+    /// Matching messages on every page
+    pub total: i64,
+    /// Names of the filters that were set, `"query"` included when text
+    /// was given, e.g. `["query", "after", "model"]`
+    pub applied_filters: Vec<&'static str>,
+}
+
+/// The filters as bound: trimmed, blanks dropped, role checked and tag
+/// normalized.
+struct Bound {
+    phrase: Option<String>,
+    pattern: Option<String>,
+    after: Option<i64>,
+    before: Option<i64>,
+    role: Option<String>,
+    provider: Option<String>,
+    model: Option<String>,
+    tag: Option<String>,
+    conversation_id: Option<String>,
+}
+
+impl Bound {
+    fn new(query: Option<&str>, filters: &SearchFilters) -> Result<Self, HistoryError> {
+        let role = non_blank(&filters.role);
+        if let Some(role) = &role {
+            if !MESSAGE_ROLES.contains(&role.as_str()) {
+                return Err(HistoryError::InvalidRole(role.clone()));
+            }
+        }
+        let text = query.map(str::trim).filter(|query| !query.is_empty());
+        let short = text.is_some_and(|text| text.chars().count() < MIN_MATCH_CHARS);
+        Ok(Self {
+            phrase: text.filter(|_| !short).map(fts_phrase),
+            pattern: text.filter(|_| short).map(like_pattern),
+            after: filters.after,
+            before: filters.before,
+            role,
+            provider: non_blank(&filters.provider),
+            model: non_blank(&filters.model),
+            tag: non_blank(&filters.tag)
+                .map(|tag| normalize_tag(&tag))
+                .transpose()?,
+            conversation_id: non_blank(&filters.conversation_id),
+        })
+    }
+
+    fn applied(&self) -> Vec<&'static str> {
+        [
+            ("query", self.phrase.is_some() || self.pattern.is_some()),
+            ("after", self.after.is_some()),
+            ("before", self.before.is_some()),
+            ("role", self.role.is_some()),
+            ("provider", self.provider.is_some()),
+            ("model", self.model.is_some()),
+            ("tag", self.tag.is_some()),
+            ("conversation_id", self.conversation_id.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect()
+    }
+
+    fn bind<'q>(
+        &'q self,
+        query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    ) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+        query
+            .bind(&self.phrase)
+            .bind(self.after)
+            .bind(self.before)
+            .bind(&self.role)
+            .bind(&self.provider)
+            .bind(&self.model)
+            .bind(&self.tag)
+            .bind(&self.conversation_id)
+            .bind(&self.pattern)
+    }
+}
+
+fn non_blank(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// An FTS5 query matching `text` as one phrase, with quotes, operators and
+/// column filters in it taken literally.
+fn fts_phrase(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// A LIKE pattern matching `text` anywhere, with `%`, `_` and `\` in it
+/// taken literally.
+fn like_pattern(text: &str) -> String {
+    let mut pattern = String::with_capacity(text.len() + 2);
+    pattern.push('%');
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Search messages, newest first.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `query` - Text to find; `None` or blank lists every message the
+///   filters match
+/// * `filters` - What to narrow the search to
+/// * `limit` - Page size
+/// * `offset` - Number of messages to skip
+///
+/// # Returns
+///
+/// * `Err(HistoryError::InvalidRole)` - `role` isn't `"user"` or `"assistant"`
+/// * `Err(HistoryError::InvalidTag)` - `tag` is too long
+pub async fn search(
+    pool: &SqlitePool,
+    query: Option<&str>,
+    filters: &SearchFilters,
+    limit: u32,
+    offset: u32,
+) -> Result<SearchResults, HistoryError> {
+    let bound = Bound::new(query, filters)?;
+
+    let count_sql = format!("SELECT COUNT(*) {}", SEARCH_FROM);
+    let total: i64 = bound
+        .bind(sqlx::query(&count_sql))
+        .fetch_one(pool)
+        .await?
+        .get(0);
+
+    let page_sql = format!(
+        "SELECT {}, c.title AS conversation_title {}
+         ORDER BY m.created_at DESC, m.id DESC
+         LIMIT ?10 OFFSET ?11",
+        MESSAGE_COLUMNS, SEARCH_FROM
+    );
+    let rows = bound
+        .bind(sqlx::query(&page_sql))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(SearchResults {
+        hits: rows
+            .iter()
+            .map(|row| SearchHit {
+                message: message_from_row(row),
+                conversation_title: row.get("conversation_title"),
+            })
+            .collect(),
+        total,
+        applied_filters: bound.applied(),
+    })
+}
+
+/// Whether the plaintext of encrypted messages is indexed
+/// (`history.search_encrypted`).
+static INDEX_SEALED: AtomicBool = AtomicBool::new(false);
+
+/// Set whether messages sealed from now on have their plaintext indexed.
+pub fn set_index_sealed(index: bool) {
+    INDEX_SEALED.store(index, Ordering::SeqCst);
+}
+
+/// Index the plaintext of a message that was just written.
+///
+/// Only does anything for sealed content with `history.search_encrypted`
+/// on; the triggers index plaintext content themselves.
+///
+/// # Arguments
+///
+/// * `conn` - Connection (or transaction) that wrote the message
+/// * `message_id` - ID of the message
+/// * `stored` - Content as written, possibly an envelope
+/// * `plaintext` - Content before sealing
+pub async fn index_sealed(
+    conn: &mut SqliteConnection,
+    message_id: &str,
+    stored: &str,
+    plaintext: &str,
+) -> Result<(), HistoryError> {
+    if !crypto::is_envelope(stored) || !INDEX_SEALED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    sqlx::query("INSERT OR IGNORE INTO message_search (message_id, content) VALUES (?, ?)")
+        .bind(message_id)
+        .bind(plaintext)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Bring the index of encrypted messages in line with
+/// `history.search_encrypted`: with `index` on, decrypt and index every
+/// sealed message that isn't yet, in batches of [`BATCH_SIZE`]; with it
+/// off, drop them all. Messages that can't be decrypted stay unindexed.
+///
+/// # Returns
+///
+/// Number of messages indexed or dropped
+pub async fn sync_sealed(
+    pool: &SqlitePool,
+    cipher: &Cipher,
+    index: bool,
+) -> Result<u64, HistoryError> {
+    if !index {
+        let result = sqlx::query(
+            "DELETE FROM message_search WHERE message_id IN (
+                 SELECT id FROM messages WHERE substr(content, 1, 4) = 'enc:')",
+        )
+        .execute(pool)
+        .await?;
+        return Ok(result.rows_affected());
+    }
+
+    let (mut indexed, mut after) = (0u64, 0i64);
+    loop {
+        let rows: Vec<(i64, String, String)> = sqlx::query_as(
+            "SELECT m.rowid, m.id, m.content FROM messages m
+             WHERE m.rowid > ? AND substr(m.content, 1, 4) = 'enc:'
+               AND NOT EXISTS (SELECT 1 FROM message_search s WHERE s.message_id = m.id)
+             ORDER BY m.rowid LIMIT ?",
+        )
+        .bind(after)
+        .bind(BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+        let Some(&(last, _, _)) = rows.last() else {
+            break;
+        };
+
+        let mut tx = db::begin_write(pool).await?;
+        for (_, id, content) in &rows {
+            let Ok(plaintext) = cipher.open(content) else {
+                continue;
+            };
+            sqlx::query("INSERT OR IGNORE INTO message_search (message_id, content) VALUES (?, ?)")
+                .bind(id)
+                .bind(plaintext)
+                .execute(&mut *tx)
+                .await?;
+            indexed += 1;
+        }
+        tx.commit().await?;
+        after = last;
+    }
+    Ok(indexed)
+}
+
+/// Merge the full-text index's segments into one, after a bulk change
+/// like an import.
+pub async fn optimize_index(pool: &SqlitePool) -> Result<(), HistoryError> {
+    sqlx::query("INSERT INTO messages_fts (messages_fts) VALUES ('optimize')")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Rebuild the full-text index from `message_search`.
+pub async fn rebuild_index(pool: &SqlitePool) -> Result<(), HistoryError> {
+    sqlx::query("INSERT INTO messages_fts (messages_fts) VALUES ('rebuild')")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::types::MessageMetadata;
+    use crate::history::{store, tags};
+
+    const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+    /// Two conversations:
+    ///
+    /// - "Containers" (tagged `devops`): docker questions answered by
+    ///   gpt-4o on day 1 and claude on day 10
+    /// - "Cooking": a pasta question answered by gpt-4o on day 20
+    struct Seeded {
+        db: Db,
+        containers: String,
+        cooking: String,
+    }
+
+    async fn seeded() -> Seeded {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let containers = store::create_conversation(pool, Some("Containers".into()), 0)
+            .await
+            .unwrap()
+            .id;
+        let cooking = store::create_conversation(pool, Some("Cooking".into()), 0)
+            .await
+            .unwrap()
+            .id;
+        tags::add_tag(pool, &containers, "DevOps").await.unwrap();
+
+        for (conversation, day, role, content, provider, model) in [
+            (
+                &containers,
+                1,
+                "user",
+                "How do I prune docker images?",
+                None,
+                None,
+            ),
+            (
+                &containers,
+                1,
+                "assistant",
+                "Run docker image prune -a.",
+                Some("openai"),
+                Some("gpt-4o"),
+            ),
+            (
+                &containers,
+                10,
+                "user",
+                "Docker compose vs swarm?",
+                None,
+                None,
+            ),
+            (
+                &containers,
+                10,
+                "assistant",
+                "Compose is for one host.",
+                Some("anthropic"),
+                Some("claude-3-5-sonnet"),
+            ),
+            (
+                &cooking,
+                20,
+                "user",
+                "How long to boil pasta? 100% al dente",
+                None,
+                None,
+            ),
+            (
+                &cooking,
+                20,
+                "assistant",
+                "About 9 minutes; no docker needed.",
+                Some("openai"),
+                Some("gpt-4o"),
+            ),
+        ] {
+            store::append_message(
+                pool,
+                conversation,
+                role,
+                content,
+                &MessageMetadata {
+                    provider: provider.map(str::to_string),
+                    model: model.map(str::to_string),
+                    ..MessageMetadata::default()
+                },
+                &[],
+                day * DAY_MS,
+            )
+            .await
+            .unwrap();
+        }
+        Seeded {
+            db,
+            containers,
+            cooking,
+        }
+    }
+
+    async fn contents(seeded: &Seeded, query: Option<&str>, filters: SearchFilters) -> Vec<String> {
+        search(seeded.db.pool(), query, &filters, 50, 0)
+            .await
+            .unwrap()
+            .hits
+            .into_iter()
+            .map(|hit| hit.message.content)
+            .collect()
+    }
+
+    // ===== Text =====
+
+    #[tokio::test]
+    async fn test_query_matches_case_insensitively_newest_first() {
+        let seeded = seeded().await;
+
+        let results = search(
+            seeded.db.pool(),
+            Some("DOCKER"),
+            &SearchFilters::default(),
+            50,
+            0,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(results.total, 4);
+        assert_eq!(results.applied_filters, ["query"]);
+        assert_eq!(
+            results.hits[0].message.content,
+            "About 9 minutes; no docker needed."
+        );
+        assert_eq!(results.hits[0].conversation_title, "Cooking");
+    }
+
+    #[tokio::test]
+    async fn test_wildcards_are_literal() {
+        let seeded = seeded().await;
+
+        assert_eq!(
+            contents(&seeded, Some("100%"), SearchFilters::default()).await,
+            ["How long to boil pasta? 100% al dente"]
+        );
+        assert!(contents(&seeded, Some("_"), SearchFilters::default())
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fts_syntax_is_literal() {
+        let seeded = seeded().await;
+
+        assert!(
+            contents(&seeded, Some("docker OR pasta"), SearchFilters::default())
+                .await
+                .is_empty()
+        );
+        assert!(
+            contents(&seeded, Some("\"docker"), SearchFilters::default())
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            contents(&seeded, Some("prune -a"), SearchFilters::default()).await,
+            ["Run docker image prune -a."]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_text_shorter_than_a_trigram() {
+        let seeded = seeded().await;
+
+        assert_eq!(
+            contents(&seeded, Some("zz"), SearchFilters::default()).await,
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            contents(&seeded, Some("-a"), SearchFilters::default()).await,
+            ["Run docker image prune -a."]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blank_query_without_filters_lists_everything() {
+        let seeded = seeded().await;
+
+        let results = search(
+            seeded.db.pool(),
+            Some("  "),
+            &SearchFilters::default(),
+            2,
+            0,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(results.total, 6);
+        assert_eq!(results.hits.len(), 2);
+        assert!(results.applied_filters.is_empty());
+    }
+
+    // ===== Single filters =====
+
+    #[tokio::test]
+    async fn test_after_and_before() {
+        let seeded = seeded().await;
+
+        let after = SearchFilters {
+            after: Some(5 * DAY_MS),
+            ..SearchFilters::default()
+        };
+        let before = SearchFilters {
+            before: Some(5 * DAY_MS),
+            ..SearchFilters::default()
+        };
+
+        assert_eq!(contents(&seeded, None, after).await.len(), 4);
+        assert_eq!(
+            contents(&seeded, None, before).await,
+            [
+                "Run docker image prune -a.",
+                "How do I prune docker images?"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_role() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            role: Some("user".into()),
+            ..SearchFilters::default()
+        };
+
+        assert_eq!(contents(&seeded, None, filters).await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_role_is_rejected() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            role: Some("system".into()),
+            ..SearchFilters::default()
+        };
+
+        assert_eq!(
+            search(seeded.db.pool(), None, &filters, 50, 0).await,
+            Err(HistoryError::InvalidRole("system".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_provider() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            provider: Some("Anthropic".into()),
+            ..SearchFilters::default()
+        };
+
+        assert_eq!(
+            contents(&seeded, None, filters).await,
+            ["Compose is for one host."]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_model() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            model: Some("gpt-4o".into()),
+            ..SearchFilters::default()
+        };
+
+        assert_eq!(
+            contents(&seeded, None, filters).await,
+            [
+                "About 9 minutes; no docker needed.",
+                "Run docker image prune -a."
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tag_is_normalized() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            tag: Some(" DEVOPS ".into()),
+            ..SearchFilters::default()
+        };
+
+        let results = search(seeded.db.pool(), None, &filters, 50, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(results.total, 4);
+        assert!(results
+            .hits
+            .iter()
+            .all(|hit| hit.message.conversation_id == seeded.containers));
+    }
+
+    #[tokio::test]
+    async fn test_conversation_id() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            conversation_id: Some(seeded.cooking.clone()),
+            ..SearchFilters::default()
+        };
+
+        assert_eq!(contents(&seeded, None, filters).await.len(), 2);
+    }
+
+    // ===== Combinations =====
+
+    #[tokio::test]
+    async fn test_query_with_model_and_time_range() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            after: Some(0),
+            before: Some(15 * DAY_MS),
+            model: Some("gpt-4o".into()),
+            ..SearchFilters::default()
+        };
+
+        let results = search(seeded.db.pool(), Some("docker"), &filters, 50, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(results.total, 1);
+        assert_eq!(
+            results.hits[0].message.content,
+            "Run docker image prune -a."
+        );
+        assert_eq!(
+            results.applied_filters,
+            ["query", "after", "before", "model"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filters_without_query_list_matches() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            role: Some("assistant".into()),
+            tag: Some("devops".into()),
+            provider: Some("  ".into()),
+            ..SearchFilters::default()
+        };
+
+        let results = search(seeded.db.pool(), None, &filters, 50, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(results.total, 2);
+        assert_eq!(results.applied_filters, ["role", "tag"]);
+    }
+
+    #[tokio::test]
+    async fn test_total_counts_every_page() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            conversation_id: Some(seeded.containers.clone()),
+            ..SearchFilters::default()
+        };
+
+        let page = search(seeded.db.pool(), Some("o"), &filters, 1, 1)
+            .await
+            .unwrap();
+
+        assert_eq!(page.total, 4);
+        assert_eq!(page.hits.len(), 1);
+        assert_eq!(page.hits[0].message.content, "Docker compose vs swarm?");
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_filters_match_nothing() {
+        let seeded = seeded().await;
+        let filters = SearchFilters {
+            role: Some("user".into()),
+            model: Some("gpt-4o".into()),
+            ..SearchFilters::default()
+        };
+
+        let results = search(seeded.db.pool(), None, &filters, 50, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(results.total, 0);
+        assert!(results.hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trashed_conversations_are_left_out() {
+        let seeded = seeded().await;
+        store::delete_conversation(seeded.db.pool(), &seeded.cooking, 30 * DAY_MS)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            contents(&seeded, Some("docker"), SearchFilters::default())
+                .await
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_like_pattern_escapes() {
+        assert_eq!(like_pattern(r"50%_a\b"), r"%50\%\_a\\b%");
+    }
+
+    // ===== Encrypted content =====
+
+    /// A sealed message in the "Containers" conversation, as written with
+    /// encryption on: the triggers leave it unindexed.
+    async fn insert_sealed(seeded: &Seeded, cipher: &Cipher, plaintext: &str) -> (String, String) {
+        let id = uuid::Uuid::now_v7().to_string();
+        let sealed = cipher.seal(plaintext).unwrap();
+        sqlx::query(
+            "INSERT INTO messages (id, conversation_id, role, content, created_at)
+             VALUES (?, ?, 'user', ?, 0)",
+        )
+        .bind(&id)
+        .bind(&seeded.containers)
+        .bind(&sealed)
+        .execute(seeded.db.pool())
+        .await
+        .unwrap();
+        (id, sealed)
+    }
+
+    async fn hit_ids(seeded: &Seeded, query: &str) -> Vec<String> {
+        search(
+            seeded.db.pool(),
+            Some(query),
+            &SearchFilters::default(),
+            50,
+            0,
+        )
+        .await
+        .unwrap()
+        .hits
+        .into_iter()
+        .map(|hit| hit.message.id)
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_content_found_with_search_encrypted() {
+        let seeded = seeded().await;
+        let cipher = Cipher::new(&Cipher::generate_key().unwrap()).unwrap();
+        let (id, _) = insert_sealed(&seeded, &cipher, "Quarterly revenue by region").await;
+        assert!(hit_ids(&seeded, "revenue").await.is_empty());
+
+        let indexed = sync_sealed(seeded.db.pool(), &cipher, true).await.unwrap();
+
+        assert_eq!(indexed, 1);
+        assert_eq!(hit_ids(&seeded, "revenue").await, vec![id.clone()]);
+        assert_eq!(hit_ids(&seeded, "by").await, [id]);
+        assert_eq!(
+            sync_sealed(seeded.db.pool(), &cipher, true).await.unwrap(),
+            0
+        );
+
+        let dropped = sync_sealed(seeded.db.pool(), &cipher, false).await.unwrap();
+
+        assert_eq!(dropped, 1);
+        assert!(hit_ids(&seeded, "revenue").await.is_empty());
+        assert_eq!(
+            contents(&seeded, Some("docker"), SearchFilters::default())
+                .await
+                .len(),
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sealed_message_indexed_when_written() {
+        let seeded = seeded().await;
+        let cipher = Cipher::new(&Cipher::generate_key().unwrap()).unwrap();
+        let (id, sealed) = insert_sealed(&seeded, &cipher, "Quarterly revenue").await;
+        let mut conn = seeded.db.pool().acquire().await.unwrap();
+
+        set_index_sealed(true);
+        index_sealed(&mut conn, &id, &sealed, "Quarterly revenue")
+            .await
+            .unwrap();
+        set_index_sealed(false);
+        drop(conn);
+
+        assert_eq!(hit_ids(&seeded, "revenue").await, [id]);
+    }
+
+    #[tokio::test]
+    async fn test_undecryptable_content_stays_unindexed() {
+        let seeded = seeded().await;
+        let other = Cipher::new(&Cipher::generate_key().unwrap()).unwrap();
+        insert_sealed(&seeded, &other, "Quarterly revenue").await;
+        let cipher = Cipher::new(&Cipher::generate_key().unwrap()).unwrap();
+
+        let indexed = sync_sealed(seeded.db.pool(), &cipher, true).await.unwrap();
+
+        assert_eq!(indexed, 0);
+        assert!(hit_ids(&seeded, "revenue").await.is_empty());
+    }
+}
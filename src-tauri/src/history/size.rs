@@ -0,0 +1,472 @@
+//! Keeping the history database under `history.max_db_size_mb`.
+//!
+//! The hourly check run by the backup scheduler compares the database's
+//! size on disk, main file and WAL together, with the limit. Over it, it
+//! sends `history-size-warning` with what the user can do about it, and
+//! with `history.auto_prune` on first deletes the oldest conversations
+//! until the database is back under [`PRUNE_TARGET_PERCENT`] of the limit.
+//!
+//! Pruning never touches pinned or archived conversations, trashed ones
+//! (which the trash purge handles), or conversations with a starred
+//! message. What is deleted is chosen by [`prune_candidates`]: oldest
+//! update first, ties broken by ID, so the same database always loses the
+//! same conversations.
+
+use std::path::Path;
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use super::search;
+use super::types::HistoryError;
+use crate::db;
+use crate::settings::HistorySettings;
+
+/// Event sent when the database is over `history.max_db_size_mb`.
+pub const WARNING_EVENT: &str = "history-size-warning";
+
+/// Share of the limit pruning brings the database back under.
+pub const PRUNE_TARGET_PERCENT: u64 = 90;
+
+const MB: u64 = 1024 * 1024;
+
+/// Something the user can do about a large database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestedAction {
+    /// Permanently delete trashed conversations
+    EmptyTrash,
+    /// `optimize_history_db` gives the space of deleted rows back
+    Optimize,
+    /// Turn on `history.auto_prune`
+    EnableAutoPrune,
+    /// Export the history, then delete it
+    ExportAndDelete,
+    /// Raise `history.max_db_size_mb`
+    RaiseLimit,
+}
+
+/// Payload of the `history-size-warning` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeWarning {
+    /// Size on disk in bytes, including the WAL file, after any pruning
+    pub size_bytes: u64,
+    /// `history.max_db_size_mb` in bytes
+    pub limit_bytes: u64,
+    /// Conversations deleted by `history.auto_prune`
+    pub pruned_conversations: u64,
+    /// What the user can do, most useful first
+    pub suggested_actions: Vec<SuggestedAction>,
+}
+
+/// The limit in bytes, if one is set.
+pub fn limit_bytes(settings: &HistorySettings) -> Option<u64> {
+    settings
+        .max_db_size_mb
+        .filter(|mb| *mb > 0)
+        .map(|mb| u64::from(mb) * MB)
+}
+
+/// Size of a database file plus its `-wal` file.
+///
+/// Missing files count as empty.
+pub fn on_disk_size(path: &Path) -> u64 {
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    [path, Path::new(&wal)]
+        .iter()
+        .filter_map(|file| std::fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Conversations to delete to free about `bytes` of database space, in
+/// the order to delete them.
+///
+/// A conversation's share of the database is estimated from its share of
+/// the stored message content, scaled to the pages in use. Candidates are
+/// taken oldest update first (ties by ID) until their estimate adds up to
+/// `bytes`; pinned, archived and trashed conversations and ones with a
+/// starred message are skipped.
+pub async fn prune_candidates(pool: &SqlitePool, bytes: u64) -> Result<Vec<String>, HistoryError> {
+    if bytes == 0 {
+        return Ok(Vec::new());
+    }
+    let (used, content): (i64, i64) = sqlx::query_as(
+        "SELECT (SELECT page_count - freelist_count FROM pragma_page_count, pragma_freelist_count)
+                  * (SELECT page_size FROM pragma_page_size),
+                (SELECT COALESCE(SUM(length(CAST(content AS BLOB))), 0) FROM messages)",
+    )
+    .fetch_one(pool)
+    .await?;
+    if content == 0 {
+        return Ok(Vec::new());
+    }
+    // Content bytes that stand for `bytes` of pages
+    let content_to_free = (bytes as f64 * content as f64 / used.max(1) as f64).ceil() as i64;
+
+    let ids = sqlx::query_scalar(
+        "SELECT id FROM (
+             SELECT c.id,
+                    SUM(COALESCE(s.bytes, 0)) OVER (
+                        ORDER BY c.updated_at ASC, c.id ASC ROWS UNBOUNDED PRECEDING
+                    ) - COALESCE(s.bytes, 0) AS freed_before
+             FROM conversations c
+             LEFT JOIN (
+                 SELECT conversation_id, SUM(length(CAST(content AS BLOB))) AS bytes
+                 FROM messages GROUP BY conversation_id
+             ) s ON s.conversation_id = c.id
+             WHERE c.pinned = 0 AND c.archived = 0 AND c.deleted_at IS NULL
+               AND NOT EXISTS (
+                   SELECT 1 FROM messages m WHERE m.conversation_id = c.id AND m.starred = 1)
+         )
+         WHERE freed_before < ?
+         ORDER BY freed_before ASC, id ASC",
+    )
+    .bind(content_to_free)
+    .fetch_all(pool)
+    .await?;
+    Ok(ids)
+}
+
+/// Delete conversations permanently, in one transaction.
+///
+/// # Returns
+///
+/// Number of conversations deleted
+pub async fn prune(pool: &SqlitePool, ids: &[String]) -> Result<u64, HistoryError> {
+    let mut tx = db::begin_write(pool).await?;
+    let mut deleted = 0;
+    for id in ids {
+        deleted += sqlx::query("DELETE FROM conversations WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+/// Give the space of deleted rows back to the file system.
+///
+/// The search index only drops deleted messages when its segments are
+/// merged, so that comes first.
+async fn compact(pool: &SqlitePool) -> Result<(), HistoryError> {
+    search::optimize_index(pool).await?;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await?;
+    sqlx::query("VACUUM").execute(pool).await?;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// What to suggest for a database over its limit.
+///
+/// # Arguments
+///
+/// * `trashed` - Whether there are trashed conversations
+/// * `free_bytes` - Space held by deleted rows, which `VACUUM` gives back
+/// * `over_bytes` - How far over the limit the database is
+/// * `auto_prune` - `history.auto_prune`
+pub fn suggested_actions(
+    trashed: bool,
+    free_bytes: u64,
+    over_bytes: u64,
+    auto_prune: bool,
+) -> Vec<SuggestedAction> {
+    let mut actions = Vec::new();
+    if trashed {
+        actions.push(SuggestedAction::EmptyTrash);
+    }
+    if free_bytes > 0 && free_bytes >= over_bytes / 2 {
+        actions.push(SuggestedAction::Optimize);
+    }
+    if !auto_prune {
+        actions.push(SuggestedAction::EnableAutoPrune);
+    }
+    actions.push(SuggestedAction::ExportAndDelete);
+    actions.push(SuggestedAction::RaiseLimit);
+    actions
+}
+
+/// Compare the database at `path` with `history.max_db_size_mb`, pruning
+/// first if `history.auto_prune` is on.
+///
+/// # Returns
+///
+/// * `Ok(None)` - No limit, or the database is under it
+/// * `Ok(Some(warning))` - The database was over the limit; pruning may
+///   have brought it back under, which `size_bytes` tells
+pub async fn check(
+    pool: &SqlitePool,
+    path: &Path,
+    settings: &HistorySettings,
+) -> Result<Option<SizeWarning>, HistoryError> {
+    let Some(limit) = limit_bytes(settings) else {
+        return Ok(None);
+    };
+    let size = on_disk_size(path);
+    if size <= limit {
+        return Ok(None);
+    }
+
+    let mut pruned = 0;
+    if settings.auto_prune {
+        // Pages in the WAL are also in the main file once checkpointed;
+        // counted twice they'd make pruning take too much
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(pool)
+            .await?;
+        let size = on_disk_size(path);
+        let target = limit / 100 * PRUNE_TARGET_PERCENT;
+        let ids = prune_candidates(pool, size.saturating_sub(target)).await?;
+        if !ids.is_empty() {
+            pruned = prune(pool, &ids).await?;
+            compact(pool).await?;
+            tracing::info!(pruned, "Pruned old conversations over the size limit");
+        }
+    }
+
+    let (trashed, free_pages, page_size): (bool, i64, i64) = sqlx::query_as(
+        "SELECT EXISTS(SELECT 1 FROM conversations WHERE deleted_at IS NOT NULL),
+                (SELECT freelist_count FROM pragma_freelist_count),
+                (SELECT page_size FROM pragma_page_size)",
+    )
+    .fetch_one(pool)
+    .await?;
+    let size = on_disk_size(path);
+    Ok(Some(SizeWarning {
+        size_bytes: size,
+        limit_bytes: limit,
+        pruned_conversations: pruned,
+        suggested_actions: suggested_actions(
+            trashed,
+            (free_pages * page_size).max(0) as u64,
+            size.saturating_sub(limit),
+            settings.auto_prune,
+        ),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Db, DATABASE_FILE};
+    use crate::history::store;
+    use crate::history::types::MessageMetadata;
+
+    /// A conversation updated at `at` with one message of `bytes` bytes.
+    async fn conversation(pool: &SqlitePool, at: i64, bytes: usize) -> String {
+        let conversation = store::create_conversation(pool, None, at).await.unwrap();
+        store::append_message(
+            pool,
+            &conversation.id,
+            "user",
+            &"x".repeat(bytes),
+            &MessageMetadata::default(),
+            &[],
+            at,
+        )
+        .await
+        .unwrap();
+        conversation.id
+    }
+
+    fn settings(max_db_size_mb: Option<u32>, auto_prune: bool) -> HistorySettings {
+        HistorySettings {
+            max_db_size_mb,
+            auto_prune,
+            ..HistorySettings::default()
+        }
+    }
+
+    async fn conversation_ids(pool: &SqlitePool) -> Vec<String> {
+        sqlx::query_scalar("SELECT id FROM conversations ORDER BY updated_at, id")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    // ===== Candidates =====
+
+    #[tokio::test]
+    async fn test_candidates_oldest_first_until_enough() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let newest = conversation(pool, 30, 10_000).await;
+        let oldest = conversation(pool, 10, 10_000).await;
+        let middle = conversation(pool, 20, 10_000).await;
+
+        let one = prune_candidates(pool, 1).await.unwrap();
+        let all = prune_candidates(pool, u64::MAX / 4).await.unwrap();
+
+        assert_eq!(all, [oldest, middle, newest]);
+        assert_eq!(one, all[..1]);
+    }
+
+    #[tokio::test]
+    async fn test_candidates_are_deterministic_on_ties() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        for _ in 0..4 {
+            conversation(pool, 5, 1_000).await;
+        }
+        let mut by_id = conversation_ids(pool).await;
+        by_id.sort();
+
+        let first = prune_candidates(pool, u64::MAX / 4).await.unwrap();
+        let second = prune_candidates(pool, u64::MAX / 4).await.unwrap();
+
+        assert_eq!(first, by_id);
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_protected_conversations_are_never_candidates() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let pinned = conversation(pool, 1, 1_000).await;
+        store::set_pinned(pool, &pinned, true).await.unwrap();
+        let archived = conversation(pool, 2, 1_000).await;
+        store::set_archived(pool, &archived, true).await.unwrap();
+        let trashed = conversation(pool, 3, 1_000).await;
+        store::delete_conversation(pool, &trashed, 4).await.unwrap();
+        let starred = conversation(pool, 5, 1_000).await;
+        let message = store::get_conversation(pool, &starred)
+            .await
+            .unwrap()
+            .messages[0]
+            .id
+            .clone();
+        store::set_message_starred(pool, &message, true)
+            .await
+            .unwrap();
+        let plain = conversation(pool, 6, 1_000).await;
+
+        let candidates = prune_candidates(pool, u64::MAX / 4).await.unwrap();
+
+        assert_eq!(candidates, [plain]);
+    }
+
+    #[tokio::test]
+    async fn test_no_candidates_for_nothing_to_free() {
+        let db = Db::in_memory().await.unwrap();
+        conversation(db.pool(), 1, 1_000).await;
+
+        assert!(prune_candidates(db.pool(), 0).await.unwrap().is_empty());
+    }
+
+    // ===== Check =====
+
+    #[tokio::test]
+    async fn test_size_includes_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        std::fs::write(&path, vec![0; 100]).unwrap();
+        assert_eq!(on_disk_size(&path), 100);
+
+        std::fs::write(
+            dir.path().join(format!("{}-wal", DATABASE_FILE)),
+            vec![0; 50],
+        )
+        .unwrap();
+        assert_eq!(on_disk_size(&path), 150);
+        assert_eq!(on_disk_size(&dir.path().join("missing.db")), 0);
+    }
+
+    #[tokio::test]
+    async fn test_under_limit_or_no_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        let db = Db::open(&path).await.unwrap();
+
+        for limit in [None, Some(0), Some(100)] {
+            assert_eq!(
+                check(db.pool(), &path, &settings(limit, true))
+                    .await
+                    .unwrap(),
+                None
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_over_limit_warns_without_pruning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        let db = Db::open(&path).await.unwrap();
+        for at in 0..3 {
+            conversation(db.pool(), at, 600_000).await;
+        }
+
+        let warning = check(db.pool(), &path, &settings(Some(1), false))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(warning.size_bytes > MB);
+        assert_eq!(warning.limit_bytes, MB);
+        assert_eq!(warning.pruned_conversations, 0);
+        assert!(warning
+            .suggested_actions
+            .contains(&SuggestedAction::EnableAutoPrune));
+        assert_eq!(conversation_ids(db.pool()).await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_auto_prune_gets_back_under_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        let db = Db::open(&path).await.unwrap();
+        let mut ids = Vec::new();
+        for at in 0..6 {
+            ids.push(conversation(db.pool(), at, 400_000).await);
+        }
+        let pinned = ids[0].clone();
+        store::set_pinned(db.pool(), &pinned, true).await.unwrap();
+
+        // Each message is stored with its search index: about three times
+        // its length
+        let warning = check(db.pool(), &path, &settings(Some(5), true))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(warning.pruned_conversations > 0);
+        assert!(warning.size_bytes <= 5 * MB / 100 * PRUNE_TARGET_PERCENT);
+        let left = conversation_ids(db.pool()).await;
+        assert!(left.contains(&pinned));
+        // The newest conversations are the ones kept
+        assert_eq!(left.last(), ids.last());
+        assert!(!warning
+            .suggested_actions
+            .contains(&SuggestedAction::EnableAutoPrune));
+    }
+
+    // ===== Suggestions =====
+
+    #[test]
+    fn test_suggested_actions() {
+        assert_eq!(
+            suggested_actions(true, 10 * MB, 4 * MB, false),
+            [
+                SuggestedAction::EmptyTrash,
+                SuggestedAction::Optimize,
+                SuggestedAction::EnableAutoPrune,
+                SuggestedAction::ExportAndDelete,
+                SuggestedAction::RaiseLimit,
+            ]
+        );
+        assert_eq!(
+            suggested_actions(false, 0, 4 * MB, true),
+            [
+                SuggestedAction::ExportAndDelete,
+                SuggestedAction::RaiseLimit
+            ]
+        );
+    }
+}
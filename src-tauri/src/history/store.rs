@@ -0,0 +1,1481 @@
+//! SQL access for conversations and messages.
+//!
+//! Plain functions over a [`SqlitePool`] so they can be tested against an
+//! in-memory database. IDs and timestamps are always generated here, never
+//! taken from the frontend.
+
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqliteConnection, SqlitePool};
+
+use super::crypto;
+use super::search;
+use super::tags::normalize_tag;
+use super::types::{
+    Attachment, Conversation, ConversationSummary, ConversationWithMessages, HistoryError, Message,
+    MessageMetadata, NewAttachment, StarredMessage, DEFAULT_TITLE, MESSAGE_ROLES, SNIPPET_LENGTH,
+    TRASH_RETENTION_MS,
+};
+use crate::db;
+
+/// Columns selected for [`conversation_from_row`].
+const CONVERSATION_COLUMNS: &str =
+    "id, title, created_at, updated_at, pinned, archived, deleted_at,
+        branched_from_conversation_id, branched_from_message_id, system_prompt_override";
+
+/// Columns selected for [`message_from_row`], from `messages m`.
+pub(super) const MESSAGE_COLUMNS: &str =
+    "m.id, m.conversation_id, m.role, m.content, m.created_at, m.starred,
+        m.parent_message_id, m.superseded_by,
+        m.provider, m.model, m.prompt_tokens, m.completion_tokens, m.duration_ms";
+
+/// Summary query over `conversations c`; callers append `WHERE`/`ORDER BY`.
+const SUMMARY_SELECT: &str =
+    "SELECT c.id, c.title, c.created_at, c.updated_at, c.pinned, c.archived, c.deleted_at,
+        c.branched_from_conversation_id, c.branched_from_message_id, c.system_prompt_override,
+        (SELECT COUNT(*) FROM messages m
+          WHERE m.conversation_id = c.id AND m.superseded_by IS NULL) AS message_count,
+        (SELECT CASE WHEN substr(m.content, 1, 4) = 'enc:' THEN m.content
+                     ELSE substr(m.content, 1, ?) END FROM messages m
+          WHERE m.conversation_id = c.id AND m.superseded_by IS NULL
+          ORDER BY m.created_at DESC, m.id DESC LIMIT 1) AS snippet
+ FROM conversations c";
+
+/// Create an empty conversation.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `title` - Title, or `None` / blank for [`DEFAULT_TITLE`]
+/// * `now_ms` - Current Unix timestamp (ms)
+pub async fn create_conversation(
+    pool: &SqlitePool,
+    title: Option<String>,
+    now_ms: i64,
+) -> Result<Conversation, HistoryError> {
+    let title = title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| DEFAULT_TITLE.to_string());
+    let conversation = Conversation {
+        id: uuid::Uuid::now_v7().to_string(),
+        title,
+        created_at: now_ms,
+        updated_at: now_ms,
+        pinned: false,
+        archived: false,
+        deleted_at: None,
+        branched_from_conversation_id: None,
+        branched_from_message_id: None,
+        system_prompt_override: None,
+    };
+
+    sqlx::query(
+        "INSERT INTO conversations (id, title, created_at, updated_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(&conversation.id)
+    .bind(&conversation.title)
+    .bind(conversation.created_at)
+    .bind(conversation.updated_at)
+    .execute(pool)
+    .await?;
+
+    Ok(conversation)
+}
+
+/// List conversations: pinned first, then most recently updated.
+///
+/// Trashed conversations are never included.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `limit` - Page size
+/// * `offset` - Number of conversations to skip
+/// * `include_archived` - Also return archived conversations
+/// * `tag` - Only return conversations with this tag (normalized first)
+pub async fn list_conversations(
+    pool: &SqlitePool,
+    limit: u32,
+    offset: u32,
+    include_archived: bool,
+    tag: Option<&str>,
+) -> Result<Vec<ConversationSummary>, HistoryError> {
+    let tag = tag.map(normalize_tag).transpose()?;
+    let sql = format!(
+        "{} WHERE c.deleted_at IS NULL AND (? OR c.archived = 0)
+           AND (? IS NULL OR c.id IN (
+                SELECT ct.conversation_id FROM conversation_tags ct
+                JOIN tags t ON t.id = ct.tag_id
+                WHERE t.name = ?))
+         ORDER BY c.pinned DESC, c.updated_at DESC, c.id DESC
+         LIMIT ? OFFSET ?",
+        SUMMARY_SELECT
+    );
+    let rows = sqlx::query(&sql)
+        .bind(SNIPPET_LENGTH)
+        .bind(include_archived)
+        .bind(&tag)
+        .bind(&tag)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(summary_from_row).collect())
+}
+
+/// List trashed conversations, most recently trashed first.
+pub async fn list_trashed_conversations(
+    pool: &SqlitePool,
+) -> Result<Vec<ConversationSummary>, HistoryError> {
+    let sql = format!(
+        "{} WHERE c.deleted_at IS NOT NULL ORDER BY c.deleted_at DESC, c.id DESC",
+        SUMMARY_SELECT
+    );
+    let rows = sqlx::query(&sql)
+        .bind(SNIPPET_LENGTH)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(summary_from_row).collect())
+}
+
+/// Load a conversation and all of its messages, oldest first.
+///
+/// Branches carry their lineage in `branched_from_*`.
+pub async fn get_conversation(
+    pool: &SqlitePool,
+    id: &str,
+) -> Result<ConversationWithMessages, HistoryError> {
+    let conversation = find_conversation(pool, id).await?;
+
+    let messages = sqlx::query(&format!(
+        "SELECT {} FROM messages m WHERE m.conversation_id = ?
+         ORDER BY m.created_at ASC, m.id ASC",
+        MESSAGE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(message_from_row)
+    .collect();
+
+    Ok(ConversationWithMessages {
+        conversation,
+        messages,
+    })
+}
+
+/// Load the most recently updated conversation with its newest messages.
+///
+/// Archived and trashed conversations are skipped. Messages are returned
+/// oldest first, like [`get_conversation`], but only the last
+/// `message_limit` of them (superseded replies left out).
+///
+/// # Returns
+///
+/// * `Ok(None)` - No active conversation exists
+pub async fn get_last_conversation(
+    pool: &SqlitePool,
+    message_limit: u32,
+) -> Result<Option<ConversationWithMessages>, HistoryError> {
+    let Some(conversation) = sqlx::query(&format!(
+        "SELECT {} FROM conversations
+         WHERE archived = 0 AND deleted_at IS NULL
+         ORDER BY updated_at DESC, id DESC
+         LIMIT 1",
+        CONVERSATION_COLUMNS
+    ))
+    .fetch_optional(pool)
+    .await?
+    .map(|row| conversation_from_row(&row)) else {
+        return Ok(None);
+    };
+
+    let mut messages = get_messages(pool, &conversation.id, None, message_limit, false).await?;
+    messages.reverse();
+
+    Ok(Some(ConversationWithMessages {
+        conversation,
+        messages,
+    }))
+}
+
+/// Change a conversation's title.
+///
+/// Marks the title as user-edited so it is never replaced by a generated one.
+pub async fn rename_conversation(
+    pool: &SqlitePool,
+    id: &str,
+    title: &str,
+) -> Result<(), HistoryError> {
+    let result = sqlx::query("UPDATE conversations SET title = ?, auto_title = 0 WHERE id = ?")
+        .bind(title.trim())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(HistoryError::NotFound(id.to_string()));
+    }
+    Ok(())
+}
+
+/// Pin or unpin a conversation.
+pub async fn set_pinned(pool: &SqlitePool, id: &str, pinned: bool) -> Result<(), HistoryError> {
+    let result = sqlx::query("UPDATE conversations SET pinned = ? WHERE id = ?")
+        .bind(pinned)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(HistoryError::NotFound(id.to_string()));
+    }
+    Ok(())
+}
+
+/// Set or clear a conversation's own system prompt.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `id` - Conversation to change
+/// * `prompt` - System prompt for this conversation; `None` or blank goes
+///   back to the global one
+pub async fn set_system_prompt(
+    pool: &SqlitePool,
+    id: &str,
+    prompt: Option<&str>,
+) -> Result<(), HistoryError> {
+    let prompt = prompt.filter(|p| !p.trim().is_empty());
+    let result = sqlx::query("UPDATE conversations SET system_prompt_override = ? WHERE id = ?")
+        .bind(prompt)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(HistoryError::NotFound(id.to_string()));
+    }
+    Ok(())
+}
+
+/// Archive or unarchive a conversation.
+pub async fn set_archived(pool: &SqlitePool, id: &str, archived: bool) -> Result<(), HistoryError> {
+    let result = sqlx::query("UPDATE conversations SET archived = ? WHERE id = ?")
+        .bind(archived)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(HistoryError::NotFound(id.to_string()));
+    }
+    Ok(())
+}
+
+/// Move a conversation to the trash. Its messages are kept until purge.
+pub async fn delete_conversation(
+    pool: &SqlitePool,
+    id: &str,
+    now_ms: i64,
+) -> Result<(), HistoryError> {
+    let result =
+        sqlx::query("UPDATE conversations SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+            .bind(now_ms)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(HistoryError::NotFound(id.to_string()));
+    }
+    Ok(())
+}
+
+/// Take a conversation out of the trash.
+pub async fn restore_conversation(pool: &SqlitePool, id: &str) -> Result<(), HistoryError> {
+    let result = sqlx::query(
+        "UPDATE conversations SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(HistoryError::NotFound(id.to_string()));
+    }
+    Ok(())
+}
+
+/// Permanently delete a conversation (trashed or not); its messages are
+/// removed by `ON DELETE CASCADE`.
+pub async fn purge_conversation(pool: &SqlitePool, id: &str) -> Result<(), HistoryError> {
+    let result = sqlx::query("DELETE FROM conversations WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(HistoryError::NotFound(id.to_string()));
+    }
+    Ok(())
+}
+
+/// Permanently delete conversations trashed more than
+/// [`TRASH_RETENTION_MS`] before `now_ms`.
+///
+/// # Returns
+///
+/// Number of conversations purged
+pub async fn purge_expired_trash(pool: &SqlitePool, now_ms: i64) -> Result<u64, HistoryError> {
+    let result = sqlx::query("DELETE FROM conversations WHERE deleted_at <= ?")
+        .bind(now_ms - TRASH_RETENTION_MS)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Append a message, bump the conversation's `updated_at` and unarchive it.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `conversation_id` - Parent conversation; must exist
+/// * `role` - `"user"` or `"assistant"`
+/// * `content` - Message text
+/// * `metadata` - Provider/model/token details, if any
+/// * `attachments` - Files added to the prompt, recorded in the same
+///   transaction
+/// * `now_ms` - Current Unix timestamp (ms)
+///
+/// # Returns
+///
+/// * `Err(HistoryError::InvalidRole)` - `role` is not a known role
+/// * `Err(HistoryError::NotFound)` - The conversation does not exist
+pub async fn append_message(
+    pool: &SqlitePool,
+    conversation_id: &str,
+    role: &str,
+    content: &str,
+    metadata: &MessageMetadata,
+    attachments: &[NewAttachment],
+    now_ms: i64,
+) -> Result<Message, HistoryError> {
+    if !MESSAGE_ROLES.contains(&role) {
+        return Err(HistoryError::InvalidRole(role.to_string()));
+    }
+
+    let mut tx = db::begin_write(pool).await?;
+
+    let parent_message_id = if role == "assistant" {
+        latest_user_message(&mut tx, conversation_id).await?
+    } else {
+        None
+    };
+
+    let message = Message {
+        id: uuid::Uuid::now_v7().to_string(),
+        conversation_id: conversation_id.to_string(),
+        role: role.to_string(),
+        content: content.to_string(),
+        created_at: now_ms,
+        starred: false,
+        parent_message_id,
+        superseded_by: None,
+        metadata: metadata.clone(),
+    };
+
+    let stored = crypto::seal_new(&message.content)?;
+    sqlx::query(
+        "INSERT INTO messages (id, conversation_id, role, content, created_at, parent_message_id, provider, model, prompt_tokens, completion_tokens, duration_ms)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&message.id)
+    .bind(&message.conversation_id)
+    .bind(&message.role)
+    .bind(&stored)
+    .bind(message.created_at)
+    .bind(&message.parent_message_id)
+    .bind(&metadata.provider)
+    .bind(&metadata.model)
+    .bind(metadata.prompt_tokens.map(i64::from))
+    .bind(metadata.completion_tokens.map(i64::from))
+    .bind(metadata.duration_ms.map(i64::from))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e.as_database_error() {
+        Some(db) if db.is_foreign_key_violation() => {
+            HistoryError::NotFound(conversation_id.to_string())
+        }
+        _ => HistoryError::from(e),
+    })?;
+    search::index_sealed(&mut tx, &message.id, &stored, &message.content).await?;
+
+    for attachment in attachments {
+        sqlx::query(
+            "INSERT INTO attachments (id, message_id, name, size, line_count, truncated, lossy, mime, data, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(uuid::Uuid::now_v7().to_string())
+        .bind(&message.id)
+        .bind(&attachment.name)
+        .bind(i64::try_from(attachment.size).unwrap_or(i64::MAX))
+        .bind(i64::try_from(attachment.line_count).unwrap_or(i64::MAX))
+        .bind(attachment.truncated)
+        .bind(attachment.lossy)
+        .bind(&attachment.mime)
+        .bind(attachment.data.as_deref().map(crypto::seal_new).transpose()?)
+        .bind(now_ms)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query("UPDATE conversations SET updated_at = ?, archived = 0 WHERE id = ?")
+        .bind(now_ms)
+        .bind(conversation_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(message)
+}
+
+/// Get a page of messages, newest first.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `conversation_id` - Conversation to read
+/// * `before` - Only return messages created strictly before this
+///   timestamp (ms); `None` starts from the newest message
+/// * `limit` - Maximum number of messages to return
+/// * `include_superseded` - Also return replies replaced by a regeneration;
+///   by default only the latest reply to each user message is returned
+pub async fn get_messages(
+    pool: &SqlitePool,
+    conversation_id: &str,
+    before: Option<i64>,
+    limit: u32,
+    include_superseded: bool,
+) -> Result<Vec<Message>, HistoryError> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM messages m
+         WHERE m.conversation_id = ? AND (? IS NULL OR m.created_at < ?)
+           AND (? OR m.superseded_by IS NULL)
+         ORDER BY m.created_at DESC, m.id DESC
+         LIMIT ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(conversation_id)
+    .bind(before)
+    .bind(before)
+    .bind(include_superseded)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(message_from_row).collect())
+}
+
+/// Star or unstar a message.
+///
+/// Works for messages in archived conversations too.
+pub async fn set_message_starred(
+    pool: &SqlitePool,
+    message_id: &str,
+    starred: bool,
+) -> Result<(), HistoryError> {
+    let result = sqlx::query("UPDATE messages SET starred = ? WHERE id = ?")
+        .bind(starred)
+        .bind(message_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(HistoryError::MessageNotFound(message_id.to_string()));
+    }
+    Ok(())
+}
+
+/// List starred messages, newest first, with their conversation titles.
+///
+/// Messages in trashed conversations are left out.
+pub async fn list_starred_messages(
+    pool: &SqlitePool,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<StarredMessage>, HistoryError> {
+    let rows = sqlx::query(&format!(
+        "SELECT {}, c.title AS conversation_title
+         FROM messages m JOIN conversations c ON c.id = m.conversation_id
+         WHERE m.starred = 1 AND c.deleted_at IS NULL
+         ORDER BY m.created_at DESC, m.id DESC
+         LIMIT ? OFFSET ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| StarredMessage {
+            message: message_from_row(row),
+            conversation_title: row.get("conversation_title"),
+        })
+        .collect())
+}
+
+/// List the files recorded with a message, in the order they were added.
+pub async fn list_attachments(
+    pool: &SqlitePool,
+    message_id: &str,
+) -> Result<Vec<Attachment>, HistoryError> {
+    let rows = sqlx::query(
+        "SELECT id, message_id, name, size, line_count, truncated, lossy, mime, data, created_at
+         FROM attachments WHERE message_id = ?
+         ORDER BY rowid",
+    )
+    .bind(message_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Attachment {
+            id: row.get("id"),
+            message_id: row.get("message_id"),
+            name: row.get("name"),
+            size: row.get("size"),
+            line_count: row.get("line_count"),
+            truncated: row.get("truncated"),
+            lossy: row.get("lossy"),
+            mime: row.get("mime"),
+            data: row
+                .get::<Option<String>, _>("data")
+                .map(crypto::open_stored),
+            created_at: row.get("created_at"),
+        })
+        .collect())
+}
+
+/// Get a single message by ID.
+pub async fn get_message(pool: &SqlitePool, message_id: &str) -> Result<Message, HistoryError> {
+    sqlx::query(&format!(
+        "SELECT {} FROM messages m WHERE m.id = ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(message_id)
+    .fetch_optional(pool)
+    .await?
+    .map(|row| message_from_row(&row))
+    .ok_or_else(|| HistoryError::MessageNotFound(message_id.to_string()))
+}
+
+/// ID of the newest user message in a conversation, used as the parent of
+/// the next assistant reply.
+pub(crate) async fn latest_user_message(
+    conn: &mut SqliteConnection,
+    conversation_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT id FROM messages
+         WHERE conversation_id = ? AND role = 'user'
+         ORDER BY created_at DESC, id DESC
+         LIMIT 1",
+    )
+    .bind(conversation_id)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Get a conversation's row without its messages.
+pub async fn find_conversation(pool: &SqlitePool, id: &str) -> Result<Conversation, HistoryError> {
+    sqlx::query(&format!(
+        "SELECT {} FROM conversations WHERE id = ?",
+        CONVERSATION_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?
+    .map(|row| conversation_from_row(&row))
+    .ok_or_else(|| HistoryError::NotFound(id.to_string()))
+}
+
+fn conversation_from_row(row: &SqliteRow) -> Conversation {
+    Conversation {
+        id: row.get("id"),
+        title: row.get("title"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        pinned: row.get("pinned"),
+        archived: row.get("archived"),
+        deleted_at: row.get("deleted_at"),
+        branched_from_conversation_id: row.get("branched_from_conversation_id"),
+        branched_from_message_id: row.get("branched_from_message_id"),
+        system_prompt_override: row.get("system_prompt_override"),
+    }
+}
+
+fn summary_from_row(row: &SqliteRow) -> ConversationSummary {
+    ConversationSummary {
+        conversation: conversation_from_row(row),
+        message_count: row.get("message_count"),
+        // Encrypted content is returned whole and cut after decrypting
+        snippet: row.get::<Option<String>, _>("snippet").map(|snippet| {
+            crypto::open_stored(snippet)
+                .chars()
+                .take(SNIPPET_LENGTH as usize)
+                .collect()
+        }),
+    }
+}
+
+pub(super) fn message_from_row(row: &SqliteRow) -> Message {
+    Message {
+        id: row.get("id"),
+        conversation_id: row.get("conversation_id"),
+        role: row.get("role"),
+        content: crypto::open_stored(row.get("content")),
+        created_at: row.get("created_at"),
+        starred: row.get("starred"),
+        parent_message_id: row.get("parent_message_id"),
+        superseded_by: row.get("superseded_by"),
+        metadata: MessageMetadata {
+            provider: row.get("provider"),
+            model: row.get("model"),
+            prompt_tokens: optional_u32(row, "prompt_tokens"),
+            completion_tokens: optional_u32(row, "completion_tokens"),
+            duration_ms: optional_u32(row, "duration_ms"),
+        },
+    }
+}
+
+fn optional_u32(row: &SqliteRow, column: &str) -> Option<u32> {
+    row.get::<Option<i64>, _>(column)
+        .and_then(|value| u32::try_from(value).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+
+    async fn insert_message(pool: &SqlitePool, conversation_id: &str, content: &str, at: i64) {
+        sqlx::query(
+            "INSERT INTO messages (id, conversation_id, role, content, created_at) VALUES (?, ?, 'user', ?, ?)",
+        )
+        .bind(uuid::Uuid::now_v7().to_string())
+        .bind(conversation_id)
+        .bind(content)
+        .bind(at)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn message_count(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    // ===== Conversations =====
+
+    #[tokio::test]
+    async fn test_create_uses_default_title() {
+        let db = Db::in_memory().await.unwrap();
+
+        let untitled = create_conversation(db.pool(), None, 1_000).await.unwrap();
+        let blank = create_conversation(db.pool(), Some("  ".to_string()), 1_000)
+            .await
+            .unwrap();
+        let titled = create_conversation(db.pool(), Some("Rust help".to_string()), 1_000)
+            .await
+            .unwrap();
+
+        assert_eq!(untitled.title, DEFAULT_TITLE);
+        assert_eq!(blank.title, DEFAULT_TITLE);
+        assert_eq!(titled.title, "Rust help");
+        assert_eq!(titled.created_at, 1_000);
+        assert_eq!(titled.updated_at, 1_000);
+        assert_ne!(untitled.id, titled.id);
+    }
+
+    #[tokio::test]
+    async fn test_crud_lifecycle() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+
+        let created = create_conversation(pool, Some("Draft".to_string()), 1_000)
+            .await
+            .unwrap();
+        insert_message(pool, &created.id, "Hello", 1_100).await;
+
+        rename_conversation(pool, &created.id, "Final")
+            .await
+            .unwrap();
+
+        let loaded = get_conversation(pool, &created.id).await.unwrap();
+        assert_eq!(loaded.conversation.title, "Final");
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.messages[0].content, "Hello");
+
+        purge_conversation(pool, &created.id).await.unwrap();
+        assert_eq!(
+            get_conversation(pool, &created.id).await,
+            Err(HistoryError::NotFound(created.id.clone()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_purge_cascades_to_messages() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let keep = create_conversation(pool, None, 0).await.unwrap();
+        let remove = create_conversation(pool, None, 0).await.unwrap();
+        insert_message(pool, &keep.id, "a", 1).await;
+        insert_message(pool, &remove.id, "b", 1).await;
+        insert_message(pool, &remove.id, "c", 2).await;
+
+        delete_conversation(pool, &remove.id, 5).await.unwrap();
+        assert_eq!(message_count(pool).await, 3);
+
+        purge_conversation(pool, &remove.id).await.unwrap();
+        assert_eq!(message_count(pool).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_conversation_is_not_found() {
+        let db = Db::in_memory().await.unwrap();
+        let missing = HistoryError::NotFound("nope".to_string());
+
+        assert_eq!(
+            rename_conversation(db.pool(), "nope", "x").await,
+            Err(missing.clone())
+        );
+        assert_eq!(
+            delete_conversation(db.pool(), "nope", 0).await,
+            Err(missing.clone())
+        );
+        assert_eq!(
+            set_system_prompt(db.pool(), "nope", None).await,
+            Err(missing.clone())
+        );
+        assert_eq!(purge_conversation(db.pool(), "nope").await, Err(missing));
+    }
+
+    #[tokio::test]
+    async fn test_system_prompt_override_round_trip() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let created = create_conversation(pool, None, 0).await.unwrap();
+
+        set_system_prompt(pool, &created.id, Some("Regex golf only"))
+            .await
+            .unwrap();
+        let loaded = get_conversation(pool, &created.id).await.unwrap();
+        assert_eq!(
+            loaded.conversation.system_prompt_override.as_deref(),
+            Some("Regex golf only")
+        );
+
+        set_system_prompt(pool, &created.id, Some("  "))
+            .await
+            .unwrap();
+        let cleared = find_conversation(pool, &created.id).await.unwrap();
+        assert_eq!(cleared.system_prompt_override, None);
+    }
+
+    #[tokio::test]
+    async fn test_list_orders_and_summarizes() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let older = create_conversation(pool, Some("Older".to_string()), 1_000)
+            .await
+            .unwrap();
+        let newer = create_conversation(pool, Some("Newer".to_string()), 2_000)
+            .await
+            .unwrap();
+        insert_message(pool, &older.id, "first", 1_100).await;
+        insert_message(pool, &older.id, &"x".repeat(500), 1_200).await;
+
+        let list = list_conversations(pool, 10, 0, false, None).await.unwrap();
+
+        assert_eq!(list[0].conversation.id, newer.id);
+        assert_eq!(list[0].message_count, 0);
+        assert_eq!(list[0].snippet, None);
+        assert_eq!(list[1].message_count, 2);
+        assert_eq!(
+            list[1].snippet.as_ref().map(|s| s.chars().count()),
+            Some(SNIPPET_LENGTH as usize)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_pagination() {
+        let db = Db::in_memory().await.unwrap();
+        for i in 0..5 {
+            create_conversation(db.pool(), None, i).await.unwrap();
+        }
+
+        let first = list_conversations(db.pool(), 2, 0, false, None)
+            .await
+            .unwrap();
+        let last = list_conversations(db.pool(), 2, 4, false, None)
+            .await
+            .unwrap();
+
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].conversation.updated_at, 4);
+        assert_eq!(last.len(), 1);
+        assert_eq!(last[0].conversation.updated_at, 0);
+    }
+
+    // ===== Trash =====
+
+    #[tokio::test]
+    async fn test_trashed_conversations_are_hidden() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let trashed = create_conversation(pool, Some("Trashed".to_string()), 1_000)
+            .await
+            .unwrap();
+        create_conversation(pool, Some("Kept".to_string()), 2_000)
+            .await
+            .unwrap();
+
+        delete_conversation(pool, &trashed.id, 3_000).await.unwrap();
+
+        assert_eq!(listed_titles(pool, true).await, ["Kept"]);
+        let trash = list_trashed_conversations(pool).await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].conversation.id, trashed.id);
+        assert_eq!(trash[0].conversation.deleted_at, Some(3_000));
+        assert_eq!(
+            delete_conversation(pool, &trashed.id, 4_000).await,
+            Err(HistoryError::NotFound(trashed.id.clone()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restore_conversation() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, Some("Oops".to_string()), 0)
+            .await
+            .unwrap();
+        insert_message(pool, &conversation.id, "keep me", 1).await;
+        delete_conversation(pool, &conversation.id, 2)
+            .await
+            .unwrap();
+
+        restore_conversation(pool, &conversation.id).await.unwrap();
+
+        assert_eq!(listed_titles(pool, false).await, ["Oops"]);
+        assert!(list_trashed_conversations(pool).await.unwrap().is_empty());
+        let loaded = get_conversation(pool, &conversation.id).await.unwrap();
+        assert_eq!(loaded.conversation.deleted_at, None);
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(
+            restore_conversation(pool, &conversation.id).await,
+            Err(HistoryError::NotFound(conversation.id.clone()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_index_rows_go_only_on_purge() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        insert_message(pool, &conversation.id, "kubernetes pods", 1).await;
+        let indexed = || async {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'kubernetes'",
+            )
+            .fetch_one(pool)
+            .await
+            .unwrap()
+        };
+        assert_eq!(indexed().await, 1);
+
+        delete_conversation(pool, &conversation.id, 2)
+            .await
+            .unwrap();
+        assert_eq!(indexed().await, 1);
+
+        purge_conversation(pool, &conversation.id).await.unwrap();
+        assert_eq!(indexed().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_purge_expired_trash_cutoff() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let now = 100 * TRASH_RETENTION_MS;
+        let expired = create_conversation(pool, None, 0).await.unwrap();
+        let boundary = create_conversation(pool, None, 0).await.unwrap();
+        let recent = create_conversation(pool, None, 0).await.unwrap();
+        let active = create_conversation(pool, None, 0).await.unwrap();
+        delete_conversation(pool, &expired.id, now - TRASH_RETENTION_MS - 1)
+            .await
+            .unwrap();
+        delete_conversation(pool, &boundary.id, now - TRASH_RETENTION_MS)
+            .await
+            .unwrap();
+        delete_conversation(pool, &recent.id, now - TRASH_RETENTION_MS + 1)
+            .await
+            .unwrap();
+
+        let purged = purge_expired_trash(pool, now).await.unwrap();
+
+        assert_eq!(purged, 2);
+        let trash = list_trashed_conversations(pool).await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].conversation.id, recent.id);
+        assert!(get_conversation(pool, &active.id).await.is_ok());
+    }
+
+    // ===== Pinning and archiving =====
+
+    async fn listed_titles(pool: &SqlitePool, include_archived: bool) -> Vec<String> {
+        list_conversations(pool, 10, 0, include_archived, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.conversation.title)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_pinned_conversations_listed_first() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let old = create_conversation(pool, Some("Old".to_string()), 1_000)
+            .await
+            .unwrap();
+        create_conversation(pool, Some("Recent".to_string()), 2_000)
+            .await
+            .unwrap();
+        create_conversation(pool, Some("Newest".to_string()), 3_000)
+            .await
+            .unwrap();
+
+        set_pinned(pool, &old.id, true).await.unwrap();
+
+        assert_eq!(
+            listed_titles(pool, false).await,
+            ["Old", "Newest", "Recent"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flags_round_trip() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+
+        set_pinned(pool, &conversation.id, true).await.unwrap();
+        set_archived(pool, &conversation.id, true).await.unwrap();
+        let loaded = get_conversation(pool, &conversation.id).await.unwrap();
+        assert!(loaded.conversation.pinned);
+        assert!(loaded.conversation.archived);
+
+        set_pinned(pool, &conversation.id, false).await.unwrap();
+        set_archived(pool, &conversation.id, false).await.unwrap();
+        let loaded = get_conversation(pool, &conversation.id).await.unwrap();
+        assert!(!loaded.conversation.pinned);
+        assert!(!loaded.conversation.archived);
+
+        assert_eq!(
+            set_pinned(pool, "nope", true).await,
+            Err(HistoryError::NotFound("nope".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_archived_hidden_unless_requested() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let noise = create_conversation(pool, Some("Noise".to_string()), 1_000)
+            .await
+            .unwrap();
+        create_conversation(pool, Some("Keep".to_string()), 2_000)
+            .await
+            .unwrap();
+
+        set_archived(pool, &noise.id, true).await.unwrap();
+
+        assert_eq!(listed_titles(pool, false).await, ["Keep"]);
+        assert_eq!(listed_titles(pool, true).await, ["Keep", "Noise"]);
+    }
+
+    #[tokio::test]
+    async fn test_append_message_unarchives() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        set_archived(pool, &conversation.id, true).await.unwrap();
+
+        append_message(
+            pool,
+            &conversation.id,
+            "user",
+            "back again",
+            &MessageMetadata::default(),
+            &[],
+            1,
+        )
+        .await
+        .unwrap();
+
+        let loaded = get_conversation(pool, &conversation.id).await.unwrap();
+        assert!(!loaded.conversation.archived);
+    }
+
+    // ===== Starred messages =====
+
+    #[tokio::test]
+    async fn test_star_toggle_round_trips() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        let message = append(pool, &conversation.id, "assistant", 1).await;
+
+        set_message_starred(pool, &message.id, true).await.unwrap();
+        let loaded = get_messages(pool, &conversation.id, None, 1, false)
+            .await
+            .unwrap();
+        assert!(loaded[0].starred);
+
+        set_message_starred(pool, &message.id, false).await.unwrap();
+        let loaded = get_messages(pool, &conversation.id, None, 1, false)
+            .await
+            .unwrap();
+        assert!(!loaded[0].starred);
+
+        assert_eq!(
+            set_message_starred(pool, "nope", true).await,
+            Err(HistoryError::MessageNotFound("nope".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_starred_newest_first_with_titles() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let rust = create_conversation(pool, Some("Rust".to_string()), 0)
+            .await
+            .unwrap();
+        let docker = create_conversation(pool, Some("Docker".to_string()), 0)
+            .await
+            .unwrap();
+        let old = append(pool, &rust.id, "assistant", 100).await;
+        let unstarred = append(pool, &rust.id, "assistant", 200).await;
+        let new = append(pool, &docker.id, "assistant", 300).await;
+        set_message_starred(pool, &old.id, true).await.unwrap();
+        set_message_starred(pool, &new.id, true).await.unwrap();
+        set_archived(pool, &docker.id, true).await.unwrap();
+
+        let starred = list_starred_messages(pool, 10, 0).await.unwrap();
+
+        let ids: Vec<&str> = starred.iter().map(|s| s.message.id.as_str()).collect();
+        assert_eq!(ids, [new.id.as_str(), old.id.as_str()]);
+        assert!(!ids.contains(&unstarred.id.as_str()));
+        assert_eq!(starred[0].conversation_title, "Docker");
+        assert_eq!(starred[1].conversation_title, "Rust");
+
+        let second_page = list_starred_messages(pool, 1, 1).await.unwrap();
+        assert_eq!(second_page[0].message.id, old.id);
+    }
+
+    #[tokio::test]
+    async fn test_list_starred_skips_trashed_conversations() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        let message = append(pool, &conversation.id, "assistant", 1).await;
+        set_message_starred(pool, &message.id, true).await.unwrap();
+
+        delete_conversation(pool, &conversation.id, 2)
+            .await
+            .unwrap();
+
+        assert!(list_starred_messages(pool, 10, 0).await.unwrap().is_empty());
+    }
+
+    // ===== Last conversation =====
+
+    #[tokio::test]
+    async fn test_last_conversation_on_empty_database() {
+        let db = Db::in_memory().await.unwrap();
+
+        assert_eq!(get_last_conversation(db.pool(), 10).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_last_conversation_is_most_recently_updated() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let older = create_conversation(pool, Some("Older".to_string()), 1_000)
+            .await
+            .unwrap();
+        create_conversation(pool, Some("Newer".to_string()), 2_000)
+            .await
+            .unwrap();
+        append(pool, &older.id, "user", 3_000).await;
+
+        let last = get_last_conversation(pool, 10).await.unwrap().unwrap();
+
+        assert_eq!(last.conversation.title, "Older");
+    }
+
+    #[tokio::test]
+    async fn test_last_conversation_skips_archived_and_trashed() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        create_conversation(pool, Some("Active".to_string()), 1_000)
+            .await
+            .unwrap();
+        let archived = create_conversation(pool, Some("Archived".to_string()), 2_000)
+            .await
+            .unwrap();
+        set_archived(pool, &archived.id, true).await.unwrap();
+        let trashed = create_conversation(pool, Some("Trashed".to_string()), 3_000)
+            .await
+            .unwrap();
+        delete_conversation(pool, &trashed.id, 4_000).await.unwrap();
+
+        let last = get_last_conversation(pool, 10).await.unwrap().unwrap();
+
+        assert_eq!(last.conversation.title, "Active");
+    }
+
+    #[tokio::test]
+    async fn test_last_conversation_keeps_newest_messages() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        for at in 1..=5 {
+            append(pool, &conversation.id, "user", at * 100).await;
+        }
+
+        let last = get_last_conversation(pool, 3).await.unwrap().unwrap();
+
+        let times: Vec<i64> = last.messages.iter().map(|m| m.created_at).collect();
+        assert_eq!(times, [300, 400, 500]);
+    }
+
+    // ===== Messages =====
+
+    async fn append(pool: &SqlitePool, conversation_id: &str, role: &str, at: i64) -> Message {
+        append_message(
+            pool,
+            conversation_id,
+            role,
+            &format!("message at {}", at),
+            &MessageMetadata::default(),
+            &[],
+            at,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_append_message_bumps_updated_at() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 1_000).await.unwrap();
+
+        let message = append(pool, &conversation.id, "user", 5_000).await;
+
+        assert_eq!(message.conversation_id, conversation.id);
+        assert_eq!(message.role, "user");
+        assert_eq!(message.created_at, 5_000);
+        let loaded = get_conversation(pool, &conversation.id).await.unwrap();
+        assert_eq!(loaded.conversation.updated_at, 5_000);
+        assert_eq!(loaded.messages, vec![message]);
+    }
+
+    #[tokio::test]
+    async fn test_append_message_stores_metadata() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        let metadata = MessageMetadata {
+            provider: Some("openai".to_string()),
+            model: Some("gpt-4o".to_string()),
+            prompt_tokens: Some(12),
+            completion_tokens: Some(34),
+            duration_ms: Some(850),
+        };
+
+        append_message(pool, &conversation.id, "assistant", "Hi", &metadata, &[], 1)
+            .await
+            .unwrap();
+
+        let messages = get_messages(pool, &conversation.id, None, 1, false)
+            .await
+            .unwrap();
+        assert_eq!(messages[0].metadata, metadata);
+    }
+
+    #[tokio::test]
+    async fn test_old_rows_serialize_without_metadata() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        insert_message(pool, &conversation.id, "Hello", 1).await;
+
+        let messages = get_messages(pool, &conversation.id, None, 1, false)
+            .await
+            .unwrap();
+        let json = serde_json::to_value(&messages[0]).unwrap();
+
+        assert_eq!(messages[0].metadata, MessageMetadata::default());
+        assert_eq!(json["content"], "Hello");
+        assert!(json.get("model").is_none());
+        assert!(json.get("duration_ms").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_append_message_rejects_unknown_role() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+
+        for role in ["system", "User", ""] {
+            let result = append_message(
+                pool,
+                &conversation.id,
+                role,
+                "x",
+                &MessageMetadata::default(),
+                &[],
+                1,
+            )
+            .await;
+            assert_eq!(result, Err(HistoryError::InvalidRole(role.to_string())));
+        }
+        assert_eq!(message_count(pool).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_append_message_to_missing_conversation_fails() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+
+        let result = append_message(
+            pool,
+            "missing",
+            "user",
+            "orphan",
+            &MessageMetadata::default(),
+            &[],
+            1,
+        )
+        .await;
+
+        assert_eq!(result, Err(HistoryError::NotFound("missing".to_string())));
+        assert_eq!(message_count(pool).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_messages_pages_newest_first() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        for at in 1..=5 {
+            append(pool, &conversation.id, "user", at * 100).await;
+        }
+
+        let first = get_messages(pool, &conversation.id, None, 2, false)
+            .await
+            .unwrap();
+        let times: Vec<i64> = first.iter().map(|m| m.created_at).collect();
+        assert_eq!(times, vec![500, 400]);
+
+        let second = get_messages(pool, &conversation.id, Some(400), 2, false)
+            .await
+            .unwrap();
+        let times: Vec<i64> = second.iter().map(|m| m.created_at).collect();
+        assert_eq!(times, vec![300, 200]);
+
+        let last = get_messages(pool, &conversation.id, Some(200), 2, false)
+            .await
+            .unwrap();
+        assert_eq!(last.len(), 1);
+        assert_eq!(last[0].created_at, 100);
+
+        let past_end = get_messages(pool, &conversation.id, Some(100), 2, false)
+            .await
+            .unwrap();
+        assert!(past_end.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_messages_only_returns_conversation() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let first = create_conversation(pool, None, 0).await.unwrap();
+        let second = create_conversation(pool, None, 0).await.unwrap();
+        append(pool, &first.id, "user", 1).await;
+        append(pool, &second.id, "assistant", 2).await;
+
+        let messages = get_messages(pool, &first.id, None, 10, false)
+            .await
+            .unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].conversation_id, first.id);
+    }
+
+    // ===== Attachments =====
+
+    fn attachment(name: &str, truncated: bool) -> NewAttachment {
+        NewAttachment {
+            name: name.to_string(),
+            size: 300_000,
+            line_count: 1_200,
+            truncated,
+            lossy: false,
+            mime: None,
+            data: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attachments_recorded_with_message() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+
+        let message = append_message(
+            pool,
+            &conversation.id,
+            "user",
+            "Why does this crash?",
+            &MessageMetadata::default(),
+            &[attachment("app.log", true), attachment("main.rs", false)],
+            7,
+        )
+        .await
+        .unwrap();
+
+        let attachments = list_attachments(pool, &message.id).await.unwrap();
+        let names: Vec<&str> = attachments.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["app.log", "main.rs"]);
+        assert!(attachments[0].truncated);
+        assert_eq!(attachments[0].size, 300_000);
+        assert_eq!(attachments[0].line_count, 1_200);
+        assert_eq!(attachments[0].message_id, message.id);
+        assert_eq!(attachments[0].created_at, 7);
+    }
+
+    #[tokio::test]
+    async fn test_image_attachment_keeps_its_data() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        let image = NewAttachment {
+            name: "Screenshot 1.png".to_string(),
+            size: 6,
+            mime: Some("image/png".to_string()),
+            data: Some("iVBORw0K".to_string()),
+            ..attachment("", false)
+        };
+
+        let message = append_message(
+            pool,
+            &conversation.id,
+            "user",
+            "What does this chart mean?",
+            &MessageMetadata::default(),
+            &[image, attachment("notes.txt", false)],
+            7,
+        )
+        .await
+        .unwrap();
+
+        let attachments = list_attachments(pool, &message.id).await.unwrap();
+        assert_eq!(attachments[0].mime.as_deref(), Some("image/png"));
+        assert_eq!(attachments[0].data.as_deref(), Some("iVBORw0K"));
+        assert_eq!(attachments[1].data, None);
+    }
+
+    #[tokio::test]
+    async fn test_attachments_removed_with_conversation() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let conversation = create_conversation(pool, None, 0).await.unwrap();
+        let message = append_message(
+            pool,
+            &conversation.id,
+            "user",
+            "x",
+            &MessageMetadata::default(),
+            &[attachment("a.txt", false)],
+            1,
+        )
+        .await
+        .unwrap();
+
+        purge_conversation(pool, &conversation.id).await.unwrap();
+
+        assert!(list_attachments(pool, &message.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_attachments_not_recorded_when_append_fails() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+
+        let result = append_message(
+            pool,
+            "missing",
+            "user",
+            "x",
+            &MessageMetadata::default(),
+            &[attachment("a.txt", false)],
+            1,
+        )
+        .await;
+
+        assert_eq!(result, Err(HistoryError::NotFound("missing".to_string())));
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM attachments")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_file_content_deserializes_as_attachment() {
+        let json = serde_json::json!({
+            "name": "data.csv",
+            "size": 42,
+            "kind": "csv",
+            "encoding": "utf-8",
+            "line_count": 3,
+            "truncated": false,
+            "lossy": true,
+            "content": "a,b\n1,2\n3,4",
+        });
+
+        let attachment: NewAttachment = serde_json::from_value(json).unwrap();
+
+        assert_eq!(attachment.name, "data.csv");
+        assert_eq!(attachment.line_count, 3);
+        assert!(attachment.lossy);
+    }
+}
@@ -0,0 +1,262 @@
+//! Conversation tags.
+//!
+//! Tag names are normalized before they reach the database, so "Rust" and
+//! " rust " are the same tag. Tags exist only while at least one
+//! conversation carries them: removing the last reference, or purging the
+//! last tagged conversation, deletes the tag through the
+//! `delete_unused_tags` trigger.
+
+use sqlx::SqlitePool;
+
+use super::types::{HistoryError, TagWithCount, MAX_TAG_CHARS};
+
+/// Trim, lowercase, and collapse inner whitespace.
+///
+/// # Returns
+///
+/// * `Err(HistoryError::InvalidTag)` - Empty, or longer than
+///   [`MAX_TAG_CHARS`] after normalization
+pub fn normalize_tag(tag: &str) -> Result<String, HistoryError> {
+    let name = tag
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    if name.is_empty() || name.chars().count() > MAX_TAG_CHARS {
+        return Err(HistoryError::InvalidTag(tag.to_string()));
+    }
+    Ok(name)
+}
+
+/// Tag a conversation. Adding a tag it already has is a no-op.
+///
+/// # Returns
+///
+/// The normalized tag name.
+pub async fn add_tag(
+    pool: &SqlitePool,
+    conversation_id: &str,
+    tag: &str,
+) -> Result<String, HistoryError> {
+    let name = normalize_tag(tag)?;
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("INSERT INTO tags (name) VALUES (?) ON CONFLICT(name) DO NOTHING")
+        .bind(&name)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        "INSERT INTO conversation_tags (conversation_id, tag_id)
+         SELECT ?, id FROM tags WHERE name = ?
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(conversation_id)
+    .bind(&name)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e.as_database_error() {
+        Some(db) if db.is_foreign_key_violation() => {
+            HistoryError::NotFound(conversation_id.to_string())
+        }
+        _ => HistoryError::from(e),
+    })?;
+
+    tx.commit().await?;
+    Ok(name)
+}
+
+/// Remove a tag from a conversation. Removing a tag it doesn't have is a
+/// no-op.
+pub async fn remove_tag(
+    pool: &SqlitePool,
+    conversation_id: &str,
+    tag: &str,
+) -> Result<(), HistoryError> {
+    let name = normalize_tag(tag)?;
+    sqlx::query(
+        "DELETE FROM conversation_tags
+         WHERE conversation_id = ? AND tag_id = (SELECT id FROM tags WHERE name = ?)",
+    )
+    .bind(conversation_id)
+    .bind(&name)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// List tags used by conversations outside the trash, by name.
+pub async fn list_tags(pool: &SqlitePool) -> Result<Vec<TagWithCount>, HistoryError> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT t.name, COUNT(*)
+         FROM tags t
+         JOIN conversation_tags ct ON ct.tag_id = t.id
+         JOIN conversations c ON c.id = ct.conversation_id
+         WHERE c.deleted_at IS NULL
+         GROUP BY t.id
+         ORDER BY t.name ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(name, conversation_count)| TagWithCount {
+            name,
+            conversation_count,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::store;
+
+    async fn conversation(pool: &SqlitePool, title: &str) -> String {
+        store::create_conversation(pool, Some(title.to_string()), 0)
+            .await
+            .unwrap()
+            .id
+    }
+
+    async fn tag_count(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM tags")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    fn counts(tags: &[TagWithCount]) -> Vec<(&str, i64)> {
+        tags.iter()
+            .map(|t| (t.name.as_str(), t.conversation_count))
+            .collect()
+    }
+
+    // ===== Normalization =====
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("  Rust ").unwrap(), "rust");
+        assert_eq!(normalize_tag("Side \t Projects").unwrap(), "side projects");
+        assert_eq!(normalize_tag(&"x".repeat(MAX_TAG_CHARS)).unwrap().len(), 32);
+    }
+
+    #[test]
+    fn test_normalize_rejects_empty_and_long_tags() {
+        assert!(matches!(
+            normalize_tag("   "),
+            Err(HistoryError::InvalidTag(_))
+        ));
+        assert!(matches!(
+            normalize_tag(&"x".repeat(MAX_TAG_CHARS + 1)),
+            Err(HistoryError::InvalidTag(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_case_variants_are_one_tag() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let first = conversation(pool, "First").await;
+        let second = conversation(pool, "Second").await;
+
+        add_tag(pool, &first, "Rust").await.unwrap();
+        add_tag(pool, &first, "rust").await.unwrap();
+        add_tag(pool, &second, " RUST ").await.unwrap();
+
+        assert_eq!(counts(&list_tags(pool).await.unwrap()), [("rust", 2)]);
+    }
+
+    // ===== Tagging =====
+
+    #[tokio::test]
+    async fn test_add_tag_to_missing_conversation() {
+        let db = Db::in_memory().await.unwrap();
+
+        let result = add_tag(db.pool(), "missing", "work").await;
+
+        assert_eq!(result, Err(HistoryError::NotFound("missing".to_string())));
+        assert_eq!(tag_count(db.pool()).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_list_tags_skips_trashed_conversations() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let kept = conversation(pool, "Kept").await;
+        let trashed = conversation(pool, "Trashed").await;
+        add_tag(pool, &kept, "work").await.unwrap();
+        add_tag(pool, &trashed, "work").await.unwrap();
+        add_tag(pool, &trashed, "recipes").await.unwrap();
+
+        store::delete_conversation(pool, &trashed, 1).await.unwrap();
+
+        assert_eq!(counts(&list_tags(pool).await.unwrap()), [("work", 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_filter_conversations_by_tag() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let work = conversation(pool, "Work").await;
+        conversation(pool, "Untagged").await;
+        add_tag(pool, &work, "Work").await.unwrap();
+
+        let tagged = store::list_conversations(pool, 10, 0, false, Some("WORK"))
+            .await
+            .unwrap();
+        let unknown = store::list_conversations(pool, 10, 0, false, Some("nope"))
+            .await
+            .unwrap();
+        let all = store::list_conversations(pool, 10, 0, false, None)
+            .await
+            .unwrap();
+
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].conversation.title, "Work");
+        assert!(unknown.is_empty());
+        assert_eq!(all.len(), 2);
+    }
+
+    // ===== Cleanup =====
+
+    #[tokio::test]
+    async fn test_removing_last_reference_deletes_tag() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let first = conversation(pool, "First").await;
+        let second = conversation(pool, "Second").await;
+        add_tag(pool, &first, "rust").await.unwrap();
+        add_tag(pool, &second, "rust").await.unwrap();
+
+        remove_tag(pool, &first, "Rust").await.unwrap();
+        assert_eq!(tag_count(pool).await, 1);
+
+        remove_tag(pool, &second, "rust").await.unwrap();
+        assert_eq!(tag_count(pool).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_purging_conversation_cleans_up_tags() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let purged = conversation(pool, "Purged").await;
+        let kept = conversation(pool, "Kept").await;
+        add_tag(pool, &purged, "recipes").await.unwrap();
+        add_tag(pool, &purged, "work").await.unwrap();
+        add_tag(pool, &kept, "work").await.unwrap();
+
+        store::purge_conversation(pool, &purged).await.unwrap();
+
+        let links: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM conversation_tags")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(links, 1);
+        assert_eq!(counts(&list_tags(pool).await.unwrap()), [("work", 1)]);
+        assert_eq!(tag_count(pool).await, 1);
+    }
+}
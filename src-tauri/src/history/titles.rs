@@ -0,0 +1,379 @@
+//! Automatic conversation titles.
+//!
+//! The first user message (and first reply, if any) is summarized by the
+//! configured provider into a short title. Conversations the user has
+//! renamed (`auto_title = 0`) are never touched. If the model call fails
+//! the title falls back to a word-boundary truncation of the first prompt,
+//! so a title is always produced.
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use super::crypto;
+use super::types::HistoryError;
+use crate::llm::client::LlmClient;
+use crate::llm::types::{ChatMessage, ChatRole, LlmRequest};
+use crate::settings::{LlmProvider, LlmSettings};
+
+/// Instruction sent as the system prompt for title requests.
+pub const TITLE_PROMPT: &str = "Summarize the conversation below as a title of 4 to 8 words. \
+Reply with the title only, without quotes or trailing punctuation.";
+
+/// Maximum length (in characters) of a generated or truncated title.
+pub const MAX_TITLE_CHARS: usize = 60;
+
+/// Reply budget for title requests; titles are a handful of tokens.
+const TITLE_MAX_TOKENS: u32 = 32;
+
+/// How much of each message is sent to the model.
+const EXCERPT_CHARS: usize = 2000;
+
+/// Payload of the `conversation-title-updated` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TitleUpdated {
+    /// Conversation that was renamed
+    pub conversation_id: String,
+    /// New title
+    pub title: String,
+}
+
+/// Model used for title requests.
+///
+/// `llm.title_model` wins; otherwise a small model for providers that have
+/// one, falling back to the main model.
+pub fn title_model(settings: &LlmSettings) -> String {
+    if let Some(model) = settings.title_model.as_deref().filter(|m| !m.is_empty()) {
+        return model.to_string();
+    }
+    let cheap = match settings.provider {
+        LlmProvider::Gemini => Some("gemini-2.0-flash-lite"),
+        LlmProvider::OpenAI => Some("gpt-4o-mini"),
+        LlmProvider::Anthropic => Some("claude-3-5-haiku-latest"),
+        // Model names are user-specific for these providers
+        LlmProvider::OpenRouter | LlmProvider::AzureOpenAI | LlmProvider::Custom => None,
+    };
+    cheap.map_or_else(|| settings.model.clone(), str::to_string)
+}
+
+/// Generate and store a title for a conversation.
+///
+/// # Arguments
+///
+/// * `client` - LLM client used for the summary
+/// * `pool` - History database pool
+/// * `settings` - LLM settings (provider, key, title model)
+/// * `conversation_id` - Conversation to title
+///
+/// # Returns
+///
+/// * `Ok(Some(title))` - The stored title
+/// * `Ok(None)` - Skipped: the title was set by the user, or there is no
+///   user message yet
+/// * `Err(HistoryError::NotFound)` - The conversation does not exist
+pub async fn generate_title(
+    client: &impl LlmClient,
+    pool: &SqlitePool,
+    settings: &LlmSettings,
+    conversation_id: &str,
+) -> Result<Option<String>, HistoryError> {
+    let auto_title: bool = sqlx::query_scalar("SELECT auto_title FROM conversations WHERE id = ?")
+        .bind(conversation_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| HistoryError::NotFound(conversation_id.to_string()))?;
+    if !auto_title {
+        return Ok(None);
+    }
+
+    let Some(prompt) = first_message(pool, conversation_id, "user").await? else {
+        return Ok(None);
+    };
+    let reply = first_message(pool, conversation_id, "assistant").await?;
+
+    let mut request = LlmRequest::from_settings(
+        settings,
+        vec![ChatMessage {
+            role: ChatRole::User,
+            content: transcript(&prompt, reply.as_deref()),
+            images: Vec::new(),
+        }],
+    );
+    request.model = title_model(settings);
+    request.system_prompt = TITLE_PROMPT.to_string();
+    request.max_tokens = TITLE_MAX_TOKENS;
+
+    let title = match client.complete(&request).await {
+        Ok(completion) => clean_title(&completion.content),
+        Err(_) => None,
+    }
+    .unwrap_or_else(|| fallback_title(&prompt));
+
+    // Re-check the flag so a rename during the model call wins
+    let result = sqlx::query("UPDATE conversations SET title = ? WHERE id = ? AND auto_title = 1")
+        .bind(&title)
+        .bind(conversation_id)
+        .execute(pool)
+        .await?;
+
+    Ok((result.rows_affected() > 0).then_some(title))
+}
+
+/// Deterministic title: the first line of `text`, cut at a word boundary.
+pub fn fallback_title(text: &str) -> String {
+    let line = text.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    let collapsed = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate(&collapsed)
+}
+
+/// Tidy a model reply into a title, or `None` if nothing usable remains.
+fn clean_title(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line
+        .strip_prefix("Title:")
+        .or_else(|| line.strip_prefix("title:"))
+        .unwrap_or(line);
+    let title = line
+        .trim()
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '*' | '#' | '“' | '”' | '`'))
+        .trim_end_matches('.')
+        .trim();
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(truncate(title))
+    }
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_TITLE_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(MAX_TITLE_CHARS).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > 0 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end())
+}
+
+fn transcript(prompt: &str, reply: Option<&str>) -> String {
+    let excerpt = |text: &str| text.chars().take(EXCERPT_CHARS).collect::<String>();
+    match reply {
+        Some(reply) => format!("User: {}\n\nAssistant: {}", excerpt(prompt), excerpt(reply)),
+        None => format!("User: {}", excerpt(prompt)),
+    }
+}
+
+async fn first_message(
+    pool: &SqlitePool,
+    conversation_id: &str,
+    role: &str,
+) -> Result<Option<String>, HistoryError> {
+    let content: Option<String> = sqlx::query_scalar(
+        "SELECT content FROM messages WHERE conversation_id = ? AND role = ?
+         ORDER BY created_at ASC, id ASC LIMIT 1",
+    )
+    .bind(conversation_id)
+    .bind(role)
+    .fetch_optional(pool)
+    .await?;
+    Ok(content.map(crypto::open_stored))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::store;
+    use crate::history::types::MessageMetadata;
+    use crate::llm::types::Completion;
+    use crate::llm::LlmError;
+    use std::sync::Mutex;
+
+    /// Client returning a fixed reply and recording requests.
+    struct TitleClient {
+        reply: Result<String, LlmError>,
+        requests: Mutex<Vec<LlmRequest>>,
+    }
+
+    impl TitleClient {
+        fn new(reply: Result<&str, LlmError>) -> Self {
+            Self {
+                reply: reply.map(str::to_string),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl LlmClient for TitleClient {
+        async fn complete(&self, request: &LlmRequest) -> Result<Completion, LlmError> {
+            self.requests.lock().unwrap().push(request.clone());
+            self.reply.clone().map(|content| Completion {
+                content,
+                usage: None,
+                cost_usd: None,
+            })
+        }
+    }
+
+    async fn conversation_with(pool: &SqlitePool, messages: &[(&str, &str)]) -> String {
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        for (i, (role, content)) in messages.iter().enumerate() {
+            store::append_message(
+                pool,
+                &conversation.id,
+                role,
+                content,
+                &MessageMetadata::default(),
+                &[],
+                i as i64 + 1,
+            )
+            .await
+            .unwrap();
+        }
+        conversation.id
+    }
+
+    async fn title_of(pool: &SqlitePool, id: &str) -> String {
+        store::get_conversation(pool, id)
+            .await
+            .unwrap()
+            .conversation
+            .title
+    }
+
+    // ===== Generation =====
+
+    #[tokio::test]
+    async fn test_generates_title_from_first_exchange() {
+        let db = Db::in_memory().await.unwrap();
+        let id = conversation_with(
+            db.pool(),
+            &[
+                ("user", "how do I extract a tar.gz"),
+                ("assistant", "Use tar -xzf"),
+                ("user", "and list it?"),
+            ],
+        )
+        .await;
+        let client = TitleClient::new(Ok("\"Extracting Tar Gz Archives.\"\n"));
+
+        let title = generate_title(&client, db.pool(), &LlmSettings::default(), &id)
+            .await
+            .unwrap();
+
+        assert_eq!(title.as_deref(), Some("Extracting Tar Gz Archives"));
+        assert_eq!(title_of(db.pool(), &id).await, "Extracting Tar Gz Archives");
+
+        let requests = client.requests.lock().unwrap();
+        assert_eq!(requests[0].model, "gemini-2.0-flash-lite");
+        assert_eq!(requests[0].system_prompt, TITLE_PROMPT);
+        assert_eq!(
+            requests[0].messages[0].content,
+            "User: how do I extract a tar.gz\n\nAssistant: Use tar -xzf"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failure_falls_back_to_truncation() {
+        let db = Db::in_memory().await.unwrap();
+        let prompt =
+            "What is the difference between a process and a thread in modern operating systems";
+        let id = conversation_with(db.pool(), &[("user", prompt)]).await;
+        let client = TitleClient::new(Err(LlmError::Timeout));
+
+        let title = generate_title(&client, db.pool(), &LlmSettings::default(), &id)
+            .await
+            .unwrap();
+
+        let expected = "What is the difference between a process and a thread in…";
+        assert_eq!(title.as_deref(), Some(expected));
+        assert_eq!(title_of(db.pool(), &id).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_user_edited_title_is_skipped() {
+        let db = Db::in_memory().await.unwrap();
+        let id = conversation_with(db.pool(), &[("user", "hello")]).await;
+        store::rename_conversation(db.pool(), &id, "Mine")
+            .await
+            .unwrap();
+        let client = TitleClient::new(Ok("Greeting"));
+
+        let title = generate_title(&client, db.pool(), &LlmSettings::default(), &id)
+            .await
+            .unwrap();
+
+        assert_eq!(title, None);
+        assert_eq!(title_of(db.pool(), &id).await, "Mine");
+        assert!(client.requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_empty_conversation_is_skipped() {
+        let db = Db::in_memory().await.unwrap();
+        let id = conversation_with(db.pool(), &[]).await;
+        let client = TitleClient::new(Ok("Anything"));
+
+        let title = generate_title(&client, db.pool(), &LlmSettings::default(), &id)
+            .await
+            .unwrap();
+
+        assert_eq!(title, None);
+        assert!(client.requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_missing_conversation_is_not_found() {
+        let db = Db::in_memory().await.unwrap();
+        let client = TitleClient::new(Ok("Anything"));
+
+        let result = generate_title(&client, db.pool(), &LlmSettings::default(), "nope").await;
+
+        assert_eq!(result, Err(HistoryError::NotFound("nope".to_string())));
+    }
+
+    // ===== Helpers =====
+
+    #[test]
+    fn test_title_model_selection() {
+        let mut settings = LlmSettings::default();
+        assert_eq!(title_model(&settings), "gemini-2.0-flash-lite");
+
+        settings.provider = LlmProvider::OpenRouter;
+        settings.model = "meta-llama/llama-3.1-70b-instruct".to_string();
+        assert_eq!(title_model(&settings), "meta-llama/llama-3.1-70b-instruct");
+
+        settings.title_model = Some("mistralai/mistral-small".to_string());
+        assert_eq!(title_model(&settings), "mistralai/mistral-small");
+    }
+
+    #[test]
+    fn test_clean_title() {
+        assert_eq!(
+            clean_title("Title: Rust Lifetimes").as_deref(),
+            Some("Rust Lifetimes")
+        );
+        assert_eq!(
+            clean_title("**Docker Basics**").as_deref(),
+            Some("Docker Basics")
+        );
+        assert_eq!(
+            clean_title("\n\n  \"Quoted\"  \n extra"),
+            Some("Quoted".to_string())
+        );
+        assert_eq!(clean_title("   "), None);
+        assert_eq!(clean_title("\"\""), None);
+    }
+
+    #[test]
+    fn test_fallback_title() {
+        assert_eq!(fallback_title("short question"), "short question");
+        assert_eq!(fallback_title("\n  first   line \nsecond"), "first line");
+        assert_eq!(
+            fallback_title(&"x".repeat(80)),
+            format!("{}…", "x".repeat(60))
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Chat history type definitions.
+//!
+//! Rows of the `conversations` and `messages` tables as returned to the
+//! frontend, plus [`HistoryError`].
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::llm::LlmError;
+
+/// Title given to conversations created without one.
+pub const DEFAULT_TITLE: &str = "New conversation";
+
+/// Roles accepted by `append_message`.
+pub const MESSAGE_ROLES: &[&str] = &["user", "assistant"];
+
+/// How long trashed conversations are kept before being purged (30 days).
+pub const TRASH_RETENTION_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+
+/// Maximum length (in characters) of a normalized tag name.
+pub const MAX_TAG_CHARS: usize = 32;
+
+/// Maximum length (in characters) of [`ConversationSummary::snippet`].
+pub const SNIPPET_LENGTH: u32 = 120;
+
+/// A chat session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conversation {
+    /// UUIDv7, generated by the backend
+    pub id: String,
+    /// Display title
+    pub title: String,
+    /// Unix timestamp (ms)
+    pub created_at: i64,
+    /// Unix timestamp (ms), bumped whenever a message is added
+    pub updated_at: i64,
+    /// Listed before unpinned conversations
+    #[serde(default)]
+    pub pinned: bool,
+    /// Hidden from the default list; cleared when a message is added
+    #[serde(default)]
+    pub archived: bool,
+    /// Unix timestamp (ms) when moved to the trash; `None` if not trashed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<i64>,
+    /// Conversation this one was branched from; cleared when that one is
+    /// purged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branched_from_conversation_id: Option<String>,
+    /// Last message copied from `branched_from_conversation_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branched_from_message_id: Option<String>,
+    /// System prompt used for this conversation instead of the active
+    /// prompt-library prompt or `llm.system_prompt`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt_override: Option<String>,
+}
+
+/// A conversation as shown in the history list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConversationSummary {
+    /// Conversation row
+    #[serde(flatten)]
+    pub conversation: Conversation,
+    /// Number of messages in the conversation
+    pub message_count: i64,
+    /// Start of the most recent message, if any
+    pub snippet: Option<String>,
+}
+
+/// A single stored message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+    /// UUIDv7, generated by the backend
+    pub id: String,
+    /// Parent conversation
+    pub conversation_id: String,
+    /// `"user"` or `"assistant"`
+    pub role: String,
+    /// Message text (markdown)
+    pub content: String,
+    /// Unix timestamp (ms)
+    pub created_at: i64,
+    /// Saved to the "Saved answers" list
+    #[serde(default)]
+    pub starred: bool,
+    /// User message this assistant reply answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_message_id: Option<String>,
+    /// Reply that replaced this one when it was regenerated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<String>,
+    /// Provider/model details; all `None` for user messages and old rows
+    #[serde(flatten)]
+    pub metadata: MessageMetadata,
+}
+
+/// A starred message with the title of its conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StarredMessage {
+    /// Message row
+    #[serde(flatten)]
+    pub message: Message,
+    /// Title of the parent conversation
+    pub conversation_title: String,
+}
+
+/// Optional details stored alongside a message.
+///
+/// Fields that are `None` are omitted when serialized.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageMetadata {
+    /// Provider that generated an assistant message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Model that generated an assistant message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Input tokens reported by the provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens: Option<u32>,
+    /// Output tokens reported by the provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completion_tokens: Option<u32>,
+    /// Time the provider took to answer (ms)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u32>,
+}
+
+/// A conversation with all of its messages, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationWithMessages {
+    /// Conversation row
+    #[serde(flatten)]
+    pub conversation: Conversation,
+    /// Messages, oldest first
+    pub messages: Vec<Message>,
+}
+
+/// A file whose text, or an image, was added to a message's prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Attachment {
+    pub id: String,
+    pub message_id: String,
+    /// File name without its directory
+    pub name: String,
+    /// Size on disk in bytes
+    pub size: i64,
+    /// Lines of the file included in the prompt
+    pub line_count: i64,
+    /// Whether the middle of the file was left out
+    pub truncated: bool,
+    /// Whether invalid characters were replaced while reading it
+    pub lossy: bool,
+    /// MIME type of an image, like `image/png`; `None` for text files
+    pub mime: Option<String>,
+    /// The image, base64-encoded; `None` for text files
+    pub data: Option<String>,
+    /// Unix timestamp (ms)
+    pub created_at: i64,
+}
+
+/// A file or image to record with a new message.
+///
+/// The `FileContent` returned by `read_file_for_prompt` and the
+/// `CapturedImage` returned by `capture_screen_region` can be passed as is;
+/// their other fields (like `content`) are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NewAttachment {
+    pub name: String,
+    pub size: u64,
+    /// `0` for images
+    #[serde(default)]
+    pub line_count: u64,
+    #[serde(default)]
+    pub truncated: bool,
+    #[serde(default)]
+    pub lossy: bool,
+    /// MIME type of an image; `None` for text files
+    #[serde(default)]
+    pub mime: Option<String>,
+    /// The image, base64-encoded; `None` for text files
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+/// A tag and how many conversations outside the trash carry it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagWithCount {
+    /// Normalized tag name
+    pub name: String,
+    /// Number of tagged conversations
+    pub conversation_count: i64,
+}
+
+/// Payload of the `restore-conversation` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RestoreConversation {
+    /// Conversation the launcher should open
+    pub conversation_id: String,
+}
+
+/// Why a history operation failed.
+///
+/// Serializes with a `kind` tag like [`crate::llm::LlmError`].
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum HistoryError {
+    /// No conversation with the given ID
+    #[error("Conversation not found: {0}")]
+    NotFound(String),
+    /// No message with the given ID
+    #[error("Message not found: {0}")]
+    MessageNotFound(String),
+    /// Message role other than `"user"` or `"assistant"`
+    #[error("Invalid message role: {0}")]
+    InvalidRole(String),
+    /// Tag name empty after trimming or longer than [`MAX_TAG_CHARS`]
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+    /// Wipe confirmation token missing, wrong, or expired
+    #[error("Wipe confirmation token is invalid or expired")]
+    InvalidToken,
+    /// Message is not a current assistant reply with a user message before it
+    #[error("Message cannot be regenerated: {0}")]
+    NotRegenerable(String),
+    /// The model call for a regeneration failed
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+    /// Unreadable or malformed export archive
+    #[error("Invalid history archive: {0}")]
+    Archive(String),
+    /// Missing or invalid database backup
+    #[error("Invalid history backup: {0}")]
+    Backup(String),
+    /// Other database operations are in progress
+    #[error("History database is busy")]
+    Busy,
+    /// The database couldn't be moved to the requested location
+    #[error("Cannot move history database: {0}")]
+    Relocate(String),
+    /// SQLite error
+    #[error("Database error: {0}")]
+    Database(String),
+    /// Unexpected failure outside the database (e.g. loading settings)
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl From<String> for HistoryError {
+    fn from(message: String) -> Self {
+        Self::Internal(message)
+    }
+}
+
+impl From<sqlx::Error> for HistoryError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error.to_string())
+    }
+}
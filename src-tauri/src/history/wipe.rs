@@ -0,0 +1,225 @@
+//! Two-step "delete all history".
+//!
+//! `request_history_wipe` hands out a random token; only
+//! `confirm_history_wipe` with that token, within [`TOKEN_TTL_MS`], deletes
+//! anything. Tokens are single-use: any confirmation attempt, right or
+//! wrong, consumes the pending token.
+
+use std::sync::Mutex;
+
+use sqlx::SqlitePool;
+
+use super::search;
+use super::types::HistoryError;
+use crate::db;
+use crate::llm::ResponseCache;
+
+/// How long a wipe token stays valid.
+pub const TOKEN_TTL_MS: i64 = 30_000;
+
+/// Pending wipe confirmation, managed as Tauri state.
+#[derive(Default)]
+pub struct WipeGuard {
+    /// `(token, issued_at_ms)` of the outstanding request
+    pending: Mutex<Option<(String, i64)>>,
+}
+
+impl WipeGuard {
+    /// Issue a new token, replacing any outstanding one.
+    pub fn issue(&self, now_ms: i64) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        *self.pending.lock().unwrap() = Some((token.clone(), now_ms));
+        token
+    }
+
+    /// Consume the pending token.
+    ///
+    /// # Returns
+    ///
+    /// * `Err(HistoryError::InvalidToken)` - No token pending, wrong token,
+    ///   or issued more than [`TOKEN_TTL_MS`] ago
+    pub fn consume(&self, token: &str, now_ms: i64) -> Result<(), HistoryError> {
+        match self.pending.lock().unwrap().take() {
+            Some((pending, issued_at))
+                if pending == token && now_ms - issued_at <= TOKEN_TTL_MS =>
+            {
+                Ok(())
+            }
+            _ => Err(HistoryError::InvalidToken),
+        }
+    }
+}
+
+/// Delete every conversation, message, draft, recorded prompt and cached
+/// answer, rebuild the now empty search index, then compact the file.
+///
+/// Cached answers repeat what was asked, so they go with the history, from
+/// `llm_cache` and from `cache`'s memory. Usage totals are left alone.
+pub async fn wipe_all(pool: &SqlitePool, cache: &ResponseCache) -> Result<(), HistoryError> {
+    let mut tx = db::begin_write(pool).await?;
+    sqlx::query("DELETE FROM llm_cache")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM drafts").execute(&mut *tx).await?;
+    sqlx::query("DELETE FROM prompt_history")
+        .execute(&mut *tx)
+        .await?;
+    // Emptied first so deleting each message has no index row to find
+    sqlx::query("DELETE FROM message_search")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM messages")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM conversations")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    cache.forget_all()?;
+    search::rebuild_index(pool).await?;
+
+    // VACUUM cannot run inside a transaction
+    sqlx::query("VACUUM").execute(pool).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::store;
+    use crate::history::types::MessageMetadata;
+
+    async fn seed(pool: &SqlitePool) {
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        store::append_message(
+            pool,
+            &conversation.id,
+            "user",
+            "secret",
+            &MessageMetadata::default(),
+            &[],
+            1,
+        )
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO usage_daily (day, provider, model, prompt_tokens, completion_tokens, request_count)
+             VALUES ('2026-01-01', 'gemini', 'gemini-2.0-flash', 1, 1, 1)",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO prompt_history (text, source, created_at) VALUES ('secret', 'typed', 1)",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn count(pool: &SqlitePool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    // ===== Tokens =====
+
+    #[test]
+    fn test_valid_token_is_single_use() {
+        let guard = WipeGuard::default();
+        let token = guard.issue(1_000);
+
+        assert_eq!(guard.consume(&token, 1_000 + TOKEN_TTL_MS), Ok(()));
+        assert_eq!(
+            guard.consume(&token, 1_000),
+            Err(HistoryError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn test_expired_token_is_rejected() {
+        let guard = WipeGuard::default();
+        let token = guard.issue(1_000);
+
+        assert_eq!(
+            guard.consume(&token, 1_001 + TOKEN_TTL_MS),
+            Err(HistoryError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn test_wrong_token_is_rejected_and_consumes() {
+        let guard = WipeGuard::default();
+        let token = guard.issue(1_000);
+
+        assert_eq!(
+            guard.consume("guess", 1_000),
+            Err(HistoryError::InvalidToken)
+        );
+        assert_eq!(
+            guard.consume(&token, 1_000),
+            Err(HistoryError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn test_new_request_replaces_token() {
+        let guard = WipeGuard::default();
+        let first = guard.issue(0);
+        let second = guard.issue(0);
+
+        assert_ne!(first, second);
+        assert_eq!(guard.consume(&second, 0), Ok(()));
+    }
+
+    // ===== Wipe =====
+
+    #[tokio::test]
+    async fn test_wipe_keeps_usage_totals() {
+        let db = Db::in_memory().await.unwrap();
+        seed(db.pool()).await;
+
+        wipe_all(db.pool(), &ResponseCache::new(db.pool().clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(count(db.pool(), "conversations").await, 0);
+        assert_eq!(count(db.pool(), "messages").await, 0);
+        assert_eq!(count(db.pool(), "prompt_history").await, 0);
+        assert_eq!(count(db.pool(), "usage_daily").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_wipe_empties_search_index() {
+        let db = Db::in_memory().await.unwrap();
+        seed(db.pool()).await;
+
+        wipe_all(db.pool(), &ResponseCache::new(db.pool().clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(count(db.pool(), "message_search").await, 0);
+        let indexed: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'secret'",
+        )
+        .fetch_one(db.pool())
+        .await
+        .unwrap();
+        assert_eq!(indexed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_wipe_forgets_cached_answers() {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        cache.put("k", "secret answer", 0).await.unwrap();
+
+        wipe_all(db.pool(), &cache).await.unwrap();
+
+        assert_eq!(count(db.pool(), "llm_cache").await, 0);
+        assert!(cache.get("k", 10, 0).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,342 @@
+//! Message catalogs, one per shipped locale.
+//!
+//! Keys are grouped by area (`tray.`, `updater.`, `shortcut.`,
+//! `notification.`, `platform.`). Placeholders are written `{name}` and
+//! filled in by [`super::t`]. English is the reference: every key must
+//! exist in it, and the other catalogs must have the same keys and
+//! placeholders (checked by the tests in [`super`]).
+
+/// A catalog: `(key, message)` pairs.
+pub type Catalog = &'static [(&'static str, &'static str)];
+
+pub const EN: Catalog = &[
+    // Tray menu
+    ("tray.toggle_launcher", "Toggle Launcher"),
+    ("tray.new_conversation", "New Conversation"),
+    ("tray.quick_actions", "Quick Actions"),
+    ("tray.no_quick_actions", "No Quick Actions"),
+    ("tray.pause_shortcuts", "Pause Shortcuts"),
+    ("tray.start_at_login", "Start at Login"),
+    ("tray.open_settings", "Open Settings"),
+    ("tray.check_updates", "Check for Updates"),
+    ("tray.quit", "Quit"),
+    ("tray.tooltip_paused", "{name} — shortcuts paused"),
+    ("tray.tooltip_busy", "{name} — working…"),
+    ("tray.tooltip_update", "{name} — update available"),
+    ("tray.tooltip_snoozed", "{name} — snoozed"),
+    (
+        "tray.tooltip_snoozed_until",
+        "{name} — snoozed until {time}",
+    ),
+    // Updater
+    (
+        "updater.http_status",
+        "The update server responded with HTTP {status}.",
+    ),
+    (
+        "updater.unreachable",
+        "Couldn't reach the update server. Check your internet connection and try again.",
+    ),
+    (
+        "updater.timeout",
+        "The update server took too long to respond. Try again later.",
+    ),
+    (
+        "updater.invalid_signature",
+        "The update's signature couldn't be verified, so it wasn't installed. \
+         Reinstall Qwik Ask from the official GitHub releases page.",
+    ),
+    (
+        "updater.manifest_malformed",
+        "The update server sent an update description this version can't read.",
+    ),
+    ("updater.io", "Couldn't save the update: {detail}"),
+    (
+        "updater.proxy_config",
+        "The proxy URL in Settings is invalid, so updates can't be checked: {detail}",
+    ),
+    ("updater.unknown", "Updating failed: {detail}"),
+    // Shortcuts
+    ("shortcut.empty", "Empty shortcut string"),
+    ("shortcut.unknown_key", "Unknown key: {key}"),
+    (
+        "shortcut.no_modifier",
+        "Shortcut must have at least one modifier (Ctrl, Alt, Shift, or Win)",
+    ),
+    ("shortcut.no_key", "Shortcut must have a non-modifier key"),
+    (
+        "shortcut.register_failed",
+        "Failed to register shortcut '{shortcut}': {error}",
+    ),
+    (
+        "shortcut.register_action_failed",
+        "Failed to register shortcut for {action}: {error}",
+    ),
+    // Global shortcut availability
+    (
+        "platform.wayland_reason",
+        "Wayland doesn't let apps register global shortcuts, and this desktop \
+         doesn't offer the global shortcuts portal.",
+    ),
+    (
+        "platform.instructions_wayland",
+        "Click the Qwik Ask tray icon to show or hide the launcher. Desktops with the global \
+         shortcuts portal (KDE Plasma, GNOME 48 or later) can bind the shortcut after a restart.",
+    ),
+    (
+        "platform.instructions_other",
+        "Click the Qwik Ask tray icon to show or hide the launcher, or choose a different \
+         shortcut in Settings.",
+    ),
+    // Notifications
+    ("notification.request_failed", "Request failed: {error}"),
+];
+
+pub const DE: Catalog = &[
+    ("tray.toggle_launcher", "Launcher ein-/ausblenden"),
+    ("tray.new_conversation", "Neue Unterhaltung"),
+    ("tray.quick_actions", "Schnellaktionen"),
+    ("tray.no_quick_actions", "Keine Schnellaktionen"),
+    ("tray.pause_shortcuts", "Tastenkürzel pausieren"),
+    ("tray.start_at_login", "Bei Anmeldung starten"),
+    ("tray.open_settings", "Einstellungen öffnen"),
+    ("tray.check_updates", "Nach Updates suchen"),
+    ("tray.quit", "Beenden"),
+    ("tray.tooltip_paused", "{name} — Tastenkürzel pausiert"),
+    ("tray.tooltip_busy", "{name} — arbeitet…"),
+    ("tray.tooltip_update", "{name} — Update verfügbar"),
+    ("tray.tooltip_snoozed", "{name} — schlummert"),
+    (
+        "tray.tooltip_snoozed_until",
+        "{name} — schlummert bis {time}",
+    ),
+    (
+        "updater.http_status",
+        "Der Update-Server antwortete mit HTTP {status}.",
+    ),
+    (
+        "updater.unreachable",
+        "Der Update-Server ist nicht erreichbar. Prüfe deine Internetverbindung und \
+         versuche es erneut.",
+    ),
+    (
+        "updater.timeout",
+        "Der Update-Server hat zu lange nicht geantwortet. Versuche es später erneut.",
+    ),
+    (
+        "updater.invalid_signature",
+        "Die Signatur des Updates konnte nicht geprüft werden, daher wurde es nicht \
+         installiert. Installiere Qwik Ask neu von der offiziellen GitHub-Release-Seite.",
+    ),
+    (
+        "updater.manifest_malformed",
+        "Der Update-Server hat eine Update-Beschreibung gesendet, die diese Version nicht \
+         lesen kann.",
+    ),
+    (
+        "updater.io",
+        "Das Update konnte nicht gespeichert werden: {detail}",
+    ),
+    (
+        "updater.proxy_config",
+        "Die Proxy-URL in den Einstellungen ist ungültig, daher kann nicht nach Updates \
+         gesucht werden: {detail}",
+    ),
+    ("updater.unknown", "Aktualisierung fehlgeschlagen: {detail}"),
+    ("shortcut.empty", "Leeres Tastenkürzel"),
+    ("shortcut.unknown_key", "Unbekannte Taste: {key}"),
+    (
+        "shortcut.no_modifier",
+        "Das Tastenkürzel braucht mindestens eine Zusatztaste (Strg, Alt, Umschalt oder Win)",
+    ),
+    (
+        "shortcut.no_key",
+        "Das Tastenkürzel braucht eine Taste, die keine Zusatztaste ist",
+    ),
+    (
+        "shortcut.register_failed",
+        "Tastenkürzel „{shortcut}“ konnte nicht registriert werden: {error}",
+    ),
+    (
+        "shortcut.register_action_failed",
+        "Tastenkürzel für {action} konnte nicht registriert werden: {error}",
+    ),
+    (
+        "platform.wayland_reason",
+        "Unter Wayland können Apps keine globalen Tastenkürzel registrieren, und diese \
+         Desktopumgebung bietet kein Portal für globale Tastenkürzel.",
+    ),
+    (
+        "platform.instructions_wayland",
+        "Klicke auf das Qwik-Ask-Symbol im Infobereich, um den Launcher ein- oder \
+         auszublenden. Desktops mit Portal für globale Tastenkürzel (KDE Plasma, GNOME 48 \
+         oder neuer) können das Tastenkürzel nach einem Neustart zuweisen.",
+    ),
+    (
+        "platform.instructions_other",
+        "Klicke auf das Qwik-Ask-Symbol im Infobereich, um den Launcher ein- oder \
+         auszublenden, oder wähle in den Einstellungen ein anderes Tastenkürzel.",
+    ),
+    (
+        "notification.request_failed",
+        "Anfrage fehlgeschlagen: {error}",
+    ),
+];
+
+pub const FR: Catalog = &[
+    ("tray.toggle_launcher", "Afficher/masquer le lanceur"),
+    ("tray.new_conversation", "Nouvelle conversation"),
+    ("tray.quick_actions", "Actions rapides"),
+    ("tray.no_quick_actions", "Aucune action rapide"),
+    ("tray.pause_shortcuts", "Suspendre les raccourcis"),
+    ("tray.start_at_login", "Lancer à l'ouverture de session"),
+    ("tray.open_settings", "Ouvrir les réglages"),
+    ("tray.check_updates", "Rechercher des mises à jour"),
+    ("tray.quit", "Quitter"),
+    ("tray.tooltip_paused", "{name} — raccourcis suspendus"),
+    ("tray.tooltip_busy", "{name} — en cours…"),
+    ("tray.tooltip_update", "{name} — mise à jour disponible"),
+    ("tray.tooltip_snoozed", "{name} — en veille"),
+    (
+        "tray.tooltip_snoozed_until",
+        "{name} — en veille jusqu’à {time}",
+    ),
+    (
+        "updater.http_status",
+        "Le serveur de mises à jour a répondu avec HTTP {status}.",
+    ),
+    (
+        "updater.unreachable",
+        "Impossible de joindre le serveur de mises à jour. Vérifiez votre connexion \
+         Internet et réessayez.",
+    ),
+    (
+        "updater.timeout",
+        "Le serveur de mises à jour a mis trop de temps à répondre. Réessayez plus tard.",
+    ),
+    (
+        "updater.invalid_signature",
+        "La signature de la mise à jour n'a pas pu être vérifiée, elle n'a donc pas été \
+         installée. Réinstallez Qwik Ask depuis la page officielle des versions GitHub.",
+    ),
+    (
+        "updater.manifest_malformed",
+        "Le serveur de mises à jour a envoyé une description que cette version ne sait pas \
+         lire.",
+    ),
+    (
+        "updater.io",
+        "Impossible d'enregistrer la mise à jour : {detail}",
+    ),
+    (
+        "updater.proxy_config",
+        "L'URL du proxy dans les réglages n'est pas valide, impossible de rechercher des \
+         mises à jour : {detail}",
+    ),
+    ("updater.unknown", "La mise à jour a échoué : {detail}"),
+    ("shortcut.empty", "Raccourci vide"),
+    ("shortcut.unknown_key", "Touche inconnue : {key}"),
+    (
+        "shortcut.no_modifier",
+        "Le raccourci doit comporter au moins une touche de modification (Ctrl, Alt, Maj \
+         ou Win)",
+    ),
+    (
+        "shortcut.no_key",
+        "Le raccourci doit comporter une touche autre qu'une touche de modification",
+    ),
+    (
+        "shortcut.register_failed",
+        "Impossible d'enregistrer le raccourci « {shortcut} » : {error}",
+    ),
+    (
+        "shortcut.register_action_failed",
+        "Impossible d'enregistrer le raccourci de {action} : {error}",
+    ),
+    (
+        "platform.wayland_reason",
+        "Wayland ne permet pas aux applications d'enregistrer des raccourcis globaux, et ce \
+         bureau ne propose pas le portail des raccourcis globaux.",
+    ),
+    (
+        "platform.instructions_wayland",
+        "Cliquez sur l'icône Qwik Ask de la zone de notification pour afficher ou masquer \
+         le lanceur. Les bureaux dotés du portail des raccourcis globaux (KDE Plasma, GNOME \
+         48 ou plus récent) peuvent associer le raccourci après un redémarrage.",
+    ),
+    (
+        "platform.instructions_other",
+        "Cliquez sur l'icône Qwik Ask de la zone de notification pour afficher ou masquer \
+         le lanceur, ou choisissez un autre raccourci dans les réglages.",
+    ),
+    (
+        "notification.request_failed",
+        "La requête a échoué : {error}",
+    ),
+];
+
+pub const ZH_CN: Catalog = &[
+    ("tray.toggle_launcher", "显示/隐藏启动器"),
+    ("tray.new_conversation", "新对话"),
+    ("tray.quick_actions", "快捷操作"),
+    ("tray.no_quick_actions", "没有快捷操作"),
+    ("tray.pause_shortcuts", "暂停快捷键"),
+    ("tray.start_at_login", "登录时启动"),
+    ("tray.open_settings", "打开设置"),
+    ("tray.check_updates", "检查更新"),
+    ("tray.quit", "退出"),
+    ("tray.tooltip_paused", "{name} — 快捷键已暂停"),
+    ("tray.tooltip_busy", "{name} — 处理中…"),
+    ("tray.tooltip_update", "{name} — 有可用更新"),
+    ("tray.tooltip_snoozed", "{name} — 已暂停"),
+    ("tray.tooltip_snoozed_until", "{name} — 暂停至 {time}"),
+    ("updater.http_status", "更新服务器返回了 HTTP {status}。"),
+    (
+        "updater.unreachable",
+        "无法连接到更新服务器。请检查网络连接后重试。",
+    ),
+    ("updater.timeout", "更新服务器响应超时。请稍后重试。"),
+    (
+        "updater.invalid_signature",
+        "无法验证更新的签名，因此未安装。请从官方 GitHub 发布页面重新安装 Qwik Ask。",
+    ),
+    (
+        "updater.manifest_malformed",
+        "更新服务器发送的更新说明无法被此版本读取。",
+    ),
+    ("updater.io", "无法保存更新：{detail}"),
+    (
+        "updater.proxy_config",
+        "设置中的代理 URL 无效，无法检查更新：{detail}",
+    ),
+    ("updater.unknown", "更新失败：{detail}"),
+    ("shortcut.empty", "快捷键为空"),
+    ("shortcut.unknown_key", "未知按键：{key}"),
+    (
+        "shortcut.no_modifier",
+        "快捷键必须至少包含一个修饰键（Ctrl、Alt、Shift 或 Win）",
+    ),
+    ("shortcut.no_key", "快捷键必须包含一个非修饰键"),
+    (
+        "shortcut.register_failed",
+        "无法注册快捷键“{shortcut}”：{error}",
+    ),
+    (
+        "shortcut.register_action_failed",
+        "无法注册 {action} 的快捷键：{error}",
+    ),
+    (
+        "platform.wayland_reason",
+        "Wayland 不允许应用注册全局快捷键，而此桌面环境未提供全局快捷键门户。",
+    ),
+    (
+        "platform.instructions_wayland",
+        "点击托盘中的 Qwik Ask 图标即可显示或隐藏启动器。提供全局快捷键门户的桌面环境\
+         （KDE Plasma、GNOME 48 或更高版本）可在重启后绑定快捷键。",
+    ),
+    (
+        "platform.instructions_other",
+        "点击托盘中的 Qwik Ask 图标即可显示或隐藏启动器，或在设置中选择其他快捷键。",
+    ),
+    ("notification.request_failed", "请求失败：{error}"),
+];
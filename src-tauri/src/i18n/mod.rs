@@ -0,0 +1,433 @@
+//! Localized backend strings.
+//!
+//! Text the Rust side shows to users (tray menu and tooltip, updater
+//! messages, shortcut errors, notification bodies) comes from the
+//! catalogs in [`catalog`] through [`t`]. The locale is the
+//! `general.locale` setting, or the OS language when it's unset, and is
+//! switched process-wide by [`apply`]. Keys missing from a catalog fall
+//! back to English.
+//!
+//! Log messages stay in English.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! // [{ code: 'en', name: 'English' }, { code: 'de', name: 'Deutsch' }, ...]
+//! const locales = await invoke<LocaleInfo[]>('get_available_locales');
+//!
+//! // `null` follows the OS language
+//! await invoke('update_settings', {
+//!   settings: { ...settings, general: { ...settings.general, locale: 'de' } },
+//! });
+//! ```
+
+mod catalog;
+
+use std::fmt::Display;
+use std::sync::RwLock;
+
+use serde::Serialize;
+
+use catalog::Catalog;
+
+/// A shipped locale.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+    ZhCn,
+}
+
+impl Locale {
+    /// Every shipped locale, English first.
+    pub const ALL: [Locale; 4] = [Locale::En, Locale::De, Locale::Fr, Locale::ZhCn];
+
+    /// BCP 47 code, as stored in `general.locale`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+            Locale::Fr => "fr",
+            Locale::ZhCn => "zh-CN",
+        }
+    }
+
+    /// The locale's name in its own language, for the settings dropdown.
+    pub fn name(self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::De => "Deutsch",
+            Locale::Fr => "Français",
+            Locale::ZhCn => "简体中文",
+        }
+    }
+
+    fn catalog(self) -> Catalog {
+        match self {
+            Locale::En => catalog::EN,
+            Locale::De => catalog::DE,
+            Locale::Fr => catalog::FR,
+            Locale::ZhCn => catalog::ZH_CN,
+        }
+    }
+
+    /// The shipped locale for a language tag, if any.
+    ///
+    /// Accepts BCP 47 tags (`de-AT`, `zh-Hans-CN`) and POSIX locale names
+    /// (`fr_FR.UTF-8`). Traditional Chinese isn't shipped, so `zh-TW`,
+    /// `zh-HK` and `zh-Hant` don't match.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .replace('_', "-")
+            .to_ascii_lowercase();
+        let mut parts = tag.split('-');
+        match parts.next()? {
+            "en" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            "fr" => Some(Locale::Fr),
+            "zh" if parts.any(|part| matches!(part, "tw" | "hk" | "mo" | "hant")) => None,
+            "zh" => Some(Locale::ZhCn),
+            _ => None,
+        }
+    }
+}
+
+/// The locale [`t`] translates to.
+static CURRENT: RwLock<Locale> = RwLock::new(Locale::En);
+
+/// The locale strings are currently translated to.
+pub fn current() -> Locale {
+    *CURRENT.read().unwrap()
+}
+
+/// The locale to use.
+///
+/// # Arguments
+///
+/// * `setting` - The `general.locale` setting; `None` follows the OS
+/// * `system` - The OS language, if known
+///
+/// # Returns
+///
+/// The first of `setting` and `system` that is shipped, or English.
+pub fn resolve(setting: Option<&str>, system: Option<&str>) -> Locale {
+    setting
+        .and_then(Locale::from_tag)
+        .or_else(|| system.and_then(Locale::from_tag))
+        .unwrap_or_default()
+}
+
+/// Switch the locale for the `general.locale` setting.
+///
+/// Strings produced afterwards use it; the tray relabels itself on its
+/// next [`crate::tray::refresh_menu`].
+pub fn apply(setting: Option<&str>) {
+    let locale = resolve(setting, system_locale().as_deref());
+    let mut current = CURRENT.write().unwrap();
+    if *current != locale {
+        tracing::info!(locale = locale.code(), "Switching locale");
+        *current = locale;
+    }
+}
+
+/// Translate `key` to the current locale, filling in `{placeholders}`.
+///
+/// # Arguments
+///
+/// * `key` - Catalog key, e.g. `"tray.quit"`
+/// * `args` - Placeholder names and values
+///
+/// # Example
+///
+/// ```rust,ignore
+/// t("shortcut.unknown_key", &[("key", &"foo")]); // "Unknown key: foo"
+/// ```
+pub fn t(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    translate(current(), key, args)
+}
+
+/// Translate `key` to `locale`.
+pub fn translate(locale: Locale, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let message = lookup(locale.catalog(), catalog::EN, key).unwrap_or(key);
+    format_message(message, args)
+}
+
+/// `key` in `catalog`, or in `fallback` if it's missing there.
+fn lookup(catalog: Catalog, fallback: Catalog, key: &str) -> Option<&'static str> {
+    let find = |catalog: Catalog| {
+        catalog
+            .iter()
+            .find(|(entry, _)| *entry == key)
+            .map(|(_, message)| *message)
+    };
+    find(catalog).or_else(|| {
+        tracing::debug!(key, "Missing translation; using English");
+        find(fallback)
+    })
+}
+
+/// Replace each `{name}` in `message` with its value from `args`.
+fn format_message(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter()
+        .fold(message.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), &value.to_string())
+        })
+}
+
+/// The OS language: `LC_ALL`, `LC_MESSAGES` or `LANG`.
+#[cfg(target_os = "linux")]
+fn system_locale() -> Option<String> {
+    env_locale()
+}
+
+/// The OS language: the first preferred language.
+#[cfg(target_os = "macos")]
+fn system_locale() -> Option<String> {
+    use objc2_foundation::NSLocale;
+
+    NSLocale::preferredLanguages()
+        .firstObject()
+        .map(|language| language.to_string())
+        .or_else(env_locale)
+}
+
+/// The OS language: the user's default locale.
+#[cfg(target_os = "windows")]
+fn system_locale() -> Option<String> {
+    use windows_sys::Win32::Globalization::GetUserDefaultLocaleName;
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut name = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(name.as_mut_ptr(), name.len() as i32) };
+    if len <= 1 {
+        return env_locale();
+    }
+    // The length includes the terminating NUL
+    Some(String::from_utf16_lossy(&name[..len as usize - 1]))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn system_locale() -> Option<String> {
+    env_locale()
+}
+
+/// The locale from the POSIX environment variables, skipping the `C`
+/// and `POSIX` locales.
+fn env_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+}
+
+/// A locale offered in the settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LocaleInfo {
+    /// Value for `general.locale`
+    pub code: &'static str,
+    /// Name in the locale's own language
+    pub name: &'static str,
+}
+
+/// The shipped locales, for the settings dropdown.
+#[tauri::command]
+pub fn get_available_locales() -> Vec<LocaleInfo> {
+    Locale::ALL
+        .iter()
+        .map(|locale| LocaleInfo {
+            code: locale.code(),
+            name: locale.name(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `{placeholders}` in a message, sorted.
+    fn placeholders(message: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = message
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    // ===== Catalogs =====
+
+    #[test]
+    fn test_every_key_in_every_locale() {
+        for locale in Locale::ALL {
+            for (key, _) in catalog::EN {
+                assert!(
+                    locale.catalog().iter().any(|(entry, _)| entry == key),
+                    "{} is missing {}",
+                    locale.code(),
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_keys_beyond_english() {
+        for locale in Locale::ALL {
+            for (key, _) in locale.catalog() {
+                assert!(
+                    catalog::EN.iter().any(|(entry, _)| entry == key),
+                    "{} has unknown key {}",
+                    locale.code(),
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_duplicate_keys() {
+        for locale in Locale::ALL {
+            let catalog = locale.catalog();
+            for (i, (key, _)) in catalog.iter().enumerate() {
+                assert!(
+                    !catalog[i + 1..].iter().any(|(other, _)| other == key),
+                    "{} has {} twice",
+                    locale.code(),
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_placeholders_match_english() {
+        for locale in Locale::ALL {
+            for (key, english) in catalog::EN {
+                let translated = lookup(locale.catalog(), &[], key).unwrap();
+                assert_eq!(
+                    placeholders(translated),
+                    placeholders(english),
+                    "{} {}",
+                    locale.code(),
+                    key
+                );
+            }
+        }
+    }
+
+    // ===== Translating =====
+
+    #[test]
+    fn test_translate_fills_placeholders() {
+        assert_eq!(
+            translate(Locale::En, "shortcut.unknown_key", &[("key", &"foo")]),
+            "Unknown key: foo"
+        );
+        assert_eq!(
+            translate(Locale::De, "updater.http_status", &[("status", &404)]),
+            "Der Update-Server antwortete mit HTTP 404."
+        );
+        assert_eq!(
+            translate(
+                Locale::En,
+                "shortcut.register_failed",
+                &[("shortcut", &"Ctrl+K"), ("error", &"in use")]
+            ),
+            "Failed to register shortcut 'Ctrl+K': in use"
+        );
+    }
+
+    #[test]
+    fn test_translate_per_locale() {
+        assert_eq!(translate(Locale::En, "tray.quit", &[]), "Quit");
+        assert_eq!(translate(Locale::De, "tray.quit", &[]), "Beenden");
+        assert_eq!(translate(Locale::Fr, "tray.quit", &[]), "Quitter");
+        assert_eq!(translate(Locale::ZhCn, "tray.quit", &[]), "退出");
+    }
+
+    #[test]
+    fn test_missing_key_falls_back_to_english() {
+        let partial: Catalog = &[("tray.quit", "Beenden")];
+
+        assert_eq!(lookup(partial, catalog::EN, "tray.quit"), Some("Beenden"));
+        assert_eq!(
+            lookup(partial, catalog::EN, "tray.open_settings"),
+            Some("Open Settings")
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_is_returned_as_is() {
+        assert_eq!(translate(Locale::Fr, "no.such.key", &[]), "no.such.key");
+    }
+
+    // ===== Locales =====
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(Locale::from_tag("en"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("en-US"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("de_AT.UTF-8"), Some(Locale::De));
+        assert_eq!(Locale::from_tag("fr-CA"), Some(Locale::Fr));
+        assert_eq!(Locale::from_tag("fr_FR@euro"), Some(Locale::Fr));
+        assert_eq!(Locale::from_tag("zh-CN"), Some(Locale::ZhCn));
+        assert_eq!(Locale::from_tag("zh-Hans-CN"), Some(Locale::ZhCn));
+        assert_eq!(Locale::from_tag("zh_SG.UTF-8"), Some(Locale::ZhCn));
+    }
+
+    #[test]
+    fn test_from_tag_unshipped() {
+        assert_eq!(Locale::from_tag("zh-TW"), None);
+        assert_eq!(Locale::from_tag("zh-Hant-HK"), None);
+        assert_eq!(Locale::from_tag("ja-JP"), None);
+        assert_eq!(Locale::from_tag("C"), None);
+        assert_eq!(Locale::from_tag(""), None);
+    }
+
+    #[test]
+    fn test_codes_roundtrip() {
+        for locale in Locale::ALL {
+            assert_eq!(Locale::from_tag(locale.code()), Some(locale));
+        }
+    }
+
+    #[test]
+    fn test_resolve_prefers_setting() {
+        assert_eq!(resolve(Some("fr"), Some("de-DE")), Locale::Fr);
+    }
+
+    #[test]
+    fn test_resolve_follows_system_without_setting() {
+        assert_eq!(resolve(None, Some("de-DE")), Locale::De);
+        assert_eq!(resolve(Some("xx"), Some("zh_CN.UTF-8")), Locale::ZhCn);
+    }
+
+    #[test]
+    fn test_resolve_defaults_to_english() {
+        assert_eq!(resolve(None, None), Locale::En);
+        assert_eq!(resolve(None, Some("ja-JP")), Locale::En);
+    }
+
+    #[test]
+    fn test_available_locales() {
+        let locales = get_available_locales();
+
+        assert_eq!(locales.len(), 4);
+        assert_eq!(
+            locales[0],
+            LocaleInfo {
+                code: "en",
+                name: "English"
+            }
+        );
+        assert!(locales.iter().any(|locale| locale.code == "zh-CN"));
+    }
+}
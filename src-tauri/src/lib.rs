@@ -17,48 +17,139 @@
 //! - [`settings`] - Settings management (types, persistence, Tauri commands)
 //! - [`shortcuts`] - Global shortcut parsing utilities
 //! - [`tray`] - System tray setup and event handling
-//! - [`window`] - Window management commands
+//! - [`window`] - Window management commands and launcher placement
 //! - [`migrations`] - SQLite database migrations for chat history
+//! - [`db`] - Rust-side connection pool for the history database
+//! - [`llm`] - LLM requests, response caching, and usage tracking
+//! - [`history`] - Conversation and message commands
+//! - [`clipboard`] - Clipboard text and the ask-about-clipboard shortcut
+//! - [`network`] - HTTP clients honoring the proxy and timeout settings
+//! - [`quick_actions`] - Predefined prompts with their own shortcuts and tray entries
+//! - [`files`] - Reading dropped or picked files into the prompt
+//! - [`capture`] - Screen region capture for image prompts
+//! - [`notifications`] - System notifications for answers that arrive while hidden
+//! - [`speech`] - Reading answers aloud
+//! - [`deeplink`] - `qwikask://` links for opening queries from other apps
+//! - [`diagnostics`] - Redacted diagnostics report for bug reports
+//! - [`app_info`] - Version, commit and paths for the About panel
+//! - [`onboarding`] - First-run walkthrough of the shortcut and provider setup
+//! - [`prompts`] - Library of saved system prompts and the active persona
+//! - [`platform`] - Wayland detection and the fallbacks when the shortcut can't be registered
+//! - [`logging`] - Rotating log file with a settings-controlled level
+//! - [`args`] - Command-line arguments (`--query`, `--hidden`, `--settings`, ...)
+//! - [`shutdown`] - Ordered shutdown with a watchdog, for quit, restart and OS exit
+//! - [`i18n`] - Localized tray labels, notifications and backend messages
+//! - [`telemetry`] - Opt-in anonymous usage events, sent at most once a day
+//! - [`paths`] - Data directories and portable mode
+//! - [`env_file`] - `.env` in the data directory, for provider credentials
 
 use tauri::Manager;
+use tauri_plugin_global_shortcut::ShortcutState;
 
+mod app_info;
+mod args;
+mod capture;
+mod clipboard;
+mod db;
+mod deeplink;
+mod diagnostics;
+mod env_file;
+mod files;
+mod history;
+mod i18n;
+mod llm;
+mod logging;
 mod migrations;
+mod network;
+mod notifications;
+mod onboarding;
+mod paths;
+mod platform;
+mod prompts;
+mod quick_actions;
 mod settings;
 mod shortcuts;
+mod shutdown;
+mod snooze;
+mod speech;
+mod telemetry;
 mod tray;
 mod updater;
 mod window;
 
-use settings::SettingsManager;
+use settings::{AppSettings, LauncherSettings, SettingsManager};
+use shortcuts::GlobalAction;
+use window::toggle::ShortcutAction;
+use window::visibility::VisibilityReason;
+
+/// Toggle shortcut used when the configured one can't be registered.
+const DEFAULT_SHORTCUT: &str = "Alt+Shift+Space";
 
 /// Main application entry point.
 ///
 /// Initializes all Tauri plugins and sets up the application:
 ///
-/// 1. **Plugins**: autostart, store, global shortcuts, opener, SQL
-/// 2. **Setup**: Settings loading, shortcut registration, tray creation
-/// 3. **Commands**: Registers all Tauri commands for frontend communication
+/// 1. **Arguments**: Parses the command line; `--version` and `--help` exit here
+/// 2. **Plugins**: single instance, deep links, autostart, store, global shortcuts,
+///    opener
+/// 3. **Setup**: Settings loading, shortcut registration, SQL plugin, history DB pool,
+///    tray creation, then the command-line intent
+/// 4. **Commands**: Registers all Tauri commands for frontend communication
 ///
 /// # Panics
 ///
 /// Panics if the Tauri application fails to start (e.g., missing configuration).
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // `--version` and `--help` exit here, before any window or plugin exists
+    let Some(intent) = args::from_env() else {
+        return;
+    };
+    // Before the builder, so every file lookup in setup sees the same mode
+    paths::init(intent.portable);
+
     tauri::Builder::default()
-        .plugin(tauri_plugin_autostart::Builder::new().build())
+        // First, so a second launch hands over its arguments before doing anything
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            args::forwarded(app, argv);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(
+            tauri_plugin_autostart::Builder::new()
+                .args(settings::AUTOSTART_ARGS.iter().copied())
+                .build(),
+        )
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
-                .with_handler(|app, _shortcut, event| {
-                    if event.state() == tauri_plugin_global_shortcut::ShortcutState::Pressed {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let is_visible = window.is_visible().unwrap_or(false);
-                            if is_visible {
-                                let _ = window.hide();
-                            } else {
-                                let _ = window.show();
-                                let _ = window.set_focus();
+                .with_handler(|app, shortcut, event| match event.state() {
+                    ShortcutState::Pressed if *shortcut == window::escape::escape_shortcut() => {
+                        window::handle_escape(app);
+                    }
+                    ShortcutState::Released if *shortcut == window::escape::escape_shortcut() => {}
+                    ShortcutState::Pressed => {
+                        match app.state::<SettingsManager>().action_for(shortcut) {
+                            Some(GlobalAction::AskClipboard) => clipboard::ask_from_shortcut(app),
+                            Some(GlobalAction::AskSelection) => {
+                                clipboard::ask_selection_from_shortcut(app)
+                            }
+                            Some(GlobalAction::QuickAction(id)) => {
+                                quick_actions::spawn(app, id, VisibilityReason::Hotkey)
                             }
+                            None => {
+                                if window::toggle_launcher(app) == Some(ShortcutAction::Show) {
+                                    history::restore_last_conversation(app);
+                                }
+                            }
+                        }
+                    }
+                    ShortcutState::Released => {
+                        if app
+                            .state::<SettingsManager>()
+                            .action_for(shortcut)
+                            .is_none()
+                        {
+                            window::handle_shortcut_release(app);
                         }
                     }
                 })
@@ -66,35 +157,305 @@ pub fn run() {
         )
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
-        .plugin(
-            tauri_plugin_sql::Builder::default()
-                .add_migrations("sqlite:history.db", migrations::get_migrations())
-                .build(),
-        )
-        .setup(|app| {
+        .plugin(tauri_plugin_notification::init())
+        .on_window_event(|window, event| match event {
+            // Hide instead of destroying the webview; the tray brings it back
+            tauri::WindowEvent::CloseRequested { api, .. } => {
+                let is_quitting = window.state::<window::QuitFlag>().is_quitting();
+                if window::should_hide_on_close(window.label(), is_quitting) {
+                    api.prevent_close();
+                    if window.label() == "main" {
+                        window::hide_launcher(window.app_handle(), VisibilityReason::Closed);
+                    } else {
+                        let _ = window.hide();
+                        window::handle_window_focus(window.app_handle(), window.label(), false);
+                    }
+                }
+            }
+            tauri::WindowEvent::Focused(focused) => {
+                window::handle_window_focus(window.app_handle(), window.label(), *focused);
+                if *focused && window.label() == "main" {
+                    window
+                        .state::<window::input_focus::FocusSignal>()
+                        .focus_gained();
+                }
+                if !focused && window.label() == "main" {
+                    window::handle_launcher_blur(window.app_handle());
+                }
+            }
+            tauri::WindowEvent::ThemeChanged(theme) if window.label() == "main" => {
+                tray::set_system_theme(window.app_handle(), *theme);
+            }
+            tauri::WindowEvent::ScaleFactorChanged { .. } if window.label() == "main" => {
+                window::handle_scale_factor_changed(window);
+            }
+            // Remember where the launcher is left (`launcher.placement`)
+            tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_)
+                if window.label() == "main" =>
+            {
+                window::remember_launcher_geometry(window.app_handle());
+            }
+            _ => {}
+        })
+        .setup(move |app| {
+            app.manage(app_info::LaunchTime::default());
             let settings_manager = SettingsManager::new(app.handle().clone());
-            initialize_settings(&settings_manager);
+            let log_level = settings_manager
+                .snapshot()
+                .map(|settings| settings.general.log_level)
+                .unwrap_or_default();
+            app.manage(logging::init(app.handle(), log_level));
+            tracing::info!(version = env!("CARGO_PKG_VERSION"), "Starting Qwik Ask");
+            app.manage(env_file::EnvFile::default());
+            if let Err(e) = env_file::load(app.handle()) {
+                tracing::warn!(error = %e, "Failed to read the .env file");
+            }
+
+            let (shortcut, registered) = initialize_settings(&settings_manager);
             app.manage(settings_manager);
+            app.manage(platform::ShortcutStatus::default());
+            platform::handle_registration(app.handle(), &shortcut, registered);
+            app.manage(window::QuitFlag::default());
+            app.manage(shutdown::ShutdownState::default());
+            app.manage(window::toggle::LauncherState::default());
+            app.manage(window::resize::ResizeDebouncer::default());
+            app.manage(window::LauncherPositioning::default());
+            app.manage(window::peek::PeekTracker::default());
+            app.manage(window::focus::FocusTracker::native());
+            app.manage(window::input_focus::FocusSignal::default());
+            app.manage(window::escape::EscapeKey::default());
+            app.manage(speech::Speaker::native());
+            app.manage(window::quit::QuitRequests::default());
+            let handle = app.handle().clone();
+            app.manage(window::quit::ActivityTracker::new(move || {
+                tray::refresh_state(&handle)
+            }));
+
+            let db_path = db::database_path(app.handle())?;
+            history::relocate::remove_stale_files(&app.state::<SettingsManager>(), &db_path);
+            // Here rather than on the builder: the migrations are keyed by
+            // the URL, which depends on `history.database_path`
+            app.handle().plugin(
+                tauri_plugin_sql::Builder::default()
+                    .add_migrations(
+                        &paths::configured_database_url(&app.state::<SettingsManager>()),
+                        migrations::get_migrations(),
+                    )
+                    .build(),
+            )?;
+            app.manage(db::schema::SchemaGuard::default());
+            // A database from a newer version is left alone; see `db::schema`
+            let too_new = tauri::async_runtime::block_on(db::schema::check(&db_path))?;
+            let db = match too_new.clone() {
+                None => tauri::async_runtime::block_on(db::Db::open(&db_path))?,
+                Some(status) => {
+                    db::schema::report(app.handle(), status);
+                    tauri::async_runtime::block_on(db::Db::in_memory())?
+                }
+            };
+            app.manage(llm::ResponseCache::new(db.pool().clone()));
+            app.manage(llm::Connectivity::default());
+            let llm_settings = app
+                .state::<SettingsManager>()
+                .snapshot()
+                .map(|settings| settings.llm.clone())
+                .unwrap_or_default();
+            app.manage(llm::RequestLimiter::new(llm::Limits::from_settings(
+                &llm_settings,
+            )));
+            app.manage(history::wipe::WipeGuard::default());
+            app.manage(history::drafts::DraftTracker::default());
+            app.manage(snooze::SnoozeGuard::default());
+            app.manage(notifications::held::HeldNotifications::default());
+            notifications::init(app.handle());
+            app.manage(tauri::async_runtime::block_on(
+                history::backup::ChangeTracker::open(&db_path),
+            )?);
+
+            app.manage(history::retention::MaintenanceScheduler::default());
+            app.manage(db);
+            history::encryption::resume(app.handle());
+            app.manage(shutdown::BackgroundTasks::default());
+            // Backing up the stand-in database would rotate real backups out
+            if too_new.is_none() {
+                app.state::<shutdown::BackgroundTasks>()
+                    .track(history::start_backup_scheduler(app.handle()));
+                // Retention, trash and draft cleanup, shortly after startup and then daily
+                app.state::<shutdown::BackgroundTasks>()
+                    .track(history::start_maintenance(app.handle()));
+            }
+            telemetry::record(app.handle(), telemetry::TelemetryEvent::AppStart);
+            app.state::<shutdown::BackgroundTasks>()
+                .track(telemetry::start_scheduler(app.handle()));
+
+            app.manage(updater::UpdateScheduler::new(db::now_ms()));
+            let updates = app
+                .state::<SettingsManager>()
+                .snapshot()
+                .map(|settings| settings.updates.clone())
+                .unwrap_or_default();
+            updater::apply_schedule(app.handle(), &updates);
+            let staged = app
+                .state::<SettingsManager>()
+                .load_state(updater::staging::STAGED_UPDATE_KEY)
+                .ok()
+                .flatten();
+            app.manage(updater::UpdateStage::restore(staged));
+            app.manage(updater::cancel::DownloadControl::default());
+            app.manage(updater::notes::ReleaseNotes::default());
+            // Before the launch is recorded, to tell a fresh install apart
+            onboarding::start(app.handle());
+            updater::record_launch(app.handle());
+            updater::confirm_restored_stage(app.handle());
 
             tray::setup(app)?;
+            deeplink::listen(app.handle());
+            args::execute(app.handle(), intent);
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             window::open_settings,
+            window::set_launcher_pinned,
+            window::resize_launcher,
+            window::save_current_position,
+            window::lock_launcher_position,
+            window::unlock_launcher_position,
+            window::show_main_window,
+            window::hide_main_window,
+            window::detach_conversation,
+            window::close_detached,
+            window::list_detached_windows,
+            window::confirm_quit,
             settings::get_settings,
             settings::update_settings,
             settings::reset_settings,
+            settings::get_settings_audit,
+            env_file::reload_env,
             settings::get_auto_startup_status,
+            settings::repair_autostart,
+            i18n::get_available_locales,
+            settings::get_shortcuts_paused,
+            settings::set_shortcuts_paused,
             settings::open_settings_file,
+            settings::open_app_data_dir,
+            paths::get_database_url,
             settings::get_environment_variable,
             updater::check_for_updates,
+            updater::get_update_status,
             updater::download_and_install_update,
+            updater::download_update,
+            updater::install_staged_update,
+            updater::cancel_update_download,
+            updater::get_release_notes,
+            updater::get_update_transition,
+            updater::is_first_run,
+            updater::acknowledge_update_transition,
             updater::restart_app,
             updater::get_current_version,
+            updater::skip_update_version,
+            llm::ask_llm,
+            llm::ask_llm_with_image,
+            llm::check_connectivity,
+            llm::language::get_supported_languages,
+            clipboard::get_clipboard_text,
+            clipboard::ask_clipboard,
+            clipboard::copy_to_clipboard,
+            quick_actions::list_quick_actions,
+            quick_actions::upsert_quick_action,
+            quick_actions::delete_quick_action,
+            quick_actions::run_quick_action,
+            files::read_file_for_prompt,
+            capture::capture_screen_region,
+            speech::speak_text,
+            speech::stop_speaking,
+            speech::get_speaking,
+            speech::list_voices,
+            telemetry::get_telemetry_preview,
+            logging::open_logs_dir,
+            logging::get_recent_logs,
+            app_info::get_app_info,
+            onboarding::get_onboarding_state,
+            onboarding::advance_onboarding,
+            onboarding::complete_onboarding,
+            prompts::list_prompts,
+            prompts::upsert_prompt,
+            prompts::duplicate_prompt,
+            prompts::delete_prompt,
+            prompts::set_active_prompt,
+            platform::get_shortcut_capability,
+            diagnostics::generate_diagnostics_report,
+            diagnostics::save_diagnostics_report,
+            llm::list_models,
+            llm::presets::list_provider_presets,
+            llm::models::set_model_alias,
+            llm::models::toggle_favorite_model,
+            llm::validate_api_key,
+            llm::clear_llm_cache,
+            llm::get_usage_stats,
+            history::create_conversation,
+            history::list_conversations,
+            history::add_tag,
+            history::remove_tag,
+            history::list_tags,
+            history::get_conversation,
+            history::branch_conversation,
+            history::get_last_conversation,
+            history::rename_conversation,
+            history::generate_conversation_title,
+            history::set_conversation_pinned,
+            history::set_conversation_archived,
+            history::set_conversation_system_prompt,
+            history::save_draft,
+            history::get_draft,
+            history::move_history_db,
+            history::get_history_capabilities,
+            snooze::snooze,
+            snooze::resume_from_snooze,
+            snooze::get_snooze_state,
+            notifications::get_focus_assist_state,
+            history::delete_conversation,
+            history::list_trashed_conversations,
+            history::restore_conversation,
+            history::purge_conversation,
+            history::append_message,
+            history::get_message_attachments,
+            history::get_messages,
+            history::copy_message,
+            history::regenerate_message,
+            history::set_message_starred,
+            history::list_starred_messages,
+            history::search_history,
+            history::export_all_history,
+            history::import_history,
+            history::request_history_wipe,
+            history::confirm_history_wipe,
+            history::get_history_stats,
+            history::get_activity_stats,
+            history::optimize_history_db,
+            history::enforce_history_retention,
+            history::get_prompt_history,
+            history::clear_prompt_history,
+            db::schema::get_db_schema_status,
+            db::schema::recreate_history_db,
+            history::backup_history_db,
+            history::list_backups,
+            history::restore_backup,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Shut down cleanly first; `shutdown` exits once it's done
+            tauri::RunEvent::ExitRequested { api, .. }
+                if app
+                    .try_state::<shutdown::ShutdownState>()
+                    .is_some_and(|state| !state.is_started()) =>
+            {
+                api.prevent_exit();
+                shutdown::shutdown(app, shutdown::ShutdownReason::ExitRequested);
+            }
+            _ => {}
+        });
 }
 
 /// Initialize settings on application startup.
@@ -111,20 +472,42 @@ pub fn run() {
 /// # Arguments
 ///
 /// * `settings_manager` - The settings manager instance to use
-fn initialize_settings(settings_manager: &SettingsManager) {
-    match settings_manager.load() {
+///
+/// # Returns
+///
+/// The toggle shortcut and whether it could be registered, for
+/// [`platform::handle_registration`].
+#[tracing::instrument(skip_all)]
+fn initialize_settings(settings_manager: &SettingsManager) -> (String, Result<(), String>) {
+    match settings_manager.snapshot() {
         Ok(settings) => {
-            if let Err(e) =
-                settings_manager.register_initial_shortcut(&settings.shortcuts.toggle_launcher)
-            {
-                eprintln!("Failed to register shortcut: {}. Using default.", e);
-                let _ = settings_manager.register_initial_shortcut("Alt+Shift+Space");
+            i18n::apply(settings.general.locale.as_deref());
+            let mut shortcut = settings.shortcuts.toggle_launcher.clone();
+            let mut registered = settings_manager.register_initial_shortcut(&shortcut);
+            if let Err(e) = &registered {
+                tracing::warn!(error = %e, "Failed to register shortcut; using the default");
+                shortcut = DEFAULT_SHORTCUT.to_string();
+                registered = settings_manager.register_initial_shortcut(&shortcut);
+            }
+            if let Err(e) = settings_manager.apply_action_shortcuts(&settings) {
+                tracing::warn!("{}", e);
             }
             let _ = settings_manager.apply_auto_startup_only(&settings);
+            if let Err(e) = settings_manager.apply_launcher_window(&settings.launcher) {
+                tracing::warn!("{}", e);
+            }
+            if let Err(e) = settings_manager.apply_dock_icon(&settings) {
+                tracing::warn!("{}", e);
+            }
+            (shortcut, registered)
         }
         Err(e) => {
-            eprintln!("Failed to load settings: {}. Using defaults.", e);
-            let _ = settings_manager.register_initial_shortcut("Alt+Shift+Space");
+            tracing::error!(error = %e, "Failed to load settings; using defaults");
+            i18n::apply(None);
+            let registered = settings_manager.register_initial_shortcut(DEFAULT_SHORTCUT);
+            let _ = settings_manager.apply_launcher_window(&LauncherSettings::default());
+            let _ = settings_manager.apply_dock_icon(&AppSettings::default());
+            (DEFAULT_SHORTCUT.to_string(), registered)
         }
     }
 }
@@ -0,0 +1,260 @@
+//! Anthropic Messages API wire format.
+//!
+//! The system prompt is sent as a top-level `system` field rather than
+//! as a message.
+
+use serde_json::{json, Value};
+
+use super::error::{error_message, LlmError};
+use super::types::{ChatMessage, Completion, HttpRequest, LlmRequest, ModelInfo, TokenUsage};
+
+/// Base URL for the Anthropic API.
+pub const API_BASE: &str = "https://api.anthropic.com/v1";
+
+/// API version sent in the `anthropic-version` header.
+pub const API_VERSION: &str = "2023-06-01";
+
+/// Build a non-streaming Messages API request.
+pub fn build_request(request: &LlmRequest) -> HttpRequest {
+    let messages: Vec<Value> = request
+        .messages
+        .iter()
+        .map(|message| json!({ "role": message.role, "content": content(message) }))
+        .collect();
+
+    let mut body = json!({
+        "model": request.model,
+        "messages": messages,
+        "max_tokens": request.max_tokens,
+        "temperature": request.temperature,
+    });
+    if !request.system_prompt.is_empty() {
+        body["system"] = json!(request.system_prompt);
+    }
+
+    HttpRequest {
+        url: format!("{}/messages", API_BASE),
+        headers: headers(request),
+        body,
+    }
+}
+
+/// A message's content: its text, or text and image blocks when it has
+/// images.
+fn content(message: &ChatMessage) -> Value {
+    if message.images.is_empty() {
+        return json!(message.content);
+    }
+    let mut blocks = vec![json!({ "type": "text", "text": message.content })];
+    blocks.extend(message.images.iter().map(|image| {
+        json!({
+            "type": "image",
+            "source": { "type": "base64", "media_type": image.mime, "data": image.data },
+        })
+    }));
+    Value::Array(blocks)
+}
+
+/// Build a `GET /models` request.
+pub fn build_models_request(request: &LlmRequest) -> HttpRequest {
+    HttpRequest {
+        url: format!("{}/models", API_BASE),
+        headers: headers(request),
+        body: Value::Null,
+    }
+}
+
+/// Parse a `/models` response.
+pub fn parse_models(body: &Value) -> Result<Vec<ModelInfo>, LlmError> {
+    let data = body["data"]
+        .as_array()
+        .ok_or_else(|| LlmError::Internal("Unexpected models response".to_string()))?;
+
+    Ok(data
+        .iter()
+        .filter_map(|model| {
+            Some(ModelInfo {
+                id: model["id"].as_str()?.to_string(),
+                name: model["display_name"].as_str().map(str::to_string),
+                context_length: None,
+                pricing: None,
+            })
+        })
+        .collect())
+}
+
+fn headers(request: &LlmRequest) -> Vec<(String, String)> {
+    vec![
+        ("x-api-key".to_string(), request.api_key.clone()),
+        ("anthropic-version".to_string(), API_VERSION.to_string()),
+    ]
+}
+
+/// Extract the generated text and token usage from a Messages API response.
+pub fn parse_response(body: &Value) -> Result<Completion, LlmError> {
+    if body["stop_reason"] == "refusal" {
+        return Err(LlmError::ContentBlocked("refusal".to_string()));
+    }
+
+    let content = body["content"][0]["text"].as_str().ok_or_else(|| {
+        LlmError::Internal("Unexpected response format from Anthropic".to_string())
+    })?;
+
+    let usage = &body["usage"];
+    let usage = match (
+        usage["input_tokens"].as_u64(),
+        usage["output_tokens"].as_u64(),
+    ) {
+        (Some(input), Some(output)) => Some(TokenUsage {
+            prompt_tokens: input as u32,
+            completion_tokens: output as u32,
+        }),
+        _ => None,
+    };
+
+    Ok(Completion {
+        content: content.to_string(),
+        usage,
+        cost_usd: None,
+    })
+}
+
+/// Map an Anthropic error response using its `error.type`.
+///
+/// **Quirk:** overload is reported with the non-standard status 529.
+pub fn parse_error(status: u16, body: &Value, retry_after_secs: Option<u64>) -> LlmError {
+    match body["error"]["type"].as_str() {
+        Some("authentication_error" | "permission_error") => LlmError::AuthFailed,
+        Some("rate_limit_error") => LlmError::RateLimited { retry_after_secs },
+        Some("overloaded_error" | "api_error") => LlmError::ProviderUnavailable,
+        _ => LlmError::from_status(status, error_message(status, body), retry_after_secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{ChatMessage, ChatRole, ImagePart};
+    use crate::settings::{LlmProvider, LlmSettings};
+
+    #[test]
+    fn test_build_request_headers_and_system_field() {
+        let settings = LlmSettings {
+            provider: LlmProvider::Anthropic,
+            api_key: "a-key".to_string(),
+            model: "claude-haiku-4-5".to_string(),
+            ..LlmSettings::default()
+        };
+        let request = LlmRequest::from_settings(
+            &settings,
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: "Hi".to_string(),
+                images: Vec::new(),
+            }],
+        );
+
+        let http = build_request(&request);
+
+        assert_eq!(http.url, "https://api.anthropic.com/v1/messages");
+        assert_eq!(http.header("x-api-key"), Some("a-key"));
+        assert_eq!(http.header("anthropic-version"), Some(API_VERSION));
+        assert!(http.body["system"]
+            .as_str()
+            .unwrap()
+            .contains("Quick Assist"));
+        assert_eq!(http.body["messages"][0]["role"], "user");
+    }
+
+    #[test]
+    fn test_build_request_sends_images_as_blocks() {
+        let request = LlmRequest::from_settings(
+            &LlmSettings::default(),
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: "What does this chart mean?".to_string(),
+                images: vec![ImagePart {
+                    mime: "image/png".to_string(),
+                    data: "iVBORw0K".to_string(),
+                }],
+            }],
+        );
+
+        let http = build_request(&request);
+        let content = &http.body["messages"][0]["content"];
+
+        assert_eq!(content[0]["type"], "text");
+        assert_eq!(
+            content[1],
+            json!({
+                "type": "image",
+                "source": { "type": "base64", "media_type": "image/png", "data": "iVBORw0K" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let body = json!({
+            "content": [{ "type": "text", "text": "Hello!" }],
+            "usage": { "input_tokens": 9, "output_tokens": 2 }
+        });
+        let completion = parse_response(&body).unwrap();
+
+        assert_eq!(completion.content, "Hello!");
+        assert_eq!(
+            completion.usage,
+            Some(TokenUsage {
+                prompt_tokens: 9,
+                completion_tokens: 2
+            })
+        );
+        assert!(parse_response(&json!({ "content": [] })).is_err());
+    }
+
+    #[test]
+    fn test_parse_models() {
+        let body = json!({ "data": [
+            { "type": "model", "id": "claude-haiku-4-5-20251001", "display_name": "Claude Haiku 4.5" }
+        ] });
+
+        let models = parse_models(&body).unwrap();
+
+        assert_eq!(models[0].id, "claude-haiku-4-5-20251001");
+        assert_eq!(models[0].name.as_deref(), Some("Claude Haiku 4.5"));
+    }
+
+    // ===== Error Mapping Tests =====
+
+    #[test]
+    fn test_parse_error_401() {
+        let body = json!({ "type": "error", "error": { "type": "authentication_error", "message": "invalid x-api-key" } });
+        assert_eq!(parse_error(401, &body, None), LlmError::AuthFailed);
+    }
+
+    #[test]
+    fn test_parse_error_429() {
+        let body = json!({ "type": "error", "error": { "type": "rate_limit_error", "message": "Slow down" } });
+        assert_eq!(
+            parse_error(429, &body, Some(30)),
+            LlmError::RateLimited {
+                retry_after_secs: Some(30)
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_error_400() {
+        let body = json!({ "type": "error", "error": { "type": "invalid_request_error", "message": "max_tokens too large" } });
+        assert_eq!(
+            parse_error(400, &body, None),
+            LlmError::BadRequest("max_tokens too large".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_error_overloaded() {
+        let body = json!({ "type": "error", "error": { "type": "overloaded_error", "message": "Overloaded" } });
+        assert_eq!(parse_error(529, &body, None), LlmError::ProviderUnavailable);
+    }
+}
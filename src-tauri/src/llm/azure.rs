@@ -0,0 +1,196 @@
+//! Azure OpenAI wire format.
+//!
+//! Request and response bodies are OpenAI's (see [`openai`](super::openai));
+//! only addressing and auth differ:
+//!
+//! - The URL names the deployment instead of the model:
+//!   `{endpoint}/openai/deployments/{deployment}/chat/completions?api-version=…`
+//! - The key is sent in an `api-key` header instead of a bearer token
+//!
+//! Chat streaming happens in the frontend (`services/llm/azure.ts`), which
+//! reads the same SSE chunks as its OpenAI client.
+
+use serde_json::Value;
+
+use super::error::LlmError;
+use super::openai;
+use super::types::{Completion, HttpRequest, LlmRequest, ModelInfo};
+use crate::settings::AzureSettings;
+
+/// API version for the deployments listing, which newer versions dropped.
+pub const DEPLOYMENTS_API_VERSION: &str = "2022-12-01";
+
+/// Build a non-streaming chat completion request for the deployment.
+///
+/// Callers must check [`deployment`] first; without one the URL is
+/// left without a deployment segment.
+pub fn build_request(request: &LlmRequest) -> HttpRequest {
+    let mut http = openai::build_request(request);
+    let (endpoint, deployment, api_version) = match deployment(request) {
+        Some(azure) => (
+            endpoint(request, azure),
+            azure.deployment.as_str(),
+            azure.api_version.as_str(),
+        ),
+        None => (String::new(), "", ""),
+    };
+
+    http.url = format!(
+        "{}/openai/deployments/{}/chat/completions?api-version={}",
+        endpoint, deployment, api_version
+    );
+    http.headers = headers(request);
+    http
+}
+
+/// Build a request listing the resource's deployments.
+pub fn build_models_request(request: &LlmRequest) -> HttpRequest {
+    let endpoint = deployment(request)
+        .map(|azure| endpoint(request, azure))
+        .unwrap_or_default();
+
+    HttpRequest {
+        url: format!(
+            "{}/openai/deployments?api-version={}",
+            endpoint, DEPLOYMENTS_API_VERSION
+        ),
+        headers: headers(request),
+        body: Value::Null,
+    }
+}
+
+/// Parse the deployments list; each deployment becomes a [`ModelInfo`]
+/// whose `id` is the deployment name and `name` the underlying model.
+pub fn parse_models(body: &Value) -> Result<Vec<ModelInfo>, LlmError> {
+    let data = body["data"]
+        .as_array()
+        .ok_or_else(|| LlmError::Internal("Unexpected deployments response".to_string()))?;
+
+    Ok(data
+        .iter()
+        .filter_map(|deployment| {
+            Some(ModelInfo {
+                id: deployment["id"].as_str()?.to_string(),
+                name: deployment["model"].as_str().map(str::to_string),
+                context_length: None,
+                pricing: None,
+            })
+        })
+        .collect())
+}
+
+/// Extract the reply (same shape as OpenAI).
+pub fn parse_response(body: &Value) -> Result<Completion, LlmError> {
+    openai::parse_response(body)
+}
+
+/// Map an error response (same shape as OpenAI, including `content_filter`).
+pub fn parse_error(status: u16, body: &Value, retry_after_secs: Option<u64>) -> LlmError {
+    openai::parse_error(status, body, retry_after_secs)
+}
+
+/// The configured deployment, if resource (or base URL) and deployment are set.
+pub fn deployment(request: &LlmRequest) -> Option<&AzureSettings> {
+    request.azure.as_ref().filter(|azure| {
+        !azure.deployment.is_empty()
+            && (!azure.resource.is_empty()
+                || request
+                    .base_url
+                    .as_deref()
+                    .is_some_and(|url| !url.is_empty()))
+    })
+}
+
+fn endpoint(request: &LlmRequest, azure: &AzureSettings) -> String {
+    match request.base_url.as_deref().filter(|url| !url.is_empty()) {
+        Some(url) => url.trim_end_matches('/').to_string(),
+        None => format!("https://{}.openai.azure.com", azure.resource),
+    }
+}
+
+fn headers(request: &LlmRequest) -> Vec<(String, String)> {
+    vec![("api-key".to_string(), request.api_key.clone())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{ChatMessage, ChatRole};
+    use crate::settings::{LlmProvider, LlmSettings};
+    use serde_json::json;
+
+    fn request(base_url: Option<&str>) -> LlmRequest {
+        let settings = LlmSettings {
+            provider: LlmProvider::AzureOpenAI,
+            api_key: "azure-key".to_string(),
+            model: "gpt-4o".to_string(),
+            base_url: base_url.map(str::to_string),
+            azure: Some(AzureSettings {
+                resource: "contoso".to_string(),
+                deployment: "gpt4o-prod".to_string(),
+                api_version: "2024-10-21".to_string(),
+            }),
+            ..LlmSettings::default()
+        };
+        LlmRequest::from_settings(
+            &settings,
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: "Hi".to_string(),
+                images: Vec::new(),
+            }],
+        )
+    }
+
+    #[test]
+    fn test_build_request_url_and_headers() {
+        let http = build_request(&request(None));
+
+        assert_eq!(
+            http.url,
+            "https://contoso.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(http.header("api-key"), Some("azure-key"));
+        assert!(http.header("authorization").is_none());
+        assert_eq!(http.body["messages"][1]["content"], "Hi");
+    }
+
+    #[test]
+    fn test_base_url_replaces_resource_host() {
+        let http = build_request(&request(Some("https://proxy.contoso.com/")));
+
+        assert!(http
+            .url
+            .starts_with("https://proxy.contoso.com/openai/deployments/gpt4o-prod/"));
+    }
+
+    #[test]
+    fn test_models_request_lists_deployments() {
+        let http = build_models_request(&request(None));
+
+        assert_eq!(
+            http.url,
+            "https://contoso.openai.azure.com/openai/deployments?api-version=2022-12-01"
+        );
+        assert!(http.body.is_null());
+
+        let body =
+            json!({ "data": [{ "id": "gpt4o-prod", "model": "gpt-4o", "status": "succeeded" }] });
+        let models = parse_models(&body).unwrap();
+        assert_eq!(models[0].id, "gpt4o-prod");
+        assert_eq!(models[0].name.as_deref(), Some("gpt-4o"));
+    }
+
+    #[test]
+    fn test_deployment_requires_name_and_endpoint() {
+        let mut missing = request(None);
+        missing.azure.as_mut().unwrap().resource.clear();
+        assert!(deployment(&missing).is_none());
+
+        missing.base_url = Some("https://proxy.contoso.com".to_string());
+        assert!(deployment(&missing).is_some());
+
+        missing.azure = None;
+        assert!(deployment(&missing).is_none());
+    }
+}
@@ -0,0 +1,404 @@
+//! Response cache for repeated identical prompts.
+//!
+//! Entries live in memory for fast lookups and are mirrored into the
+//! `llm_cache` table so they survive restarts. Both keep the newest
+//! [`MAX_ENTRIES`] entries. Keys are a SHA-256 hash of everything that
+//! influences the answer (provider, model, endpoint, Azure deployment,
+//! extra headers, system prompt, messages, temperature, max tokens) —
+//! never the API key.
+//!
+//! Answers are written to `llm_cache` sealed like message content while
+//! `history.encrypt_content` is on (see [`crypto`]); one that can't be
+//! opened any more counts as a miss.
+//!
+//! The cache is opt-in: a TTL of `0` minutes (the default) disables both
+//! lookups and writes. Only non-streaming `ask_llm` requests are cached;
+//! streamed answers always go to the provider.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use super::types::LlmRequest;
+use crate::history::crypto;
+
+/// Most entries kept, in memory and on disk.
+pub const MAX_ENTRIES: usize = 500;
+
+/// A cached reply and when it was stored.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    content: String,
+    /// Unix timestamp (ms)
+    created_at: i64,
+}
+
+/// In-memory + SQLite-backed LLM response cache.
+///
+/// Managed as Tauri state; all methods take `&self`.
+pub struct ResponseCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    pool: SqlitePool,
+}
+
+impl ResponseCache {
+    /// Create a cache persisting to the given history database pool.
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            pool,
+        }
+    }
+
+    /// Compute the cache key for a request.
+    pub fn key(request: &LlmRequest) -> String {
+        // Sorted, so the same headers always hash the same
+        let headers: Option<BTreeMap<&String, &String>> = request
+            .extra_headers
+            .as_ref()
+            .map(|headers| headers.iter().collect());
+        let material = json!({
+            "provider": request.provider,
+            "model": request.model,
+            "base_url": request.base_url,
+            "preset": request.preset,
+            "azure": request.azure,
+            "extra_headers": headers,
+            "auth_header_name": request.auth_header_name,
+            "max_tokens": request.max_tokens,
+            "system_prompt": request.system_prompt,
+            "messages": request.messages,
+            "temperature": request.temperature,
+        });
+
+        let digest = Sha256::digest(material.to_string().as_bytes());
+        format!("{:x}", digest)
+    }
+
+    /// Look up a fresh entry.
+    ///
+    /// Returns `None` when the cache is disabled (`ttl_minutes == 0`), the key
+    /// is unknown, or the entry is older than the TTL. Expired entries are
+    /// evicted as a side effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key from [`ResponseCache::key`]
+    /// * `ttl_minutes` - Maximum entry age
+    /// * `now_ms` - Current Unix timestamp (ms), injected for testability
+    pub async fn get(
+        &self,
+        key: &str,
+        ttl_minutes: u32,
+        now_ms: i64,
+    ) -> Result<Option<String>, String> {
+        if ttl_minutes == 0 {
+            return Ok(None);
+        }
+        let ttl_ms = i64::from(ttl_minutes) * 60_000;
+
+        let memory_hit = self.lock()?.get(key).cloned();
+        let entry = match memory_hit {
+            Some(entry) => Some(entry),
+            None => {
+                let row: Option<(String, i64)> =
+                    sqlx::query_as("SELECT content, created_at FROM llm_cache WHERE key = ?")
+                        .bind(key)
+                        .fetch_optional(&self.pool)
+                        .await
+                        .map_err(|e| format!("Failed to read LLM cache: {}", e))?;
+                row.map(|(content, created_at)| CacheEntry {
+                    content: crypto::open_stored(content),
+                    created_at,
+                })
+            }
+        };
+
+        match entry {
+            Some(entry)
+                if now_ms - entry.created_at < ttl_ms && entry.content != crypto::UNREADABLE =>
+            {
+                let content = entry.content.clone();
+                self.remember(key, entry)?;
+                Ok(Some(content))
+            }
+            Some(_) => {
+                self.remove(key).await?;
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Store a reply under `key`, replacing any previous entry.
+    ///
+    /// Beyond [`MAX_ENTRIES`], the oldest entries are dropped.
+    pub async fn put(&self, key: &str, content: &str, now_ms: i64) -> Result<(), String> {
+        sqlx::query("INSERT OR REPLACE INTO llm_cache (key, content, created_at) VALUES (?, ?, ?)")
+            .bind(key)
+            .bind(crypto::seal_new(content).map_err(|e| e.to_string())?)
+            .bind(now_ms)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to write LLM cache: {}", e))?;
+        sqlx::query(
+            "DELETE FROM llm_cache WHERE key NOT IN
+             (SELECT key FROM llm_cache ORDER BY created_at DESC LIMIT ?)",
+        )
+        .bind(MAX_ENTRIES as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to trim LLM cache: {}", e))?;
+
+        self.remember(
+            key,
+            CacheEntry {
+                content: content.to_string(),
+                created_at: now_ms,
+            },
+        )
+    }
+
+    /// Keep `entry` in memory, dropping the oldest entry when full.
+    fn remember(&self, key: &str, entry: CacheEntry) -> Result<(), String> {
+        let mut entries = self.lock()?;
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.created_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key.to_string(), entry);
+        Ok(())
+    }
+
+    /// Remove every cached entry from memory and disk.
+    pub async fn clear(&self) -> Result<(), String> {
+        sqlx::query("DELETE FROM llm_cache")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to clear LLM cache: {}", e))?;
+
+        self.lock()?.clear();
+        Ok(())
+    }
+
+    /// Forget the entries held in memory, once `llm_cache` was emptied
+    /// some other way.
+    pub fn forget_all(&self) -> Result<(), String> {
+        self.lock()?.clear();
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), String> {
+        self.lock()?.remove(key);
+        sqlx::query("DELETE FROM llm_cache WHERE key = ?")
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to evict LLM cache entry: {}", e))?;
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, CacheEntry>>, String> {
+        self.entries
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::crypto::Cipher;
+    use crate::llm::types::{ChatMessage, ChatRole};
+    use crate::settings::{AzureSettings, LlmSettings};
+
+    const MINUTE: i64 = 60_000;
+
+    fn request(prompt: &str) -> LlmRequest {
+        LlmRequest::from_settings(
+            &LlmSettings::default(),
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: prompt.to_string(),
+                images: Vec::new(),
+            }],
+        )
+    }
+
+    async fn cache() -> (Db, ResponseCache) {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        (db, cache)
+    }
+
+    // ===== Key Tests =====
+
+    #[test]
+    fn test_key_is_stable_and_ignores_api_key() {
+        let mut a = request("tar extract flags");
+        let b = request("tar extract flags");
+        a.api_key = "secret".to_string();
+
+        assert_eq!(ResponseCache::key(&a), ResponseCache::key(&b));
+        assert_eq!(ResponseCache::key(&a).len(), 64);
+    }
+
+    #[test]
+    fn test_key_changes_with_inputs() {
+        let base = request("tar extract flags");
+        let mut other_model = base.clone();
+        other_model.model = "gemini-2.5-pro".to_string();
+        let mut other_temp = base.clone();
+        other_temp.temperature = 0.2;
+
+        let key = ResponseCache::key(&base);
+        assert_ne!(key, ResponseCache::key(&request("tar create flags")));
+        assert_ne!(key, ResponseCache::key(&other_model));
+        assert_ne!(key, ResponseCache::key(&other_temp));
+    }
+
+    #[test]
+    fn test_key_changes_with_endpoint() {
+        let base = request("tar extract flags");
+        let mut other_url = base.clone();
+        other_url.base_url = Some("http://localhost:11434/v1".to_string());
+        let mut other_headers = base.clone();
+        other_headers.extra_headers =
+            Some(HashMap::from([("X-Tenant".to_string(), "b".to_string())]));
+        let mut other_deployment = base.clone();
+        other_deployment.azure = Some(AzureSettings {
+            resource: "contoso".to_string(),
+            deployment: "gpt-4o-eu".to_string(),
+            api_version: "2024-10-21".to_string(),
+        });
+
+        let key = ResponseCache::key(&base);
+        assert_ne!(key, ResponseCache::key(&other_url));
+        assert_ne!(key, ResponseCache::key(&other_headers));
+        assert_ne!(key, ResponseCache::key(&other_deployment));
+    }
+
+    #[test]
+    fn test_key_ignores_header_order() {
+        let mut a = request("tar extract flags");
+        let mut b = a.clone();
+        let headers: Vec<(String, String)> = (0..8)
+            .map(|n| (format!("X-Header-{}", n), n.to_string()))
+            .collect();
+        a.extra_headers = Some(headers.iter().cloned().collect());
+        b.extra_headers = Some(headers.into_iter().rev().collect());
+
+        assert_eq!(ResponseCache::key(&a), ResponseCache::key(&b));
+    }
+
+    // ===== Lookup Tests =====
+
+    #[tokio::test]
+    async fn test_hit_after_put() {
+        let (_db, cache) = cache().await;
+        cache.put("k", "answer", 0).await.unwrap();
+
+        let hit = cache.get("k", 10, 5 * MINUTE).await.unwrap();
+        assert_eq!(hit.as_deref(), Some("answer"));
+    }
+
+    #[tokio::test]
+    async fn test_miss_for_unknown_key() {
+        let (_db, cache) = cache().await;
+        assert!(cache.get("missing", 10, 0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_entry_expires_after_ttl() {
+        let (db, cache) = cache().await;
+        cache.put("k", "answer", 0).await.unwrap();
+
+        assert!(cache.get("k", 10, 10 * MINUTE).await.unwrap().is_none());
+
+        let remaining: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM llm_cache")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(remaining.0, 0, "Expired entry should be evicted from disk");
+    }
+
+    #[tokio::test]
+    async fn test_disabled_cache_never_hits() {
+        let (_db, cache) = cache().await;
+        cache.put("k", "answer", 0).await.unwrap();
+
+        assert!(cache.get("k", 0, 0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_entries_survive_restart() {
+        let (db, cache) = cache().await;
+        cache.put("k", "answer", 0).await.unwrap();
+
+        let reopened = ResponseCache::new(db.pool().clone());
+        let hit = reopened.get("k", 10, MINUTE).await.unwrap();
+        assert_eq!(hit.as_deref(), Some("answer"));
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_sealed_entry_is_a_miss() {
+        let (db, cache) = cache().await;
+        // Sealed with a key that isn't loaded
+        let other_key = Cipher::new(&Cipher::generate_key().unwrap()).unwrap();
+        sqlx::query("INSERT INTO llm_cache (key, content, created_at) VALUES ('k', ?, 0)")
+            .bind(other_key.seal("answer").unwrap())
+            .execute(db.pool())
+            .await
+            .unwrap();
+
+        assert!(cache.get("k", 10, MINUTE).await.unwrap().is_none());
+        let stored: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM llm_cache")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(stored.0, 0);
+    }
+
+    #[tokio::test]
+    async fn test_oldest_entries_are_dropped_when_full() {
+        let (db, cache) = cache().await;
+        for n in 0..=MAX_ENTRIES {
+            cache.put(&n.to_string(), "answer", n as i64).await.unwrap();
+        }
+
+        assert_eq!(cache.lock().unwrap().len(), MAX_ENTRIES);
+        assert!(!cache.lock().unwrap().contains_key("0"));
+        let stored: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM llm_cache")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(stored.0, MAX_ENTRIES as i64);
+        assert!(cache.get("0", 10, MINUTE).await.unwrap().is_none());
+        assert!(cache
+            .get(&MAX_ENTRIES.to_string(), 10, MINUTE)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_clear_removes_everything() {
+        let (_db, cache) = cache().await;
+        cache.put("a", "1", 0).await.unwrap();
+        cache.put("b", "2", 0).await.unwrap();
+
+        cache.clear().await.unwrap();
+
+        assert!(cache.get("a", 10, 0).await.unwrap().is_none());
+        assert!(cache.get("b", 10, 0).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,280 @@
+//! LLM client abstraction and the HTTP implementation.
+//!
+//! [`LlmClient`] is the seam between request orchestration (caching, etc.)
+//! and the network, so orchestration can be tested with a mock client.
+
+use serde_json::Value;
+
+use super::error::LlmError;
+use super::types::{Completion, HttpRequest, LlmRequest, ModelInfo};
+use super::{anthropic, azure, gemini, openai, openrouter, presets};
+use crate::network;
+use crate::settings::{LlmProvider, NetworkSettings};
+
+/// Something that can turn an [`LlmRequest`] into generated text.
+pub trait LlmClient {
+    /// Send the request and return the model's reply.
+    async fn complete(&self, request: &LlmRequest) -> Result<Completion, LlmError>;
+}
+
+/// Translate a request into the selected provider's wire format.
+pub fn build_http_request(request: &LlmRequest) -> HttpRequest {
+    match request.provider {
+        LlmProvider::Gemini => gemini::build_request(request),
+        LlmProvider::OpenAI => openai::build_request(request),
+        LlmProvider::Custom => presets::build_request(request),
+        LlmProvider::Anthropic => anthropic::build_request(request),
+        LlmProvider::OpenRouter => openrouter::build_request(request),
+        LlmProvider::AzureOpenAI => azure::build_request(request),
+    }
+}
+
+/// Build the provider's model listing request.
+pub fn build_models_request(request: &LlmRequest) -> HttpRequest {
+    match request.provider {
+        LlmProvider::Gemini => gemini::build_models_request(request),
+        LlmProvider::OpenAI => openai::build_models_request(request, openai::DEFAULT_BASE_URL),
+        LlmProvider::Custom => presets::build_models_request(request),
+        LlmProvider::Anthropic => anthropic::build_models_request(request),
+        LlmProvider::OpenRouter => openrouter::build_models_request(request),
+        LlmProvider::AzureOpenAI => azure::build_models_request(request),
+    }
+}
+
+/// Parse the provider's model listing.
+pub fn parse_models(provider: &LlmProvider, body: &Value) -> Result<Vec<ModelInfo>, LlmError> {
+    match provider {
+        LlmProvider::Gemini => gemini::parse_models(body),
+        LlmProvider::OpenAI | LlmProvider::Custom => openai::parse_models(body),
+        LlmProvider::Anthropic => anthropic::parse_models(body),
+        LlmProvider::OpenRouter => openrouter::parse_models(body),
+        LlmProvider::AzureOpenAI => azure::parse_models(body),
+    }
+}
+
+/// Parse a successful response body for the selected provider.
+pub fn parse_http_response(provider: &LlmProvider, body: &Value) -> Result<Completion, LlmError> {
+    match provider {
+        LlmProvider::Gemini => gemini::parse_response(body),
+        LlmProvider::OpenAI | LlmProvider::Custom => openai::parse_response(body),
+        LlmProvider::Anthropic => anthropic::parse_response(body),
+        LlmProvider::OpenRouter => openrouter::parse_response(body),
+        LlmProvider::AzureOpenAI => azure::parse_response(body),
+    }
+}
+
+/// Map a non-success response for the selected provider.
+pub fn parse_http_error(
+    provider: &LlmProvider,
+    status: u16,
+    body: &Value,
+    retry_after_secs: Option<u64>,
+) -> LlmError {
+    match provider {
+        LlmProvider::Gemini => gemini::parse_error(status, body, retry_after_secs),
+        LlmProvider::OpenAI | LlmProvider::Custom => {
+            openai::parse_error(status, body, retry_after_secs)
+        }
+        LlmProvider::Anthropic => anthropic::parse_error(status, body, retry_after_secs),
+        LlmProvider::OpenRouter => openrouter::parse_error(status, body, retry_after_secs),
+        LlmProvider::AzureOpenAI => azure::parse_error(status, body, retry_after_secs),
+    }
+}
+
+/// Check that the request has everything its provider needs.
+///
+/// A missing API key is an auth failure unless the endpoint is local;
+/// Azure additionally needs a resource (or base URL) and a deployment, and
+/// a `Custom` preset has to exist.
+pub fn check_configured(request: &LlmRequest) -> Result<(), LlmError> {
+    presets::for_request(request)?;
+    let is_local = request
+        .base_url
+        .as_deref()
+        .is_some_and(|url| url.contains("localhost") || url.contains("127.0.0.1"));
+    if request.api_key.is_empty() && !is_local {
+        return Err(LlmError::AuthFailed);
+    }
+
+    if request.provider == LlmProvider::AzureOpenAI && azure::deployment(request).is_none() {
+        return Err(LlmError::BadRequest(
+            "Azure resource and deployment are not configured. Please set them in Settings."
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Real client that talks to provider APIs over HTTPS.
+#[derive(Default)]
+pub struct HttpClient {
+    http: reqwest::Client,
+}
+
+impl HttpClient {
+    /// Create a client that uses the configured proxy and timeout.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HttpClient)` - Client for provider requests
+    /// * `Err(String)` - The proxy URL is invalid
+    pub fn new(settings: &NetworkSettings) -> Result<Self, String> {
+        Ok(Self {
+            http: network::http_client(settings)?,
+        })
+    }
+
+    /// List the models available to the configured API key.
+    pub async fn list_models(&self, request: &LlmRequest) -> Result<Vec<ModelInfo>, LlmError> {
+        check_configured(request)?;
+        let body = self
+            .send(&request.provider, &build_models_request(request))
+            .await?;
+        parse_models(&request.provider, &body)
+    }
+
+    /// Send a provider request and return the JSON body of a success response.
+    ///
+    /// Requests with a `Null` body are sent as `GET`, everything else as a
+    /// JSON `POST`.
+    async fn send(&self, provider: &LlmProvider, request: &HttpRequest) -> Result<Value, LlmError> {
+        let mut builder = if request.body.is_null() {
+            self.http.get(&request.url)
+        } else {
+            self.http.post(&request.url).json(&request.body)
+        };
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| LlmError::from_reqwest(&e))?;
+        let status = response.status();
+        let retry_after_secs = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok());
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+
+        if !status.is_success() {
+            return Err(parse_http_error(
+                provider,
+                status.as_u16(),
+                &body,
+                retry_after_secs,
+            ));
+        }
+
+        Ok(body)
+    }
+}
+
+impl LlmClient for HttpClient {
+    async fn complete(&self, request: &LlmRequest) -> Result<Completion, LlmError> {
+        check_configured(request)?;
+
+        let body = self
+            .send(&request.provider, &build_http_request(request))
+            .await?;
+        parse_http_response(&request.provider, &body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{ChatMessage, ChatRole};
+    use crate::settings::LlmSettings;
+
+    fn request(provider: LlmProvider, api_key: &str, base_url: Option<&str>) -> LlmRequest {
+        let settings = LlmSettings {
+            provider,
+            api_key: api_key.to_string(),
+            base_url: base_url.map(str::to_string),
+            ..LlmSettings::default()
+        };
+        LlmRequest::from_settings(
+            &settings,
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: "Hi".to_string(),
+                images: Vec::new(),
+            }],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_missing_api_key_is_auth_failure() {
+        let result = HttpClient::default()
+            .complete(&request(LlmProvider::OpenAI, "", None))
+            .await;
+
+        assert_eq!(result, Err(LlmError::AuthFailed));
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused_is_network_error() {
+        // Nothing listens on port 1, so the connection is refused immediately
+        let result = HttpClient::default()
+            .complete(&request(
+                LlmProvider::Custom,
+                "",
+                Some("http://127.0.0.1:1/v1"),
+            ))
+            .await;
+
+        assert!(
+            matches!(result, Err(LlmError::Network(_))),
+            "got {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_openrouter_dispatch() {
+        let http = build_http_request(&request(LlmProvider::OpenRouter, "or-key", None));
+        assert!(http
+            .url
+            .starts_with(crate::llm::openrouter::DEFAULT_BASE_URL));
+
+        let models = build_models_request(&request(LlmProvider::OpenRouter, "or-key", None));
+        assert_eq!(models.url, "https://openrouter.ai/api/v1/models");
+        assert!(models.body.is_null());
+    }
+
+    #[test]
+    fn test_azure_requires_deployment() {
+        let result = check_configured(&request(LlmProvider::AzureOpenAI, "azure-key", None));
+        assert!(matches!(result, Err(LlmError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_custom_preset_dispatch() {
+        let mut request = request(LlmProvider::Custom, "gsk-key", None);
+        request.preset = Some("groq".to_string());
+        assert_eq!(
+            build_http_request(&request).url,
+            "https://api.groq.com/openai/v1/chat/completions"
+        );
+
+        request.preset = Some("unknown".to_string());
+        assert!(matches!(
+            check_configured(&request),
+            Err(LlmError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_custom_provider_uses_openai_error_mapping() {
+        let body = serde_json::json!({ "error": { "message": "Unknown model" } });
+
+        assert_eq!(
+            parse_http_error(&LlmProvider::Custom, 404, &body, None),
+            LlmError::BadRequest("Unknown model".to_string())
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! Connectivity check run before LLM requests.
+//!
+//! A cheap `HEAD` to the provider's host tells us whether the network is
+//! reachable, so an offline ask fails immediately instead of waiting for
+//! the full request timeout. Results are cached per host for
+//! [`CACHE_TTL_MS`] to keep the check off the hot path.
+//!
+//! An ask probes each profile of its fallback chain before trying it
+//! through a [`ChainProbe`], so an unreachable primary host moves on to the
+//! fallbacks. The frontend is only told it's offline when none of the hosts
+//! probed answered.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// How long a probe result is reused.
+pub const CACHE_TTL_MS: i64 = 30_000;
+
+/// Timeout for a single probe.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Payload of the `connectivity-changed` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ConnectivityChanged {
+    /// Whether the last probe reached the host
+    pub online: bool,
+}
+
+/// A cached probe result, for the diagnostics report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProbeResult {
+    /// Origin probed, e.g. `https://api.openai.com/`
+    pub origin: String,
+    pub online: bool,
+    /// When the probe ran (Unix ms)
+    pub checked_at: i64,
+}
+
+/// Something that can tell whether a URL is reachable.
+pub trait Prober {
+    /// Return `true` if the server answered at all (any HTTP status).
+    async fn probe(&self, url: &str) -> bool;
+}
+
+/// Prober that sends a `HEAD` request with [`PROBE_TIMEOUT`].
+pub struct HttpProber {
+    http: reqwest::Client,
+}
+
+impl Default for HttpProber {
+    fn default() -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(PROBE_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Prober for HttpProber {
+    async fn probe(&self, url: &str) -> bool {
+        self.http.head(url).send().await.is_ok()
+    }
+}
+
+/// Prober that reports every host reachable, e.g. behind a proxy, where a
+/// direct probe would fail.
+pub struct AssumeOnline;
+
+impl Prober for AssumeOnline {
+    async fn probe(&self, _url: &str) -> bool {
+        true
+    }
+}
+
+/// Cached connectivity state, managed as Tauri state.
+pub struct Connectivity<P = HttpProber> {
+    prober: P,
+    /// Last result per origin as `(online, checked_at_ms)`
+    results: Mutex<HashMap<String, (bool, i64)>>,
+    /// Last result reported to the frontend
+    last_online: Mutex<Option<bool>>,
+}
+
+impl Default for Connectivity {
+    fn default() -> Self {
+        Self::new(HttpProber::default())
+    }
+}
+
+impl<P: Prober> Connectivity<P> {
+    /// Create a connectivity cache using the given prober.
+    pub fn new(prober: P) -> Self {
+        Self {
+            prober,
+            results: Mutex::new(HashMap::new()),
+            last_online: Mutex::new(None),
+        }
+    }
+
+    /// Check whether the host of `url` is reachable and report the result.
+    ///
+    /// # Returns
+    ///
+    /// `(online, changed)` where `changed` is `Some` when the result differs
+    /// from the last one reported and should be emitted.
+    pub async fn check(
+        &self,
+        url: &str,
+        max_age_ms: i64,
+        now_ms: i64,
+    ) -> (bool, Option<ConnectivityChanged>) {
+        let online = self.probe(url, max_age_ms, now_ms).await;
+        (online, self.report(online))
+    }
+
+    /// Check whether the host of `url` is reachable, without reporting it.
+    ///
+    /// Loopback URLs are always considered reachable.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Any URL on the host to check (only the origin is probed)
+    /// * `max_age_ms` - Reuse a cached result younger than this (`0` forces a probe)
+    /// * `now_ms` - Current Unix timestamp (ms)
+    pub async fn probe(&self, url: &str, max_age_ms: i64, now_ms: i64) -> bool {
+        let Some(origin) = origin(url) else {
+            return true;
+        };
+        if is_loopback(&origin) {
+            return true;
+        }
+
+        let cached = self
+            .results
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&origin)
+            .copied();
+        match cached {
+            Some((online, checked_at)) if now_ms - checked_at < max_age_ms => online,
+            _ => {
+                let online = self.prober.probe(&origin).await;
+                self.results
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(origin, (online, now_ms));
+                online
+            }
+        }
+    }
+
+    /// Record `online` as the state the frontend knows about.
+    ///
+    /// Returns `Some` when it differs from the last one reported and should
+    /// be emitted.
+    pub fn report(&self, online: bool) -> Option<ConnectivityChanged> {
+        let mut last_online = self.last_online.lock().unwrap_or_else(|e| e.into_inner());
+        let changed = (*last_online != Some(online)).then_some(ConnectivityChanged { online });
+        *last_online = Some(online);
+        changed
+    }
+
+    /// The cached result of every origin probed, most recent first.
+    pub fn results(&self) -> Vec<ProbeResult> {
+        let mut results: Vec<ProbeResult> = self
+            .results
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(origin, &(online, checked_at))| ProbeResult {
+                origin: origin.clone(),
+                online,
+                checked_at,
+            })
+            .collect();
+        results.sort_by_key(|result| std::cmp::Reverse(result.checked_at));
+        results
+    }
+}
+
+/// [`Prober`] for the profiles of one fallback chain.
+///
+/// Probes go through the [`Connectivity`] cache without being reported;
+/// [`finish`](Self::finish) then reports offline only if no host answered.
+pub struct ChainProbe<'a, P = HttpProber> {
+    connectivity: &'a Connectivity<P>,
+    now_ms: i64,
+    /// Whether any probed host answered; `None` before the first probe
+    online: Mutex<Option<bool>>,
+}
+
+impl<'a, P: Prober> ChainProbe<'a, P> {
+    /// Probe with results up to [`CACHE_TTL_MS`] old.
+    pub fn new(connectivity: &'a Connectivity<P>, now_ms: i64) -> Self {
+        Self {
+            connectivity,
+            now_ms,
+            online: Mutex::new(None),
+        }
+    }
+
+    /// Report the outcome of the chain's probes.
+    ///
+    /// # Returns
+    ///
+    /// `Some` when the state differs from the last one reported and should
+    /// be emitted; `None` as well when nothing was probed.
+    pub fn finish(self) -> Option<ConnectivityChanged> {
+        let online = self
+            .online
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())?;
+        self.connectivity.report(online)
+    }
+}
+
+impl<P: Prober> Prober for ChainProbe<'_, P> {
+    async fn probe(&self, url: &str) -> bool {
+        let online = self
+            .connectivity
+            .probe(url, CACHE_TTL_MS, self.now_ms)
+            .await;
+        let mut any = self.online.lock().unwrap_or_else(|e| e.into_inner());
+        *any = Some(any.unwrap_or(false) || online);
+        online
+    }
+}
+
+fn origin(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    Some(match parsed.port() {
+        Some(port) => format!("{}://{}:{}/", parsed.scheme(), host, port),
+        None => format!("{}://{}/", parsed.scheme(), host),
+    })
+}
+
+fn is_loopback(origin: &str) -> bool {
+    origin.contains("://localhost") || origin.contains("://127.0.0.1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    struct FakeProber {
+        online: AtomicBool,
+        probes: AtomicUsize,
+    }
+
+    impl Prober for &FakeProber {
+        async fn probe(&self, _url: &str) -> bool {
+            self.probes.fetch_add(1, Ordering::SeqCst);
+            self.online.load(Ordering::SeqCst)
+        }
+    }
+
+    fn fake(online: bool) -> FakeProber {
+        FakeProber {
+            online: AtomicBool::new(online),
+            probes: AtomicUsize::new(0),
+        }
+    }
+
+    const URL: &str = "https://api.openai.com/v1/chat/completions";
+
+    #[test]
+    fn test_origin_strips_path_and_query() {
+        assert_eq!(
+            origin("https://generativelanguage.googleapis.com/v1beta/models/x?key=k").as_deref(),
+            Some("https://generativelanguage.googleapis.com/")
+        );
+        assert_eq!(
+            origin("http://example.com:8080/v1").as_deref(),
+            Some("http://example.com:8080/")
+        );
+        assert!(origin("not a url").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_result_is_cached_within_ttl() {
+        let prober = fake(true);
+        let connectivity = Connectivity::new(&prober);
+
+        connectivity.check(URL, CACHE_TTL_MS, 0).await;
+        connectivity.check(URL, CACHE_TTL_MS, 10_000).await;
+        assert_eq!(prober.probes.load(Ordering::SeqCst), 1);
+
+        connectivity.check(URL, CACHE_TTL_MS, 40_000).await;
+        assert_eq!(prober.probes.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_zero_max_age_forces_probe() {
+        let prober = fake(true);
+        let connectivity = Connectivity::new(&prober);
+
+        connectivity.check(URL, 0, 0).await;
+        connectivity.check(URL, 0, 0).await;
+        assert_eq!(prober.probes.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_results_newest_first() {
+        let prober = fake(false);
+        let connectivity = Connectivity::new(&prober);
+
+        connectivity.check(URL, 0, 10).await;
+        connectivity
+            .check("http://localhost:11434/api/chat", 0, 15)
+            .await;
+        connectivity.check("https://api.x.ai/v1", 0, 20).await;
+
+        assert_eq!(
+            connectivity.results(),
+            vec![
+                ProbeResult {
+                    origin: "https://api.x.ai/".to_string(),
+                    online: false,
+                    checked_at: 20,
+                },
+                ProbeResult {
+                    origin: "https://api.openai.com/".to_string(),
+                    online: false,
+                    checked_at: 10,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_change_reported_only_on_transition() {
+        let prober = fake(true);
+        let connectivity = Connectivity::new(&prober);
+
+        let (online, changed) = connectivity.check(URL, 0, 0).await;
+        assert!(online);
+        assert_eq!(changed, Some(ConnectivityChanged { online: true }));
+
+        let (_, changed) = connectivity.check(URL, 0, 1).await;
+        assert_eq!(changed, None);
+
+        prober.online.store(false, Ordering::SeqCst);
+        let (online, changed) = connectivity.check(URL, 0, 2).await;
+        assert!(!online);
+        assert_eq!(changed, Some(ConnectivityChanged { online: false }));
+    }
+
+    #[tokio::test]
+    async fn test_probe_does_not_report() {
+        let prober = fake(false);
+        let connectivity = Connectivity::new(&prober);
+
+        assert!(!connectivity.probe(URL, 0, 0).await);
+
+        assert_eq!(
+            connectivity.report(false),
+            Some(ConnectivityChanged { online: false })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chain_offline_only_when_every_host_is() {
+        struct ByHost;
+        impl Prober for ByHost {
+            async fn probe(&self, url: &str) -> bool {
+                url.contains("googleapis")
+            }
+        }
+        let connectivity = Connectivity::new(ByHost);
+
+        let chain = ChainProbe::new(&connectivity, 0);
+        assert!(!chain.probe(URL).await);
+        assert!(
+            chain
+                .probe("https://generativelanguage.googleapis.com/v1beta")
+                .await
+        );
+        assert_eq!(chain.finish(), Some(ConnectivityChanged { online: true }));
+
+        let chain = ChainProbe::new(&connectivity, 0);
+        assert!(!chain.probe(URL).await);
+        assert!(!chain.probe("https://api.x.ai/v1").await);
+        assert_eq!(chain.finish(), Some(ConnectivityChanged { online: false }));
+
+        assert_eq!(ChainProbe::new(&connectivity, 0).finish(), None);
+    }
+
+    #[tokio::test]
+    async fn test_loopback_is_never_probed() {
+        let prober = fake(false);
+        let connectivity = Connectivity::new(&prober);
+
+        let (online, _) = connectivity
+            .check("http://localhost:11434/v1/chat/completions", 0, 0)
+            .await;
+
+        assert!(online);
+        assert_eq!(prober.probes.load(Ordering::SeqCst), 0);
+    }
+}
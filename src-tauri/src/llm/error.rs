@@ -0,0 +1,167 @@
+//! Structured LLM errors surfaced to the frontend.
+//!
+//! Every provider maps its HTTP status codes and error bodies into
+//! [`LlmError`], so the UI can tell "your API key is wrong" apart from
+//! "you're offline" instead of showing a raw message.
+//!
+//! # Serialization
+//!
+//! ```json
+//! { "kind": "auth_failed" }
+//! { "kind": "rate_limited", "detail": { "retry_after_secs": 20 } }
+//! { "kind": "network", "detail": "Connection refused" }
+//! ```
+
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+/// Why an LLM request failed.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum LlmError {
+    /// API key missing, invalid, or lacking permission
+    #[error("Authentication failed. Please check your API key in Settings.")]
+    AuthFailed,
+    /// Too many requests, from the provider or the client-side limits (see
+    /// [`super::limiter`]); `retry_after_secs` comes from the `Retry-After`
+    /// header or the limiter
+    #[error("Too many requests, please try again shortly")]
+    RateLimited { retry_after_secs: Option<u64> },
+    /// The provider could not be reached
+    #[error("Network error: {0}")]
+    Network(String),
+    /// The provider rejected the request itself
+    #[error("{0}")]
+    BadRequest(String),
+    /// The prompt or reply was blocked by the provider's safety filters
+    #[error("Blocked by content filter: {0}")]
+    ContentBlocked(String),
+    /// The request took too long
+    #[error("The request timed out")]
+    Timeout,
+    /// The provider reported an outage or overload
+    #[error("The provider is temporarily unavailable")]
+    ProviderUnavailable,
+    /// Local failure unrelated to the provider (settings, database, parsing)
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl LlmError {
+    /// Map an HTTP status to an error using the provider's error message.
+    ///
+    /// Provider modules call this after handling their own special cases.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - HTTP status code (non-success)
+    /// * `message` - Human-readable message from the error body
+    /// * `retry_after_secs` - Parsed `Retry-After` header, if any
+    pub fn from_status(status: u16, message: String, retry_after_secs: Option<u64>) -> Self {
+        match status {
+            401 | 403 => Self::AuthFailed,
+            408 | 504 => Self::Timeout,
+            429 => Self::RateLimited { retry_after_secs },
+            500..=599 => Self::ProviderUnavailable,
+            _ => Self::BadRequest(message),
+        }
+    }
+
+    /// Map a transport-level failure.
+    pub fn from_reqwest(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::Network(error.to_string())
+        }
+    }
+
+    /// Whether the next profile in the fallback chain should be tried.
+    ///
+    /// Auth and availability problems may not affect another provider;
+    /// a rejected or blocked request would fail the same way elsewhere.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::AuthFailed
+                | Self::RateLimited { .. }
+                | Self::Network(_)
+                | Self::Timeout
+                | Self::ProviderUnavailable
+        )
+    }
+}
+
+impl From<String> for LlmError {
+    fn from(message: String) -> Self {
+        Self::Internal(message)
+    }
+}
+
+/// Read `error.message` from an error body, falling back to the status code.
+pub fn error_message(status: u16, body: &Value) -> String {
+    body["error"]["message"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("HTTP error {}", status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_mapping() {
+        assert_eq!(
+            LlmError::from_status(401, String::new(), None),
+            LlmError::AuthFailed
+        );
+        assert_eq!(
+            LlmError::from_status(429, String::new(), Some(7)),
+            LlmError::RateLimited {
+                retry_after_secs: Some(7)
+            }
+        );
+        assert_eq!(
+            LlmError::from_status(503, String::new(), None),
+            LlmError::ProviderUnavailable
+        );
+        assert_eq!(
+            LlmError::from_status(400, "bad".to_string(), None),
+            LlmError::BadRequest("bad".to_string())
+        );
+    }
+
+    #[test]
+    fn test_retryable_variants() {
+        assert!(LlmError::AuthFailed.is_retryable());
+        assert!(LlmError::Timeout.is_retryable());
+        assert!(!LlmError::BadRequest(String::new()).is_retryable());
+        assert!(!LlmError::ContentBlocked(String::new()).is_retryable());
+        assert!(!LlmError::Internal(String::new()).is_retryable());
+    }
+
+    #[test]
+    fn test_serializes_with_kind_tag() {
+        let auth = serde_json::to_value(LlmError::AuthFailed).unwrap();
+        assert_eq!(auth, serde_json::json!({ "kind": "auth_failed" }));
+
+        let limited = serde_json::to_value(LlmError::RateLimited {
+            retry_after_secs: Some(20),
+        })
+        .unwrap();
+        assert_eq!(limited["kind"], "rate_limited");
+        assert_eq!(limited["detail"]["retry_after_secs"], 20);
+
+        let network = serde_json::to_value(LlmError::Network("offline".to_string())).unwrap();
+        assert_eq!(network["detail"], "offline");
+    }
+
+    #[test]
+    fn test_error_message_falls_back_to_status() {
+        let body = serde_json::json!({ "error": { "message": "Invalid model" } });
+        assert_eq!(error_message(400, &body), "Invalid model");
+        assert_eq!(error_message(502, &Value::Null), "HTTP error 502");
+    }
+}
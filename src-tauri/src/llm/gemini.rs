@@ -0,0 +1,300 @@
+//! Google Gemini `generateContent` wire format.
+//!
+//! **Quirk:** Gemini uses `model` instead of `assistant` for AI turns and
+//! takes the system prompt as a separate `systemInstruction` field.
+
+use serde_json::{json, Value};
+
+use super::error::{error_message, LlmError};
+use super::types::{ChatRole, Completion, HttpRequest, LlmRequest, ModelInfo, TokenUsage};
+
+/// Base URL for the Gemini models API.
+pub const API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+
+/// Build a non-streaming `generateContent` request.
+pub fn build_request(request: &LlmRequest) -> HttpRequest {
+    let contents: Vec<Value> = request
+        .messages
+        .iter()
+        .map(|message| {
+            let role = match message.role {
+                ChatRole::User => "user",
+                ChatRole::Assistant => "model",
+            };
+            let mut parts = vec![json!({ "text": message.content })];
+            parts.extend(message.images.iter().map(
+                |image| json!({ "inlineData": { "mimeType": image.mime, "data": image.data } }),
+            ));
+            json!({ "role": role, "parts": parts })
+        })
+        .collect();
+
+    let mut body = json!({
+        "contents": contents,
+        "generationConfig": {
+            "temperature": request.temperature,
+            "topP": 0.95,
+            "topK": 40,
+            "maxOutputTokens": request.max_tokens,
+        },
+    });
+    if !request.system_prompt.is_empty() {
+        body["systemInstruction"] = json!({ "parts": [{ "text": request.system_prompt }] });
+    }
+
+    HttpRequest {
+        url: format!(
+            "{}/{}:generateContent?key={}",
+            API_BASE, request.model, request.api_key
+        ),
+        headers: Vec::new(),
+        body,
+    }
+}
+
+/// Build a request listing the models available to the API key.
+pub fn build_models_request(request: &LlmRequest) -> HttpRequest {
+    HttpRequest {
+        url: format!("{}?key={}", API_BASE, request.api_key),
+        headers: Vec::new(),
+        body: Value::Null,
+    }
+}
+
+/// Parse the models list, keeping only models that support `generateContent`.
+///
+/// **Quirk:** names come back as `models/gemini-2.0-flash`; the prefix is stripped.
+pub fn parse_models(body: &Value) -> Result<Vec<ModelInfo>, LlmError> {
+    let models = body["models"]
+        .as_array()
+        .ok_or_else(|| LlmError::Internal("Unexpected models response".to_string()))?;
+
+    Ok(models
+        .iter()
+        .filter(|model| {
+            model["supportedGenerationMethods"]
+                .as_array()
+                .is_some_and(|methods| methods.iter().any(|m| m == "generateContent"))
+        })
+        .filter_map(|model| {
+            let name = model["name"].as_str()?;
+            Some(ModelInfo {
+                id: name.trim_start_matches("models/").to_string(),
+                name: model["displayName"].as_str().map(str::to_string),
+                context_length: model["inputTokenLimit"].as_u64().map(|n| n as u32),
+                pricing: None,
+            })
+        })
+        .collect())
+}
+
+/// Extract the generated text and token usage from a `generateContent` response.
+///
+/// Safety blocks come back as successful responses without text, either
+/// with `promptFeedback.blockReason` or a `SAFETY` finish reason.
+pub fn parse_response(body: &Value) -> Result<Completion, LlmError> {
+    if let Some(reason) = body["promptFeedback"]["blockReason"].as_str() {
+        return Err(LlmError::ContentBlocked(reason.to_string()));
+    }
+
+    let candidate = &body["candidates"][0];
+    let content = match candidate["content"]["parts"][0]["text"].as_str() {
+        Some(content) => content,
+        None if candidate["finishReason"] == "SAFETY" => {
+            return Err(LlmError::ContentBlocked("SAFETY".to_string()))
+        }
+        None => {
+            return Err(LlmError::Internal(
+                "Unexpected response format from Gemini".to_string(),
+            ))
+        }
+    };
+
+    let usage = &body["usageMetadata"];
+    let usage = usage["promptTokenCount"].as_u64().map(|prompt| TokenUsage {
+        prompt_tokens: prompt as u32,
+        completion_tokens: usage["candidatesTokenCount"].as_u64().unwrap_or(0) as u32,
+    });
+
+    Ok(Completion {
+        content: content.to_string(),
+        usage,
+        cost_usd: None,
+    })
+}
+
+/// Map a Gemini error response.
+///
+/// **Quirk:** an invalid API key is reported as `400 INVALID_ARGUMENT`
+/// with reason `API_KEY_INVALID`, not as a 401.
+pub fn parse_error(status: u16, body: &Value, retry_after_secs: Option<u64>) -> LlmError {
+    let error = &body["error"];
+    let key_invalid = error["details"]
+        .as_array()
+        .is_some_and(|details| details.iter().any(|d| d["reason"] == "API_KEY_INVALID"));
+    if key_invalid {
+        return LlmError::AuthFailed;
+    }
+
+    match error["status"].as_str() {
+        Some("UNAUTHENTICATED" | "PERMISSION_DENIED") => LlmError::AuthFailed,
+        Some("RESOURCE_EXHAUSTED") => LlmError::RateLimited { retry_after_secs },
+        Some("UNAVAILABLE") => LlmError::ProviderUnavailable,
+        Some("DEADLINE_EXCEEDED") => LlmError::Timeout,
+        _ => LlmError::from_status(status, error_message(status, body), retry_after_secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{ChatMessage, ImagePart};
+    use crate::settings::LlmSettings;
+
+    #[test]
+    fn test_build_request_maps_assistant_to_model() {
+        let settings = LlmSettings {
+            api_key: "g-key".to_string(),
+            model: "gemini-2.0-flash".to_string(),
+            ..LlmSettings::default()
+        };
+        let request = LlmRequest::from_settings(
+            &settings,
+            vec![
+                ChatMessage {
+                    role: ChatRole::User,
+                    content: "Hi".to_string(),
+                    images: Vec::new(),
+                },
+                ChatMessage {
+                    role: ChatRole::Assistant,
+                    content: "Hello".to_string(),
+                    images: Vec::new(),
+                },
+            ],
+        );
+
+        let http = build_request(&request);
+
+        assert!(http
+            .url
+            .ends_with("/gemini-2.0-flash:generateContent?key=g-key"));
+        assert_eq!(http.body["contents"][0]["role"], "user");
+        assert_eq!(http.body["contents"][1]["role"], "model");
+        assert!(http.body["systemInstruction"]["parts"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Quick Assist"));
+    }
+
+    #[test]
+    fn test_build_request_sends_images_inline() {
+        let request = LlmRequest::from_settings(
+            &LlmSettings::default(),
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: "What does this chart mean?".to_string(),
+                images: vec![ImagePart {
+                    mime: "image/png".to_string(),
+                    data: "iVBORw0K".to_string(),
+                }],
+            }],
+        );
+
+        let http = build_request(&request);
+        let parts = &http.body["contents"][0]["parts"];
+
+        assert_eq!(parts[0]["text"], "What does this chart mean?");
+        assert_eq!(
+            parts[1],
+            json!({ "inlineData": { "mimeType": "image/png", "data": "iVBORw0K" } })
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let body = json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Hello!" }] } }],
+            "usageMetadata": { "promptTokenCount": 20, "candidatesTokenCount": 4 }
+        });
+        let completion = parse_response(&body).unwrap();
+
+        assert_eq!(completion.content, "Hello!");
+        assert_eq!(
+            completion.usage,
+            Some(TokenUsage {
+                prompt_tokens: 20,
+                completion_tokens: 4
+            })
+        );
+        assert!(parse_response(&json!({ "candidates": [] })).is_err());
+    }
+
+    #[test]
+    fn test_parse_response_blocked_prompt() {
+        let body = json!({ "promptFeedback": { "blockReason": "SAFETY" } });
+        assert_eq!(
+            parse_response(&body),
+            Err(LlmError::ContentBlocked("SAFETY".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_models_filters_and_strips_prefix() {
+        let body = json!({ "models": [
+            {
+                "name": "models/gemini-2.0-flash",
+                "displayName": "Gemini 2.0 Flash",
+                "inputTokenLimit": 1048576,
+                "supportedGenerationMethods": ["generateContent", "countTokens"]
+            },
+            { "name": "models/text-embedding-004", "supportedGenerationMethods": ["embedContent"] }
+        ] });
+
+        let models = parse_models(&body).unwrap();
+
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].id, "gemini-2.0-flash");
+        assert_eq!(models[0].name.as_deref(), Some("Gemini 2.0 Flash"));
+        assert_eq!(models[0].context_length, Some(1_048_576));
+    }
+
+    // ===== Error Mapping Tests =====
+
+    #[test]
+    fn test_parse_error_401() {
+        let body = json!({ "error": { "code": 401, "message": "Request had invalid authentication credentials.", "status": "UNAUTHENTICATED" } });
+        assert_eq!(parse_error(401, &body, None), LlmError::AuthFailed);
+    }
+
+    #[test]
+    fn test_parse_error_invalid_key_is_auth_failure() {
+        let body = json!({ "error": {
+            "code": 400,
+            "message": "API key not valid. Please pass a valid API key.",
+            "status": "INVALID_ARGUMENT",
+            "details": [{ "reason": "API_KEY_INVALID" }]
+        } });
+        assert_eq!(parse_error(400, &body, None), LlmError::AuthFailed);
+    }
+
+    #[test]
+    fn test_parse_error_429() {
+        let body = json!({ "error": { "code": 429, "message": "Quota exceeded", "status": "RESOURCE_EXHAUSTED" } });
+        assert_eq!(
+            parse_error(429, &body, None),
+            LlmError::RateLimited {
+                retry_after_secs: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_error_400() {
+        let body = json!({ "error": { "code": 400, "message": "Invalid JSON payload", "status": "INVALID_ARGUMENT" } });
+        assert_eq!(
+            parse_error(400, &body, None),
+            LlmError::BadRequest("Invalid JSON payload".to_string())
+        );
+    }
+}
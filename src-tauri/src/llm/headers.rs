@@ -0,0 +1,307 @@
+//! Extra HTTP headers for OpenAI-compatible endpoints.
+//!
+//! Proxies such as LiteLLM can require headers of their own (an
+//! `X-Org-Id`) or expect the bearer token under another name.
+//! `llm.extra_headers` are sent with every request to the OpenAI, Custom
+//! and OpenRouter providers, model listings included, so `validate_api_key`
+//! sends them too. `llm.auth_header_name` replaces `Authorization` as the
+//! header carrying `Bearer <api key>`. Profiles have the same two fields.
+//!
+//! Header values can be secrets: the log and the diagnostics report mask
+//! the ones whose names look sensitive (see
+//! [`redact::sensitive_header`](crate::logging::redact::sensitive_header)).
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! settings.llm.extra_headers = { 'X-Org-Id': 'acme' }; // or null
+//! settings.llm.auth_header_name = 'X-LiteLLM-Key';     // null = Authorization
+//! await invoke('update_settings', { settings }); // rejects illegal header names
+//! ```
+
+use std::collections::HashMap;
+
+use super::types::LlmRequest;
+use crate::logging::redact;
+use crate::settings::LlmSettings;
+
+/// Header carrying the API key unless `auth_header_name` says otherwise.
+pub const DEFAULT_AUTH_HEADER: &str = "Authorization";
+
+/// Headers the HTTP client sets itself.
+const RESERVED: &[&str] = &["content-type", "content-length", "host"];
+
+/// Whether `name` is a legal header name (an RFC 9110 token).
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+/// Check one provider configuration's `extra_headers` and `auth_header_name`.
+///
+/// # Returns
+///
+/// * `Ok(())` - Every name is a legal header name and no value contains a
+///   line break or other control character
+/// * `Err(String)` - What's wrong with which header
+pub fn validate(
+    extra_headers: Option<&HashMap<String, String>>,
+    auth_header_name: Option<&str>,
+) -> Result<(), String> {
+    if let Some(name) = auth_header_name {
+        if !is_valid_name(name) {
+            return Err(format!("Invalid auth header name '{}'", name));
+        }
+    }
+    for (name, value) in extra_headers.into_iter().flatten() {
+        if !is_valid_name(name) {
+            return Err(format!("Invalid header name '{}'", name));
+        }
+        if RESERVED.contains(&name.to_ascii_lowercase().as_str()) {
+            return Err(format!("Header '{}' can't be overridden", name));
+        }
+        if value.chars().any(|c| c.is_control() && c != '\t') {
+            return Err(format!("Invalid value for header '{}'", name));
+        }
+    }
+    Ok(())
+}
+
+/// Check the headers of the primary configuration and every profile.
+///
+/// # Returns
+///
+/// * `Ok(())` - All headers are valid
+/// * `Err(String)` - The first problem, naming the profile it's in
+pub fn validate_settings(settings: &LlmSettings) -> Result<(), String> {
+    validate(
+        settings.extra_headers.as_ref(),
+        settings.auth_header_name.as_deref(),
+    )?;
+    for profile in &settings.profiles {
+        validate(
+            profile.extra_headers.as_ref(),
+            profile.auth_header_name.as_deref(),
+        )
+        .map_err(|e| format!("Profile '{}': {}", profile.name, e))?;
+    }
+    Ok(())
+}
+
+/// The authentication and extra headers for an OpenAI-compatible request.
+///
+/// The API key header comes first and is omitted when no key is
+/// configured, so local endpoints without authentication keep working.
+/// Extra headers follow in name order; one with the same name as the API
+/// key header is dropped while a key is set.
+pub fn build(request: &LlmRequest) -> Vec<(String, String)> {
+    let auth_name = request
+        .auth_header_name
+        .as_deref()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DEFAULT_AUTH_HEADER);
+    let has_key = !request.api_key.is_empty();
+
+    let mut headers = Vec::new();
+    if has_key {
+        headers.push((auth_name.to_string(), format!("Bearer {}", request.api_key)));
+    }
+    let mut extra: Vec<_> = request
+        .extra_headers
+        .iter()
+        .flatten()
+        .filter(|(name, _)| !(has_key && name.eq_ignore_ascii_case(auth_name)))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    extra.sort();
+    headers.extend(extra);
+    headers
+}
+
+/// A loggable list of a request's extra headers, with sensitive values
+/// masked.
+pub fn describe(extra_headers: Option<&HashMap<String, String>>) -> String {
+    let mut described: Vec<_> = extra_headers
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| {
+            if redact::sensitive_header(name) {
+                format!("{}: {}", name, redact::secret(value))
+            } else {
+                format!("{}: {}", name, value)
+            }
+        })
+        .collect();
+    if described.is_empty() {
+        return "(none)".to_string();
+    }
+    described.sort();
+    described.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{ChatMessage, ChatRole};
+    use crate::settings::{LlmProfile, LlmProvider};
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn request(
+        api_key: &str,
+        extra_headers: &[(&str, &str)],
+        auth_header_name: Option<&str>,
+    ) -> LlmRequest {
+        let settings = LlmSettings {
+            provider: LlmProvider::Custom,
+            api_key: api_key.to_string(),
+            base_url: Some("http://localhost:4000/v1".to_string()),
+            extra_headers: Some(headers(extra_headers)),
+            auth_header_name: auth_header_name.map(str::to_string),
+            ..LlmSettings::default()
+        };
+        LlmRequest::from_settings(
+            &settings,
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: "Hi".to_string(),
+                images: Vec::new(),
+            }],
+        )
+    }
+
+    // ===== Construction =====
+
+    #[test]
+    fn test_extra_headers_follow_auth_in_name_order() {
+        let built = build(&request(
+            "sk-test",
+            &[("X-Team", "search"), ("X-Org-Id", "acme")],
+            None,
+        ));
+
+        assert_eq!(
+            built,
+            vec![
+                ("Authorization".to_string(), "Bearer sk-test".to_string()),
+                ("X-Org-Id".to_string(), "acme".to_string()),
+                ("X-Team".to_string(), "search".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_auth_header_name() {
+        let built = build(&request("sk-test", &[], Some("X-LiteLLM-Key")));
+
+        assert_eq!(
+            built,
+            vec![("X-LiteLLM-Key".to_string(), "Bearer sk-test".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_without_key_only_extra_headers() {
+        let built = build(&request("", &[("Authorization", "Basic abc")], None));
+
+        assert_eq!(
+            built,
+            vec![("Authorization".to_string(), "Basic abc".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_api_key_header_wins() {
+        let built = build(&request(
+            "sk-test",
+            &[("x-litellm-key", "other")],
+            Some("X-LiteLLM-Key"),
+        ));
+
+        assert_eq!(built.len(), 1);
+        assert_eq!(built[0].1, "Bearer sk-test");
+    }
+
+    // ===== Validation =====
+
+    #[test]
+    fn test_validate_accepts_tokens() {
+        assert!(validate(None, None).is_ok());
+        assert!(validate(
+            Some(&headers(&[("X-Org-Id", "acme"), ("x_trace~1", "a\tb")])),
+            Some("X-LiteLLM-Key")
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_illegal_names() {
+        assert_eq!(
+            validate(Some(&headers(&[("X Org", "acme")])), None),
+            Err("Invalid header name 'X Org'".to_string())
+        );
+        assert_eq!(
+            validate(Some(&headers(&[("", "acme")])), None),
+            Err("Invalid header name ''".to_string())
+        );
+        assert_eq!(
+            validate(None, Some("Auth:")),
+            Err("Invalid auth header name 'Auth:'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_line_breaks_and_reserved() {
+        assert_eq!(
+            validate(Some(&headers(&[("X-Org-Id", "acme\r\nHost: evil")])), None),
+            Err("Invalid value for header 'X-Org-Id'".to_string())
+        );
+        assert_eq!(
+            validate(Some(&headers(&[("Content-Type", "text/plain")])), None),
+            Err("Header 'Content-Type' can't be overridden".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_settings_names_profile() {
+        let settings = LlmSettings {
+            profiles: vec![LlmProfile {
+                name: "proxy".to_string(),
+                provider: LlmProvider::Custom,
+                api_key: String::new(),
+                model: "gpt-4o".to_string(),
+                base_url: None,
+                preset: None,
+                azure: None,
+                extra_headers: None,
+                auth_header_name: Some("bad name".to_string()),
+            }],
+            ..LlmSettings::default()
+        };
+
+        assert_eq!(
+            validate_settings(&settings),
+            Err("Profile 'proxy': Invalid auth header name 'bad name'".to_string())
+        );
+    }
+
+    // ===== Logging =====
+
+    #[test]
+    fn test_describe_masks_sensitive_values() {
+        let described = describe(Some(&headers(&[
+            ("X-Org-Id", "acme"),
+            ("X-Proxy-Token", "tok-abcdefghijkl1234"),
+        ])));
+
+        assert_eq!(described, "X-Org-Id: acme, X-Proxy-Token: …1234");
+        assert_eq!(describe(None), "(none)");
+    }
+}
@@ -0,0 +1,218 @@
+//! Response language preference (`llm.response_language`).
+//!
+//! When set, an instruction such as "Always respond in Brazilian
+//! Portuguese." is appended to the system prompt as it's put into a
+//! request, after the prompt library and inline prompt have been resolved.
+//! `"auto"` asks for answers in the language of the question instead.
+//! Quick action templates expand into the user message, so they're
+//! unaffected.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const languages = await invoke<LanguageInfo[]>('get_supported_languages');
+//! // [{ code: 'auto', name: 'Same as the question' }, { code: 'pt-BR', name: 'Brazilian Portuguese' }, ...]
+//! settings.llm.response_language = 'pt-BR'; // or null to leave it to the prompt
+//! await invoke('update_settings', { settings });
+//! ```
+
+use serde::Serialize;
+
+/// `llm.response_language` value for answering in the question's language.
+pub const AUTO: &str = "auto";
+
+/// Supported BCP 47 codes and the names used in the instruction.
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "Arabic"),
+    ("cs", "Czech"),
+    ("da", "Danish"),
+    ("de", "German"),
+    ("el", "Greek"),
+    ("en", "English"),
+    ("en-GB", "British English"),
+    ("en-US", "American English"),
+    ("es", "Spanish"),
+    ("es-MX", "Mexican Spanish"),
+    ("fi", "Finnish"),
+    ("fr", "French"),
+    ("fr-CA", "Canadian French"),
+    ("he", "Hebrew"),
+    ("hi", "Hindi"),
+    ("hu", "Hungarian"),
+    ("id", "Indonesian"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("nb", "Norwegian Bokmål"),
+    ("nl", "Dutch"),
+    ("pl", "Polish"),
+    ("pt-BR", "Brazilian Portuguese"),
+    ("pt-PT", "European Portuguese"),
+    ("ro", "Romanian"),
+    ("ru", "Russian"),
+    ("sv", "Swedish"),
+    ("th", "Thai"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("vi", "Vietnamese"),
+    ("zh-CN", "Simplified Chinese"),
+    ("zh-TW", "Traditional Chinese"),
+];
+
+/// A choice for `llm.response_language`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LanguageInfo {
+    pub code: &'static str,
+    pub name: &'static str,
+}
+
+/// Name of a supported language; codes match case-insensitively and with
+/// `_` for `-`.
+fn name(code: &str) -> Option<&'static str> {
+    let code = code.replace('_', "-");
+    LANGUAGES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(&code))
+        .map(|(_, name)| *name)
+}
+
+/// Check a `llm.response_language` value.
+///
+/// # Returns
+///
+/// * `Ok(())` - Unset, `"auto"` or a supported code
+/// * `Err(String)` - Anything else
+pub fn validate(setting: Option<&str>) -> Result<(), String> {
+    match setting {
+        Some(code) if code != AUTO && name(code).is_none() => {
+            Err(format!("Unsupported response language '{}'", code))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The instruction for a `llm.response_language` value.
+///
+/// # Returns
+///
+/// `None` when unset or not a supported code.
+pub fn instruction(setting: Option<&str>) -> Option<String> {
+    match setting? {
+        AUTO => Some("Always respond in the language the user wrote in.".to_string()),
+        code => match name(code) {
+            Some(name) => Some(format!("Always respond in {}.", name)),
+            None => {
+                tracing::warn!(code, "Ignoring unsupported response language");
+                None
+            }
+        },
+    }
+}
+
+/// `system_prompt` with the language instruction appended, if any.
+pub fn apply(system_prompt: &str, setting: Option<&str>) -> String {
+    match instruction(setting) {
+        Some(instruction) if system_prompt.trim().is_empty() => instruction,
+        Some(instruction) => format!("{}\n\n{}", system_prompt.trim_end(), instruction),
+        None => system_prompt.to_string(),
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// List the choices for `llm.response_language`, `"auto"` first.
+#[tauri::command]
+pub fn get_supported_languages() -> Vec<LanguageInfo> {
+    std::iter::once((AUTO, "Same as the question"))
+        .chain(LANGUAGES.iter().copied())
+        .map(|(code, name)| LanguageInfo { code, name })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===== Instruction =====
+
+    #[test]
+    fn test_unset_adds_nothing() {
+        assert_eq!(instruction(None), None);
+        assert_eq!(apply("Be brief.", None), "Be brief.");
+    }
+
+    #[test]
+    fn test_set_language() {
+        assert_eq!(
+            instruction(Some("pt-BR")).as_deref(),
+            Some("Always respond in Brazilian Portuguese.")
+        );
+        assert_eq!(
+            apply("Be brief.", Some("pt-BR")),
+            "Be brief.\n\nAlways respond in Brazilian Portuguese."
+        );
+    }
+
+    #[test]
+    fn test_auto_follows_question() {
+        assert_eq!(
+            apply("Be brief.\n", Some("auto")),
+            "Be brief.\n\nAlways respond in the language the user wrote in."
+        );
+    }
+
+    #[test]
+    fn test_empty_prompt_gets_only_instruction() {
+        assert_eq!(apply("", Some("de")), "Always respond in German.");
+    }
+
+    #[test]
+    fn test_codes_match_loosely() {
+        assert_eq!(instruction(Some("pt_br")), instruction(Some("pt-BR")));
+        assert_eq!(
+            instruction(Some("ZH-tw")).as_deref(),
+            Some("Always respond in Traditional Chinese.")
+        );
+    }
+
+    #[test]
+    fn test_unknown_code_adds_nothing() {
+        assert_eq!(apply("Be brief.", Some("xx")), "Be brief.");
+    }
+
+    // ===== Validation =====
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(None).is_ok());
+        assert!(validate(Some("auto")).is_ok());
+        assert!(validate(Some("pt-BR")).is_ok());
+        assert_eq!(
+            validate(Some("klingon")),
+            Err("Unsupported response language 'klingon'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_supported_languages_start_with_auto() {
+        let languages = get_supported_languages();
+
+        assert_eq!(languages[0].code, AUTO);
+        assert_eq!(languages.len(), LANGUAGES.len() + 1);
+    }
+
+    #[test]
+    fn test_codes_are_unique() {
+        for (i, (code, _)) in LANGUAGES.iter().enumerate() {
+            assert!(
+                !LANGUAGES[..i]
+                    .iter()
+                    .any(|(other, _)| other.eq_ignore_ascii_case(code)),
+                "{} listed twice",
+                code
+            );
+        }
+    }
+}
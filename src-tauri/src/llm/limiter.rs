@@ -0,0 +1,581 @@
+//! Client-side limits on LLM requests.
+//!
+//! Guards the provider quota against runaway callers, such as a frontend
+//! stuck in a retry loop. Two limits apply to every request that reaches
+//! the network (cache hits and local answers don't count):
+//!
+//! - `llm.max_concurrent_requests` requests in flight at once, enforced
+//!   with a semaphore
+//! - `llm.requests_per_minute` requests started per minute, as a token
+//!   bucket that holds up to a minute's worth, so short bursts are fine
+//!
+//! With `llm.queue_when_limited` a request over a limit waits its turn and
+//! the caller is told once through `on_queued`; otherwise it fails right
+//! away with [`LlmError::RateLimited`].
+//!
+//! [`RequestLimiter`] is managed as Tauri state and shared by every path
+//! that calls a model: `ask_llm`, quick actions, regenerating and titles.
+//! Time comes from a [`Clock`], so tests can drive it by hand.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, TryAcquireError};
+
+use super::client::LlmClient;
+use super::error::LlmError;
+use super::types::{Completion, LlmRequest};
+use crate::settings::LlmSettings;
+
+/// Source of time for the limiter.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Wait for `duration`.
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// The real clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// The limits, from [`LlmSettings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// At least 1
+    pub max_concurrent: u32,
+    /// `0` for no rate limit
+    pub per_minute: u32,
+    /// Wait instead of failing
+    pub queue: bool,
+}
+
+impl Limits {
+    pub fn from_settings(settings: &LlmSettings) -> Self {
+        Self {
+            max_concurrent: settings.max_concurrent_requests.max(1),
+            per_minute: settings.requests_per_minute,
+            queue: settings.queue_when_limited,
+        }
+    }
+}
+
+/// Which limit held a request back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueReason {
+    /// Too many requests in flight
+    Concurrency,
+    /// Too many requests this minute
+    Rate,
+}
+
+/// Payload of the `llm-queued` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LlmQueued {
+    pub reason: QueueReason,
+    /// How long until the request can go out, when known
+    pub wait_ms: Option<u64>,
+}
+
+/// Requests started per minute, refilled continuously.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    per_sec: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket, or `None` without a rate limit.
+    fn new(per_minute: u32, now: Instant) -> Option<Self> {
+        (per_minute > 0).then(|| Self {
+            capacity: per_minute as f64,
+            tokens: per_minute as f64,
+            per_sec: per_minute as f64 / 60.0,
+            updated: now,
+        })
+    }
+
+    /// Take a token.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Taken
+    /// * `Err(Duration)` - Empty; how long until the next token
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.updated = now;
+
+        // Tolerate rounding in the refill, so a token is there on time
+        if self.tokens >= 1.0 - 1e-9 {
+            self.tokens = (self.tokens - 1.0).max(0.0);
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.per_sec))
+        }
+    }
+}
+
+struct State {
+    limits: Limits,
+    bucket: Option<TokenBucket>,
+    slots: Arc<Semaphore>,
+}
+
+impl State {
+    fn new(limits: Limits, now: Instant) -> Self {
+        Self {
+            limits,
+            bucket: TokenBucket::new(limits.per_minute, now),
+            slots: Arc::new(Semaphore::new(limits.max_concurrent as usize)),
+        }
+    }
+}
+
+/// A request slot; dropping it lets the next request in.
+#[derive(Debug)]
+pub struct RequestPermit {
+    _slot: OwnedSemaphorePermit,
+}
+
+/// Enforces the request limits, managed as Tauri state.
+pub struct RequestLimiter {
+    clock: Arc<dyn Clock>,
+    state: Mutex<State>,
+}
+
+impl RequestLimiter {
+    pub fn new(limits: Limits) -> Self {
+        Self::with_clock(limits, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(limits: Limits, clock: Arc<dyn Clock>) -> Self {
+        let state = State::new(limits, clock.now());
+        Self {
+            clock,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Switch to new limits.
+    ///
+    /// The bucket starts full and requests waiting for a slot move to the
+    /// new limit; requests in flight keep running. Unchanged limits are
+    /// left alone, so saving settings can't be used to refill the bucket.
+    pub fn configure(&self, limits: Limits) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.limits == limits {
+            return;
+        }
+        tracing::info!(?limits, "LLM request limits changed");
+        // Wakes the waiters, which then queue on the new semaphore
+        state.slots.close();
+        *state = State::new(limits, self.clock.now());
+    }
+
+    /// Wait for (or fail to get) a slot and a token.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_queued` - Called once if the request has to wait
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RequestPermit)` - The request may go out; hold the permit
+    ///   until it's done
+    /// * `Err(LlmError::RateLimited)` - Over a limit and queueing is off
+    pub async fn acquire(
+        &self,
+        on_queued: impl FnOnce(LlmQueued),
+    ) -> Result<RequestPermit, LlmError> {
+        let mut on_queued = Some(on_queued);
+        let mut queued = |reason, wait: Option<Duration>| {
+            if let Some(notify) = on_queued.take() {
+                notify(LlmQueued {
+                    reason,
+                    wait_ms: wait.map(|wait| wait.as_millis() as u64),
+                });
+            }
+        };
+
+        // The slot first, so a token isn't spent on a request that fails
+        let slot = loop {
+            let (slots, queue) = self.slots()?;
+            match slots.clone().try_acquire_owned() {
+                Ok(slot) => break slot,
+                Err(TryAcquireError::Closed) => continue,
+                Err(TryAcquireError::NoPermits) if !queue => {
+                    return Err(LlmError::RateLimited {
+                        retry_after_secs: None,
+                    })
+                }
+                Err(TryAcquireError::NoPermits) => {}
+            }
+            queued(QueueReason::Concurrency, None);
+            if let Ok(slot) = slots.acquire_owned().await {
+                break slot;
+            }
+        };
+
+        loop {
+            let (wait, queue) = {
+                let mut state = self.lock()?;
+                let now = self.clock.now();
+                let queue = state.limits.queue;
+                match state.bucket.as_mut().map(|bucket| bucket.try_take(now)) {
+                    None | Some(Ok(())) => return Ok(RequestPermit { _slot: slot }),
+                    Some(Err(wait)) => (wait, queue),
+                }
+            };
+            if !queue {
+                return Err(LlmError::RateLimited {
+                    retry_after_secs: Some(wait.as_millis().div_ceil(1000) as u64),
+                });
+            }
+            queued(QueueReason::Rate, Some(wait));
+            self.clock.sleep(wait).await;
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, State>, LlmError> {
+        self.state
+            .lock()
+            .map_err(|e| LlmError::Internal(format!("Lock error: {}", e)))
+    }
+
+    fn slots(&self) -> Result<(Arc<Semaphore>, bool), LlmError> {
+        let state = self.lock()?;
+        Ok((state.slots.clone(), state.limits.queue))
+    }
+}
+
+/// A client whose requests go through a [`RequestLimiter`].
+pub struct Limited<'a, C, F> {
+    client: &'a C,
+    limiter: &'a RequestLimiter,
+    on_queued: F,
+}
+
+impl<'a, C, F: Fn(LlmQueued)> Limited<'a, C, F> {
+    /// Wrap `client`; `on_queued` is called each time a request waits.
+    pub fn new(client: &'a C, limiter: &'a RequestLimiter, on_queued: F) -> Self {
+        Self {
+            client,
+            limiter,
+            on_queued,
+        }
+    }
+}
+
+impl<C: LlmClient, F: Fn(LlmQueued)> LlmClient for Limited<'_, C, F> {
+    async fn complete(&self, request: &LlmRequest) -> Result<Completion, LlmError> {
+        let _permit = self.limiter.acquire(&self.on_queued).await?;
+        self.client.complete(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A clock that only moves when told to; sleeping moves it.
+    struct MockClock {
+        now: Mutex<Instant>,
+        slept: Mutex<Vec<Duration>>,
+    }
+
+    impl MockClock {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                now: Mutex::new(Instant::now()),
+                slept: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.now.lock().unwrap() += duration;
+        }
+
+        fn slept(&self) -> Vec<Duration> {
+            self.slept.lock().unwrap().clone()
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            *self.now.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            self.advance(duration);
+            self.slept.lock().unwrap().push(duration);
+            Box::pin(std::future::ready(()))
+        }
+    }
+
+    fn limits(max_concurrent: u32, per_minute: u32, queue: bool) -> Limits {
+        Limits {
+            max_concurrent,
+            per_minute,
+            queue,
+        }
+    }
+
+    fn limiter(limits: Limits) -> (RequestLimiter, Arc<MockClock>) {
+        let clock = MockClock::new();
+        (RequestLimiter::with_clock(limits, clock.clone()), clock)
+    }
+
+    async fn acquire(limiter: &RequestLimiter) -> Result<RequestPermit, LlmError> {
+        limiter.acquire(|_| {}).await
+    }
+
+    // ===== Settings =====
+
+    #[test]
+    fn test_limits_from_settings() {
+        let settings = LlmSettings {
+            max_concurrent_requests: 0,
+            requests_per_minute: 30,
+            queue_when_limited: false,
+            ..LlmSettings::default()
+        };
+
+        assert_eq!(Limits::from_settings(&settings), limits(1, 30, false));
+        assert_eq!(
+            Limits::from_settings(&LlmSettings::default()),
+            limits(2, 10, true)
+        );
+    }
+
+    // ===== Rate Limit =====
+
+    #[tokio::test]
+    async fn test_burst_up_to_rate_then_fail_fast() {
+        let (limiter, _) = limiter(limits(10, 10, false));
+
+        for _ in 0..10 {
+            drop(acquire(&limiter).await.unwrap());
+        }
+
+        // 10 per minute refills one every 6s
+        assert_eq!(
+            acquire(&limiter).await.unwrap_err(),
+            LlmError::RateLimited {
+                retry_after_secs: Some(6)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bucket_refills_over_time() {
+        let (limiter, clock) = limiter(limits(10, 10, false));
+        for _ in 0..10 {
+            drop(acquire(&limiter).await.unwrap());
+        }
+
+        clock.advance(Duration::from_secs(5));
+        assert!(acquire(&limiter).await.is_err());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(acquire(&limiter).await.is_ok());
+        assert!(acquire(&limiter).await.is_err());
+
+        // Never more than a minute's worth
+        clock.advance(Duration::from_secs(3600));
+        for _ in 0..10 {
+            drop(acquire(&limiter).await.unwrap());
+        }
+        assert!(acquire(&limiter).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_waits_for_token() {
+        let (limiter, clock) = limiter(limits(10, 2, true));
+        drop(acquire(&limiter).await.unwrap());
+        drop(acquire(&limiter).await.unwrap());
+        let mut events = Vec::new();
+
+        let permit = limiter.acquire(|queued| events.push(queued)).await;
+
+        assert!(permit.is_ok());
+        assert_eq!(clock.slept(), vec![Duration::from_secs(30)]);
+        assert_eq!(
+            events,
+            vec![LlmQueued {
+                reason: QueueReason::Rate,
+                wait_ms: Some(30_000)
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forty_requests_in_ten_seconds() {
+        let (limiter, clock) = limiter(limits(2, 10, false));
+        let mut sent = 0;
+
+        for _ in 0..40 {
+            if let Ok(permit) = acquire(&limiter).await {
+                sent += 1;
+                drop(permit);
+            }
+            clock.advance(Duration::from_millis(250));
+        }
+
+        // The burst, plus one refill at 6s
+        assert_eq!(sent, 11);
+    }
+
+    #[tokio::test]
+    async fn test_no_rate_limit() {
+        let (limiter, clock) = limiter(limits(1, 0, false));
+
+        for _ in 0..100 {
+            drop(acquire(&limiter).await.unwrap());
+        }
+        assert!(clock.slept().is_empty());
+    }
+
+    // ===== Concurrency =====
+
+    #[tokio::test]
+    async fn test_concurrency_fail_fast() {
+        let (limiter, _) = limiter(limits(2, 0, false));
+        let first = acquire(&limiter).await.unwrap();
+        let _second = acquire(&limiter).await.unwrap();
+
+        assert_eq!(
+            acquire(&limiter).await.unwrap_err(),
+            LlmError::RateLimited {
+                retry_after_secs: None
+            }
+        );
+
+        drop(first);
+        assert!(acquire(&limiter).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_queue_waits_for_slot() {
+        let (limiter, _) = limiter(limits(1, 0, true));
+        let first = acquire(&limiter).await.unwrap();
+        let mut events = Vec::new();
+
+        {
+            let waiting = limiter.acquire(|queued| events.push(queued));
+            tokio::pin!(waiting);
+            assert!(futures_poll(waiting.as_mut()).is_none());
+
+            drop(first);
+            assert!(waiting.await.is_ok());
+        }
+        assert_eq!(
+            events,
+            vec![LlmQueued {
+                reason: QueueReason::Concurrency,
+                wait_ms: None
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_token_not_spent_on_rejected_request() {
+        let (limiter, _) = limiter(limits(1, 2, false));
+        let first = acquire(&limiter).await.unwrap();
+
+        assert!(acquire(&limiter).await.is_err());
+        drop(first);
+
+        // The rejected request didn't take the second token
+        assert!(acquire(&limiter).await.is_ok());
+    }
+
+    // ===== Reconfiguring =====
+
+    #[tokio::test]
+    async fn test_configure_resets_bucket() {
+        let (limiter, _) = limiter(limits(2, 1, false));
+        drop(acquire(&limiter).await.unwrap());
+        assert!(acquire(&limiter).await.is_err());
+
+        limiter.configure(limits(2, 2, false));
+
+        assert!(acquire(&limiter).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_configure_with_same_limits_keeps_bucket() {
+        let (limiter, _) = limiter(limits(2, 1, false));
+        drop(acquire(&limiter).await.unwrap());
+
+        limiter.configure(limits(2, 1, false));
+
+        assert!(acquire(&limiter).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_configure_moves_waiters_to_new_limit() {
+        let (limiter, _) = limiter(limits(1, 0, true));
+        let _first = acquire(&limiter).await.unwrap();
+
+        let waiting = acquire(&limiter);
+        tokio::pin!(waiting);
+        assert!(futures_poll(waiting.as_mut()).is_none());
+
+        limiter.configure(limits(2, 0, true));
+
+        assert!(waiting.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_limited_client_holds_slot_during_request() {
+        struct Probe<'a>(&'a RequestLimiter);
+
+        impl LlmClient for Probe<'_> {
+            async fn complete(&self, _request: &LlmRequest) -> Result<Completion, LlmError> {
+                // The only slot is taken by the request itself
+                assert!(self.0.acquire(|_| {}).await.is_err());
+                Ok(Completion {
+                    content: "ok".to_string(),
+                    usage: None,
+                    cost_usd: None,
+                })
+            }
+        }
+
+        let (limiter, _) = limiter(limits(1, 0, false));
+        let probe = Probe(&limiter);
+        let client = Limited::new(&probe, &limiter, |_| {});
+        let request = LlmRequest::from_settings(&LlmSettings::default(), Vec::new());
+
+        assert!(client.complete(&request).await.is_ok());
+        assert!(acquire(&limiter).await.is_ok());
+    }
+
+    /// Poll a future once.
+    fn futures_poll<F: Future>(future: Pin<&mut F>) -> Option<F::Output> {
+        let waker = std::task::Waker::noop();
+        let mut context = std::task::Context::from_waker(waker);
+        match future.poll(&mut context) {
+            std::task::Poll::Ready(output) => Some(output),
+            std::task::Poll::Pending => None,
+        }
+    }
+}
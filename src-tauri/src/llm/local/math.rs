@@ -0,0 +1,305 @@
+//! Arithmetic expression evaluator.
+//!
+//! Supports `+ - * / ^`, parentheses, unary minus, and decimal numbers.
+//! `^` is right-associative and binds tighter than unary minus
+//! (`-2^2 = -4`), matching common calculator behavior.
+//!
+//! # Grammar
+//!
+//! ```text
+//! expr   := term (('+' | '-') term)*
+//! term   := unary (('*' | '/') unary)*
+//! unary  := '-' unary | power
+//! power  := atom ('^' unary)?
+//! atom   := number | '(' expr ')'
+//! ```
+
+/// Why an expression could not be evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathError {
+    /// Not a well-formed arithmetic expression
+    Syntax,
+    /// Division by zero
+    DivisionByZero,
+    /// Result is infinite or NaN (e.g. `10^400`)
+    Overflow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(f64),
+    Op(char),
+    LParen,
+    RParen,
+}
+
+/// Evaluate an arithmetic expression.
+pub fn evaluate(expr: &str) -> Result<f64, MathError> {
+    let tokens = tokenize(expr)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.expr()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(MathError::Syntax);
+    }
+    if !value.is_finite() {
+        return Err(MathError::Overflow);
+    }
+    Ok(value)
+}
+
+/// Answer `input` if it is unambiguously a pure arithmetic expression.
+///
+/// Returns `None` for anything that could mean something else: a lone
+/// number, dates (`2024-01-15`), phone numbers (`555-1234`), ranges
+/// (`3-5`), or expressions that fail to evaluate.
+pub fn answer(input: &str) -> Option<String> {
+    let expr = input.trim().trim_end_matches(['=', '?']).trim();
+    if expr.is_empty() || looks_ambiguous(expr) {
+        return None;
+    }
+
+    let value = evaluate(expr).ok()?;
+    Some(format!("{} = {}", expr, format_number(value)))
+}
+
+/// Format a result without float noise (`0.1 + 0.2` → `0.3`).
+pub fn format_number(value: f64) -> String {
+    if value == value.trunc() && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let formatted = format!("{:.10}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn looks_ambiguous(expr: &str) -> bool {
+    let operators: Vec<char> = expr
+        .chars()
+        .filter(|c| matches!(c, '+' | '-' | '*' | '/' | '^'))
+        .collect();
+
+    // A lone (possibly negative) number isn't a calculation
+    let binary_ops = expr
+        .char_indices()
+        .filter(|(i, c)| matches!(c, '+' | '*' | '/' | '^') || (*c == '-' && *i > 0))
+        .count();
+    if binary_ops == 0 {
+        return true;
+    }
+
+    // Dashes without spaces are dates, phone numbers, or ranges
+    let has_space = expr.contains(' ');
+    if !has_space && operators.iter().all(|c| *c == '-') {
+        return true;
+    }
+
+    // Two or more slashes without spaces are dates (1/2/2024)
+    !has_space && operators.len() >= 2 && operators.iter().all(|c| *c == '/')
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, MathError> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' => i += 1,
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text.parse().map_err(|_| MathError::Syntax)?;
+                tokens.push(Token::Number(number));
+            }
+            '+' | '-' | '*' | '/' | '^' => {
+                tokens.push(Token::Op(c));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            _ => return Err(MathError::Syntax),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn expr(&mut self) -> Result<f64, MathError> {
+        let mut value = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, MathError> {
+        let mut value = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/'))) = self.peek() {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            if op == '*' {
+                value *= rhs;
+            } else if rhs == 0.0 {
+                return Err(MathError::DivisionByZero);
+            } else {
+                value /= rhs;
+            }
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<f64, MathError> {
+        if let Some(Token::Op('-')) = self.peek() {
+            self.pos += 1;
+            return Ok(-self.unary()?);
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<f64, MathError> {
+        let base = self.atom()?;
+        if let Some(Token::Op('^')) = self.peek() {
+            self.pos += 1;
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<f64, MathError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::LParen) => {
+                let value = self.expr()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(value),
+                    _ => Err(MathError::Syntax),
+                }
+            }
+            _ => Err(MathError::Syntax),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===== Evaluator Tests =====
+
+    #[test]
+    fn test_basic_operations() {
+        assert_eq!(evaluate("37*48"), Ok(1776.0));
+        assert_eq!(evaluate("10 - 4"), Ok(6.0));
+        assert_eq!(evaluate("7 / 2"), Ok(3.5));
+        assert_eq!(evaluate("2 ^ 10"), Ok(1024.0));
+        assert_eq!(evaluate("1.5 + 2.25"), Ok(3.75));
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        assert_eq!(evaluate("2 + 3 * 4"), Ok(14.0));
+        assert_eq!(evaluate("(2 + 3) * 4"), Ok(20.0));
+        assert_eq!(evaluate("10 - 4 - 3"), Ok(3.0));
+        assert_eq!(evaluate("100 / 10 / 5"), Ok(2.0));
+        assert_eq!(evaluate("2 * 3 ^ 2"), Ok(18.0));
+    }
+
+    #[test]
+    fn test_power_is_right_associative() {
+        assert_eq!(evaluate("2 ^ 3 ^ 2"), Ok(512.0));
+    }
+
+    #[test]
+    fn test_unary_minus() {
+        assert_eq!(evaluate("-5 + 3"), Ok(-2.0));
+        assert_eq!(evaluate("-(2 + 3)"), Ok(-5.0));
+        assert_eq!(evaluate("-2 ^ 2"), Ok(-4.0));
+        assert_eq!(evaluate("3 * -2"), Ok(-6.0));
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        assert_eq!(evaluate("1 / 0"), Err(MathError::DivisionByZero));
+        assert_eq!(evaluate("5 / (3 - 3)"), Err(MathError::DivisionByZero));
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(evaluate("10 ^ 400"), Err(MathError::Overflow));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(evaluate("2 +"), Err(MathError::Syntax));
+        assert_eq!(evaluate("(2 + 3"), Err(MathError::Syntax));
+        assert_eq!(evaluate("2 + 3)"), Err(MathError::Syntax));
+        assert_eq!(evaluate("1.2.3 + 1"), Err(MathError::Syntax));
+        assert_eq!(evaluate("2 x 3"), Err(MathError::Syntax));
+        assert_eq!(evaluate(""), Err(MathError::Syntax));
+    }
+
+    // ===== Answer Tests =====
+
+    #[test]
+    fn test_answer_formats_result() {
+        assert_eq!(answer("37*48").as_deref(), Some("37*48 = 1776"));
+        assert_eq!(answer("0.1 + 0.2").as_deref(), Some("0.1 + 0.2 = 0.3"));
+        assert_eq!(answer("10 / 3 =").as_deref(), Some("10 / 3 = 3.3333333333"));
+    }
+
+    #[test]
+    fn test_ambiguous_inputs_fall_through() {
+        for input in [
+            "42",
+            "-7",
+            "2024-01-15",
+            "555-1234",
+            "3-5",
+            "1/2/2024",
+            "1 / 0",
+            "what is 2+2",
+            "20% of 50",
+            "1,000 + 1",
+            "2x3",
+        ] {
+            assert_eq!(answer(input), None, "{:?} should fall through", input);
+        }
+    }
+
+    #[test]
+    fn test_spaced_subtraction_is_answered() {
+        assert_eq!(answer("2024 - 1990").as_deref(), Some("2024 - 1990 = 34"));
+        assert_eq!(answer("1/2").as_deref(), Some("1/2 = 0.5"));
+    }
+}
@@ -0,0 +1,39 @@
+//! Local answers for inputs that don't need a model.
+//!
+//! Pure arithmetic (`37*48`) and simple unit conversions (`12 km in miles`)
+//! are answered instantly without a network call. Both handlers are
+//! deliberately conservative: anything ambiguous returns `None` and the
+//! request goes to the model as usual.
+//!
+//! - [`math`] - Arithmetic expression evaluator
+//! - [`units`] - Length, mass, temperature, and data size conversions
+
+pub mod math;
+pub mod units;
+
+/// Try to answer `input` locally.
+///
+/// # Returns
+///
+/// * `Some(String)` - Markdown answer
+/// * `None` - Not a local question; send it to the model
+pub fn answer(input: &str) -> Option<String> {
+    math::answer(input).or_else(|| units::answer(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_to_math_and_units() {
+        assert_eq!(answer("37*48").as_deref(), Some("37*48 = 1776"));
+        assert_eq!(answer("5 ft to cm").as_deref(), Some("5 ft = 152.4 cm"));
+    }
+
+    #[test]
+    fn test_regular_questions_fall_through() {
+        assert_eq!(answer("tar extract flags"), None);
+        assert_eq!(answer("What is 2+2 in binary?"), None);
+    }
+}
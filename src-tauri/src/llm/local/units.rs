@@ -0,0 +1,316 @@
+//! Simple unit conversions.
+//!
+//! Recognizes `<number> <unit> in|to|as|into <unit>` (optionally prefixed
+//! with `convert`) for length, mass, temperature, and data sizes.
+//!
+//! **Quirk:** `KB`/`MB`/`GB`/`TB` are decimal (1000-based, as used by disk
+//! vendors); use `KiB`/`MiB`/`GiB`/`TiB` for 1024-based sizes.
+
+use super::math::format_number;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Temperature,
+    Data,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Unit {
+    /// Symbol shown in the answer
+    symbol: &'static str,
+    dimension: Dimension,
+    /// Value of one unit in the dimension's base unit (m, kg, byte).
+    /// Unused for temperature.
+    factor: f64,
+}
+
+const fn unit(symbol: &'static str, dimension: Dimension, factor: f64) -> Unit {
+    Unit {
+        symbol,
+        dimension,
+        factor,
+    }
+}
+
+/// `(aliases, unit)`; aliases are matched case-insensitively.
+const UNITS: &[(&[&str], Unit)] = &[
+    // Length (base: meter)
+    (
+        &[
+            "mm",
+            "millimeter",
+            "millimeters",
+            "millimetre",
+            "millimetres",
+        ],
+        unit("mm", Dimension::Length, 0.001),
+    ),
+    (
+        &[
+            "cm",
+            "centimeter",
+            "centimeters",
+            "centimetre",
+            "centimetres",
+        ],
+        unit("cm", Dimension::Length, 0.01),
+    ),
+    (
+        &["m", "meter", "meters", "metre", "metres"],
+        unit("m", Dimension::Length, 1.0),
+    ),
+    (
+        &["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+        unit("km", Dimension::Length, 1000.0),
+    ),
+    (
+        &["in", "inch", "inches"],
+        unit("in", Dimension::Length, 0.0254),
+    ),
+    (
+        &["ft", "foot", "feet"],
+        unit("ft", Dimension::Length, 0.3048),
+    ),
+    (
+        &["yd", "yard", "yards"],
+        unit("yd", Dimension::Length, 0.9144),
+    ),
+    (
+        &["mi", "mile", "miles"],
+        unit("mi", Dimension::Length, 1609.344),
+    ),
+    // Mass (base: kilogram)
+    (
+        &["mg", "milligram", "milligrams"],
+        unit("mg", Dimension::Mass, 0.000_001),
+    ),
+    (&["g", "gram", "grams"], unit("g", Dimension::Mass, 0.001)),
+    (
+        &["kg", "kilogram", "kilograms", "kilo", "kilos"],
+        unit("kg", Dimension::Mass, 1.0),
+    ),
+    (
+        &["t", "tonne", "tonnes"],
+        unit("t", Dimension::Mass, 1000.0),
+    ),
+    (
+        &["oz", "ounce", "ounces"],
+        unit("oz", Dimension::Mass, 0.028_349_523_125),
+    ),
+    (
+        &["lb", "lbs", "pound", "pounds"],
+        unit("lb", Dimension::Mass, 0.453_592_37),
+    ),
+    (
+        &["st", "stone", "stones"],
+        unit("st", Dimension::Mass, 6.350_293_18),
+    ),
+    // Temperature (converted via Celsius)
+    (
+        &["c", "°c", "celsius", "degrees celsius"],
+        unit("°C", Dimension::Temperature, 0.0),
+    ),
+    (
+        &["f", "°f", "fahrenheit", "degrees fahrenheit"],
+        unit("°F", Dimension::Temperature, 0.0),
+    ),
+    (
+        &["k", "kelvin", "kelvins"],
+        unit("K", Dimension::Temperature, 0.0),
+    ),
+    // Data (base: byte)
+    (&["bit", "bits"], unit("bit", Dimension::Data, 0.125)),
+    (&["b", "byte", "bytes"], unit("B", Dimension::Data, 1.0)),
+    (
+        &["kb", "kilobyte", "kilobytes"],
+        unit("KB", Dimension::Data, 1e3),
+    ),
+    (
+        &["mb", "megabyte", "megabytes"],
+        unit("MB", Dimension::Data, 1e6),
+    ),
+    (
+        &["gb", "gigabyte", "gigabytes"],
+        unit("GB", Dimension::Data, 1e9),
+    ),
+    (
+        &["tb", "terabyte", "terabytes"],
+        unit("TB", Dimension::Data, 1e12),
+    ),
+    (
+        &["kib", "kibibyte", "kibibytes"],
+        unit("KiB", Dimension::Data, 1024.0),
+    ),
+    (
+        &["mib", "mebibyte", "mebibytes"],
+        unit("MiB", Dimension::Data, 1_048_576.0),
+    ),
+    (
+        &["gib", "gibibyte", "gibibytes"],
+        unit("GiB", Dimension::Data, 1_073_741_824.0),
+    ),
+    (
+        &["tib", "tebibyte", "tebibytes"],
+        unit("TiB", Dimension::Data, 1_099_511_627_776.0),
+    ),
+];
+
+const KEYWORDS: &[&str] = &["in", "to", "as", "into"];
+
+/// Answer `input` if it is a conversion between two known units of the
+/// same dimension. Anything else returns `None`.
+pub fn answer(input: &str) -> Option<String> {
+    let input = input.trim().trim_end_matches('?').trim().to_lowercase();
+    let input = input.strip_prefix("convert ").unwrap_or(&input);
+    let tokens: Vec<&str> = input.split_whitespace().collect();
+
+    let (value, rest) = split_number(&tokens)?;
+
+    // "12 in in cm": try every keyword position until both sides are units
+    for (k, token) in rest.iter().enumerate() {
+        if k == 0 || !KEYWORDS.contains(token) {
+            continue;
+        }
+        let (Some(from), Some(to)) = (
+            lookup(&rest[..k].join(" ")),
+            lookup(&rest[k + 1..].join(" ")),
+        ) else {
+            continue;
+        };
+        if from.dimension != to.dimension || from == to {
+            return None;
+        }
+
+        let result = convert(value, from, to)?;
+        return Some(format!(
+            "{} {} = {} {}",
+            format_number(value),
+            from.symbol,
+            format_number(round_significant(result)),
+            to.symbol
+        ));
+    }
+
+    None
+}
+
+/// Split the leading number, allowing it to be glued to the unit (`12km`).
+fn split_number<'a>(tokens: &[&'a str]) -> Option<(f64, Vec<&'a str>)> {
+    let first = tokens.first()?;
+    if let Ok(value) = first.parse::<f64>() {
+        return Some((value, tokens[1..].to_vec()));
+    }
+
+    let split = first.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))?;
+    let (number, unit) = first.split_at(split);
+    let value = number.parse::<f64>().ok()?;
+    let mut rest = vec![unit];
+    rest.extend_from_slice(&tokens[1..]);
+    Some((value, rest))
+}
+
+fn lookup(name: &str) -> Option<Unit> {
+    UNITS
+        .iter()
+        .find(|(aliases, _)| aliases.contains(&name))
+        .map(|(_, unit)| *unit)
+}
+
+fn convert(value: f64, from: Unit, to: Unit) -> Option<f64> {
+    if from.dimension != Dimension::Temperature {
+        return Some(value * from.factor / to.factor);
+    }
+
+    let celsius = match from.symbol {
+        "°C" => value,
+        "°F" => (value - 32.0) * 5.0 / 9.0,
+        _ => value - 273.15,
+    };
+    if celsius < -273.15 {
+        return None;
+    }
+    Some(match to.symbol {
+        "°C" => celsius,
+        "°F" => celsius * 9.0 / 5.0 + 32.0,
+        _ => celsius + 273.15,
+    })
+}
+
+/// Round to 6 significant digits so answers don't show float noise.
+fn round_significant(value: f64) -> f64 {
+    if value == 0.0 {
+        return 0.0;
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    let scale = 10f64.powi(5 - magnitude);
+    (value * scale).round() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_length() {
+        assert_eq!(
+            answer("12 km in miles").as_deref(),
+            Some("12 km = 7.45645 mi")
+        );
+        assert_eq!(answer("5 ft to cm").as_deref(), Some("5 ft = 152.4 cm"));
+        assert_eq!(answer("12 in in cm").as_deref(), Some("12 in = 30.48 cm"));
+    }
+
+    #[test]
+    fn test_mass() {
+        assert_eq!(answer("1 kg to lbs").as_deref(), Some("1 kg = 2.20462 lb"));
+        assert_eq!(
+            answer("convert 8 oz to g").as_deref(),
+            Some("8 oz = 226.796 g")
+        );
+    }
+
+    #[test]
+    fn test_temperature() {
+        assert_eq!(answer("100 c to f").as_deref(), Some("100 °C = 212 °F"));
+        assert_eq!(
+            answer("-40 fahrenheit in celsius").as_deref(),
+            Some("-40 °F = -40 °C")
+        );
+        assert_eq!(answer("0 kelvin to c").as_deref(), Some("0 K = -273.15 °C"));
+    }
+
+    #[test]
+    fn test_below_absolute_zero_falls_through() {
+        assert_eq!(answer("-500 c to f"), None);
+    }
+
+    #[test]
+    fn test_data_sizes() {
+        assert_eq!(answer("1 GB in MB").as_deref(), Some("1 GB = 1000 MB"));
+        assert_eq!(answer("1 GiB in MiB").as_deref(), Some("1 GiB = 1024 MiB"));
+        assert_eq!(answer("8 bits to bytes").as_deref(), Some("8 bit = 1 B"));
+    }
+
+    #[test]
+    fn test_number_glued_to_unit() {
+        assert_eq!(answer("10km to m").as_deref(), Some("10 km = 10000 m"));
+    }
+
+    #[test]
+    fn test_ambiguous_inputs_fall_through() {
+        for input in [
+            "12 km",
+            "km to miles",
+            "5 kg to km",
+            "5 m in m",
+            "3 cups to grams",
+            "how far is 12 km in miles",
+            "1 m in a day",
+            "12 km in miles and back",
+        ] {
+            assert_eq!(answer(input), None, "{:?} should fall through", input);
+        }
+    }
+}
@@ -0,0 +1,898 @@
+//! LLM feature module.
+//!
+//! Sends chat requests to the configured provider from the Rust side so
+//! cross-cutting behavior (caching, provider fallback, ...) lives in one
+//! place instead of in each frontend service.
+//!
+//! # Architecture
+//!
+//! - [`types`] - Provider-neutral request/response types
+//! - [`client`] - `LlmClient` trait and the HTTP implementation
+//! - [`error`] - `LlmError`, the structured error returned to the frontend
+//! - [`gemini`], [`openai`], [`anthropic`], [`openrouter`], [`azure`] - Per-provider wire formats
+//! - [`presets`] - Vendor presets (Groq, Mistral, ...) for the `custom` provider
+//! - [`models`] - Model aliases and favorites for the model switcher
+//! - [`cache`] - Opt-in response cache (`llm.cache_ttl_minutes`)
+//! - [`connectivity`] - Offline detection before network calls
+//! - [`language`] - `llm.response_language` instruction and supported languages
+//! - [`limiter`] - Concurrency and per-minute limits on requests
+//! - [`local`] - Instant local answers for arithmetic and unit conversions
+//! - [`usage`], [`pricing`] - Token usage tracking and cost estimates
+//! - This file - Request orchestration and Tauri commands
+//!
+//! # Frontend Integration
+//!
+//! ```typescript
+//! const response = await invoke<LlmResponse>('ask_llm', {
+//!   messages: [{ role: 'user', content: 'tar extract flags' }],
+//!   conversationId: 'conv-123', // optional: store the reply in history
+//!   source: 'clipboard', // optional: where the prompt came from, for prompt history
+//! });
+//! if (response.cached) showCachedBadge();
+//!
+//! // Ask about a screen capture (see `capture_screen_region`)
+//! const image = await invoke<CapturedImage>('capture_screen_region', { region: null });
+//! await invoke<LlmResponse>('ask_llm_with_image', { messages, image, conversationId });
+//!
+//! // Emitted when the provider host becomes reachable/unreachable
+//! await listen<{ online: boolean }>('connectivity-changed', ({ payload }) => {
+//!   offlineBanner.visible = !payload.online;
+//! });
+//!
+//! // Emitted when the primary provider failed and a fallback profile answered
+//! await listen<FallbackUsed>('llm-fallback-used', ({ payload }) => {
+//!   console.warn(`${payload.failed.join(', ')} failed, answered by ${payload.used}`);
+//! });
+//!
+//! // Emitted when a request waits for `llm.max_concurrent_requests` or
+//! // `llm.requests_per_minute`; with queueing off it fails with `rate_limited`
+//! await listen<LlmQueued>('llm-queued', ({ payload }) => {
+//!   // { reason: 'rate', wait_ms: 4200 }
+//! });
+//!
+//! const stats = await invoke<UsageStats>('get_usage_stats', { rangeDays: 30 });
+//! ```
+
+pub mod anthropic;
+pub mod azure;
+pub mod cache;
+pub mod client;
+pub mod connectivity;
+pub mod error;
+pub mod gemini;
+pub mod headers;
+pub mod language;
+pub mod limiter;
+pub mod local;
+pub mod models;
+pub mod openai;
+pub mod openrouter;
+pub mod presets;
+pub mod pricing;
+pub mod types;
+pub mod usage;
+
+pub use cache::ResponseCache;
+pub use connectivity::Connectivity;
+pub use error::LlmError;
+pub use limiter::{Limits, RequestLimiter};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tracing::Instrument;
+
+use crate::db::{now_ms, Db};
+use crate::history::prompt_history::PromptSource;
+use crate::history::{self, drafts};
+use crate::logging::redact;
+use crate::network;
+use crate::notifications;
+use crate::prompts;
+use crate::settings::{LlmSettings, SettingsManager};
+use crate::telemetry::{self, TelemetryEvent};
+use crate::window::quit::ActivityTracker;
+use client::{HttpClient, LlmClient};
+use connectivity::{AssumeOnline, ChainProbe, Prober};
+use limiter::{Limited, LlmQueued};
+use models::ListedModel;
+use types::{ChatMessage, ChatRole, ImagePart, LlmRequest, LlmResponse, ResponseSource};
+use usage::UsageStats;
+
+/// Answer a request, consulting the response cache first.
+///
+/// On a cache miss the client is called and a successful reply is stored.
+/// With `ttl_minutes == 0` the cache is bypassed entirely.
+///
+/// # Arguments
+///
+/// * `client` - Client used on a cache miss
+/// * `cache` - Response cache
+/// * `request` - Fully resolved request
+/// * `ttl_minutes` - Cache TTL from settings
+/// * `now_ms` - Current Unix timestamp (ms)
+pub async fn ask_with_cache<C: LlmClient>(
+    client: &C,
+    cache: &ResponseCache,
+    request: &LlmRequest,
+    ttl_minutes: u32,
+    now_ms: i64,
+) -> Result<LlmResponse, LlmError> {
+    let key = ResponseCache::key(request);
+
+    if let Some(content) = cache
+        .get(&key, ttl_minutes, now_ms)
+        .await
+        .map_err(LlmError::Internal)?
+    {
+        return Ok(LlmResponse {
+            content,
+            provider: request.provider.clone(),
+            model: request.model.clone(),
+            profile: request.profile.clone(),
+            failed_profiles: Vec::new(),
+            source: ResponseSource::Model,
+            cached: true,
+            usage: None,
+            cost_usd: None,
+            message_id: None,
+        });
+    }
+
+    let completion = client.complete(request).await?;
+
+    if ttl_minutes > 0 {
+        cache
+            .put(&key, &completion.content, now_ms)
+            .await
+            .map_err(LlmError::Internal)?;
+    }
+
+    Ok(LlmResponse {
+        content: completion.content,
+        provider: request.provider.clone(),
+        model: request.model.clone(),
+        profile: request.profile.clone(),
+        failed_profiles: Vec::new(),
+        source: ResponseSource::Model,
+        cached: false,
+        usage: completion.usage,
+        cost_usd: completion.cost_usd,
+        message_id: None,
+    })
+}
+
+/// Answer a request, trying each profile in `chain` until one succeeds.
+///
+/// Each profile's host is probed first; an unreachable one fails with
+/// `LlmError::Network("offline")` without a request. Only retryable errors
+/// (auth, rate limit, network, offline, server errors) move on to the next
+/// profile; a fatal error is returned immediately. The response lists the
+/// profiles that failed before the answering one.
+///
+/// # Arguments
+///
+/// * `client` - Client used for every attempt
+/// * `prober` - Checks each profile's host before it's tried
+/// * `cache` - Response cache, consulted per profile
+/// * `chain` - Primary request followed by fallbacks (see [`LlmRequest::chain_from_settings`])
+/// * `ttl_minutes` - Cache TTL from settings
+/// * `now_ms` - Current Unix timestamp (ms)
+///
+/// # Returns
+///
+/// * `Ok(LlmResponse)` - Reply from the first profile that succeeded
+/// * `Err(LlmError)` - The first fatal error, or the last error if every profile failed
+pub async fn ask_with_fallback<C: LlmClient, P: Prober>(
+    client: &C,
+    prober: &P,
+    cache: &ResponseCache,
+    chain: &[LlmRequest],
+    ttl_minutes: u32,
+    now_ms: i64,
+) -> Result<LlmResponse, LlmError> {
+    let mut failed_profiles = Vec::new();
+    let mut last_error = LlmError::Internal("No LLM profile configured".to_string());
+
+    for request in chain {
+        let result = if prober.probe(&client::build_http_request(request).url).await {
+            ask_with_cache(client, cache, request, ttl_minutes, now_ms).await
+        } else {
+            tracing::warn!(profile = %request.profile, "Offline; profile skipped");
+            Err(LlmError::Network("offline".to_string()))
+        };
+        match result {
+            Ok(mut response) => {
+                response.failed_profiles = failed_profiles;
+                return Ok(response);
+            }
+            Err(error) if error.is_retryable() => {
+                failed_profiles.push(request.profile.clone());
+                last_error = error;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    Err(last_error)
+}
+
+/// `client` behind the shared [`RequestLimiter`], announcing waits with
+/// `llm-queued`.
+pub fn limited<'a, C: LlmClient>(
+    app: &'a AppHandle,
+    client: &'a C,
+) -> Limited<'a, C, impl Fn(LlmQueued) + 'a> {
+    Limited::new(
+        client,
+        app.state::<RequestLimiter>().inner(),
+        move |queued| {
+            tracing::info!(reason = ?queued.reason, wait_ms = ?queued.wait_ms, "Request queued");
+            let _ = app.emit("llm-queued", queued);
+        },
+    )
+}
+
+/// Answer the last user message locally, if `llm.local_answers` allows it.
+///
+/// Returns `None` when local answers are disabled or the message needs a
+/// model (see [`local::answer`]).
+pub fn answer_locally(settings: &LlmSettings, messages: &[ChatMessage]) -> Option<LlmResponse> {
+    if !settings.local_answers {
+        return None;
+    }
+    let last = messages
+        .last()
+        .filter(|m| m.role == ChatRole::User && m.images.is_empty())?;
+    let content = local::answer(&last.content)?;
+
+    Some(LlmResponse {
+        content,
+        provider: settings.provider.clone(),
+        model: types::LOCAL_MODEL.to_string(),
+        profile: types::PRIMARY_PROFILE.to_string(),
+        failed_profiles: Vec::new(),
+        source: ResponseSource::Local,
+        cached: false,
+        usage: None,
+        cost_usd: None,
+        message_id: None,
+    })
+}
+
+/// Payload of the `llm-fallback-used` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct FallbackUsed {
+    /// Profiles that failed, in the order they were tried
+    pub failed: Vec<String>,
+    /// Profile that answered
+    pub used: String,
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Send a conversation to the configured LLM and return the full reply.
+///
+/// Uses the provider, model, and system prompt from settings. Arithmetic
+/// and unit conversions are answered locally with `source: "local"` when
+/// `llm.local_answers` is on. Otherwise each profile's host is checked
+/// before it's tried (cached for 30 seconds); an unreachable one is skipped
+/// like a failed one, and when none is reachable the call fails with
+/// `LlmError::Network("offline")` and `connectivity-changed` reports it. When
+/// `llm.cache_ttl_minutes` is non-zero, identical requests within the TTL
+/// are answered from the cache with `cached: true`.
+///
+/// If the primary provider is unavailable, the profiles listed in
+/// `llm.fallback_profiles` are tried in order and `llm-fallback-used` is
+/// emitted when one of them answers.
+///
+/// When `conversation_id` is given, the reply is stored as an assistant
+/// message together with its token usage (see [`usage::record_reply`]).
+/// The prompt's draft is cleared either way, and the prompt is recorded
+/// for up-arrow recall (see [`crate::history::prompt_history`]).
+///
+/// If the launcher was hidden in the meantime, a system notification
+/// announces the answer or error (see [`crate::notifications`]).
+///
+/// # Arguments
+///
+/// * `messages` - Conversation so far, oldest first
+/// * `conversation_id` - Conversation to append the reply to, if any
+/// * `source` - Where the prompt came from; defaults to typed
+///
+/// # Returns
+///
+/// * `Ok(LlmResponse)` - The model's reply
+/// * `Err(LlmError)` - Structured error, tagged by `kind` for the UI
+#[tauri::command]
+pub async fn ask_llm(
+    app: AppHandle,
+    db: State<'_, Db>,
+    messages: Vec<ChatMessage>,
+    conversation_id: Option<String>,
+    source: Option<PromptSource>,
+) -> Result<LlmResponse, LlmError> {
+    let started_ms = now_ms();
+    if let Err(e) = drafts::clear_submitted(db.pool(), conversation_id.as_deref()).await {
+        tracing::warn!(error = %e, "Failed to clear draft");
+    }
+    if let Some(prompt) = messages.last().filter(|m| m.role == ChatRole::User) {
+        history::record_prompt(&app, &prompt.content, source.unwrap_or_default()).await;
+    }
+    let result = match ask(&app, messages, conversation_id.as_deref()).await {
+        Ok(response) => {
+            let duration_ms = (now_ms() - started_ms).max(0) as u32;
+            store_reply(&db, conversation_id.clone(), response, duration_ms).await
+        }
+        Err(e) => Err(e),
+    };
+    notifications::notify_completion(&app, started_ms, conversation_id.as_deref(), &result).await;
+    result
+}
+
+/// Send a conversation with an image, like a screen capture, to the LLM.
+///
+/// The image is added to the last user message, which the model sees as
+/// text followed by the image, and the conversation is answered like
+/// `ask_llm`. Record the image with the user message by passing it to
+/// `append_message` as an attachment.
+///
+/// # Arguments
+///
+/// * `messages` - Conversation so far, oldest first, ending with the prompt
+/// * `image` - The image; a `CapturedImage` can be passed as is
+/// * `conversation_id` - Conversation to append the reply to, if any
+/// * `source` - Where the prompt came from; defaults to typed
+///
+/// # Returns
+///
+/// * `Ok(LlmResponse)` - The model's reply
+/// * `Err(LlmError)` - Structured error, tagged by `kind` for the UI;
+///   `BadRequest` when there is no user message to add the image to
+#[tauri::command]
+pub async fn ask_llm_with_image(
+    app: AppHandle,
+    db: State<'_, Db>,
+    mut messages: Vec<ChatMessage>,
+    image: ImagePart,
+    conversation_id: Option<String>,
+    source: Option<PromptSource>,
+) -> Result<LlmResponse, LlmError> {
+    attach_image(&mut messages, image)?;
+    ask_llm(app, db, messages, conversation_id, source).await
+}
+
+/// Add `image` to the last message, which must be the user's prompt.
+fn attach_image(messages: &mut [ChatMessage], image: ImagePart) -> Result<(), LlmError> {
+    let prompt = messages
+        .last_mut()
+        .filter(|m| m.role == ChatRole::User)
+        .ok_or_else(|| LlmError::BadRequest("No prompt to attach the image to".to_string()))?;
+    prompt.images.push(image);
+    Ok(())
+}
+
+/// Answer a conversation the way `ask_llm` does, without storing the reply.
+///
+/// Also used by quick actions, which answer outside the launcher.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+/// * `messages` - Conversation so far, oldest first
+/// * `conversation_id` - Stored conversation, whose system prompt override
+///   applies
+pub async fn ask(
+    app: &AppHandle,
+    messages: Vec<ChatMessage>,
+    conversation_id: Option<&str>,
+) -> Result<LlmResponse, LlmError> {
+    let mut settings = app.state::<SettingsManager>().load()?;
+    if let Some(response) = answer_locally(&settings.llm, &messages) {
+        return Ok(response);
+    }
+    settings.llm.system_prompt =
+        prompts::resolve_system_prompt(app.state::<Db>().pool(), &settings.llm, conversation_id)
+            .await;
+
+    let chain = LlmRequest::chain_from_settings(&settings.llm, messages)?;
+    let _activity = app.state::<ActivityTracker>().begin();
+    let primary = &chain[0];
+    telemetry::record(
+        app,
+        TelemetryEvent::AskSubmitted {
+            provider: primary.provider.clone(),
+            model: primary.model.clone(),
+        },
+    );
+    let span = tracing::info_span!("ask", provider = ?primary.provider, model = %primary.model);
+    async {
+        tracing::info!(fallbacks = chain.len() - 1, "Sending request");
+        tracing::debug!(
+            key = %redact::secret(&primary.api_key),
+            headers = %headers::describe(primary.extra_headers.as_ref()),
+            prompt = %redact::summary(primary.messages.last().map_or("", |m| m.content.as_str())),
+            "Request details"
+        );
+
+        let http = HttpClient::new(&settings.network)?;
+        let connectivity = app.state::<Connectivity>();
+        let cache = app.state::<ResponseCache>();
+        // The probe connects directly, so it would report offline behind a proxy
+        let result = if network::proxy_url(&settings.network)?.is_none() {
+            let probe = ChainProbe::new(&connectivity, now_ms());
+            let result = ask_with_fallback(
+                &limited(app, &http),
+                &probe,
+                &cache,
+                &chain,
+                settings.llm.cache_ttl_minutes,
+                now_ms(),
+            )
+            .await;
+            if let Some(changed) = probe.finish() {
+                let _ = app.emit("connectivity-changed", changed);
+            }
+            result
+        } else {
+            ask_with_fallback(
+                &limited(app, &http),
+                &AssumeOnline,
+                &cache,
+                &chain,
+                settings.llm.cache_ttl_minutes,
+                now_ms(),
+            )
+            .await
+        };
+        let response = result.inspect_err(|e| tracing::warn!(error = %e, "Request failed"))?;
+        tracing::info!(
+            profile = %response.profile,
+            cached = response.cached,
+            answer = %redact::summary(&response.content),
+            "Answer received"
+        );
+
+        if !response.failed_profiles.is_empty() {
+            let _ = app.emit(
+                "llm-fallback-used",
+                FallbackUsed {
+                    failed: response.failed_profiles.clone(),
+                    used: response.profile.clone(),
+                },
+            );
+        }
+
+        Ok(response)
+    }
+    .instrument(span)
+    .await
+}
+
+/// Store the reply in the conversation, if one was given, with how long it
+/// took (`duration_ms`).
+async fn store_reply(
+    db: &Db,
+    conversation_id: Option<String>,
+    mut response: LlmResponse,
+    duration_ms: u32,
+) -> Result<LlmResponse, LlmError> {
+    if let Some(conversation_id) = conversation_id {
+        let message_id = usage::record_reply(
+            db.pool(),
+            &conversation_id,
+            &response,
+            duration_ms,
+            now_ms(),
+        )
+        .await?;
+        response.message_id = Some(message_id);
+    }
+
+    Ok(response)
+}
+
+/// Probe the configured provider's host, bypassing the cached result.
+///
+/// Emits `connectivity-changed` when the state differs from the last check.
+#[tauri::command]
+pub async fn check_connectivity(
+    app: AppHandle,
+    settings_manager: State<'_, SettingsManager>,
+    connectivity: State<'_, Connectivity>,
+) -> Result<bool, String> {
+    let settings = settings_manager.snapshot()?;
+    Ok(probe_provider(&app, &connectivity, &settings.llm, 0).await)
+}
+
+/// Check whether the provider configured in `llm` is reachable.
+///
+/// # Arguments
+///
+/// * `max_age_ms` - Reuse a cached result younger than this (`0` forces a probe)
+pub async fn probe_provider(
+    app: &AppHandle,
+    connectivity: &Connectivity,
+    llm: &LlmSettings,
+    max_age_ms: i64,
+) -> bool {
+    let request = LlmRequest::from_settings(llm, Vec::new());
+    let url = client::build_http_request(&request).url;
+    check_online(app, connectivity, &url, max_age_ms).await
+}
+
+/// Check connectivity and emit `connectivity-changed` on transitions.
+async fn check_online(
+    app: &AppHandle,
+    connectivity: &Connectivity,
+    url: &str,
+    max_age_ms: i64,
+) -> bool {
+    let (online, changed) = connectivity.check(url, max_age_ms, now_ms()).await;
+    if let Some(changed) = changed {
+        let _ = app.emit("connectivity-changed", changed);
+    }
+    online
+}
+
+/// List the models offered by the configured provider.
+///
+/// Uses the provider, API key, and base URL from settings. OpenRouter
+/// entries include pricing. Each model carries its alias and favorite
+/// flag, favorites first (see [`models`]).
+#[tauri::command]
+pub async fn list_models(
+    settings_manager: State<'_, SettingsManager>,
+) -> Result<Vec<ListedModel>, LlmError> {
+    let settings = settings_manager.snapshot()?;
+    let request = LlmRequest::from_settings(&settings.llm, Vec::new());
+    let listed = HttpClient::new(&settings.network)?
+        .list_models(&request)
+        .await?;
+    Ok(models::merge(listed, &settings.llm))
+}
+
+/// Check whether the configured API key is accepted by the provider.
+///
+/// Lists models (deployments for Azure) as a cheap authenticated call.
+///
+/// # Returns
+///
+/// * `Ok(true)` - The key works
+/// * `Ok(false)` - The provider rejected the key
+/// * `Err(LlmError)` - Any other failure (offline, misconfigured, ...)
+#[tauri::command]
+pub async fn validate_api_key(
+    settings_manager: State<'_, SettingsManager>,
+) -> Result<bool, LlmError> {
+    let settings = settings_manager.snapshot()?;
+    let request = LlmRequest::from_settings(&settings.llm, Vec::new());
+    match HttpClient::new(&settings.network)?
+        .list_models(&request)
+        .await
+    {
+        Ok(_) => Ok(true),
+        Err(LlmError::AuthFailed) => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+/// Remove every entry from the LLM response cache.
+#[tauri::command]
+pub async fn clear_llm_cache(cache: State<'_, ResponseCache>) -> Result<(), String> {
+    cache.clear().await
+}
+
+/// Get token usage and estimated cost for the last `range_days` days.
+///
+/// Costs come from the built-in price table, overridden by
+/// `llm.model_prices`. Models without a known price report `cost_usd: null`.
+///
+/// # Arguments
+///
+/// * `range_days` - Number of days to include, ending today (UTC)
+#[tauri::command]
+pub async fn get_usage_stats(
+    settings_manager: State<'_, SettingsManager>,
+    db: State<'_, Db>,
+    range_days: u32,
+) -> Result<UsageStats, String> {
+    let settings = settings_manager.snapshot()?;
+    usage::usage_stats(db.pool(), range_days, now_ms(), &settings.llm.model_prices).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::settings::LlmProvider;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use types::{Completion, TokenUsage};
+
+    /// Client that counts calls and echoes a fixed reply.
+    struct MockClient {
+        calls: AtomicUsize,
+    }
+
+    impl LlmClient for MockClient {
+        async fn complete(&self, _request: &LlmRequest) -> Result<Completion, LlmError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Completion {
+                content: "tar -xvf file.tar".to_string(),
+                usage: Some(TokenUsage {
+                    prompt_tokens: 10,
+                    completion_tokens: 5,
+                }),
+                cost_usd: None,
+            })
+        }
+    }
+
+    /// Client that fails for selected profiles and records every attempt.
+    struct ScriptedClient {
+        failures: HashMap<&'static str, LlmError>,
+        attempts: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedClient {
+        fn new(failures: Vec<(&'static str, LlmError)>) -> Self {
+            Self {
+                failures: failures.into_iter().collect(),
+                attempts: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn attempts(&self) -> Vec<String> {
+            self.attempts.lock().unwrap().clone()
+        }
+    }
+
+    impl LlmClient for ScriptedClient {
+        async fn complete(&self, request: &LlmRequest) -> Result<Completion, LlmError> {
+            self.attempts.lock().unwrap().push(request.profile.clone());
+            match self.failures.get(request.profile.as_str()) {
+                Some(error) => Err(error.clone()),
+                None => Ok(Completion {
+                    content: format!("answer from {}", request.profile),
+                    usage: None,
+                    cost_usd: None,
+                }),
+            }
+        }
+    }
+
+    fn chain() -> Vec<LlmRequest> {
+        let mut primary = request();
+        primary.provider = LlmProvider::OpenAI;
+        let mut backup = request();
+        backup.profile = "backup".to_string();
+        vec![primary, backup]
+    }
+
+    fn unavailable() -> LlmError {
+        LlmError::ProviderUnavailable
+    }
+
+    fn request() -> LlmRequest {
+        LlmRequest::from_settings(
+            &LlmSettings::default(),
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: "tar extract flags".to_string(),
+                images: Vec::new(),
+            }],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_second_identical_request_is_cached() {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        let client = MockClient {
+            calls: AtomicUsize::new(0),
+        };
+
+        let first = ask_with_cache(&client, &cache, &request(), 10, 0)
+            .await
+            .unwrap();
+        let second = ask_with_cache(&client, &cache, &request(), 10, 1_000)
+            .await
+            .unwrap();
+
+        assert!(!first.cached);
+        assert!(first.usage.is_some());
+        assert!(second.cached);
+        assert!(second.usage.is_none(), "Cached replies cost no tokens");
+        assert_eq!(second.content, first.content);
+        assert_eq!(client.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_cache_always_calls_client() {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        let client = MockClient {
+            calls: AtomicUsize::new(0),
+        };
+
+        for _ in 0..2 {
+            let response = ask_with_cache(&client, &cache, &request(), 0, 0)
+                .await
+                .unwrap();
+            assert!(!response.cached);
+        }
+        assert_eq!(client.calls.load(Ordering::SeqCst), 2);
+    }
+
+    // ===== Local Answer Tests =====
+
+    fn user(content: &str) -> Vec<ChatMessage> {
+        vec![ChatMessage {
+            role: ChatRole::User,
+            content: content.to_string(),
+            images: Vec::new(),
+        }]
+    }
+
+    #[test]
+    fn test_local_answer_for_arithmetic() {
+        let response = answer_locally(&LlmSettings::default(), &user("37*48")).unwrap();
+
+        assert_eq!(response.source, ResponseSource::Local);
+        assert_eq!(response.content, "37*48 = 1776");
+        assert!(response.usage.is_none());
+    }
+
+    #[test]
+    fn test_local_answers_can_be_disabled() {
+        let settings = LlmSettings {
+            local_answers: false,
+            ..LlmSettings::default()
+        };
+        assert!(answer_locally(&settings, &user("37*48")).is_none());
+    }
+
+    #[test]
+    fn test_non_local_question_goes_to_model() {
+        assert!(answer_locally(&LlmSettings::default(), &user("tar extract flags")).is_none());
+    }
+
+    // ===== Image Tests =====
+
+    fn png() -> ImagePart {
+        ImagePart {
+            mime: "image/png".to_string(),
+            data: "iVBORw0K".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_image_attached_to_prompt() {
+        let mut messages = user("37*48");
+
+        attach_image(&mut messages, png()).unwrap();
+
+        assert_eq!(messages[0].images, [png()]);
+        // The model has to see the image, even for arithmetic
+        assert!(answer_locally(&LlmSettings::default(), &messages).is_none());
+    }
+
+    #[test]
+    fn test_image_needs_a_prompt() {
+        let mut messages = Vec::new();
+
+        let err = attach_image(&mut messages, png()).unwrap_err();
+
+        assert!(matches!(err, LlmError::BadRequest(_)));
+    }
+
+    // ===== Fallback Tests =====
+
+    #[tokio::test]
+    async fn test_fallback_not_used_when_primary_succeeds() {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        let client = ScriptedClient::new(vec![]);
+
+        let response = ask_with_fallback(&client, &AssumeOnline, &cache, &chain(), 0, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(response.profile, types::PRIMARY_PROFILE);
+        assert_eq!(response.provider, LlmProvider::OpenAI);
+        assert!(response.failed_profiles.is_empty());
+        assert_eq!(client.attempts(), vec![types::PRIMARY_PROFILE]);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_answers_when_primary_unavailable() {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        let client = ScriptedClient::new(vec![(types::PRIMARY_PROFILE, unavailable())]);
+
+        let response = ask_with_fallback(&client, &AssumeOnline, &cache, &chain(), 0, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(response.profile, "backup");
+        assert_eq!(response.provider, LlmProvider::Gemini);
+        assert_eq!(response.content, "answer from backup");
+        assert_eq!(response.failed_profiles, vec![types::PRIMARY_PROFILE]);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_returns_last_error_when_all_fail() {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        let client = ScriptedClient::new(vec![
+            (types::PRIMARY_PROFILE, unavailable()),
+            ("backup", LlmError::AuthFailed),
+        ]);
+
+        let error = ask_with_fallback(&client, &AssumeOnline, &cache, &chain(), 0, 0)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error, LlmError::AuthFailed);
+        assert_eq!(client.attempts(), vec![types::PRIMARY_PROFILE, "backup"]);
+    }
+
+    /// Reaches only the hosts whose URL contains the given text.
+    struct HostProber(&'static str);
+
+    impl Prober for HostProber {
+        async fn probe(&self, url: &str) -> bool {
+            url.contains(self.0)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_offline_primary_falls_back() {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        let client = ScriptedClient::new(vec![]);
+
+        let response =
+            ask_with_fallback(&client, &HostProber("googleapis"), &cache, &chain(), 0, 0)
+                .await
+                .unwrap();
+
+        assert_eq!(response.profile, "backup");
+        assert_eq!(response.failed_profiles, vec![types::PRIMARY_PROFILE]);
+        assert_eq!(client.attempts(), vec!["backup"]);
+    }
+
+    #[tokio::test]
+    async fn test_every_profile_offline_sends_nothing() {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        let client = ScriptedClient::new(vec![]);
+
+        let error = ask_with_fallback(&client, &HostProber("unreachable"), &cache, &chain(), 0, 0)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error, LlmError::Network("offline".to_string()));
+        assert!(client.attempts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fatal_error_skips_fallback() {
+        let db = Db::in_memory().await.unwrap();
+        let cache = ResponseCache::new(db.pool().clone());
+        let client = ScriptedClient::new(vec![(
+            types::PRIMARY_PROFILE,
+            LlmError::BadRequest("Context length exceeded".to_string()),
+        )]);
+
+        let error = ask_with_fallback(&client, &AssumeOnline, &cache, &chain(), 0, 0)
+            .await
+            .unwrap_err();
+
+        assert!(!error.is_retryable());
+        assert_eq!(client.attempts(), vec![types::PRIMARY_PROFILE]);
+    }
+}
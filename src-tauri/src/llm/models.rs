@@ -0,0 +1,384 @@
+//! Model aliases and favorites for the launcher's model switcher.
+//!
+//! IDs like `gemini-2.0-flash-thinking-exp-01-21` are hard to read in a
+//! dropdown, so `llm.model_aliases` maps a model ID to a display name and
+//! `llm.favorite_models` lists the models shown first. Both are cosmetic:
+//! requests always carry the real ID from `llm.model`.
+//!
+//! `list_models` returns every model with its alias, display name and
+//! favorite flag, favorites first. `settings-changed` carries
+//! `model_names`, the display names of the configured, profile, favorite
+//! and aliased models, so the dropdown can label them without listing
+//! models again.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const models = await invoke<ListedModel[]>('list_models');
+//! // [{ id: 'gpt-4o', display_name: 'Daily driver', alias: 'Daily driver', favorite: true, ... }, ...]
+//! await invoke('set_model_alias', { model: 'gpt-4o', alias: 'Daily driver' }); // '' removes it
+//! const favorite = await invoke<boolean>('toggle_favorite_model', { model: 'gpt-4o' });
+//! await listen<SettingsChanged>('settings-changed', ({ payload }) => {
+//!   label.textContent = payload.model_names[payload.settings.llm.model];
+//! });
+//! ```
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tauri::State;
+
+use super::types::ModelInfo;
+use crate::settings::{LlmSettings, SettingsManager};
+
+/// Longest alias accepted, in characters.
+pub const MAX_ALIAS_CHARS: usize = 64;
+
+/// A model from `list_models` with its alias and favorite flag.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListedModel {
+    #[serde(flatten)]
+    pub info: ModelInfo,
+    /// Alias from `llm.model_aliases`
+    pub alias: Option<String>,
+    /// The alias, else the provider's name for the model, else its ID
+    pub display_name: String,
+    /// Whether the model is in `llm.favorite_models`
+    pub favorite: bool,
+}
+
+/// The name to show for `model`: its alias, or the ID itself.
+pub fn display_name(llm: &LlmSettings, model: &str) -> String {
+    llm.model_aliases
+        .get(model)
+        .cloned()
+        .unwrap_or_else(|| model.to_string())
+}
+
+/// Display names of every model the launcher may show without listing
+/// models: the configured model, the title model, profile models,
+/// favorites and aliased models.
+pub fn display_names(llm: &LlmSettings) -> HashMap<String, String> {
+    std::iter::once(&llm.model)
+        .chain(&llm.title_model)
+        .chain(llm.profiles.iter().map(|profile| &profile.model))
+        .chain(&llm.favorite_models)
+        .chain(llm.model_aliases.keys())
+        .filter(|model| !model.is_empty())
+        .map(|model| (model.clone(), display_name(llm, model)))
+        .collect()
+}
+
+/// Add aliases and favorite flags to a provider's model list.
+///
+/// Favorites come first, in the order they were favorited; the other
+/// models keep the provider's order.
+pub fn merge(models: Vec<ModelInfo>, llm: &LlmSettings) -> Vec<ListedModel> {
+    let mut listed: Vec<_> = models
+        .into_iter()
+        .map(|info| {
+            let alias = llm.model_aliases.get(&info.id).cloned();
+            let display_name = alias
+                .clone()
+                .or_else(|| info.name.clone())
+                .unwrap_or_else(|| info.id.clone());
+            ListedModel {
+                favorite: llm.favorite_models.contains(&info.id),
+                info,
+                alias,
+                display_name,
+            }
+        })
+        .collect();
+    listed.sort_by_key(|model| {
+        llm.favorite_models
+            .iter()
+            .position(|id| *id == model.info.id)
+            .unwrap_or(usize::MAX)
+    });
+    listed
+}
+
+/// Check a model ID and alias before saving them.
+///
+/// # Returns
+///
+/// * `Ok(())` - The alias can be saved (or removed, if blank)
+/// * `Err(String)` - The model ID is blank or the alias is too long
+pub fn validate_alias(model: &str, alias: &str) -> Result<(), String> {
+    if model.trim().is_empty() {
+        return Err("Model ID is empty".to_string());
+    }
+    if alias.trim().chars().count() > MAX_ALIAS_CHARS {
+        return Err(format!(
+            "Alias is longer than {} characters",
+            MAX_ALIAS_CHARS
+        ));
+    }
+    Ok(())
+}
+
+/// Set or remove (with a blank alias, or one equal to the ID) the alias of
+/// `model`.
+pub fn set_alias(llm: &mut LlmSettings, model: &str, alias: &str) {
+    let alias = alias.trim();
+    if alias.is_empty() || alias == model {
+        llm.model_aliases.remove(model);
+    } else {
+        llm.model_aliases
+            .insert(model.to_string(), alias.to_string());
+    }
+}
+
+/// Add `model` to the favorites, or remove it if it's there.
+///
+/// # Returns
+///
+/// Whether the model is a favorite now.
+pub fn toggle_favorite(llm: &mut LlmSettings, model: &str) -> bool {
+    match llm.favorite_models.iter().position(|id| id == model) {
+        Some(index) => {
+            llm.favorite_models.remove(index);
+            false
+        }
+        None => {
+            llm.favorite_models.push(model.to_string());
+            true
+        }
+    }
+}
+
+/// Change the settings current when the update runs, so a save in
+/// progress isn't undone.
+async fn update_llm(
+    settings_manager: &SettingsManager,
+    change: impl FnOnce(&mut LlmSettings),
+) -> Result<(), String> {
+    settings_manager
+        .update(|settings| {
+            change(&mut settings.llm);
+            Ok(())
+        })
+        .await
+        .map(|_| ())
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Give a model a display name.
+///
+/// # Arguments
+///
+/// * `model` - Model ID
+/// * `alias` - Display name; blank removes the alias
+///
+/// # Returns
+///
+/// * `Ok(())` - Saved; `settings-changed` carries the new name
+/// * `Err(String)` - The model ID is blank, the alias too long, or saving
+///   failed
+#[tauri::command]
+pub async fn set_model_alias(
+    settings_manager: State<'_, SettingsManager>,
+    model: String,
+    alias: String,
+) -> Result<(), String> {
+    validate_alias(&model, &alias)?;
+    update_llm(&settings_manager, |llm| set_alias(llm, &model, &alias)).await
+}
+
+/// Add a model to the favorites, or remove it.
+///
+/// # Returns
+///
+/// * `Ok(bool)` - Whether the model is a favorite now
+/// * `Err(String)` - The model ID is blank or saving failed
+#[tauri::command]
+pub async fn toggle_favorite_model(
+    settings_manager: State<'_, SettingsManager>,
+    model: String,
+) -> Result<bool, String> {
+    validate_alias(&model, "")?;
+    let mut favorite = false;
+    update_llm(&settings_manager, |llm| {
+        favorite = toggle_favorite(llm, &model)
+    })
+    .await?;
+    Ok(favorite)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::LlmProfile;
+
+    fn model(id: &str, name: Option<&str>) -> ModelInfo {
+        ModelInfo {
+            id: id.to_string(),
+            name: name.map(str::to_string),
+            context_length: None,
+            pricing: None,
+        }
+    }
+
+    fn settings() -> LlmSettings {
+        let mut llm = LlmSettings {
+            model: "gemini-2.0-flash-thinking-exp-01-21".to_string(),
+            ..LlmSettings::default()
+        };
+        set_alias(&mut llm, "gemini-2.0-flash-thinking-exp-01-21", "Thinking");
+        llm
+    }
+
+    fn ids(listed: &[ListedModel]) -> Vec<&str> {
+        listed.iter().map(|model| model.info.id.as_str()).collect()
+    }
+
+    // ===== Merge =====
+
+    #[test]
+    fn test_merge_puts_favorites_first_in_favorite_order() {
+        let mut llm = settings();
+        toggle_favorite(&mut llm, "c");
+        toggle_favorite(&mut llm, "a");
+
+        let listed = merge(
+            vec![
+                model("a", None),
+                model("b", None),
+                model("c", None),
+                model("d", None),
+            ],
+            &llm,
+        );
+
+        assert_eq!(ids(&listed), ["c", "a", "b", "d"]);
+        assert!(listed[0].favorite && listed[1].favorite);
+        assert!(!listed[2].favorite);
+    }
+
+    #[test]
+    fn test_merge_display_name_prefers_alias_then_provider_name() {
+        let listed = merge(
+            vec![
+                model(
+                    "gemini-2.0-flash-thinking-exp-01-21",
+                    Some("Gemini Thinking"),
+                ),
+                model("gemini-2.0-flash", Some("Gemini 2.0 Flash")),
+                model("local-model", None),
+            ],
+            &settings(),
+        );
+
+        assert_eq!(listed[0].alias.as_deref(), Some("Thinking"));
+        assert_eq!(listed[0].display_name, "Thinking");
+        assert_eq!(listed[1].alias, None);
+        assert_eq!(listed[1].display_name, "Gemini 2.0 Flash");
+        assert_eq!(listed[2].display_name, "local-model");
+    }
+
+    #[test]
+    fn test_listed_model_shape() {
+        let listed = merge(vec![model("gpt-4o", None)], &LlmSettings::default());
+
+        assert_eq!(
+            serde_json::to_value(&listed[0]).unwrap(),
+            serde_json::json!({
+                "id": "gpt-4o",
+                "name": null,
+                "context_length": null,
+                "pricing": null,
+                "alias": null,
+                "display_name": "gpt-4o",
+                "favorite": false,
+            })
+        );
+    }
+
+    // ===== Aliases =====
+
+    #[test]
+    fn test_display_name_resolves_alias() {
+        let llm = settings();
+
+        assert_eq!(
+            display_name(&llm, "gemini-2.0-flash-thinking-exp-01-21"),
+            "Thinking"
+        );
+        assert_eq!(display_name(&llm, "gpt-4o"), "gpt-4o");
+    }
+
+    #[test]
+    fn test_blank_or_identical_alias_removes_it() {
+        let mut llm = settings();
+        set_alias(&mut llm, "gemini-2.0-flash-thinking-exp-01-21", "  ");
+        assert!(llm.model_aliases.is_empty());
+
+        set_alias(&mut llm, "gpt-4o", " Daily ");
+        assert_eq!(llm.model_aliases["gpt-4o"], "Daily");
+        set_alias(&mut llm, "gpt-4o", "gpt-4o");
+        assert!(llm.model_aliases.is_empty());
+    }
+
+    #[test]
+    fn test_validate_alias() {
+        assert!(validate_alias("gpt-4o", "Daily").is_ok());
+        assert!(validate_alias("gpt-4o", "").is_ok());
+        assert_eq!(
+            validate_alias(" ", "Daily"),
+            Err("Model ID is empty".to_string())
+        );
+        assert!(validate_alias("gpt-4o", &"x".repeat(MAX_ALIAS_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_display_names_cover_configured_models() {
+        let mut llm = settings();
+        llm.title_model = Some("gemini-2.0-flash-lite".to_string());
+        llm.profiles.push(LlmProfile {
+            name: "backup".to_string(),
+            provider: llm.provider.clone(),
+            api_key: String::new(),
+            model: "gpt-4o".to_string(),
+            base_url: None,
+            preset: None,
+            azure: None,
+            extra_headers: None,
+            auth_header_name: None,
+        });
+        toggle_favorite(&mut llm, "claude-sonnet-4-5");
+        set_alias(&mut llm, "gpt-4o", "Backup");
+
+        let names = display_names(&llm);
+
+        assert_eq!(names.len(), 4);
+        assert_eq!(names["gemini-2.0-flash-thinking-exp-01-21"], "Thinking");
+        assert_eq!(names["gemini-2.0-flash-lite"], "gemini-2.0-flash-lite");
+        assert_eq!(names["gpt-4o"], "Backup");
+        assert_eq!(names["claude-sonnet-4-5"], "claude-sonnet-4-5");
+    }
+
+    #[test]
+    fn test_aliases_do_not_change_requests() {
+        let llm = settings();
+
+        let request = crate::llm::types::LlmRequest::from_settings(&llm, Vec::new());
+
+        assert_eq!(request.model, "gemini-2.0-flash-thinking-exp-01-21");
+    }
+
+    // ===== Favorites =====
+
+    #[test]
+    fn test_toggle_favorite() {
+        let mut llm = LlmSettings::default();
+
+        assert!(toggle_favorite(&mut llm, "gpt-4o"));
+        assert_eq!(llm.favorite_models, vec!["gpt-4o".to_string()]);
+        assert!(!toggle_favorite(&mut llm, "gpt-4o"));
+        assert!(llm.favorite_models.is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+//! OpenAI chat completions wire format.
+//!
+//! Also used for the `Custom` provider, which targets OpenAI-compatible
+//! endpoints (Ollama, LM Studio, ...) through `base_url`, or a vendor
+//! through one of the [`presets`](super::presets). Headers, including any
+//! `extra_headers`, come from [`headers::build`].
+
+use serde_json::{json, Value};
+
+use super::error::{error_message, LlmError};
+use super::headers;
+use super::types::{ChatMessage, Completion, HttpRequest, LlmRequest, ModelInfo, TokenUsage};
+
+/// Default base URL for the OpenAI API.
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Build a non-streaming chat completion request.
+///
+/// The bearer token is omitted when no API key is configured so local
+/// endpoints without authentication keep working.
+pub fn build_request(request: &LlmRequest) -> HttpRequest {
+    build_request_with_default(request, DEFAULT_BASE_URL)
+}
+
+/// Build a chat completion request for an OpenAI-compatible service.
+///
+/// `default_base_url` is used when the request has no `base_url` override.
+pub fn build_request_with_default(request: &LlmRequest, default_base_url: &str) -> HttpRequest {
+    let base_url = resolve_base_url(request, default_base_url);
+
+    let mut messages = Vec::with_capacity(request.messages.len() + 1);
+    if !request.system_prompt.is_empty() {
+        messages.push(json!({ "role": "system", "content": request.system_prompt }));
+    }
+    for message in &request.messages {
+        messages.push(json!({ "role": message.role, "content": content(message) }));
+    }
+
+    HttpRequest {
+        url: format!("{}/chat/completions", base_url),
+        headers: headers::build(request),
+        body: json!({
+            "model": request.model,
+            "messages": messages,
+            "temperature": request.temperature,
+            "max_tokens": request.max_tokens,
+        }),
+    }
+}
+
+/// A message's content: its text, or text and image parts when it has
+/// images.
+fn content(message: &ChatMessage) -> Value {
+    if message.images.is_empty() {
+        return json!(message.content);
+    }
+    let mut parts = vec![json!({ "type": "text", "text": message.content })];
+    parts.extend(
+        message
+            .images
+            .iter()
+            .map(|image| json!({ "type": "image_url", "image_url": { "url": image.data_url() } })),
+    );
+    Value::Array(parts)
+}
+
+/// Build a `GET /models` request for an OpenAI-compatible service.
+pub fn build_models_request(request: &LlmRequest, default_base_url: &str) -> HttpRequest {
+    HttpRequest {
+        url: format!("{}/models", resolve_base_url(request, default_base_url)),
+        headers: headers::build(request),
+        body: Value::Null,
+    }
+}
+
+/// Parse a `/models` response (`{ "data": [{ "id": ... }] }`).
+pub fn parse_models(body: &Value) -> Result<Vec<ModelInfo>, LlmError> {
+    let data = body["data"]
+        .as_array()
+        .ok_or_else(|| LlmError::Internal("Unexpected models response".to_string()))?;
+
+    Ok(data
+        .iter()
+        .filter_map(|model| model["id"].as_str())
+        .map(|id| ModelInfo {
+            id: id.to_string(),
+            name: None,
+            context_length: None,
+            pricing: None,
+        })
+        .collect())
+}
+
+fn resolve_base_url<'a>(request: &'a LlmRequest, default_base_url: &'a str) -> &'a str {
+    request
+        .base_url
+        .as_deref()
+        .filter(|url| !url.is_empty())
+        .unwrap_or(default_base_url)
+        .trim_end_matches('/')
+}
+
+/// Extract the generated text and token usage from a chat completion response.
+pub fn parse_response(body: &Value) -> Result<Completion, LlmError> {
+    let choice = &body["choices"][0];
+    let content = match choice["message"]["content"].as_str() {
+        Some(content) => content,
+        None if choice["finish_reason"] == "content_filter" => {
+            return Err(LlmError::ContentBlocked("content_filter".to_string()))
+        }
+        None => {
+            return Err(LlmError::Internal(
+                "Unexpected response format from OpenAI".to_string(),
+            ))
+        }
+    };
+
+    let usage = &body["usage"];
+    let usage = match (
+        usage["prompt_tokens"].as_u64(),
+        usage["completion_tokens"].as_u64(),
+    ) {
+        (Some(prompt), Some(completion)) => Some(TokenUsage {
+            prompt_tokens: prompt as u32,
+            completion_tokens: completion as u32,
+        }),
+        _ => None,
+    };
+
+    Ok(Completion {
+        content: content.to_string(),
+        usage,
+        cost_usd: None,
+    })
+}
+
+/// Map an OpenAI error response.
+///
+/// Content policy rejections arrive as 400s with a dedicated error code.
+pub fn parse_error(status: u16, body: &Value, retry_after_secs: Option<u64>) -> LlmError {
+    let message = error_message(status, body);
+    match body["error"]["code"].as_str() {
+        Some("content_policy_violation" | "content_filter") => LlmError::ContentBlocked(message),
+        _ => LlmError::from_status(status, message, retry_after_secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{ChatMessage, ChatRole, ImagePart};
+    use crate::settings::{LlmProvider, LlmSettings};
+
+    fn request(base_url: Option<&str>, api_key: &str) -> LlmRequest {
+        let settings = LlmSettings {
+            provider: LlmProvider::OpenAI,
+            api_key: api_key.to_string(),
+            model: "gpt-4o".to_string(),
+            base_url: base_url.map(str::to_string),
+            ..LlmSettings::default()
+        };
+        LlmRequest::from_settings(
+            &settings,
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: "Hi".to_string(),
+                images: Vec::new(),
+            }],
+        )
+    }
+
+    #[test]
+    fn test_build_request_uses_default_base_url() {
+        let http = build_request(&request(None, "sk-test"));
+
+        assert_eq!(http.url, "https://api.openai.com/v1/chat/completions");
+        assert_eq!(http.header("authorization"), Some("Bearer sk-test"));
+        assert_eq!(http.body["model"], "gpt-4o");
+        assert_eq!(http.body["messages"][0]["role"], "system");
+        assert_eq!(http.body["messages"][1]["content"], "Hi");
+    }
+
+    #[test]
+    fn test_build_request_custom_base_url_without_key() {
+        let http = build_request(&request(Some("http://localhost:11434/v1/"), ""));
+
+        assert_eq!(http.url, "http://localhost:11434/v1/chat/completions");
+        assert!(http.header("authorization").is_none());
+    }
+
+    #[test]
+    fn test_build_request_sends_images_as_parts() {
+        let mut request = request(None, "sk-test");
+        request.messages[0].images.push(ImagePart {
+            mime: "image/png".to_string(),
+            data: "iVBORw0K".to_string(),
+        });
+
+        let http = build_request(&request);
+        let content = &http.body["messages"][1]["content"];
+
+        assert_eq!(content[0], json!({ "type": "text", "text": "Hi" }));
+        assert_eq!(content[1]["type"], "image_url");
+        assert_eq!(
+            content[1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0K"
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let body = json!({
+            "choices": [{ "message": { "content": "Hello!" } }],
+            "usage": { "prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15 }
+        });
+        let completion = parse_response(&body).unwrap();
+
+        assert_eq!(completion.content, "Hello!");
+        assert_eq!(
+            completion.usage,
+            Some(TokenUsage {
+                prompt_tokens: 12,
+                completion_tokens: 3
+            })
+        );
+        assert!(parse_response(&json!({})).is_err());
+    }
+
+    #[test]
+    fn test_parse_response_without_usage() {
+        let body = json!({ "choices": [{ "message": { "content": "Hello!" } }] });
+        assert!(parse_response(&body).unwrap().usage.is_none());
+    }
+
+    #[test]
+    fn test_parse_response_content_filter() {
+        let body = json!({ "choices": [{ "message": {}, "finish_reason": "content_filter" }] });
+        assert!(matches!(
+            parse_response(&body),
+            Err(LlmError::ContentBlocked(_))
+        ));
+    }
+
+    #[test]
+    fn test_models_request_and_parse() {
+        let http = build_models_request(&request(None, "sk-test"), DEFAULT_BASE_URL);
+        assert_eq!(http.url, "https://api.openai.com/v1/models");
+        assert_eq!(http.header("authorization"), Some("Bearer sk-test"));
+
+        let body =
+            json!({ "data": [{ "id": "gpt-4o", "object": "model" }, { "id": "gpt-4o-mini" }] });
+        let ids: Vec<String> = parse_models(&body)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec!["gpt-4o", "gpt-4o-mini"]);
+    }
+
+    // ===== Error Mapping Tests =====
+
+    #[test]
+    fn test_parse_error_401() {
+        let body = json!({ "error": { "message": "Incorrect API key provided", "code": "invalid_api_key" } });
+        assert_eq!(parse_error(401, &body, None), LlmError::AuthFailed);
+    }
+
+    #[test]
+    fn test_parse_error_429() {
+        let body =
+            json!({ "error": { "message": "Rate limit reached", "code": "rate_limit_exceeded" } });
+        assert_eq!(
+            parse_error(429, &body, Some(12)),
+            LlmError::RateLimited {
+                retry_after_secs: Some(12)
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_error_400() {
+        let body = json!({ "error": { "message": "Invalid model", "code": null } });
+        assert_eq!(
+            parse_error(400, &body, None),
+            LlmError::BadRequest("Invalid model".to_string())
+        );
+
+        let blocked =
+            json!({ "error": { "message": "Rejected", "code": "content_policy_violation" } });
+        assert_eq!(
+            parse_error(400, &blocked, None),
+            LlmError::ContentBlocked("Rejected".to_string())
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! OpenRouter wire format.
+//!
+//! OpenRouter speaks the OpenAI chat completions protocol, so requests and
+//! responses reuse [`openai`](super::openai). Differences:
+//!
+//! - Attribution headers (`HTTP-Referer`, `X-Title`) identify the app
+//! - `usage.include` asks for the billed cost, reported as `usage.cost`
+//! - Model IDs are `vendor/model` (e.g. `anthropic/claude-sonnet-4.5`)
+//! - `/models` includes per-token pricing as decimal strings
+
+use serde_json::{json, Value};
+
+use super::error::LlmError;
+use super::openai;
+use super::types::{Completion, HttpRequest, LlmRequest, ModelInfo};
+use crate::settings::ModelPrice;
+
+/// Default base URL for the OpenRouter API.
+pub const DEFAULT_BASE_URL: &str = "https://openrouter.ai/api/v1";
+
+/// Sent as `HTTP-Referer` so requests are attributed to the app.
+pub const APP_URL: &str = "https://github.com/LokeshShelva/qwik-ask";
+
+/// Sent as `X-Title`.
+pub const APP_TITLE: &str = "Qwik Ask";
+
+/// Build a non-streaming chat completion request.
+pub fn build_request(request: &LlmRequest) -> HttpRequest {
+    let mut http = openai::build_request_with_default(request, DEFAULT_BASE_URL);
+    http.headers.extend(attribution_headers());
+    http.body["usage"] = json!({ "include": true });
+    http
+}
+
+/// Build a `GET /models` request.
+pub fn build_models_request(request: &LlmRequest) -> HttpRequest {
+    let mut http = openai::build_models_request(request, DEFAULT_BASE_URL);
+    http.headers.extend(attribution_headers());
+    http
+}
+
+/// Extract the reply, token usage, and billed cost.
+pub fn parse_response(body: &Value) -> Result<Completion, LlmError> {
+    let mut completion = openai::parse_response(body)?;
+    completion.cost_usd = body["usage"]["cost"].as_f64();
+    Ok(completion)
+}
+
+/// Parse the models list including pricing.
+///
+/// **Quirk:** prices are USD per single token as decimal strings
+/// (`"0.000003"`); they are converted to USD per million tokens. Router
+/// models report `"-1"` (variable price) and get no pricing.
+pub fn parse_models(body: &Value) -> Result<Vec<ModelInfo>, LlmError> {
+    let data = body["data"]
+        .as_array()
+        .ok_or_else(|| LlmError::Internal("Unexpected models response".to_string()))?;
+
+    Ok(data
+        .iter()
+        .filter_map(|model| {
+            let pricing = &model["pricing"];
+            let pricing = match (
+                per_million(&pricing["prompt"]),
+                per_million(&pricing["completion"]),
+            ) {
+                (Some(input), Some(output)) => Some(ModelPrice {
+                    input_per_million: input,
+                    output_per_million: output,
+                }),
+                _ => None,
+            };
+
+            Some(ModelInfo {
+                id: model["id"].as_str()?.to_string(),
+                name: model["name"].as_str().map(str::to_string),
+                context_length: model["context_length"].as_u64().map(|n| n as u32),
+                pricing,
+            })
+        })
+        .collect())
+}
+
+/// Map an OpenRouter error response (same shape as OpenAI's).
+pub fn parse_error(status: u16, body: &Value, retry_after_secs: Option<u64>) -> LlmError {
+    openai::parse_error(status, body, retry_after_secs)
+}
+
+fn attribution_headers() -> [(String, String); 2] {
+    [
+        ("HTTP-Referer".to_string(), APP_URL.to_string()),
+        ("X-Title".to_string(), APP_TITLE.to_string()),
+    ]
+}
+
+fn per_million(price_per_token: &Value) -> Option<f64> {
+    let price: f64 = price_per_token.as_str()?.parse().ok()?;
+    (price >= 0.0).then_some(price * 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{ChatMessage, ChatRole, TokenUsage};
+    use crate::settings::{LlmProvider, LlmSettings};
+
+    fn request() -> LlmRequest {
+        let settings = LlmSettings {
+            provider: LlmProvider::OpenRouter,
+            api_key: "or-key".to_string(),
+            model: "anthropic/claude-sonnet-4.5".to_string(),
+            ..LlmSettings::default()
+        };
+        LlmRequest::from_settings(
+            &settings,
+            vec![ChatMessage {
+                role: ChatRole::User,
+                content: "Hi".to_string(),
+                images: Vec::new(),
+            }],
+        )
+    }
+
+    #[test]
+    fn test_build_request_defaults_and_headers() {
+        let http = build_request(&request());
+
+        assert_eq!(http.url, "https://openrouter.ai/api/v1/chat/completions");
+        assert_eq!(http.header("authorization"), Some("Bearer or-key"));
+        assert_eq!(http.header("http-referer"), Some(APP_URL));
+        assert_eq!(http.header("x-title"), Some(APP_TITLE));
+        assert_eq!(http.body["model"], "anthropic/claude-sonnet-4.5");
+        assert_eq!(http.body["usage"]["include"], true);
+    }
+
+    #[test]
+    fn test_parse_response_with_cost() {
+        let body = json!({
+            "id": "gen-123",
+            "model": "anthropic/claude-sonnet-4.5",
+            "choices": [{ "message": { "role": "assistant", "content": "Hello!" }, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 14, "completion_tokens": 3, "total_tokens": 17, "cost": 0.000087 }
+        });
+
+        let completion = parse_response(&body).unwrap();
+
+        assert_eq!(completion.content, "Hello!");
+        assert_eq!(
+            completion.usage,
+            Some(TokenUsage {
+                prompt_tokens: 14,
+                completion_tokens: 3
+            })
+        );
+        assert_eq!(completion.cost_usd, Some(0.000087));
+    }
+
+    #[test]
+    fn test_parse_models_with_pricing() {
+        let body = json!({ "data": [
+            {
+                "id": "anthropic/claude-sonnet-4.5",
+                "name": "Anthropic: Claude Sonnet 4.5",
+                "context_length": 1000000,
+                "pricing": { "prompt": "0.000003", "completion": "0.000015", "request": "0" }
+            },
+            { "id": "openrouter/auto", "name": "Auto Router", "pricing": { "prompt": "-1", "completion": "-1" } },
+            { "id": "vendor/no-pricing" }
+        ] });
+
+        let models = parse_models(&body).unwrap();
+
+        assert_eq!(models.len(), 3);
+        assert_eq!(models[0].id, "anthropic/claude-sonnet-4.5");
+        assert_eq!(models[0].context_length, Some(1_000_000));
+        let price = models[0].pricing.unwrap();
+        assert!((price.input_per_million - 3.0).abs() < 1e-9);
+        assert!((price.output_per_million - 15.0).abs() < 1e-9);
+        assert!(models[1].pricing.is_none());
+        assert!(models[2].pricing.is_none());
+    }
+}
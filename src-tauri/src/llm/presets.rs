@@ -0,0 +1,356 @@
+//! Vendor presets for the `custom` provider.
+//!
+//! Groq, Mistral, Together and DeepSeek all speak the OpenAI chat
+//! completions protocol, so instead of a provider variant each they are
+//! entries in [`PRESETS`]: `provider: "custom"` plus `preset: "groq"`
+//! selects one. A preset supplies the base URL, the header carrying the API
+//! key, a default model, and quirks the request has to work around. Adding
+//! a vendor is a table entry plus tests.
+//!
+//! `base_url` and `auth_header_name` in settings still win over the
+//! preset's values, so a preset can be pointed at a proxy.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const presets = await invoke<ProviderPreset[]>('list_provider_presets');
+//! const groq = presets.find((p) => p.id === 'groq')!;
+//! settings.llm = { ...settings.llm, provider: 'custom', preset: groq.id, model: groq.default_model };
+//! await invoke('update_settings', { settings });
+//! ```
+
+use serde::Serialize;
+
+use super::error::LlmError;
+use super::openai;
+use super::types::{ChatMessage, ChatRole, HttpRequest, LlmRequest};
+use crate::settings::LlmProvider;
+
+/// An OpenAI-compatible vendor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProviderPreset {
+    /// Stored in `llm.preset`
+    pub id: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// API root; `/chat/completions` and `/models` are appended
+    pub base_url: &'static str,
+    /// Header carrying `Bearer <api key>`
+    pub auth_header: &'static str,
+    /// Model suggested when the preset is picked
+    pub default_model: &'static str,
+    /// Whether the API accepts a `system` message; without one the system
+    /// prompt is folded into the first user message
+    pub system_role: bool,
+}
+
+/// Every known preset.
+pub const PRESETS: &[ProviderPreset] = &[
+    ProviderPreset {
+        id: "groq",
+        name: "Groq",
+        base_url: "https://api.groq.com/openai/v1",
+        auth_header: "Authorization",
+        default_model: "llama-3.3-70b-versatile",
+        system_role: true,
+    },
+    ProviderPreset {
+        id: "mistral",
+        name: "Mistral",
+        base_url: "https://api.mistral.ai/v1",
+        auth_header: "Authorization",
+        default_model: "mistral-large-latest",
+        system_role: true,
+    },
+    ProviderPreset {
+        id: "together",
+        name: "Together AI",
+        base_url: "https://api.together.xyz/v1",
+        auth_header: "Authorization",
+        default_model: "meta-llama/Llama-3.3-70B-Instruct-Turbo",
+        system_role: true,
+    },
+    ProviderPreset {
+        id: "deepseek",
+        name: "DeepSeek",
+        base_url: "https://api.deepseek.com/v1",
+        auth_header: "Authorization",
+        default_model: "deepseek-chat",
+        system_role: true,
+    },
+];
+
+/// Look up a preset by ID.
+pub fn find(id: &str) -> Option<&'static ProviderPreset> {
+    PRESETS.iter().find(|preset| preset.id == id)
+}
+
+/// The preset a request uses, if it's a `Custom` request with one.
+///
+/// # Returns
+///
+/// * `Ok(Some(preset))` - The request's preset
+/// * `Ok(None)` - Not a `Custom` request, or no preset selected
+/// * `Err(LlmError)` - `preset` names no known preset
+pub fn for_request(request: &LlmRequest) -> Result<Option<&'static ProviderPreset>, LlmError> {
+    match request.preset.as_deref().filter(|id| !id.is_empty()) {
+        Some(id) if request.provider == LlmProvider::Custom => find(id)
+            .map(Some)
+            .ok_or_else(|| LlmError::BadRequest(format!("Unknown provider preset '{}'", id))),
+        _ => Ok(None),
+    }
+}
+
+/// Build a chat completion request for a `Custom` request, through its
+/// preset when it has one.
+pub fn build_request(request: &LlmRequest) -> HttpRequest {
+    match for_request(request).ok().flatten() {
+        Some(preset) => {
+            openai::build_request_with_default(&resolve(request, preset), preset.base_url)
+        }
+        None => openai::build_request(request),
+    }
+}
+
+/// Build a `GET /models` request for a `Custom` request.
+pub fn build_models_request(request: &LlmRequest) -> HttpRequest {
+    match for_request(request).ok().flatten() {
+        Some(preset) => openai::build_models_request(&resolve(request, preset), preset.base_url),
+        None => openai::build_models_request(request, openai::DEFAULT_BASE_URL),
+    }
+}
+
+/// Apply a preset's defaults and quirks to a request.
+fn resolve(request: &LlmRequest, preset: &ProviderPreset) -> LlmRequest {
+    let mut resolved = request.clone();
+    if resolved.model.is_empty() {
+        resolved.model = preset.default_model.to_string();
+    }
+    if resolved
+        .auth_header_name
+        .as_deref()
+        .is_none_or(str::is_empty)
+    {
+        resolved.auth_header_name = Some(preset.auth_header.to_string());
+    }
+    if !preset.system_role {
+        fold_system_prompt(&mut resolved);
+    }
+    resolved
+}
+
+/// Move the system prompt to the start of the first user message.
+fn fold_system_prompt(request: &mut LlmRequest) {
+    let system_prompt = std::mem::take(&mut request.system_prompt);
+    if system_prompt.is_empty() {
+        return;
+    }
+    match request
+        .messages
+        .iter_mut()
+        .find(|message| message.role == ChatRole::User)
+    {
+        Some(message) => message.content = format!("{}\n\n{}", system_prompt, message.content),
+        None => request.messages.insert(
+            0,
+            ChatMessage {
+                role: ChatRole::User,
+                content: system_prompt,
+                images: Vec::new(),
+            },
+        ),
+    }
+}
+
+/// List the vendor presets selectable for the `custom` provider.
+#[tauri::command]
+pub fn list_provider_presets() -> Vec<ProviderPreset> {
+    PRESETS.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::LlmSettings;
+    use std::collections::HashMap;
+
+    fn request(preset: &str) -> LlmRequest {
+        let settings = LlmSettings {
+            provider: LlmProvider::Custom,
+            preset: Some(preset.to_string()),
+            api_key: "key-123".to_string(),
+            model: String::new(),
+            system_prompt: "Be brief.".to_string(),
+            ..LlmSettings::default()
+        };
+        LlmRequest::from_settings(
+            &settings,
+            vec![
+                ChatMessage {
+                    role: ChatRole::User,
+                    content: "Hi".to_string(),
+                    images: Vec::new(),
+                },
+                ChatMessage {
+                    role: ChatRole::Assistant,
+                    content: "Hello!".to_string(),
+                    images: Vec::new(),
+                },
+                ChatMessage {
+                    role: ChatRole::User,
+                    content: "What's new?".to_string(),
+                    images: Vec::new(),
+                },
+            ],
+        )
+    }
+
+    /// A vendor without a `system` role.
+    const NO_SYSTEM_ROLE: ProviderPreset = ProviderPreset {
+        id: "nosystem",
+        name: "No System",
+        base_url: "https://api.example.com/v1",
+        auth_header: "X-Api-Key",
+        default_model: "plain-1",
+        system_role: false,
+    };
+
+    // ===== Table =====
+
+    #[test]
+    fn test_preset_ids_are_unique() {
+        for (i, preset) in PRESETS.iter().enumerate() {
+            assert!(
+                PRESETS[i + 1..].iter().all(|other| other.id != preset.id),
+                "duplicate preset '{}'",
+                preset.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_each_preset_builds_its_urls_and_headers() {
+        let expected = [
+            ("groq", "https://api.groq.com/openai/v1"),
+            ("mistral", "https://api.mistral.ai/v1"),
+            ("together", "https://api.together.xyz/v1"),
+            ("deepseek", "https://api.deepseek.com/v1"),
+        ];
+        assert_eq!(PRESETS.len(), expected.len());
+
+        for (id, base_url) in expected {
+            let preset = find(id).unwrap();
+            let http = build_request(&request(id));
+            assert_eq!(http.url, format!("{}/chat/completions", base_url), "{}", id);
+            assert_eq!(
+                http.header("authorization"),
+                Some("Bearer key-123"),
+                "{}",
+                id
+            );
+            assert_eq!(http.body["model"], preset.default_model, "{}", id);
+            assert_eq!(http.body["messages"][0]["role"], "system", "{}", id);
+
+            let models = build_models_request(&request(id));
+            assert_eq!(models.url, format!("{}/models", base_url), "{}", id);
+            assert_eq!(
+                models.header("authorization"),
+                Some("Bearer key-123"),
+                "{}",
+                id
+            );
+        }
+    }
+
+    // ===== Resolution =====
+
+    #[test]
+    fn test_settings_override_preset() {
+        let mut request = request("groq");
+        request.base_url = Some("http://localhost:4000/v1".to_string());
+        request.auth_header_name = Some("X-LiteLLM-Key".to_string());
+        request.model = "llama-3.1-8b-instant".to_string();
+        request.extra_headers = Some(HashMap::from([(
+            "X-Org-Id".to_string(),
+            "acme".to_string(),
+        )]));
+
+        let http = build_request(&request);
+
+        assert_eq!(http.url, "http://localhost:4000/v1/chat/completions");
+        assert_eq!(http.header("x-litellm-key"), Some("Bearer key-123"));
+        assert_eq!(http.header("x-org-id"), Some("acme"));
+        assert_eq!(http.body["model"], "llama-3.1-8b-instant");
+    }
+
+    #[test]
+    fn test_preset_auth_header() {
+        let http = openai::build_request_with_default(
+            &resolve(&request("nosystem"), &NO_SYSTEM_ROLE),
+            NO_SYSTEM_ROLE.base_url,
+        );
+
+        assert_eq!(http.header("x-api-key"), Some("Bearer key-123"));
+        assert!(http.header("authorization").is_none());
+    }
+
+    #[test]
+    fn test_no_system_role_folds_into_first_user_message() {
+        let resolved = resolve(&request("nosystem"), &NO_SYSTEM_ROLE);
+        let http = openai::build_request_with_default(&resolved, NO_SYSTEM_ROLE.base_url);
+
+        let messages = http.body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["role"], "user");
+        assert_eq!(messages[0]["content"], "Be brief.\n\nHi");
+        assert_eq!(messages[2]["content"], "What's new?");
+    }
+
+    #[test]
+    fn test_fold_without_user_message() {
+        let mut request = request("nosystem");
+        request.messages.clear();
+
+        let resolved = resolve(&request, &NO_SYSTEM_ROLE);
+
+        assert!(resolved.system_prompt.is_empty());
+        assert_eq!(resolved.messages.len(), 1);
+        assert_eq!(resolved.messages[0].role, ChatRole::User);
+        assert_eq!(resolved.messages[0].content, "Be brief.");
+    }
+
+    #[test]
+    fn test_unknown_preset_is_bad_request() {
+        assert_eq!(
+            for_request(&request("nope")),
+            Err(LlmError::BadRequest(
+                "Unknown provider preset 'nope'".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_preset_ignored_for_other_providers() {
+        let mut request = request("groq");
+        request.provider = LlmProvider::OpenAI;
+
+        assert_eq!(for_request(&request), Ok(None));
+    }
+
+    #[test]
+    fn test_list_shape() {
+        let listed = serde_json::to_value(list_provider_presets()).unwrap();
+
+        assert_eq!(
+            listed[0],
+            serde_json::json!({
+                "id": "groq",
+                "name": "Groq",
+                "base_url": "https://api.groq.com/openai/v1",
+                "auth_header": "Authorization",
+                "default_model": "llama-3.3-70b-versatile",
+                "system_role": true,
+            })
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Built-in model price table for usage cost estimates.
+//!
+//! Prices are USD per million tokens and only meant for rough estimates.
+//! Users can override or extend the table through `llm.model_prices`.
+
+use std::collections::HashMap;
+
+use crate::settings::ModelPrice;
+
+/// Built-in prices as `(model id, input per 1M, output per 1M)`.
+///
+/// Dated or suffixed variants (e.g. `claude-haiku-4-5-20251001`) match the
+/// longest entry that is a prefix of the model ID.
+const BUILTIN_PRICES: &[(&str, f64, f64)] = &[
+    // OpenAI
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-5", 1.25, 10.00),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5-nano", 0.05, 0.40),
+    // Gemini
+    ("gemini-2.0-flash", 0.10, 0.40),
+    ("gemini-2.5-flash", 0.30, 2.50),
+    ("gemini-2.5-flash-lite", 0.10, 0.40),
+    ("gemini-2.5-pro", 1.25, 10.00),
+    // Anthropic
+    ("claude-haiku-4-5", 1.00, 5.00),
+    ("claude-sonnet-4-5", 3.00, 15.00),
+    ("claude-opus-4-5", 5.00, 25.00),
+];
+
+/// Find the price for a model.
+///
+/// Settings overrides win over the built-in table. Returns `None` for
+/// unknown models so callers can report the cost as unknown rather than zero.
+///
+/// # Arguments
+///
+/// * `model` - Model identifier as sent to the provider
+/// * `overrides` - `llm.model_prices` from settings
+pub fn price_for(model: &str, overrides: &HashMap<String, ModelPrice>) -> Option<ModelPrice> {
+    if let Some(price) = overrides.get(model) {
+        return Some(*price);
+    }
+
+    BUILTIN_PRICES
+        .iter()
+        .filter(|(id, _, _)| model == *id || model.starts_with(&format!("{}-", id)))
+        .max_by_key(|(id, _, _)| id.len())
+        .map(|(_, input, output)| ModelPrice {
+            input_per_million: *input,
+            output_per_million: *output,
+        })
+}
+
+/// Estimate the cost of a number of tokens in USD.
+///
+/// Returns `None` when the model has no known price.
+pub fn estimate_cost(
+    model: &str,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    overrides: &HashMap<String, ModelPrice>,
+) -> Option<f64> {
+    price_for(model, overrides).map(|price| {
+        (prompt_tokens as f64 * price.input_per_million
+            + completion_tokens as f64 * price.output_per_million)
+            / 1_000_000.0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_exact_builtin_match() {
+        let price = price_for("gpt-4o", &HashMap::new()).unwrap();
+        assert!(approx(price.input_per_million, 2.50));
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        // "gpt-4o-mini" must not be priced as "gpt-4o"
+        let mini = price_for("gpt-4o-mini", &HashMap::new()).unwrap();
+        assert!(approx(mini.input_per_million, 0.15));
+
+        let dated = price_for("claude-haiku-4-5-20251001", &HashMap::new()).unwrap();
+        assert!(approx(dated.output_per_million, 5.00));
+    }
+
+    #[test]
+    fn test_prefix_requires_separator() {
+        // "gpt-50" is not a variant of "gpt-5"
+        assert!(price_for("gpt-50", &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_override_wins_over_builtin() {
+        let overrides = HashMap::from([(
+            "gpt-4o".to_string(),
+            ModelPrice {
+                input_per_million: 1.0,
+                output_per_million: 1.0,
+            },
+        )]);
+        let cost = estimate_cost("gpt-4o", 1_000_000, 0, &overrides).unwrap();
+        assert!(approx(cost, 1.0));
+    }
+
+    #[test]
+    fn test_estimate_cost_math() {
+        // 2000 * 2.50 / 1M + 500 * 10.00 / 1M = 0.005 + 0.005
+        let cost = estimate_cost("gpt-4o", 2_000, 500, &HashMap::new()).unwrap();
+        assert!(approx(cost, 0.01));
+    }
+
+    #[test]
+    fn test_unknown_model_has_no_cost() {
+        assert!(estimate_cost("llama3:8b", 1_000, 1_000, &HashMap::new()).is_none());
+    }
+}
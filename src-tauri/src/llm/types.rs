@@ -0,0 +1,352 @@
+//! LLM request and response types.
+//!
+//! Provider-neutral shapes shared by every client. Provider modules translate
+//! an [`LlmRequest`] into their wire format and back into text.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::language;
+use crate::settings::{AzureSettings, LlmProfile, LlmProvider, LlmSettings, ModelPrice};
+
+/// Sampling temperature used for chat requests (matches the frontend services).
+pub const DEFAULT_TEMPERATURE: f32 = 0.7;
+
+/// Upper bound on generated tokens for chat requests.
+pub const DEFAULT_MAX_TOKENS: u32 = 8192;
+
+/// Profile name reported for the top-level provider settings.
+pub const PRIMARY_PROFILE: &str = "primary";
+
+/// Model name reported for answers computed locally.
+pub const LOCAL_MODEL: &str = "local";
+
+/// Author of a chat message.
+///
+/// Serializes to lowercase strings: `"user"`, `"assistant"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    /// Message typed by the user
+    User,
+    /// Reply generated by the model
+    Assistant,
+}
+
+/// An image sent with a message, for models that accept images.
+///
+/// The `CapturedImage` returned by `capture_screen_region` deserializes as
+/// one; its other fields are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImagePart {
+    /// MIME type, like `image/png`
+    pub mime: String,
+    /// The image, base64-encoded
+    pub data: String,
+}
+
+impl ImagePart {
+    /// The image as a `data:` URL.
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime, self.data)
+    }
+}
+
+/// A single message in a conversation sent to the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Who wrote the message
+    pub role: ChatRole,
+    /// Message text (markdown)
+    pub content: String,
+    /// Images sent after the text (user messages only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImagePart>,
+}
+
+/// A fully resolved request ready to be sent to a provider.
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    /// Name of the profile the request was built from
+    pub profile: String,
+    /// Provider that should answer
+    pub provider: LlmProvider,
+    /// Model identifier
+    pub model: String,
+    /// API key (never part of the cache key)
+    pub api_key: String,
+    /// Base URL override for OpenAI-compatible endpoints
+    pub base_url: Option<String>,
+    /// Vendor preset of a `Custom` request
+    pub preset: Option<String>,
+    /// Azure deployment, for the `AzureOpenAI` provider
+    pub azure: Option<AzureSettings>,
+    /// Extra headers for OpenAI-compatible endpoints
+    pub extra_headers: Option<HashMap<String, String>>,
+    /// Header carrying the API key instead of `Authorization`
+    pub auth_header_name: Option<String>,
+    /// System instructions
+    pub system_prompt: String,
+    /// Conversation so far, oldest first
+    pub messages: Vec<ChatMessage>,
+    /// Sampling temperature
+    pub temperature: f32,
+    /// Maximum tokens to generate
+    pub max_tokens: u32,
+}
+
+impl LlmRequest {
+    /// Build a request from the user's LLM settings and a conversation.
+    pub fn from_settings(settings: &LlmSettings, messages: Vec<ChatMessage>) -> Self {
+        Self {
+            profile: PRIMARY_PROFILE.to_string(),
+            provider: settings.provider.clone(),
+            model: settings.model.clone(),
+            api_key: settings.api_key.clone(),
+            base_url: settings.base_url.clone(),
+            preset: settings.preset.clone(),
+            azure: settings.azure.clone(),
+            extra_headers: settings.extra_headers.clone(),
+            auth_header_name: settings.auth_header_name.clone(),
+            system_prompt: language::apply(
+                &settings.system_prompt,
+                settings.response_language.as_deref(),
+            ),
+            messages,
+            temperature: DEFAULT_TEMPERATURE,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+
+    /// Build a request for a named profile, sharing the settings' system prompt
+    /// and response language.
+    pub fn from_profile(
+        settings: &LlmSettings,
+        profile: &LlmProfile,
+        messages: Vec<ChatMessage>,
+    ) -> Self {
+        Self {
+            profile: profile.name.clone(),
+            provider: profile.provider.clone(),
+            model: profile.model.clone(),
+            api_key: profile.api_key.clone(),
+            base_url: profile.base_url.clone(),
+            preset: profile.preset.clone(),
+            azure: profile.azure.clone(),
+            extra_headers: profile.extra_headers.clone(),
+            auth_header_name: profile.auth_header_name.clone(),
+            system_prompt: language::apply(
+                &settings.system_prompt,
+                settings.response_language.as_deref(),
+            ),
+            messages,
+            temperature: DEFAULT_TEMPERATURE,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+
+    /// Build the primary request followed by one request per fallback profile.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<LlmRequest>)` - Requests in the order they should be tried
+    /// * `Err(String)` - A fallback name doesn't match any profile
+    pub fn chain_from_settings(
+        settings: &LlmSettings,
+        messages: Vec<ChatMessage>,
+    ) -> Result<Vec<Self>, String> {
+        let mut chain = vec![Self::from_settings(settings, messages.clone())];
+
+        for name in &settings.fallback_profiles {
+            let profile = settings
+                .profiles
+                .iter()
+                .find(|profile| &profile.name == name)
+                .ok_or_else(|| format!("Unknown fallback profile: {}", name))?;
+            chain.push(Self::from_profile(settings, profile, messages.clone()));
+        }
+
+        Ok(chain)
+    }
+}
+
+/// Token counts reported by a provider for one request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// Tokens in the prompt (system prompt + conversation)
+    pub prompt_tokens: u32,
+    /// Tokens generated in the reply
+    pub completion_tokens: u32,
+}
+
+/// Parsed reply from a provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    /// Generated text (markdown)
+    pub content: String,
+    /// Token counts, when the provider reports them
+    pub usage: Option<TokenUsage>,
+    /// Cost in USD, when the provider reports it (OpenRouter)
+    pub cost_usd: Option<f64>,
+}
+
+/// Where an answer came from.
+///
+/// Serializes to lowercase strings: `"model"`, `"local"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseSource {
+    /// Generated by a provider (possibly served from the cache)
+    #[default]
+    Model,
+    /// Computed locally (arithmetic, unit conversion) without a network call
+    Local,
+}
+
+/// Response returned to the frontend by `ask_llm`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LlmResponse {
+    /// Generated text (markdown)
+    pub content: String,
+    /// Provider that produced the answer
+    pub provider: LlmProvider,
+    /// Model that produced the answer
+    pub model: String,
+    /// Profile that produced the answer ([`PRIMARY_PROFILE`] unless a fallback was used)
+    pub profile: String,
+    /// Profiles that failed before `profile` answered, in the order they were tried
+    pub failed_profiles: Vec<String>,
+    /// Whether a model or a local handler produced the answer
+    pub source: ResponseSource,
+    /// `true` when served from the response cache without a network call
+    pub cached: bool,
+    /// Token counts for this request (`None` for cached replies)
+    pub usage: Option<TokenUsage>,
+    /// Provider-reported cost in USD (`None` when not reported or cached)
+    pub cost_usd: Option<f64>,
+    /// ID of the stored assistant message, when a conversation was given
+    pub message_id: Option<String>,
+}
+
+/// A model offered by a provider, as returned by `list_models`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelInfo {
+    /// Identifier to put in `llm.model`
+    pub id: String,
+    /// Human-readable name, when the provider has one
+    pub name: Option<String>,
+    /// Maximum context window in tokens, when known
+    pub context_length: Option<u32>,
+    /// Price per million tokens, when the provider publishes it
+    pub pricing: Option<ModelPrice>,
+}
+
+/// Provider-specific HTTP request produced by a provider module.
+///
+/// Kept as plain data so request construction can be tested without a network.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    /// Fully qualified endpoint URL
+    pub url: String,
+    /// Extra headers (`Content-Type: application/json` is always added)
+    pub headers: Vec<(String, String)>,
+    /// JSON request body; `Null` means the request is a `GET`
+    pub body: serde_json::Value,
+}
+
+impl HttpRequest {
+    /// Look up a header value by case-insensitive name.
+    #[cfg(test)]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> LlmProfile {
+        LlmProfile {
+            name: name.to_string(),
+            provider: LlmProvider::Gemini,
+            api_key: "gemini-key".to_string(),
+            model: "gemini-2.0-flash".to_string(),
+            base_url: None,
+            preset: None,
+            azure: None,
+            extra_headers: None,
+            auth_header_name: None,
+        }
+    }
+
+    fn message() -> Vec<ChatMessage> {
+        vec![ChatMessage {
+            role: ChatRole::User,
+            content: "Hi".to_string(),
+            images: Vec::new(),
+        }]
+    }
+
+    #[test]
+    fn test_chain_without_fallbacks_is_primary_only() {
+        let chain = LlmRequest::chain_from_settings(&LlmSettings::default(), message()).unwrap();
+
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].profile, PRIMARY_PROFILE);
+    }
+
+    #[test]
+    fn test_chain_follows_fallback_order() {
+        let settings = LlmSettings {
+            provider: LlmProvider::OpenAI,
+            system_prompt: "Be brief".to_string(),
+            profiles: vec![profile("a"), profile("b")],
+            fallback_profiles: vec!["b".to_string(), "a".to_string()],
+            ..LlmSettings::default()
+        };
+
+        let chain = LlmRequest::chain_from_settings(&settings, message()).unwrap();
+        let names: Vec<&str> = chain.iter().map(|r| r.profile.as_str()).collect();
+
+        assert_eq!(names, vec![PRIMARY_PROFILE, "b", "a"]);
+        assert_eq!(chain[1].provider, LlmProvider::Gemini);
+        assert_eq!(chain[1].system_prompt, "Be brief");
+        assert_eq!(chain[1].messages, message());
+    }
+
+    #[test]
+    fn test_chain_appends_response_language() {
+        let settings = LlmSettings {
+            system_prompt: "Be brief".to_string(),
+            response_language: Some("pt-BR".to_string()),
+            profiles: vec![profile("a")],
+            fallback_profiles: vec!["a".to_string()],
+            ..LlmSettings::default()
+        };
+
+        let chain = LlmRequest::chain_from_settings(&settings, message()).unwrap();
+
+        for request in &chain {
+            assert_eq!(
+                request.system_prompt,
+                "Be brief\n\nAlways respond in Brazilian Portuguese."
+            );
+        }
+    }
+
+    #[test]
+    fn test_chain_rejects_unknown_profile() {
+        let settings = LlmSettings {
+            fallback_profiles: vec!["missing".to_string()],
+            ..LlmSettings::default()
+        };
+
+        let err = LlmRequest::chain_from_settings(&settings, message()).unwrap_err();
+        assert!(err.contains("missing"));
+    }
+}
@@ -0,0 +1,452 @@
+//! Token usage recording and aggregation.
+//!
+//! Every answered request that reports token counts is written twice:
+//! onto its `messages` row (`prompt_tokens`, `completion_tokens`) and into
+//! the `usage_daily` rollup keyed by UTC day, provider, and model. Both
+//! writes happen in the same transaction as the message insert. The message
+//! row also records the provider and model that actually answered, which
+//! differs from the settings when a fallback profile was used.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::{SqliteConnection, SqlitePool};
+
+use super::pricing::estimate_cost;
+use super::types::LlmResponse;
+use crate::db;
+use crate::history::store::latest_user_message;
+use crate::history::{crypto, search};
+use crate::settings::ModelPrice;
+
+/// Token totals for one provider/model on one day.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyUsage {
+    /// UTC day as `YYYY-MM-DD`
+    pub day: String,
+    /// Provider name (e.g. `"openai"`)
+    pub provider: String,
+    /// Model identifier
+    pub model: String,
+    /// Sum of prompt tokens
+    pub prompt_tokens: u64,
+    /// Sum of completion tokens
+    pub completion_tokens: u64,
+    /// Number of requests
+    pub request_count: u64,
+    /// Estimated cost in USD, `None` when the model has no known price
+    pub cost_usd: Option<f64>,
+}
+
+/// Result of `get_usage_stats`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageStats {
+    /// Number of days covered, ending today (UTC)
+    pub range_days: u32,
+    /// Per-day, per-model rows, oldest day first
+    pub days: Vec<DailyUsage>,
+    /// Sum of prompt tokens over the range
+    pub total_prompt_tokens: u64,
+    /// Sum of completion tokens over the range
+    pub total_completion_tokens: u64,
+    /// Sum of known costs; models without a price contribute nothing
+    pub estimated_cost_usd: f64,
+}
+
+/// Store an assistant reply and its token usage atomically.
+///
+/// Inserts the message (linked to the latest user message as its parent)
+/// with how long it took, bumps the conversation's `updated_at`, and adds
+/// the token counts to `usage_daily` in a single transaction.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `conversation_id` - Conversation the reply belongs to
+/// * `response` - The reply to store
+/// * `duration_ms` - How long the reply took, stored as `duration_ms`
+/// * `now_ms` - Current Unix timestamp (ms)
+///
+/// # Returns
+///
+/// The ID of the inserted message.
+pub async fn record_reply(
+    pool: &SqlitePool,
+    conversation_id: &str,
+    response: &LlmResponse,
+    duration_ms: u32,
+    now_ms: i64,
+) -> Result<String, String> {
+    let message_id = uuid::Uuid::now_v7().to_string();
+    let usage = response.usage;
+
+    let mut tx = db::begin_write(pool)
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let parent_message_id = latest_user_message(&mut tx, conversation_id)
+        .await
+        .map_err(|e| format!("Failed to find parent message: {}", e))?;
+
+    let stored = crypto::seal_new(&response.content).map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO messages (id, conversation_id, role, content, created_at, parent_message_id, provider, model, prompt_tokens, completion_tokens, duration_ms)
+         VALUES (?, ?, 'assistant', ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&message_id)
+    .bind(conversation_id)
+    .bind(&stored)
+    .bind(now_ms)
+    .bind(parent_message_id)
+    .bind(response.provider.as_str())
+    .bind(&response.model)
+    .bind(usage.map(|u| i64::from(u.prompt_tokens)))
+    .bind(usage.map(|u| i64::from(u.completion_tokens)))
+    .bind(i64::from(duration_ms))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to insert message: {}", e))?;
+    search::index_sealed(&mut tx, &message_id, &stored, &response.content)
+        .await
+        .map_err(|e| format!("Failed to index message: {}", e))?;
+
+    sqlx::query("UPDATE conversations SET updated_at = ?, archived = 0 WHERE id = ?")
+        .bind(now_ms)
+        .bind(conversation_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update conversation: {}", e))?;
+
+    add_daily_usage(&mut tx, response, now_ms)
+        .await
+        .map_err(|e| format!("Failed to record usage: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(message_id)
+}
+
+/// Add a reply's token counts to the `usage_daily` rollup.
+///
+/// Does nothing when the provider reported no usage. Runs on the caller's
+/// connection so it can share the message insert's transaction.
+pub async fn add_daily_usage(
+    conn: &mut SqliteConnection,
+    response: &LlmResponse,
+    now_ms: i64,
+) -> Result<(), sqlx::Error> {
+    let Some(usage) = response.usage else {
+        return Ok(());
+    };
+
+    sqlx::query(
+        "INSERT INTO usage_daily (day, provider, model, prompt_tokens, completion_tokens, request_count)
+         VALUES (date(? / 1000, 'unixepoch'), ?, ?, ?, ?, 1)
+         ON CONFLICT(day, provider, model) DO UPDATE SET
+             prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+             completion_tokens = completion_tokens + excluded.completion_tokens,
+             request_count = request_count + 1",
+    )
+    .bind(now_ms)
+    .bind(response.provider.as_str())
+    .bind(&response.model)
+    .bind(i64::from(usage.prompt_tokens))
+    .bind(i64::from(usage.completion_tokens))
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Aggregate usage for the last `range_days` days (including today, UTC).
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `range_days` - Window size; `0` is treated as `1` (today only)
+/// * `now_ms` - Current Unix timestamp (ms)
+/// * `price_overrides` - `llm.model_prices` from settings
+pub async fn usage_stats(
+    pool: &SqlitePool,
+    range_days: u32,
+    now_ms: i64,
+    price_overrides: &HashMap<String, ModelPrice>,
+) -> Result<UsageStats, String> {
+    let range_days = range_days.max(1);
+    let modifier = format!("-{} days", range_days - 1);
+
+    let rows: Vec<(String, String, String, i64, i64, i64)> = sqlx::query_as(
+        "SELECT day, provider, model, prompt_tokens, completion_tokens, request_count
+         FROM usage_daily
+         WHERE day >= date(? / 1000, 'unixepoch', ?)
+         ORDER BY day ASC, provider ASC, model ASC",
+    )
+    .bind(now_ms)
+    .bind(modifier)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load usage: {}", e))?;
+
+    let days: Vec<DailyUsage> = rows
+        .into_iter()
+        .map(
+            |(day, provider, model, prompt, completion, count)| DailyUsage {
+                cost_usd: estimate_cost(&model, prompt as u64, completion as u64, price_overrides),
+                day,
+                provider,
+                model,
+                prompt_tokens: prompt as u64,
+                completion_tokens: completion as u64,
+                request_count: count as u64,
+            },
+        )
+        .collect();
+
+    Ok(UsageStats {
+        range_days,
+        total_prompt_tokens: days.iter().map(|d| d.prompt_tokens).sum(),
+        total_completion_tokens: days.iter().map(|d| d.completion_tokens).sum(),
+        estimated_cost_usd: days.iter().filter_map(|d| d.cost_usd).sum(),
+        days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::llm::types::{ResponseSource, TokenUsage};
+    use crate::settings::LlmProvider;
+
+    /// 2025-01-10T12:00:00Z
+    const DAY_10: i64 = 1_736_510_400_000;
+    const DAY_MS: i64 = 86_400_000;
+    const LATENCY_MS: u32 = 1_200;
+
+    async fn seeded_db() -> Db {
+        let db = Db::in_memory().await.unwrap();
+        sqlx::query(
+            "INSERT INTO conversations (id, title, created_at, updated_at) VALUES ('c1', 'Test', 0, 0)",
+        )
+        .execute(db.pool())
+        .await
+        .unwrap();
+        db
+    }
+
+    fn reply(model: &str, usage: Option<TokenUsage>) -> LlmResponse {
+        LlmResponse {
+            content: "answer".to_string(),
+            provider: LlmProvider::OpenAI,
+            model: model.to_string(),
+            profile: "primary".to_string(),
+            failed_profiles: Vec::new(),
+            source: ResponseSource::Model,
+            cached: false,
+            usage,
+            cost_usd: None,
+            message_id: None,
+        }
+    }
+
+    fn tokens(prompt: u32, completion: u32) -> Option<TokenUsage> {
+        Some(TokenUsage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+        })
+    }
+
+    // ===== Recording Tests =====
+
+    #[tokio::test]
+    async fn test_record_reply_writes_message_and_usage() {
+        let db = seeded_db().await;
+
+        let id = record_reply(
+            db.pool(),
+            "c1",
+            &reply("gpt-4o", tokens(100, 20)),
+            LATENCY_MS,
+            DAY_10,
+        )
+        .await
+        .unwrap();
+
+        let message: (String, String, i64, i64, i64) = sqlx::query_as(
+            "SELECT provider, model, prompt_tokens, completion_tokens, duration_ms FROM messages WHERE id = ?",
+        )
+        .bind(&id)
+        .fetch_one(db.pool())
+        .await
+        .unwrap();
+        assert_eq!(
+            message,
+            ("openai".to_string(), "gpt-4o".to_string(), 100, 20, 1_200)
+        );
+
+        let updated: (i64,) =
+            sqlx::query_as("SELECT updated_at FROM conversations WHERE id = 'c1'")
+                .fetch_one(db.pool())
+                .await
+                .unwrap();
+        assert_eq!(updated.0, DAY_10);
+    }
+
+    #[tokio::test]
+    async fn test_record_reply_without_usage_skips_rollup() {
+        let db = seeded_db().await;
+        record_reply(db.pool(), "c1", &reply("gpt-4o", None), LATENCY_MS, DAY_10)
+            .await
+            .unwrap();
+
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM usage_daily")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(count.0, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_insert_leaves_no_usage() {
+        let db = seeded_db().await;
+
+        // Unknown conversation violates the foreign key, so nothing is written
+        let result = record_reply(
+            db.pool(),
+            "missing",
+            &reply("gpt-4o", tokens(1, 1)),
+            LATENCY_MS,
+            DAY_10,
+        )
+        .await;
+        assert!(result.is_err());
+
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM usage_daily")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(count.0, 0);
+    }
+
+    // ===== Aggregation Tests =====
+
+    #[tokio::test]
+    async fn test_usage_aggregates_per_day_and_model() {
+        let db = seeded_db().await;
+        let pool = db.pool();
+        record_reply(
+            pool,
+            "c1",
+            &reply("gpt-4o", tokens(100, 10)),
+            LATENCY_MS,
+            DAY_10,
+        )
+        .await
+        .unwrap();
+        record_reply(
+            pool,
+            "c1",
+            &reply("gpt-4o", tokens(50, 5)),
+            LATENCY_MS,
+            DAY_10 + 1_000,
+        )
+        .await
+        .unwrap();
+        record_reply(
+            pool,
+            "c1",
+            &reply("gpt-4o-mini", tokens(7, 3)),
+            LATENCY_MS,
+            DAY_10,
+        )
+        .await
+        .unwrap();
+        record_reply(
+            pool,
+            "c1",
+            &reply("gpt-4o", tokens(1, 1)),
+            LATENCY_MS,
+            DAY_10 - DAY_MS,
+        )
+        .await
+        .unwrap();
+
+        let stats = usage_stats(pool, 7, DAY_10, &HashMap::new()).await.unwrap();
+
+        assert_eq!(stats.days.len(), 3);
+        assert_eq!(stats.days[0].day, "2025-01-09");
+        let gpt4o_today = &stats.days[1];
+        assert_eq!(gpt4o_today.day, "2025-01-10");
+        assert_eq!(gpt4o_today.model, "gpt-4o");
+        assert_eq!(gpt4o_today.provider, "openai");
+        assert_eq!(gpt4o_today.prompt_tokens, 150);
+        assert_eq!(gpt4o_today.completion_tokens, 15);
+        assert_eq!(gpt4o_today.request_count, 2);
+        assert_eq!(stats.total_prompt_tokens, 158);
+        assert_eq!(stats.total_completion_tokens, 19);
+    }
+
+    #[tokio::test]
+    async fn test_usage_range_excludes_older_days() {
+        let db = seeded_db().await;
+        let pool = db.pool();
+        record_reply(
+            pool,
+            "c1",
+            &reply("gpt-4o", tokens(1, 1)),
+            LATENCY_MS,
+            DAY_10,
+        )
+        .await
+        .unwrap();
+        record_reply(
+            pool,
+            "c1",
+            &reply("gpt-4o", tokens(1, 1)),
+            LATENCY_MS,
+            DAY_10 - 3 * DAY_MS,
+        )
+        .await
+        .unwrap();
+
+        let today_only = usage_stats(pool, 1, DAY_10, &HashMap::new()).await.unwrap();
+        assert_eq!(today_only.days.len(), 1);
+
+        let four_days = usage_stats(pool, 4, DAY_10, &HashMap::new()).await.unwrap();
+        assert_eq!(four_days.days.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_usage_cost_with_unknown_model() {
+        let db = seeded_db().await;
+        let pool = db.pool();
+        record_reply(
+            pool,
+            "c1",
+            &reply("gpt-4o", tokens(2_000, 500)),
+            LATENCY_MS,
+            DAY_10,
+        )
+        .await
+        .unwrap();
+        record_reply(
+            pool,
+            "c1",
+            &reply("llama3:8b", tokens(9_999, 9_999)),
+            LATENCY_MS,
+            DAY_10,
+        )
+        .await
+        .unwrap();
+
+        let stats = usage_stats(pool, 1, DAY_10, &HashMap::new()).await.unwrap();
+
+        let known = stats.days.iter().find(|d| d.model == "gpt-4o").unwrap();
+        let unknown = stats.days.iter().find(|d| d.model == "llama3:8b").unwrap();
+        assert!((known.cost_usd.unwrap() - 0.01).abs() < 1e-9);
+        assert!(unknown.cost_usd.is_none());
+        assert!((stats.estimated_cost_usd - 0.01).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,238 @@
+//! Logging to a file the user can send with a bug report.
+//!
+//! Events from `tracing` are written to `{app_data_dir}/logs`, one
+//! `qwik-ask.<YYYY-MM-DD>.log` file per (UTC) day with a week of files kept,
+//! by `tracing-appender` (see [`subscriber`]). The level comes
+//! from `general.log_level` at startup and follows the setting when it is
+//! changed (see [`apply_level`]).
+//!
+//! API keys and prompts are never logged as-is: log calls use
+//! [`redact::secret`] and [`redact::summary`], and every line is scrubbed
+//! of key-like strings before it is written.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! // Settings → Diagnostics
+//! const tail = await invoke<string[]>('get_recent_logs', { lines: 200 });
+//! await invoke('open_logs_dir');
+//! ```
+
+pub mod redact;
+mod subscriber;
+
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+
+use crate::paths;
+use crate::settings::LogLevel;
+pub use subscriber::LogFilter;
+use subscriber::Scrubbed;
+
+/// Directory under the app data dir holding the log files.
+pub const LOG_DIR: &str = "logs";
+
+/// Log file names are `<FILE_PREFIX>.<YYYY-MM-DD>.<FILE_SUFFIX>`.
+pub const FILE_PREFIX: &str = "qwik-ask";
+
+/// See [`FILE_PREFIX`].
+pub const FILE_SUFFIX: &str = "log";
+
+/// Log files kept, including the current one.
+pub const KEEP_FILES: usize = 7;
+
+/// Most lines `get_recent_logs` returns.
+pub const MAX_RECENT_LINES: u32 = 5000;
+
+/// The directory the log files are written to.
+pub fn logs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(paths::data_dir(app)?.join(LOG_DIR))
+}
+
+/// Install the file logger as the global `tracing` subscriber.
+///
+/// If the log file can't be opened, events still go to stderr in debug
+/// builds.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle, for the app data dir
+/// * `level` - The `general.log_level` setting
+///
+/// # Returns
+///
+/// The filter to manage as state, so the level can be changed later.
+pub fn init(app: &AppHandle, level: LogLevel) -> LogFilter {
+    let (filter_layer, filter) = LogFilter::new(level);
+    let file = logs_dir(app).and_then(|dir| {
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(FILE_PREFIX)
+            .filename_suffix(FILE_SUFFIX)
+            .max_log_files(KEEP_FILES)
+            .build(dir)
+            .map_err(|e| format!("Failed to open log file: {}", e))
+    });
+    let file = file.map_err(|e| eprintln!("{}", e)).ok();
+
+    let subscriber = Registry::default()
+        .with(filter_layer)
+        .with(file.map(|file| {
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Scrubbed(file))
+        }))
+        .with(cfg!(debug_assertions).then(|| {
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Scrubbed(std::io::stderr))
+        }));
+    if tracing::subscriber::set_global_default(subscriber).is_err() {
+        eprintln!("A tracing subscriber is already installed");
+    }
+    filter
+}
+
+/// Follow a change of `general.log_level`.
+pub fn apply_level(app: &AppHandle, level: LogLevel) {
+    let Some(filter) = app.try_state::<LogFilter>() else {
+        return;
+    };
+    if filter.level() != level {
+        filter.set(level);
+        tracing::info!(?level, "Log level changed");
+    }
+}
+
+/// The last `lines` lines across the log files, oldest first.
+pub fn tail(files: &[PathBuf], lines: usize) -> Vec<String> {
+    let mut tail: Vec<String> = Vec::new();
+    // Files are newest first; fill from the end of each
+    for path in files {
+        if tail.len() >= lines {
+            break;
+        }
+        let Ok(bytes) = std::fs::read(path) else {
+            continue;
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let wanted = lines - tail.len();
+        let mut older: Vec<String> = text
+            .lines()
+            .rev()
+            .take(wanted)
+            .map(str::to_string)
+            .collect();
+        older.reverse();
+        older.append(&mut tail);
+        tail = older;
+    }
+    tail
+}
+
+/// Open the log directory in the system file manager.
+///
+/// # Returns
+///
+/// * `Ok(())` - Directory opened
+/// * `Err(String)` - The path couldn't be resolved or opened
+#[tauri::command]
+pub async fn open_logs_dir(app: AppHandle) -> Result<(), String> {
+    let dir = logs_dir(&app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log dir: {}", e))?;
+    app.opener()
+        .open_path(dir.to_string_lossy().to_string(), None::<&str>)
+        .map_err(|e| format!("Failed to open log dir: {}", e))
+}
+
+/// Read the end of the log, for the diagnostics tab.
+///
+/// # Arguments
+///
+/// * `lines` - How many lines; capped at [`MAX_RECENT_LINES`]
+///
+/// # Returns
+///
+/// * `Ok(Vec<String>)` - The lines, oldest first; reaches back into earlier
+///   days' files when today's is shorter
+/// * `Err(String)` - The log directory couldn't be resolved
+#[tauri::command]
+pub async fn get_recent_logs(app: AppHandle, lines: u32) -> Result<Vec<String>, String> {
+    let dir = logs_dir(&app)?;
+    let lines = lines.min(MAX_RECENT_LINES) as usize;
+    tauri::async_runtime::spawn_blocking(move || recent_lines(&dir, lines))
+        .await
+        .map_err(|e| format!("Failed to read logs: {}", e))
+}
+
+/// The last `lines` lines of the log files in `dir`, oldest first.
+pub fn recent_lines(dir: &Path, lines: usize) -> Vec<String> {
+    tail(&log_files(dir), lines)
+}
+
+/// The log files in `dir`, newest first.
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", FILE_PREFIX);
+    let suffix = format!(".{}", FILE_SUFFIX);
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with(&prefix) && name.ends_with(&suffix))
+        .collect();
+    // Dates sort by name; newest first
+    names.sort_by(|a, b| b.cmp(a));
+    names
+        .into_iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, lines: &[&str]) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_tail_of_todays_file() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "qwik-ask.2024-03-01.log", &["a", "b", "c"]);
+
+        assert_eq!(recent_lines(dir.path(), 2), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_tail_reaches_into_earlier_days() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "qwik-ask.2024-03-01.log", &["old1", "old2"]);
+        write(dir.path(), "qwik-ask.2024-03-02.log", &["mid1", "mid2"]);
+        write(dir.path(), "qwik-ask.2024-03-03.log", &["new1"]);
+        write(dir.path(), "notes.txt", &["unrelated"]);
+
+        assert_eq!(
+            recent_lines(dir.path(), 4),
+            vec!["old2", "mid1", "mid2", "new1"]
+        );
+        assert_eq!(recent_lines(dir.path(), 100).len(), 5);
+    }
+
+    #[test]
+    fn test_tail_without_logs() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(recent_lines(&dir.path().join("missing"), 10).is_empty());
+        assert!(recent_lines(dir.path(), 0).is_empty());
+    }
+}
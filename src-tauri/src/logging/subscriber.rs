@@ -0,0 +1,216 @@
+//! The `tracing` subscriber that writes the log file.
+//!
+//! A `tracing-subscriber` registry with a reloadable [`Targets`] filter and
+//! `fmt` layers, one per line:
+//!
+//! ```text
+//! 2024-02-29T13:02:03.045123Z  WARN quick_assist_lib::llm: ask{model=gemini-2.0-flash}: Request failed error=...
+//! ```
+//!
+//! Events from this crate are filtered by the [`LogFilter`] level, which can
+//! be changed while the app runs; events from dependencies only at `warn`
+//! and above. Every line is scrubbed of key-like strings before it is
+//! written (see [`Scrubbed`]).
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use tracing::Level;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::{reload, Registry};
+
+use super::redact;
+use crate::settings::LogLevel;
+
+/// Target prefix of this crate's events.
+const CRATE_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+/// The filter layer [`LogFilter`] controls.
+pub type FilterLayer = reload::Layer<Targets, Registry>;
+
+/// The log level, shared between the subscriber and the settings.
+#[derive(Clone)]
+pub struct LogFilter {
+    handle: reload::Handle<Targets, Registry>,
+    level: Arc<Mutex<LogLevel>>,
+}
+
+impl LogFilter {
+    /// A filter at `level`, and the layer to install it with.
+    pub fn new(level: LogLevel) -> (FilterLayer, Self) {
+        let (layer, handle) = reload::Layer::new(targets(level));
+        let filter = Self {
+            handle,
+            level: Arc::new(Mutex::new(level)),
+        };
+        (layer, filter)
+    }
+
+    /// The current level.
+    pub fn level(&self) -> LogLevel {
+        *self.level.lock().unwrap()
+    }
+
+    /// Change the level; takes effect for the next event.
+    pub fn set(&self, level: LogLevel) {
+        if let Err(e) = self.handle.reload(targets(level)) {
+            tracing::error!(error = %e, "Failed to change the log level");
+            return;
+        }
+        *self.level.lock().unwrap() = level;
+    }
+}
+
+/// What is logged at the `general.log_level` setting `setting`.
+pub fn targets(setting: LogLevel) -> Targets {
+    let level = match setting {
+        LogLevel::Error => Level::ERROR,
+        LogLevel::Warn => Level::WARN,
+        LogLevel::Info => Level::INFO,
+        LogLevel::Debug => Level::DEBUG,
+    };
+    Targets::new()
+        .with_default(Level::WARN)
+        .with_target(CRATE_TARGET, level)
+}
+
+/// Scrubs what `M`'s writers write (see [`redact::scrub`]).
+///
+/// The `fmt` layer writes each event in one call, so each write is a
+/// whole line.
+pub struct Scrubbed<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Scrubbed<M> {
+    type Writer = ScrubbedWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ScrubbedWriter(self.0.make_writer())
+    }
+}
+
+/// A writer from [`Scrubbed`].
+pub struct ScrubbedWriter<W>(W);
+
+impl<W: Write> Write for ScrubbedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = redact::scrub(&String::from_utf8_lossy(buf));
+        self.0.write_all(line.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const TARGET: &str = "quick_assist_lib::llm";
+
+    /// Collects what the subscriber writes.
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Output {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn subscriber(layer: FilterLayer, output: &Output) -> impl tracing::Subscriber {
+        let output = output.clone();
+        Registry::default().with(layer).with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Scrubbed(move || output.clone())),
+        )
+    }
+
+    // ===== Filtering =====
+
+    #[test]
+    fn test_level_filter() {
+        assert!(targets(LogLevel::Info).would_enable(TARGET, &Level::INFO));
+        assert!(targets(LogLevel::Info).would_enable(TARGET, &Level::ERROR));
+        assert!(!targets(LogLevel::Info).would_enable(TARGET, &Level::DEBUG));
+        assert!(targets(LogLevel::Debug).would_enable(TARGET, &Level::DEBUG));
+        assert!(!targets(LogLevel::Debug).would_enable(TARGET, &Level::TRACE));
+        assert!(!targets(LogLevel::Error).would_enable(TARGET, &Level::WARN));
+    }
+
+    #[test]
+    fn test_dependencies_only_warn() {
+        assert!(!targets(LogLevel::Debug).would_enable("sqlx::query", &Level::INFO));
+        assert!(targets(LogLevel::Debug).would_enable("sqlx::query", &Level::WARN));
+    }
+
+    #[test]
+    fn test_filter_can_change() {
+        let output = Output::default();
+        let (layer, filter) = LogFilter::new(LogLevel::Warn);
+
+        tracing::subscriber::with_default(subscriber(layer, &output), || {
+            tracing::debug!("hidden at warn");
+            filter.set(LogLevel::Debug);
+            tracing::debug!("shown at debug");
+        });
+
+        assert_eq!(filter.level(), LogLevel::Debug);
+        let lines = output.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with("shown at debug"));
+    }
+
+    // ===== Output =====
+
+    #[test]
+    fn test_events_are_written_with_spans() {
+        let output = Output::default();
+        let (layer, _filter) = LogFilter::new(LogLevel::Info);
+
+        tracing::subscriber::with_default(subscriber(layer, &output), || {
+            let _span = tracing::info_span!("ask", model = "gpt-4o").entered();
+            tracing::info!(attempt = 2, "Sending request");
+            tracing::debug!("hidden at info");
+        });
+        tracing::info!("after the subscriber is gone");
+
+        let lines = output.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with(
+            "  INFO ask{model=\"gpt-4o\"}: quick_assist_lib::logging::subscriber::tests: Sending request attempt=2"
+        ));
+    }
+
+    #[test]
+    fn test_lines_are_scrubbed() {
+        let output = Output::default();
+        let (layer, _filter) = LogFilter::new(LogLevel::Info);
+
+        tracing::subscriber::with_default(subscriber(layer, &output), || {
+            tracing::warn!("Request failed: https://example.com/v1?key=AIzaSecret123");
+        });
+
+        let lines = output.lines();
+        assert!(lines[0].ends_with("Request failed: https://example.com/v1?key=[redacted]"));
+    }
+}
@@ -23,8 +23,170 @@
 //!     created_at INTEGER NOT NULL,  -- Unix timestamp (ms)
 //!     FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
 //! );
+//!
+//! -- llm_cache: cached LLM responses keyed by request hash
+//! CREATE TABLE llm_cache (
+//!     key TEXT PRIMARY KEY,         -- SHA-256 of the request
+//!     content TEXT NOT NULL,
+//!     created_at INTEGER NOT NULL   -- Unix timestamp (ms)
+//! );
+//!
+//! -- usage_daily: token totals per provider/model per UTC day
+//! CREATE TABLE usage_daily (
+//!     day TEXT NOT NULL,            -- 'YYYY-MM-DD'
+//!     provider TEXT NOT NULL,
+//!     model TEXT NOT NULL,
+//!     prompt_tokens INTEGER NOT NULL DEFAULT 0,
+//!     completion_tokens INTEGER NOT NULL DEFAULT 0,
+//!     request_count INTEGER NOT NULL DEFAULT 0,
+//!     PRIMARY KEY (day, provider, model)
+//! );
+//! ```
+//!
+//! Migration 3 also adds nullable `prompt_tokens` / `completion_tokens`
+//! columns to `messages`; migration 4 adds nullable `provider` / `model`
+//! columns recording which model actually answered; migration 5 adds a
+//! nullable `duration_ms` column with the provider's response time.
+//! Migration 6 adds `pinned` / `archived` flags (0 or 1) to `conversations`;
+//! migration 7 adds `auto_title` (1 until the user renames the conversation);
+//! migration 8 adds a nullable `deleted_at` timestamp for the trash;
+//! migration 9 adds a `starred` flag (0 or 1) to `messages`.
+//! Migration 10 adds nullable `parent_message_id` (the user message a reply
+//! answers) and `superseded_by` (the reply that regenerated it) to `messages`.
+//! Migration 11 adds conversation tags:
+//!
+//! ```sql
+//! CREATE TABLE tags (
+//!     id INTEGER PRIMARY KEY,
+//!     name TEXT NOT NULL UNIQUE     -- normalized: trimmed, lowercase
+//! );
+//!
+//! CREATE TABLE conversation_tags (
+//!     conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+//!     tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+//!     PRIMARY KEY (conversation_id, tag_id)
+//! );
+//! ```
+//!
+//! A trigger deletes a tag once its last `conversation_tags` row is gone.
+//! Migration 12 records files added to a message's prompt:
+//!
+//! ```sql
+//! CREATE TABLE attachments (
+//!     id TEXT PRIMARY KEY,
+//!     message_id TEXT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+//!     name TEXT NOT NULL,           -- file name without its directory
+//!     size INTEGER NOT NULL,        -- bytes on disk
+//!     line_count INTEGER NOT NULL,  -- lines included in the prompt
+//!     truncated INTEGER NOT NULL DEFAULT 0,
+//!     lossy INTEGER NOT NULL DEFAULT 0,
+//!     created_at INTEGER NOT NULL   -- Unix timestamp (ms)
+//! );
+//! ```
+//!
+//! Migration 13 adds the prompt library, seeded with the built-in default
+//! system prompt (id `default`):
+//!
+//! ```sql
+//! CREATE TABLE prompts (
+//!     id TEXT PRIMARY KEY,
+//!     name TEXT NOT NULL,
+//!     content TEXT NOT NULL,
+//!     is_builtin INTEGER NOT NULL DEFAULT 0,  -- 1 for prompts shipped with the app
+//!     created_at INTEGER NOT NULL,  -- Unix timestamp (ms)
+//!     updated_at INTEGER NOT NULL   -- Unix timestamp (ms)
+//! );
+//! ```
+//!
+//! Migration 14 adds the opt-in telemetry queue (see `telemetry`):
+//!
+//! ```sql
+//! CREATE TABLE telemetry_queue (
+//!     id INTEGER PRIMARY KEY AUTOINCREMENT,
+//!     event TEXT NOT NULL,          -- JSON of a TelemetryEvent
+//!     created_at INTEGER NOT NULL   -- Unix timestamp (ms)
+//! );
+//! ```
+//!
+//! Migration 15 adds nullable `branched_from_conversation_id` /
+//! `branched_from_message_id` to `conversations`, recording where a branch
+//! was copied from; a trigger clears both when the source is purged.
+//! Migration 16 adds a nullable `system_prompt_override` to `conversations`.
+//! Migration 17 adds unsent prompt drafts, one per conversation plus one
+//! with a NULL `conversation_id` for a new conversation:
+//!
+//! ```sql
+//! CREATE TABLE drafts (
+//!     conversation_id TEXT REFERENCES conversations(id) ON DELETE CASCADE,
+//!     text TEXT NOT NULL,
+//!     updated_at INTEGER NOT NULL   -- Unix timestamp (ms)
+//! );
+//! ```
+//!
+//! Migration 18 adds the submitted prompts recalled with the up arrow (see
+//! `history::prompt_history`):
+//!
+//! ```sql
+//! CREATE TABLE prompt_history (
+//!     id INTEGER PRIMARY KEY AUTOINCREMENT,
+//!     text TEXT NOT NULL,           -- sealed like message content
+//!     source TEXT NOT NULL,         -- 'typed', 'clipboard' or 'quick_action'
+//!     created_at INTEGER NOT NULL   -- Unix timestamp (ms)
+//! );
+//! ```
+//!
+//! Migration 19 adds the audit log of settings changes (see
+//! `settings::audit`):
+//!
+//! ```sql
+//! CREATE TABLE settings_audit (
+//!     id INTEGER PRIMARY KEY AUTOINCREMENT,
+//!     source TEXT NOT NULL,         -- 'ui'
+//!     revision INTEGER NOT NULL,    -- settings revision of the save
+//!     changes TEXT NOT NULL,        -- JSON [{ path, old, new }], secrets masked
+//!     created_at INTEGER NOT NULL   -- Unix timestamp (ms)
+//! );
 //! ```
 //!
+//! Migration 20 adds nullable `mime` / `data` columns to `attachments`
+//! holding images sent with a prompt, like screen captures; `data` is the
+//! base64 image, sealed like message content.
+//!
+//! Migration 21 adds full-text search over message content (see
+//! `history::search`). `message_search` holds the searchable text of each
+//! message and `messages_fts` is an FTS5 trigram index over it, so any
+//! substring of three or more characters can be matched:
+//!
+//! ```sql
+//! CREATE TABLE message_search (
+//!     id INTEGER PRIMARY KEY,       -- rowid in messages_fts
+//!     message_id TEXT NOT NULL UNIQUE,
+//!     content TEXT NOT NULL         -- plaintext, never an envelope
+//! );
+//!
+//! CREATE VIRTUAL TABLE messages_fts USING fts5(
+//!     content, content='message_search', content_rowid='id', tokenize='trigram'
+//! );
+//! ```
+//!
+//! Triggers keep both in step with `messages`: plaintext content is indexed
+//! when a message is inserted or its content changes, and a message's row
+//! goes when the message is deleted. Encrypted content is left to the app.
+//! Messages get their own `id` here rather than reusing their rowid, which
+//! `VACUUM` may renumber.
+//!
+//! # Down Migrations
+//!
+//! Every migration from version 2 on is followed by a `MigrationKind::Down`
+//! with the same version that undoes it: tables, indexes and triggers are
+//! dropped and added columns removed, in reverse order. Neither the plugin
+//! nor [`crate::db`] ever runs them; they document how to take a database
+//! back to an older schema by hand, and the tests check each one restores
+//! the schema before its Up.
+//!
+//! A database migrated by a newer version of the app is never opened; see
+//! [`crate::db::schema`].
+//!
 //! # Adding New Migrations
 //!
 //! To add a new migration:
@@ -32,31 +194,49 @@
 //! 1. Add a new `Migration` struct to the vector in `get_migrations()`
 //! 2. Increment the version number
 //! 3. Write the SQL for the migration
+//! 4. Add the `Down` migration undoing it right after it
 //!
 //! ```rust,ignore
 //! Migration {
-//!     version: 2,
+//!     version: 18,
 //!     description: "add_index_on_content",
 //!     sql: "CREATE INDEX idx_messages_content ON messages(content);",
 //!     kind: MigrationKind::Up,
-//! }
+//! },
+//! Migration {
+//!     version: 18,
+//!     description: "drop_index_on_content",
+//!     sql: "DROP INDEX IF EXISTS idx_messages_content;",
+//!     kind: MigrationKind::Down,
+//! },
 //! ```
 
 use tauri_plugin_sql::{Migration, MigrationKind};
 
+/// Newest schema version this build knows, the highest Up migration.
+pub fn latest_version() -> i64 {
+    get_migrations()
+        .iter()
+        .filter(|m| matches!(m.kind, MigrationKind::Up))
+        .map(|m| m.version)
+        .max()
+        .unwrap_or(0)
+}
+
 /// Get all database migrations.
 ///
-/// Returns migrations in order. Each migration runs only once,
-/// tracked by version number in the database.
+/// Returns migrations in order, each Up followed by the Down undoing it.
+/// Each Up runs only once, tracked by version number in the database.
 ///
 /// # Returns
 ///
 /// Vector of migrations to apply (if not already applied)
 pub fn get_migrations() -> Vec<Migration> {
-    vec![Migration {
-        version: 1,
-        description: "create_history_tables",
-        sql: r#"
+    vec![
+        Migration {
+            version: 1,
+            description: "create_history_tables",
+            sql: r#"
                 CREATE TABLE IF NOT EXISTS conversations (
                     id TEXT PRIMARY KEY,
                     title TEXT NOT NULL,
@@ -78,13 +258,566 @@ pub fn get_migrations() -> Vec<Migration> {
                 CREATE INDEX IF NOT EXISTS idx_messages_conversation 
                     ON messages(conversation_id);
             "#,
-        kind: MigrationKind::Up,
-    }]
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "create_llm_cache",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS llm_cache (
+                    key TEXT PRIMARY KEY,
+                    content TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "drop_llm_cache",
+            sql: r#"
+                DROP TABLE IF EXISTS llm_cache;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 3,
+            description: "add_token_usage",
+            sql: r#"
+                ALTER TABLE messages ADD COLUMN prompt_tokens INTEGER;
+                ALTER TABLE messages ADD COLUMN completion_tokens INTEGER;
+
+                CREATE TABLE IF NOT EXISTS usage_daily (
+                    day TEXT NOT NULL,
+                    provider TEXT NOT NULL,
+                    model TEXT NOT NULL,
+                    prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                    completion_tokens INTEGER NOT NULL DEFAULT 0,
+                    request_count INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (day, provider, model)
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 3,
+            description: "drop_token_usage",
+            sql: r#"
+                DROP TABLE IF EXISTS usage_daily;
+
+                ALTER TABLE messages DROP COLUMN completion_tokens;
+                ALTER TABLE messages DROP COLUMN prompt_tokens;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 4,
+            description: "add_message_provider",
+            sql: r#"
+                ALTER TABLE messages ADD COLUMN provider TEXT;
+                ALTER TABLE messages ADD COLUMN model TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "drop_message_provider",
+            sql: r#"
+                ALTER TABLE messages DROP COLUMN model;
+                ALTER TABLE messages DROP COLUMN provider;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 5,
+            description: "add_message_duration",
+            sql: r#"
+                ALTER TABLE messages ADD COLUMN duration_ms INTEGER;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "drop_message_duration",
+            sql: r#"
+                ALTER TABLE messages DROP COLUMN duration_ms;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 6,
+            description: "add_conversation_flags",
+            sql: r#"
+                ALTER TABLE conversations ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+                ALTER TABLE conversations ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "drop_conversation_flags",
+            sql: r#"
+                ALTER TABLE conversations DROP COLUMN archived;
+                ALTER TABLE conversations DROP COLUMN pinned;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 7,
+            description: "add_conversation_auto_title",
+            sql: r#"
+                ALTER TABLE conversations ADD COLUMN auto_title INTEGER NOT NULL DEFAULT 1;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "drop_conversation_auto_title",
+            sql: r#"
+                ALTER TABLE conversations DROP COLUMN auto_title;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 8,
+            description: "add_conversation_deleted_at",
+            sql: r#"
+                ALTER TABLE conversations ADD COLUMN deleted_at INTEGER;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "drop_conversation_deleted_at",
+            sql: r#"
+                ALTER TABLE conversations DROP COLUMN deleted_at;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 9,
+            description: "add_message_starred",
+            sql: r#"
+                ALTER TABLE messages ADD COLUMN starred INTEGER NOT NULL DEFAULT 0;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "drop_message_starred",
+            sql: r#"
+                ALTER TABLE messages DROP COLUMN starred;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 10,
+            description: "add_message_lineage",
+            sql: r#"
+                ALTER TABLE messages ADD COLUMN parent_message_id TEXT;
+                ALTER TABLE messages ADD COLUMN superseded_by TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "drop_message_lineage",
+            sql: r#"
+                ALTER TABLE messages DROP COLUMN superseded_by;
+                ALTER TABLE messages DROP COLUMN parent_message_id;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 11,
+            description: "add_conversation_tags",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS tags (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE
+                );
+
+                CREATE TABLE IF NOT EXISTS conversation_tags (
+                    conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+                    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                    PRIMARY KEY (conversation_id, tag_id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_conversation_tags_tag
+                    ON conversation_tags(tag_id);
+
+                CREATE TRIGGER IF NOT EXISTS delete_unused_tags
+                AFTER DELETE ON conversation_tags
+                BEGIN
+                    DELETE FROM tags
+                    WHERE id = OLD.tag_id
+                      AND NOT EXISTS (SELECT 1 FROM conversation_tags WHERE tag_id = OLD.tag_id);
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "drop_conversation_tags",
+            sql: r#"
+                DROP TRIGGER IF EXISTS delete_unused_tags;
+                DROP INDEX IF EXISTS idx_conversation_tags_tag;
+                DROP TABLE IF EXISTS conversation_tags;
+                DROP TABLE IF EXISTS tags;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 12,
+            description: "add_message_attachments",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS attachments (
+                    id TEXT PRIMARY KEY,
+                    message_id TEXT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+                    name TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    line_count INTEGER NOT NULL,
+                    truncated INTEGER NOT NULL DEFAULT 0,
+                    lossy INTEGER NOT NULL DEFAULT 0,
+                    created_at INTEGER NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_attachments_message
+                    ON attachments(message_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "drop_message_attachments",
+            sql: r#"
+                DROP INDEX IF EXISTS idx_attachments_message;
+                DROP TABLE IF EXISTS attachments;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 13,
+            description: "add_prompt_library",
+            // The seed is `DEFAULT_SYSTEM_PROMPT` as of this migration
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS prompts (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    is_builtin INTEGER NOT NULL DEFAULT 0,
+                    created_at INTEGER NOT NULL,
+                    updated_at INTEGER NOT NULL
+                );
+
+                INSERT OR IGNORE INTO prompts (id, name, content, is_builtin, created_at, updated_at)
+                VALUES ('default', 'Quick Assist', 'You are Quick Assist, a fast and helpful AI assistant. You provide concise, accurate, and actionable responses.
+
+Guidelines:
+- Be direct and concise - users want quick answers
+- Use markdown formatting for better readability
+- For code, always specify the language in code blocks
+- If a question is ambiguous, give the most likely answer first, then briefly mention alternatives
+- Avoid unnecessary pleasantries - get straight to the point', 1, 0, 0);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "drop_prompt_library",
+            sql: r#"
+                DROP TABLE IF EXISTS prompts;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 14,
+            description: "add_telemetry_queue",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS telemetry_queue (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    event TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "drop_telemetry_queue",
+            sql: r#"
+                DROP TABLE IF EXISTS telemetry_queue;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 15,
+            description: "add_conversation_branches",
+            sql: r#"
+                ALTER TABLE conversations ADD COLUMN branched_from_conversation_id TEXT;
+                ALTER TABLE conversations ADD COLUMN branched_from_message_id TEXT;
+
+                CREATE TRIGGER IF NOT EXISTS clear_branch_lineage
+                AFTER DELETE ON conversations
+                BEGIN
+                    UPDATE conversations
+                    SET branched_from_conversation_id = NULL, branched_from_message_id = NULL
+                    WHERE branched_from_conversation_id = OLD.id;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "drop_conversation_branches",
+            sql: r#"
+                DROP TRIGGER IF EXISTS clear_branch_lineage;
+
+                ALTER TABLE conversations DROP COLUMN branched_from_message_id;
+                ALTER TABLE conversations DROP COLUMN branched_from_conversation_id;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 16,
+            description: "add_conversation_system_prompt",
+            sql: r#"
+                ALTER TABLE conversations ADD COLUMN system_prompt_override TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "drop_conversation_system_prompt",
+            sql: r#"
+                ALTER TABLE conversations DROP COLUMN system_prompt_override;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 17,
+            description: "add_drafts",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS drafts (
+                    conversation_id TEXT REFERENCES conversations(id) ON DELETE CASCADE,
+                    text TEXT NOT NULL,
+                    updated_at INTEGER NOT NULL
+                );
+
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_drafts_conversation
+                    ON drafts(COALESCE(conversation_id, ''));
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "drop_drafts",
+            sql: r#"
+                DROP INDEX IF EXISTS idx_drafts_conversation;
+                DROP TABLE IF EXISTS drafts;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 18,
+            description: "add_prompt_history",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS prompt_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    text TEXT NOT NULL,
+                    source TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_prompt_history_created
+                    ON prompt_history(created_at DESC);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "drop_prompt_history",
+            sql: r#"
+                DROP INDEX IF EXISTS idx_prompt_history_created;
+                DROP TABLE IF EXISTS prompt_history;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 19,
+            description: "add_settings_audit",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS settings_audit (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    source TEXT NOT NULL,
+                    revision INTEGER NOT NULL,
+                    changes TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "drop_settings_audit",
+            sql: r#"
+                DROP TABLE IF EXISTS settings_audit;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 20,
+            description: "add_attachment_images",
+            sql: r#"
+                ALTER TABLE attachments ADD COLUMN mime TEXT;
+                ALTER TABLE attachments ADD COLUMN data TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 20,
+            description: "drop_attachment_images",
+            sql: r#"
+                ALTER TABLE attachments DROP COLUMN data;
+                ALTER TABLE attachments DROP COLUMN mime;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 21,
+            description: "add_message_search",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS message_search (
+                    id INTEGER PRIMARY KEY,
+                    message_id TEXT NOT NULL UNIQUE,
+                    content TEXT NOT NULL
+                );
+
+                CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+                    content,
+                    content = 'message_search',
+                    content_rowid = 'id',
+                    tokenize = 'trigram'
+                );
+
+                CREATE TRIGGER IF NOT EXISTS message_search_insert
+                AFTER INSERT ON message_search
+                BEGIN
+                    INSERT INTO messages_fts (rowid, content) VALUES (new.id, new.content);
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS message_search_delete
+                AFTER DELETE ON message_search
+                BEGIN
+                    INSERT INTO messages_fts (messages_fts, rowid, content)
+                        VALUES ('delete', old.id, old.content);
+                END;
+
+                INSERT INTO message_search (message_id, content)
+                    SELECT id, content FROM messages WHERE substr(content, 1, 4) != 'enc:';
+
+                CREATE TRIGGER IF NOT EXISTS messages_search_insert
+                AFTER INSERT ON messages
+                WHEN substr(new.content, 1, 4) != 'enc:'
+                BEGIN
+                    INSERT INTO message_search (message_id, content) VALUES (new.id, new.content);
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS messages_search_update
+                AFTER UPDATE OF content ON messages
+                BEGIN
+                    DELETE FROM message_search WHERE message_id = old.id;
+                    INSERT INTO message_search (message_id, content)
+                        SELECT new.id, new.content WHERE substr(new.content, 1, 4) != 'enc:';
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS messages_search_delete
+                AFTER DELETE ON messages
+                BEGIN
+                    DELETE FROM message_search WHERE message_id = old.id;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 21,
+            description: "drop_message_search",
+            sql: r#"
+                DROP TRIGGER IF EXISTS messages_search_delete;
+                DROP TRIGGER IF EXISTS messages_search_update;
+                DROP TRIGGER IF EXISTS messages_search_insert;
+                DROP TRIGGER IF EXISTS message_search_delete;
+                DROP TRIGGER IF EXISTS message_search_insert;
+                DROP TABLE IF EXISTS messages_fts;
+                DROP TABLE IF EXISTS message_search;
+            "#,
+            kind: MigrationKind::Down,
+        },
+    ]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sqlx::{Connection, SqliteConnection};
+    use std::collections::BTreeSet;
+
+    fn ups() -> Vec<Migration> {
+        get_migrations()
+            .into_iter()
+            .filter(|m| matches!(m.kind, MigrationKind::Up))
+            .collect()
+    }
+
+    fn down(version: i64) -> Option<Migration> {
+        get_migrations()
+            .into_iter()
+            .find(|m| m.version == version && matches!(m.kind, MigrationKind::Down))
+    }
+
+    /// Tables named after `TABLE` or `INTO` in `sql`.
+    fn tables(sql: &str) -> BTreeSet<String> {
+        let words: Vec<&str> = sql.split_whitespace().collect();
+        let mut tables = BTreeSet::new();
+        for (i, word) in words.iter().enumerate() {
+            if *word != "TABLE" && *word != "INTO" {
+                continue;
+            }
+            let name = words[i + 1..]
+                .iter()
+                .find(|w| !matches!(**w, "IF" | "NOT" | "EXISTS"))
+                .unwrap();
+            tables.insert(name.trim_end_matches(['(', ';']).to_string());
+        }
+        tables
+    }
+
+    /// Tables with their columns, indexes and triggers, in a comparable form.
+    async fn schema(conn: &mut SqliteConnection) -> Vec<String> {
+        let objects: Vec<(String, String)> = sqlx::query_as(
+            "SELECT type, name FROM sqlite_master
+             WHERE name NOT LIKE 'sqlite_%' ORDER BY type, name",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+        let mut schema = Vec::new();
+        for (kind, name) in objects {
+            if kind == "table" {
+                let columns: Vec<(String, String, i64, Option<String>)> = sqlx::query_as(
+                    "SELECT name, type, \"notnull\", dflt_value FROM pragma_table_info(?)",
+                )
+                .bind(&name)
+                .fetch_all(&mut *conn)
+                .await
+                .unwrap();
+                schema.push(format!("table {} {:?}", name, columns));
+            } else {
+                schema.push(format!("{} {}", kind, name));
+            }
+        }
+        schema
+    }
 
     #[test]
     fn test_get_migrations_returns_non_empty() {
@@ -103,7 +836,7 @@ mod tests {
 
     #[test]
     fn test_migrations_have_sequential_versions() {
-        let migrations = get_migrations();
+        let migrations = ups();
         for (i, migration) in migrations.iter().enumerate() {
             let expected_version = (i + 1) as i64;
             assert_eq!(
@@ -156,4 +889,106 @@ mod tests {
             "First migration should create messages table"
         );
     }
+
+    #[test]
+    fn test_message_column_migrations_only_alter_messages() {
+        let migrations = ups();
+
+        for version in [4, 5, 9, 10] {
+            let migration = &migrations[version - 1];
+            let statements: Vec<&str> = migration
+                .sql
+                .split(';')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .collect();
+
+            assert!(!statements.is_empty());
+            for statement in statements {
+                assert!(
+                    statement.starts_with("ALTER TABLE messages ADD COLUMN"),
+                    "Migration {} should only add columns to messages: {}",
+                    version,
+                    statement
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fifth_migration_adds_duration() {
+        let migrations = ups();
+        let fifth = &migrations[4];
+
+        assert_eq!(fifth.version, 5);
+        assert!(fifth.sql.contains("duration_ms INTEGER"));
+    }
+
+    // ===== Down migrations =====
+
+    #[test]
+    fn test_every_up_from_version_2_has_a_down() {
+        assert!(down(1).is_none(), "Migration 1 has nothing to go back to");
+        for up in ups().iter().skip(1) {
+            let down = down(up.version)
+                .unwrap_or_else(|| panic!("Migration {} should have a Down", up.version));
+            assert!(!down.sql.trim().is_empty());
+            assert!(!down.description.is_empty());
+        }
+        assert_eq!(get_migrations().len(), ups().len() * 2 - 1);
+    }
+
+    #[test]
+    fn test_down_follows_its_up() {
+        let migrations = get_migrations();
+        for (i, migration) in migrations.iter().enumerate() {
+            if matches!(migration.kind, MigrationKind::Down) {
+                let up = &migrations[i - 1];
+                assert!(matches!(up.kind, MigrationKind::Up));
+                assert_eq!(up.version, migration.version);
+            }
+        }
+    }
+
+    #[test]
+    fn test_down_references_the_same_tables() {
+        for up in ups().iter().skip(1) {
+            let down = down(up.version).unwrap();
+            assert_eq!(
+                tables(down.sql),
+                tables(up.sql),
+                "Down migration {} should touch the tables its Up does",
+                up.version
+            );
+        }
+    }
+
+    #[test]
+    fn test_latest_version_ignores_downs() {
+        assert_eq!(latest_version(), ups().last().unwrap().version);
+    }
+
+    #[tokio::test]
+    async fn test_each_down_restores_the_previous_schema() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        let mut before = Vec::new();
+        for up in ups() {
+            before.push(schema(&mut conn).await);
+            sqlx::raw_sql(up.sql).execute(&mut conn).await.unwrap();
+        }
+
+        for up in ups().iter().skip(1).rev() {
+            let down = down(up.version).unwrap();
+            sqlx::raw_sql(down.sql)
+                .execute(&mut conn)
+                .await
+                .unwrap_or_else(|e| panic!("Down migration {} failed: {}", up.version, e));
+            assert_eq!(
+                schema(&mut conn).await,
+                before[up.version as usize - 1],
+                "Down migration {} should restore the schema before it",
+                up.version
+            );
+        }
+    }
 }
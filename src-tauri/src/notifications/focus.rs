@@ -0,0 +1,148 @@
+//! Whether the user asked not to be disturbed, or is presenting.
+//!
+//! - **Windows**: `SHQueryUserNotificationState`, which reports quiet
+//!   hours and full-screen apps, games and presentations
+//! - **macOS**: the Do Not Disturb flag of the notification center where
+//!   it is still readable (macOS 11 and earlier); Focus modes aren't
+//!   visible to apps without an entitlement, so newer systems report
+//!   [`FocusAssistState::Unknown`]
+//! - **Linux**: the `Inhibited` property of the notification server, which
+//!   KDE Plasma and some others provide; full-screen apps aren't detected
+
+use serde::Serialize;
+
+/// What the system says about showing notifications now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusAssistState {
+    /// Notifications are welcome
+    Off,
+    /// Do not disturb, Focus Assist or quiet hours, or nobody is at the
+    /// screen
+    DoNotDisturb,
+    /// A full-screen app, game or presentation is in front
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Fullscreen,
+    /// The platform doesn't say; treated like [`FocusAssistState::Off`]
+    Unknown,
+}
+
+impl FocusAssistState {
+    /// Whether notifications should be held back.
+    pub fn is_quiet(self) -> bool {
+        matches!(self, Self::DoNotDisturb | Self::Fullscreen)
+    }
+}
+
+/// Where the focus state comes from; the system, or a fake in tests.
+pub trait FocusProvider {
+    fn state(&self) -> FocusAssistState;
+}
+
+/// The state as reported by the OS.
+pub struct SystemFocus;
+
+impl FocusProvider for SystemFocus {
+    fn state(&self) -> FocusAssistState {
+        focus_assist_state()
+    }
+}
+
+/// Whether do not disturb is on or a full-screen app is in front.
+pub fn is_dnd_or_fullscreen() -> bool {
+    focus_assist_state().is_quiet()
+}
+
+/// The focus state as reported by the OS.
+#[cfg(target_os = "windows")]
+pub fn focus_assist_state() -> FocusAssistState {
+    use windows_sys::Win32::UI::Shell::{
+        SHQueryUserNotificationState, QUNS_ACCEPTS_NOTIFICATIONS, QUNS_APP, QUNS_BUSY,
+        QUNS_NOT_PRESENT, QUNS_PRESENTATION_MODE, QUNS_QUIET_TIME, QUNS_RUNNING_D3D_FULL_SCREEN,
+    };
+
+    let mut state = 0;
+    // SAFETY: `state` is a valid out pointer for the call
+    if unsafe { SHQueryUserNotificationState(&mut state) } < 0 {
+        return FocusAssistState::Unknown;
+    }
+    match state {
+        QUNS_ACCEPTS_NOTIFICATIONS => FocusAssistState::Off,
+        QUNS_QUIET_TIME | QUNS_NOT_PRESENT => FocusAssistState::DoNotDisturb,
+        QUNS_BUSY | QUNS_RUNNING_D3D_FULL_SCREEN | QUNS_PRESENTATION_MODE | QUNS_APP => {
+            FocusAssistState::Fullscreen
+        }
+        _ => FocusAssistState::Unknown,
+    }
+}
+
+/// The focus state as reported by the OS.
+#[cfg(target_os = "macos")]
+pub fn focus_assist_state() -> FocusAssistState {
+    let output = std::process::Command::new("defaults")
+        .args([
+            "-currentHost",
+            "read",
+            "com.apple.notificationcenterui",
+            "doNotDisturb",
+        ])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            match String::from_utf8_lossy(&output.stdout).trim() {
+                "1" => FocusAssistState::DoNotDisturb,
+                "0" => FocusAssistState::Off,
+                _ => FocusAssistState::Unknown,
+            }
+        }
+        _ => FocusAssistState::Unknown,
+    }
+}
+
+/// The focus state as reported by the OS.
+#[cfg(target_os = "linux")]
+pub fn focus_assist_state() -> FocusAssistState {
+    use zbus::blocking::{Connection, Proxy};
+
+    let inhibited = Connection::session().and_then(|connection| {
+        Proxy::new(
+            &connection,
+            "org.freedesktop.Notifications",
+            "/org/freedesktop/Notifications",
+            "org.freedesktop.Notifications",
+        )?
+        .get_property::<bool>("Inhibited")
+    });
+    match inhibited {
+        Ok(true) => FocusAssistState::DoNotDisturb,
+        Ok(false) => FocusAssistState::Off,
+        Err(_) => FocusAssistState::Unknown,
+    }
+}
+
+/// The focus state as reported by the OS.
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+pub fn focus_assist_state() -> FocusAssistState {
+    FocusAssistState::Unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_dnd_and_fullscreen_are_quiet() {
+        assert!(FocusAssistState::DoNotDisturb.is_quiet());
+        assert!(FocusAssistState::Fullscreen.is_quiet());
+        assert!(!FocusAssistState::Off.is_quiet());
+        assert!(!FocusAssistState::Unknown.is_quiet());
+    }
+
+    #[test]
+    fn test_state_shape() {
+        assert_eq!(
+            serde_json::to_value(FocusAssistState::DoNotDisturb).unwrap(),
+            "do_not_disturb"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Notifications held back while the user is busy.
+//!
+//! A notification due while [`FocusAssistState::is_quiet`] is held here
+//! instead of shown. The first one held starts [`release_when_clear`],
+//! which looks again every [`POLL_INTERVAL`] and hands everything held over
+//! in order once the state clears, then stops.
+//!
+//! [`FocusAssistState::is_quiet`]: super::focus::FocusAssistState::is_quiet
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::focus::FocusProvider;
+
+/// How often the focus state is checked while something is held.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A notification waiting to be shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeldNotification {
+    /// A finished answer (see [`super::notify_completion`])
+    Completion {
+        title: String,
+        body: String,
+        conversation_id: Option<String>,
+    },
+    /// An update found by a background check
+    UpdateAvailable { version: String },
+}
+
+/// The held notifications, oldest first; managed as Tauri state.
+#[derive(Default)]
+pub struct HeldNotifications(Mutex<Vec<HeldNotification>>);
+
+impl HeldNotifications {
+    /// Hold `notification` back.
+    ///
+    /// # Returns
+    ///
+    /// `true` if nothing was held before, so [`release_when_clear`] needs
+    /// starting.
+    pub fn hold(&self, notification: HeldNotification) -> bool {
+        let mut held = self.0.lock().unwrap();
+        held.push(notification);
+        held.len() == 1
+    }
+
+    /// Number of notifications held.
+    pub fn count(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    /// Take everything held, unless `focus` is still quiet.
+    fn take_if_clear(&self, focus: &impl FocusProvider) -> Option<Vec<HeldNotification>> {
+        let mut held = self.0.lock().unwrap();
+        if focus.state().is_quiet() {
+            return None;
+        }
+        Some(std::mem::take(&mut *held))
+    }
+}
+
+/// Check `focus` every `interval` and `deliver` the held notifications
+/// once it clears.
+///
+/// Returns after delivering; a notification held after that starts a new
+/// run, as [`HeldNotifications::hold`] reports.
+pub async fn release_when_clear(
+    held: &HeldNotifications,
+    focus: &impl FocusProvider,
+    interval: Duration,
+    mut deliver: impl FnMut(HeldNotification),
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+        if let Some(notifications) = held.take_if_clear(focus) {
+            notifications.into_iter().for_each(&mut deliver);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::focus::FocusAssistState;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    /// Quiet until told otherwise.
+    #[derive(Default)]
+    struct FakeFocus {
+        clear: AtomicBool,
+    }
+
+    impl FocusProvider for FakeFocus {
+        fn state(&self) -> FocusAssistState {
+            if self.clear.load(Ordering::SeqCst) {
+                FocusAssistState::Off
+            } else {
+                FocusAssistState::Fullscreen
+            }
+        }
+    }
+
+    fn update(version: &str) -> HeldNotification {
+        HeldNotification::UpdateAvailable {
+            version: version.to_string(),
+        }
+    }
+
+    const TICK: Duration = Duration::from_millis(10);
+
+    // ===== Holding =====
+
+    #[test]
+    fn test_first_hold_starts_polling() {
+        let held = HeldNotifications::default();
+
+        assert!(held.hold(update("1.0.0")));
+        assert!(!held.hold(update("1.1.0")));
+        assert_eq!(held.count(), 2);
+    }
+
+    #[test]
+    fn test_nothing_taken_while_quiet() {
+        let held = HeldNotifications::default();
+        let focus = FakeFocus::default();
+        held.hold(update("1.0.0"));
+
+        assert_eq!(held.take_if_clear(&focus), None);
+        assert_eq!(held.count(), 1);
+    }
+
+    // ===== Releasing =====
+
+    #[tokio::test]
+    async fn test_released_in_order_once_clear() {
+        let held = Arc::new(HeldNotifications::default());
+        let focus = Arc::new(FakeFocus::default());
+        let completion = HeldNotification::Completion {
+            title: "Tar flags".to_string(),
+            body: "Use `tar -xzf`".to_string(),
+            conversation_id: Some("c1".to_string()),
+        };
+        held.hold(completion.clone());
+        held.hold(update("1.1.0"));
+
+        let release = tokio::spawn({
+            let (held, focus) = (Arc::clone(&held), Arc::clone(&focus));
+            async move {
+                let mut delivered = Vec::new();
+                release_when_clear(&held, &*focus, TICK, |n| delivered.push(n)).await;
+                delivered
+            }
+        });
+        tokio::time::sleep(TICK * 5).await;
+        assert!(!release.is_finished());
+        assert_eq!(held.count(), 2);
+
+        focus.clear.store(true, Ordering::SeqCst);
+        let delivered = release.await.unwrap();
+
+        assert_eq!(delivered, [completion, update("1.1.0")]);
+        assert_eq!(held.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_hold_after_release_starts_again() {
+        let held = HeldNotifications::default();
+        let focus = FakeFocus::default();
+        focus.clear.store(true, Ordering::SeqCst);
+        held.hold(update("1.0.0"));
+
+        release_when_clear(&held, &focus, TICK, |_| {}).await;
+
+        assert!(held.hold(update("1.1.0")));
+    }
+}
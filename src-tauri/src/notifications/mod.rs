@@ -0,0 +1,335 @@
+//! System notifications for answers that arrive while the launcher is hidden.
+//!
+//! When `ask_llm` finishes (with an answer or an error) and the launcher
+//! isn't visible, a notification shows the conversation title and the start
+//! of the answer. Clicking it shows the launcher and emits
+//! `open-conversation` for that thread.
+//!
+//! Nothing is sent when `general.notify_on_completion` is off, or when the
+//! answer came back within [`MIN_ELAPSED_MS`]: the user most likely just
+//! dismissed the launcher and hasn't moved on yet.
+//!
+//! # Do Not Disturb
+//!
+//! While do not disturb is on or a full-screen app is in front (see
+//! [`focus`]), completion notifications and the notification for an update
+//! found in the background are held back and shown once that ends, checked
+//! every minute (see [`held`]). A held update notification arrives as a
+//! second `update-available` with `notify: true`. `get_focus_assist_state`
+//! reports the state and how many notifications are held, for the
+//! diagnostics panel.
+//!
+//! Notifications are sent through `tauri-plugin-notification`, which
+//! reports clicks back on Windows, macOS and Linux (see [`init`]). Windows
+//! only reports a click while the toast is on screen, not from the Action
+//! Center.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! await listen<OpenConversation>('open-conversation', ({ payload }) => {
+//!   router.openConversation(payload.conversation_id);
+//! });
+//!
+//! const focus = await invoke<FocusAssist>('get_focus_assist_state');
+//! // { state: 'fullscreen', held: 2 }   state: 'off' | 'do_not_disturb' | 'fullscreen' | 'unknown'
+//! ```
+
+pub mod focus;
+pub mod held;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use focus::{FocusAssistState, SystemFocus};
+use held::{HeldNotification, HeldNotifications};
+
+use crate::db::{now_ms, Db};
+use crate::history::store;
+use crate::i18n::t;
+use crate::llm::types::LlmResponse;
+use crate::llm::LlmError;
+use crate::settings::SettingsManager;
+use crate::snooze;
+use crate::updater::UpdateAvailable;
+use crate::window::{self, visibility::VisibilityReason};
+
+/// Answers quicker than this (ms) never notify.
+pub const MIN_ELAPSED_MS: i64 = 2_000;
+
+/// Longest answer excerpt shown in a notification, in characters.
+pub const SNIPPET_CHARS: usize = 100;
+
+/// Title used when the answer isn't stored in a conversation.
+const APP_TITLE: &str = "Qwik Ask";
+
+/// Key of the conversation ID in a completion notification's extras.
+const CONVERSATION_EXTRA: &str = "conversation_id";
+
+/// The plugin's action ID for a click on the notification itself.
+const TAP_ACTION: &str = "tap";
+
+/// Focus state and held notifications, for the diagnostics panel.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FocusAssist {
+    pub state: FocusAssistState,
+    /// Notifications waiting for the state to clear
+    pub held: usize,
+}
+
+/// Payload of the `open-conversation` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OpenConversation {
+    pub conversation_id: String,
+}
+
+/// Whether a finished request should be announced.
+///
+/// # Arguments
+///
+/// * `enabled` - The `general.notify_on_completion` setting
+/// * `launcher_visible` - Whether the launcher is on screen
+/// * `elapsed_ms` - How long the request took
+pub fn should_notify(enabled: bool, launcher_visible: bool, elapsed_ms: i64) -> bool {
+    enabled && !launcher_visible && elapsed_ms >= MIN_ELAPSED_MS
+}
+
+/// The first [`SNIPPET_CHARS`] characters of `text` on one line.
+///
+/// Whitespace runs (including newlines) collapse to single spaces, and an
+/// ellipsis marks text that was cut.
+pub fn snippet(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= SNIPPET_CHARS {
+        return line;
+    }
+    let mut cut: String = line.chars().take(SNIPPET_CHARS).collect();
+    cut.truncate(cut.trim_end().len());
+    cut.push('…');
+    cut
+}
+
+/// Notification body for a finished request.
+fn body(result: &Result<LlmResponse, LlmError>) -> String {
+    match result {
+        Ok(response) => snippet(&response.content),
+        Err(error) => snippet(&t("notification.request_failed", &[("error", error)])),
+    }
+}
+
+/// Announce a finished `ask_llm` request if the launcher is hidden.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+/// * `started_ms` - When the request started (Unix ms)
+/// * `conversation_id` - Conversation the answer belongs to, if any
+/// * `result` - The answer or error
+pub async fn notify_completion(
+    app: &AppHandle,
+    started_ms: i64,
+    conversation_id: Option<&str>,
+    result: &Result<LlmResponse, LlmError>,
+) {
+    if snooze::is_snoozed(app) {
+        return;
+    }
+    let enabled = app
+        .state::<SettingsManager>()
+        .snapshot()
+        .map(|settings| settings.general.notify_on_completion)
+        .unwrap_or(false);
+    let visible = app
+        .get_webview_window("main")
+        .and_then(|window| window.is_visible().ok())
+        .unwrap_or(false);
+    if !should_notify(enabled, visible, now_ms() - started_ms) {
+        return;
+    }
+
+    let title = match conversation_id {
+        Some(id) => store::find_conversation(app.state::<Db>().pool(), id)
+            .await
+            .map(|conversation| conversation.title)
+            .unwrap_or_else(|_| APP_TITLE.to_string()),
+        None => APP_TITLE.to_string(),
+    };
+
+    let notification = HeldNotification::Completion {
+        title,
+        body: body(result),
+        conversation_id: conversation_id.map(str::to_string),
+    };
+    if let Some(notification) = hold_if_quiet(app, notification).await {
+        show(app, notification);
+    }
+}
+
+/// Hold `notification` back if do not disturb is on or a full-screen app
+/// is in front, and show it once that ends.
+///
+/// # Returns
+///
+/// The notification, if it should be shown now.
+pub async fn hold_if_quiet(
+    app: &AppHandle,
+    notification: HeldNotification,
+) -> Option<HeldNotification> {
+    let quiet = tauri::async_runtime::spawn_blocking(focus::is_dnd_or_fullscreen)
+        .await
+        .unwrap_or(false);
+    let Some(held) = app.try_state::<HeldNotifications>().filter(|_| quiet) else {
+        return Some(notification);
+    };
+    tracing::debug!("Holding a notification until do not disturb ends");
+    if held.hold(notification) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let held = app.state::<HeldNotifications>();
+            held::release_when_clear(&held, &SystemFocus, held::POLL_INTERVAL, |notification| {
+                show(&app, notification)
+            })
+            .await;
+        });
+    }
+    None
+}
+
+/// Handle clicks on completion notifications: show the launcher and emit
+/// `open-conversation` for the notification's thread.
+///
+/// Call once at startup; the plugin only reports clicks on notifications
+/// shown after a handler exists.
+pub fn init(app: &AppHandle) {
+    let handle = app.clone();
+    let registered = app.notification().on_action(move |action| {
+        if action.action_id() != TAP_ACTION {
+            return;
+        }
+        let conversation_id = action
+            .notification()
+            .and_then(|notification| notification.extra().get(CONVERSATION_EXTRA))
+            .and_then(|id| id.as_str())
+            .map(str::to_string);
+        let app = handle.clone();
+        let _ = handle.run_on_main_thread(move || {
+            window::show_launcher(&app, VisibilityReason::Notification);
+            if let Some(conversation_id) = conversation_id {
+                let _ = app.emit("open-conversation", OpenConversation { conversation_id });
+            }
+        });
+    });
+    if let Err(e) = registered {
+        tracing::warn!(error = %e, "Failed to handle notification clicks");
+    }
+}
+
+/// Show `notification` now.
+fn show(app: &AppHandle, notification: HeldNotification) {
+    match notification {
+        HeldNotification::Completion {
+            title,
+            body,
+            conversation_id,
+        } => {
+            let mut builder = app.notification().builder().title(title).body(body);
+            if let Some(conversation_id) = conversation_id {
+                builder = builder.extra(CONVERSATION_EXTRA, conversation_id);
+            }
+            if let Err(e) = builder.show() {
+                tracing::warn!(error = %e, "Failed to show notification");
+            }
+        }
+        // The frontend shows update notifications
+        HeldNotification::UpdateAvailable { version } => {
+            let _ = app.emit(
+                "update-available",
+                UpdateAvailable {
+                    version,
+                    notify: true,
+                },
+            );
+        }
+    }
+}
+
+/// Whether do not disturb or a full-screen app is holding notifications
+/// back, and how many.
+#[tauri::command]
+pub async fn get_focus_assist_state(
+    held: State<'_, HeldNotifications>,
+) -> Result<FocusAssist, String> {
+    let state = tauri::async_runtime::spawn_blocking(focus::focus_assist_state)
+        .await
+        .map_err(|e| format!("Failed to read focus state: {}", e))?;
+    Ok(FocusAssist {
+        state,
+        held: held.count(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===== Suppression =====
+
+    #[test]
+    fn test_notifies_slow_answer_while_hidden() {
+        assert!(should_notify(true, false, 15_000));
+        assert!(should_notify(true, false, MIN_ELAPSED_MS));
+    }
+
+    #[test]
+    fn test_no_notification_while_visible() {
+        assert!(!should_notify(true, true, 15_000));
+    }
+
+    #[test]
+    fn test_no_notification_for_quick_answers() {
+        assert!(!should_notify(true, false, MIN_ELAPSED_MS - 1));
+        assert!(!should_notify(true, false, 0));
+    }
+
+    #[test]
+    fn test_no_notification_when_disabled() {
+        assert!(!should_notify(false, false, 15_000));
+    }
+
+    // ===== Body =====
+
+    #[test]
+    fn test_short_text_kept_on_one_line() {
+        assert_eq!(
+            snippet("  Use `tar -xzf`\n\nto extract.  "),
+            "Use `tar -xzf` to extract."
+        );
+    }
+
+    #[test]
+    fn test_long_text_cut_with_ellipsis() {
+        let text = "word ".repeat(50);
+
+        let cut = snippet(&text);
+
+        assert!(cut.ends_with('…'));
+        assert!(cut.chars().count() <= SNIPPET_CHARS + 1);
+        assert!(cut.starts_with("word word"));
+    }
+
+    #[test]
+    fn test_single_long_word_cut() {
+        let cut = snippet(&"x".repeat(300));
+
+        assert_eq!(cut.chars().count(), SNIPPET_CHARS + 1);
+        assert!(cut.ends_with('…'));
+    }
+
+    #[test]
+    fn test_error_body() {
+        let result = Err(LlmError::Network("offline".to_string()));
+
+        assert!(body(&result).starts_with("Request failed: "));
+    }
+}
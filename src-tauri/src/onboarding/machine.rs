@@ -0,0 +1,282 @@
+//! The onboarding steps as a pure state machine.
+//!
+//! ```text
+//! Welcome → Hotkey → Provider → Done
+//! ```
+//!
+//! Steps advance one at a time and can go back to any earlier step until
+//! onboarding is done. Leaving the provider step needs a working API key
+//! unless the user skips it. [`OnboardingState::complete`] finishes from
+//! any step.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::settings::AppSettings;
+
+/// A step of onboarding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    /// What the app does
+    Welcome,
+    /// Pick or confirm the toggle shortcut
+    Hotkey,
+    /// Choose a provider and enter an API key
+    Provider,
+    /// Finished or dismissed
+    Done,
+}
+
+impl OnboardingStep {
+    /// The step after this one.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Welcome => Self::Hotkey,
+            Self::Hotkey => Self::Provider,
+            Self::Provider | Self::Done => Self::Done,
+        }
+    }
+}
+
+/// Onboarding progress, kept in the settings store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnboardingState {
+    pub step: OnboardingStep,
+    /// The provider step was skipped without a working key
+    #[serde(default)]
+    pub provider_skipped: bool,
+}
+
+impl Default for OnboardingState {
+    fn default() -> Self {
+        Self {
+            step: OnboardingStep::Welcome,
+            provider_skipped: false,
+        }
+    }
+}
+
+/// Outcome of checking the API key when leaving the provider step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCheck {
+    /// The provider accepted the key
+    Valid,
+    /// The provider rejected the key
+    Rejected,
+    /// The user chose to skip the step
+    Skipped,
+}
+
+/// Why a step change was refused.
+///
+/// Serializes with a `kind` tag like [`crate::llm::LlmError`].
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum OnboardingError {
+    /// Steps can't be skipped forward, and nothing follows `done`
+    #[error("Can't go from {from:?} to {to:?}")]
+    InvalidTransition {
+        from: OnboardingStep,
+        to: OnboardingStep,
+    },
+    /// The provider rejected the API key
+    #[error("The API key was rejected")]
+    KeyRejected,
+    /// The key couldn't be checked (offline, misconfigured, ...)
+    #[error("Couldn't check the API key: {0}")]
+    KeyCheckFailed(String),
+    /// The settings store couldn't be read or written
+    #[error("{0}")]
+    Store(String),
+}
+
+impl From<String> for OnboardingError {
+    fn from(message: String) -> Self {
+        Self::Store(message)
+    }
+}
+
+impl OnboardingState {
+    /// Whether moving to `to` has to check the API key first.
+    pub fn needs_key_check(&self, to: OnboardingStep) -> bool {
+        self.step == OnboardingStep::Provider && to == OnboardingStep::Done
+    }
+
+    /// Move to `to`.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The next step, or any earlier one
+    /// * `key` - Result of the key check when [`Self::needs_key_check`]
+    ///   says one is needed; `None` counts as rejected there
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidTransition` - `to` skips a step, repeats the current one,
+    ///   or onboarding is already done
+    /// * `KeyRejected` - Leaving the provider step with a rejected key
+    pub fn advance(
+        self,
+        to: OnboardingStep,
+        key: Option<KeyCheck>,
+    ) -> Result<Self, OnboardingError> {
+        let from = self.step;
+        let forward = to == from.next() && from != OnboardingStep::Done;
+        let back = to < from && from != OnboardingStep::Done;
+        if !forward && !back {
+            return Err(OnboardingError::InvalidTransition { from, to });
+        }
+
+        let mut provider_skipped = self.provider_skipped;
+        if self.needs_key_check(to) {
+            match key {
+                Some(KeyCheck::Valid) => provider_skipped = false,
+                Some(KeyCheck::Skipped) => provider_skipped = true,
+                Some(KeyCheck::Rejected) | None => return Err(OnboardingError::KeyRejected),
+            }
+        }
+        Ok(Self {
+            step: to,
+            provider_skipped,
+        })
+    }
+
+    /// Finish onboarding from any step, e.g. when the user dismisses it.
+    pub fn complete(self) -> Self {
+        Self {
+            step: OnboardingStep::Done,
+            provider_skipped: self.provider_skipped || self.step <= OnboardingStep::Provider,
+        }
+    }
+
+    /// Whether onboarding is finished.
+    pub fn is_done(&self) -> bool {
+        self.step == OnboardingStep::Done
+    }
+}
+
+/// Whether the app was just installed: it has never recorded a version and
+/// the settings are untouched.
+///
+/// # Arguments
+///
+/// * `last_seen_version` - Version recorded by the previous launch, if any
+/// * `settings` - The loaded settings
+pub fn is_fresh_install(last_seen_version: Option<&str>, settings: &AppSettings) -> bool {
+    last_seen_version.is_none()
+        && serde_json::to_value(settings).ok() == serde_json::to_value(AppSettings::default()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use OnboardingStep::*;
+
+    fn at(step: OnboardingStep) -> OnboardingState {
+        OnboardingState {
+            step,
+            provider_skipped: false,
+        }
+    }
+
+    // ===== Transitions =====
+
+    #[test]
+    fn test_walk_through_all_steps() {
+        let state = OnboardingState::default()
+            .advance(Hotkey, None)
+            .and_then(|state| state.advance(Provider, None))
+            .and_then(|state| state.advance(Done, Some(KeyCheck::Valid)))
+            .unwrap();
+
+        assert_eq!(state, at(Done));
+        assert!(state.is_done());
+    }
+
+    #[test]
+    fn test_cannot_skip_forward() {
+        assert_eq!(
+            at(Welcome).advance(Provider, Some(KeyCheck::Valid)),
+            Err(OnboardingError::InvalidTransition {
+                from: Welcome,
+                to: Provider
+            })
+        );
+        assert!(at(Hotkey).advance(Done, Some(KeyCheck::Valid)).is_err());
+        assert!(at(Hotkey).advance(Hotkey, Some(KeyCheck::Valid)).is_err());
+    }
+
+    #[test]
+    fn test_can_go_back() {
+        assert_eq!(at(Provider).advance(Welcome, None), Ok(at(Welcome)));
+        assert_eq!(at(Provider).advance(Hotkey, None), Ok(at(Hotkey)));
+    }
+
+    #[test]
+    fn test_done_is_final() {
+        assert!(at(Done).advance(Provider, Some(KeyCheck::Valid)).is_err());
+        assert!(at(Done).advance(Done, Some(KeyCheck::Valid)).is_err());
+        assert_eq!(at(Done).complete(), at(Done));
+    }
+
+    // ===== Provider key =====
+
+    #[test]
+    fn test_provider_needs_working_key() {
+        assert!(at(Provider).needs_key_check(Done));
+        assert!(!at(Provider).needs_key_check(Hotkey));
+        assert!(!at(Hotkey).needs_key_check(Provider));
+
+        assert_eq!(
+            at(Provider).advance(Done, None),
+            Err(OnboardingError::KeyRejected)
+        );
+        assert_eq!(
+            at(Provider).advance(Done, Some(KeyCheck::Rejected)),
+            Err(OnboardingError::KeyRejected)
+        );
+    }
+
+    #[test]
+    fn test_provider_can_be_skipped() {
+        let state = at(Provider).advance(Done, Some(KeyCheck::Skipped)).unwrap();
+
+        assert!(state.is_done());
+        assert!(state.provider_skipped);
+    }
+
+    #[test]
+    fn test_complete_early_counts_as_skipped() {
+        let state = at(Hotkey).complete();
+
+        assert!(state.is_done());
+        assert!(state.provider_skipped);
+    }
+
+    // ===== Serialization =====
+
+    #[test]
+    fn test_state_serialization() {
+        let json = serde_json::to_value(at(Hotkey)).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({ "step": "hotkey", "provider_skipped": false })
+        );
+        let error = serde_json::to_value(OnboardingError::KeyRejected).unwrap();
+        assert_eq!(error, serde_json::json!({ "kind": "key_rejected" }));
+    }
+
+    // ===== Fresh install =====
+
+    #[test]
+    fn test_fresh_install() {
+        let mut settings = AppSettings::default();
+        assert!(is_fresh_install(None, &settings));
+        assert!(!is_fresh_install(Some("1.2.0"), &settings));
+
+        settings.llm.api_key = "sk-test".to_string();
+        assert!(!is_fresh_install(None, &settings));
+    }
+}
@@ -0,0 +1,158 @@
+//! First-run onboarding.
+//!
+//! A fresh install opens the settings window on the `onboarding` section
+//! instead of starting hidden in the tray, and walks through the steps in
+//! [`machine`]: a welcome, the toggle shortcut, and the provider and API
+//! key. Progress is kept in the settings store, so quitting halfway picks
+//! up at the same step on the next launch. Existing installs are marked
+//! done the first time they run a version with onboarding.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const state = await invoke<OnboardingState>('get_onboarding_state');
+//! // { step: 'welcome' | 'hotkey' | 'provider' | 'done', provider_skipped: false }
+//!
+//! await invoke('advance_onboarding', { step: 'hotkey' });
+//! // Leaving the provider step checks the key; errors with
+//! // { kind: 'key_rejected' } unless skipped
+//! await invoke('advance_onboarding', { step: 'done', skip: false });
+//!
+//! // "Skip setup"
+//! await invoke('complete_onboarding');
+//! ```
+
+pub mod machine;
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::llm;
+use crate::settings::SettingsManager;
+use crate::updater::transition::LAST_SEEN_VERSION_KEY;
+use crate::window;
+pub use machine::{KeyCheck, OnboardingError, OnboardingState, OnboardingStep};
+
+/// Store key holding the [`OnboardingState`].
+pub const ONBOARDING_KEY: &str = "onboarding";
+
+/// Settings section showing the onboarding flow.
+pub const ONBOARDING_SECTION: &str = "onboarding";
+
+/// The stored state; a fresh state if none is stored yet.
+fn load(settings_manager: &SettingsManager) -> Result<OnboardingState, String> {
+    Ok(settings_manager
+        .load_state(ONBOARDING_KEY)?
+        .unwrap_or_default())
+}
+
+/// Decide at startup whether to show onboarding, and show it.
+///
+/// Must run before the launch version is recorded, which is how a fresh
+/// install is told apart from an update.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle
+pub fn start(app: &AppHandle) {
+    let settings_manager = app.state::<SettingsManager>();
+    let state = match settings_manager.load_state::<OnboardingState>(ONBOARDING_KEY) {
+        Ok(Some(state)) => state,
+        Ok(None) => {
+            let last_seen: Option<String> = settings_manager
+                .load_state(LAST_SEEN_VERSION_KEY)
+                .ok()
+                .flatten();
+            let fresh = settings_manager
+                .snapshot()
+                .is_ok_and(|settings| machine::is_fresh_install(last_seen.as_deref(), &settings));
+            let state = if fresh {
+                OnboardingState::default()
+            } else {
+                // Set up before onboarding existed
+                OnboardingState {
+                    step: OnboardingStep::Done,
+                    provider_skipped: false,
+                }
+            };
+            if let Err(e) = settings_manager.save_state(ONBOARDING_KEY, &state) {
+                tracing::warn!(error = %e, "Failed to save onboarding state");
+            }
+            state
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load onboarding state");
+            return;
+        }
+    };
+
+    if !state.is_done() {
+        tracing::info!(step = ?state.step, "Showing onboarding");
+        if let Err(e) = window::open_settings_window(app, Some(ONBOARDING_SECTION.to_string())) {
+            tracing::warn!(error = %e, "Failed to show onboarding");
+        }
+    }
+}
+
+/// Get the onboarding progress.
+#[tauri::command]
+pub fn get_onboarding_state(
+    settings_manager: State<SettingsManager>,
+) -> Result<OnboardingState, OnboardingError> {
+    Ok(load(&settings_manager)?)
+}
+
+/// Move to another onboarding step.
+///
+/// Leaving the provider step for `done` runs `validate_api_key` first and
+/// only moves on if the key works, unless `skip` is set.
+///
+/// # Arguments
+///
+/// * `step` - The next step, or any earlier one
+/// * `skip` - Leave the provider step without a working key (defaults to
+///   `false`)
+///
+/// # Returns
+///
+/// * `Ok(OnboardingState)` - The new state, already saved
+/// * `Err(OnboardingError)` - The move isn't allowed, the key was rejected
+///   or couldn't be checked, or the store failed
+#[tauri::command]
+pub async fn advance_onboarding(
+    settings_manager: State<'_, SettingsManager>,
+    step: OnboardingStep,
+    skip: Option<bool>,
+) -> Result<OnboardingState, OnboardingError> {
+    let state = load(&settings_manager)?;
+    let key = if !state.needs_key_check(step) {
+        None
+    } else if skip.unwrap_or(false) {
+        Some(KeyCheck::Skipped)
+    } else {
+        match llm::validate_api_key(settings_manager.clone()).await {
+            Ok(true) => Some(KeyCheck::Valid),
+            Ok(false) => Some(KeyCheck::Rejected),
+            Err(e) => return Err(OnboardingError::KeyCheckFailed(e.to_string())),
+        }
+    };
+
+    let state = state.advance(step, key)?;
+    settings_manager.save_state(ONBOARDING_KEY, &state)?;
+    Ok(state)
+}
+
+/// Finish onboarding from whatever step it is at.
+///
+/// # Returns
+///
+/// * `Ok(OnboardingState)` - The finished state, already saved
+/// * `Err(OnboardingError)` - The store couldn't be read or written
+#[tauri::command]
+pub fn complete_onboarding(
+    settings_manager: State<SettingsManager>,
+) -> Result<OnboardingState, OnboardingError> {
+    let state = load(&settings_manager)?.complete();
+    settings_manager.save_state(ONBOARDING_KEY, &state)?;
+    tracing::info!(provider_skipped = state.provider_skipped, "Onboarding done");
+    Ok(state)
+}
@@ -0,0 +1,321 @@
+//! Where the app keeps its files, and portable mode.
+//!
+//! Normally settings, logs, backups and staged updates live in the OS app
+//! data directory and the history database in the app config directory
+//! (where `tauri-plugin-sql` puts it). In portable mode, started with
+//! `--portable` or with a [`PORTABLE_FLAG`] file next to the executable,
+//! all of them live in a [`PORTABLE_DATA_DIR`] directory beside the
+//! executable instead, so the app can run from a USB stick. Autostart is
+//! off in portable mode: the executable's path changes between machines.
+//!
+//! The mode is decided once by [`init`], before the Tauri builder runs,
+//! since the SQL plugin's migrations are keyed by the database URL. Code
+//! that needs a directory goes through [`data_dir`] or [`database_dir`],
+//! never `app.path().app_data_dir()` directly.
+//!
+//! `history.database_path` overrides where the database file is, in either
+//! mode (see [`database_file`]). It's set by `move_history_db`; the SQL
+//! plugin is registered in setup, once the settings are loaded, so its
+//! migrations are keyed by [`configured_database_url`].
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! // 'sqlite:history.db', or an absolute path in portable mode or once moved
+//! const db = await Database.load(await invoke<string>('get_database_url'));
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::DATABASE_FILE;
+use crate::settings::SettingsManager;
+
+/// File next to the executable that turns on portable mode.
+pub const PORTABLE_FLAG: &str = "portable.flag";
+
+/// Directory next to the executable holding the data in portable mode.
+pub const PORTABLE_DATA_DIR: &str = "data";
+
+/// Where the app keeps its files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataMode {
+    /// The OS app data and config directories
+    Installed,
+    /// A directory beside the executable
+    Portable { root: PathBuf },
+}
+
+static MODE: OnceLock<DataMode> = OnceLock::new();
+
+/// Decide the mode for this process.
+///
+/// # Arguments
+///
+/// * `exe` - Path of the running executable
+/// * `portable_arg` - Whether `--portable` was passed
+pub fn detect(exe: &Path, portable_arg: bool) -> DataMode {
+    let Some(exe_dir) = exe.parent() else {
+        return DataMode::Installed;
+    };
+    if portable_arg || exe_dir.join(PORTABLE_FLAG).is_file() {
+        DataMode::Portable {
+            root: exe_dir.join(PORTABLE_DATA_DIR),
+        }
+    } else {
+        DataMode::Installed
+    }
+}
+
+/// Detect the mode from the running executable and remember it.
+///
+/// Call once at startup; later calls return the first result.
+pub fn init(portable_arg: bool) -> &'static DataMode {
+    MODE.get_or_init(|| match std::env::current_exe() {
+        Ok(exe) => detect(&exe, portable_arg),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to get executable path, portable mode is off");
+            DataMode::Installed
+        }
+    })
+}
+
+/// The mode decided by [`init`]; installed if it hasn't run.
+pub fn mode() -> &'static DataMode {
+    MODE.get().unwrap_or(&DataMode::Installed)
+}
+
+/// Whether the app runs in portable mode.
+pub fn is_portable() -> bool {
+    matches!(mode(), DataMode::Portable { .. })
+}
+
+/// Pick the directory for `mode`: the portable root, or `os_dir`.
+fn resolve(
+    mode: &DataMode,
+    os_dir: impl FnOnce() -> Result<PathBuf, String>,
+) -> Result<PathBuf, String> {
+    match mode {
+        DataMode::Portable { root } => Ok(root.clone()),
+        DataMode::Installed => os_dir(),
+    }
+}
+
+/// Create `dir` if needed.
+fn ensure(dir: PathBuf) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create data dir '{}': {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Resolve (and create) the directory for settings, logs, backups and
+/// staged updates.
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    ensure(resolve(mode(), || {
+        app.path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to get app data dir: {}", e))
+    })?)
+}
+
+/// Resolve (and create) the directory holding the history database.
+pub fn database_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    ensure(resolve(mode(), || {
+        app.path()
+            .app_config_dir()
+            .map_err(|e| format!("Failed to get app config dir: {}", e))
+    })?)
+}
+
+/// The configured database location, if `history.database_path` is set.
+fn configured_file(configured: Option<&str>) -> Option<PathBuf> {
+    configured
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// Resolve the history database file.
+///
+/// # Arguments
+///
+/// * `configured` - `history.database_path`; when unset the file is
+///   [`DATABASE_FILE`] in [`database_dir`]
+pub fn database_file(app: &AppHandle, configured: Option<&str>) -> Result<PathBuf, String> {
+    match configured_file(configured) {
+        Some(path) => Ok(path),
+        None => Ok(database_dir(app)?.join(DATABASE_FILE)),
+    }
+}
+
+/// The `tauri-plugin-sql` URL of the history database for `mode`.
+///
+/// The plugin resolves relative paths against the app config directory
+/// and leaves absolute ones alone.
+///
+/// # Arguments
+///
+/// * `mode` - Where the app keeps its files
+/// * `configured` - `history.database_path`, which wins over `mode`
+pub fn database_url(mode: &DataMode, configured: Option<&str>) -> String {
+    if let Some(path) = configured_file(configured) {
+        return format!("sqlite:{}", path.display());
+    }
+    match mode {
+        DataMode::Installed => format!("sqlite:{}", DATABASE_FILE),
+        DataMode::Portable { root } => {
+            format!("sqlite:{}", root.join(DATABASE_FILE).display())
+        }
+    }
+}
+
+/// The `tauri-plugin-sql` URL of the history database with the current
+/// `history.database_path`.
+pub fn configured_database_url(settings_manager: &SettingsManager) -> String {
+    let settings = settings_manager.snapshot().ok();
+    database_url(
+        mode(),
+        settings
+            .as_ref()
+            .and_then(|settings| settings.history.database_path.as_deref()),
+    )
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// The URL to load the history database with through `tauri-plugin-sql`.
+///
+/// The one its migrations are registered under, unless the database was
+/// moved with `move_history_db` since the app started.
+#[tauri::command]
+pub fn get_database_url(settings_manager: State<SettingsManager>) -> String {
+    configured_database_url(&settings_manager)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exe_in(dir: &Path) -> PathBuf {
+        dir.join("qwik-ask")
+    }
+
+    // ===== Detection =====
+
+    #[test]
+    fn test_installed_without_flag_or_arg() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(detect(&exe_in(dir.path()), false), DataMode::Installed);
+    }
+
+    #[test]
+    fn test_flag_file_enables_portable() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(PORTABLE_FLAG), "").unwrap();
+
+        assert_eq!(
+            detect(&exe_in(dir.path()), false),
+            DataMode::Portable {
+                root: dir.path().join(PORTABLE_DATA_DIR)
+            }
+        );
+    }
+
+    #[test]
+    fn test_arg_enables_portable() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            detect(&exe_in(dir.path()), true),
+            DataMode::Portable {
+                root: dir.path().join(PORTABLE_DATA_DIR)
+            }
+        );
+    }
+
+    #[test]
+    fn test_flag_directory_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(PORTABLE_FLAG)).unwrap();
+
+        assert_eq!(detect(&exe_in(dir.path()), false), DataMode::Installed);
+    }
+
+    // ===== Resolution =====
+
+    #[test]
+    fn test_installed_uses_os_dir() {
+        let dir = resolve(&DataMode::Installed, || {
+            Ok(PathBuf::from("/home/me/.local/share/qwik-ask"))
+        });
+
+        assert_eq!(dir, Ok(PathBuf::from("/home/me/.local/share/qwik-ask")));
+    }
+
+    #[test]
+    fn test_installed_passes_on_os_error() {
+        let dir = resolve(&DataMode::Installed, || Err("no home".to_string()));
+
+        assert_eq!(dir, Err("no home".to_string()));
+    }
+
+    #[test]
+    fn test_portable_ignores_os_dir() {
+        let mode = DataMode::Portable {
+            root: PathBuf::from("/media/usb/qwik-ask/data"),
+        };
+
+        let dir = resolve(&mode, || panic!("asked for the OS dir in portable mode"));
+
+        assert_eq!(dir, Ok(PathBuf::from("/media/usb/qwik-ask/data")));
+    }
+
+    #[test]
+    fn test_ensure_creates_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join(PORTABLE_DATA_DIR);
+
+        assert_eq!(ensure(data.clone()), Ok(data.clone()));
+        assert!(data.is_dir());
+    }
+
+    #[test]
+    fn test_database_url() {
+        let portable = DataMode::Portable {
+            root: PathBuf::from("/media/usb/qwik-ask/data"),
+        };
+
+        assert_eq!(
+            database_url(&DataMode::Installed, None),
+            "sqlite:history.db"
+        );
+        assert_eq!(
+            database_url(&portable, None),
+            format!(
+                "sqlite:{}",
+                Path::new("/media/usb/qwik-ask/data/history.db").display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_configured_database_wins() {
+        let portable = DataMode::Portable {
+            root: PathBuf::from("/media/usb/qwik-ask/data"),
+        };
+        let moved = Path::new("/mnt/d/qwik-ask/history.db");
+
+        assert_eq!(
+            database_url(&portable, moved.to_str()),
+            format!("sqlite:{}", moved.display())
+        );
+        assert_eq!(
+            database_url(&DataMode::Installed, Some(" ")),
+            "sqlite:history.db"
+        );
+    }
+}
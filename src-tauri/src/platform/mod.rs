@@ -0,0 +1,302 @@
+//! Desktop session detection and what it means for the global shortcut.
+//!
+//! On Wayland, apps can't grab keys themselves, so registering the toggle
+//! shortcut fails (most visibly on GNOME). When it does:
+//!
+//! 1. Left-clicking the tray icon toggles the launcher instead of following
+//!    `general.tray_left_click`, so the app stays usable
+//! 2. On Wayland, the shortcut is bound through the XDG GlobalShortcuts
+//!    portal where the desktop has one (see [`portal`])
+//! 3. If that fails too, or the session isn't Wayland, `shortcut-unavailable`
+//!    is emitted with the reason and what to do instead
+//!
+//! [`ShortcutCapability`] records the outcome for the settings page.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const capability = await invoke<ShortcutCapability>('get_shortcut_capability');
+//! // { kind: 'native' } | { kind: 'portal' } | { kind: 'unavailable', reason: '...' }
+//!
+//! await listen<ShortcutUnavailable>('shortcut-unavailable', ({ payload }) => {
+//!   showBanner(payload.reason, payload.instructions);
+//! });
+//! ```
+
+pub mod portal;
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::i18n::t;
+
+/// The session variables that tell X11 and Wayland apart.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionEnv {
+    /// `XDG_SESSION_TYPE`
+    pub session_type: Option<String>,
+    /// `WAYLAND_DISPLAY`
+    pub wayland_display: Option<String>,
+}
+
+impl SessionEnv {
+    /// Read the variables from the process environment.
+    pub fn from_env() -> Self {
+        Self {
+            session_type: std::env::var("XDG_SESSION_TYPE").ok(),
+            wayland_display: std::env::var("WAYLAND_DISPLAY").ok(),
+        }
+    }
+
+    /// Whether this is a Wayland session.
+    ///
+    /// `XDG_SESSION_TYPE` decides when set; otherwise a `WAYLAND_DISPLAY`
+    /// means Wayland.
+    pub fn is_wayland(&self) -> bool {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        match non_empty(&self.session_type) {
+            Some(session_type) => session_type.eq_ignore_ascii_case("wayland"),
+            None => non_empty(&self.wayland_display).is_some(),
+        }
+    }
+}
+
+/// Whether the toggle shortcut works, for the settings page.
+///
+/// Serializes with a `kind` tag: `{ kind: 'unavailable', reason: '...' }`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ShortcutCapability {
+    /// Registered with the OS
+    #[default]
+    Native,
+    /// Bound through the XDG GlobalShortcuts portal; the desktop decides
+    /// the keys
+    Portal,
+    /// Not registered; the tray icon toggles the launcher instead
+    Unavailable { reason: String },
+}
+
+/// Payload of the `shortcut-unavailable` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShortcutUnavailable {
+    /// Why the shortcut doesn't work
+    pub reason: String,
+    /// What the user can do instead
+    pub instructions: String,
+}
+
+/// The capability after trying to register the shortcut natively.
+///
+/// # Arguments
+///
+/// * `env` - The session variables
+/// * `registration` - Result of registering the toggle shortcut
+pub fn capability(env: &SessionEnv, registration: &Result<(), String>) -> ShortcutCapability {
+    match registration {
+        Ok(()) => ShortcutCapability::Native,
+        Err(_) if env.is_wayland() => ShortcutCapability::Unavailable {
+            reason: t("platform.wayland_reason", &[]),
+        },
+        Err(e) => ShortcutCapability::Unavailable { reason: e.clone() },
+    }
+}
+
+/// What to tell the user when the shortcut is unavailable.
+pub fn unavailable_notice(env: &SessionEnv, reason: &str) -> ShortcutUnavailable {
+    let instructions = if env.is_wayland() {
+        t("platform.instructions_wayland", &[])
+    } else {
+        t("platform.instructions_other", &[])
+    };
+    ShortcutUnavailable {
+        reason: reason.to_string(),
+        instructions,
+    }
+}
+
+/// The current [`ShortcutCapability`], managed as Tauri state.
+#[derive(Default)]
+pub struct ShortcutStatus(Mutex<ShortcutCapability>);
+
+impl ShortcutStatus {
+    pub fn get(&self) -> ShortcutCapability {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn set(&self, capability: ShortcutCapability) {
+        *self.0.lock().unwrap() = capability;
+    }
+}
+
+/// Whether the toggle shortcut is unavailable, so the tray icon should
+/// toggle the launcher.
+pub fn shortcut_unavailable(app: &AppHandle) -> bool {
+    app.try_state::<ShortcutStatus>()
+        .is_some_and(|status| matches!(status.get(), ShortcutCapability::Unavailable { .. }))
+}
+
+/// Act on the result of registering the toggle shortcut at startup.
+///
+/// Nothing happens if it was registered. Otherwise the tray fallback takes
+/// over and, on Wayland, the portal is tried on a background thread;
+/// `shortcut-unavailable` is emitted once nothing else is left.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle
+/// * `shortcut` - The toggle shortcut that was registered
+/// * `registration` - Result of registering it
+pub fn handle_registration(app: &AppHandle, shortcut: &str, registration: Result<(), String>) {
+    let env = SessionEnv::from_env();
+    let capability = capability(&env, &registration);
+    let ShortcutCapability::Unavailable { reason } = &capability else {
+        return;
+    };
+    tracing::warn!(
+        wayland = env.is_wayland(),
+        reason = %reason,
+        "Global shortcut unavailable; the tray icon toggles the launcher"
+    );
+    let notice = unavailable_notice(&env, reason);
+    app.state::<ShortcutStatus>().set(capability);
+
+    if env.is_wayland() {
+        bind_through_portal(app.clone(), shortcut.to_string(), notice);
+    } else {
+        let _ = app.emit("shortcut-unavailable", notice);
+    }
+}
+
+/// Bind the shortcut through the portal, emitting `notice` if that fails.
+#[cfg(target_os = "linux")]
+fn bind_through_portal(app: AppHandle, shortcut: String, notice: ShortcutUnavailable) {
+    std::thread::spawn(move || match portal::bind(&shortcut) {
+        Ok(bound) => {
+            tracing::info!("Bound the shortcut through the global shortcuts portal");
+            app.state::<ShortcutStatus>()
+                .set(ShortcutCapability::Portal);
+            bound.listen(|| {
+                if crate::window::toggle_launcher(&app)
+                    == Some(crate::window::toggle::ShortcutAction::Show)
+                {
+                    crate::history::restore_last_conversation(&app);
+                }
+            });
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Global shortcuts portal unavailable");
+            let _ = app.emit("shortcut-unavailable", notice);
+        }
+    });
+}
+
+#[cfg(not(target_os = "linux"))]
+fn bind_through_portal(app: AppHandle, _shortcut: String, notice: ShortcutUnavailable) {
+    let _ = app.emit("shortcut-unavailable", notice);
+}
+
+/// Whether the toggle shortcut works, and why not.
+#[tauri::command]
+pub fn get_shortcut_capability(status: State<ShortcutStatus>) -> ShortcutCapability {
+    status.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(session_type: Option<&str>, wayland_display: Option<&str>) -> SessionEnv {
+        SessionEnv {
+            session_type: session_type.map(str::to_string),
+            wayland_display: wayland_display.map(str::to_string),
+        }
+    }
+
+    // ===== Session =====
+
+    #[test]
+    fn test_session_type_decides() {
+        assert!(env(Some("wayland"), None).is_wayland());
+        assert!(env(Some("Wayland"), Some("wayland-0")).is_wayland());
+        assert!(!env(Some("x11"), Some("wayland-0")).is_wayland());
+        assert!(!env(Some("tty"), None).is_wayland());
+    }
+
+    #[test]
+    fn test_wayland_display_without_session_type() {
+        assert!(env(None, Some("wayland-0")).is_wayland());
+        assert!(env(Some(""), Some("wayland-1")).is_wayland());
+        assert!(!env(None, Some(" ")).is_wayland());
+        assert!(!env(None, None).is_wayland());
+    }
+
+    // ===== Capability =====
+
+    #[test]
+    fn test_registered_shortcut_is_native() {
+        assert_eq!(
+            capability(&env(Some("wayland"), None), &Ok(())),
+            ShortcutCapability::Native
+        );
+    }
+
+    #[test]
+    fn test_failure_on_wayland_explains_wayland() {
+        let failed = Err("Failed to register shortcut 'Alt+Shift+Space': denied".to_string());
+
+        assert_eq!(
+            capability(&env(Some("wayland"), None), &failed),
+            ShortcutCapability::Unavailable {
+                reason: t("platform.wayland_reason", &[])
+            }
+        );
+    }
+
+    #[test]
+    fn test_failure_elsewhere_keeps_error() {
+        let failed = Err("Failed to register shortcut 'Alt+Shift+Space': in use".to_string());
+
+        assert_eq!(
+            capability(&env(Some("x11"), None), &failed),
+            ShortcutCapability::Unavailable {
+                reason: "Failed to register shortcut 'Alt+Shift+Space': in use".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_notice_instructions() {
+        let wayland = unavailable_notice(&env(Some("wayland"), None), "no portal");
+        let x11 = unavailable_notice(&env(Some("x11"), None), "in use");
+
+        assert!(wayland.instructions.contains("portal"));
+        assert!(x11.instructions.contains("different shortcut"));
+        assert_eq!(x11.reason, "in use");
+    }
+
+    // ===== Serialization =====
+
+    #[test]
+    fn test_capability_serialization() {
+        assert_eq!(
+            serde_json::to_value(ShortcutCapability::Native).unwrap(),
+            serde_json::json!({ "kind": "native" })
+        );
+        assert_eq!(
+            serde_json::to_value(ShortcutCapability::Unavailable {
+                reason: "no".to_string()
+            })
+            .unwrap(),
+            serde_json::json!({ "kind": "unavailable", "reason": "no" })
+        );
+    }
+}
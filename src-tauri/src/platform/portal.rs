@@ -0,0 +1,225 @@
+//! The XDG GlobalShortcuts portal (Linux).
+//!
+//! Wayland compositors don't let apps grab keys themselves, but desktops
+//! that implement `org.freedesktop.portal.GlobalShortcuts` (KDE Plasma,
+//! GNOME 48 and later) bind shortcuts on an app's behalf. The desktop may
+//! ask the user to confirm the binding, and has the final say on which
+//! keys trigger it.
+//!
+//! Every portal call returns a request object whose `Response` signal
+//! carries the result; the request path is predictable from the
+//! connection name and a token, so the signal is subscribed to before the
+//! call is made.
+
+/// Portal shortcut ID of the launcher toggle.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub const TOGGLE_ID: &str = "toggle-launcher";
+
+/// A shortcut like `"Alt+Shift+Space"` in the portal's trigger format
+/// (`"ALT+SHIFT+space"`), offered as the preferred trigger.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn preferred_trigger(shortcut: &str) -> String {
+    shortcut
+        .split('+')
+        .map(str::trim)
+        .map(|part| match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" | "commandorcontrol" | "cmdorctrl" => "CTRL".to_string(),
+            "alt" | "option" => "ALT".to_string(),
+            "shift" => "SHIFT".to_string(),
+            "super" | "meta" | "cmd" | "command" => "LOGO".to_string(),
+            key if key.chars().count() == 1 || key == "space" => key.to_string(),
+            _ => part.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::bind;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::HashMap;
+
+    use zbus::blocking::proxy::SignalIterator;
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::{DynamicType, ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+    use super::{preferred_trigger, TOGGLE_ID};
+
+    const DESTINATION: &str = "org.freedesktop.portal.Desktop";
+    const PATH: &str = "/org/freedesktop/portal/desktop";
+    const INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+
+    /// `Response` code of a successful request.
+    const RESPONSE_SUCCESS: u32 = 0;
+
+    fn error(e: zbus::Error) -> String {
+        format!("Global shortcuts portal failed: {}", e)
+    }
+
+    /// A shortcut bound through the portal.
+    pub struct PortalShortcut {
+        session: OwnedObjectPath,
+        activated: SignalIterator<'static>,
+        // Closing the connection ends the session and unbinds the shortcut
+        _connection: Connection,
+    }
+
+    impl PortalShortcut {
+        /// Call `on_activated` each time the shortcut is pressed.
+        ///
+        /// Blocks for as long as the session lives, so call it from a
+        /// background thread.
+        pub fn listen(self, on_activated: impl Fn()) {
+            for message in self.activated {
+                let Ok((session, id, _timestamp, _options)) =
+                    message
+                        .body()
+                        .deserialize::<(OwnedObjectPath, String, u64, HashMap<String, OwnedValue>)>(
+                        )
+                else {
+                    continue;
+                };
+                if session == self.session && id == TOGGLE_ID {
+                    on_activated();
+                }
+            }
+        }
+    }
+
+    /// Bind the launcher toggle through the portal.
+    ///
+    /// Blocks until the desktop has answered, which may include the user
+    /// confirming a dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `shortcut` - The `shortcuts.toggle_launcher` setting, offered as
+    ///   the preferred trigger
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PortalShortcut)` - Bound; call [`PortalShortcut::listen`]
+    /// * `Err(String)` - No portal, or the binding was refused
+    pub fn bind(shortcut: &str) -> Result<PortalShortcut, String> {
+        let connection = Connection::session().map_err(error)?;
+        let portal = Proxy::new(&connection, DESTINATION, PATH, INTERFACE).map_err(error)?;
+        // Fails when the desktop's portal doesn't implement the interface
+        let version: u32 = portal.get_property("version").map_err(error)?;
+        tracing::info!(version, "Using the global shortcuts portal");
+
+        let token = format!("qwikask{}", std::process::id());
+        let session = {
+            let options: HashMap<&str, Value> = HashMap::from([
+                ("handle_token", Value::from(format!("{}_session", token))),
+                ("session_handle_token", Value::from(token.clone())),
+            ]);
+            let results = request(
+                &connection,
+                &portal,
+                "CreateSession",
+                &format!("{}_session", token),
+                &(options,),
+            )?;
+            session_handle(&results)?
+        };
+
+        let activated = portal.receive_signal("Activated").map_err(error)?;
+        let shortcuts = vec![(
+            TOGGLE_ID,
+            HashMap::from([
+                ("description", Value::from("Show or hide Qwik Ask")),
+                (
+                    "preferred_trigger",
+                    Value::from(preferred_trigger(shortcut)),
+                ),
+            ]),
+        )];
+        let options: HashMap<&str, Value> =
+            HashMap::from([("handle_token", Value::from(format!("{}_bind", token)))]);
+        request(
+            &connection,
+            &portal,
+            "BindShortcuts",
+            &format!("{}_bind", token),
+            &(session.as_ref(), shortcuts, "", options),
+        )?;
+
+        Ok(PortalShortcut {
+            session,
+            activated,
+            _connection: connection,
+        })
+    }
+
+    /// Call a portal method and wait for its `Response`.
+    fn request<B>(
+        connection: &Connection,
+        portal: &Proxy,
+        method: &'static str,
+        handle_token: &str,
+        body: &B,
+    ) -> Result<HashMap<String, OwnedValue>, String>
+    where
+        B: serde::Serialize + DynamicType,
+    {
+        let sender = connection
+            .unique_name()
+            .ok_or("Global shortcuts portal failed: no bus name")?
+            .trim_start_matches(':')
+            .replace('.', "_");
+        let path = format!("{}/request/{}/{}", PATH, sender, handle_token);
+        let request = Proxy::new(
+            connection,
+            DESTINATION,
+            ObjectPath::try_from(path).map_err(|e| error(e.into()))?,
+            "org.freedesktop.portal.Request",
+        )
+        .map_err(error)?;
+        let mut responses = request.receive_signal("Response").map_err(error)?;
+
+        let _handle: OwnedObjectPath = portal.call(method, body).map_err(error)?;
+
+        let response = responses
+            .next()
+            .ok_or("Global shortcuts portal closed the request")?;
+        let (code, results) = response
+            .body()
+            .deserialize::<(u32, HashMap<String, OwnedValue>)>()
+            .map_err(error)?;
+        if code != RESPONSE_SUCCESS {
+            return Err(format!("{} was refused (response {})", method, code));
+        }
+        Ok(results)
+    }
+
+    /// The session from a `CreateSession` response; a string per the
+    /// spec, an object path from some implementations.
+    fn session_handle(results: &HashMap<String, OwnedValue>) -> Result<OwnedObjectPath, String> {
+        let value = results
+            .get("session_handle")
+            .ok_or("Global shortcuts portal returned no session")?;
+        if let Ok(path) = <&str>::try_from(value) {
+            return OwnedObjectPath::try_from(path.to_string()).map_err(|e| error(e.into()));
+        }
+        <&ObjectPath>::try_from(value)
+            .map(|path| OwnedObjectPath::from(path.to_owned()))
+            .map_err(|e| error(e.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===== Triggers =====
+
+    #[test]
+    fn test_preferred_trigger() {
+        assert_eq!(preferred_trigger("Alt+Shift+Space"), "ALT+SHIFT+space");
+        assert_eq!(preferred_trigger("Ctrl+K"), "CTRL+k");
+        assert_eq!(preferred_trigger("Super+F12"), "LOGO+F12");
+        assert_eq!(preferred_trigger("CommandOrControl+Alt+A"), "CTRL+ALT+a");
+    }
+}
@@ -0,0 +1,139 @@
+//! Prompt library: saved system prompts ("personas").
+//!
+//! Prompts are stored in the history database (see [`store`]). The
+//! `llm.active_prompt_id` setting picks the one sent with each request,
+//! resolved when the request is built; without it, or if the prompt is
+//! gone, the inline `llm.system_prompt` is used as before. A conversation's
+//! own system prompt (`set_conversation_system_prompt`) beats both.
+//!
+//! The built-in "Quick Assist" prompt can't be edited or deleted, only
+//! duplicated.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const prompts = await invoke<Prompt[]>('list_prompts');
+//! const saved = await invoke<Prompt>('upsert_prompt', {
+//!   prompt: { name: 'Translator', content: 'Translate everything to French.' },
+//! });
+//! const copy = await invoke<Prompt>('duplicate_prompt', { id: 'default' });
+//! await invoke('delete_prompt', { id: saved.id });
+//! // Errors with { kind: 'builtin' } for built-in prompts
+//!
+//! // Switch persona; `null` goes back to the inline system prompt
+//! await invoke('set_active_prompt', { id: copy.id });
+//! await listen<PromptChanged>('prompt-changed', ({ payload }) => {
+//!   // { prompt_id: '0192...' }
+//! });
+//! ```
+
+pub mod store;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::db::{now_ms, Db};
+use crate::settings::SettingsManager;
+pub use store::{resolve_system_prompt, Prompt, PromptError, PromptInput};
+
+/// Payload of the `prompt-changed` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PromptChanged {
+    /// The active prompt, or `None` for the inline system prompt
+    pub prompt_id: Option<String>,
+}
+
+/// Save `llm.active_prompt_id` and tell every window.
+async fn save_active(
+    app: &AppHandle,
+    settings_manager: &SettingsManager,
+    prompt_id: Option<String>,
+) -> Result<(), PromptError> {
+    settings_manager
+        .update(|settings| {
+            settings.llm.active_prompt_id = prompt_id.clone();
+            Ok(())
+        })
+        .await?;
+    tracing::info!(prompt_id = ?prompt_id, "Active prompt changed");
+    let _ = app.emit("prompt-changed", PromptChanged { prompt_id });
+    Ok(())
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// List the saved prompts, built-in ones first.
+#[tauri::command]
+pub async fn list_prompts(db: State<'_, Db>) -> Result<Vec<Prompt>, PromptError> {
+    store::list(db.pool()).await
+}
+
+/// Create or update a prompt.
+///
+/// # Arguments
+///
+/// * `prompt` - Prompt to save; without an id a new one is created
+///
+/// # Returns
+///
+/// * `Ok(Prompt)` - The saved prompt, with its id
+/// * `Err(PromptError)` - The name is blank, the prompt is built in, or it
+///   doesn't exist
+#[tauri::command]
+pub async fn upsert_prompt(db: State<'_, Db>, prompt: PromptInput) -> Result<Prompt, PromptError> {
+    store::upsert(db.pool(), prompt, now_ms()).await
+}
+
+/// Copy a prompt, typically a built-in one, into an editable prompt.
+#[tauri::command]
+pub async fn duplicate_prompt(db: State<'_, Db>, id: String) -> Result<Prompt, PromptError> {
+    store::duplicate(db.pool(), &id, now_ms()).await
+}
+
+/// Delete a prompt.
+///
+/// Deleting the active prompt switches back to the inline system prompt.
+///
+/// # Returns
+///
+/// * `Ok(())` - Deleted
+/// * `Err(PromptError)` - The prompt is built in or doesn't exist
+#[tauri::command]
+pub async fn delete_prompt(
+    app: AppHandle,
+    settings_manager: State<'_, SettingsManager>,
+    db: State<'_, Db>,
+    id: String,
+) -> Result<(), PromptError> {
+    store::delete(db.pool(), &id).await?;
+    if settings_manager.snapshot()?.llm.active_prompt_id.as_deref() == Some(id.as_str()) {
+        save_active(&app, &settings_manager, None).await?;
+    }
+    Ok(())
+}
+
+/// Switch the prompt sent with requests. Emits `prompt-changed`.
+///
+/// # Arguments
+///
+/// * `id` - Prompt to use, or `None` for the inline `llm.system_prompt`
+///
+/// # Returns
+///
+/// * `Ok(())` - Saved
+/// * `Err(PromptError)` - No prompt has this id, or settings couldn't be
+///   saved
+#[tauri::command]
+pub async fn set_active_prompt(
+    app: AppHandle,
+    settings_manager: State<'_, SettingsManager>,
+    db: State<'_, Db>,
+    id: Option<String>,
+) -> Result<(), PromptError> {
+    if let Some(id) = &id {
+        store::get(db.pool(), id).await?;
+    }
+    save_active(&app, &settings_manager, id).await
+}
@@ -0,0 +1,503 @@
+//! Prompt library storage.
+//!
+//! Prompts live in the `prompts` table (migration 13). Built-in prompts
+//! ship with the app and can't be edited or deleted; [`duplicate`] makes an
+//! editable copy instead.
+
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use thiserror::Error;
+
+use crate::settings::LlmSettings;
+
+/// A saved system prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Prompt {
+    pub id: String,
+    /// Name shown in the persona menus
+    pub name: String,
+    /// The system prompt text
+    pub content: String,
+    /// Shipped with the app; can't be edited or deleted
+    pub is_builtin: bool,
+    /// Unix timestamp (ms)
+    pub created_at: i64,
+    /// Unix timestamp (ms)
+    pub updated_at: i64,
+}
+
+/// A prompt to create or update.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PromptInput {
+    /// Prompt to update; `None` creates a new one
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    pub content: String,
+}
+
+/// Why a prompt library operation failed.
+///
+/// Serializes with a `kind` tag like [`crate::history::HistoryError`].
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum PromptError {
+    /// No prompt with the given ID
+    #[error("Prompt not found: {0}")]
+    NotFound(String),
+    /// The prompt is built in; duplicate it to make changes
+    #[error("Built-in prompts can't be changed or deleted: {0}")]
+    Builtin(String),
+    /// Name empty after trimming
+    #[error("Prompt name can't be empty")]
+    EmptyName,
+    /// SQLite error
+    #[error("Database error: {0}")]
+    Database(String),
+    /// Unexpected failure outside the database (e.g. saving settings)
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl From<String> for PromptError {
+    fn from(message: String) -> Self {
+        Self::Internal(message)
+    }
+}
+
+impl From<sqlx::Error> for PromptError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error.to_string())
+    }
+}
+
+/// Every prompt, built-in ones first, then by name.
+pub async fn list(pool: &SqlitePool) -> Result<Vec<Prompt>, PromptError> {
+    let rows = sqlx::query(
+        "SELECT id, name, content, is_builtin, created_at, updated_at
+         FROM prompts
+         ORDER BY is_builtin DESC, name COLLATE NOCASE, created_at",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(prompt_from_row).collect())
+}
+
+/// A prompt by ID.
+///
+/// # Returns
+///
+/// * `Ok(Prompt)` - The prompt
+/// * `Err(PromptError::NotFound)` - No prompt has this ID
+pub async fn get(pool: &SqlitePool, id: &str) -> Result<Prompt, PromptError> {
+    find(pool, id)
+        .await?
+        .ok_or_else(|| PromptError::NotFound(id.to_string()))
+}
+
+async fn find(pool: &SqlitePool, id: &str) -> Result<Option<Prompt>, PromptError> {
+    let row = sqlx::query(
+        "SELECT id, name, content, is_builtin, created_at, updated_at
+         FROM prompts WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.as_ref().map(prompt_from_row))
+}
+
+/// Create a prompt, or update the one with `input.id`.
+///
+/// # Returns
+///
+/// * `Ok(Prompt)` - The saved prompt, with its ID
+/// * `Err(PromptError::Builtin)` - `input.id` is a built-in prompt
+/// * `Err(PromptError::NotFound)` - `input.id` doesn't exist
+/// * `Err(PromptError::EmptyName)` - The name is blank
+pub async fn upsert(
+    pool: &SqlitePool,
+    input: PromptInput,
+    now_ms: i64,
+) -> Result<Prompt, PromptError> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err(PromptError::EmptyName);
+    }
+
+    let Some(id) = input.id else {
+        let id = uuid::Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO prompts (id, name, content, is_builtin, created_at, updated_at)
+             VALUES (?, ?, ?, 0, ?, ?)",
+        )
+        .bind(&id)
+        .bind(&name)
+        .bind(&input.content)
+        .bind(now_ms)
+        .bind(now_ms)
+        .execute(pool)
+        .await?;
+        return get(pool, &id).await;
+    };
+
+    if get(pool, &id).await?.is_builtin {
+        return Err(PromptError::Builtin(id));
+    }
+    sqlx::query("UPDATE prompts SET name = ?, content = ?, updated_at = ? WHERE id = ?")
+        .bind(&name)
+        .bind(&input.content)
+        .bind(now_ms)
+        .bind(&id)
+        .execute(pool)
+        .await?;
+    get(pool, &id).await
+}
+
+/// Copy a prompt into a new, editable one named "<name> (copy)".
+pub async fn duplicate(pool: &SqlitePool, id: &str, now_ms: i64) -> Result<Prompt, PromptError> {
+    let original = get(pool, id).await?;
+    upsert(
+        pool,
+        PromptInput {
+            id: None,
+            name: format!("{} (copy)", original.name),
+            content: original.content,
+        },
+        now_ms,
+    )
+    .await
+}
+
+/// Delete a prompt.
+///
+/// # Returns
+///
+/// * `Ok(())` - Deleted
+/// * `Err(PromptError::Builtin)` - The prompt is built in
+/// * `Err(PromptError::NotFound)` - No prompt has this ID
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), PromptError> {
+    if get(pool, id).await?.is_builtin {
+        return Err(PromptError::Builtin(id.to_string()));
+    }
+    sqlx::query("DELETE FROM prompts WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The system prompt to send with a request.
+///
+/// In order of preference:
+///
+/// 1. The conversation's `system_prompt_override`, if it has one
+/// 2. The content of `llm.active_prompt_id` if set and still in the library
+/// 3. The inline `llm.system_prompt`, which is all settings saved before the
+///    library existed have
+///
+/// The `llm.response_language` instruction is appended to whichever wins
+/// when the request is built.
+///
+/// # Arguments
+///
+/// * `pool` - History database pool
+/// * `settings` - LLM settings
+/// * `conversation_id` - Conversation the request belongs to, if any
+pub async fn resolve_system_prompt(
+    pool: &SqlitePool,
+    settings: &LlmSettings,
+    conversation_id: Option<&str>,
+) -> String {
+    if let Some(conversation_id) = conversation_id {
+        match conversation_override(pool, conversation_id).await {
+            Ok(Some(prompt)) => return prompt,
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to load conversation system prompt");
+            }
+        }
+    }
+
+    let Some(id) = settings.active_prompt_id.as_deref() else {
+        return settings.system_prompt.clone();
+    };
+    match find(pool, id).await {
+        Ok(Some(prompt)) => prompt.content,
+        Ok(None) => {
+            tracing::warn!(
+                prompt_id = id,
+                "Active prompt is missing; using system_prompt"
+            );
+            settings.system_prompt.clone()
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load active prompt; using system_prompt");
+            settings.system_prompt.clone()
+        }
+    }
+}
+
+/// A conversation's `system_prompt_override`; `None` if it has none or
+/// doesn't exist.
+async fn conversation_override(
+    pool: &SqlitePool,
+    conversation_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let prompt: Option<Option<String>> =
+        sqlx::query_scalar("SELECT system_prompt_override FROM conversations WHERE id = ?")
+            .bind(conversation_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(prompt.flatten())
+}
+
+fn prompt_from_row(row: &SqliteRow) -> Prompt {
+    Prompt {
+        id: row.get("id"),
+        name: row.get("name"),
+        content: row.get("content"),
+        is_builtin: row.get("is_builtin"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use crate::history::store;
+    use crate::llm::types::LlmRequest;
+
+    /// The built-in prompt seeded by the migration
+    const DEFAULT_PROMPT_ID: &str = "default";
+
+    fn default_system_prompt() -> String {
+        LlmSettings::default().system_prompt
+    }
+
+    fn input(id: Option<&str>, name: &str, content: &str) -> PromptInput {
+        PromptInput {
+            id: id.map(str::to_string),
+            name: name.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    // ===== Seeding =====
+
+    #[tokio::test]
+    async fn test_default_prompt_is_seeded() {
+        let db = Db::in_memory().await.unwrap();
+
+        let prompts = list(db.pool()).await.unwrap();
+
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].id, DEFAULT_PROMPT_ID);
+        assert_eq!(prompts[0].content, default_system_prompt());
+        assert!(prompts[0].is_builtin);
+    }
+
+    // ===== CRUD =====
+
+    #[tokio::test]
+    async fn test_create_and_update() {
+        let db = Db::in_memory().await.unwrap();
+
+        let created = upsert(db.pool(), input(None, " Translator ", "Translate"), 10)
+            .await
+            .unwrap();
+        let updated = upsert(
+            db.pool(),
+            input(Some(&created.id), "Translator", "Translate to French"),
+            20,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(created.name, "Translator");
+        assert!(!created.is_builtin);
+        assert_eq!(updated.content, "Translate to French");
+        assert_eq!((updated.created_at, updated.updated_at), (10, 20));
+        assert_eq!(list(db.pool()).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_upserts() {
+        let db = Db::in_memory().await.unwrap();
+
+        assert_eq!(
+            upsert(db.pool(), input(None, "  ", "x"), 0).await,
+            Err(PromptError::EmptyName)
+        );
+        assert_eq!(
+            upsert(db.pool(), input(Some("missing"), "x", "x"), 0).await,
+            Err(PromptError::NotFound("missing".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete() {
+        let db = Db::in_memory().await.unwrap();
+        let prompt = upsert(db.pool(), input(None, "Coder", "Write code"), 0)
+            .await
+            .unwrap();
+
+        delete(db.pool(), &prompt.id).await.unwrap();
+
+        assert_eq!(
+            get(db.pool(), &prompt.id).await,
+            Err(PromptError::NotFound(prompt.id))
+        );
+    }
+
+    // ===== Built-in protection =====
+
+    #[tokio::test]
+    async fn test_builtin_cannot_be_deleted_or_edited() {
+        let db = Db::in_memory().await.unwrap();
+        let builtin = Err(PromptError::Builtin(DEFAULT_PROMPT_ID.to_string()));
+
+        assert_eq!(delete(db.pool(), DEFAULT_PROMPT_ID).await, builtin);
+        assert_eq!(
+            upsert(db.pool(), input(Some(DEFAULT_PROMPT_ID), "Mine", "x"), 0)
+                .await
+                .map(|_| ()),
+            builtin
+        );
+        assert_eq!(
+            get(db.pool(), DEFAULT_PROMPT_ID).await.unwrap().content,
+            default_system_prompt()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_builtin() {
+        let db = Db::in_memory().await.unwrap();
+
+        let copy = duplicate(db.pool(), DEFAULT_PROMPT_ID, 5).await.unwrap();
+
+        assert_ne!(copy.id, DEFAULT_PROMPT_ID);
+        assert_eq!(copy.name, "Quick Assist (copy)");
+        assert_eq!(copy.content, default_system_prompt());
+        assert!(!copy.is_builtin);
+        delete(db.pool(), &copy.id).await.unwrap();
+    }
+
+    // ===== Resolving =====
+
+    #[tokio::test]
+    async fn test_resolve_active_prompt() {
+        let db = Db::in_memory().await.unwrap();
+        let prompt = upsert(db.pool(), input(None, "Pirate", "Talk like a pirate"), 0)
+            .await
+            .unwrap();
+        let settings = LlmSettings {
+            system_prompt: "Inline".to_string(),
+            active_prompt_id: Some(prompt.id),
+            ..LlmSettings::default()
+        };
+
+        assert_eq!(
+            resolve_system_prompt(db.pool(), &settings, None).await,
+            "Talk like a pirate"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_falls_back_to_inline_prompt() {
+        let db = Db::in_memory().await.unwrap();
+        let mut settings = LlmSettings {
+            system_prompt: "Inline".to_string(),
+            ..LlmSettings::default()
+        };
+
+        assert_eq!(
+            resolve_system_prompt(db.pool(), &settings, None).await,
+            "Inline"
+        );
+
+        settings.active_prompt_id = Some("deleted".to_string());
+        assert_eq!(
+            resolve_system_prompt(db.pool(), &settings, None).await,
+            "Inline"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_prefers_conversation_override() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let prompt = upsert(pool, input(None, "Pirate", "Talk like a pirate"), 0)
+            .await
+            .unwrap();
+        let settings = LlmSettings {
+            system_prompt: "Inline".to_string(),
+            active_prompt_id: Some(prompt.id),
+            ..LlmSettings::default()
+        };
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        store::set_system_prompt(pool, &conversation.id, Some("Regex golf only"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            resolve_system_prompt(pool, &settings, Some(&conversation.id)).await,
+            "Regex golf only"
+        );
+        assert_eq!(
+            resolve_system_prompt(pool, &settings, None).await,
+            "Talk like a pirate"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_without_override_uses_settings() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let settings = LlmSettings {
+            system_prompt: "Inline".to_string(),
+            ..LlmSettings::default()
+        };
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        store::set_system_prompt(pool, &conversation.id, Some("Regex golf only"))
+            .await
+            .unwrap();
+        store::set_system_prompt(pool, &conversation.id, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            resolve_system_prompt(pool, &settings, Some(&conversation.id)).await,
+            "Inline"
+        );
+        assert_eq!(
+            resolve_system_prompt(pool, &settings, Some("missing")).await,
+            "Inline"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_override_gets_response_language() {
+        let db = Db::in_memory().await.unwrap();
+        let pool = db.pool();
+        let mut settings = LlmSettings {
+            response_language: Some("de".to_string()),
+            ..LlmSettings::default()
+        };
+        let conversation = store::create_conversation(pool, None, 0).await.unwrap();
+        store::set_system_prompt(pool, &conversation.id, Some("Regex golf only"))
+            .await
+            .unwrap();
+
+        settings.system_prompt =
+            resolve_system_prompt(pool, &settings, Some(&conversation.id)).await;
+        let request = LlmRequest::from_settings(&settings, Vec::new());
+
+        assert_eq!(
+            request.system_prompt,
+            "Regex golf only\n\nAlways respond in German."
+        );
+    }
+}
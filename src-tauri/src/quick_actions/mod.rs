@@ -0,0 +1,471 @@
+//! Quick actions: predefined prompts with their own shortcuts and tray entries.
+//!
+//! A [`QuickAction`] is a prompt template such as `"Summarize:\n\n{{input}}"`
+//! whose `{{input}}` is filled in from the clipboard or the selected text
+//! (see [`QuickActionInput`]). The answer is asked in the launcher, copied
+//! to the clipboard or pasted back into the foreground app (see
+//! [`QuickActionOutput`]). Input longer than `launcher.clipboard_max_chars`
+//! is cut off.
+//!
+//! Quick actions are stored in the settings as `quick_actions`. Each one
+//! can have a global shortcut, registered with the other action shortcuts,
+//! and is listed in the tray's "Quick Actions" submenu. Both are updated
+//! whenever an action is saved or deleted.
+//!
+//! Running from a shortcut is what `selection` input and `insert_back`
+//! output are made for: from the tray or the launcher, the foreground app
+//! may be the tray or Qwik Ask itself.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const actions = await invoke<QuickAction[]>('list_quick_actions');
+//! const saved = await invoke<QuickAction>('upsert_quick_action', {
+//!   action: { id: '', name: 'Translate', prompt_template: 'Translate to German:\n\n{{input}}',
+//!             input: 'selection', output: 'insert_back', shortcut: 'Ctrl+Alt+T' },
+//! });
+//! await invoke('delete_quick_action', { id: saved.id });
+//! await invoke('run_quick_action', { id: saved.id });
+//!
+//! // `show_launcher` actions: ask the prompt in the launcher
+//! await listen<QuickActionPrompt>('quick-action-prompt', ({ payload }) =>
+//!   invoke('ask_llm', { messages: [{ role: 'user', content: payload.prompt }], source: 'quick_action' }));
+//!
+//! // Runs from a shortcut or the tray that failed
+//! await listen<QuickActionFailed>('quick-action-error', ({ payload }) => showToast(payload.message));
+//! ```
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::clipboard;
+use crate::history::{self, prompt_history::PromptSource};
+use crate::llm;
+use crate::llm::types::{ChatMessage, ChatRole};
+use crate::settings::{QuickAction, QuickActionInput, QuickActionOutput, SettingsManager};
+use crate::shortcuts;
+use crate::telemetry::{self, TelemetryEvent};
+use crate::window::{self, visibility::VisibilityReason};
+
+/// Placeholder in a prompt template that is replaced by the input.
+pub const INPUT_PLACEHOLDER: &str = "{{input}}";
+
+/// Payload of the `quick-action-prompt` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuickActionPrompt {
+    pub action_id: String,
+    /// Action name, for showing what is being asked
+    pub name: String,
+    /// Expanded prompt to ask
+    pub prompt: String,
+    /// Whether the input was cut off
+    pub truncated: bool,
+}
+
+/// Payload of the `quick-action-error` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuickActionFailed {
+    pub action_id: String,
+    pub message: String,
+}
+
+/// Check that a quick action can be saved.
+///
+/// Shortcut conflicts are checked separately, against all shortcuts (see
+/// [`shortcuts::action_shortcuts`]).
+///
+/// # Returns
+///
+/// * `Ok(())` - The action is valid
+/// * `Err(String)` - The name or template is empty, the template lacks
+///   `{{input}}` while an input is set, or the shortcut is invalid
+pub fn validate(action: &QuickAction) -> Result<(), String> {
+    if action.name.trim().is_empty() {
+        return Err("Quick action name can't be empty".to_string());
+    }
+    if action.prompt_template.trim().is_empty() {
+        return Err(format!(
+            "Quick action '{}' needs a prompt template",
+            action.name
+        ));
+    }
+    if action.input != QuickActionInput::None && !action.prompt_template.contains(INPUT_PLACEHOLDER)
+    {
+        return Err(format!(
+            "The prompt template of '{}' must contain {} to use its input",
+            action.name, INPUT_PLACEHOLDER
+        ));
+    }
+    if let Some(shortcut) = action.shortcut.as_deref().filter(|s| !s.trim().is_empty()) {
+        shortcuts::parse_shortcut(shortcut)?;
+    }
+    Ok(())
+}
+
+/// Fill the input into a prompt template.
+///
+/// Every `{{input}}` is replaced; without input they are removed.
+pub fn expand(template: &str, input: Option<&str>) -> String {
+    template.replace(INPUT_PLACEHOLDER, input.unwrap_or_default())
+}
+
+/// Add a quick action, or replace the one with the same id.
+///
+/// # Arguments
+///
+/// * `actions` - Saved quick actions
+/// * `action` - Action to save; an empty id creates a new one
+///
+/// # Returns
+///
+/// * `Ok(QuickAction)` - The saved action, with its id
+/// * `Err(String)` - The action is invalid (see [`validate`])
+pub fn upsert(
+    actions: &mut Vec<QuickAction>,
+    mut action: QuickAction,
+) -> Result<QuickAction, String> {
+    validate(&action)?;
+    action.name = action.name.trim().to_string();
+    if action.id.is_empty() {
+        action.id = uuid::Uuid::new_v4().to_string();
+    }
+    match actions.iter_mut().find(|saved| saved.id == action.id) {
+        Some(saved) => *saved = action.clone(),
+        None => actions.push(action.clone()),
+    }
+    Ok(action)
+}
+
+/// Remove a quick action.
+///
+/// # Returns
+///
+/// Whether an action with `id` existed.
+pub fn remove(actions: &mut Vec<QuickAction>, id: &str) -> bool {
+    let count = actions.len();
+    actions.retain(|action| action.id != id);
+    actions.len() != count
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// List the saved quick actions.
+#[tauri::command]
+pub fn list_quick_actions(
+    settings_manager: State<SettingsManager>,
+) -> Result<Vec<QuickAction>, String> {
+    Ok(settings_manager.snapshot()?.quick_actions.clone())
+}
+
+/// Create or update a quick action.
+///
+/// Registers its shortcut and updates the tray menu.
+///
+/// # Arguments
+///
+/// * `action` - Action to save; an empty id creates a new one
+///
+/// # Returns
+///
+/// * `Ok(QuickAction)` - The saved action, with its id
+/// * `Err(String)` - The action is invalid or its shortcut is already in use
+#[tauri::command]
+pub async fn upsert_quick_action(
+    settings_manager: State<'_, SettingsManager>,
+    action: QuickAction,
+) -> Result<QuickAction, String> {
+    let (_, saved) = settings_manager
+        .update(|settings| {
+            let saved = upsert(&mut settings.quick_actions, action)?;
+            shortcuts::action_shortcuts(&settings.shortcuts, &settings.quick_actions)?;
+            Ok(saved)
+        })
+        .await?;
+    Ok(saved)
+}
+
+/// Delete a quick action, unregistering its shortcut.
+///
+/// # Returns
+///
+/// * `Ok(())` - Deleted
+/// * `Err(String)` - No action has this id, or settings couldn't be saved
+#[tauri::command]
+pub async fn delete_quick_action(
+    settings_manager: State<'_, SettingsManager>,
+    id: String,
+) -> Result<(), String> {
+    settings_manager
+        .update(|settings| {
+            if !remove(&mut settings.quick_actions, &id) {
+                return Err(format!("Quick action '{}' not found", id));
+            }
+            Ok(())
+        })
+        .await?;
+    Ok(())
+}
+
+/// Run a quick action.
+///
+/// # Returns
+///
+/// * `Ok(())` - The prompt was sent to the launcher, or the answer was
+///   copied or pasted
+/// * `Err(String)` - No such action, no input to run on, or the model
+///   request failed
+#[tauri::command]
+pub async fn run_quick_action(app: AppHandle, id: String) -> Result<(), String> {
+    run(&app, &id, VisibilityReason::Command).await
+}
+
+/// Run a quick action from its shortcut or the tray.
+///
+/// Runs in the background; failures are emitted as `quick-action-error`.
+pub fn spawn(app: &AppHandle, id: String, reason: VisibilityReason) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(message) = run(&app, &id, reason).await {
+            tracing::warn!(error = %message, "Quick action failed");
+            let _ = app.emit(
+                "quick-action-error",
+                QuickActionFailed {
+                    action_id: id,
+                    message,
+                },
+            );
+        }
+    });
+}
+
+/// Gather the input, expand the template and deliver the answer.
+async fn run(app: &AppHandle, id: &str, reason: VisibilityReason) -> Result<(), String> {
+    let settings = app.state::<SettingsManager>().snapshot()?;
+    let action = settings
+        .quick_actions
+        .iter()
+        .find(|action| action.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Quick action '{}' not found", id))?;
+    telemetry::record(app, TelemetryEvent::QuickActionUsed);
+
+    let input = match action.input {
+        QuickActionInput::None => None,
+        QuickActionInput::Clipboard => Some(
+            clipboard::read()
+                .await
+                .ok_or_else(|| format!("The clipboard has no text for '{}'", action.name))?,
+        ),
+        QuickActionInput::Selection => {
+            let selection = tauri::async_runtime::spawn_blocking(clipboard::capture_selection)
+                .await
+                .map_err(|e| format!("Failed to capture the selection: {}", e))?;
+            Some(selection.ok_or_else(|| format!("No text is selected for '{}'", action.name))?)
+        }
+    };
+    let input = input.as_deref().map(str::trim);
+    if input == Some("") {
+        return Err(format!("No text to run '{}' on", action.name));
+    }
+    let (input, truncated) = match input {
+        Some(text) => {
+            let (text, truncated) =
+                clipboard::truncate_chars(text, settings.launcher.clipboard_max_chars as usize);
+            (Some(text), truncated)
+        }
+        None => (None, false),
+    };
+    let prompt = expand(&action.prompt_template, input);
+
+    if action.output == QuickActionOutput::ShowLauncher {
+        let payload = QuickActionPrompt {
+            action_id: action.id,
+            name: action.name,
+            prompt,
+            truncated,
+        };
+        let handle = app.clone();
+        return app
+            .run_on_main_thread(move || {
+                window::show_launcher(&handle, reason);
+                let _ = handle.emit("quick-action-prompt", payload);
+            })
+            .map_err(|e| format!("Failed to show the launcher: {}", e));
+    }
+
+    history::record_prompt(app, &prompt, PromptSource::QuickAction).await;
+    let answer = llm::ask(
+        app,
+        vec![ChatMessage {
+            role: ChatRole::User,
+            content: prompt,
+            images: Vec::new(),
+        }],
+        None,
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .content;
+    match action.output {
+        QuickActionOutput::InsertBack => clipboard::paste(&answer).await,
+        _ => clipboard::copy(&answer, clipboard::CopyFormat::Html).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::ShortcutSettings;
+    use crate::shortcuts::GlobalAction;
+
+    fn action(name: &str, template: &str, input: QuickActionInput) -> QuickAction {
+        QuickAction {
+            id: String::new(),
+            name: name.to_string(),
+            prompt_template: template.to_string(),
+            input,
+            output: QuickActionOutput::ShowLauncher,
+            shortcut: None,
+        }
+    }
+
+    // ===== Templates =====
+
+    #[test]
+    fn test_expand_replaces_every_placeholder() {
+        assert_eq!(
+            expand("Translate to German:\n\n{{input}}", Some("Good morning")),
+            "Translate to German:\n\nGood morning"
+        );
+        assert_eq!(expand("{{input}} / {{input}}", Some("x")), "x / x");
+    }
+
+    #[test]
+    fn test_expand_without_input() {
+        assert_eq!(expand("Tell me a joke", None), "Tell me a joke");
+        assert_eq!(expand("Explain: {{input}}", None), "Explain: ");
+    }
+
+    #[test]
+    fn test_expand_leaves_input_braces_alone() {
+        // Input is inserted once, not expanded again
+        assert_eq!(
+            expand("Explain: {{input}}", Some("fn f() -> {{input}} {}")),
+            "Explain: fn f() -> {{input}} {}"
+        );
+    }
+
+    // ===== Validation =====
+
+    #[test]
+    fn test_template_needs_placeholder_with_input() {
+        for input in [QuickActionInput::Clipboard, QuickActionInput::Selection] {
+            let error = validate(&action("Summarize", "Summarize this", input)).unwrap_err();
+            assert!(error.contains("{{input}}"));
+        }
+        assert!(validate(&action("Joke", "Tell me a joke", QuickActionInput::None)).is_ok());
+    }
+
+    #[test]
+    fn test_name_and_template_required() {
+        assert!(validate(&action(" ", "{{input}}", QuickActionInput::Clipboard)).is_err());
+        assert!(validate(&action("Empty", "  ", QuickActionInput::None)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_shortcut_rejected() {
+        let mut summarize = action("Summarize", "{{input}}", QuickActionInput::Clipboard);
+        summarize.shortcut = Some("S".to_string());
+
+        assert!(validate(&summarize).is_err());
+
+        summarize.shortcut = Some(" ".to_string());
+        assert!(validate(&summarize).is_ok());
+    }
+
+    // ===== Lifecycle =====
+
+    #[test]
+    fn test_upsert_assigns_id_and_updates() {
+        let mut actions = Vec::new();
+
+        let created = upsert(
+            &mut actions,
+            action(
+                " Summarize ",
+                "Summarize: {{input}}",
+                QuickActionInput::Clipboard,
+            ),
+        )
+        .unwrap();
+
+        assert!(!created.id.is_empty());
+        assert_eq!(created.name, "Summarize");
+        assert_eq!(actions, vec![created.clone()]);
+
+        let updated = upsert(
+            &mut actions,
+            QuickAction {
+                prompt_template: "TL;DR: {{input}}".to_string(),
+                ..created.clone()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(updated.id, created.id);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].prompt_template, "TL;DR: {{input}}");
+    }
+
+    #[test]
+    fn test_invalid_upsert_changes_nothing() {
+        let mut actions = Vec::new();
+
+        let result = upsert(
+            &mut actions,
+            action("Summarize", "Summarize", QuickActionInput::Selection),
+        );
+
+        assert!(result.is_err());
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut actions = Vec::new();
+        let created = upsert(
+            &mut actions,
+            action("Joke", "Tell me a joke", QuickActionInput::None),
+        )
+        .unwrap();
+
+        assert!(!remove(&mut actions, "missing"));
+        assert!(remove(&mut actions, &created.id));
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn test_shortcut_follows_lifecycle() {
+        let mut actions = Vec::new();
+        let mut summarize = action("Summarize", "{{input}}", QuickActionInput::Clipboard);
+        summarize.shortcut = Some("Ctrl+Alt+1".to_string());
+        let created = upsert(&mut actions, summarize).unwrap();
+        let bound = |actions: &[QuickAction]| {
+            shortcuts::action_shortcuts(&ShortcutSettings::default(), actions)
+                .unwrap()
+                .into_iter()
+                .map(|(action, _)| action)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            bound(&actions),
+            vec![GlobalAction::QuickAction(created.id.clone())]
+        );
+
+        remove(&mut actions, &created.id);
+        assert_eq!(bound(&actions), vec![]);
+    }
+}
@@ -0,0 +1,233 @@
+//! Keeping the login item in sync with the app.
+//!
+//! The OS entry that starts the app at login records an executable path
+//! and arguments. Both can go stale: portable installs move directories,
+//! and entries registered by older versions lack [`AUTOSTART_ARGS`]. The
+//! entry the app registered is remembered in the settings store under
+//! [`AUTOSTART_ENTRY_KEY`]; at startup and whenever `general.auto_startup`
+//! is applied, [`reconcile`] compares it with the current one and the
+//! entry is refreshed (disabled and enabled again) when they differ.
+//!
+//! [`repair`] is for the settings page, when the OS state disagrees with
+//! the setting.
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// Settings store key of the registered [`AutostartEntry`].
+pub const AUTOSTART_ENTRY_KEY: &str = "autostart_entry";
+
+/// Arguments the app is started with at login.
+pub const AUTOSTART_ARGS: &[&str] = &["--hidden"];
+
+/// What the login item launches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutostartEntry {
+    /// Path of the executable (or AppImage, or app bundle)
+    pub executable: String,
+    /// Command-line arguments
+    pub args: Vec<String>,
+}
+
+impl AutostartEntry {
+    /// The entry the autostart plugin registers for this process.
+    pub fn current(app: &AppHandle) -> Result<Self, String> {
+        Ok(Self {
+            executable: executable(app)?,
+            args: AUTOSTART_ARGS.iter().map(|arg| arg.to_string()).collect(),
+        })
+    }
+}
+
+/// The executable path the autostart plugin registers.
+#[cfg(target_os = "linux")]
+fn executable(app: &AppHandle) -> Result<String, String> {
+    use tauri::Manager;
+
+    if let Some(appimage) = app.env().appimage {
+        return Ok(appimage.display().to_string());
+    }
+    current_exe()
+}
+
+#[cfg(target_os = "macos")]
+fn executable(_app: &AppHandle) -> Result<String, String> {
+    let exe = std::env::current_exe()
+        .and_then(|exe| exe.canonicalize())
+        .map_err(|e| format!("Failed to get executable path: {}", e))?;
+    Ok(exe.display().to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn executable(_app: &AppHandle) -> Result<String, String> {
+    current_exe()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn current_exe() -> Result<String, String> {
+    std::env::current_exe()
+        .map(|exe| exe.display().to_string())
+        .map_err(|e| format!("Failed to get executable path: {}", e))
+}
+
+/// What to do with the login item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutostartAction {
+    /// It's as it should be
+    Keep,
+    /// Register it
+    Enable,
+    /// Remove it
+    Disable,
+    /// Remove and register it again, to update the path and arguments
+    Refresh,
+}
+
+/// Decide what brings the login item in line with the setting.
+///
+/// # Arguments
+///
+/// * `wanted` - The `general.auto_startup` setting
+/// * `registered` - Whether the OS has a login item
+/// * `stored` - The entry last registered, if remembered
+/// * `current` - The entry this process would register
+pub fn reconcile(
+    wanted: bool,
+    registered: bool,
+    stored: Option<&AutostartEntry>,
+    current: &AutostartEntry,
+) -> AutostartAction {
+    match (wanted, registered) {
+        (true, false) => AutostartAction::Enable,
+        (false, true) => AutostartAction::Disable,
+        // Without a stored entry it was registered by an older version
+        (true, true) if stored != Some(current) => AutostartAction::Refresh,
+        _ => AutostartAction::Keep,
+    }
+}
+
+/// What repairing the login item does: register it afresh when wanted,
+/// remove it otherwise.
+pub fn repair(wanted: bool, registered: bool) -> AutostartAction {
+    match (wanted, registered) {
+        (true, true) => AutostartAction::Refresh,
+        (true, false) => AutostartAction::Enable,
+        (false, true) => AutostartAction::Disable,
+        (false, false) => AutostartAction::Keep,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(executable: &str, args: &[&str]) -> AutostartEntry {
+        AutostartEntry {
+            executable: executable.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    fn current() -> AutostartEntry {
+        entry("/opt/qwik-ask/qwik-ask", &["--hidden"])
+    }
+
+    // ===== Reconcile =====
+
+    #[test]
+    fn test_matching_entry_is_kept() {
+        assert_eq!(
+            reconcile(true, true, Some(&current()), &current()),
+            AutostartAction::Keep
+        );
+    }
+
+    #[test]
+    fn test_moved_executable_is_refreshed() {
+        let stored = entry("/home/me/Downloads/qwik-ask", &["--hidden"]);
+
+        assert_eq!(
+            reconcile(true, true, Some(&stored), &current()),
+            AutostartAction::Refresh
+        );
+    }
+
+    #[test]
+    fn test_changed_args_are_refreshed() {
+        let stored = entry("/opt/qwik-ask/qwik-ask", &[]);
+
+        assert_eq!(
+            reconcile(true, true, Some(&stored), &current()),
+            AutostartAction::Refresh
+        );
+    }
+
+    #[test]
+    fn test_unremembered_entry_is_refreshed() {
+        assert_eq!(
+            reconcile(true, true, None, &current()),
+            AutostartAction::Refresh
+        );
+    }
+
+    #[test]
+    fn test_missing_entry_is_enabled() {
+        assert_eq!(
+            reconcile(true, false, Some(&current()), &current()),
+            AutostartAction::Enable
+        );
+        assert_eq!(
+            reconcile(true, false, None, &current()),
+            AutostartAction::Enable
+        );
+    }
+
+    #[test]
+    fn test_unwanted_entry_is_disabled() {
+        let stored = entry("/elsewhere/qwik-ask", &[]);
+
+        assert_eq!(
+            reconcile(false, true, Some(&stored), &current()),
+            AutostartAction::Disable
+        );
+    }
+
+    #[test]
+    fn test_stale_entry_ignored_when_disabled() {
+        let stored = entry("/elsewhere/qwik-ask", &[]);
+
+        assert_eq!(
+            reconcile(false, false, Some(&stored), &current()),
+            AutostartAction::Keep
+        );
+    }
+
+    // ===== Repair =====
+
+    #[test]
+    fn test_repair() {
+        assert_eq!(repair(true, true), AutostartAction::Refresh);
+        assert_eq!(repair(true, false), AutostartAction::Enable);
+        assert_eq!(repair(false, true), AutostartAction::Disable);
+        assert_eq!(repair(false, false), AutostartAction::Keep);
+    }
+
+    // ===== Serialization =====
+
+    #[test]
+    fn test_entry_roundtrip() {
+        let value = serde_json::to_value(current()).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "executable": "/opt/qwik-ask/qwik-ask",
+                "args": ["--hidden"]
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<AutostartEntry>(value).unwrap(),
+            current()
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! In-memory copy of the settings.
+//!
+//! Settings are read far more often than they change: every command, LLM
+//! request and launcher toggle looks at them. [`SettingsCache`] reads and
+//! deserializes them from the store once, hands out shared snapshots, and
+//! replaces its copy on save before writing through to the store.
+//!
+//! A failed read isn't cached, so corrupted settings are retried on the
+//! next access instead of being replaced with defaults.
+
+use std::sync::{Arc, RwLock};
+
+use super::types::AppSettings;
+
+/// Where the settings are persisted.
+pub trait SettingsBackend: Send + Sync {
+    /// Read the stored settings; `None` if nothing is stored yet.
+    fn read(&self) -> Result<Option<serde_json::Value>, String>;
+
+    /// Store the settings.
+    fn write(&self, value: serde_json::Value) -> Result<(), String>;
+}
+
+/// Settings cached in memory in front of a [`SettingsBackend`].
+pub struct SettingsCache<B> {
+    backend: B,
+    current: RwLock<Option<Arc<AppSettings>>>,
+}
+
+impl<B: SettingsBackend> SettingsCache<B> {
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            current: RwLock::new(None),
+        }
+    }
+
+    /// The current settings, read from the backend on first use.
+    ///
+    /// Returns defaults if nothing is stored yet.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Arc<AppSettings>)` - Shared snapshot; later saves don't change it
+    /// * `Err(String)` - The stored settings couldn't be read or parsed
+    pub fn snapshot(&self) -> Result<Arc<AppSettings>, String> {
+        if let Some(settings) = self.current.read().unwrap().as_ref() {
+            return Ok(Arc::clone(settings));
+        }
+
+        let mut current = self.current.write().unwrap();
+        // Another thread may have read them while this one waited
+        if let Some(settings) = current.as_ref() {
+            return Ok(Arc::clone(settings));
+        }
+        let settings = Arc::new(match self.backend.read()? {
+            Some(value) => serde_json::from_value(value)
+                .map_err(|e| format!("Failed to deserialize settings: {}", e))?,
+            None => AppSettings::default(),
+        });
+        *current = Some(Arc::clone(&settings));
+        Ok(settings)
+    }
+
+    /// Replace the cached settings and write them to the backend.
+    ///
+    /// Readers see the new settings as soon as this returns, even if the
+    /// write failed.
+    pub fn save(&self, settings: &AppSettings) -> Result<(), String> {
+        let value = serde_json::to_value(settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        *self.current.write().unwrap() = Some(Arc::new(settings.clone()));
+        self.backend.write(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Keeps the settings in memory and counts accesses.
+    #[derive(Default)]
+    struct CountingBackend {
+        stored: Mutex<Option<serde_json::Value>>,
+        reads: AtomicUsize,
+        writes: AtomicUsize,
+    }
+
+    impl SettingsBackend for Arc<CountingBackend> {
+        fn read(&self) -> Result<Option<serde_json::Value>, String> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Ok(self.stored.lock().unwrap().clone())
+        }
+
+        fn write(&self, value: serde_json::Value) -> Result<(), String> {
+            self.writes.fetch_add(1, Ordering::SeqCst);
+            *self.stored.lock().unwrap() = Some(value);
+            Ok(())
+        }
+    }
+
+    fn cache() -> (Arc<CountingBackend>, SettingsCache<Arc<CountingBackend>>) {
+        let backend = Arc::new(CountingBackend::default());
+        (Arc::clone(&backend), SettingsCache::new(backend))
+    }
+
+    // ===== Reading =====
+
+    #[test]
+    fn test_defaults_when_nothing_stored() {
+        let (_, cache) = cache();
+
+        assert_eq!(
+            serde_json::to_value(&*cache.snapshot().unwrap()).unwrap(),
+            serde_json::to_value(AppSettings::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_reads_backend_once() {
+        let (backend, cache) = cache();
+
+        for _ in 0..100 {
+            cache.snapshot().unwrap();
+        }
+
+        assert_eq!(backend.reads.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_failed_read_is_retried() {
+        let (backend, cache) = cache();
+        *backend.stored.lock().unwrap() = Some(serde_json::json!({ "general": 42 }));
+
+        assert!(cache.snapshot().is_err());
+
+        *backend.stored.lock().unwrap() = None;
+        assert!(cache.snapshot().is_ok());
+        assert_eq!(backend.reads.load(Ordering::SeqCst), 2);
+    }
+
+    // ===== Saving =====
+
+    #[test]
+    fn test_save_writes_once_and_updates_snapshot() {
+        let (backend, cache) = cache();
+        let before = cache.snapshot().unwrap();
+        let mut settings = (*before).clone();
+        settings.llm.model = "gpt-4o".to_string();
+
+        cache.save(&settings).unwrap();
+
+        assert_eq!(cache.snapshot().unwrap().llm.model, "gpt-4o");
+        assert_ne!(before.llm.model, "gpt-4o");
+        assert_eq!(backend.writes.load(Ordering::SeqCst), 1);
+        assert_eq!(backend.reads.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            backend.stored.lock().unwrap().as_ref().unwrap()["llm"]["model"],
+            "gpt-4o"
+        );
+    }
+
+    #[test]
+    fn test_readers_during_saves_see_whole_settings() {
+        let (_, cache) = cache();
+        let cache = Arc::new(cache);
+
+        let saver = {
+            let cache = Arc::clone(&cache);
+            std::thread::spawn(move || {
+                for i in 0..200 {
+                    let mut settings = AppSettings::default();
+                    // Changed together, so a reader must never see them differ
+                    settings.llm.model = format!("model-{}", i);
+                    settings.llm.system_prompt = format!("model-{}", i);
+                    cache.save(&settings).unwrap();
+                }
+            })
+        };
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                std::thread::spawn(move || {
+                    for _ in 0..500 {
+                        let settings = cache.snapshot().unwrap();
+                        if settings.llm.model.starts_with("model-") {
+                            assert_eq!(settings.llm.model, settings.llm.system_prompt);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        saver.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(cache.snapshot().unwrap().llm.model, "model-199");
+    }
+}
@@ -0,0 +1,525 @@
+//! What changed between two versions of the settings.
+//!
+//! Applying settings touches the OS: toggling autostart, unregistering and
+//! registering global shortcuts (which briefly leaves the hotkey dead),
+//! changing window flags. [`SettingsDelta`] records which of those are
+//! affected by a save, so [`SettingsManager::apply`] only runs the
+//! [`ApplyStep`]s that have something to do. Changing the model or the
+//! theme touches none of them.
+//!
+//! [`SettingsDelta::changes`] lists what a save changed by dotted path, for
+//! the audit log (see [`super::audit`]).
+//!
+//! [`SettingsManager::apply`]: super::SettingsManager::apply
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::types::{AppSettings, UpdateSettings};
+use crate::llm::Limits;
+
+/// One part of applying settings to the running app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyStep {
+    /// Change the log level
+    LogLevel,
+    /// Switch the language of backend strings
+    Locale,
+    /// Enable or disable launching at login
+    AutoStartup,
+    /// Re-register the toggle shortcut
+    ToggleShortcut,
+    /// Re-register the ask and quick action shortcuts
+    ActionShortcuts,
+    /// Update the launcher's always-on-top and taskbar flags
+    LauncherWindow,
+    /// Show or hide the Dock icon (macOS)
+    DockIcon,
+    /// Restart background update checks
+    UpdateSchedule,
+    /// Reset the LLM request limiter
+    RequestLimits,
+    /// Encrypt or decrypt stored message content
+    ContentEncryption,
+    /// Restart the wait for the next history maintenance pass
+    MaintenanceSchedule,
+}
+
+/// One setting that differs between two [`AppSettings`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingChange {
+    /// Dotted path, e.g. `llm.model` or `llm.profiles.0.api_key`
+    pub path: String,
+    /// Value before the save; `null` when it didn't exist
+    pub old: Value,
+    /// Value after the save; `null` when it was removed
+    pub new: Value,
+}
+
+/// Which applied settings differ between two [`AppSettings`].
+///
+/// Each field is `true` when the settings its step reads have changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SettingsDelta {
+    /// `general.log_level`
+    pub log_level: bool,
+    /// `general.locale`
+    pub locale: bool,
+    /// `general.auto_startup`
+    pub auto_startup: bool,
+    /// `shortcuts.toggle_launcher`
+    pub toggle_shortcut: bool,
+    /// `shortcuts.ask_clipboard`, `shortcuts.ask_selection` and the quick
+    /// action shortcuts
+    pub action_shortcuts: bool,
+    /// `launcher.always_on_top` and `launcher.skip_taskbar`
+    pub launcher_window: bool,
+    /// `general.hide_dock_icon`
+    pub dock_icon: bool,
+    /// `updates`, apart from `skipped_version`
+    pub update_schedule: bool,
+    /// `llm.max_concurrent_requests`, `llm.requests_per_minute` and
+    /// `llm.queue_when_limited`
+    pub request_limits: bool,
+    /// `history.encrypt_content` and `history.search_encrypted`
+    pub content_encryption: bool,
+    /// `history.auto_maintenance` and `history.retention_days`
+    pub maintenance_schedule: bool,
+}
+
+impl SettingsDelta {
+    /// Compare `old` and `new` field by field.
+    pub fn between(old: &AppSettings, new: &AppSettings) -> Self {
+        Self {
+            log_level: old.general.log_level != new.general.log_level,
+            locale: old.general.locale != new.general.locale,
+            auto_startup: old.general.auto_startup != new.general.auto_startup,
+            toggle_shortcut: old.shortcuts.toggle_launcher != new.shortcuts.toggle_launcher,
+            action_shortcuts: old.shortcuts.ask_clipboard != new.shortcuts.ask_clipboard
+                || old.shortcuts.ask_selection != new.shortcuts.ask_selection
+                || action_bindings(old).ne(action_bindings(new)),
+            launcher_window: old.launcher.always_on_top != new.launcher.always_on_top
+                || old.launcher.skip_taskbar != new.launcher.skip_taskbar,
+            dock_icon: old.general.hide_dock_icon != new.general.hide_dock_icon,
+            update_schedule: schedule(&old.updates) != schedule(&new.updates),
+            request_limits: Limits::from_settings(&old.llm) != Limits::from_settings(&new.llm),
+            content_encryption: old.history.encrypt_content != new.history.encrypt_content
+                || old.history.search_encrypted != new.history.search_encrypted,
+            maintenance_schedule: old.history.auto_maintenance != new.history.auto_maintenance
+                || old.history.retention_days != new.history.retention_days,
+        }
+    }
+
+    /// Every setting that differs between `old` and `new`, by dotted path.
+    ///
+    /// Objects, maps and lists are compared member by member (list items by
+    /// index), so a change deep inside `llm.profiles` is reported as the
+    /// one field that changed. Paths are sorted.
+    pub fn changes(old: &AppSettings, new: &AppSettings) -> Vec<SettingChange> {
+        let mut changes = Vec::new();
+        let (old, new) = match (serde_json::to_value(old), serde_json::to_value(new)) {
+            (Ok(old), Ok(new)) => (old, new),
+            _ => return changes,
+        };
+        diff(String::new(), &old, &new, &mut changes);
+        changes
+    }
+
+    /// Whether nothing needs applying.
+    pub fn is_empty(&self) -> bool {
+        self.steps().is_empty()
+    }
+
+    /// The steps to run, in order.
+    pub fn steps(&self) -> Vec<ApplyStep> {
+        [
+            (self.log_level, ApplyStep::LogLevel),
+            (self.locale, ApplyStep::Locale),
+            (self.auto_startup, ApplyStep::AutoStartup),
+            (self.toggle_shortcut, ApplyStep::ToggleShortcut),
+            (self.action_shortcuts, ApplyStep::ActionShortcuts),
+            (self.launcher_window, ApplyStep::LauncherWindow),
+            (self.dock_icon, ApplyStep::DockIcon),
+            (self.update_schedule, ApplyStep::UpdateSchedule),
+            (self.request_limits, ApplyStep::RequestLimits),
+            (self.content_encryption, ApplyStep::ContentEncryption),
+            (self.maintenance_schedule, ApplyStep::MaintenanceSchedule),
+        ]
+        .into_iter()
+        .filter_map(|(changed, step)| changed.then_some(step))
+        .collect()
+    }
+
+    /// Run the steps with `perform`, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Every step succeeded
+    /// * `Err(String)` - A step failed; the steps after it don't run
+    pub fn run(&self, perform: impl FnMut(ApplyStep) -> Result<(), String>) -> Result<(), String> {
+        self.steps().into_iter().try_for_each(perform)
+    }
+}
+
+/// Add the differences between `old` and `new` below `path` to `changes`.
+fn diff(path: String, old: &Value, new: &Value, changes: &mut Vec<SettingChange>) {
+    if old == new {
+        return;
+    }
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (old, new) {
+        // Empty maps and lists may be left out when saved
+        (Value::Null, Value::Object(_) | Value::Array(_)) => {
+            diff(path, &empty_like(new), new, changes)
+        }
+        (Value::Object(_) | Value::Array(_), Value::Null) => {
+            diff(path, old, &empty_like(old), changes)
+        }
+        (Value::Object(old), Value::Object(new)) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                diff(
+                    child(key),
+                    old.get(key).unwrap_or(&Value::Null),
+                    new.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for i in 0..old.len().max(new.len()) {
+                match (old.get(i), new.get(i)) {
+                    (Some(old), Some(new)) => diff(child(&i.to_string()), old, new, changes),
+                    // Added and removed items are whole changes
+                    (old, new) => changes.push(SettingChange {
+                        path: child(&i.to_string()),
+                        old: old.cloned().unwrap_or_default(),
+                        new: new.cloned().unwrap_or_default(),
+                    }),
+                }
+            }
+        }
+        _ => changes.push(SettingChange {
+            path,
+            old: old.clone(),
+            new: new.clone(),
+        }),
+    }
+}
+
+/// An empty map or list, like `value`.
+fn empty_like(value: &Value) -> Value {
+    match value {
+        Value::Array(_) => Value::Array(Vec::new()),
+        _ => Value::Object(Default::default()),
+    }
+}
+
+/// The quick action shortcuts, by action id.
+fn action_bindings(settings: &AppSettings) -> impl Iterator<Item = (&str, Option<&str>)> {
+    settings
+        .quick_actions
+        .iter()
+        .map(|action| (action.id.as_str(), action.shortcut.as_deref()))
+}
+
+/// The update settings that affect the schedule; the skipped version is
+/// read at check time.
+fn schedule(updates: &UpdateSettings) -> UpdateSettings {
+    UpdateSettings {
+        skipped_version: None,
+        ..updates.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::types::{LogLevel, QuickAction, Theme};
+
+    fn changed(edit: impl FnOnce(&mut AppSettings)) -> SettingsDelta {
+        let old = AppSettings::default();
+        let mut new = old.clone();
+        edit(&mut new);
+        SettingsDelta::between(&old, &new)
+    }
+
+    fn action(id: &str, shortcut: Option<&str>) -> QuickAction {
+        QuickAction {
+            id: id.to_string(),
+            name: "Translate".to_string(),
+            prompt_template: "Translate: {{input}}".to_string(),
+            input: Default::default(),
+            output: Default::default(),
+            shortcut: shortcut.map(str::to_string),
+        }
+    }
+
+    // ===== Unrelated changes =====
+
+    #[test]
+    fn test_identical_settings_are_empty() {
+        assert!(changed(|_| {}).is_empty());
+    }
+
+    #[test]
+    fn test_unapplied_settings_are_empty() {
+        let delta = changed(|s| {
+            s.general.theme = Theme::Light;
+            s.llm.model = "gpt-4o".to_string();
+            s.llm.system_prompt = "Be brief.".to_string();
+            s.shortcuts.peek_mode = !s.shortcuts.peek_mode;
+            s.updates.skipped_version = Some("1.2.0".to_string());
+        });
+
+        assert_eq!(delta, SettingsDelta::default());
+    }
+
+    #[test]
+    fn test_quick_action_prompt_is_not_a_shortcut_change() {
+        let old = AppSettings {
+            quick_actions: vec![action("a", Some("Alt+T"))],
+            ..AppSettings::default()
+        };
+        let mut new = old.clone();
+        new.quick_actions[0].prompt_template = "Summarize: {{input}}".to_string();
+        new.quick_actions[0].name = "Summarize".to_string();
+
+        assert!(SettingsDelta::between(&old, &new).is_empty());
+    }
+
+    // ===== Field to step =====
+
+    #[test]
+    fn test_log_level() {
+        let delta = changed(|s| s.general.log_level = LogLevel::Debug);
+        assert_eq!(delta.steps(), vec![ApplyStep::LogLevel]);
+    }
+
+    #[test]
+    fn test_locale() {
+        let delta = changed(|s| s.general.locale = Some("de".to_string()));
+        assert_eq!(delta.steps(), vec![ApplyStep::Locale]);
+    }
+
+    #[test]
+    fn test_auto_startup() {
+        let delta = changed(|s| s.general.auto_startup = !s.general.auto_startup);
+        assert_eq!(delta.steps(), vec![ApplyStep::AutoStartup]);
+    }
+
+    #[test]
+    fn test_toggle_shortcut() {
+        let delta = changed(|s| s.shortcuts.toggle_launcher = "Ctrl+Space".to_string());
+        assert_eq!(delta.steps(), vec![ApplyStep::ToggleShortcut]);
+    }
+
+    #[test]
+    fn test_ask_shortcuts() {
+        let clipboard = changed(|s| s.shortcuts.ask_clipboard = Some("Alt+C".to_string()));
+        let selection = changed(|s| s.shortcuts.ask_selection = Some("Alt+S".to_string()));
+
+        assert_eq!(clipboard.steps(), vec![ApplyStep::ActionShortcuts]);
+        assert_eq!(selection.steps(), vec![ApplyStep::ActionShortcuts]);
+    }
+
+    #[test]
+    fn test_quick_action_shortcuts() {
+        let old = AppSettings {
+            quick_actions: vec![action("a", Some("Alt+T"))],
+            ..AppSettings::default()
+        };
+        let rebound = {
+            let mut new = old.clone();
+            new.quick_actions[0].shortcut = Some("Alt+R".to_string());
+            new
+        };
+        let added = {
+            let mut new = old.clone();
+            new.quick_actions.push(action("b", None));
+            new
+        };
+        let removed = AppSettings {
+            quick_actions: Vec::new(),
+            ..old.clone()
+        };
+
+        for new in [rebound, added, removed] {
+            assert_eq!(
+                SettingsDelta::between(&old, &new).steps(),
+                vec![ApplyStep::ActionShortcuts]
+            );
+        }
+    }
+
+    #[test]
+    fn test_launcher_window() {
+        let on_top = changed(|s| s.launcher.always_on_top = !s.launcher.always_on_top);
+        let taskbar = changed(|s| s.launcher.skip_taskbar = !s.launcher.skip_taskbar);
+
+        assert_eq!(on_top.steps(), vec![ApplyStep::LauncherWindow]);
+        assert_eq!(taskbar.steps(), vec![ApplyStep::LauncherWindow]);
+    }
+
+    #[test]
+    fn test_dock_icon() {
+        let delta = changed(|s| s.general.hide_dock_icon = !s.general.hide_dock_icon);
+        assert_eq!(delta.steps(), vec![ApplyStep::DockIcon]);
+    }
+
+    #[test]
+    fn test_request_limits() {
+        let concurrency = changed(|s| s.llm.max_concurrent_requests = 4);
+        let rate = changed(|s| s.llm.requests_per_minute = 0);
+        let queue = changed(|s| s.llm.queue_when_limited = !s.llm.queue_when_limited);
+
+        assert_eq!(concurrency.steps(), vec![ApplyStep::RequestLimits]);
+        assert_eq!(rate.steps(), vec![ApplyStep::RequestLimits]);
+        assert_eq!(queue.steps(), vec![ApplyStep::RequestLimits]);
+    }
+
+    #[test]
+    fn test_update_schedule() {
+        let interval = changed(|s| s.updates.check_interval_hours += 1);
+        let auto_check = changed(|s| s.updates.auto_check = !s.updates.auto_check);
+
+        assert_eq!(interval.steps(), vec![ApplyStep::UpdateSchedule]);
+        assert_eq!(auto_check.steps(), vec![ApplyStep::UpdateSchedule]);
+    }
+
+    #[test]
+    fn test_content_encryption() {
+        let encrypt = changed(|s| s.history.encrypt_content = true);
+        let search = changed(|s| s.history.search_encrypted = true);
+
+        assert_eq!(encrypt.steps(), vec![ApplyStep::ContentEncryption]);
+        assert_eq!(search.steps(), vec![ApplyStep::ContentEncryption]);
+    }
+
+    #[test]
+    fn test_maintenance_schedule() {
+        let retention = changed(|s| s.history.retention_days = 90);
+        let auto = changed(|s| s.history.auto_maintenance = false);
+        let drafts = changed(|s| s.history.draft_retention_days = 1);
+
+        assert_eq!(retention.steps(), vec![ApplyStep::MaintenanceSchedule]);
+        assert_eq!(auto.steps(), vec![ApplyStep::MaintenanceSchedule]);
+        assert!(drafts.is_empty());
+    }
+
+    // ===== Steps =====
+
+    #[test]
+    fn test_several_changes_keep_order() {
+        let delta = changed(|s| {
+            s.updates.auto_check = !s.updates.auto_check;
+            s.shortcuts.toggle_launcher = "Ctrl+Space".to_string();
+            s.general.auto_startup = !s.general.auto_startup;
+        });
+
+        assert_eq!(
+            delta.steps(),
+            vec![
+                ApplyStep::AutoStartup,
+                ApplyStep::ToggleShortcut,
+                ApplyStep::UpdateSchedule,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_model_change_leaves_shortcuts_untouched() {
+        let delta = changed(|s| s.llm.model = "claude-sonnet-4-5".to_string());
+        let mut performed = Vec::new();
+
+        delta
+            .run(|step| {
+                performed.push(step);
+                Ok(())
+            })
+            .unwrap();
+
+        assert!(performed.is_empty());
+    }
+
+    #[test]
+    fn test_run_stops_at_failure() {
+        let delta = changed(|s| {
+            s.general.auto_startup = !s.general.auto_startup;
+            s.shortcuts.toggle_launcher = "Ctrl+Space".to_string();
+            s.launcher.always_on_top = !s.launcher.always_on_top;
+        });
+        let mut performed = Vec::new();
+
+        let result = delta.run(|step| {
+            performed.push(step);
+            match step {
+                ApplyStep::ToggleShortcut => Err("in use".to_string()),
+                _ => Ok(()),
+            }
+        });
+
+        assert_eq!(result, Err("in use".to_string()));
+        assert_eq!(
+            performed,
+            vec![ApplyStep::AutoStartup, ApplyStep::ToggleShortcut]
+        );
+    }
+
+    // ===== Changed paths =====
+
+    #[test]
+    fn test_changes_of_identical_settings_are_empty() {
+        let settings = AppSettings::default();
+
+        assert!(SettingsDelta::changes(&settings, &settings).is_empty());
+    }
+
+    #[test]
+    fn test_changes_are_dotted_leaf_paths() {
+        let old = AppSettings::default();
+        let mut new = old.clone();
+        new.llm.model = "gpt-4o".to_string();
+        new.general.theme = Theme::Light;
+        new.llm
+            .model_aliases
+            .insert("gpt-4o".to_string(), "Daily".to_string());
+
+        let changes = SettingsDelta::changes(&old, &new);
+
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["general.theme", "llm.model", "llm.model_aliases.gpt-4o"]
+        );
+        assert_eq!(changes[1].old, Value::String(old.llm.model.clone()));
+        assert_eq!(changes[1].new, "gpt-4o");
+        assert_eq!(changes[2].old, Value::Null);
+    }
+
+    #[test]
+    fn test_changes_in_lists_by_index() {
+        let old = AppSettings {
+            quick_actions: vec![action("a", None)],
+            ..AppSettings::default()
+        };
+        let mut new = old.clone();
+        new.quick_actions[0].shortcut = Some("Alt+T".to_string());
+        new.quick_actions.push(action("b", None));
+
+        let changes = SettingsDelta::changes(&old, &new);
+
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["quick_actions.0.shortcut", "quick_actions.1"]);
+        assert_eq!(changes[1].old, Value::Null);
+        assert_eq!(changes[1].new["id"], "b");
+    }
+}
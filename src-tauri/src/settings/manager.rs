@@ -1,17 +1,45 @@
 //! Settings manager for load, save, and apply operations.
 //!
 //! This module provides the `SettingsManager` struct which handles:
-//! - Loading/saving settings from `tauri-plugin-store`
-//! - Applying settings (auto-startup, global shortcuts)
-//! - Thread-safe shortcut state management
-
-use super::types::AppSettings;
-use crate::shortcuts::parse_shortcut;
-use std::{env, sync::Mutex};
-use tauri::{AppHandle};
+//! - Loading/saving settings from `tauri-plugin-store`, through an in-memory
+//!   cache (see [`super::cache`])
+//! - Remembered app state kept in the same store (e.g. launcher geometry)
+//! - Applying the settings that changed (auto-startup, global shortcuts,
+//!   launcher window flags, the macOS Dock icon; see [`super::delta`])
+//! - Thread-safe shortcut state management, including pausing the shortcut
+//! - Updates one at a time, each with a revision (see [`super::revision`])
+//!   and recorded in the audit log (see [`super::audit`])
+
+use super::audit::{self, AuditSource};
+use super::autostart::{self, AutostartAction, AutostartEntry, AUTOSTART_ENTRY_KEY};
+use super::cache::{SettingsBackend, SettingsCache};
+use super::delta::{ApplyStep, SettingChange, SettingsDelta};
+use super::revision::{SettingsChanged, SettingsTarget, UpdateLock, CHANGED_EVENT};
+use super::types::{AppSettings, LauncherSettings};
+use crate::db::{self, Db};
+use crate::history;
+use crate::i18n::{self, t};
+use crate::llm;
+use crate::logging;
+use crate::paths;
+use crate::shortcuts::{self, parse_shortcut, GlobalAction};
+use crate::tray;
+use crate::updater;
+use crate::window::dock::{self, ActivationPolicy};
+use crate::window::toggle::{self, LauncherState};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    env,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+use tauri::{AppHandle, Emitter, Manager, Wry};
 use tauri_plugin_autostart::ManagerExt;
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
-use tauri_plugin_store::StoreExt;
+use tauri_plugin_store::{Store, StoreExt};
 
 /// Manages application settings persistence and application.
 ///
@@ -28,17 +56,34 @@ use tauri_plugin_store::StoreExt;
 /// ```rust,ignore
 /// let manager = SettingsManager::new(app.handle().clone());
 ///
-/// // Load settings
-/// let settings = manager.load()?;
+/// // Read settings (cheap; shared with other readers)
+/// let settings = manager.snapshot()?;
 ///
-/// // Update and apply
-/// manager.save(&new_settings)?;
-/// manager.apply(&new_settings)?;
+/// // Change, save and apply what changed, after any update in progress
+/// let (revision, ()) = manager
+///     .update(|settings| {
+///         settings.general.theme = Theme::Dark;
+///         Ok(())
+///     })
+///     .await?;
 /// ```
 pub struct SettingsManager {
     app: AppHandle,
+    /// The settings, read from the store once
+    settings: SettingsCache<StoreBackend>,
     /// Currently registered shortcut, used to unregister before registering a new one
     current_shortcut: Mutex<Option<Shortcut>>,
+    /// Currently registered per-action shortcuts
+    action_shortcuts: Mutex<Vec<(GlobalAction, Shortcut)>>,
+    /// Whether the shortcut is unregistered on request. Not persisted;
+    /// every launch starts with the shortcut active.
+    shortcuts_paused: AtomicBool,
+    /// Last applied `(always_on_top, skip_taskbar)` launcher flags
+    current_window_flags: Mutex<Option<(bool, bool)>>,
+    /// Last applied activation policy (macOS)
+    current_activation_policy: Mutex<Option<ActivationPolicy>>,
+    /// Held for the whole of [`Self::update`]
+    updates: UpdateLock,
 }
 
 impl SettingsManager {
@@ -49,103 +94,289 @@ impl SettingsManager {
     /// * `app` - Tauri app handle for accessing plugins and state
     pub fn new(app: AppHandle) -> Self {
         Self {
+            settings: SettingsCache::new(StoreBackend { app: app.clone() }),
             app,
             current_shortcut: Mutex::new(None),
+            action_shortcuts: Mutex::new(Vec::new()),
+            shortcuts_paused: AtomicBool::new(false),
+            current_window_flags: Mutex::new(None),
+            current_activation_policy: Mutex::new(None),
+            updates: UpdateLock::default(),
         }
     }
 
-    /// Load settings from the store.
+    /// The current settings, shared with other readers.
     ///
-    /// Returns default settings if no settings file exists or if the
-    /// stored settings are corrupted/incompatible.
+    /// Served from memory; the store is only read the first time. Returns
+    /// default settings if no settings file exists.
     ///
     /// # Returns
     ///
-    /// * `Ok(AppSettings)` - Loaded or default settings
-    /// * `Err(String)` - Error accessing the store
-    pub fn load(&self) -> Result<AppSettings, String> {
-        let mut settings_file = "settings.json";
-        if env::var("QWIK_ASK_DEV").is_ok() {
-            settings_file = "dev_settings.json";
-        }
-
-        let store = self
-            .app
-            .store(settings_file)
-            .map_err(|e| format!("Failed to access store: {}", e))?;
+    /// * `Ok(Arc<AppSettings>)` - Current or default settings
+    /// * `Err(String)` - Error accessing the store, or the stored settings
+    ///   are corrupted/incompatible
+    pub fn snapshot(&self) -> Result<Arc<AppSettings>, String> {
+        self.settings.snapshot()
+    }
 
-        if let Some(settings_value) = store.get("settings") {
-            serde_json::from_value(settings_value.clone())
-                .map_err(|e| format!("Failed to deserialize settings: {}", e))
-        } else {
-            Ok(AppSettings::default())
-        }
+    /// A copy of the current settings to modify without saving, such as
+    /// with a conversation's system prompt resolved.
+    ///
+    /// Use [`snapshot`](Self::snapshot) to only read them, and
+    /// [`update`](Self::update) to change them.
+    pub fn load(&self) -> Result<AppSettings, String> {
+        Ok((*self.snapshot()?).clone())
     }
 
-    /// Save settings to the store.
+    /// Save settings.
     ///
-    /// Persists settings immediately to disk.
+    /// The new settings are visible to readers right away; writing them to
+    /// disk happens in the background.
     ///
     /// # Arguments
     ///
     /// * `settings` - Complete settings object to save
-    pub fn save(&self, settings: &AppSettings) -> Result<(), String> {
-        let mut settings_file = "settings.json";
-        if env::var("QWIK_ASK_DEV").is_ok() {
-            settings_file = "dev_settings.json";
-        }
-
-        let store = self
-            .app
-            .store(settings_file)
-            .map_err(|e| format!("Failed to access store: {}", e))?;
-
-        let settings_value = serde_json::to_value(settings)
-            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    ///
+    /// Only [`update`](Self::update) saves, so every change gets a revision,
+    /// an audit entry and `settings-changed`.
+    fn save(&self, settings: &AppSettings) -> Result<(), String> {
+        self.settings.save(settings)
+    }
 
-        store.set("settings", settings_value);
+    /// Change the current settings, save them, apply what changed and send
+    /// `settings-changed`, once any update in progress has finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `mutate` - Changes the current settings; an error cancels the
+    ///   update
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((u64, T))` - Revision of this save, and what `mutate` returned
+    /// * `Err(String)` - `mutate`, save or apply failed
+    pub async fn update<T>(
+        &self,
+        mutate: impl FnOnce(&mut AppSettings) -> Result<T, String>,
+    ) -> Result<(u64, T), String> {
+        self.updates.commit(self, AuditSource::Ui, mutate).await
+    }
 
-        store
-            .save()
-            .map_err(|e| format!("Failed to persist settings: {}", e))?;
+    /// Read app state stored next to the settings under `key`.
+    ///
+    /// For values the app remembers on its own, such as the launcher
+    /// geometry, which must not round-trip through `update_settings`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(None)` - Nothing stored yet, or the stored value is unreadable
+    pub fn load_state<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        let store = self.store()?;
+        Ok(store
+            .get(key)
+            .and_then(|value| serde_json::from_value(value).ok()))
+    }
 
+    /// Store app state next to the settings under `key`.
+    ///
+    /// The store writes to disk shortly afterwards (debounced), so this is
+    /// cheap enough to call on every window move.
+    pub fn save_state<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String> {
+        let value =
+            serde_json::to_value(value).map_err(|e| format!("Failed to serialize state: {}", e))?;
+        self.store()?.set(key, value);
         Ok(())
     }
 
-    /// Apply settings to the running application.
+    fn store(&self) -> Result<Arc<Store<Wry>>, String> {
+        open_store(&self.app)
+    }
+
+    /// Apply the changed parts of the settings to the running application.
     ///
-    /// Updates system state to match settings:
+    /// Only the steps in `delta` run, so saving an unrelated setting (the
+    /// model, the theme) leaves autostart and the global shortcuts alone:
     /// - Enables/disables auto-startup in the OS
-    /// - Re-registers global shortcut if changed
+    /// - Re-registers the global shortcut
+    /// - Re-registers per-action shortcuts
+    /// - Updates the launcher's always-on-top and taskbar flags
+    /// - Shows or hides the Dock icon (macOS)
+    /// - Restarts background update checks
+    /// - Changes the log level and the language of backend strings
+    /// - Starts encrypting or decrypting stored message content
+    /// - Restarts the wait for the next history maintenance pass
     ///
     /// # Arguments
     ///
     /// * `settings` - Settings to apply
-    pub fn apply(&self, settings: &AppSettings) -> Result<(), String> {
-        self.apply_auto_startup(settings.general.auto_startup)?;
-        self.apply_shortcut(&settings.shortcuts.toggle_launcher)?;
+    /// * `delta` - What changed since the last applied settings
+    #[tracing::instrument(skip_all, fields(steps = ?delta.steps()))]
+    pub fn apply(&self, settings: &AppSettings, delta: &SettingsDelta) -> Result<(), String> {
+        delta.run(|step| match step {
+            ApplyStep::LogLevel => {
+                logging::apply_level(&self.app, settings.general.log_level);
+                Ok(())
+            }
+            ApplyStep::Locale => {
+                i18n::apply(settings.general.locale.as_deref());
+                Ok(())
+            }
+            ApplyStep::AutoStartup => self.apply_auto_startup(settings.general.auto_startup),
+            ApplyStep::ToggleShortcut => self.apply_shortcut(&settings.shortcuts.toggle_launcher),
+            ApplyStep::ActionShortcuts => self.apply_action_shortcuts(settings),
+            ApplyStep::LauncherWindow => self.apply_launcher_window(&settings.launcher),
+            ApplyStep::DockIcon => self.apply_dock_icon(settings),
+            ApplyStep::UpdateSchedule => {
+                updater::apply_schedule(&self.app, &settings.updates);
+                Ok(())
+            }
+            ApplyStep::RequestLimits => {
+                if let Some(limiter) = self.app.try_state::<llm::RequestLimiter>() {
+                    limiter.configure(llm::Limits::from_settings(&settings.llm));
+                }
+                Ok(())
+            }
+            ApplyStep::ContentEncryption => {
+                history::encryption::apply(&self.app, &settings.history)
+            }
+            ApplyStep::MaintenanceSchedule => {
+                history::reschedule_maintenance(&self.app);
+                Ok(())
+            }
+        })
+    }
+
+    /// Apply `launcher.always_on_top` and `launcher.skip_taskbar` to the
+    /// launcher window.
+    ///
+    /// Only flags that differ from the last applied ones are touched, so
+    /// this is also used at startup. A pinned launcher stays on top
+    /// regardless of `always_on_top`. `skip_taskbar` is ignored on macOS,
+    /// where windows can't leave the Dock individually (see
+    /// [`Self::apply_dock_icon`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `launcher` - Launcher settings to apply
+    pub fn apply_launcher_window(&self, launcher: &LauncherSettings) -> Result<(), String> {
+        let Some(window) = self.app.get_webview_window("main") else {
+            return Ok(());
+        };
+        let mut current = self
+            .current_window_flags
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        let (old_on_top, old_skip) = match *current {
+            Some((on_top, skip)) => (Some(on_top), Some(skip)),
+            None => (None, None),
+        };
+
+        if old_on_top != Some(launcher.always_on_top) {
+            let pinned = self
+                .app
+                .try_state::<LauncherState>()
+                .is_some_and(|state| state.is_pinned());
+            window
+                .set_always_on_top(toggle::keeps_on_top(pinned, launcher.always_on_top))
+                .map_err(|e| format!("Failed to set always on top: {}", e))?;
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        if old_skip != Some(launcher.skip_taskbar) {
+            window
+                .set_skip_taskbar(launcher.skip_taskbar)
+                .map_err(|e| format!("Failed to set taskbar visibility: {}", e))?;
+        }
+        #[cfg(target_os = "macos")]
+        let _ = old_skip;
+
+        *current = Some((launcher.always_on_top, launcher.skip_taskbar));
+        Ok(())
+    }
+
+    /// Apply `general.hide_dock_icon` by switching between the accessory
+    /// (menu bar only) and regular activation policies.
+    ///
+    /// Only acts when the policy differs from the last applied one, so this
+    /// is also used at startup. Does nothing outside macOS.
+    pub fn apply_dock_icon(&self, settings: &AppSettings) -> Result<(), String> {
+        let policy = dock::activation_policy(&settings.general);
+        let mut current = self
+            .current_activation_policy
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        if *current != Some(policy) {
+            dock::set_activation_policy(&self.app, policy)?;
+            *current = Some(policy);
+        }
         Ok(())
     }
 
     /// Enable or disable auto-startup.
     ///
     /// Only performs an action if the current state differs from the
-    /// desired state to avoid unnecessary system calls.
+    /// desired state to avoid unnecessary system calls. An enabled entry
+    /// registered with another executable path or arguments is refreshed
+    /// (see [`autostart::reconcile`]).
     fn apply_auto_startup(&self, enabled: bool) -> Result<(), String> {
-        let autostart = self.app.autolaunch();
-        let is_enabled = autostart.is_enabled().unwrap_or(false);
+        let enabled = autostart_allowed(enabled);
+        let is_enabled = self.app.autolaunch().is_enabled().unwrap_or(false);
+        let current = AutostartEntry::current(&self.app)?;
+        let stored: Option<AutostartEntry> = self.load_state(AUTOSTART_ENTRY_KEY)?;
+        let action = autostart::reconcile(enabled, is_enabled, stored.as_ref(), &current);
+        self.perform_autostart(action, current)
+    }
 
-        if enabled && !is_enabled {
-            autostart
-                .enable()
-                .map_err(|e| format!("Failed to enable autostart: {}", e))?;
-        } else if !enabled && is_enabled {
+    /// Register the login item afresh, or remove it if auto-startup is off.
+    ///
+    /// For when the OS state disagrees with `general.auto_startup`, e.g.
+    /// after the entry was removed or edited outside the app.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - Whether auto-startup is now enabled in the OS
+    /// * `Err(String)` - The login item couldn't be changed
+    pub fn repair_auto_startup(&self) -> Result<bool, String> {
+        let enabled = autostart_allowed(self.snapshot()?.general.auto_startup);
+        let is_enabled = self.get_auto_startup_status().unwrap_or(false);
+        let current = AutostartEntry::current(&self.app)?;
+        self.perform_autostart(autostart::repair(enabled, is_enabled), current)?;
+        self.get_auto_startup_status()
+    }
+
+    /// Carry out `action` and remember the entry if one was registered.
+    fn perform_autostart(
+        &self,
+        action: AutostartAction,
+        current: AutostartEntry,
+    ) -> Result<(), String> {
+        let autostart = self.app.autolaunch();
+        let disable = || {
             autostart
                 .disable()
-                .map_err(|e| format!("Failed to disable autostart: {}", e))?;
-        }
+                .map_err(|e| format!("Failed to disable autostart: {}", e))
+        };
+        let enable = || {
+            autostart
+                .enable()
+                .map_err(|e| format!("Failed to enable autostart: {}", e))
+        };
 
-        Ok(())
+        match action {
+            AutostartAction::Keep => return Ok(()),
+            AutostartAction::Disable => return disable(),
+            AutostartAction::Enable => enable()?,
+            AutostartAction::Refresh => {
+                tracing::info!(
+                    executable = %current.executable,
+                    "Refreshing the autostart entry"
+                );
+                // The entry may already be gone; enabling decides
+                let _ = disable();
+                enable()?;
+            }
+        }
+        self.save_state(AUTOSTART_ENTRY_KEY, &current)
     }
 
     /// Apply a new global shortcut.
@@ -157,6 +388,9 @@ impl SettingsManager {
     /// 4. Register new shortcut
     /// 5. Store as current for future comparisons
     ///
+    /// While shortcuts are paused, only step 5 happens; the new shortcut is
+    /// registered on resume.
+    ///
     /// # Arguments
     ///
     /// * `shortcut_str` - Shortcut string like "Alt+Shift+Space"
@@ -175,23 +409,160 @@ impl SettingsManager {
             .lock()
             .map_err(|e| format!("Lock error: {}", e))?;
 
+        let paused = self.shortcuts_paused();
+
         // No-op if shortcut hasn't changed
         if let Some(ref old_shortcut) = *current {
             if *old_shortcut == new_shortcut {
                 return Ok(());
             }
-            let _ = global_shortcut.unregister(old_shortcut.clone());
+            if !paused {
+                let _ = global_shortcut.unregister(*old_shortcut);
+            }
         }
 
-        global_shortcut
-            .register(new_shortcut.clone())
-            .map_err(|e| format!("Failed to register shortcut '{}': {}", shortcut_str, e))?;
+        if !paused {
+            global_shortcut.register(new_shortcut).map_err(|e| {
+                t(
+                    "shortcut.register_failed",
+                    &[("shortcut", &shortcut_str), ("error", &e)],
+                )
+            })?;
+        }
 
         *current = Some(new_shortcut);
 
         Ok(())
     }
 
+    /// Apply the per-action shortcuts.
+    ///
+    /// Unregisters the previous ones and registers the new ones if they
+    /// changed; also used at startup. While shortcuts are paused they are
+    /// only remembered and registered on resume.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - Settings with the shortcuts and quick actions
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a shortcut is invalid, conflicts with another one,
+    /// or is already in use by another application. Shortcuts that did
+    /// register stay active.
+    pub fn apply_action_shortcuts(&self, settings: &AppSettings) -> Result<(), String> {
+        let bindings = shortcuts::action_shortcuts(&settings.shortcuts, &settings.quick_actions)?;
+        let mut current = self
+            .action_shortcuts
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        if *current == bindings {
+            return Ok(());
+        }
+
+        if self.shortcuts_paused() {
+            *current = bindings;
+            return Ok(());
+        }
+
+        let global_shortcut = self.app.global_shortcut();
+        for (_, shortcut) in current.drain(..) {
+            let _ = global_shortcut.unregister(shortcut);
+        }
+        let mut result = Ok(());
+        for (action, shortcut) in bindings {
+            match global_shortcut.register(shortcut) {
+                Ok(()) => current.push((action, shortcut)),
+                Err(e) if result.is_ok() => {
+                    result = Err(t(
+                        "shortcut.register_action_failed",
+                        &[("action", &format!("{:?}", action)), ("error", &e)],
+                    ));
+                }
+                Err(_) => {}
+            }
+        }
+        result
+    }
+
+    /// The action bound to a shortcut, if it isn't the launcher toggle.
+    pub fn action_for(&self, shortcut: &Shortcut) -> Option<GlobalAction> {
+        self.action_shortcuts
+            .lock()
+            .ok()?
+            .iter()
+            .find(|(_, bound)| bound == shortcut)
+            .map(|(action, _)| action.clone())
+    }
+
+    /// Whether `shortcut_str` is the toggle shortcut currently registered.
+    ///
+    /// `false` if registering it failed, e.g. because another app holds
+    /// the key combination (startup then falls back to the default).
+    pub fn is_shortcut_registered(&self, shortcut_str: &str) -> bool {
+        let Ok(shortcut) = parse_shortcut(shortcut_str) else {
+            return false;
+        };
+        self.current_shortcut
+            .lock()
+            .is_ok_and(|current| *current == Some(shortcut))
+    }
+
+    /// Whether the global shortcut is paused.
+    pub fn shortcuts_paused(&self) -> bool {
+        self.shortcuts_paused.load(Ordering::SeqCst)
+    }
+
+    /// Pause or resume the global shortcut.
+    ///
+    /// Pausing unregisters the shortcut so the key combination reaches other
+    /// apps; resuming registers it again. The pause isn't persisted.
+    ///
+    /// # Arguments
+    ///
+    /// * `paused` - Whether the shortcut should be paused
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shortcut can't be registered again on resume
+    /// (e.g. another app took it meanwhile); it stays paused in that case.
+    pub fn set_shortcuts_paused(&self, paused: bool) -> Result<(), String> {
+        let current = self
+            .current_shortcut
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        if self.shortcuts_paused() == paused {
+            return Ok(());
+        }
+
+        let global_shortcut = self.app.global_shortcut();
+        if let Some(shortcut) = *current {
+            if paused {
+                let _ = global_shortcut.unregister(shortcut);
+            } else {
+                global_shortcut.register(shortcut).map_err(|e| {
+                    t(
+                        "shortcut.register_failed",
+                        &[("shortcut", &shortcut), ("error", &e)],
+                    )
+                })?;
+            }
+        }
+        // Action shortcuts are best-effort; the toggle decides the result
+        if let Ok(actions) = self.action_shortcuts.lock() {
+            for (_, shortcut) in actions.iter() {
+                if paused {
+                    let _ = global_shortcut.unregister(*shortcut);
+                } else if let Err(e) = global_shortcut.register(*shortcut) {
+                    tracing::warn!(error = %e, "Failed to register shortcut");
+                }
+            }
+        }
+
+        self.shortcuts_paused.store(paused, Ordering::SeqCst);
+        Ok(())
+    }
+
     /// Register the initial shortcut on application startup.
     ///
     /// Unlike `apply_shortcut`, this doesn't try to unregister an old shortcut
@@ -204,9 +575,12 @@ impl SettingsManager {
         let new_shortcut = parse_shortcut(shortcut_str)?;
 
         let global_shortcut = self.app.global_shortcut();
-        global_shortcut
-            .register(new_shortcut.clone())
-            .map_err(|e| format!("Failed to register shortcut '{}': {}", shortcut_str, e))?;
+        global_shortcut.register(new_shortcut).map_err(|e| {
+            t(
+                "shortcut.register_failed",
+                &[("shortcut", &shortcut_str), ("error", &e)],
+            )
+        })?;
 
         let mut current = self
             .current_shortcut
@@ -228,11 +602,121 @@ impl SettingsManager {
             .map_err(|e| format!("Failed to check autostart status: {}", e))
     }
 
+    /// Turn auto-startup on or off and save it as `general.auto_startup`.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to start the app at login
+    pub async fn set_auto_startup(&self, enabled: bool) -> Result<(), String> {
+        self.apply_auto_startup(enabled)?;
+        self.update(|settings| {
+            settings.general.auto_startup = enabled;
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
     /// Apply only auto-startup setting.
     ///
-    /// Used during initial setup to avoid double shortcut registration.
+    /// Used during initial setup to avoid double shortcut registration;
+    /// also refreshes a stale login item.
     /// The shortcut is registered separately via `register_initial_shortcut`.
     pub fn apply_auto_startup_only(&self, settings: &AppSettings) -> Result<(), String> {
         self.apply_auto_startup(settings.general.auto_startup)
     }
 }
+
+/// `general.auto_startup`, unless the app runs in portable mode, where
+/// the login item would point at a path that's gone on the next machine.
+fn autostart_allowed(enabled: bool) -> bool {
+    if enabled && paths::is_portable() {
+        tracing::warn!("Autostart is disabled in portable mode");
+        return false;
+    }
+    enabled
+}
+
+/// Path of the settings store in the data dir (`dev_settings.json` when
+/// `QWIK_ASK_DEV` is set).
+pub fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let mut settings_file = "settings.json";
+    if env::var("QWIK_ASK_DEV").is_ok() {
+        settings_file = "dev_settings.json";
+    }
+
+    Ok(paths::data_dir(app)?.join(settings_file))
+}
+
+impl SettingsTarget for SettingsManager {
+    fn snapshot(&self) -> Result<Arc<AppSettings>, String> {
+        SettingsManager::snapshot(self)
+    }
+
+    fn save(&self, settings: &AppSettings) -> Result<(), String> {
+        SettingsManager::save(self, settings)
+    }
+
+    fn apply(&self, settings: &AppSettings, delta: &SettingsDelta) -> Result<(), String> {
+        SettingsManager::apply(self, settings, delta)
+    }
+
+    async fn audit(
+        &self,
+        source: AuditSource,
+        revision: u64,
+        settings: &AppSettings,
+        changes: Vec<SettingChange>,
+    ) {
+        let Some(db) = self.app.try_state::<Db>() else {
+            return;
+        };
+        let pool = db.pool().clone();
+        let limit = settings.history.settings_audit_limit;
+        if let Err(e) = audit::record(&pool, source, revision, changes, limit, db::now_ms()).await {
+            tracing::warn!(error = %e, "Failed to record settings change");
+        }
+    }
+
+    fn changed(&self, revision: u64, settings: &AppSettings) {
+        let _ = self.app.emit(
+            CHANGED_EVENT,
+            SettingsChanged {
+                revision,
+                settings: settings.clone(),
+                model_names: llm::models::display_names(&settings.llm),
+            },
+        );
+        tray::refresh_menu(&self.app);
+    }
+}
+
+/// Open the settings store.
+fn open_store(app: &AppHandle) -> Result<Arc<Store<Wry>>, String> {
+    app.store(settings_path(app)?)
+        .map_err(|e| format!("Failed to access store: {}", e))
+}
+
+/// The settings in `tauri-plugin-store`, under the `settings` key.
+struct StoreBackend {
+    app: AppHandle,
+}
+
+impl SettingsBackend for StoreBackend {
+    fn read(&self) -> Result<Option<serde_json::Value>, String> {
+        Ok(open_store(&self.app)?.get("settings"))
+    }
+
+    /// Update the store and write it to disk on a background thread, so a
+    /// save doesn't wait for the disk.
+    fn write(&self, value: serde_json::Value) -> Result<(), String> {
+        let store = open_store(&self.app)?;
+        store.set("settings", value);
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = store.save() {
+                tracing::warn!(error = %e, "Failed to persist settings");
+            }
+        });
+        Ok(())
+    }
+}
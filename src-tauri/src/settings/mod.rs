@@ -1,12 +1,17 @@
 //! Settings feature module.
 //!
 //! Provides persistent storage for application settings using `tauri-plugin-store`.
-//! Settings are stored in `settings.json` in the app data directory.
+//! Settings are stored in `settings.json` in the app data directory, or
+//! beside the executable in portable mode (see [`crate::paths`]).
 //!
 //! # Architecture
 //!
 //! - [`types`] - Data structures (`AppSettings`, `Theme`, `LlmProvider`) and defaults
 //! - [`manager`] - `SettingsManager` for load/save/apply operations
+//! - [`delta`] - `SettingsDelta`, what a save changed and needs applying
+//! - [`revision`] - One update at a time, and the `settings-changed` revision
+//! - [`audit`] - What each update changed, kept in the history database
+//! - [`autostart`] - Keeping the login item's path and arguments current
 //! - This file - Tauri commands exposed to the frontend
 //!
 //! # Frontend Integration
@@ -17,19 +22,47 @@
 //! // Load settings
 //! const settings = await invoke<AppSettings>('get_settings');
 //!
-//! // Update settings
-//! await invoke('update_settings', { settings: newSettings });
+//! // Update settings; other windows get `settings-changed` with the revision
+//! const revision = await invoke<number>('update_settings', { settings: newSettings });
+//!
+//! // Re-register the login item when the OS disagrees with the setting
+//! if ((await invoke<boolean>('get_auto_startup_status')) !== settings.general.auto_startup) {
+//!   await invoke<boolean>('repair_autostart');
+//! }
+//!
+//! // What changed recently, newest first
+//! const entries = await invoke<SettingsAuditEntry[]>('get_settings_audit', { limit: 20 });
+//!
+//! // Temporarily release the global shortcut
+//! await invoke('set_shortcuts_paused', { paused: true });
 //! ```
 
+pub mod audit;
+mod autostart;
+mod cache;
+mod delta;
 mod manager;
+mod revision;
 mod types;
 
 use std::env;
 
+pub use audit::SettingsAuditEntry;
+pub use autostart::AUTOSTART_ARGS;
 pub use manager::SettingsManager;
-pub use types::AppSettings;
+pub use types::{
+    AppSettings, AzureSettings, GeneralSettings, HistorySettings, LauncherPlacement,
+    LauncherSettings, LlmProfile, LlmProvider, LlmSettings, LogLevel, ModelPrice, NetworkSettings,
+    QuickAction, QuickActionInput, QuickActionOutput, ShortcutSettings, ToggleBehavior,
+    TrayLeftClick, UpdateChannel, UpdateSettings,
+};
 
-use tauri::{AppHandle, Manager, State};
+use crate::db::Db;
+use crate::env_file::EnvFile;
+use crate::llm::{headers, language};
+use crate::paths;
+use crate::tray;
+use tauri::{AppHandle, State};
 use tauri_plugin_opener::OpenerExt;
 
 // ============================================================================
@@ -56,11 +89,34 @@ pub fn get_settings(settings_manager: State<SettingsManager>) -> Result<AppSetti
     settings_manager.load()
 }
 
+/// Get the most recent entries of the settings audit log.
+///
+/// # Arguments
+///
+/// * `limit` - Maximum number of entries to return
+///
+/// # Returns
+///
+/// * `Ok(Vec<SettingsAuditEntry>)` - Entries, newest first, secrets masked
+/// * `Err(String)` - The history database couldn't be read
+#[tauri::command]
+pub async fn get_settings_audit(
+    db: State<'_, Db>,
+    limit: u32,
+) -> Result<Vec<SettingsAuditEntry>, String> {
+    audit::list(db.pool(), limit).await
+}
+
 /// Update application settings.
 ///
-/// Saves settings to disk and applies them immediately:
-/// - Updates auto-startup registry entry
-/// - Re-registers global shortcut if changed
+/// Saves settings to disk and applies what changed immediately:
+/// - Updates auto-startup registry entry if `general.auto_startup` changed
+/// - Re-registers global shortcuts if their keys changed
+/// - Updates the launcher's always-on-top and taskbar visibility if changed
+///
+/// Settings that aren't applied, such as the model, touch nothing. A call
+/// made while an earlier one is still applying waits for it. Every window
+/// then gets `settings-changed` with the new revision.
 ///
 /// # Arguments
 ///
@@ -68,8 +124,10 @@ pub fn get_settings(settings_manager: State<SettingsManager>) -> Result<AppSetti
 ///
 /// # Returns
 ///
-/// * `Ok(())` - Settings saved and applied
-/// * `Err(String)` - Error message if save or apply fails
+/// * `Ok(u64)` - Settings saved and applied; the revision of this save
+/// * `Err(String)` - Error message if `llm.response_language` isn't supported,
+///   a header in `llm.extra_headers` or `llm.auth_header_name` is invalid,
+///   or save or apply fails
 ///
 /// # Example (Frontend)
 ///
@@ -78,13 +136,39 @@ pub fn get_settings(settings_manager: State<SettingsManager>) -> Result<AppSetti
 /// await invoke('update_settings', { settings: updated });
 /// ```
 #[tauri::command]
-pub fn update_settings(
-    settings_manager: State<SettingsManager>,
-    settings: AppSettings,
-) -> Result<(), String> {
-    settings_manager.save(&settings)?;
-    settings_manager.apply(&settings)?;
-    Ok(())
+pub async fn update_settings(
+    settings_manager: State<'_, SettingsManager>,
+    mut settings: AppSettings,
+) -> Result<u64, String> {
+    language::validate(settings.llm.response_language.as_deref())?;
+    headers::validate_settings(&settings.llm)?;
+    let (revision, ()) = settings_manager
+        .update(move |current| {
+            keep_owned_fields(current, &mut settings);
+            *current = settings;
+            Ok(())
+        })
+        .await?;
+    Ok(revision)
+}
+
+/// Copy the fields `update_settings` mustn't change from `current`.
+///
+/// They have commands of their own, and a window holding settings from
+/// before one of those ran would otherwise undo it.
+fn keep_owned_fields(current: &AppSettings, settings: &mut AppSettings) {
+    // `move_history_db`
+    settings.history.database_path = current.history.database_path.clone();
+    // `skip_update_version`
+    settings.updates.skipped_version = current.updates.skipped_version.clone();
+    // `upsert_quick_action`, `delete_quick_action`
+    settings.quick_actions = current.quick_actions.clone();
+    // `set_active_prompt`
+    settings.llm.active_prompt_id = current.llm.active_prompt_id.clone();
+    // `set_model_alias`
+    settings.llm.model_aliases = current.llm.model_aliases.clone();
+    // `toggle_favorite_model`
+    settings.llm.favorite_models = current.llm.favorite_models.clone();
 }
 
 /// Reset all settings to defaults.
@@ -93,14 +177,52 @@ pub fn update_settings(
 ///
 /// # Returns
 ///
-/// * `Ok(AppSettings)` - The default settings (for UI update)
+/// * `Ok(AppSettings)` - The settings after the reset (for UI update)
 /// * `Err(String)` - Error message if reset fails
 #[tauri::command]
-pub fn reset_settings(settings_manager: State<SettingsManager>) -> Result<AppSettings, String> {
-    let default_settings = AppSettings::default();
-    settings_manager.save(&default_settings)?;
-    settings_manager.apply(&default_settings)?;
-    Ok(default_settings)
+pub async fn reset_settings(
+    settings_manager: State<'_, SettingsManager>,
+) -> Result<AppSettings, String> {
+    let (_, settings) = settings_manager
+        .update(|settings| {
+            // Resetting mustn't lose track of a moved database
+            let database_path = settings.history.database_path.take();
+            *settings = AppSettings::default();
+            settings.history.database_path = database_path;
+            Ok(settings.clone())
+        })
+        .await?;
+    Ok(settings)
+}
+
+/// Check whether the global shortcut is paused.
+#[tauri::command]
+pub fn get_shortcuts_paused(settings_manager: State<SettingsManager>) -> bool {
+    settings_manager.shortcuts_paused()
+}
+
+/// Pause or resume the global shortcut.
+///
+/// While paused the shortcut is unregistered, so the key combination
+/// reaches other apps. The pause lasts until resumed or the app restarts.
+///
+/// # Arguments
+///
+/// * `paused` - Whether the shortcut should be paused
+///
+/// # Returns
+///
+/// * `Ok(())` - Shortcut paused or resumed
+/// * `Err(String)` - The shortcut couldn't be registered again
+#[tauri::command]
+pub fn set_shortcuts_paused(
+    app: AppHandle,
+    settings_manager: State<SettingsManager>,
+    paused: bool,
+) -> Result<(), String> {
+    let result = settings_manager.set_shortcuts_paused(paused);
+    tray::refresh_menu(&app);
+    result
 }
 
 /// Check if the application is configured to start at system login.
@@ -117,10 +239,30 @@ pub fn get_auto_startup_status(settings_manager: State<SettingsManager>) -> Resu
     settings_manager.get_auto_startup_status()
 }
 
+/// Bring the login item back in line with `general.auto_startup`.
+///
+/// Registers it afresh, with the current executable path and arguments,
+/// or removes it when auto-startup is off. Offered by the settings page
+/// when [`get_auto_startup_status`] disagrees with the setting.
+///
+/// # Returns
+///
+/// * `Ok(bool)` - Whether auto-startup is now enabled in the OS
+/// * `Err(String)` - The login item couldn't be changed
+#[tauri::command]
+pub fn repair_autostart(
+    app: AppHandle,
+    settings_manager: State<SettingsManager>,
+) -> Result<bool, String> {
+    let result = settings_manager.repair_auto_startup();
+    tray::refresh_menu(&app);
+    result
+}
+
 /// Open the settings JSON file in the default system editor.
 ///
 /// Useful for advanced users who want to manually edit settings.
-/// The file is located at `{app_data_dir}/settings.json`.
+/// The file is located at `{data_dir}/settings.json`.
 ///
 /// # Returns
 ///
@@ -128,13 +270,7 @@ pub fn get_auto_startup_status(settings_manager: State<SettingsManager>) -> Resu
 /// * `Err(String)` - Error if file path resolution or opening fails
 #[tauri::command]
 pub async fn open_settings_file(app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-
-    let settings_path = app_data_dir.join("settings.json");
-    let path_str = settings_path.to_string_lossy().to_string();
+    let path_str = manager::settings_path(&app)?.to_string_lossy().to_string();
 
     app.opener()
         .open_path(path_str, None::<&str>)
@@ -143,11 +279,83 @@ pub async fn open_settings_file(app: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// Open the data directory (settings, logs, backups) in the file manager.
+///
+/// In portable mode this is the `data` directory beside the executable.
+///
+/// # Returns
+///
+/// * `Ok(())` - Directory opened
+/// * `Err(String)` - The path couldn't be resolved or opened
 #[tauri::command]
-pub async fn get_environment_variable(env_name: String) -> Result<Option<String>, String> {
+pub async fn open_app_data_dir(app: AppHandle) -> Result<(), String> {
+    let dir = paths::data_dir(&app)?;
+    app.opener()
+        .open_path(dir.to_string_lossy().to_string(), None::<&str>)
+        .map_err(|e| format!("Failed to open data dir: {}", e))
+}
+
+/// Get an environment variable, from `.env` in the data directory if set
+/// there (see [`crate::env_file`]), otherwise from the process environment.
+///
+/// # Returns
+///
+/// * `Ok(Some(String))` - The value
+/// * `Ok(None)` - The variable isn't set
+/// * `Err(String)` - The value isn't valid Unicode
+#[tauri::command]
+pub async fn get_environment_variable(
+    env_file: State<'_, EnvFile>,
+    env_name: String,
+) -> Result<Option<String>, String> {
+    if let Some(value) = env_file.get(&env_name) {
+        return Ok(Some(value));
+    }
     match env::var(env_name) {
         Ok(value) => Ok(Some(value)),
         Err(env::VarError::NotPresent) => Ok(None),
-        Err(e) =>Err(format!("Unable to read env: {}", e))
+        Err(e) => Err(format!("Unable to read env: {}", e)),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_settings_keep_command_owned_fields() {
+        let mut current = AppSettings::default();
+        current.history.database_path = Some("/data/history.db".to_string());
+        current.updates.skipped_version = Some("1.2.0".to_string());
+        current.quick_actions = vec![QuickAction {
+            id: "a".to_string(),
+            name: "Summarize".to_string(),
+            prompt_template: "Summarize: {{input}}".to_string(),
+            input: Default::default(),
+            output: Default::default(),
+            shortcut: None,
+        }];
+        current.llm.active_prompt_id = Some("translator".to_string());
+        current
+            .llm
+            .model_aliases
+            .insert("gpt-4o".to_string(), "Daily".to_string());
+        current.llm.favorite_models = vec!["gpt-4o".to_string()];
+
+        // A window that loaded its settings before those commands ran
+        let mut stale = AppSettings::default();
+        stale.shortcuts.toggle_launcher = "Alt+Q".to_string();
+        keep_owned_fields(&current, &mut stale);
+
+        assert_eq!(stale.shortcuts.toggle_launcher, "Alt+Q");
+        assert_eq!(
+            stale.history.database_path.as_deref(),
+            Some("/data/history.db")
+        );
+        assert_eq!(stale.updates.skipped_version.as_deref(), Some("1.2.0"));
+        assert_eq!(stale.quick_actions, current.quick_actions);
+        assert_eq!(stale.llm.active_prompt_id.as_deref(), Some("translator"));
+        assert_eq!(stale.llm.model_aliases["gpt-4o"], "Daily");
+        assert_eq!(stale.llm.favorite_models, vec!["gpt-4o".to_string()]);
+    }
+}
@@ -0,0 +1,343 @@
+//! One settings update at a time.
+//!
+//! Two quick saves from the settings page used to interleave their save
+//! and apply steps, so the shortcut registered could end up being the one
+//! from the earlier save while the later one was on disk. [`UpdateLock`]
+//! runs the whole update (read the current settings, change them, save,
+//! apply, notify) under an async mutex; an update that arrives while
+//! another is applying waits for it. Every settings write goes through it,
+//! so none skips the revision, the audit log or `settings-changed`.
+//!
+//! Each save bumps a revision, returned by `update_settings` and sent with
+//! `settings-changed`, so a window can ignore an event older than the
+//! settings it already has. Saves that change anything are also recorded
+//! in the audit log (see [`super::audit`]).
+//!
+//! ```typescript
+//! let revision = await invoke<number>('update_settings', { settings });
+//! await listen<SettingsChanged>('settings-changed', ({ payload }) => {
+//!   if (payload.revision <= revision) return; // our own save, or a stale one
+//!   revision = payload.revision;
+//!   form.reset(payload.settings);
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use super::audit::AuditSource;
+use super::delta::{SettingChange, SettingsDelta};
+use super::types::AppSettings;
+
+/// Event sent after every settings update.
+pub const CHANGED_EVENT: &str = "settings-changed";
+
+/// Payload of the `settings-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsChanged {
+    /// Revision of the save; higher is newer
+    pub revision: u64,
+    pub settings: AppSettings,
+    /// Display names of the configured and favorite models, keyed by model
+    /// ID (see `llm::models`)
+    pub model_names: HashMap<String, String>,
+}
+
+/// What an update reads, saves and applies; the [`super::SettingsManager`]
+/// in the app.
+pub trait SettingsTarget {
+    fn snapshot(&self) -> Result<Arc<AppSettings>, String>;
+
+    fn save(&self, settings: &AppSettings) -> Result<(), String>;
+
+    fn apply(&self, settings: &AppSettings, delta: &SettingsDelta) -> Result<(), String>;
+
+    /// Record what a saved update changed; never fails the update.
+    async fn audit(
+        &self,
+        source: AuditSource,
+        revision: u64,
+        settings: &AppSettings,
+        changes: Vec<SettingChange>,
+    );
+
+    /// Tell the app the settings changed; called while still holding the
+    /// lock, so notifications go out in revision order.
+    fn changed(&self, revision: u64, settings: &AppSettings);
+}
+
+/// Serializes settings updates and counts their revisions.
+#[derive(Default)]
+pub struct UpdateLock(Mutex<u64>);
+
+impl UpdateLock {
+    /// Change the settings, save them and apply what changed, after any
+    /// update in progress.
+    ///
+    /// `mutate` changes a copy of the settings read under the lock, so an
+    /// update only touches what it means to and never undoes one that
+    /// finished while it waited.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Where the settings live
+    /// * `source` - Where the update came from, for the audit log
+    /// * `mutate` - Changes the current settings; an error cancels the
+    ///   update
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((u64, T))` - Revision of this save, and what `mutate` returned
+    /// * `Err(String)` - `mutate` or saving failed (nothing is saved and
+    ///   the revision isn't used), or applying failed (the settings are
+    ///   saved and announced anyway)
+    pub async fn commit<T>(
+        &self,
+        target: &impl SettingsTarget,
+        source: AuditSource,
+        mutate: impl FnOnce(&mut AppSettings) -> Result<T, String>,
+    ) -> Result<(u64, T), String> {
+        let mut revision = self.0.lock().await;
+        let current = target.snapshot()?;
+        let mut settings = (*current).clone();
+        let value = mutate(&mut settings)?;
+        let delta = SettingsDelta::between(&current, &settings);
+
+        target.save(&settings)?;
+        *revision += 1;
+        let applied = if delta.is_empty() {
+            Ok(())
+        } else {
+            target.apply(&settings, &delta)
+        };
+        let changes = SettingsDelta::changes(&current, &settings);
+        if !changes.is_empty() {
+            target.audit(source, *revision, &settings, changes).await;
+        }
+        target.changed(*revision, &settings);
+        applied.map(|()| (*revision, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::delta::ApplyStep;
+    use std::sync::Mutex as StdMutex;
+    use std::time::Duration;
+
+    /// Settings in memory, with the toggle shortcut "registered" slowly
+    /// enough for unserialized updates to interleave.
+    #[derive(Default)]
+    struct FakeTarget {
+        stored: StdMutex<Arc<AppSettings>>,
+        registered: StdMutex<String>,
+        events: StdMutex<Vec<(u64, String)>>,
+        audited: StdMutex<Vec<(u64, Vec<SettingChange>)>>,
+        fail_apply: bool,
+    }
+
+    impl SettingsTarget for FakeTarget {
+        fn snapshot(&self) -> Result<Arc<AppSettings>, String> {
+            Ok(Arc::clone(&self.stored.lock().unwrap()))
+        }
+
+        fn save(&self, settings: &AppSettings) -> Result<(), String> {
+            *self.stored.lock().unwrap() = Arc::new(settings.clone());
+            Ok(())
+        }
+
+        fn apply(&self, settings: &AppSettings, delta: &SettingsDelta) -> Result<(), String> {
+            if self.fail_apply {
+                return Err("Failed to register shortcut".to_string());
+            }
+            delta.run(|step| {
+                if step == ApplyStep::ToggleShortcut {
+                    std::thread::sleep(Duration::from_millis(1));
+                    *self.registered.lock().unwrap() = settings.shortcuts.toggle_launcher.clone();
+                }
+                Ok(())
+            })
+        }
+
+        async fn audit(
+            &self,
+            _source: AuditSource,
+            revision: u64,
+            _settings: &AppSettings,
+            changes: Vec<SettingChange>,
+        ) {
+            self.audited.lock().unwrap().push((revision, changes));
+        }
+
+        fn changed(&self, revision: u64, settings: &AppSettings) {
+            self.events
+                .lock()
+                .unwrap()
+                .push((revision, settings.shortcuts.toggle_launcher.clone()));
+        }
+    }
+
+    fn with_shortcut(shortcut: &str) -> AppSettings {
+        let mut settings = AppSettings::default();
+        settings.shortcuts.toggle_launcher = shortcut.to_string();
+        settings
+    }
+
+    /// A mutation replacing the settings with `new`.
+    fn replace(new: AppSettings) -> impl FnOnce(&mut AppSettings) -> Result<(), String> {
+        move |settings| {
+            *settings = new;
+            Ok(())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_updates_stay_consistent() {
+        const UPDATES: u64 = 40;
+        let target = Arc::new(FakeTarget::default());
+        let lock = Arc::new(UpdateLock::default());
+
+        let tasks: Vec<_> = (0..UPDATES)
+            .map(|i| {
+                let (target, lock) = (Arc::clone(&target), Arc::clone(&lock));
+                tokio::spawn(async move {
+                    lock.commit(
+                        &*target,
+                        AuditSource::Ui,
+                        replace(with_shortcut(&format!("Ctrl+Shift+F{}", i % 12 + 1))),
+                    )
+                    .await
+                    .unwrap()
+                    .0
+                })
+            })
+            .collect();
+        let mut revisions = Vec::new();
+        for task in tasks {
+            revisions.push(task.await.unwrap());
+        }
+
+        revisions.sort_unstable();
+        assert_eq!(revisions, (1..=UPDATES).collect::<Vec<_>>());
+
+        let events = target.events.lock().unwrap();
+        let (last_revision, last_shortcut) = events.last().unwrap();
+        assert_eq!(*last_revision, UPDATES);
+        assert!(events.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let on_disk = target.snapshot().unwrap().shortcuts.toggle_launcher.clone();
+        assert_eq!(on_disk, *last_shortcut);
+        assert_eq!(*target.registered.lock().unwrap(), on_disk);
+    }
+
+    #[tokio::test]
+    async fn test_mutate_changes_the_current_settings() {
+        let target = FakeTarget::default();
+        let lock = UpdateLock::default();
+        let mut moved = AppSettings::default();
+        moved.history.database_path = Some("/data/history.db".into());
+        target.save(&moved).unwrap();
+
+        let (_, old) = lock
+            .commit(&target, AuditSource::Ui, |settings| {
+                Ok(settings
+                    .updates
+                    .skipped_version
+                    .replace("1.2.0".to_string()))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(old, None);
+        let stored = target.snapshot().unwrap();
+        assert_eq!(stored.updates.skipped_version.as_deref(), Some("1.2.0"));
+        assert_eq!(
+            stored.history.database_path.as_deref(),
+            Some("/data/history.db")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_mutate_saves_nothing() {
+        let target = FakeTarget::default();
+        let lock = UpdateLock::default();
+
+        let result = lock
+            .commit(&target, AuditSource::Ui, |settings| {
+                settings.shortcuts.toggle_launcher = "Alt+Q".to_string();
+                Err::<(), _>("Quick action 'x' not found".to_string())
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert!(target.events.lock().unwrap().is_empty());
+        assert_eq!(
+            target.snapshot().unwrap().shortcuts.toggle_launcher,
+            AppSettings::default().shortcuts.toggle_launcher
+        );
+        // The revision wasn't used
+        let (revision, ()) = lock
+            .commit(&target, AuditSource::Ui, replace(AppSettings::default()))
+            .await
+            .unwrap();
+        assert_eq!(revision, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_apply_is_still_saved_and_announced() {
+        let target = FakeTarget {
+            fail_apply: true,
+            ..FakeTarget::default()
+        };
+        let lock = UpdateLock::default();
+
+        let result = lock
+            .commit(&target, AuditSource::Ui, replace(with_shortcut("Alt+Q")))
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(*target.events.lock().unwrap(), [(1, "Alt+Q".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_settings_still_get_a_revision() {
+        let target = FakeTarget::default();
+        let lock = UpdateLock::default();
+
+        let (first, ()) = lock
+            .commit(&target, AuditSource::Ui, replace(AppSettings::default()))
+            .await
+            .unwrap();
+        let (second, ()) = lock
+            .commit(&target, AuditSource::Ui, replace(AppSettings::default()))
+            .await
+            .unwrap();
+
+        assert_eq!((first, second), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_only_saves_that_change_something_are_audited() {
+        let target = FakeTarget::default();
+        let lock = UpdateLock::default();
+
+        lock.commit(&target, AuditSource::Ui, replace(AppSettings::default()))
+            .await
+            .unwrap();
+        lock.commit(&target, AuditSource::Ui, replace(with_shortcut("Alt+Q")))
+            .await
+            .unwrap();
+
+        let audited = target.audited.lock().unwrap();
+        assert_eq!(audited.len(), 1);
+        let (revision, changes) = &audited[0];
+        assert_eq!(*revision, 2);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "shortcuts.toggle_launcher");
+        assert_eq!(changes[0].new, "Alt+Q");
+    }
+}
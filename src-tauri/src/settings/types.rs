@@ -9,26 +9,120 @@
 //! AppSettings
 //! ├── GeneralSettings
 //! │   ├── auto_startup: bool
-//! │   └── theme: Theme (dark/light/system)
+//! │   ├── theme: Theme (dark/light/system)
+//! │   ├── restore_last_conversation: bool (reopen the latest thread on show)
+//! │   ├── restore_focus: bool (refocus the previous app when the launcher hides)
+//! │   ├── tray_left_click: TrayLeftClick (open_launcher/open_settings)
+//! │   ├── notify_on_completion: bool (notify when a slow answer lands while hidden)
+//! │   ├── tts_enabled: bool (allow reading answers aloud)
+//! │   ├── tts_voice: Option<String> (None = system default voice)
+//! │   ├── tts_rate: f32 (speaking speed, 0.5-2.0)
+//! │   ├── log_level: LogLevel (error/warn/info/debug)
+//! │   ├── hide_dock_icon: bool (menu-bar app without a Dock icon; macOS only)
+//! │   ├── locale: Option<String> (None = follow the OS language)
+//! │   ├── telemetry_enabled: bool (opt-in anonymous usage events)
+//! │   └── telemetry_endpoint: Option<String> (where usage events are sent)
 //! ├── ShortcutSettings
-//! │   └── toggle_launcher: String
+//! │   ├── toggle_launcher: String
+//! │   ├── peek_mode: bool (hold the shortcut to peek, release to hide)
+//! │   ├── peek_hold_ms: u32 (how long a press must last to count as a peek)
+//! │   ├── ask_clipboard: Option<String> (show the launcher prefilled with the clipboard)
+//! │   └── ask_selection: Option<String> (show the launcher prefilled with the selected text)
+//! ├── LauncherSettings
+//! │   ├── placement: LauncherPlacement (center/top_center/near_cursor/remember_last)
+//! │   ├── position_locked: bool (always show at the saved position, overriding placement)
+//! │   ├── hide_on_blur: bool (hide when another app takes focus, unless pinned)
+//! │   ├── toggle_behavior: ToggleBehavior (toggle/focus_first)
+//! │   ├── max_height: u32 (tallest the launcher grows to fit an answer)
+//! │   ├── always_on_top: bool (stay above other windows, pinned or not)
+//! │   ├── skip_taskbar: bool (hide from the taskbar/Alt-Tab; not on macOS)
+//! │   ├── detached_width: u32 (initial width of detached conversation windows)
+//! │   ├── detached_height: u32 (initial height of detached conversation windows)
+//! │   ├── clipboard_max_chars: u32 (longest clipboard text prefilled into the prompt)
+//! │   └── file_max_kb: u32 (largest file read whole into the prompt)
+//! ├── HistorySettings
+//! │   ├── enabled: bool (privacy switch for features built on history)
+//! │   ├── auto_backup_interval_days: u32 (0 = automatic backups off)
+//! │   ├── backup_keep_count: u32 (newest backups kept by rotation)
+//! │   ├── draft_retention_days: u32 (unsent drafts kept this long, 0 = forever)
+//! │   ├── prompt_history_limit: u32 (submitted prompts kept for recall, 0 = none recorded)
+//! │   ├── settings_audit_limit: u32 (settings changes kept in the audit log, 0 = none recorded)
+//! │   ├── retention_days: u32 (conversations kept this long after their last update, 0 = forever)
+//! │   ├── auto_maintenance: bool (daily retention, trash, draft and size cleanup)
+//! │   ├── database_path: Option<String> (set by move_history_db)
+//! │   ├── encrypt_content: bool (message content encrypted at rest)
+//! │   ├── search_encrypted: bool (keep search while content is encrypted)
+//! │   ├── max_db_size_mb: Option<u32> (warn above this database size)
+//! │   └── auto_prune: bool (delete oldest conversations above max_db_size_mb)
+//! ├── NetworkSettings
+//! │   ├── proxy_url: Option<String> (HTTP(S) proxy for LLM and update requests)
+//! │   └── request_timeout_secs: u32 (0 = no timeout)
+//! ├── UpdateSettings
+//! │   ├── auto_check: bool (check for updates in the background)
+//! │   ├── check_interval_hours: u32 (hours between background checks)
+//! │   ├── notify: bool (notify when a background check finds an update)
+//! │   ├── channel: UpdateChannel (stable/beta)
+//! │   ├── auto_install: bool (install a downloaded update on quit)
+//! │   ├── release_notes_url: Option<String> (release feed for notes across versions)
+//! │   └── skipped_version: Option<String> (update version the user dismissed)
+//! ├── quick_actions: Vec<QuickAction>
+//! │   ├── id: String
+//! │   ├── name: String (tray menu label)
+//! │   ├── prompt_template: String (`{{input}}` is replaced with the input)
+//! │   ├── input: QuickActionInput (none/clipboard/selection)
+//! │   ├── output: QuickActionOutput (show_launcher/copy_to_clipboard/insert_back)
+//! │   └── shortcut: Option<String>
 //! └── LlmSettings
-//!     ├── provider: LlmProvider (gemini/openai)
+//!     ├── provider: LlmProvider (gemini/openai/anthropic/openrouter/azureopenai/custom)
+//!     ├── preset: Option<String> (vendor from list_provider_presets, with custom)
 //!     ├── api_key: String
-//!     └── system_prompt: String
+//!     ├── title_model: Option<String> (model for conversation titles)
+//!     ├── system_prompt: String
+//!     ├── active_prompt_id: Option<String> (prompt library entry used instead)
+//!     ├── response_language: Option<String> (BCP 47 code or "auto")
+//!     ├── azure: Option<AzureSettings> (resource, deployment, api_version)
+//!     ├── extra_headers: Option<HashMap<String, String>> (OpenAI-compatible endpoints)
+//!     ├── auth_header_name: Option<String> (API key header instead of Authorization)
+//!     ├── cache_ttl_minutes: u32 (0 = cache disabled)
+//!     ├── local_answers: bool (answer arithmetic/unit conversions locally)
+//!     ├── max_concurrent_requests: u32 (requests in flight at once, default 2)
+//!     ├── requests_per_minute: u32 (0 = unlimited, default 10)
+//!     ├── queue_when_limited: bool (wait instead of failing with rate_limited)
+//!     ├── model_prices: HashMap<String, ModelPrice> (cost estimate overrides)
+//!     ├── model_aliases: HashMap<String, String> (display names for model IDs)
+//!     ├── favorite_models: Vec<String> (model IDs listed first in the switcher)
+//!     ├── profiles: Vec<LlmProfile> (named provider configurations)
+//!     └── fallback_profiles: Vec<String> (profile names tried when the primary fails)
 //! ```
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 /// Root settings structure containing all application configuration.
 ///
 /// Serialized to JSON for persistence via `tauri-plugin-store`.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppSettings {
     /// General application preferences
     pub general: GeneralSettings,
     /// Keyboard shortcut configuration
     pub shortcuts: ShortcutSettings,
+    /// Launcher window behavior
+    #[serde(default)]
+    pub launcher: LauncherSettings,
+    /// Chat history storage and backups
+    #[serde(default)]
+    pub history: HistorySettings,
+    /// Proxy and timeouts for outgoing requests
+    #[serde(default)]
+    pub network: NetworkSettings,
+    /// Background update checks
+    #[serde(default)]
+    pub updates: UpdateSettings,
+    /// Predefined prompts with their own shortcuts and tray entries
+    #[serde(default)]
+    pub quick_actions: Vec<QuickAction>,
     /// LLM provider configuration
     pub llm: LlmSettings,
 }
@@ -40,15 +134,84 @@ pub struct GeneralSettings {
     pub auto_startup: bool,
     /// UI color theme
     pub theme: Theme,
+    /// Reopen the most recent conversation when the launcher is shown
+    #[serde(default)]
+    pub restore_last_conversation: bool,
+    /// Give focus back to the previously active app when the launcher is
+    /// dismissed (Windows and macOS)
+    #[serde(default = "default_true")]
+    pub restore_focus: bool,
+    /// What left-clicking the tray icon does
+    #[serde(default)]
+    pub tray_left_click: TrayLeftClick,
+    /// Show a system notification when an answer arrives while the launcher
+    /// is hidden
+    #[serde(default = "default_true")]
+    pub notify_on_completion: bool,
+    /// Whether answers can be read aloud
+    #[serde(default = "default_true")]
+    pub tts_enabled: bool,
+    /// Voice ID from `list_voices`; `None` uses the system default
+    #[serde(default)]
+    pub tts_voice: Option<String>,
+    /// Speaking speed relative to normal (0.5-2.0)
+    #[serde(default = "default_tts_rate")]
+    pub tts_rate: f32,
+    /// Least severe level written to the log file
+    #[serde(default)]
+    pub log_level: LogLevel,
+    /// Run as a menu-bar app without a Dock icon or Cmd+Tab entry (macOS;
+    /// ignored elsewhere)
+    #[serde(default = "default_hide_dock_icon")]
+    pub hide_dock_icon: bool,
+    /// Language of tray labels, notifications and backend messages, as a
+    /// code from `get_available_locales`; `None` follows the OS
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Send anonymous usage events (see `get_telemetry_preview`); off
+    /// unless the user opts in
+    #[serde(default)]
+    pub telemetry_enabled: bool,
+    /// URL usage events are POSTed to; nothing is sent while unset
+    #[serde(default)]
+    pub telemetry_endpoint: Option<String>,
+}
+
+/// How much goes into the log file.
+///
+/// Serializes to lowercase strings: `"error"`, `"warn"`, `"info"`, `"debug"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    /// Also request details such as prompt sizes and key fingerprints
+    Debug,
+}
+
+/// What left-clicking the tray icon does.
+///
+/// Serializes to snake_case strings: `"open_launcher"`, `"open_settings"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayLeftClick {
+    /// Show the launcher
+    OpenLauncher,
+    /// Open the settings window
+    #[default]
+    OpenSettings,
 }
 
 /// UI color theme options.
 ///
 /// Serializes to lowercase strings: `"dark"`, `"light"`, `"system"`.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Theme {
     /// Always use dark theme
+    #[default]
     Dark,
     /// Always use light theme
     Light,
@@ -63,6 +226,230 @@ pub struct ShortcutSettings {
     ///
     /// Format: `"Modifier+Modifier+Key"` (e.g., `"Alt+Shift+Space"`)
     pub toggle_launcher: String,
+    /// Show the launcher only while the shortcut is held down.
+    ///
+    /// Quick taps still toggle it.
+    #[serde(default)]
+    pub peek_mode: bool,
+    /// Minimum hold, in milliseconds, that hides the launcher on release
+    #[serde(default = "default_peek_hold_ms")]
+    pub peek_hold_ms: u32,
+    /// Global hotkey that shows the launcher with the clipboard text as the
+    /// prompt; `None` leaves it unbound
+    #[serde(default)]
+    pub ask_clipboard: Option<String>,
+    /// Global hotkey that shows the launcher with the text selected in the
+    /// foreground app as the prompt; `None` leaves it unbound
+    #[serde(default)]
+    pub ask_selection: Option<String>,
+}
+
+/// Launcher window behavior.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LauncherSettings {
+    /// Where the launcher appears when shown
+    #[serde(default)]
+    pub placement: LauncherPlacement,
+    /// Show the launcher at the position saved with
+    /// `save_current_position`, whatever `placement` says
+    #[serde(default)]
+    pub position_locked: bool,
+    /// Hide the launcher when it loses focus (ignored while pinned)
+    #[serde(default = "default_true")]
+    pub hide_on_blur: bool,
+    /// What the global shortcut does while the launcher is visible
+    #[serde(default)]
+    pub toggle_behavior: ToggleBehavior,
+    /// Tallest the launcher grows to fit its content, in logical pixels
+    #[serde(default = "default_launcher_max_height")]
+    pub max_height: u32,
+    /// Keep the launcher above other windows even when it isn't pinned
+    #[serde(default)]
+    pub always_on_top: bool,
+    /// Keep the launcher out of the taskbar and Alt-Tab list.
+    ///
+    /// Not used on macOS, where `general.hide_dock_icon` keeps the whole app
+    /// out of the Dock and Cmd-Tab instead.
+    #[serde(default = "default_true")]
+    pub skip_taskbar: bool,
+    /// Initial width of detached conversation windows, in logical pixels
+    #[serde(default = "default_detached_width")]
+    pub detached_width: u32,
+    /// Initial height of detached conversation windows, in logical pixels
+    #[serde(default = "default_detached_height")]
+    pub detached_height: u32,
+    /// Longest clipboard text, in characters, prefilled into the prompt
+    #[serde(default = "default_clipboard_max_chars")]
+    pub clipboard_max_chars: u32,
+    /// Largest file, in KB, added to the prompt whole; larger files keep
+    /// only their start and end
+    #[serde(default = "default_file_max_kb")]
+    pub file_max_kb: u32,
+}
+
+/// What the global shortcut does while the launcher is visible.
+///
+/// Serializes to snake_case strings: `"toggle"`, `"focus_first"`.
+/// A pinned launcher is always focused first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToggleBehavior {
+    /// Hide it
+    #[default]
+    Toggle,
+    /// Focus it if another app has focus, hide it otherwise
+    FocusFirst,
+}
+
+/// Where the launcher window is placed when shown.
+///
+/// Serializes to snake_case strings: `"center"`, `"top_center"`,
+/// `"near_cursor"`, `"remember_last"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LauncherPlacement {
+    /// Centered on the monitor containing the cursor
+    #[default]
+    Center,
+    /// Horizontally centered near the top of the monitor containing the cursor
+    TopCenter,
+    /// Just below the cursor
+    NearCursor,
+    /// The last position and size the user left it at, remembered per
+    /// monitor layout
+    RememberLast,
+}
+
+/// Chat history storage preferences.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySettings {
+    /// Whether chat history is kept.
+    ///
+    /// When off, features derived from history (such as activity stats)
+    /// return empty results.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Days between automatic backups of the history database.
+    ///
+    /// `0` disables automatic backups.
+    #[serde(default = "default_backup_interval_days")]
+    pub auto_backup_interval_days: u32,
+    /// How many of the newest backups to keep; older ones are deleted
+    #[serde(default = "default_backup_keep_count")]
+    pub backup_keep_count: u32,
+    /// Days an unsent prompt draft is kept after it was last saved.
+    ///
+    /// `0` keeps drafts until they're submitted.
+    #[serde(default = "default_draft_retention_days")]
+    pub draft_retention_days: u32,
+    /// How many submitted prompts are kept for up-arrow recall; the oldest
+    /// go first.
+    ///
+    /// `0` records none.
+    #[serde(default = "default_prompt_history_limit")]
+    pub prompt_history_limit: u32,
+    /// How many settings saves the audit log keeps; the oldest go first.
+    ///
+    /// `0` records none.
+    #[serde(default = "default_settings_audit_limit")]
+    pub settings_audit_limit: u32,
+    /// Days a conversation is kept after its last update.
+    ///
+    /// `0` keeps conversations forever. Pinned and archived conversations,
+    /// and ones with starred messages, are never deleted.
+    #[serde(default)]
+    pub retention_days: u32,
+    /// Whether the daily maintenance pass runs (retention, trash purge,
+    /// draft cleanup, size cap, `PRAGMA optimize`)
+    #[serde(default = "default_true")]
+    pub auto_maintenance: bool,
+    /// Where the history database was moved to with `move_history_db`.
+    ///
+    /// Unset keeps it in the app config directory (or the portable data
+    /// directory). Only changed by `move_history_db`; `update_settings` and
+    /// `reset_settings` keep the current value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_path: Option<String>,
+    /// Whether message content is stored encrypted, under a key kept in
+    /// the OS keychain.
+    ///
+    /// Turning it on or off re-encrypts or decrypts existing messages in
+    /// the background.
+    #[serde(default)]
+    pub encrypt_content: bool,
+    /// Whether search stays available while content is encrypted.
+    ///
+    /// On, the search index keeps the plaintext of encrypted messages, so
+    /// it can be read from the database file; off hides search instead.
+    /// Turning it on or off indexes or drops that plaintext in the
+    /// background.
+    #[serde(default)]
+    pub search_encrypted: bool,
+    /// Database size (MB, including the WAL file) above which the hourly
+    /// check sends `history-size-warning`.
+    ///
+    /// Unset or `0` means no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_db_size_mb: Option<u32>,
+    /// Whether going over `max_db_size_mb` also deletes the oldest
+    /// conversations until the database is back under 90% of it.
+    ///
+    /// Pinned and archived conversations, and ones with starred messages,
+    /// are never deleted.
+    #[serde(default)]
+    pub auto_prune: bool,
+}
+
+/// Outgoing HTTP request preferences, shared by LLM and update requests.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkSettings {
+    /// Proxy for all requests, e.g. `http://proxy.corp.example:8080`
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// Seconds before a request is abandoned; `0` disables the timeout
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u32,
+}
+
+/// Background update check preferences.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateSettings {
+    /// Whether to check for updates in the background
+    #[serde(default = "default_true")]
+    pub auto_check: bool,
+    /// Hours between background checks
+    #[serde(default = "default_check_interval_hours")]
+    pub check_interval_hours: u32,
+    /// Show a system notification when a background check finds an update
+    #[serde(default = "default_true")]
+    pub notify: bool,
+    /// Update version the user chose to skip; cleared when a newer one
+    /// appears
+    #[serde(default)]
+    pub skipped_version: Option<String>,
+    /// Release channel to get updates from
+    #[serde(default)]
+    pub channel: UpdateChannel,
+    /// Install a downloaded update when the app quits
+    #[serde(default = "default_true")]
+    pub auto_install: bool,
+    /// Release feed (GitHub releases API format) for notes across several
+    /// versions; the project's GitHub releases when unset
+    #[serde(default)]
+    pub release_notes_url: Option<String>,
+}
+
+/// Update release channel.
+///
+/// Serializes to snake_case strings: `"stable"`, `"beta"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    /// Regular releases
+    #[default]
+    Stable,
+    /// Prereleases ahead of the next regular release
+    Beta,
 }
 
 /// Default system prompt for AI interactions.
@@ -87,71 +474,401 @@ pub struct LlmSettings {
     /// Model identifier (e.g., "gpt-4o", "gemini-2.0-flash")
     #[serde(default = "default_model")]
     pub model: String,
+    /// Cheaper model used to generate conversation titles.
+    ///
+    /// When unset, a built-in small model for the provider is used if one
+    /// is known, otherwise `model`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_model: Option<String>,
     /// Base URL for custom OpenAI-compatible endpoints
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub base_url: Option<String>,
+    /// Vendor preset (e.g. `"groq"`) supplying the base URL, auth and
+    /// quirks of the `custom` provider; `base_url` still overrides its URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    /// Azure OpenAI deployment (only used by the `azureopenai` provider)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azure: Option<AzureSettings>,
+    /// Headers added to requests to OpenAI-compatible endpoints, such as a
+    /// proxy's `X-Org-Id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_headers: Option<HashMap<String, String>>,
+    /// Header carrying `Bearer <api_key>` instead of `Authorization`, for
+    /// OpenAI-compatible endpoints
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_header_name: Option<String>,
     /// System prompt to customize AI behavior
     #[serde(default = "default_system_prompt")]
     pub system_prompt: String,
+    /// Prompt library entry used as the system prompt instead of
+    /// `system_prompt`; ignored if the prompt no longer exists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_prompt_id: Option<String>,
+    /// Language answers are always given in, appended to the system prompt
+    /// as an instruction; a BCP 47 code from `get_supported_languages` or
+    /// `"auto"` for the language of the question
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_language: Option<String>,
+    /// How long identical requests are answered from the response cache.
+    ///
+    /// `0` disables the cache entirely.
+    #[serde(default)]
+    pub cache_ttl_minutes: u32,
+    /// Answer arithmetic and unit conversions locally without calling the model
+    #[serde(default = "default_true")]
+    pub local_answers: bool,
+    /// Most requests in flight at once; further ones wait or fail
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: u32,
+    /// Most requests started per minute, in bursts of up to this many.
+    ///
+    /// `0` disables the limit.
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+    /// Whether requests over a limit wait their turn (emitting `llm-queued`)
+    /// instead of failing with `rate_limited`
+    #[serde(default = "default_true")]
+    pub queue_when_limited: bool,
+    /// Per-model price overrides for usage cost estimates, keyed by model ID
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub model_prices: HashMap<String, ModelPrice>,
+    /// Display names for model IDs, keyed by model ID; only shown, never
+    /// sent to the provider
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub model_aliases: HashMap<String, String>,
+    /// Model IDs listed first by `list_models`, in the order they were
+    /// favorited
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub favorite_models: Vec<String>,
+    /// Additional named provider configurations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<LlmProfile>,
+    /// Profile names tried in order when the primary provider is unavailable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_profiles: Vec<String>,
+}
+
+/// A named provider configuration, used as a fallback target.
+///
+/// The system prompt is shared with the primary configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmProfile {
+    /// Unique profile name referenced by `fallback_profiles`
+    pub name: String,
+    /// Which AI provider to use
+    pub provider: LlmProvider,
+    /// API key for the provider
+    pub api_key: String,
+    /// Model identifier
+    pub model: String,
+    /// Base URL for custom OpenAI-compatible endpoints
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Vendor preset for the `custom` provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    /// Azure OpenAI deployment (only used by the `azureopenai` provider)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azure: Option<AzureSettings>,
+    /// Headers added to requests to OpenAI-compatible endpoints
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_headers: Option<HashMap<String, String>>,
+    /// Header carrying the API key instead of `Authorization`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_header_name: Option<String>,
+}
+
+/// Azure OpenAI deployment configuration.
+///
+/// Requests go to
+/// `https://{resource}.openai.azure.com/openai/deployments/{deployment}/...`,
+/// or to `base_url` instead of the resource host when one is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureSettings {
+    /// Azure resource name (the `{resource}` in the endpoint host)
+    #[serde(default)]
+    pub resource: String,
+    /// Deployment name chosen when the model was deployed
+    pub deployment: String,
+    /// REST API version sent as `api-version`
+    #[serde(default = "default_azure_api_version")]
+    pub api_version: String,
+}
+
+/// Azure OpenAI API version used when none is configured.
+pub const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+
+fn default_azure_api_version() -> String {
+    DEFAULT_AZURE_API_VERSION.to_string()
+}
+
+/// A predefined prompt, run from its shortcut, the tray or the launcher.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuickAction {
+    /// Unique identifier; assigned when the action is created
+    #[serde(default)]
+    pub id: String,
+    /// Shown in the tray menu
+    pub name: String,
+    /// Prompt sent to the model, with `{{input}}` replaced by the input
+    pub prompt_template: String,
+    /// What fills in `{{input}}`
+    #[serde(default)]
+    pub input: QuickActionInput,
+    /// Where the answer goes
+    #[serde(default)]
+    pub output: QuickActionOutput,
+    /// Global hotkey; `None` leaves it unbound
+    #[serde(default)]
+    pub shortcut: Option<String>,
+}
+
+/// What a quick action's `{{input}}` is filled in with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuickActionInput {
+    /// No input; the template is the whole prompt
+    None,
+    /// The clipboard text
+    #[default]
+    Clipboard,
+    /// The text selected in the foreground app
+    Selection,
+}
+
+/// Where a quick action's answer goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuickActionOutput {
+    /// Ask in the launcher, like a typed prompt
+    #[default]
+    ShowLauncher,
+    /// Put the answer on the clipboard
+    CopyToClipboard,
+    /// Paste the answer into the foreground app
+    InsertBack,
+}
+
+/// Price of a model in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// Cost per million prompt (input) tokens
+    pub input_per_million: f64,
+    /// Cost per million completion (output) tokens
+    pub output_per_million: f64,
 }
 
 fn default_model() -> String {
     "gemini-2.0-flash".to_string()
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_hide_dock_icon() -> bool {
+    cfg!(target_os = "macos")
+}
+
+fn default_tts_rate() -> f32 {
+    1.0
+}
+
+fn default_peek_hold_ms() -> u32 {
+    400
+}
+
+fn default_launcher_max_height() -> u32 {
+    600
+}
+
+fn default_detached_width() -> u32 {
+    520
+}
+
+fn default_detached_height() -> u32 {
+    640
+}
+
+fn default_clipboard_max_chars() -> u32 {
+    8000
+}
+
+fn default_file_max_kb() -> u32 {
+    256
+}
+
+fn default_check_interval_hours() -> u32 {
+    24
+}
+
+fn default_request_timeout_secs() -> u32 {
+    60
+}
+
+fn default_max_concurrent_requests() -> u32 {
+    2
+}
+
+fn default_requests_per_minute() -> u32 {
+    10
+}
+
+fn default_backup_interval_days() -> u32 {
+    7
+}
+
+fn default_backup_keep_count() -> u32 {
+    5
+}
+
+fn default_draft_retention_days() -> u32 {
+    7
+}
+
+fn default_prompt_history_limit() -> u32 {
+    500
+}
+
+fn default_settings_audit_limit() -> u32 {
+    200
+}
+
 fn default_system_prompt() -> String {
     DEFAULT_SYSTEM_PROMPT.to_string()
 }
 
 /// Supported LLM providers.
 ///
-/// Serializes to lowercase strings: `"gemini"`, `"openai"`, `"anthropic"`, `"custom"`.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Serializes to lowercase strings: `"gemini"`, `"openai"`, `"anthropic"`,
+/// `"openrouter"`, `"azureopenai"`, `"custom"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LlmProvider {
     /// Google Gemini API
+    #[default]
     Gemini,
     /// OpenAI API
     OpenAI,
     /// Anthropic Claude API
     Anthropic,
+    /// OpenRouter (OpenAI-compatible, `vendor/model` model IDs)
+    OpenRouter,
+    /// Azure OpenAI deployment (see [`AzureSettings`])
+    AzureOpenAI,
     /// Custom OpenAI-compatible endpoint
     Custom,
 }
 
+impl LlmProvider {
+    /// The serialized name of the provider (e.g. `"openai"`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LlmProvider::Gemini => "gemini",
+            LlmProvider::OpenAI => "openai",
+            LlmProvider::Anthropic => "anthropic",
+            LlmProvider::OpenRouter => "openrouter",
+            LlmProvider::AzureOpenAI => "azureopenai",
+            LlmProvider::Custom => "custom",
+        }
+    }
+}
+
 // ============================================================================
 // Default Implementations
 // ============================================================================
 
-impl Default for AppSettings {
+impl Default for GeneralSettings {
     fn default() -> Self {
         Self {
-            general: GeneralSettings::default(),
-            shortcuts: ShortcutSettings::default(),
-            llm: LlmSettings::default(),
+            auto_startup: true,
+            theme: Theme::Dark,
+            restore_last_conversation: false,
+            restore_focus: true,
+            tray_left_click: TrayLeftClick::OpenSettings,
+            notify_on_completion: true,
+            tts_enabled: true,
+            tts_voice: None,
+            tts_rate: default_tts_rate(),
+            log_level: LogLevel::Info,
+            hide_dock_icon: default_hide_dock_icon(),
+            locale: None,
+            telemetry_enabled: false,
+            telemetry_endpoint: None,
         }
     }
 }
 
-impl Default for GeneralSettings {
+impl Default for ShortcutSettings {
     fn default() -> Self {
         Self {
-            auto_startup: true,
-            theme: Theme::Dark,
+            toggle_launcher: "Alt+Shift+Space".to_string(),
+            peek_mode: false,
+            peek_hold_ms: default_peek_hold_ms(),
+            ask_clipboard: None,
+            ask_selection: None,
+        }
+    }
+}
+
+impl Default for LauncherSettings {
+    fn default() -> Self {
+        Self {
+            placement: LauncherPlacement::Center,
+            position_locked: false,
+            hide_on_blur: true,
+            toggle_behavior: ToggleBehavior::Toggle,
+            max_height: default_launcher_max_height(),
+            always_on_top: false,
+            skip_taskbar: true,
+            detached_width: default_detached_width(),
+            detached_height: default_detached_height(),
+            clipboard_max_chars: default_clipboard_max_chars(),
+            file_max_kb: default_file_max_kb(),
         }
     }
 }
 
-impl Default for Theme {
+impl Default for HistorySettings {
     fn default() -> Self {
-        Theme::Dark
+        Self {
+            enabled: true,
+            auto_backup_interval_days: default_backup_interval_days(),
+            backup_keep_count: default_backup_keep_count(),
+            draft_retention_days: default_draft_retention_days(),
+            prompt_history_limit: default_prompt_history_limit(),
+            settings_audit_limit: default_settings_audit_limit(),
+            retention_days: 0,
+            auto_maintenance: true,
+            database_path: None,
+            encrypt_content: false,
+            search_encrypted: false,
+            max_db_size_mb: None,
+            auto_prune: false,
+        }
     }
 }
 
-impl Default for ShortcutSettings {
+impl Default for NetworkSettings {
     fn default() -> Self {
         Self {
-            toggle_launcher: "Alt+Shift+Space".to_string(),
+            proxy_url: None,
+            request_timeout_secs: default_request_timeout_secs(),
+        }
+    }
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            auto_check: true,
+            check_interval_hours: default_check_interval_hours(),
+            notify: true,
+            skipped_version: None,
+            channel: UpdateChannel::Stable,
+            auto_install: true,
+            release_notes_url: None,
         }
     }
 }
@@ -162,18 +879,29 @@ impl Default for LlmSettings {
             provider: LlmProvider::Gemini,
             api_key: String::new(),
             model: default_model(),
+            title_model: None,
             base_url: None,
+            preset: None,
+            azure: None,
+            extra_headers: None,
+            auth_header_name: None,
             system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+            active_prompt_id: None,
+            response_language: None,
+            cache_ttl_minutes: 0,
+            local_answers: true,
+            max_concurrent_requests: default_max_concurrent_requests(),
+            requests_per_minute: default_requests_per_minute(),
+            queue_when_limited: true,
+            model_prices: HashMap::new(),
+            model_aliases: HashMap::new(),
+            favorite_models: Vec::new(),
+            profiles: Vec::new(),
+            fallback_profiles: Vec::new(),
         }
     }
 }
 
-impl Default for LlmProvider {
-    fn default() -> Self {
-        LlmProvider::Gemini
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,9 +915,56 @@ mod tests {
         // General defaults
         assert!(settings.general.auto_startup);
         assert!(matches!(settings.general.theme, Theme::Dark));
+        assert!(settings.general.restore_focus);
+        assert_eq!(
+            settings.general.tray_left_click,
+            TrayLeftClick::OpenSettings
+        );
+        assert!(settings.general.notify_on_completion);
+        assert!(settings.general.tts_enabled);
+        assert!(!settings.general.telemetry_enabled);
+        assert_eq!(settings.general.tts_voice, None);
+        assert_eq!(settings.general.tts_rate, 1.0);
+        assert_eq!(settings.general.log_level, LogLevel::Info);
 
         // Shortcut defaults
         assert_eq!(settings.shortcuts.toggle_launcher, "Alt+Shift+Space");
+        assert!(!settings.shortcuts.peek_mode);
+        assert_eq!(settings.shortcuts.peek_hold_ms, 400);
+        assert_eq!(settings.shortcuts.ask_clipboard, None);
+        assert_eq!(settings.shortcuts.ask_selection, None);
+
+        // Launcher defaults
+        assert_eq!(settings.launcher.placement, LauncherPlacement::Center);
+        assert!(!settings.launcher.position_locked);
+        assert!(settings.launcher.hide_on_blur);
+        assert_eq!(settings.launcher.toggle_behavior, ToggleBehavior::Toggle);
+        assert_eq!(settings.launcher.max_height, 600);
+        assert!(!settings.launcher.always_on_top);
+        assert!(settings.launcher.skip_taskbar);
+        assert_eq!(settings.launcher.detached_width, 520);
+        assert_eq!(settings.launcher.detached_height, 640);
+        assert_eq!(settings.launcher.clipboard_max_chars, 8000);
+        assert_eq!(settings.launcher.file_max_kb, 256);
+
+        // History defaults
+        assert!(settings.history.enabled);
+        assert_eq!(settings.history.auto_backup_interval_days, 7);
+        assert_eq!(settings.history.backup_keep_count, 5);
+
+        // Network defaults
+        assert_eq!(settings.network.proxy_url, None);
+        assert_eq!(settings.network.request_timeout_secs, 60);
+
+        // Update defaults
+        assert!(settings.updates.auto_check);
+        assert_eq!(settings.updates.check_interval_hours, 24);
+        assert!(settings.updates.notify);
+        assert_eq!(settings.updates.channel, UpdateChannel::Stable);
+        assert!(settings.updates.auto_install);
+
+        // No quick actions until the user adds some
+        assert!(settings.quick_actions.is_empty());
 
         // LLM defaults
         assert!(matches!(settings.llm.provider, LlmProvider::Gemini));
@@ -227,6 +1002,15 @@ mod tests {
         assert!(matches!(system, Theme::System));
     }
 
+    #[test]
+    fn test_launcher_placement_serializes_to_snake_case() {
+        let remember = serde_json::to_string(&LauncherPlacement::RememberLast).unwrap();
+        let near: LauncherPlacement = serde_json::from_str("\"near_cursor\"").unwrap();
+
+        assert_eq!(remember, "\"remember_last\"");
+        assert_eq!(near, LauncherPlacement::NearCursor);
+    }
+
     #[test]
     fn test_provider_serializes_to_lowercase() {
         let gemini = serde_json::to_string(&LlmProvider::Gemini).unwrap();
@@ -236,6 +1020,21 @@ mod tests {
         assert_eq!(openai, "\"openai\"");
     }
 
+    #[test]
+    fn test_provider_as_str_matches_serialized_name() {
+        for provider in [
+            LlmProvider::Gemini,
+            LlmProvider::OpenAI,
+            LlmProvider::Anthropic,
+            LlmProvider::OpenRouter,
+            LlmProvider::AzureOpenAI,
+            LlmProvider::Custom,
+        ] {
+            let json = serde_json::to_string(&provider).unwrap();
+            assert_eq!(json, format!("\"{}\"", provider.as_str()));
+        }
+    }
+
     #[test]
     fn test_provider_deserializes_from_lowercase() {
         let gemini: LlmProvider = serde_json::from_str("\"gemini\"").unwrap();
@@ -267,16 +1066,120 @@ mod tests {
             general: GeneralSettings {
                 auto_startup: true,
                 theme: Theme::Light,
+                restore_last_conversation: true,
+                restore_focus: false,
+                tray_left_click: TrayLeftClick::OpenLauncher,
+                notify_on_completion: false,
+                tts_enabled: false,
+                tts_voice: Some("en-us".to_string()),
+                tts_rate: 1.5,
+                log_level: LogLevel::Debug,
+                hide_dock_icon: true,
+                locale: Some("fr".to_string()),
+                telemetry_enabled: true,
+                telemetry_endpoint: Some("https://example.com/events".to_string()),
             },
             shortcuts: ShortcutSettings {
                 toggle_launcher: "Ctrl+Alt+Q".to_string(),
+                peek_mode: true,
+                peek_hold_ms: 250,
+                ask_clipboard: Some("Ctrl+Alt+V".to_string()),
+                ask_selection: Some("Ctrl+Alt+S".to_string()),
+            },
+            launcher: LauncherSettings {
+                placement: LauncherPlacement::RememberLast,
+                position_locked: true,
+                hide_on_blur: false,
+                toggle_behavior: ToggleBehavior::FocusFirst,
+                max_height: 400,
+                always_on_top: true,
+                skip_taskbar: false,
+                detached_width: 800,
+                detached_height: 900,
+                clipboard_max_chars: 500,
+                file_max_kb: 64,
+            },
+            history: HistorySettings {
+                enabled: false,
+                auto_backup_interval_days: 0,
+                backup_keep_count: 2,
+                draft_retention_days: 0,
+                prompt_history_limit: 20,
+                settings_audit_limit: 50,
+                retention_days: 180,
+                auto_maintenance: false,
+                database_path: Some("/mnt/d/qwik-ask/history.db".to_string()),
+                encrypt_content: true,
+                search_encrypted: false,
+                max_db_size_mb: Some(2048),
+                auto_prune: true,
+            },
+            network: NetworkSettings {
+                proxy_url: Some("http://proxy.example:8080".to_string()),
+                request_timeout_secs: 15,
             },
+            updates: UpdateSettings {
+                auto_check: false,
+                check_interval_hours: 6,
+                notify: false,
+                skipped_version: Some("1.2.0".to_string()),
+                channel: UpdateChannel::Beta,
+                auto_install: false,
+                release_notes_url: Some("https://example.com/releases.json".to_string()),
+            },
+            quick_actions: vec![QuickAction {
+                id: "summarize".to_string(),
+                name: "Summarize".to_string(),
+                prompt_template: "Summarize:\n\n{{input}}".to_string(),
+                input: QuickActionInput::Selection,
+                output: QuickActionOutput::CopyToClipboard,
+                shortcut: Some("Ctrl+Alt+1".to_string()),
+            }],
             llm: LlmSettings {
                 provider: LlmProvider::OpenAI,
                 api_key: "test-api-key".to_string(),
                 model: "gpt-4o".to_string(),
+                title_model: Some("gpt-4o-mini".to_string()),
                 base_url: None,
+                preset: None,
+                azure: None,
+                extra_headers: Some(HashMap::from([(
+                    "X-Org-Id".to_string(),
+                    "acme".to_string(),
+                )])),
+                auth_header_name: Some("X-LiteLLM-Key".to_string()),
                 system_prompt: "Custom prompt".to_string(),
+                cache_ttl_minutes: 30,
+                local_answers: false,
+                max_concurrent_requests: 4,
+                requests_per_minute: 0,
+                queue_when_limited: false,
+                model_prices: HashMap::from([(
+                    "my-model".to_string(),
+                    ModelPrice {
+                        input_per_million: 1.0,
+                        output_per_million: 2.0,
+                    },
+                )]),
+                model_aliases: HashMap::from([(
+                    "gemini-2.0-flash-thinking-exp-01-21".to_string(),
+                    "Flash Thinking".to_string(),
+                )]),
+                favorite_models: vec!["gpt-4o".to_string()],
+                profiles: vec![LlmProfile {
+                    name: "backup".to_string(),
+                    provider: LlmProvider::Gemini,
+                    api_key: "gemini-key".to_string(),
+                    model: "gemini-2.0-flash".to_string(),
+                    base_url: None,
+                    preset: None,
+                    azure: None,
+                    extra_headers: None,
+                    auth_header_name: None,
+                }],
+                fallback_profiles: vec!["backup".to_string()],
+                active_prompt_id: Some("translator".to_string()),
+                response_language: Some("pt-BR".to_string()),
             },
         };
 
@@ -285,10 +1188,110 @@ mod tests {
 
         assert!(restored.general.auto_startup);
         assert!(matches!(restored.general.theme, Theme::Light));
+        assert!(restored.general.restore_last_conversation);
+        assert!(!restored.general.restore_focus);
+        assert_eq!(
+            restored.general.tray_left_click,
+            TrayLeftClick::OpenLauncher
+        );
+        assert!(!restored.general.notify_on_completion);
+        assert!(!restored.general.tts_enabled);
+        assert_eq!(restored.general.tts_voice.as_deref(), Some("en-us"));
+        assert_eq!(restored.general.tts_rate, 1.5);
+        assert_eq!(restored.general.log_level, LogLevel::Debug);
+        assert!(restored.general.hide_dock_icon);
+        assert_eq!(restored.general.locale.as_deref(), Some("fr"));
+        assert!(restored.general.telemetry_enabled);
+        assert_eq!(
+            restored.general.telemetry_endpoint.as_deref(),
+            Some("https://example.com/events")
+        );
         assert_eq!(restored.shortcuts.toggle_launcher, "Ctrl+Alt+Q");
+        assert!(restored.shortcuts.peek_mode);
+        assert_eq!(restored.shortcuts.peek_hold_ms, 250);
+        assert_eq!(
+            restored.shortcuts.ask_clipboard.as_deref(),
+            Some("Ctrl+Alt+V")
+        );
+        assert_eq!(
+            restored.shortcuts.ask_selection.as_deref(),
+            Some("Ctrl+Alt+S")
+        );
+        assert_eq!(restored.launcher.placement, LauncherPlacement::RememberLast);
+        assert!(!restored.launcher.hide_on_blur);
+        assert_eq!(
+            restored.launcher.toggle_behavior,
+            ToggleBehavior::FocusFirst
+        );
+        assert_eq!(restored.launcher.max_height, 400);
+        assert!(restored.launcher.position_locked);
+        assert!(restored.launcher.always_on_top);
+        assert!(!restored.launcher.skip_taskbar);
+        assert_eq!(restored.launcher.detached_width, 800);
+        assert_eq!(restored.launcher.detached_height, 900);
+        assert_eq!(restored.launcher.clipboard_max_chars, 500);
+        assert_eq!(restored.launcher.file_max_kb, 64);
+        assert!(!restored.history.enabled);
+        assert_eq!(restored.history.auto_backup_interval_days, 0);
+        assert_eq!(restored.history.backup_keep_count, 2);
+        assert_eq!(restored.history.retention_days, 180);
+        assert!(!restored.history.auto_maintenance);
+        assert_eq!(restored.history.draft_retention_days, 0);
+        assert_eq!(restored.history.prompt_history_limit, 20);
+        assert_eq!(restored.history.settings_audit_limit, 50);
+        assert_eq!(
+            restored.history.database_path.as_deref(),
+            Some("/mnt/d/qwik-ask/history.db")
+        );
+        assert!(restored.history.encrypt_content);
+        assert_eq!(restored.history.max_db_size_mb, Some(2048));
+        assert!(restored.history.auto_prune);
+        assert_eq!(
+            restored.network.proxy_url.as_deref(),
+            Some("http://proxy.example:8080")
+        );
+        assert_eq!(restored.network.request_timeout_secs, 15);
+        assert!(!restored.updates.auto_check);
+        assert_eq!(restored.updates.check_interval_hours, 6);
+        assert!(!restored.updates.notify);
+        assert_eq!(restored.updates.skipped_version.as_deref(), Some("1.2.0"));
+        assert_eq!(restored.updates.channel, UpdateChannel::Beta);
+        assert!(!restored.updates.auto_install);
+        assert_eq!(
+            restored.updates.release_notes_url.as_deref(),
+            Some("https://example.com/releases.json")
+        );
+        assert_eq!(restored.quick_actions, custom.quick_actions);
         assert!(matches!(restored.llm.provider, LlmProvider::OpenAI));
         assert_eq!(restored.llm.api_key, "test-api-key");
+        assert_eq!(restored.llm.title_model.as_deref(), Some("gpt-4o-mini"));
         assert_eq!(restored.llm.system_prompt, "Custom prompt");
+        assert_eq!(restored.llm.response_language.as_deref(), Some("pt-BR"));
+        assert_eq!(
+            restored.llm.extra_headers.as_ref().unwrap()["X-Org-Id"],
+            "acme"
+        );
+        assert_eq!(
+            restored.llm.auth_header_name.as_deref(),
+            Some("X-LiteLLM-Key")
+        );
+        assert_eq!(restored.llm.cache_ttl_minutes, 30);
+        assert!(!restored.llm.local_answers);
+        assert_eq!(restored.llm.max_concurrent_requests, 4);
+        assert_eq!(restored.llm.requests_per_minute, 0);
+        assert!(!restored.llm.queue_when_limited);
+        assert_eq!(
+            restored.llm.model_prices["my-model"].output_per_million,
+            2.0
+        );
+        assert_eq!(
+            restored.llm.model_aliases["gemini-2.0-flash-thinking-exp-01-21"],
+            "Flash Thinking"
+        );
+        assert_eq!(restored.llm.favorite_models, vec!["gpt-4o".to_string()]);
+        assert_eq!(restored.llm.profiles[0].name, "backup");
+        assert_eq!(restored.llm.fallback_profiles, vec!["backup".to_string()]);
+        assert_eq!(restored.llm.active_prompt_id.as_deref(), Some("translator"));
     }
 
     // ===== Missing Field Handling =====
@@ -303,4 +1306,130 @@ mod tests {
         assert!(!llm.system_prompt.is_empty());
         assert!(llm.system_prompt.contains("Quick Assist"));
     }
+
+    #[test]
+    fn test_openrouter_accepts_vendor_model_ids() {
+        let json = r#"{"provider":"openrouter","api_key":"or-key","model":"meta-llama/llama-3.3-70b-instruct"}"#;
+        let llm: LlmSettings = serde_json::from_str(json).unwrap();
+
+        assert_eq!(llm.provider, LlmProvider::OpenRouter);
+        assert_eq!(llm.model, "meta-llama/llama-3.3-70b-instruct");
+    }
+
+    #[test]
+    fn test_azure_settings_default_api_version() {
+        let json = r#"{"provider":"azureopenai","api_key":"k","azure":{"resource":"contoso","deployment":"gpt4o-prod"}}"#;
+        let llm: LlmSettings = serde_json::from_str(json).unwrap();
+
+        assert_eq!(llm.provider, LlmProvider::AzureOpenAI);
+        let azure = llm.azure.unwrap();
+        assert_eq!(azure.deployment, "gpt4o-prod");
+        assert_eq!(azure.api_version, DEFAULT_AZURE_API_VERSION);
+    }
+
+    #[test]
+    fn test_tray_left_click_serialization() {
+        let json = serde_json::to_string(&TrayLeftClick::OpenLauncher).unwrap();
+        assert_eq!(json, r#""open_launcher""#);
+
+        let parsed: TrayLeftClick = serde_json::from_str(r#""open_settings""#).unwrap();
+        assert_eq!(parsed, TrayLeftClick::OpenSettings);
+    }
+
+    #[test]
+    fn test_log_level_serialization() {
+        let json = serde_json::to_string(&LogLevel::Warn).unwrap();
+        assert_eq!(json, r#""warn""#);
+
+        let parsed: LogLevel = serde_json::from_str(r#""debug""#).unwrap();
+        assert_eq!(parsed, LogLevel::Debug);
+    }
+
+    #[test]
+    fn test_update_channel_serialization() {
+        let json = serde_json::to_string(&UpdateChannel::Beta).unwrap();
+        assert_eq!(json, r#""beta""#);
+
+        let parsed: UpdateChannel = serde_json::from_str(r#""stable""#).unwrap();
+        assert_eq!(parsed, UpdateChannel::Stable);
+    }
+
+    #[test]
+    fn test_quick_action_defaults_when_missing() {
+        let json = r#"{"name":"Explain","prompt_template":"Explain: {{input}}"}"#;
+        let action: QuickAction = serde_json::from_str(json).unwrap();
+
+        assert_eq!(action.id, "");
+        assert_eq!(action.input, QuickActionInput::Clipboard);
+        assert_eq!(action.output, QuickActionOutput::ShowLauncher);
+        assert_eq!(action.shortcut, None);
+        assert_eq!(
+            serde_json::to_value(QuickActionOutput::InsertBack).unwrap(),
+            "insert_back"
+        );
+    }
+
+    #[test]
+    fn test_llm_settings_cache_disabled_when_missing() {
+        let json = r#"{"provider":"gemini","api_key":"key123"}"#;
+        let llm: LlmSettings = serde_json::from_str(json).unwrap();
+
+        assert_eq!(llm.cache_ttl_minutes, 0);
+    }
+
+    #[test]
+    fn test_llm_settings_local_answers_enabled_when_missing() {
+        let json = r#"{"provider":"gemini","api_key":"key123"}"#;
+        let llm: LlmSettings = serde_json::from_str(json).unwrap();
+
+        assert!(llm.local_answers);
+    }
+
+    #[test]
+    fn test_llm_settings_request_limits_default_when_missing() {
+        let json = r#"{"provider":"gemini","api_key":"key123"}"#;
+        let llm: LlmSettings = serde_json::from_str(json).unwrap();
+
+        assert_eq!(llm.max_concurrent_requests, 2);
+        assert_eq!(llm.requests_per_minute, 10);
+        assert!(llm.queue_when_limited);
+    }
+
+    #[test]
+    fn test_llm_settings_no_aliases_or_favorites_when_missing() {
+        let json = r#"{"provider":"gemini","api_key":"key123"}"#;
+        let llm: LlmSettings = serde_json::from_str(json).unwrap();
+
+        assert!(llm.model_aliases.is_empty());
+        assert!(llm.favorite_models.is_empty());
+    }
+
+    #[test]
+    fn test_history_settings_default_when_missing() {
+        let json = r#"{
+            "general": {"auto_startup": true, "theme": "dark"},
+            "shortcuts": {"toggle_launcher": "Alt+Shift+Space"},
+            "llm": {"provider": "gemini", "api_key": ""}
+        }"#;
+        let settings: AppSettings = serde_json::from_str(json).unwrap();
+
+        assert!(settings.history.enabled);
+        assert_eq!(settings.history.auto_backup_interval_days, 7);
+        assert_eq!(settings.history.backup_keep_count, 5);
+        assert_eq!(settings.history.draft_retention_days, 7);
+        assert_eq!(settings.history.prompt_history_limit, 500);
+        assert_eq!(settings.history.settings_audit_limit, 200);
+        assert_eq!(settings.history.database_path, None);
+        assert!(!settings.history.encrypt_content);
+        assert!(!settings.history.search_encrypted);
+        assert_eq!(settings.history.max_db_size_mb, None);
+        assert!(!settings.history.auto_prune);
+        assert_eq!(settings.updates, UpdateSettings::default());
+        assert_eq!(settings.network, NetworkSettings::default());
+        assert_eq!(settings.launcher.placement, LauncherPlacement::Center);
+        assert!(!settings.launcher.position_locked);
+        assert!(settings.launcher.hide_on_blur);
+        assert!(!settings.launcher.always_on_top);
+        assert!(settings.launcher.skip_taskbar);
+    }
 }
@@ -3,6 +3,10 @@
 //! Parses human-readable shortcut strings like `"Alt+Shift+Space"` into
 //! Tauri's `Shortcut` struct for registration with the global shortcut plugin.
 //!
+//! Besides `shortcuts.toggle_launcher`, actions can have their own shortcut
+//! (see [`GlobalAction`]), including each quick action; [`action_shortcuts`]
+//! parses those.
+//!
 //! # Supported Keys
 //!
 //! **Modifiers:** `Ctrl`, `Alt`, `Shift`, `Win`/`Meta`/`Cmd`
@@ -26,6 +30,67 @@
 
 use tauri_plugin_global_shortcut::{Code, Modifiers, Shortcut};
 
+use crate::i18n::t;
+use crate::settings::{QuickAction, ShortcutSettings};
+
+/// An action with its own global shortcut.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlobalAction {
+    /// Show the launcher with the clipboard text as the prompt
+    /// (`shortcuts.ask_clipboard`)
+    AskClipboard,
+    /// Show the launcher with the text selected in the foreground app as
+    /// the prompt (`shortcuts.ask_selection`)
+    AskSelection,
+    /// Run the quick action with this id
+    QuickAction(String),
+}
+
+/// Parse the per-action shortcuts from settings.
+///
+/// Unbound actions (unset or blank) are left out.
+///
+/// # Arguments
+///
+/// * `settings` - Shortcut settings
+/// * `quick_actions` - Quick actions, each with an optional shortcut
+///
+/// # Returns
+///
+/// * `Ok(Vec<(GlobalAction, Shortcut)>)` - Shortcuts to register
+/// * `Err(String)` - A shortcut is invalid, or is already used by
+///   `toggle_launcher` or another action
+pub fn action_shortcuts(
+    settings: &ShortcutSettings,
+    quick_actions: &[QuickAction],
+) -> Result<Vec<(GlobalAction, Shortcut)>, String> {
+    let toggle = parse_shortcut(&settings.toggle_launcher).ok();
+    let configured = [
+        (GlobalAction::AskClipboard, &settings.ask_clipboard),
+        (GlobalAction::AskSelection, &settings.ask_selection),
+    ]
+    .into_iter()
+    .chain(quick_actions.iter().map(|quick_action| {
+        (
+            GlobalAction::QuickAction(quick_action.id.clone()),
+            &quick_action.shortcut,
+        )
+    }));
+
+    let mut bindings: Vec<(GlobalAction, Shortcut)> = Vec::new();
+    for (action, shortcut_str) in configured {
+        let Some(shortcut_str) = shortcut_str.as_deref().filter(|s| !s.trim().is_empty()) else {
+            continue;
+        };
+        let shortcut = parse_shortcut(shortcut_str)?;
+        if Some(shortcut) == toggle || bindings.iter().any(|(_, bound)| *bound == shortcut) {
+            return Err(format!("Shortcut '{}' is already in use", shortcut_str));
+        }
+        bindings.push((action, shortcut));
+    }
+    Ok(bindings)
+}
+
 /// Parse a shortcut string into a `Shortcut` struct.
 ///
 /// The string format is `"Modifier+Modifier+Key"` where:
@@ -68,7 +133,7 @@ pub fn parse_shortcut(shortcut_str: &str) -> Result<Shortcut, String> {
     let parts: Vec<&str> = shortcut_str.split('+').map(|s| s.trim()).collect();
 
     if parts.is_empty() {
-        return Err("Empty shortcut string".to_string());
+        return Err(t("shortcut.empty", &[]));
     }
 
     let mut modifiers = Modifiers::empty();
@@ -181,7 +246,7 @@ pub fn parse_shortcut(shortcut_str: &str) -> Result<Shortcut, String> {
             "numpad9" => key_code = Some(Code::Numpad9),
 
             other => {
-                return Err(format!("Unknown key: {}", other));
+                return Err(t("shortcut.unknown_key", &[("key", &other)]));
             }
         }
     }
@@ -189,15 +254,12 @@ pub fn parse_shortcut(shortcut_str: &str) -> Result<Shortcut, String> {
     match key_code {
         Some(code) => {
             if modifiers.is_empty() {
-                Err(
-                    "Shortcut must have at least one modifier (Ctrl, Alt, Shift, or Win)"
-                        .to_string(),
-                )
+                Err(t("shortcut.no_modifier", &[]))
             } else {
                 Ok(Shortcut::new(Some(modifiers), code))
             }
         }
-        None => Err("Shortcut must have a non-modifier key".to_string()),
+        None => Err(t("shortcut.no_key", &[])),
     }
 }
 
@@ -324,4 +386,115 @@ mod tests {
         let result = parse_shortcut("Ctrl+Shift");
         assert!(result.is_err());
     }
+
+    // ===== Action Shortcut Tests =====
+
+    fn with_ask_clipboard(shortcut: Option<&str>) -> ShortcutSettings {
+        ShortcutSettings {
+            ask_clipboard: shortcut.map(str::to_string),
+            ..ShortcutSettings::default()
+        }
+    }
+
+    #[test]
+    fn test_action_shortcuts_unbound() {
+        assert_eq!(action_shortcuts(&with_ask_clipboard(None), &[]), Ok(vec![]));
+        assert_eq!(
+            action_shortcuts(&with_ask_clipboard(Some(" ")), &[]),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn test_action_shortcuts_bound() {
+        let bindings = action_shortcuts(&with_ask_clipboard(Some("Ctrl+Alt+V")), &[]).unwrap();
+
+        assert_eq!(
+            bindings,
+            vec![(
+                GlobalAction::AskClipboard,
+                Shortcut::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyV)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_action_shortcuts_invalid() {
+        let result = action_shortcuts(&with_ask_clipboard(Some("V")), &[]);
+        assert!(result.unwrap_err().contains("modifier"));
+    }
+
+    #[test]
+    fn test_action_shortcut_conflicts_with_toggle() {
+        let result = action_shortcuts(&with_ask_clipboard(Some("shift+alt+space")), &[]);
+        assert!(result.unwrap_err().contains("already in use"));
+    }
+
+    #[test]
+    fn test_action_shortcuts_conflict_with_each_other() {
+        let settings = ShortcutSettings {
+            ask_selection: Some("Alt+Ctrl+V".to_string()),
+            ..with_ask_clipboard(Some("Ctrl+Alt+V"))
+        };
+
+        let result = action_shortcuts(&settings, &[]);
+
+        assert!(result
+            .unwrap_err()
+            .contains("'Alt+Ctrl+V' is already in use"));
+    }
+
+    #[test]
+    fn test_ask_selection_shortcut() {
+        let settings = ShortcutSettings {
+            ask_selection: Some("Ctrl+Alt+S".to_string()),
+            ..with_ask_clipboard(Some("Ctrl+Alt+V"))
+        };
+
+        let bindings = action_shortcuts(&settings, &[]).unwrap();
+
+        assert_eq!(bindings[1].0, GlobalAction::AskSelection);
+        assert_eq!(
+            bindings[1].1,
+            Shortcut::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyS)
+        );
+    }
+
+    fn quick_action(id: &str, shortcut: Option<&str>) -> QuickAction {
+        QuickAction {
+            id: id.to_string(),
+            name: id.to_string(),
+            prompt_template: "{{input}}".to_string(),
+            input: Default::default(),
+            output: Default::default(),
+            shortcut: shortcut.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_quick_action_shortcuts() {
+        let quick_actions = [
+            quick_action("summarize", Some("Ctrl+Alt+1")),
+            quick_action("unbound", None),
+        ];
+
+        let bindings = action_shortcuts(&with_ask_clipboard(None), &quick_actions).unwrap();
+
+        assert_eq!(
+            bindings,
+            vec![(
+                GlobalAction::QuickAction("summarize".to_string()),
+                Shortcut::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::Digit1)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_quick_action_shortcut_conflicts_with_action() {
+        let quick_actions = [quick_action("paste", Some("Ctrl+Alt+V"))];
+
+        let result = action_shortcuts(&with_ask_clipboard(Some("Ctrl+Alt+V")), &quick_actions);
+
+        assert!(result.unwrap_err().contains("already in use"));
+    }
 }
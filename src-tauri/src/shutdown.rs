@@ -0,0 +1,332 @@
+//! Shutting down cleanly.
+//!
+//! Quitting from the tray, the OS asking the app to exit and restarting
+//! after an update all go through [`shutdown`], which runs the steps of a
+//! [`ShutdownPlan`] in order before exiting:
+//!
+//! 1. Stop background tasks, so nothing starts writing to the database
+//! 2. Unregister the global shortcuts, freeing the key combinations
+//! 3. Save the launcher geometry, while the window still exists
+//! 4. Close the database, flushing pending writes and the WAL
+//! 5. Install a staged update if `updates.auto_install` is on; last, since
+//!    on Windows the installer takes over the process
+//!
+//! A failed step is logged and the rest still run. A [`Watchdog`] exits the
+//! process if the steps take longer than [`WATCHDOG_TIMEOUT`], so a hung
+//! step can't keep the app alive. It is disarmed before the update is
+//! installed, which must not be cut short.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+use crate::db::Db;
+use crate::updater;
+use crate::window::{self, QuitFlag};
+
+/// How long shutting down may take before the process is ended anyway.
+pub const WATCHDOG_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Why the app is shutting down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// Quit from the tray or the settings window
+    Quit,
+    /// Restarting to run an installed update
+    Restart,
+    /// The OS or the event loop asked the app to exit
+    ExitRequested,
+}
+
+/// One step of shutting down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownStep {
+    /// Abort the update scheduler and the backup scheduler
+    StopBackgroundTasks,
+    /// Unregister the toggle and action shortcuts
+    UnregisterShortcuts,
+    /// Remember where the launcher is
+    SaveWindowGeometry,
+    /// Close the SQLite pool
+    CloseDatabase,
+    /// Install a staged update when `updates.auto_install` is on
+    InstallUpdate,
+}
+
+/// What happens once the steps have run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitAction {
+    Exit,
+    Restart,
+}
+
+/// The steps to run, in order, and how to end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShutdownPlan {
+    pub steps: Vec<ShutdownStep>,
+    pub then: ExitAction,
+}
+
+impl ShutdownPlan {
+    /// The plan for `reason`.
+    pub fn new(reason: ShutdownReason) -> Self {
+        Self {
+            steps: vec![
+                ShutdownStep::StopBackgroundTasks,
+                ShutdownStep::UnregisterShortcuts,
+                ShutdownStep::SaveWindowGeometry,
+                ShutdownStep::CloseDatabase,
+                ShutdownStep::InstallUpdate,
+            ],
+            then: match reason {
+                ShutdownReason::Restart => ExitAction::Restart,
+                ShutdownReason::Quit | ShutdownReason::ExitRequested => ExitAction::Exit,
+            },
+        }
+    }
+
+    /// Run every step with `perform`, in order.
+    ///
+    /// # Returns
+    ///
+    /// The steps that failed, with their errors. A failure doesn't stop
+    /// the steps after it.
+    pub fn run(
+        &self,
+        mut perform: impl FnMut(ShutdownStep) -> Result<(), String>,
+    ) -> Vec<(ShutdownStep, String)> {
+        self.steps
+            .iter()
+            .filter_map(|&step| perform(step).err().map(|e| (step, e)))
+            .collect()
+    }
+}
+
+/// Calls a function unless disarmed within a timeout.
+///
+/// Dropping the watchdog disarms it.
+pub struct Watchdog {
+    disarm: Sender<()>,
+}
+
+impl Watchdog {
+    /// Start counting down on a background thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - Time allowed before `on_timeout` is called
+    /// * `on_timeout` - Called on the watchdog thread if the timeout passes
+    pub fn start(timeout: Duration, on_timeout: impl FnOnce() + Send + 'static) -> Self {
+        let (disarm, disarmed) = mpsc::channel::<()>();
+        std::thread::spawn(move || {
+            if disarmed.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout) {
+                on_timeout();
+            }
+        });
+        Self { disarm }
+    }
+
+    /// Stop the countdown.
+    pub fn disarm(self) {
+        let _ = self.disarm.send(());
+    }
+}
+
+/// Shutdown progress, managed as Tauri state.
+#[derive(Default)]
+pub struct ShutdownState {
+    started: AtomicBool,
+}
+
+impl ShutdownState {
+    /// Whether shutting down has begun.
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+}
+
+/// Background tasks to abort on shutdown, managed as Tauri state.
+#[derive(Default)]
+pub struct BackgroundTasks(Mutex<Vec<JoinHandle<()>>>);
+
+impl BackgroundTasks {
+    /// Abort `task` when the app shuts down.
+    pub fn track(&self, task: JoinHandle<()>) {
+        self.0.lock().unwrap().push(task);
+    }
+
+    fn abort_all(&self) {
+        for task in self.0.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
+
+/// Shut down and exit (or restart).
+///
+/// Only the first call does anything; later ones, such as the
+/// `ExitRequested` caused by the exit itself, return right away.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle
+/// * `reason` - Why the app is shutting down
+pub fn shutdown(app: &AppHandle, reason: ShutdownReason) {
+    let state = app.state::<ShutdownState>();
+    if state.started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    app.state::<QuitFlag>().set();
+    tracing::info!(?reason, "Shutting down");
+
+    let mut watchdog = Some(Watchdog::start(WATCHDOG_TIMEOUT, || {
+        tracing::warn!("Shutdown timed out; exiting");
+        std::process::exit(0);
+    }));
+    let plan = ShutdownPlan::new(reason);
+    let failed = plan.run(|step| {
+        // Ending the process halfway through an install would break the app
+        if step == ShutdownStep::InstallUpdate {
+            if let Some(watchdog) = watchdog.take() {
+                watchdog.disarm();
+            }
+        }
+        perform(app, step)
+    });
+    for (step, e) in failed {
+        tracing::warn!(?step, error = %e, "Shutdown step failed");
+    }
+    if let Some(watchdog) = watchdog {
+        watchdog.disarm();
+    }
+
+    match plan.then {
+        ExitAction::Exit => app.exit(0),
+        ExitAction::Restart => app.restart(),
+    }
+}
+
+fn perform(app: &AppHandle, step: ShutdownStep) -> Result<(), String> {
+    match step {
+        ShutdownStep::StopBackgroundTasks => {
+            updater::stop_schedule(app);
+            if let Some(tasks) = app.try_state::<BackgroundTasks>() {
+                tasks.abort_all();
+            }
+            Ok(())
+        }
+        ShutdownStep::UnregisterShortcuts => app
+            .global_shortcut()
+            .unregister_all()
+            .map_err(|e| format!("Failed to unregister shortcuts: {}", e)),
+        ShutdownStep::SaveWindowGeometry => {
+            window::remember_launcher_geometry(app);
+            Ok(())
+        }
+        ShutdownStep::CloseDatabase => {
+            if let Some(db) = app.try_state::<Db>() {
+                tauri::async_runtime::block_on(db.pool().close());
+            }
+            Ok(())
+        }
+        ShutdownStep::InstallUpdate => {
+            updater::install_on_quit(app);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn position(plan: &ShutdownPlan, step: ShutdownStep) -> usize {
+        plan.steps.iter().position(|s| *s == step).unwrap()
+    }
+
+    // ===== Plan =====
+
+    #[test]
+    fn test_plan_order() {
+        let plan = ShutdownPlan::new(ShutdownReason::Quit);
+
+        // Nothing may write to the database once it is closed
+        assert!(
+            position(&plan, ShutdownStep::StopBackgroundTasks)
+                < position(&plan, ShutdownStep::CloseDatabase)
+        );
+        // The installer may end the process on Windows
+        assert_eq!(plan.steps.last(), Some(&ShutdownStep::InstallUpdate));
+        assert_eq!(plan.steps.len(), 5);
+        assert_eq!(plan.then, ExitAction::Exit);
+    }
+
+    #[test]
+    fn test_plan_for_each_reason() {
+        assert_eq!(
+            ShutdownPlan::new(ShutdownReason::Restart).then,
+            ExitAction::Restart
+        );
+        assert_eq!(
+            ShutdownPlan::new(ShutdownReason::ExitRequested).steps,
+            ShutdownPlan::new(ShutdownReason::Quit).steps
+        );
+    }
+
+    #[test]
+    fn test_run_continues_after_failure() {
+        let plan = ShutdownPlan::new(ShutdownReason::Quit);
+        let mut ran = Vec::new();
+
+        let failed = plan.run(|step| {
+            ran.push(step);
+            if step == ShutdownStep::UnregisterShortcuts {
+                Err("busy".to_string())
+            } else {
+                Ok(())
+            }
+        });
+
+        assert_eq!(ran, plan.steps);
+        assert_eq!(
+            failed,
+            vec![(ShutdownStep::UnregisterShortcuts, "busy".to_string())]
+        );
+    }
+
+    // ===== Watchdog =====
+
+    #[test]
+    fn test_watchdog_fires_after_timeout() {
+        let (fired, on_fire) = mpsc::channel();
+        let start = Instant::now();
+
+        let _watchdog = Watchdog::start(Duration::from_millis(50), move || {
+            let _ = fired.send(());
+        });
+
+        on_fire.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_disarmed_watchdog_does_not_fire() {
+        let fired = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&fired);
+
+        let watchdog = Watchdog::start(Duration::from_millis(50), move || {
+            flag.store(true, Ordering::SeqCst);
+        });
+        watchdog.disarm();
+        std::thread::sleep(Duration::from_millis(150));
+
+        assert!(!fired.load(Ordering::SeqCst));
+    }
+}
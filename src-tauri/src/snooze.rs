@@ -0,0 +1,410 @@
+//! Snooze: making the app inert for a while.
+//!
+//! While snoozed the global shortcuts are unregistered, completion
+//! notifications aren't shown, and the background update checks, backups
+//! and telemetry flushes skip their turn. The tray shows the paused icon
+//! with "Snoozed until HH:MM". A timed snooze resumes by itself; `None`
+//! lasts until `resume_from_snooze` or the app restarts (it isn't
+//! persisted).
+//!
+//! Everything affected asks the one managed [`SnoozeGuard`] through
+//! [`is_snoozed`] instead of keeping its own flag.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const state = await invoke<SnoozeState>('snooze', { durationMinutes: 60 }); // null = until resumed
+//! // { snoozed: true, until: 1717000000000 }
+//! await invoke<SnoozeState>('resume_from_snooze');
+//! const current = await invoke<SnoozeState>('get_snooze_state');
+//!
+//! // Sent on every change, including the automatic resume
+//! await listen<SnoozeState>('snooze-changed', ({ payload }) => updateBanner(payload));
+//! ```
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::db::now_ms;
+use crate::llm::limiter::{Clock, SystemClock};
+use crate::settings::SettingsManager;
+use crate::tray;
+
+/// Event sent when the app is snoozed or resumes.
+pub const CHANGED_EVENT: &str = "snooze-changed";
+
+/// How long work that was due during a snooze waits before looking again.
+pub const RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Whether the app is snoozed, as returned to the frontend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SnoozeState {
+    pub snoozed: bool,
+    /// When it resumes by itself (Unix ms); `None` if it waits for
+    /// `resume_from_snooze`
+    pub until: Option<i64>,
+}
+
+/// A snooze in progress.
+#[derive(Debug, Clone, Copy)]
+struct Active {
+    until_ms: Option<i64>,
+    /// Whether the shortcuts were already paused before snoozing, so
+    /// resuming leaves them paused
+    shortcuts_were_paused: bool,
+    /// Identifies this snooze to its resume timer
+    generation: u64,
+}
+
+/// The snooze state, managed as Tauri state.
+#[derive(Debug, Default)]
+pub struct SnoozeGuard {
+    inner: Mutex<(Option<Active>, u64)>,
+}
+
+impl SnoozeGuard {
+    /// Start snoozing, or change when a running snooze ends.
+    ///
+    /// # Arguments
+    ///
+    /// * `until_ms` - When to resume (Unix ms); `None` for until resumed
+    /// * `shortcuts_paused` - Whether the shortcuts are paused right now;
+    ///   ignored when already snoozed
+    ///
+    /// # Returns
+    ///
+    /// The generation to pass to [`end`](Self::end) from a resume timer
+    pub fn start(&self, until_ms: Option<i64>, shortcuts_paused: bool) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        let (active, generation) = &mut *inner;
+        *generation += 1;
+        let shortcuts_were_paused = match active {
+            Some(active) => active.shortcuts_were_paused,
+            None => shortcuts_paused,
+        };
+        *active = Some(Active {
+            until_ms,
+            shortcuts_were_paused,
+            generation: *generation,
+        });
+        *generation
+    }
+
+    /// Stop snoozing.
+    ///
+    /// # Arguments
+    ///
+    /// * `generation` - Only end the snooze [`start`](Self::start) returned
+    ///   this for, so a stale timer can't end a newer one; `None` ends any
+    ///
+    /// # Returns
+    ///
+    /// `Some(shortcuts_were_paused)` if a snooze ended
+    pub fn end(&self, generation: Option<u64>) -> Option<bool> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.0 {
+            Some(active) if generation.is_none_or(|g| g == active.generation) => {
+                inner.0 = None;
+                Some(active.shortcuts_were_paused)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the app is snoozed at `now_ms`.
+    ///
+    /// A timed snooze counts as over once its time has passed, even if the
+    /// timer that resumes it hasn't fired yet (e.g. after the computer slept).
+    pub fn is_snoozed(&self, now_ms: i64) -> bool {
+        self.state(now_ms).snoozed
+    }
+
+    /// The state at `now_ms`.
+    pub fn state(&self, now_ms: i64) -> SnoozeState {
+        match self.inner.lock().unwrap().0 {
+            Some(active) if active.until_ms.is_none_or(|until| now_ms < until) => SnoozeState {
+                snoozed: true,
+                until: active.until_ms,
+            },
+            _ => SnoozeState {
+                snoozed: false,
+                until: None,
+            },
+        }
+    }
+}
+
+/// Whether the app is snoozed right now.
+///
+/// The check every snoozable subsystem makes; `false` before the guard is
+/// managed.
+pub fn is_snoozed(app: &AppHandle) -> bool {
+    app.try_state::<SnoozeGuard>()
+        .is_some_and(|guard| guard.is_snoozed(now_ms()))
+}
+
+/// `HH:MM` of a Unix ms timestamp in `tz`, for the tray tooltip.
+pub fn clock_time<Tz: TimeZone>(ms: i64, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    tz.timestamp_millis_opt(ms)
+        .single()
+        .map(|time| time.format("%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// `HH:MM` of a Unix ms timestamp in local time.
+pub fn local_clock_time(ms: i64) -> String {
+    clock_time(ms, &Local)
+}
+
+/// Wait out a timed snooze, then end it unless it was resumed or replaced
+/// meanwhile.
+///
+/// # Returns
+///
+/// `Some(shortcuts_were_paused)` if this timer ended the snooze
+async fn resume_after(
+    guard: &SnoozeGuard,
+    clock: &dyn Clock,
+    generation: u64,
+    duration: Duration,
+) -> Option<bool> {
+    clock.sleep(duration).await;
+    guard.end(Some(generation))
+}
+
+/// Undo what snoozing did after `guard.end` succeeded.
+fn resumed(app: &AppHandle, shortcuts_were_paused: bool) {
+    if !shortcuts_were_paused {
+        if let Err(e) = app.state::<SettingsManager>().set_shortcuts_paused(false) {
+            tracing::warn!(error = %e, "Failed to restore shortcuts after snooze");
+        }
+    }
+    tracing::info!("Snooze ended");
+    changed(app);
+}
+
+/// Refresh the tray and tell the frontend.
+fn changed(app: &AppHandle) {
+    tray::refresh_menu(app);
+    tray::refresh_state(app);
+    let _ = app.emit(CHANGED_EVENT, app.state::<SnoozeGuard>().state(now_ms()));
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Make the app inert: no shortcuts, notifications or background checks.
+///
+/// Snoozing again while snoozed changes when it ends.
+///
+/// # Arguments
+///
+/// * `duration_minutes` - How long; `None` until `resume_from_snooze`
+///
+/// # Returns
+///
+/// * `Ok(SnoozeState)` - The new state
+/// * `Err(String)` - The shortcuts couldn't be paused
+#[tauri::command]
+pub fn snooze(
+    app: AppHandle,
+    guard: State<'_, SnoozeGuard>,
+    settings_manager: State<'_, SettingsManager>,
+    duration_minutes: Option<u32>,
+) -> Result<SnoozeState, String> {
+    let now = now_ms();
+    let duration = duration_minutes.map(|minutes| Duration::from_secs(u64::from(minutes) * 60));
+    let until_ms = duration.map(|duration| now + duration.as_millis() as i64);
+
+    let generation = guard.start(until_ms, settings_manager.shortcuts_paused());
+    settings_manager.set_shortcuts_paused(true)?;
+    tracing::info!(minutes = ?duration_minutes, "Snoozed");
+
+    if let Some(duration) = duration {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let guard = app.state::<SnoozeGuard>();
+            if let Some(shortcuts_were_paused) =
+                resume_after(&guard, &SystemClock, generation, duration).await
+            {
+                resumed(&app, shortcuts_were_paused);
+            }
+        });
+    }
+    changed(&app);
+    Ok(guard.state(now))
+}
+
+/// End a snooze early. Does nothing when not snoozed.
+#[tauri::command]
+pub fn resume_from_snooze(app: AppHandle, guard: State<'_, SnoozeGuard>) -> SnoozeState {
+    if let Some(shortcuts_were_paused) = guard.end(None) {
+        resumed(&app, shortcuts_were_paused);
+    }
+    guard.state(now_ms())
+}
+
+/// Whether the app is snoozed, and until when.
+#[tauri::command]
+pub fn get_snooze_state(guard: State<'_, SnoozeGuard>) -> SnoozeState {
+    guard.state(now_ms())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::time::Instant;
+
+    /// Records requested sleeps and returns from them immediately.
+    #[derive(Default)]
+    struct MockClock {
+        sleeps: Mutex<Vec<Duration>>,
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            Instant::now()
+        }
+
+        fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            self.sleeps.lock().unwrap().push(duration);
+            Box::pin(std::future::ready(()))
+        }
+    }
+
+    const HOUR_MS: i64 = 60 * 60 * 1000;
+
+    // ===== Guard =====
+
+    #[test]
+    fn test_not_snoozed_by_default() {
+        let guard = SnoozeGuard::default();
+
+        assert!(!guard.is_snoozed(0));
+        assert_eq!(
+            guard.state(0),
+            SnoozeState {
+                snoozed: false,
+                until: None
+            }
+        );
+        assert_eq!(guard.end(None), None);
+    }
+
+    #[test]
+    fn test_timed_snooze_ends_at_deadline() {
+        let guard = SnoozeGuard::default();
+        guard.start(Some(HOUR_MS), false);
+
+        assert!(guard.is_snoozed(0));
+        assert!(guard.is_snoozed(HOUR_MS - 1));
+        assert!(!guard.is_snoozed(HOUR_MS));
+        assert_eq!(
+            guard.state(10),
+            SnoozeState {
+                snoozed: true,
+                until: Some(HOUR_MS)
+            }
+        );
+    }
+
+    #[test]
+    fn test_indefinite_snooze_lasts_until_ended() {
+        let guard = SnoozeGuard::default();
+        guard.start(None, false);
+
+        assert!(guard.is_snoozed(i64::MAX));
+        assert_eq!(guard.end(None), Some(false));
+        assert!(!guard.is_snoozed(0));
+    }
+
+    #[test]
+    fn test_resnooze_keeps_original_shortcut_pause() {
+        let guard = SnoozeGuard::default();
+        guard.start(Some(HOUR_MS), true);
+        // By now the snooze itself paused the shortcuts
+        guard.start(Some(2 * HOUR_MS), true);
+
+        assert_eq!(guard.state(0).until, Some(2 * HOUR_MS));
+        assert_eq!(guard.end(None), Some(true));
+
+        guard.start(None, false);
+        guard.start(None, true);
+        assert_eq!(guard.end(None), Some(false));
+    }
+
+    #[test]
+    fn test_stale_generation_does_not_end_newer_snooze() {
+        let guard = SnoozeGuard::default();
+        let first = guard.start(Some(HOUR_MS), false);
+        let second = guard.start(Some(2 * HOUR_MS), false);
+
+        assert_eq!(guard.end(Some(first)), None);
+        assert!(guard.is_snoozed(0));
+        assert_eq!(guard.end(Some(second)), Some(false));
+    }
+
+    // ===== Auto-resume =====
+
+    #[tokio::test]
+    async fn test_resume_after_sleeps_for_duration() {
+        let guard = SnoozeGuard::default();
+        let clock = MockClock::default();
+        let generation = guard.start(Some(HOUR_MS), false);
+
+        let ended = resume_after(&guard, &clock, generation, Duration::from_secs(3600)).await;
+
+        assert_eq!(ended, Some(false));
+        assert_eq!(*clock.sleeps.lock().unwrap(), [Duration::from_secs(3600)]);
+        assert!(!guard.is_snoozed(0));
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_early_resume_is_noop() {
+        let guard = SnoozeGuard::default();
+        let clock = MockClock::default();
+        let generation = guard.start(Some(HOUR_MS), false);
+        guard.end(None);
+        guard.start(None, false);
+
+        let ended = resume_after(&guard, &clock, generation, Duration::from_secs(3600)).await;
+
+        assert_eq!(ended, None);
+        assert!(guard.is_snoozed(0));
+    }
+
+    // ===== Formatting =====
+
+    #[test]
+    fn test_clock_time() {
+        let ms = 1_700_000_000_000; // 2023-11-14 22:13:20 UTC
+
+        assert_eq!(clock_time(ms, &Utc), "22:13");
+        assert_eq!(
+            clock_time(ms, &FixedOffset::east_opt(2 * 3600).unwrap()),
+            "00:13"
+        );
+    }
+
+    #[test]
+    fn test_state_serialization() {
+        let json = serde_json::to_value(SnoozeState {
+            snoozed: true,
+            until: Some(5),
+        })
+        .unwrap();
+
+        assert_eq!(json, serde_json::json!({ "snoozed": true, "until": 5 }));
+    }
+}
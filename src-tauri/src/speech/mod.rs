@@ -0,0 +1,383 @@
+//! Reading answers aloud.
+//!
+//! `speak_text` strips the markdown from an answer (code blocks become
+//! "(code omitted)", see [`markdown::to_speech`]) and speaks it with the
+//! platform's speech synthesizer. Only one utterance plays at a time:
+//! starting a new one stops the old one.
+//!
+//! Voice and speed come from `general.tts_voice` and `general.tts_rate`
+//! unless a voice is passed; `general.tts_enabled` turns the feature off.
+//!
+//! The synthesizer sits behind [`SpeechEngine`] so [`Speaker`] can be
+//! tested with a fake; see [`native`] for what each platform uses.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! const handle = await invoke<SpeechHandle>('speak_text', { text: message.content, voice: null });
+//! speakingId.value = handle.id;
+//! await invoke<boolean>('stop_speaking');
+//! // Poll while the speak button shows "stop"
+//! const playing = await invoke<SpeechHandle | null>('get_speaking');
+//!
+//! const voices = await invoke<Voice[]>('list_voices');
+//! ```
+
+pub mod native;
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::clipboard::markdown;
+use crate::settings::SettingsManager;
+use native::NativeSpeech;
+
+/// Slowest and fastest speaking rate, relative to the voice's normal speed.
+pub const MIN_RATE: f32 = 0.5;
+pub const MAX_RATE: f32 = 2.0;
+
+/// A voice the synthesizer offers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Voice {
+    /// Value for `general.tts_voice` / the `voice` argument
+    pub id: String,
+    /// Name shown in settings
+    pub name: String,
+    /// Language tag, when the platform reports one
+    pub language: Option<String>,
+}
+
+/// Identifies a started utterance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SpeechHandle {
+    pub id: u64,
+}
+
+/// Something being spoken.
+pub trait Utterance: Send {
+    /// Stop speaking; does nothing if already finished.
+    fn stop(&mut self);
+
+    /// Whether it has finished speaking.
+    fn is_finished(&mut self) -> bool;
+}
+
+/// A platform speech synthesizer.
+pub trait SpeechEngine: Send + Sync {
+    type Utterance: Utterance;
+
+    /// Start speaking `text` without waiting for it to finish.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Plain text to speak
+    /// * `voice` - Voice ID from [`SpeechEngine::voices`], or `None` for the
+    ///   system default
+    /// * `rate` - Speed relative to normal, between [`MIN_RATE`] and [`MAX_RATE`]
+    fn start(&self, text: &str, voice: Option<&str>, rate: f32) -> Result<Self::Utterance, String>;
+
+    /// The installed voices.
+    fn voices(&self) -> Result<Vec<Voice>, String>;
+}
+
+/// Plays one utterance at a time, managed as Tauri state.
+pub struct Speaker<E: SpeechEngine = NativeSpeech> {
+    engine: E,
+    /// The utterance playing or last played, with its handle ID
+    current: Mutex<Option<(u64, E::Utterance)>>,
+    next_id: Mutex<u64>,
+}
+
+impl Speaker {
+    /// Speaker using the current platform's synthesizer.
+    pub fn native() -> Self {
+        Self::new(NativeSpeech)
+    }
+}
+
+impl<E: SpeechEngine> Speaker<E> {
+    pub fn new(engine: E) -> Self {
+        Self {
+            engine,
+            current: Mutex::new(None),
+            next_id: Mutex::new(1),
+        }
+    }
+
+    /// Stop whatever is playing and start speaking `text`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Plain text to speak
+    /// * `voice` - Voice ID, or `None` for the system default
+    /// * `rate` - Speed relative to normal; clamped to [`MIN_RATE`]..=[`MAX_RATE`]
+    pub fn speak(
+        &self,
+        text: &str,
+        voice: Option<&str>,
+        rate: f32,
+    ) -> Result<SpeechHandle, String> {
+        let mut current = self.current.lock().unwrap();
+        if let Some((_, mut utterance)) = current.take() {
+            utterance.stop();
+        }
+
+        let utterance = self.engine.start(text, voice, clamp_rate(rate))?;
+        let mut next_id = self.next_id.lock().unwrap();
+        let id = *next_id;
+        *next_id += 1;
+        *current = Some((id, utterance));
+        Ok(SpeechHandle { id })
+    }
+
+    /// Stop the current utterance.
+    ///
+    /// # Returns
+    ///
+    /// `true` if something was still playing.
+    pub fn stop(&self) -> bool {
+        let Some((_, mut utterance)) = self.current.lock().unwrap().take() else {
+            return false;
+        };
+        let playing = !utterance.is_finished();
+        if playing {
+            utterance.stop();
+        }
+        playing
+    }
+
+    /// The handle of the utterance still playing, if any.
+    pub fn speaking(&self) -> Option<SpeechHandle> {
+        let mut current = self.current.lock().unwrap();
+        if let Some((id, utterance)) = current.as_mut() {
+            if !utterance.is_finished() {
+                return Some(SpeechHandle { id: *id });
+            }
+        }
+        *current = None;
+        None
+    }
+
+    /// The installed voices.
+    pub fn voices(&self) -> Result<Vec<Voice>, String> {
+        self.engine.voices()
+    }
+}
+
+/// A speaking rate within [`MIN_RATE`]..=[`MAX_RATE`]; non-numbers are normal speed.
+pub fn clamp_rate(rate: f32) -> f32 {
+    if rate.is_nan() {
+        1.0
+    } else {
+        rate.clamp(MIN_RATE, MAX_RATE)
+    }
+}
+
+/// Read an answer aloud, stopping anything already playing.
+///
+/// # Arguments
+///
+/// * `text` - Answer markdown
+/// * `voice` - Voice ID from `list_voices`; `None` uses `general.tts_voice`
+///
+/// # Returns
+///
+/// * `Ok(SpeechHandle)` - The started utterance
+/// * `Err(String)` - Speech is turned off, there's nothing to say, or the
+///   synthesizer failed
+#[tauri::command]
+pub fn speak_text(
+    settings_manager: State<'_, SettingsManager>,
+    speaker: State<'_, Speaker>,
+    text: String,
+    voice: Option<String>,
+) -> Result<SpeechHandle, String> {
+    let settings = settings_manager.snapshot()?;
+    let settings = &settings.general;
+    if !settings.tts_enabled {
+        return Err("Text-to-speech is turned off".to_string());
+    }
+    let spoken = markdown::to_speech(&text);
+    if spoken.trim().is_empty() {
+        return Err("There is no text to read".to_string());
+    }
+    let voice = voice.or_else(|| settings.tts_voice.clone());
+    speaker.speak(&spoken, voice.as_deref(), settings.tts_rate)
+}
+
+/// Stop reading aloud.
+///
+/// # Returns
+///
+/// `true` if something was playing.
+#[tauri::command]
+pub fn stop_speaking(speaker: State<'_, Speaker>) -> bool {
+    speaker.stop()
+}
+
+/// The utterance still playing, so the frontend can reset its speak
+/// button once playback ends.
+#[tauri::command]
+pub fn get_speaking(speaker: State<'_, Speaker>) -> Option<SpeechHandle> {
+    speaker.speaking()
+}
+
+/// List the voices of the platform's speech synthesizer.
+#[tauri::command]
+pub async fn list_voices(speaker: State<'_, Speaker>) -> Result<Vec<Voice>, String> {
+    speaker.voices()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    /// Log of what the fake engine was asked to do.
+    type Log = Arc<StdMutex<Vec<String>>>;
+
+    struct FakeUtterance {
+        text: String,
+        log: Log,
+        finished: Arc<StdMutex<bool>>,
+    }
+
+    impl Utterance for FakeUtterance {
+        fn stop(&mut self) {
+            self.log.lock().unwrap().push(format!("stop {}", self.text));
+            *self.finished.lock().unwrap() = true;
+        }
+
+        fn is_finished(&mut self) -> bool {
+            *self.finished.lock().unwrap()
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeEngine {
+        log: Log,
+        /// Finished flag of the most recent utterance
+        last_finished: Arc<StdMutex<Arc<StdMutex<bool>>>>,
+        fail: bool,
+    }
+
+    impl SpeechEngine for FakeEngine {
+        type Utterance = FakeUtterance;
+
+        fn start(
+            &self,
+            text: &str,
+            voice: Option<&str>,
+            rate: f32,
+        ) -> Result<FakeUtterance, String> {
+            if self.fail {
+                return Err("no synthesizer".to_string());
+            }
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("start {} {:?} {}", text, voice, rate));
+            let finished = Arc::new(StdMutex::new(false));
+            *self.last_finished.lock().unwrap() = finished.clone();
+            Ok(FakeUtterance {
+                text: text.to_string(),
+                log: self.log.clone(),
+                finished,
+            })
+        }
+
+        fn voices(&self) -> Result<Vec<Voice>, String> {
+            Ok(vec![])
+        }
+    }
+
+    fn speaker() -> (Speaker<FakeEngine>, Log) {
+        let engine = FakeEngine::default();
+        let log = engine.log.clone();
+        (Speaker::new(engine), log)
+    }
+
+    fn entries(log: &Log) -> Vec<String> {
+        log.lock().unwrap().clone()
+    }
+
+    // ===== Single playback =====
+
+    #[test]
+    fn test_speak_starts_utterance() {
+        let (speaker, log) = speaker();
+
+        let handle = speaker.speak("hello", Some("en"), 1.0).unwrap();
+
+        assert_eq!(handle, SpeechHandle { id: 1 });
+        assert_eq!(speaker.speaking(), Some(handle));
+        assert_eq!(entries(&log), vec!["start hello Some(\"en\") 1"]);
+    }
+
+    #[test]
+    fn test_new_utterance_stops_old() {
+        let (speaker, log) = speaker();
+
+        let first = speaker.speak("one", None, 1.0).unwrap();
+        let second = speaker.speak("two", None, 1.0).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(speaker.speaking(), Some(second));
+        assert_eq!(
+            entries(&log),
+            vec!["start one None 1", "stop one", "start two None 1"]
+        );
+    }
+
+    #[test]
+    fn test_stop_reports_whether_playing() {
+        let (speaker, log) = speaker();
+        speaker.speak("one", None, 1.0).unwrap();
+
+        assert!(speaker.stop());
+        assert!(!speaker.stop());
+        assert_eq!(speaker.speaking(), None);
+        assert_eq!(entries(&log), vec!["start one None 1", "stop one"]);
+    }
+
+    #[test]
+    fn test_finished_utterance_is_not_stopped() {
+        let (speaker, log) = speaker();
+        speaker.speak("one", None, 1.0).unwrap();
+        *speaker.engine.last_finished.lock().unwrap().lock().unwrap() = true;
+
+        assert_eq!(speaker.speaking(), None);
+        assert!(!speaker.stop());
+        assert_eq!(entries(&log), vec!["start one None 1"]);
+    }
+
+    #[test]
+    fn test_failed_start_leaves_nothing_playing() {
+        let speaker = Speaker::new(FakeEngine {
+            fail: true,
+            ..FakeEngine::default()
+        });
+
+        assert_eq!(
+            speaker.speak("one", None, 1.0),
+            Err("no synthesizer".to_string())
+        );
+        assert_eq!(speaker.speaking(), None);
+    }
+
+    // ===== Rate =====
+
+    #[test]
+    fn test_rate_is_clamped() {
+        let (speaker, log) = speaker();
+
+        speaker.speak("fast", None, 5.0).unwrap();
+
+        assert_eq!(entries(&log), vec!["start fast None 2"]);
+        assert_eq!(clamp_rate(0.1), MIN_RATE);
+        assert_eq!(clamp_rate(1.25), 1.25);
+        assert_eq!(clamp_rate(f32::NAN), 1.0);
+    }
+}
@@ -0,0 +1,89 @@
+//! Speech synthesis with each platform's built-in voices, through `tts`.
+//!
+//! WinRT on Windows, AVFoundation on macOS and Speech Dispatcher on Linux.
+//! Each utterance gets its own synthesizer, so stopping one or picking its
+//! voice doesn't affect the next.
+
+use tts::Tts;
+
+use super::{SpeechEngine, Utterance, Voice};
+
+/// The current platform's [`SpeechEngine`].
+pub struct NativeSpeech;
+
+/// A synthesizer speaking one text.
+pub struct TtsUtterance {
+    tts: Tts,
+}
+
+impl Utterance for TtsUtterance {
+    fn stop(&mut self) {
+        let _ = self.tts.stop();
+    }
+
+    fn is_finished(&mut self) -> bool {
+        !self.tts.is_speaking().unwrap_or(false)
+    }
+}
+
+fn open() -> Result<Tts, String> {
+    Tts::default().map_err(|e| format!("Failed to start speech: {}", e))
+}
+
+/// `rate` (relative to normal) on the synthesizer's own scale.
+///
+/// Scales with a positive normal speed are multiplied; Speech Dispatcher's
+/// runs from -100 to 100 around 0, so half and double speed are its ends.
+fn backend_rate(tts: &Tts, rate: f32) -> f32 {
+    let (min, normal, max) = (tts.min_rate(), tts.normal_rate(), tts.max_rate());
+    let target = if normal > 0.0 {
+        normal * rate
+    } else if rate >= 1.0 {
+        normal + (max - normal) * rate.log2()
+    } else {
+        normal + (normal - min) * rate.log2()
+    };
+    target.clamp(min, max)
+}
+
+impl SpeechEngine for NativeSpeech {
+    type Utterance = TtsUtterance;
+
+    fn start(&self, text: &str, voice: Option<&str>, rate: f32) -> Result<TtsUtterance, String> {
+        let mut tts = open()?;
+        if let Some(voice) = voice {
+            let voices = tts
+                .voices()
+                .map_err(|e| format!("Failed to list voices: {}", e))?;
+            // A voice saved before it was uninstalled speaks with the default
+            match voices.iter().find(|v| v.id() == voice) {
+                Some(voice) => tts
+                    .set_voice(voice)
+                    .map_err(|e| format!("Failed to select the voice: {}", e))?,
+                None => tracing::warn!(voice, "Voice isn't installed; using the default"),
+            }
+        }
+        if tts.supported_features().rate {
+            let rate = backend_rate(&tts, rate);
+            tts.set_rate(rate)
+                .map_err(|e| format!("Failed to set the speaking rate: {}", e))?;
+        }
+        tts.speak(text, true)
+            .map_err(|e| format!("Failed to start speech: {}", e))?;
+        Ok(TtsUtterance { tts })
+    }
+
+    fn voices(&self) -> Result<Vec<Voice>, String> {
+        let voices = open()?
+            .voices()
+            .map_err(|e| format!("Failed to list voices: {}", e))?;
+        Ok(voices
+            .into_iter()
+            .map(|voice| Voice {
+                id: voice.id(),
+                name: voice.name(),
+                language: Some(voice.language().to_string()).filter(|tag| !tag.is_empty()),
+            })
+            .collect())
+    }
+}
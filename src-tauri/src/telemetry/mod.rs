@@ -0,0 +1,156 @@
+//! Opt-in anonymous usage telemetry.
+//!
+//! Nothing is recorded unless `general.telemetry_enabled` is on (it's off
+//! by default). When it is, coarse [`TelemetryEvent`]s are queued in the
+//! `telemetry_queue` table and the scheduler started in `lib.rs` POSTs them
+//! as a [`TelemetryBatch`] to `general.telemetry_endpoint` at most once a
+//! day. Events are a closed enum, so prompt text, titles and API keys can't
+//! be part of one.
+//!
+//! Failures are silent: a batch that can't be sent (offline, server error)
+//! is dropped rather than retried, and turning telemetry off empties the
+//! queue. Without an endpoint nothing is sent; the newest events are kept
+//! so the preview has something to show.
+//!
+//! # Frontend Usage
+//!
+//! ```typescript
+//! // Exactly what the next batch would contain
+//! const preview = await invoke<TelemetryPreview>('get_telemetry_preview');
+//! // { enabled: true, endpoint: 'https://...', batch: { schema_version: 1,
+//! //   app_version: '1.2.0', os: 'linux',
+//! //   events: [{ name: 'ask_submitted', provider: 'openai', model: 'gpt-4o', day: '2024-06-01' }] } }
+//! ```
+
+pub mod queue;
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::{now_ms, Db};
+use crate::network;
+use crate::settings::{AppSettings, SettingsManager};
+use crate::snooze;
+pub use queue::{TelemetryBatch, TelemetryEvent};
+
+/// Settings store key of when the last batch was handled (Unix ms).
+pub const LAST_SENT_KEY: &str = "telemetry_last_sent";
+
+/// How often the scheduler checks whether a batch is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Queue an event, if telemetry is on.
+///
+/// Runs in the background and never fails the caller.
+pub fn record(app: &AppHandle, event: TelemetryEvent) {
+    let enabled = app
+        .state::<SettingsManager>()
+        .snapshot()
+        .is_ok_and(|settings| settings.general.telemetry_enabled);
+    if !enabled {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = queue::enqueue(app.state::<Db>().pool(), &event, now_ms()).await {
+            tracing::debug!(error = %e, "Failed to record telemetry event");
+        }
+    });
+}
+
+/// Start sending batches in the background.
+///
+/// # Returns
+///
+/// The scheduler task, to be tracked by `BackgroundTasks`
+pub fn start_scheduler(app: &AppHandle) -> JoinHandle<()> {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = scheduled_flush(&app).await {
+                tracing::debug!(error = %e, "Telemetry flush failed");
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    })
+}
+
+async fn scheduled_flush(app: &AppHandle) -> Result<(), String> {
+    if snooze::is_snoozed(app) {
+        return Ok(());
+    }
+    let settings_manager = app.state::<SettingsManager>();
+    let settings = settings_manager.snapshot()?;
+    let last_sent = settings_manager.load_state::<i64>(LAST_SENT_KEY)?;
+    let now = now_ms();
+
+    let outcome = queue::flush(
+        app.state::<Db>().pool(),
+        settings.general.telemetry_enabled,
+        settings.general.telemetry_endpoint.is_some(),
+        last_sent,
+        now,
+        |batch| send(&settings, batch),
+    )
+    .await?;
+    tracing::debug!(outcome = ?outcome, "Telemetry flush");
+    if outcome.handled() {
+        settings_manager.save_state(LAST_SENT_KEY, &now)?;
+    }
+    Ok(())
+}
+
+/// POST a batch to `general.telemetry_endpoint`.
+async fn send(settings: &AppSettings, batch: TelemetryBatch) -> Result<(), String> {
+    let endpoint = settings
+        .general
+        .telemetry_endpoint
+        .as_deref()
+        .ok_or("No telemetry endpoint")?;
+    network::http_client(&settings.network)?
+        .post(endpoint)
+        .json(&batch)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to send telemetry: {}", e))?;
+    Ok(())
+}
+
+/// Response of `get_telemetry_preview`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryPreview {
+    /// `general.telemetry_enabled`
+    pub enabled: bool,
+    /// `general.telemetry_endpoint`
+    pub endpoint: Option<String>,
+    /// What the next batch would be, byte for byte once serialized
+    pub batch: TelemetryBatch,
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Show what the next telemetry batch would contain.
+///
+/// # Returns
+///
+/// * `Ok(TelemetryPreview)` - The queued events, as they would be sent
+/// * `Err(String)` - Settings or the queue couldn't be read
+#[tauri::command]
+pub async fn get_telemetry_preview(
+    settings_manager: State<'_, SettingsManager>,
+    db: State<'_, Db>,
+) -> Result<TelemetryPreview, String> {
+    let settings = settings_manager.snapshot()?;
+    let events = queue::pending(db.pool(), queue::MAX_BATCH).await?;
+    Ok(TelemetryPreview {
+        enabled: settings.general.telemetry_enabled,
+        endpoint: settings.general.telemetry_endpoint.clone(),
+        batch: TelemetryBatch::new(events),
+    })
+}
@@ -0,0 +1,500 @@
+//! Usage events, their local queue and batching.
+//!
+//! Events are a closed enum: there is no variant that carries prompt text,
+//! titles, keys or any other free-form JSON, so nothing else can end up in
+//! a batch. Each event is stored as a row of `telemetry_queue` and sent
+//! with the UTC day it happened on, not its exact time.
+
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use crate::settings::LlmProvider;
+
+/// Version of the batch format, sent with every batch.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Most events sent in one batch; the rest wait for the next one.
+pub const MAX_BATCH: i64 = 500;
+
+/// Most events kept while they can't be sent; older ones are dropped.
+pub const MAX_QUEUED: i64 = 1_000;
+
+/// Shortest time between two batches (ms).
+pub const SEND_INTERVAL_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// A usage event.
+///
+/// Serializes with a `name` tag:
+/// `{ name: 'ask_submitted', provider: 'openai', model: 'gpt-4o' }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    /// The app was started
+    AppStart,
+    /// A question was sent to a model
+    AskSubmitted {
+        provider: LlmProvider,
+        model: String,
+    },
+    /// The app runs a new version for the first time
+    UpdateInstalled { version: String },
+    /// A quick action was run; which one isn't recorded, since the names
+    /// are the user's
+    QuickActionUsed,
+}
+
+/// A queued event as sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueuedEvent {
+    /// Queue row, used to delete the event once it's handled
+    #[serde(skip)]
+    pub id: i64,
+    #[serde(flatten)]
+    pub event: TelemetryEvent,
+    /// UTC day the event happened, as `YYYY-MM-DD`
+    pub day: String,
+}
+
+/// What is POSTed to the telemetry endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TelemetryBatch {
+    pub schema_version: u32,
+    pub app_version: String,
+    /// `linux`, `macos` or `windows`
+    pub os: String,
+    /// Oldest first
+    pub events: Vec<QueuedEvent>,
+}
+
+impl TelemetryBatch {
+    /// A batch of `events` from this build.
+    pub fn new(events: Vec<QueuedEvent>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            events,
+        }
+    }
+}
+
+/// Queue an event.
+pub async fn enqueue(pool: &SqlitePool, event: &TelemetryEvent, now_ms: i64) -> Result<(), String> {
+    let json = serde_json::to_string(event)
+        .map_err(|e| format!("Failed to serialize telemetry event: {}", e))?;
+    sqlx::query("INSERT INTO telemetry_queue (event, created_at) VALUES (?, ?)")
+        .bind(json)
+        .bind(now_ms)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to queue telemetry event: {}", e))?;
+    Ok(())
+}
+
+/// The oldest `limit` queued events.
+///
+/// Rows that no longer parse (from a newer version, say) are left out;
+/// they're deleted with the batch they were read with.
+pub async fn pending(pool: &SqlitePool, limit: i64) -> Result<Vec<QueuedEvent>, String> {
+    let rows = sqlx::query(
+        "SELECT id, event, date(created_at / 1000, 'unixepoch') AS day
+         FROM telemetry_queue ORDER BY id LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to read telemetry queue: {}", e))?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let event = serde_json::from_str(row.get::<&str, _>("event")).ok()?;
+            Some(QueuedEvent {
+                id: row.get("id"),
+                event,
+                day: row.get("day"),
+            })
+        })
+        .collect())
+}
+
+/// Delete queued events up to and including row `last_id`.
+async fn delete_through(pool: &SqlitePool, last_id: i64) -> Result<(), String> {
+    sqlx::query("DELETE FROM telemetry_queue WHERE id <= ?")
+        .bind(last_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update telemetry queue: {}", e))?;
+    Ok(())
+}
+
+/// Delete every queued event.
+pub async fn clear(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::query("DELETE FROM telemetry_queue")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to clear telemetry queue: {}", e))?;
+    Ok(())
+}
+
+/// Keep only the newest [`MAX_QUEUED`] events.
+async fn trim(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::query(
+        "DELETE FROM telemetry_queue WHERE id <=
+         (SELECT id FROM telemetry_queue ORDER BY id DESC LIMIT 1 OFFSET ?)",
+    )
+    .bind(MAX_QUEUED)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to trim telemetry queue: {}", e))?;
+    Ok(())
+}
+
+/// Whether a batch may be sent.
+///
+/// # Arguments
+///
+/// * `last_sent_ms` - When the last batch was sent (or dropped), if ever
+/// * `now_ms` - Current Unix timestamp (ms)
+pub fn is_due(last_sent_ms: Option<i64>, now_ms: i64) -> bool {
+    last_sent_ms.is_none_or(|last| now_ms - last >= SEND_INTERVAL_MS)
+}
+
+/// What [`flush`] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlushOutcome {
+    /// Telemetry is off; the queue was emptied
+    Disabled,
+    /// No endpoint is configured; events stay queued, up to [`MAX_QUEUED`]
+    NoEndpoint,
+    /// The last batch was sent less than a day ago
+    NotDue,
+    /// Nothing to send
+    Empty,
+    /// The batch was sent and removed from the queue
+    Sent { count: usize },
+    /// Sending failed (offline, server error); the batch was dropped
+    Dropped { count: usize },
+}
+
+impl FlushOutcome {
+    /// Whether a batch was handled, so the next one waits a day.
+    pub fn handled(self) -> bool {
+        matches!(
+            self,
+            FlushOutcome::Sent { .. } | FlushOutcome::Dropped { .. }
+        )
+    }
+}
+
+/// Send the next batch if telemetry is on and one is due.
+///
+/// Events are never retried: a batch that can't be sent is dropped.
+///
+/// # Arguments
+///
+/// * `pool` - Database pool
+/// * `enabled` - The `general.telemetry_enabled` setting
+/// * `has_endpoint` - Whether `general.telemetry_endpoint` is set
+/// * `last_sent_ms` - When the last batch was handled, if ever
+/// * `now_ms` - Current Unix timestamp (ms)
+/// * `send` - POSTs the batch
+pub async fn flush<F, Fut>(
+    pool: &SqlitePool,
+    enabled: bool,
+    has_endpoint: bool,
+    last_sent_ms: Option<i64>,
+    now_ms: i64,
+    send: F,
+) -> Result<FlushOutcome, String>
+where
+    F: FnOnce(TelemetryBatch) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    if !enabled {
+        clear(pool).await?;
+        return Ok(FlushOutcome::Disabled);
+    }
+    if !has_endpoint {
+        trim(pool).await?;
+        return Ok(FlushOutcome::NoEndpoint);
+    }
+    if !is_due(last_sent_ms, now_ms) {
+        return Ok(FlushOutcome::NotDue);
+    }
+
+    // Read the ids first: unparseable rows are dropped with the batch
+    let last_id: Option<i64> = sqlx::query_scalar(
+        "SELECT MAX(id) FROM (SELECT id FROM telemetry_queue ORDER BY id LIMIT ?)",
+    )
+    .bind(MAX_BATCH)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to read telemetry queue: {}", e))?;
+    let Some(last_id) = last_id else {
+        return Ok(FlushOutcome::Empty);
+    };
+    let events = pending(pool, MAX_BATCH).await?;
+    let count = events.len();
+
+    let sent = send(TelemetryBatch::new(events)).await;
+    delete_through(pool, last_id).await?;
+    Ok(match sent {
+        Ok(()) => FlushOutcome::Sent { count },
+        Err(_) => FlushOutcome::Dropped { count },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use std::sync::Mutex;
+
+    /// 2024-06-01T12:00:00Z
+    const NOW: i64 = 1_717_243_200_000;
+
+    fn ask(model: &str) -> TelemetryEvent {
+        TelemetryEvent::AskSubmitted {
+            provider: LlmProvider::OpenAI,
+            model: model.to_string(),
+        }
+    }
+
+    async fn queued(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM telemetry_queue")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    // ===== Payload =====
+
+    #[test]
+    fn test_event_shapes() {
+        assert_eq!(
+            serde_json::to_value(TelemetryEvent::AppStart).unwrap(),
+            serde_json::json!({ "name": "app_start" })
+        );
+        assert_eq!(
+            serde_json::to_value(ask("gpt-4o")).unwrap(),
+            serde_json::json!({ "name": "ask_submitted", "provider": "openai", "model": "gpt-4o" })
+        );
+        assert_eq!(
+            serde_json::to_value(TelemetryEvent::UpdateInstalled {
+                version: "1.2.0".to_string()
+            })
+            .unwrap(),
+            serde_json::json!({ "name": "update_installed", "version": "1.2.0" })
+        );
+        assert_eq!(
+            serde_json::to_value(TelemetryEvent::QuickActionUsed).unwrap(),
+            serde_json::json!({ "name": "quick_action_used" })
+        );
+    }
+
+    #[test]
+    fn test_batch_shape() {
+        let batch = TelemetryBatch::new(vec![QueuedEvent {
+            id: 7,
+            event: TelemetryEvent::AppStart,
+            day: "2024-06-01".to_string(),
+        }]);
+
+        assert_eq!(
+            serde_json::to_value(&batch).unwrap(),
+            serde_json::json!({
+                "schema_version": SCHEMA_VERSION,
+                "app_version": env!("CARGO_PKG_VERSION"),
+                "os": std::env::consts::OS,
+                "events": [{ "name": "app_start", "day": "2024-06-01" }]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_queued_events_carry_only_the_day() {
+        let db = Db::in_memory().await.unwrap();
+        enqueue(db.pool(), &ask("gpt-4o"), NOW).await.unwrap();
+
+        let events = pending(db.pool(), MAX_BATCH).await.unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, ask("gpt-4o"));
+        assert_eq!(events[0].day, "2024-06-01");
+    }
+
+    #[tokio::test]
+    async fn test_unparseable_rows_are_skipped() {
+        let db = Db::in_memory().await.unwrap();
+        sqlx::query(
+            "INSERT INTO telemetry_queue (event, created_at) VALUES ('{\"name\":\"future\"}', 0)",
+        )
+        .execute(db.pool())
+        .await
+        .unwrap();
+        enqueue(db.pool(), &TelemetryEvent::AppStart, NOW)
+            .await
+            .unwrap();
+
+        let events = pending(db.pool(), MAX_BATCH).await.unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, TelemetryEvent::AppStart);
+    }
+
+    // ===== Scheduling =====
+
+    #[test]
+    fn test_is_due() {
+        assert!(is_due(None, NOW));
+        assert!(is_due(Some(NOW - SEND_INTERVAL_MS), NOW));
+        assert!(!is_due(Some(NOW - SEND_INTERVAL_MS + 1), NOW));
+        assert!(!is_due(Some(NOW), NOW));
+    }
+
+    // ===== Flushing =====
+
+    #[tokio::test]
+    async fn test_flush_sends_batch_and_empties_queue() {
+        let db = Db::in_memory().await.unwrap();
+        enqueue(db.pool(), &TelemetryEvent::AppStart, NOW)
+            .await
+            .unwrap();
+        enqueue(db.pool(), &ask("gpt-4o"), NOW).await.unwrap();
+        let sent = Mutex::new(None);
+
+        let outcome = flush(db.pool(), true, true, None, NOW, |batch| {
+            *sent.lock().unwrap() = Some(batch);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, FlushOutcome::Sent { count: 2 });
+        assert!(outcome.handled());
+        let batch = sent.into_inner().unwrap().unwrap();
+        assert_eq!(batch.events[0].event, TelemetryEvent::AppStart);
+        assert_eq!(batch.events[1].event, ask("gpt-4o"));
+        assert_eq!(queued(db.pool()).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_flush_batches_oldest_first() {
+        let db = Db::in_memory().await.unwrap();
+        for i in 0..MAX_BATCH + 3 {
+            enqueue(db.pool(), &ask(&format!("model-{}", i)), NOW)
+                .await
+                .unwrap();
+        }
+        let sent = Mutex::new(None);
+
+        let outcome = flush(db.pool(), true, true, None, NOW, |batch| {
+            *sent.lock().unwrap() = Some(batch);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            outcome,
+            FlushOutcome::Sent {
+                count: MAX_BATCH as usize
+            }
+        );
+        let batch = sent.into_inner().unwrap().unwrap();
+        assert_eq!(batch.events[0].event, ask("model-0"));
+        assert_eq!(queued(db.pool()).await, 3);
+        let rest = pending(db.pool(), MAX_BATCH).await.unwrap();
+        assert_eq!(rest[0].event, ask(&format!("model-{}", MAX_BATCH)));
+    }
+
+    #[tokio::test]
+    async fn test_flush_waits_a_day() {
+        let db = Db::in_memory().await.unwrap();
+        enqueue(db.pool(), &TelemetryEvent::AppStart, NOW)
+            .await
+            .unwrap();
+
+        let outcome = flush(db.pool(), true, true, Some(NOW - 1_000), NOW, |_| async {
+            panic!("sent before a day passed")
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, FlushOutcome::NotDue);
+        assert!(!outcome.handled());
+        assert_eq!(queued(db.pool()).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_flush_with_empty_queue() {
+        let db = Db::in_memory().await.unwrap();
+
+        let outcome = flush(db.pool(), true, true, None, NOW, |_| async {
+            panic!("sent an empty batch")
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, FlushOutcome::Empty);
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_drops_batch() {
+        let db = Db::in_memory().await.unwrap();
+        enqueue(db.pool(), &TelemetryEvent::AppStart, NOW)
+            .await
+            .unwrap();
+
+        let outcome = flush(db.pool(), true, true, None, NOW, |_| async {
+            Err("offline".to_string())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, FlushOutcome::Dropped { count: 1 });
+        assert!(outcome.handled());
+        assert_eq!(queued(db.pool()).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_clears_queue_without_sending() {
+        let db = Db::in_memory().await.unwrap();
+        enqueue(db.pool(), &TelemetryEvent::AppStart, NOW)
+            .await
+            .unwrap();
+
+        let outcome = flush(db.pool(), false, true, None, NOW, |_| async {
+            panic!("sent while disabled")
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, FlushOutcome::Disabled);
+        assert_eq!(queued(db.pool()).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_no_endpoint_keeps_newest_events() {
+        let db = Db::in_memory().await.unwrap();
+        for i in 0..MAX_QUEUED + 5 {
+            enqueue(db.pool(), &ask(&format!("model-{}", i)), NOW)
+                .await
+                .unwrap();
+        }
+
+        let outcome = flush(db.pool(), true, false, None, NOW, |_| async {
+            panic!("sent without an endpoint")
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, FlushOutcome::NoEndpoint);
+        assert_eq!(queued(db.pool()).await, MAX_QUEUED);
+        let oldest = pending(db.pool(), 1).await.unwrap();
+        assert_eq!(oldest[0].event, ask("model-5"));
+    }
+}
@@ -1,24 +1,133 @@
 //! System tray module.
 //!
 //! Sets up the system tray icon with a context menu and click handlers.
-//! The tray provides quick access to settings, update checking, and quit functionality.
+//! The tray provides quick access to the launcher, settings, update
+//! checking, and quit functionality. It matters most on Linux, where global
+//! shortcuts are unreliable.
 //!
 //! # Behavior
 //!
-//! - **Left click**: Opens the settings window
-//! - **Right click**: Shows context menu with "Open Settings", "Check for Updates", and "Quit"
+//! - **Left click**: Shows the launcher or opens the settings window,
+//!   per `general.tray_left_click` (read on every click); toggles the
+//!   launcher instead while the global shortcut is unavailable (see
+//!   [`crate::platform`])
+//! - **Right click**: Shows the context menu:
+//!   - "Toggle Launcher" and "New Conversation"
+//!   - "Quick Actions" submenu, one entry per saved quick action
+//!   - "Pause Shortcuts" and "Start at Login" check items
+//!   - "Open Settings" and "Check for Updates"
+//!   - "Quit"
+//!
+//! The check items go through the same `SettingsManager` code paths as the
+//! settings commands. Their checked state is refreshed by [`refresh_menu`]
+//! after every change, including ones made from the settings window, which
+//! also rebuilds the quick actions submenu and relabels the menu in the
+//! current locale (see [`crate::i18n`]).
+//!
+//! # Icon and Tooltip
+//!
+//! The icon and tooltip reflect the app's [`TrayState`]: grayed out while
+//! snoozed (with "Snoozed until HH:MM") or the shortcut is paused, badged when an update is available, and a
+//! "working…" tooltip while an LLM request or update download is in
+//! flight (see [`ActivityTracker`]). Icon swapping is unreliable with Linux tray
+//! hosts, so there only the tooltip follows the state.
+//!
+//! The icon is drawn for where it sits: a monochrome template image in the
+//! macOS menu bar, and on Windows and Linux the colored icon on a dark
+//! taskbar or a dark glyph on a light one. The OS theme is re-read when
+//! the launcher reports `system-theme-changed` (see [`set_system_theme`]).
+//!
+//! # Frontend Integration
+//!
+//! ```typescript
+//! // "New Conversation" shows the launcher, then asks it to start fresh
+//! await listen('new-conversation', () => startNewConversation());
+//!
+//! // A tray toggle failed, e.g. autostart registration was denied
+//! await listen<string>('tray-error', ({ payload }) => showToast(payload));
+//!
+//! // The OS switched between light and dark mode; 'light' | 'dark'
+//! await listen<string>('system-theme-changed', ({ payload }) => applySystemTheme(payload));
+//! ```
+
+pub mod state;
+
+use std::sync::Mutex;
+
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{include_image, App, AppHandle, Emitter, Manager, Wry};
+
+use crate::db::now_ms;
+use crate::i18n::t;
+use crate::platform;
+use crate::quick_actions;
+use crate::settings::{QuickAction, SettingsManager, TrayLeftClick};
+use crate::snooze::SnoozeGuard;
+use crate::updater;
+use crate::window;
+use crate::window::quit::ActivityTracker;
+use crate::window::visibility::VisibilityReason;
+pub use state::TrayState;
+use state::{SystemTheme, TrayIconAsset, TrayPlatform, TrayStatus};
+
+/// Grayed-out icon shown while the shortcut is paused.
+const PAUSED_ICON: Image<'_> = include_image!("./icons/tray/paused.png");
+
+/// Badged icon shown when an update is available.
+const UPDATE_ICON: Image<'_> = include_image!("./icons/tray/update.png");
+
+/// Dark glyphs for light taskbars.
+const LIGHT_ICON: Image<'_> = include_image!("./icons/tray/light.png");
+const LIGHT_PAUSED_ICON: Image<'_> = include_image!("./icons/tray/light-paused.png");
+const LIGHT_UPDATE_ICON: Image<'_> = include_image!("./icons/tray/light-update.png");
 
-use tauri::menu::{Menu, MenuItem};
-use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
-use tauri::{App, Emitter, Manager};
-use tauri_plugin_updater::UpdaterExt;
+/// Template images for the macOS menu bar.
+const TEMPLATE_ICON: Image<'_> = include_image!("./icons/tray/template.png");
+const TEMPLATE_PAUSED_ICON: Image<'_> = include_image!("./icons/tray/template-paused.png");
+const TEMPLATE_UPDATE_ICON: Image<'_> = include_image!("./icons/tray/template-update.png");
+
+/// Event sent when the OS switches between light and dark mode.
+pub const THEME_CHANGED_EVENT: &str = "system-theme-changed";
+
+/// Menu id prefix of quick action entries, followed by the action's id.
+const QUICK_ACTION_PREFIX: &str = "quick_action:";
+
+/// The tray icon and its menu items, managed as Tauri state so they can
+/// be updated.
+struct TrayMenu {
+    tray: TrayIcon<Wry>,
+    /// Plain items with their catalog keys, relabelled on locale changes
+    items: Vec<(MenuItem<Wry>, &'static str)>,
+    pause_shortcuts: CheckMenuItem<Wry>,
+    start_at_login: CheckMenuItem<Wry>,
+    quick_actions: Submenu<Wry>,
+    /// The icon shown, so unchanged icons aren't set again
+    icon: Mutex<TrayIconAsset>,
+}
+
+impl TrayMenu {
+    /// Set every label in the current locale.
+    fn relabel(&self) {
+        for (item, key) in &self.items {
+            let _ = item.set_text(t(key, &[]));
+        }
+        let _ = self
+            .pause_shortcuts
+            .set_text(t("tray.pause_shortcuts", &[]));
+        let _ = self.start_at_login.set_text(t("tray.start_at_login", &[]));
+        let _ = self.quick_actions.set_text(t("tray.quick_actions", &[]));
+    }
+}
 
 /// Setup the system tray with menu and event handlers.
 ///
 /// Creates a tray icon with:
 /// - App icon
-/// - Context menu (Settings, Check for Updates, Quit)
-/// - Left-click handler to open settings
+/// - Context menu (Toggle Launcher, New Conversation, Quick Actions,
+///   Pause Shortcuts, Start at Login, Settings, Check for Updates, Quit)
+/// - Left-click handler following `general.tray_left_click`
 ///
 /// # Arguments
 ///
@@ -39,45 +148,364 @@ use tauri_plugin_updater::UpdaterExt;
 /// })
 /// ```
 pub fn setup(app: &App) -> Result<(), Box<dyn std::error::Error>> {
-    let settings_item = MenuItem::with_id(app, "settings", "Open Settings", true, None::<&str>)?;
-    let update_item =
-        MenuItem::with_id(app, "check_updates", "Check for Updates", true, None::<&str>)?;
-    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-    let menu = Menu::with_items(app, &[&settings_item, &update_item, &quit_item])?;
-
-    let _tray = TrayIconBuilder::new()
-        .icon(app.default_window_icon().unwrap().clone())
+    let item = |id: &str, key: &str| MenuItem::with_id(app, id, t(key, &[]), true, None::<&str>);
+    let launcher_item = item("launcher", "tray.toggle_launcher")?;
+    let new_conversation_item = item("new_conversation", "tray.new_conversation")?;
+    let quick_actions_menu =
+        Submenu::with_id(app, "quick_actions", t("tray.quick_actions", &[]), true)?;
+    let settings_manager = app.state::<SettingsManager>();
+    let pause_item = CheckMenuItem::with_id(
+        app,
+        "pause_shortcuts",
+        t("tray.pause_shortcuts", &[]),
+        true,
+        settings_manager.shortcuts_paused(),
+        None::<&str>,
+    )?;
+    let autostart_item = CheckMenuItem::with_id(
+        app,
+        "start_at_login",
+        t("tray.start_at_login", &[]),
+        true,
+        settings_manager.get_auto_startup_status().unwrap_or(false),
+        None::<&str>,
+    )?;
+    let settings_item = item("settings", "tray.open_settings")?;
+    let update_item = item("check_updates", "tray.check_updates")?;
+    let quit_item = item("quit", "tray.quit")?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &launcher_item,
+            &new_conversation_item,
+            &quick_actions_menu,
+            &PredefinedMenuItem::separator(app)?,
+            &pause_item,
+            &autostart_item,
+            &PredefinedMenuItem::separator(app)?,
+            &settings_item,
+            &update_item,
+            &PredefinedMenuItem::separator(app)?,
+            &quit_item,
+        ],
+    )?;
+
+    let status = TrayStatus::default();
+    if let Some(theme) = app
+        .get_webview_window("main")
+        .and_then(|window| window.theme().ok())
+    {
+        status.set_theme(theme.into());
+    }
+    let icon = state::icon_asset(TrayPlatform::current(), status.theme(), TrayState::Idle);
+
+    let tray = TrayIconBuilder::new()
+        .icon(icon_image(app.handle(), icon).unwrap())
+        .icon_as_template(icon.is_template())
         .menu(&menu)
         .show_menu_on_left_click(false)
         .tooltip("Qwik Ask")
         .on_menu_event(|app, event| match event.id.as_ref() {
+            "launcher" => {
+                toggle_launcher(app);
+            }
+            "new_conversation" => {
+                window::show_launcher(app, VisibilityReason::Tray);
+                let _ = app.emit("new-conversation", ());
+            }
+            "pause_shortcuts" => {
+                let settings_manager = app.state::<SettingsManager>();
+                let paused = !settings_manager.shortcuts_paused();
+                if let Err(e) = settings_manager.set_shortcuts_paused(paused) {
+                    report_error(app, e);
+                }
+                refresh_menu(app);
+            }
+            "start_at_login" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    let settings_manager = app.state::<SettingsManager>();
+                    let enabled = !settings_manager.get_auto_startup_status().unwrap_or(false);
+                    if let Err(e) = settings_manager.set_auto_startup(enabled).await {
+                        report_error(&app, e);
+                    }
+                    refresh_menu(&app);
+                });
+            }
             "settings" => {
-                open_settings_window(app);
+                open_settings_window(app, None);
             }
             "check_updates" => {
                 check_for_updates_from_tray(app.clone());
             }
             "quit" => {
-                app.exit(0);
+                window::request_quit(app);
+            }
+            id => {
+                if let Some(action_id) = id.strip_prefix(QUICK_ACTION_PREFIX) {
+                    quick_actions::spawn(app, action_id.to_string(), VisibilityReason::Tray);
+                }
             }
-            _ => {}
         })
         .on_tray_icon_event(|tray, event| {
-            // Left click opens settings
-            if let TrayIconEvent::Click {
-                button: MouseButton::Left,
-                button_state: MouseButtonState::Up,
+            let TrayIconEvent::Click {
+                button,
+                button_state,
                 ..
             } = event
-            {
-                open_settings_window(tray.app_handle());
+            else {
+                return;
+            };
+            let app = tray.app_handle();
+            match click_action(button, button_state, tray_left_click(app)) {
+                // Without the shortcut the tray icon is the way to the launcher
+                Some(_) if platform::shortcut_unavailable(app) => toggle_launcher(app),
+                Some(TrayLeftClick::OpenLauncher) => {
+                    window::show_launcher(app, VisibilityReason::Tray);
+                }
+                Some(TrayLeftClick::OpenSettings) => open_settings_window(app, None),
+                None => {}
             }
         })
         .build(app)?;
 
+    app.manage(status);
+    app.manage(TrayMenu {
+        tray,
+        items: vec![
+            (launcher_item, "tray.toggle_launcher"),
+            (new_conversation_item, "tray.new_conversation"),
+            (settings_item, "tray.open_settings"),
+            (update_item, "tray.check_updates"),
+            (quit_item, "tray.quit"),
+        ],
+        pause_shortcuts: pause_item,
+        start_at_login: autostart_item,
+        quick_actions: quick_actions_menu,
+        icon: Mutex::new(icon),
+    });
+    refresh_menu(app.handle());
+
     Ok(())
 }
 
+/// Sync the tray's check items with the current state.
+///
+/// Call after anything that may pause the shortcut, change auto-startup,
+/// change the quick actions or switch the locale. Also relabels the menu,
+/// rebuilds the quick actions submenu and refreshes the icon, which
+/// reflects the pause. Does nothing if the tray hasn't been set up.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+pub fn refresh_menu(app: &AppHandle) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let settings_manager = app.state::<SettingsManager>();
+    menu.relabel();
+    let _ = menu
+        .pause_shortcuts
+        .set_checked(settings_manager.shortcuts_paused());
+    let _ = menu
+        .start_at_login
+        .set_checked(settings_manager.get_auto_startup_status().unwrap_or(false));
+    let quick_actions = settings_manager
+        .snapshot()
+        .map(|settings| settings.quick_actions.clone())
+        .unwrap_or_default();
+    if let Err(e) = rebuild_quick_actions(app, &menu.quick_actions, &quick_actions) {
+        tracing::warn!(error = %e, "Failed to update the quick actions menu");
+    }
+    refresh_state(app);
+}
+
+/// Replace the quick actions submenu's entries.
+fn rebuild_quick_actions(
+    app: &AppHandle,
+    submenu: &Submenu<Wry>,
+    quick_actions: &[QuickAction],
+) -> tauri::Result<()> {
+    for item in submenu.items()? {
+        submenu.remove(&item)?;
+    }
+    for (id, label) in quick_action_entries(quick_actions) {
+        submenu.append(&MenuItem::with_id(app, id, label, true, None::<&str>)?)?;
+    }
+    if quick_actions.is_empty() {
+        submenu.append(&MenuItem::with_id(
+            app,
+            "no_quick_actions",
+            t("tray.no_quick_actions", &[]),
+            false,
+            None::<&str>,
+        )?)?;
+    }
+    Ok(())
+}
+
+/// Menu ids and labels of the quick actions submenu, with the shortcut
+/// shown next to the name.
+fn quick_action_entries(quick_actions: &[QuickAction]) -> Vec<(String, String)> {
+    quick_actions
+        .iter()
+        .map(|action| {
+            let label = match action.shortcut.as_deref().filter(|s| !s.trim().is_empty()) {
+                Some(shortcut) => format!("{} ({})", action.name, shortcut),
+                None => action.name.clone(),
+            };
+            (format!("{}{}", QUICK_ACTION_PREFIX, action.id), label)
+        })
+        .collect()
+}
+
+/// Show `state` in the tray icon and tooltip.
+///
+/// Does nothing if the tray hasn't been set up.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+/// * `state` - State to show
+pub fn set_state(app: &AppHandle, state: TrayState) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let version = app.package_info().version.to_string();
+    let (_, tooltip) = state::appearance(state, &version);
+    let _ = menu.tray.set_tooltip(Some(tooltip));
+
+    let theme = app
+        .try_state::<TrayStatus>()
+        .map(|status| status.theme())
+        .unwrap_or_default();
+    let asset = state::icon_asset(TrayPlatform::current(), theme, state);
+    let mut shown = menu.icon.lock().unwrap();
+    if *shown == asset {
+        return;
+    }
+    if let Some(icon) = icon_image(app, asset) {
+        let _ = menu.tray.set_icon(Some(icon));
+        let _ = menu.tray.set_icon_as_template(asset.is_template());
+        *shown = asset;
+    }
+}
+
+/// The image of a bundled tray icon.
+fn icon_image(app: &AppHandle, asset: TrayIconAsset) -> Option<Image<'static>> {
+    match asset {
+        TrayIconAsset::App => app
+            .default_window_icon()
+            .map(|icon| icon.clone().to_owned()),
+        TrayIconAsset::Paused => Some(PAUSED_ICON),
+        TrayIconAsset::Update => Some(UPDATE_ICON),
+        TrayIconAsset::Light => Some(LIGHT_ICON),
+        TrayIconAsset::LightPaused => Some(LIGHT_PAUSED_ICON),
+        TrayIconAsset::LightUpdate => Some(LIGHT_UPDATE_ICON),
+        TrayIconAsset::Template => Some(TEMPLATE_ICON),
+        TrayIconAsset::TemplatePaused => Some(TEMPLATE_PAUSED_ICON),
+        TrayIconAsset::TemplateUpdate => Some(TEMPLATE_UPDATE_ICON),
+    }
+}
+
+/// Record that the OS switched between light and dark mode: redraw the
+/// tray icon for it and tell the frontend with `system-theme-changed`.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+/// * `theme` - The theme the launcher window reported
+pub fn set_system_theme(app: &AppHandle, theme: tauri::Theme) {
+    let theme = SystemTheme::from(theme);
+    if let Some(status) = app.try_state::<TrayStatus>() {
+        status.set_theme(theme);
+    }
+    let _ = app.emit(THEME_CHANGED_EVENT, theme);
+    refresh_state(app);
+}
+
+/// Show the state resolved from the current app state.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+pub fn refresh_state(app: &AppHandle) {
+    let Some(status) = app.try_state::<TrayStatus>() else {
+        return;
+    };
+    let snooze = app
+        .try_state::<SnoozeGuard>()
+        .map(|guard| guard.state(now_ms()))
+        .unwrap_or_default();
+    let paused = app.state::<SettingsManager>().shortcuts_paused();
+    let busy = app
+        .try_state::<ActivityTracker>()
+        .is_some_and(|activity| activity.is_busy());
+    set_state(app, status.resolve(snooze, paused, busy));
+}
+
+/// Record the result of an update check in the tray.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+/// * `available` - Whether a newer version can be installed
+pub fn set_update_available(app: &AppHandle, available: bool) {
+    if let Some(status) = app.try_state::<TrayStatus>() {
+        status.set_update_available(available);
+    }
+    refresh_state(app);
+}
+
+/// Log a failed tray action and tell the frontend with `tray-error`.
+fn report_error(app: &AppHandle, message: String) {
+    tracing::warn!(error = %message, "Tray action failed");
+    let _ = app.emit("tray-error", message);
+}
+
+/// Decide what a tray icon click does.
+///
+/// Only a released left click does anything; right clicks open the menu.
+///
+/// # Arguments
+///
+/// * `button` - Mouse button clicked
+/// * `state` - Whether the button went down or up
+/// * `left_click` - The `general.tray_left_click` setting
+fn click_action(
+    button: MouseButton,
+    state: MouseButtonState,
+    left_click: TrayLeftClick,
+) -> Option<TrayLeftClick> {
+    match (button, state) {
+        (MouseButton::Left, MouseButtonState::Up) => Some(left_click),
+        _ => None,
+    }
+}
+
+/// The `general.tray_left_click` setting, or its default when settings
+/// can't be read.
+fn tray_left_click(app: &AppHandle) -> TrayLeftClick {
+    app.state::<SettingsManager>()
+        .snapshot()
+        .map(|settings| settings.general.tray_left_click)
+        .unwrap_or_default()
+}
+
+/// Hide the launcher if it is showing, show it otherwise.
+fn toggle_launcher(app: &AppHandle) {
+    let visible = app
+        .get_webview_window("main")
+        .and_then(|window| window.is_visible().ok())
+        .unwrap_or(false);
+    if visible {
+        window::hide_launcher(app, VisibilityReason::Tray);
+    } else {
+        window::show_launcher(app, VisibilityReason::Tray);
+    }
+}
+
 /// Open the settings window and hide the main launcher.
 ///
 /// Helper function shared between menu click and tray icon click handlers.
@@ -85,14 +513,10 @@ pub fn setup(app: &App) -> Result<(), Box<dyn std::error::Error>> {
 /// # Arguments
 ///
 /// * `app` - The Tauri AppHandle
-fn open_settings_window(app: &tauri::AppHandle) {
-    if let Some(window) = app.get_webview_window("settings") {
-        // Hide main window when opening settings
-        if let Some(main_window) = app.get_webview_window("main") {
-            let _ = main_window.hide();
-        }
-        let _ = window.show();
-        let _ = window.set_focus();
+/// * `section` - Settings section to navigate to, if any
+fn open_settings_window(app: &tauri::AppHandle, section: Option<&str>) {
+    if let Err(e) = window::open_settings_window(app, section.map(str::to_string)) {
+        tracing::warn!(error = %e, "Failed to open settings");
     }
 }
 
@@ -105,32 +529,90 @@ fn open_settings_window(app: &tauri::AppHandle) {
 ///
 /// * `app` - The Tauri AppHandle
 fn check_for_updates_from_tray(app: tauri::AppHandle) {
-    // Open settings window to show update progress
-    open_settings_window(&app);
+    // Open settings window on the updates panel to show progress
+    open_settings_window(&app, Some("updates"));
 
     // Spawn async update check
     tauri::async_runtime::spawn(async move {
-        match app.updater() {
-            Ok(updater) => match updater.check().await {
-                Ok(Some(update)) => {
-                    let _ = app.emit(
-                        "update-available",
-                        serde_json::json!({
-                            "version": update.version,
-                            "body": update.body,
-                        }),
-                    );
-                }
-                Ok(None) => {
-                    let _ = app.emit("update-not-available", ());
-                }
-                Err(e) => {
-                    let _ = app.emit("update-error", e.to_string());
-                }
-            },
-            Err(e) => {
-                let _ = app.emit("update-error", e.to_string());
+        match updater::check_now(&app).await.result {
+            updater::UpdateCheckResult::Available(info) => {
+                let body = app
+                    .state::<updater::notes::ReleaseNotes>()
+                    .latest()
+                    .filter(|note| note.version == info.version)
+                    .map(|note| note.body);
+                let _ = app.emit(
+                    "update-available",
+                    serde_json::json!({
+                        "version": info.version,
+                        "body": body,
+                    }),
+                );
+            }
+            updater::UpdateCheckResult::UpToDate => {
+                let _ = app.emit("update-not-available", ());
+            }
+            updater::UpdateCheckResult::Error(e) => {
+                let _ = app.emit("update-error", e);
             }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_left_click_follows_setting() {
+        for setting in [TrayLeftClick::OpenLauncher, TrayLeftClick::OpenSettings] {
+            assert_eq!(
+                click_action(MouseButton::Left, MouseButtonState::Up, setting),
+                Some(setting)
+            );
+        }
+    }
+
+    #[test]
+    fn test_other_clicks_do_nothing() {
+        let setting = TrayLeftClick::OpenLauncher;
+
+        assert_eq!(
+            click_action(MouseButton::Left, MouseButtonState::Down, setting),
+            None
+        );
+        assert_eq!(
+            click_action(MouseButton::Right, MouseButtonState::Up, setting),
+            None
+        );
+        assert_eq!(
+            click_action(MouseButton::Middle, MouseButtonState::Up, setting),
+            None
+        );
+    }
+
+    #[test]
+    fn test_quick_action_entries() {
+        let action = |id: &str, shortcut: Option<&str>| QuickAction {
+            id: id.to_string(),
+            name: "Summarize".to_string(),
+            prompt_template: "{{input}}".to_string(),
+            input: Default::default(),
+            output: Default::default(),
+            shortcut: shortcut.map(str::to_string),
+        };
+
+        let entries = quick_action_entries(&[action("a1", Some("Ctrl+Alt+1")), action("b2", None)]);
+
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "quick_action:a1".to_string(),
+                    "Summarize (Ctrl+Alt+1)".to_string()
+                ),
+                ("quick_action:b2".to_string(), "Summarize".to_string()),
+            ]
+        );
+    }
+}
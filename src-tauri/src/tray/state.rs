@@ -0,0 +1,408 @@
+//! Tray icon state.
+//!
+//! The tray shows one [`TrayState`] at a time, resolved from what is going
+//! on: a snooze, requests or downloads in flight, a paused shortcut, an
+//! available update. Each state maps to an icon variant and a tooltip, and
+//! the variant to a bundled icon drawn for the platform and the theme of
+//! the taskbar it sits on (see [`icon_asset`]).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+
+use crate::i18n::t;
+use crate::snooze::{self, SnoozeState};
+
+/// What the tray icon currently shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayState {
+    /// Nothing to report
+    Idle,
+    /// The app is snoozed, until the given time (Unix ms) or resumed
+    Snoozed { until: Option<i64> },
+    /// The global shortcut is paused
+    Paused,
+    /// An LLM request or update download is in flight
+    Busy,
+    /// A newer version can be installed
+    UpdateAvailable,
+}
+
+/// Bundled tray icon variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayIconVariant {
+    /// The app icon
+    Default,
+    /// Grayed-out app icon
+    Paused,
+    /// App icon with an update badge
+    Update,
+}
+
+/// Platform the tray icon is drawn on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayPlatform {
+    MacOs,
+    Windows,
+    Linux,
+}
+
+impl TrayPlatform {
+    /// The platform this build runs on.
+    pub const fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::MacOs
+        } else if cfg!(target_os = "windows") {
+            Self::Windows
+        } else {
+            Self::Linux
+        }
+    }
+}
+
+/// Light or dark mode of the OS, as sent with `system-theme-changed`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemTheme {
+    Light,
+    #[default]
+    Dark,
+}
+
+impl From<tauri::Theme> for SystemTheme {
+    fn from(theme: tauri::Theme) -> Self {
+        match theme {
+            tauri::Theme::Light => Self::Light,
+            _ => Self::Dark,
+        }
+    }
+}
+
+/// Bundled tray icon files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayIconAsset {
+    /// The colored app icon, for dark taskbars
+    App,
+    /// `paused.png`
+    Paused,
+    /// `update.png`
+    Update,
+    /// `light.png`: a dark glyph for light taskbars
+    Light,
+    /// `light-paused.png`
+    LightPaused,
+    /// `light-update.png`
+    LightUpdate,
+    /// `template.png`: a monochrome template image the macOS menu bar tints
+    Template,
+    /// `template-paused.png`
+    TemplatePaused,
+    /// `template-update.png`
+    TemplateUpdate,
+}
+
+impl TrayIconAsset {
+    /// Whether the icon is a template image, drawn in the menu bar's color.
+    pub fn is_template(self) -> bool {
+        matches!(
+            self,
+            Self::Template | Self::TemplatePaused | Self::TemplateUpdate
+        )
+    }
+}
+
+/// Icon variant for a state.
+pub fn variant(state: TrayState) -> TrayIconVariant {
+    match state {
+        TrayState::Idle | TrayState::Busy => TrayIconVariant::Default,
+        TrayState::Snoozed { .. } | TrayState::Paused => TrayIconVariant::Paused,
+        TrayState::UpdateAvailable => TrayIconVariant::Update,
+    }
+}
+
+/// The icon file to show for a state.
+///
+/// macOS always gets a template image, which the menu bar tints for its
+/// own appearance. Elsewhere the colored icons are for dark taskbars and
+/// the dark glyphs for light ones. Icon swapping is unreliable with Linux
+/// tray hosts, so there the icon only follows the theme, not the state.
+///
+/// # Arguments
+///
+/// * `platform` - Platform the icon is drawn on
+/// * `theme` - Current OS theme
+/// * `state` - State to show
+pub fn icon_asset(platform: TrayPlatform, theme: SystemTheme, state: TrayState) -> TrayIconAsset {
+    let variant = match platform {
+        TrayPlatform::Linux => TrayIconVariant::Default,
+        _ => variant(state),
+    };
+    match (platform, theme, variant) {
+        (TrayPlatform::MacOs, _, TrayIconVariant::Default) => TrayIconAsset::Template,
+        (TrayPlatform::MacOs, _, TrayIconVariant::Paused) => TrayIconAsset::TemplatePaused,
+        (TrayPlatform::MacOs, _, TrayIconVariant::Update) => TrayIconAsset::TemplateUpdate,
+        (_, SystemTheme::Dark, TrayIconVariant::Default) => TrayIconAsset::App,
+        (_, SystemTheme::Dark, TrayIconVariant::Paused) => TrayIconAsset::Paused,
+        (_, SystemTheme::Dark, TrayIconVariant::Update) => TrayIconAsset::Update,
+        (_, SystemTheme::Light, TrayIconVariant::Default) => TrayIconAsset::Light,
+        (_, SystemTheme::Light, TrayIconVariant::Paused) => TrayIconAsset::LightPaused,
+        (_, SystemTheme::Light, TrayIconVariant::Update) => TrayIconAsset::LightUpdate,
+    }
+}
+
+/// Icon variant and tooltip for a state.
+///
+/// # Arguments
+///
+/// * `state` - State to show
+/// * `version` - Running app version, shown in the tooltip
+pub fn appearance(state: TrayState, version: &str) -> (TrayIconVariant, String) {
+    let name = format!("Qwik Ask v{}", version);
+    let tooltip = |key| t(key, &[("name", &name)]);
+    let text = match state {
+        TrayState::Idle => name.clone(),
+        TrayState::Snoozed { until: None } => tooltip("tray.tooltip_snoozed"),
+        TrayState::Snoozed { until: Some(until) } => t(
+            "tray.tooltip_snoozed_until",
+            &[("name", &name), ("time", &snooze::local_clock_time(until))],
+        ),
+        TrayState::Paused => tooltip("tray.tooltip_paused"),
+        TrayState::Busy => tooltip("tray.tooltip_busy"),
+        TrayState::UpdateAvailable => tooltip("tray.tooltip_update"),
+    };
+    (variant(state), text)
+}
+
+/// Tray-only state: whether an update is available, and the OS theme. Work
+/// in flight and the shortcut pause are tracked elsewhere. Managed as Tauri
+/// state.
+#[derive(Debug, Default)]
+pub struct TrayStatus {
+    update_available: AtomicBool,
+    light_theme: AtomicBool,
+}
+
+impl TrayStatus {
+    /// Record the OS theme.
+    pub fn set_theme(&self, theme: SystemTheme) {
+        self.light_theme
+            .store(theme == SystemTheme::Light, Ordering::SeqCst);
+    }
+
+    /// The last recorded OS theme; dark until one is recorded.
+    pub fn theme(&self) -> SystemTheme {
+        if self.light_theme.load(Ordering::SeqCst) {
+            SystemTheme::Light
+        } else {
+            SystemTheme::Dark
+        }
+    }
+
+    /// Record the result of an update check.
+    pub fn set_update_available(&self, available: bool) {
+        self.update_available.store(available, Ordering::SeqCst);
+    }
+
+    /// The state to show.
+    ///
+    /// A snooze wins (it was asked for explicitly), then work in flight,
+    /// then a paused shortcut (which changes how the app responds), then an
+    /// available update.
+    ///
+    /// # Arguments
+    ///
+    /// * `snooze` - Whether the app is snoozed
+    /// * `paused` - Whether the global shortcut is paused
+    /// * `busy` - Whether an LLM request or download is in flight
+    pub fn resolve(&self, snooze: SnoozeState, paused: bool, busy: bool) -> TrayState {
+        if snooze.snoozed {
+            TrayState::Snoozed {
+                until: snooze.until,
+            }
+        } else if busy {
+            TrayState::Busy
+        } else if paused {
+            TrayState::Paused
+        } else if self.update_available.load(Ordering::SeqCst) {
+            TrayState::UpdateAvailable
+        } else {
+            TrayState::Idle
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AWAKE: SnoozeState = SnoozeState {
+        snoozed: false,
+        until: None,
+    };
+
+    // ===== Appearance =====
+
+    #[test]
+    fn test_appearance_per_state() {
+        assert_eq!(
+            appearance(TrayState::Idle, "1.2.3"),
+            (TrayIconVariant::Default, "Qwik Ask v1.2.3".to_string())
+        );
+        assert_eq!(
+            appearance(TrayState::Paused, "1.2.3"),
+            (
+                TrayIconVariant::Paused,
+                "Qwik Ask v1.2.3 — shortcuts paused".to_string()
+            )
+        );
+        assert_eq!(
+            appearance(TrayState::Busy, "1.2.3"),
+            (
+                TrayIconVariant::Default,
+                "Qwik Ask v1.2.3 — working…".to_string()
+            )
+        );
+        assert_eq!(
+            appearance(TrayState::UpdateAvailable, "1.2.3"),
+            (
+                TrayIconVariant::Update,
+                "Qwik Ask v1.2.3 — update available".to_string()
+            )
+        );
+        assert_eq!(
+            appearance(TrayState::Snoozed { until: None }, "1.2.3"),
+            (
+                TrayIconVariant::Paused,
+                "Qwik Ask v1.2.3 — snoozed".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_snoozed_tooltip_shows_local_time() {
+        let until = 1_700_000_000_000;
+
+        let (variant, tooltip) = appearance(TrayState::Snoozed { until: Some(until) }, "1.2.3");
+
+        assert_eq!(variant, TrayIconVariant::Paused);
+        assert_eq!(
+            tooltip,
+            format!(
+                "Qwik Ask v1.2.3 — snoozed until {}",
+                snooze::local_clock_time(until)
+            )
+        );
+    }
+
+    // ===== Icon Assets =====
+
+    const STATES: [TrayState; 5] = [
+        TrayState::Idle,
+        TrayState::Busy,
+        TrayState::Paused,
+        TrayState::Snoozed { until: None },
+        TrayState::UpdateAvailable,
+    ];
+
+    #[test]
+    fn test_macos_uses_templates_in_any_theme() {
+        for theme in [SystemTheme::Light, SystemTheme::Dark] {
+            let assets = STATES.map(|state| icon_asset(TrayPlatform::MacOs, theme, state));
+
+            assert_eq!(
+                assets,
+                [
+                    TrayIconAsset::Template,
+                    TrayIconAsset::Template,
+                    TrayIconAsset::TemplatePaused,
+                    TrayIconAsset::TemplatePaused,
+                    TrayIconAsset::TemplateUpdate,
+                ]
+            );
+            assert!(assets.iter().all(|asset| asset.is_template()));
+        }
+    }
+
+    #[test]
+    fn test_windows_follows_theme_and_state() {
+        let dark = STATES.map(|state| icon_asset(TrayPlatform::Windows, SystemTheme::Dark, state));
+        let light =
+            STATES.map(|state| icon_asset(TrayPlatform::Windows, SystemTheme::Light, state));
+
+        assert_eq!(
+            dark,
+            [
+                TrayIconAsset::App,
+                TrayIconAsset::App,
+                TrayIconAsset::Paused,
+                TrayIconAsset::Paused,
+                TrayIconAsset::Update,
+            ]
+        );
+        assert_eq!(
+            light,
+            [
+                TrayIconAsset::Light,
+                TrayIconAsset::Light,
+                TrayIconAsset::LightPaused,
+                TrayIconAsset::LightPaused,
+                TrayIconAsset::LightUpdate,
+            ]
+        );
+        assert!(!dark.iter().chain(&light).any(|asset| asset.is_template()));
+    }
+
+    #[test]
+    fn test_linux_follows_theme_only() {
+        for state in STATES {
+            assert_eq!(
+                icon_asset(TrayPlatform::Linux, SystemTheme::Dark, state),
+                TrayIconAsset::App
+            );
+            assert_eq!(
+                icon_asset(TrayPlatform::Linux, SystemTheme::Light, state),
+                TrayIconAsset::Light
+            );
+        }
+    }
+
+    #[test]
+    fn test_theme_defaults_to_dark() {
+        let status = TrayStatus::default();
+        assert_eq!(status.theme(), SystemTheme::Dark);
+
+        status.set_theme(SystemTheme::Light);
+        assert_eq!(status.theme(), SystemTheme::Light);
+        assert_eq!(SystemTheme::from(tauri::Theme::Dark), SystemTheme::Dark);
+    }
+
+    // ===== Resolution =====
+
+    #[test]
+    fn test_idle_by_default() {
+        assert_eq!(
+            TrayStatus::default().resolve(AWAKE, false, false),
+            TrayState::Idle
+        );
+    }
+
+    #[test]
+    fn test_priority() {
+        let status = TrayStatus::default();
+        let snoozed = SnoozeState {
+            snoozed: true,
+            until: Some(5),
+        };
+        status.set_update_available(true);
+        assert_eq!(
+            status.resolve(AWAKE, false, false),
+            TrayState::UpdateAvailable
+        );
+        assert_eq!(status.resolve(AWAKE, true, false), TrayState::Paused);
+        assert_eq!(status.resolve(AWAKE, true, true), TrayState::Busy);
+        assert_eq!(
+            status.resolve(snoozed, true, true),
+            TrayState::Snoozed { until: Some(5) }
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Cancelling update downloads.
+//!
+//! A download registers with [`DownloadControl`] and gets a
+//! [`DownloadHandle`], which the download races against. Cancelling
+//! signals the handle; dropping it (however the download ends) frees the
+//! slot for the next download. Only one download runs at a time.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::watch;
+
+struct Slot {
+    id: u64,
+    cancel: watch::Sender<bool>,
+}
+
+/// The running update download, managed as Tauri state.
+#[derive(Default)]
+pub struct DownloadControl {
+    slot: Arc<Mutex<Option<Slot>>>,
+    next_id: AtomicU64,
+}
+
+impl DownloadControl {
+    /// Register a download.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DownloadHandle)` - Keeps the download registered until dropped
+    /// * `Err(String)` - Another download is in progress
+    pub fn begin(&self) -> Result<DownloadHandle, String> {
+        let mut slot = self.slot.lock().unwrap();
+        if slot.is_some() {
+            return Err("An update download is already in progress".to_string());
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (cancel, cancelled) = watch::channel(false);
+        *slot = Some(Slot { id, cancel });
+        Ok(DownloadHandle {
+            slot: Arc::clone(&self.slot),
+            id,
+            cancelled,
+        })
+    }
+
+    /// Cancel the running download.
+    ///
+    /// # Returns
+    ///
+    /// Whether a download was running; cancelling with none is a no-op.
+    pub fn cancel(&self) -> bool {
+        match self.slot.lock().unwrap().as_ref() {
+            Some(slot) => {
+                let _ = slot.cancel.send(true);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A registered download; unregisters when dropped.
+pub struct DownloadHandle {
+    slot: Arc<Mutex<Option<Slot>>>,
+    id: u64,
+    cancelled: watch::Receiver<bool>,
+}
+
+impl DownloadHandle {
+    /// Whether the download was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Wait until the download is cancelled.
+    pub async fn cancelled(&mut self) {
+        // The sender lives in the slot, which this handle keeps
+        let _ = self.cancelled.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+impl Drop for DownloadHandle {
+    fn drop(&mut self) {
+        let mut slot = self.slot.lock().unwrap();
+        if slot.as_ref().is_some_and(|slot| slot.id == self.id) {
+            *slot = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::quit::ActivityTracker;
+    use std::future::pending;
+
+    #[test]
+    fn test_cancel_without_download_is_noop() {
+        let control = DownloadControl::default();
+
+        assert!(!control.cancel());
+        assert!(control.begin().is_ok());
+    }
+
+    #[test]
+    fn test_one_download_at_a_time() {
+        let control = DownloadControl::default();
+        let _download = control.begin().unwrap();
+
+        assert!(control.begin().is_err());
+    }
+
+    #[test]
+    fn test_cancel_signals_download() {
+        let control = DownloadControl::default();
+        let download = control.begin().unwrap();
+
+        assert!(control.cancel());
+
+        assert!(download.is_cancelled());
+    }
+
+    #[test]
+    fn test_finished_download_frees_slot() {
+        let control = DownloadControl::default();
+
+        drop(control.begin().unwrap());
+
+        assert!(!control.cancel());
+        let retry = control.begin().unwrap();
+        assert!(!retry.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_download_releases_activity() {
+        let control = DownloadControl::default();
+        let tracker = ActivityTracker::default();
+
+        let download = async {
+            let _activity = tracker.begin();
+            let mut handle = control.begin().unwrap();
+            tokio::select! {
+                _ = pending::<()>() => Ok(()),
+                _ = handle.cancelled() => Err("cancelled"),
+            }
+        };
+        let cancel = async {
+            tokio::task::yield_now().await;
+            assert!(tracker.is_busy());
+            assert!(control.cancel());
+        };
+        let (result, _) = tokio::join!(download, cancel);
+
+        assert_eq!(result, Err("cancelled"));
+        assert!(!tracker.is_busy());
+        assert!(control.begin().is_ok());
+    }
+}
@@ -0,0 +1,134 @@
+//! Update release channels.
+//!
+//! Stable updates come from the endpoint configured in `tauri.conf.json`.
+//! Other channels use [`ENDPOINT_TEMPLATE`] with `{channel}` replaced by
+//! the channel name, so each channel's `latest.json` is published under a
+//! release tag of the same name.
+//!
+//! Switching from beta back to stable usually leaves the app newer than the
+//! latest stable release. Checks report that as up to date rather than
+//! offering the older release, unless a downgrade is explicitly allowed.
+
+use semver::Version;
+use tauri::Url;
+
+use crate::settings::UpdateChannel;
+
+/// Update manifest URL for non-stable channels.
+pub const ENDPOINT_TEMPLATE: &str =
+    "https://github.com/LokeshShelva/qwik-ask/releases/download/{channel}/latest.json";
+
+/// Name of a channel, as used in endpoint URLs and payloads.
+pub fn channel_name(channel: UpdateChannel) -> &'static str {
+    match channel {
+        UpdateChannel::Stable => "stable",
+        UpdateChannel::Beta => "beta",
+    }
+}
+
+/// Fill in `{channel}` in an endpoint template.
+///
+/// # Arguments
+///
+/// * `template` - Endpoint URL containing `{channel}`
+/// * `channel` - Channel to substitute
+pub fn endpoint_for(template: &str, channel: UpdateChannel) -> String {
+    template.replace("{channel}", channel_name(channel))
+}
+
+/// Endpoint to check instead of the configured one.
+///
+/// # Returns
+///
+/// * `Ok(None)` - Use the configured endpoint (stable)
+/// * `Ok(Some(Url))` - The channel's endpoint
+/// * `Err(String)` - The templated URL is invalid
+pub fn endpoint_override(channel: UpdateChannel) -> Result<Option<Url>, String> {
+    if channel == UpdateChannel::Stable {
+        return Ok(None);
+    }
+    let endpoint = endpoint_for(ENDPOINT_TEMPLATE, channel);
+    Url::parse(&endpoint)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse update endpoint '{}': {}", endpoint, e))
+}
+
+/// Whether an available version should be offered as an update.
+///
+/// # Arguments
+///
+/// * `current` - Running app version
+/// * `available` - Version in the channel's manifest
+/// * `allow_downgrade` - Offer versions older than the running one
+pub fn is_offered(current: &Version, available: &Version, allow_downgrade: bool) -> bool {
+    if allow_downgrade {
+        available != current
+    } else {
+        available > current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    // ===== Endpoints =====
+
+    #[test]
+    fn test_endpoint_templating() {
+        assert_eq!(
+            endpoint_for(
+                "https://example.com/{channel}/latest.json",
+                UpdateChannel::Beta
+            ),
+            "https://example.com/beta/latest.json"
+        );
+        assert_eq!(
+            endpoint_for("https://example.com/latest.json", UpdateChannel::Beta),
+            "https://example.com/latest.json"
+        );
+    }
+
+    #[test]
+    fn test_stable_uses_configured_endpoint() {
+        assert_eq!(endpoint_override(UpdateChannel::Stable), Ok(None));
+    }
+
+    #[test]
+    fn test_beta_endpoint() {
+        let url = endpoint_override(UpdateChannel::Beta).unwrap().unwrap();
+
+        assert_eq!(
+            url.as_str(),
+            "https://github.com/LokeshShelva/qwik-ask/releases/download/beta/latest.json"
+        );
+    }
+
+    // ===== Downgrade guard =====
+
+    #[test]
+    fn test_newer_version_is_offered() {
+        assert!(is_offered(&v("1.2.0"), &v("1.3.0"), false));
+        assert!(is_offered(&v("1.3.0-beta.1"), &v("1.3.0"), false));
+    }
+
+    #[test]
+    fn test_same_version_is_not_offered() {
+        assert!(!is_offered(&v("1.2.0"), &v("1.2.0"), false));
+        assert!(!is_offered(&v("1.2.0"), &v("1.2.0"), true));
+    }
+
+    #[test]
+    fn test_downgrade_needs_opt_in() {
+        // Back on stable after running a beta
+        let current = v("1.3.0-beta.2");
+        let stable = v("1.2.0");
+
+        assert!(!is_offered(&current, &stable, false));
+        assert!(is_offered(&current, &stable, true));
+    }
+}
@@ -0,0 +1,273 @@
+//! Structured updater errors surfaced to the frontend.
+//!
+//! Errors from the updater plugin are mapped into an [`UpdateError`] with a
+//! [`UpdateErrorKind`] and a message the user can act on, plus what's
+//! needed to diagnose it: the endpoint contacted, the HTTP status and the
+//! underlying error.
+//!
+//! # Serialization
+//!
+//! ```json
+//! {
+//!   "kind": "network",
+//!   "message": "The update server responded with HTTP 404.",
+//!   "detail": "Download request failed with status: 404 Not Found",
+//!   "endpoint": "https://github.com/.../qwik-ask_1.3.0_x64-setup.exe",
+//!   "status": 404
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use tauri_plugin_updater::Error;
+
+use crate::i18n::t;
+
+/// What kind of failure an [`UpdateError`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateErrorKind {
+    /// The update server couldn't be reached or answered with an error
+    Network,
+    /// The update server took too long
+    Timeout,
+    /// The package's signature doesn't match the app's public key
+    InvalidSignature,
+    /// The update manifest couldn't be understood
+    ManifestMalformed,
+    /// Reading or writing files failed
+    Io,
+    /// The proxy in the network settings is invalid
+    ProxyConfig,
+    /// Anything else
+    Unknown,
+}
+
+/// Why checking for, downloading or installing an update failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateError {
+    pub kind: UpdateErrorKind,
+    /// What to tell the user
+    pub message: String,
+    /// The underlying error
+    pub detail: String,
+    /// Manifest or download URL contacted, if any
+    pub endpoint: Option<String>,
+    /// HTTP status of the failed response, if known
+    pub status: Option<u16>,
+}
+
+impl UpdateError {
+    /// Create an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - What kind of failure it is
+    /// * `detail` - The underlying error
+    /// * `endpoint` - URL contacted, if any
+    /// * `status` - HTTP status, if known
+    pub fn new(
+        kind: UpdateErrorKind,
+        detail: impl Into<String>,
+        endpoint: Option<String>,
+        status: Option<u16>,
+    ) -> Self {
+        let detail = detail.into();
+        Self {
+            kind,
+            message: user_message(kind, &detail, status),
+            detail,
+            endpoint,
+            status,
+        }
+    }
+
+    /// Map an updater plugin error.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The plugin's error
+    /// * `endpoint` - Manifest or download URL that was contacted
+    pub fn from_updater(error: &Error, endpoint: Option<String>) -> Self {
+        let (kind, status) = match error {
+            Error::Reqwest(e) if e.is_timeout() => (UpdateErrorKind::Timeout, None),
+            Error::Reqwest(e) if e.is_decode() => (
+                UpdateErrorKind::ManifestMalformed,
+                e.status().map(|s| s.as_u16()),
+            ),
+            Error::Reqwest(e) => (UpdateErrorKind::Network, e.status().map(|s| s.as_u16())),
+            Error::Network(message) => (UpdateErrorKind::Network, parse_status(message)),
+            // The endpoint answered without a usable release (e.g. HTTP 404)
+            Error::ReleaseNotFound => (UpdateErrorKind::Network, None),
+            Error::Minisign(_) | Error::Base64(_) | Error::SignatureUtf8(_) => {
+                (UpdateErrorKind::InvalidSignature, None)
+            }
+            Error::Serialization(_) | Error::Semver(_) | Error::TargetNotFound(_) => {
+                (UpdateErrorKind::ManifestMalformed, None)
+            }
+            Error::Io(_)
+            | Error::TempDirNotFound
+            | Error::TempDirNotOnSameMountPoint
+            | Error::FailedToDetermineExtractPath => (UpdateErrorKind::Io, None),
+            _ => (UpdateErrorKind::Unknown, None),
+        };
+        Self::new(kind, error.to_string(), endpoint, status)
+    }
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for UpdateError {}
+
+/// The message shown for an error.
+fn user_message(kind: UpdateErrorKind, detail: &str, status: Option<u16>) -> String {
+    match (kind, status) {
+        (UpdateErrorKind::Network, Some(status)) => {
+            t("updater.http_status", &[("status", &status)])
+        }
+        (UpdateErrorKind::Network, None) => t("updater.unreachable", &[]),
+        (UpdateErrorKind::Timeout, _) => t("updater.timeout", &[]),
+        (UpdateErrorKind::InvalidSignature, _) => t("updater.invalid_signature", &[]),
+        (UpdateErrorKind::ManifestMalformed, _) => t("updater.manifest_malformed", &[]),
+        (UpdateErrorKind::Io, _) => t("updater.io", &[("detail", &detail)]),
+        (UpdateErrorKind::ProxyConfig, _) => t("updater.proxy_config", &[("detail", &detail)]),
+        (UpdateErrorKind::Unknown, _) => t("updater.unknown", &[("detail", &detail)]),
+    }
+}
+
+/// The status in the plugin's "... failed with status: 404 Not Found".
+fn parse_status(message: &str) -> Option<u16> {
+    let (_, rest) = message.split_once("status: ")?;
+    rest.get(..3)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDPOINT: &str = "https://example.com/latest.json";
+
+    fn map(error: Error) -> UpdateError {
+        UpdateError::from_updater(&error, Some(ENDPOINT.to_string()))
+    }
+
+    #[test]
+    fn test_download_status_is_network_error() {
+        let error = map(Error::Network(
+            "Download request failed with status: 404 Not Found".to_string(),
+        ));
+
+        assert_eq!(error.kind, UpdateErrorKind::Network);
+        assert_eq!(error.status, Some(404));
+        assert_eq!(error.endpoint.as_deref(), Some(ENDPOINT));
+        assert!(error.message.contains("404"));
+    }
+
+    #[test]
+    fn test_release_not_found_is_network_error() {
+        let error = map(Error::ReleaseNotFound);
+
+        assert_eq!(error.kind, UpdateErrorKind::Network);
+        assert_eq!(error.status, None);
+    }
+
+    #[test]
+    fn test_request_error_is_network_error() {
+        let request_error = reqwest::Client::new().get("not a url").build().unwrap_err();
+
+        assert_eq!(
+            map(Error::Reqwest(request_error)).kind,
+            UpdateErrorKind::Network
+        );
+    }
+
+    #[test]
+    fn test_signature_errors_advise_reinstalling() {
+        let error = map(Error::SignatureUtf8("bad".to_string()));
+
+        assert_eq!(error.kind, UpdateErrorKind::InvalidSignature);
+        assert!(error.message.contains("Reinstall"));
+    }
+
+    #[test]
+    fn test_manifest_errors() {
+        let json_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let semver_error = semver::Version::parse("one").unwrap_err();
+
+        assert_eq!(
+            map(Error::Serialization(json_error)).kind,
+            UpdateErrorKind::ManifestMalformed
+        );
+        assert_eq!(
+            map(Error::Semver(semver_error)).kind,
+            UpdateErrorKind::ManifestMalformed
+        );
+        assert_eq!(
+            map(Error::TargetNotFound("linux-x86_64".to_string())).kind,
+            UpdateErrorKind::ManifestMalformed
+        );
+    }
+
+    #[test]
+    fn test_io_errors() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+
+        let error = map(Error::Io(io_error));
+
+        assert_eq!(error.kind, UpdateErrorKind::Io);
+        assert!(error.message.contains("denied"));
+        assert_eq!(map(Error::TempDirNotFound).kind, UpdateErrorKind::Io);
+    }
+
+    #[test]
+    fn test_other_errors_are_unknown() {
+        assert_eq!(map(Error::EmptyEndpoints).kind, UpdateErrorKind::Unknown);
+        assert_eq!(
+            map(Error::InsecureTransportProtocol).kind,
+            UpdateErrorKind::Unknown
+        );
+    }
+
+    #[test]
+    fn test_proxy_config_error() {
+        let error = UpdateError::new(
+            UpdateErrorKind::ProxyConfig,
+            "Invalid proxy URL 'proxy:8080': relative URL without a base",
+            None,
+            None,
+        );
+
+        assert!(error.message.contains("proxy URL in Settings"));
+        assert!(error.message.contains("proxy:8080"));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap()["kind"],
+            "proxy_config"
+        );
+    }
+
+    #[test]
+    fn test_status_parsing() {
+        assert_eq!(
+            parse_status("failed with status: 503 Service Unavailable"),
+            Some(503)
+        );
+        assert_eq!(parse_status("connection refused"), None);
+        assert_eq!(parse_status("status: abc"), None);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let error = UpdateError::new(UpdateErrorKind::Timeout, "timed out", None, None);
+
+        let json = serde_json::to_value(&error).unwrap();
+
+        assert_eq!(json["kind"], "timeout");
+        assert_eq!(json["detail"], "timed out");
+        assert!(json["endpoint"].is_null());
+    }
+}
@@ -6,28 +6,152 @@
 //! # Features
 //!
 //! - Check for available updates
-//! - Download and install updates
-//! - Emit events for update progress
+//! - Background checks every `updates.check_interval_hours` (see [`schedule`])
+//! - Download updates in the background and install them on quit
+//! - Emit events for update progress, with transfer speed and ETA
 //!
 //! # Update Flow
 //!
 //! 1. Check for updates via the configured endpoint
 //! 2. If update available, optionally download and install
 //! 3. Restart the application to apply the update
+//!
+//! # Background Checks
+//!
+//! With `updates.auto_check` on, updates are checked in the background
+//! and `update-available` is emitted when one is found, unless it is the
+//! version the user skipped. The scheduler is restarted whenever the update
+//! settings change. While do not disturb is on, `notify` is `false` and the
+//! event is sent again with `notify: true` once it ends (see
+//! [`crate::notifications`]).
+//!
+//! # Skipping Versions
+//!
+//! `skip_update_version` stores `updates.skipped_version`. Checks then
+//! treat that version as up to date, unless the user asked explicitly
+//! (`force`), in which case it is reported with `skipped: true`. The skip
+//! is cleared once a newer version is found (see [`version`]).
+//!
+//! # Staged Updates
+//!
+//! `download_update` downloads an update without installing it, so an
+//! ongoing conversation isn't interrupted. The staged package is installed
+//! by `install_staged_update`, or when the app quits if
+//! `updates.auto_install` is on. A download in progress can be stopped with
+//! `cancel_update_download` (see [`cancel`]). Checks report `staged: true` for a version
+//! that is already downloaded, and drop a staged package once a newer
+//! version appears (see [`staging`]).
+//!
+//! # Release Notes
+//!
+//! `get_release_notes` returns the notes of every version between the
+//! running one and the update, from the release feed (see [`notes`]).
+//! Checks set `notes_available` when there is something to show.
+//!
+//! # Channels
+//!
+//! `updates.channel` picks the endpoint checked (see [`channel`]). A
+//! channel whose latest version is older than the running one (e.g. back on
+//! stable after a beta) is reported as up to date; pass `allowDowngrade` to
+//! get the older version offered and installed instead.
+//!
+//! # Check Status
+//!
+//! Every check is recorded in the settings store, and
+//! `get_update_status` returns it with the channel, the endpoint (tokens
+//! masked), the skipped and staged versions and whether a check is running.
+//! A check asked for while the same one is running shares its result
+//! rather than contacting the server again (see [`status`]).
+//!
+//! # After Updating
+//!
+//! Each launch records the running version (see [`transition`]). After an
+//! update, `app-updated` is emitted and `get_update_transition` returns
+//! `(from, to)` until `acknowledge_update_transition`; a fresh install
+//! reports `is_first_run` instead.
+//!
+//! # Errors
+//!
+//! Failed checks report an [`error::UpdateError`] with a kind, a message
+//! for the user, the endpoint contacted and the HTTP status. Background
+//! and tray checks emit the same payload as `update-error`. Download and
+//! install commands return its message.
+//!
+//! Checks and downloads use the proxy and timeout from `network` settings,
+//! read each time the updater is built. An invalid proxy URL fails with the
+//! `proxy_config` kind rather than a network error.
+//!
+//! ```typescript
+//! await listen<{ version: string; notify: boolean }>('update-available', ({ payload }) => {
+//!   if (payload.notify) new Notification('Qwik Ask', { body: `v${payload.version} is available` });
+//! });
+//!
+//! const result = await invoke<UpdateCheck>('check_for_updates', { allowDowngrade: true });
+//! // { channel: 'stable', status: 'Available', data: { version: '1.2.0', skipped: false } }
+//! await invoke('download_and_install_update', { allowDowngrade: true });
+//!
+//! await listen<DownloadProgress>('update-download-stats', ({ payload }) => {
+//!   // { downloaded_bytes, total_bytes, percent, bytes_per_sec, eta_secs }
+//! });
+//! if (!result.data.staged) await invoke('download_update');
+//! await invoke('install_staged_update');
+//! ```
+
+pub mod cancel;
+pub mod channel;
+pub mod error;
+pub mod notes;
+pub mod progress;
+pub mod schedule;
+pub mod staging;
+pub mod status;
+pub mod transition;
+pub mod version;
 
-use serde::Serialize;
-use tauri::Emitter;
-use tauri_plugin_updater::UpdaterExt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_updater::{Update, Updater, UpdaterExt};
+
+use crate::db::now_ms;
+use crate::network;
+use crate::notifications::{self, held::HeldNotification};
+use crate::paths;
+use crate::settings::{SettingsManager, UpdateChannel, UpdateSettings};
+use crate::shutdown::{self, ShutdownReason};
+use crate::snooze;
+use crate::telemetry::{self, TelemetryEvent};
+use crate::tray;
+use crate::window::quit::ActivityTracker;
+use cancel::DownloadControl;
+use error::{UpdateError, UpdateErrorKind};
+use notes::{ReleaseNote, ReleaseNotes};
+use progress::DownloadMeter;
+use schedule::CheckSchedule;
+use staging::{Stage, StagedUpdate};
+use status::{InFlight, StagedStatus, UpdateStatus};
+use version::SkipStatus;
 
 /// Information about an available update.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateInfo {
     /// The new version available
     pub version: String,
+    /// Whether the user skipped this version; only reported by forced checks
+    pub skipped: bool,
+    /// Whether this version is downloaded and ready to install
+    pub staged: bool,
+    /// Whether `get_release_notes` has something to show, without fetching
+    /// the release feed
+    pub notes_available: bool,
 }
 
 /// Result of checking for updates.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "status", content = "data")]
 pub enum UpdateCheckResult {
     /// An update is available
@@ -35,38 +159,349 @@ pub enum UpdateCheckResult {
     /// Already on the latest version
     UpToDate,
     /// Error occurred while checking
-    Error(String),
+    Error(UpdateError),
+}
+
+/// Result of the `check_for_updates` command: the check result plus the
+/// channel that was checked.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateCheck {
+    /// Channel checked
+    pub channel: UpdateChannel,
+    #[serde(flatten)]
+    pub result: UpdateCheckResult,
+}
+
+/// The outcome of the most recent check, manual, tray or background.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastUpdateCheck {
+    /// When the check finished (Unix ms)
+    pub checked_at: i64,
+    /// Channel checked
+    pub channel: UpdateChannel,
+    pub result: UpdateCheckResult,
+}
+
+/// How to check for updates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CheckOptions {
+    /// Report the skipped version too
+    force: bool,
+    /// Offer versions older than the running one
+    allow_downgrade: bool,
 }
 
 /// Check for available updates.
 ///
-/// Queries the configured update endpoint to check if a newer version is available.
+/// Queries the endpoint of the `updates.channel` channel to check if a newer
+/// version is available.
 ///
 /// # Arguments
 ///
 /// * `app` - The Tauri AppHandle
+/// * `force` - Report the update even if the user skipped this version
+///   (defaults to `false`)
+/// * `allow_downgrade` - Report the channel's version even if it is older
+///   than the running one (defaults to `false`)
 ///
 /// # Returns
 ///
-/// * `UpdateCheckResult` - The result of the update check
+/// * `UpdateCheck` - The result of the update check and the channel checked
 #[tauri::command]
-pub async fn check_for_updates(app: tauri::AppHandle) -> UpdateCheckResult {
-    let updater = match app.updater() {
+pub async fn check_for_updates(
+    app: tauri::AppHandle,
+    force: Option<bool>,
+    allow_downgrade: Option<bool>,
+) -> UpdateCheck {
+    let options = CheckOptions {
+        force: force.unwrap_or(false),
+        allow_downgrade: allow_downgrade.unwrap_or(false),
+    };
+    let channel = update_channel(&app);
+    UpdateCheck {
+        channel,
+        result: check(&app, channel, options).await,
+    }
+}
+
+/// What the updates panel shows about checking.
+///
+/// # Returns
+///
+/// The last check (kept across restarts), the channel and endpoint
+/// checked, the skipped version, the staged update and whether a check is
+/// running now.
+///
+/// # Frontend Usage
+///
+/// ```typescript
+/// const status = await invoke<UpdateStatus>('get_update_status');
+/// // { last_check: { checked_at, channel, status: 'UpToDate' }, channel: 'stable',
+/// //   endpoint: 'https://.../latest.json?token=[redacted]', skipped_version: null,
+/// //   staged: { version: '1.3.0', ready: true }, checking: false }
+/// ```
+#[tauri::command]
+pub fn get_update_status(app: AppHandle) -> UpdateStatus {
+    let channel = update_channel(&app);
+    let scheduler = app.try_state::<UpdateScheduler>();
+    let last_check = scheduler
+        .as_ref()
+        .and_then(|scheduler| scheduler.last_check())
+        .or_else(|| status::load(&*app.state::<SettingsManager>()));
+    let staged = app.try_state::<UpdateStage>().and_then(|stage| {
+        let stage = stage.0.lock().unwrap();
+        stage.staged().map(|marker| StagedStatus {
+            version: marker.version.clone(),
+            ready: stage.is_ready(&marker.version),
+        })
+    });
+    UpdateStatus {
+        last_check,
+        channel,
+        endpoint: update_endpoint(&app, channel).map(|url| status::redact_endpoint(&url)),
+        skipped_version: skipped_version(&app),
+        staged,
+        checking: scheduler.is_some_and(|scheduler| scheduler.in_flight.is_running()),
+    }
+}
+
+/// Release notes of the versions after `from_version` up to and including
+/// `to_version`, newest first.
+///
+/// The release feed is fetched once per session. If it can't be fetched,
+/// only the notes of the update found by the last check are returned.
+///
+/// # Arguments
+///
+/// * `from_version` - Running version
+/// * `to_version` - Version being updated to
+///
+/// # Frontend Usage
+///
+/// ```typescript
+/// const notes = await invoke<ReleaseNote[]>('get_release_notes', {
+///   fromVersion: await invoke('get_current_version'),
+///   toVersion: info.version,
+/// });
+/// // [{ version: '1.5.0', date: '2025-03-01T10:00:00Z', body: '...' }, ...]
+/// ```
+#[tauri::command]
+pub async fn get_release_notes(
+    app: AppHandle,
+    from_version: String,
+    to_version: String,
+) -> Vec<ReleaseNote> {
+    let settings = app
+        .state::<SettingsManager>()
+        .snapshot()
+        .unwrap_or_default();
+    let feed_url = settings
+        .updates
+        .release_notes_url
+        .clone()
+        .unwrap_or_else(|| notes::DEFAULT_FEED_URL.to_string());
+    app.state::<ReleaseNotes>()
+        .between(&settings.network, &feed_url, &from_version, &to_version)
+        .await
+}
+
+/// Skip an update version.
+///
+/// Checks treat it as up to date until a newer version is released.
+///
+/// # Arguments
+///
+/// * `version` - Version to skip, as reported by `check_for_updates`
+///
+/// # Frontend Usage
+///
+/// ```typescript
+/// await invoke('skip_update_version', { version: info.version });
+/// ```
+#[tauri::command]
+pub async fn skip_update_version(
+    app: AppHandle,
+    settings_manager: State<'_, SettingsManager>,
+    version: String,
+) -> Result<(), String> {
+    settings_manager
+        .update(|settings| {
+            settings.updates.skipped_version = Some(version);
+            Ok(())
+        })
+        .await?;
+    tray::set_update_available(&app, false);
+    Ok(())
+}
+
+/// Check the `updates.channel` channel with the default options, as the
+/// tray menu does.
+pub async fn check_now(app: &AppHandle) -> UpdateCheck {
+    let channel = update_channel(app);
+    UpdateCheck {
+        channel,
+        result: check(app, channel, CheckOptions::default()).await,
+    }
+}
+
+/// Check for updates and record the result in the tray.
+///
+/// Shared by the `check_for_updates` command, the tray menu and the
+/// background scheduler. The skipped version is reported as up to date
+/// unless `force` is set; a newer version clears the skip. A check while
+/// the same one is running shares its result. The result is kept for
+/// [`UpdateScheduler::last_check`] and in the settings store.
+async fn check(
+    app: &AppHandle,
+    channel: UpdateChannel,
+    options: CheckOptions,
+) -> UpdateCheckResult {
+    let Some(scheduler) = app.try_state::<UpdateScheduler>() else {
+        return query(app, channel, options).await;
+    };
+    scheduler
+        .in_flight
+        .run((channel, options), || async {
+            let result = query(app, channel, options).await;
+            let last = LastUpdateCheck {
+                checked_at: now_ms(),
+                channel,
+                result: result.clone(),
+            };
+            if let Err(e) = status::persist(&*app.state::<SettingsManager>(), &last) {
+                tracing::warn!(error = %e, "Failed to save update check");
+            }
+            *scheduler.last_check.lock().unwrap() = Some(last);
+            result
+        })
+        .await
+}
+
+async fn query(
+    app: &AppHandle,
+    channel: UpdateChannel,
+    options: CheckOptions,
+) -> UpdateCheckResult {
+    let updater = match build_updater(app, channel, options.allow_downgrade) {
         Ok(updater) => updater,
-        Err(e) => {
-            return UpdateCheckResult::Error(format!("Failed to initialize updater: {}", e));
-        }
+        Err(e) => return UpdateCheckResult::Error(e),
     };
 
     match updater.check().await {
-        Ok(Some(update)) => UpdateCheckResult::Available(UpdateInfo {
-            version: update.version.clone(),
-        }),
-        Ok(None) => UpdateCheckResult::UpToDate,
-        Err(e) => UpdateCheckResult::Error(format!("Failed to check for updates: {}", e)),
+        Ok(Some(update)) => {
+            let version = update.version.clone();
+            let notes = app.state::<ReleaseNotes>();
+            notes.set_latest(ReleaseNote {
+                version: version.clone(),
+                date: update.raw_json["pub_date"].as_str().map(str::to_string),
+                body: update.body.clone().unwrap_or_default(),
+            });
+            let notes_available = notes.has_notes(&update.current_version, &version);
+            reconcile_stage(app, Some(update));
+            let staged = app
+                .try_state::<UpdateStage>()
+                .is_some_and(|stage| stage.is_ready(&version));
+            let skipped = skipped_version(app);
+            let status = version::skip_status(&version, skipped.as_deref());
+            let is_skipped = status == SkipStatus::Skipped;
+            if status == SkipStatus::Superseded {
+                if let Err(e) = clear_skipped_version(app).await {
+                    tracing::warn!(error = %e, "Failed to clear the skipped version");
+                }
+            }
+            tray::set_update_available(app, !is_skipped);
+            if is_skipped && !options.force {
+                return UpdateCheckResult::UpToDate;
+            }
+            UpdateCheckResult::Available(UpdateInfo {
+                version,
+                skipped: is_skipped,
+                staged,
+                notes_available,
+            })
+        }
+        Ok(None) => {
+            reconcile_stage(app, None);
+            tray::set_update_available(app, false);
+            UpdateCheckResult::UpToDate
+        }
+        Err(e) => {
+            UpdateCheckResult::Error(UpdateError::from_updater(&e, update_endpoint(app, channel)))
+        }
     }
 }
 
+/// Download an available update without installing it.
+///
+/// The package is staged (see [`staging`]) and installed later by
+/// [`install_staged_update`], or when the app quits if
+/// `updates.auto_install` is on. A staged package of another version is
+/// replaced.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+/// * `allow_downgrade` - Download the channel's version even if it is older
+///   than the running one (defaults to `false`)
+///
+/// # Returns
+///
+/// * `Ok(String)` - The staged version
+/// * `Err(String)` - Error message if the download failed
+///
+/// # Events
+///
+/// - `update-download-progress` - Progress percentage (0-100)
+/// - `update-download-stats` - Bytes, speed and ETA (see [`progress`])
+/// - `update-download-finished` - Download completed
+/// - `update-staged` - The staged version, once written to disk
+/// - `update-download-cancelled` - Stopped by `cancel_update_download`
+#[tauri::command]
+pub async fn download_update(
+    app: tauri::AppHandle,
+    allow_downgrade: Option<bool>,
+) -> Result<String, String> {
+    let _activity = app.state::<ActivityTracker>().begin();
+    stage_update(&app, allow_downgrade.unwrap_or(false)).await
+}
+
+/// Cancel the update download in progress.
+///
+/// The download returns an error, and `update-download-cancelled` is
+/// emitted. Nothing is staged, so a later download starts from scratch.
+///
+/// # Returns
+///
+/// Whether a download was running; with none this does nothing.
+///
+/// # Frontend Usage
+///
+/// ```typescript
+/// await invoke('cancel_update_download');
+/// ```
+#[tauri::command]
+pub fn cancel_update_download(download_control: State<DownloadControl>) -> bool {
+    download_control.cancel()
+}
+
+/// Install the staged update.
+///
+/// The application will need to be restarted to apply the update.
+///
+/// # Returns
+///
+/// * `Ok(())` - Update installed successfully
+/// * `Err(String)` - Nothing is staged, the staged version hasn't been
+///   confirmed by a check since startup, or installing failed
+///
+/// # Events
+///
+/// - `update-install-started` - Installation finished, restart to apply
+#[tauri::command]
+pub async fn install_staged_update(app: tauri::AppHandle) -> Result<(), String> {
+    install_staged(&app)
+}
+
 /// Download and install an available update.
 ///
 /// Downloads the update package and installs it. The application will need to
@@ -75,6 +510,8 @@ pub async fn check_for_updates(app: tauri::AppHandle) -> UpdateCheckResult {
 /// # Arguments
 ///
 /// * `app` - The Tauri AppHandle
+/// * `allow_downgrade` - Install the channel's version even if it is older
+///   than the running one (defaults to `false`)
 ///
 /// # Returns
 ///
@@ -85,61 +522,332 @@ pub async fn check_for_updates(app: tauri::AppHandle) -> UpdateCheckResult {
 ///
 /// Emits progress events during download:
 /// - `update-download-progress` - Progress percentage (0-100)
+/// - `update-download-stats` - Bytes, speed and ETA (see [`progress`])
 /// - `update-download-finished` - Download completed
+/// - `update-staged` - The downloaded version
 /// - `update-install-started` - Installation started
 #[tauri::command]
-pub async fn download_and_install_update(app: tauri::AppHandle) -> Result<(), String> {
-    let updater = app
-        .updater()
-        .map_err(|e| format!("Failed to initialize updater: {}", e))?;
+pub async fn download_and_install_update(
+    app: tauri::AppHandle,
+    allow_downgrade: Option<bool>,
+) -> Result<(), String> {
+    let _activity = app.state::<ActivityTracker>().begin();
+    stage_update(&app, allow_downgrade.unwrap_or(false)).await?;
+    install_staged(&app)
+}
+
+/// Install the staged update while quitting.
+///
+/// Does nothing unless `updates.auto_install` is on and an update is
+/// staged. Failures are logged; quitting goes ahead regardless.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+pub fn install_on_quit(app: &AppHandle) {
+    let Some(stage) = app.try_state::<UpdateStage>() else {
+        return;
+    };
+    let auto_install = app
+        .state::<SettingsManager>()
+        .snapshot()
+        .map(|settings| settings.updates.auto_install)
+        .unwrap_or(false);
+    if !auto_install || stage.0.lock().unwrap().staged().is_none() {
+        return;
+    }
+    if let Err(e) = install_staged(app) {
+        tracing::error!(error = %e, "Failed to install update on quit");
+    }
+}
+
+/// Confirm a stage restored at startup with a check in the background,
+/// so it can be installed (or discarded if it's out of date).
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+pub fn confirm_restored_stage(app: &AppHandle) {
+    let Some(stage) = app.try_state::<UpdateStage>() else {
+        return;
+    };
+    if stage.0.lock().unwrap().staged().is_none() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let channel = update_channel(&app);
+        if let UpdateCheckResult::Error(e) = check(&app, channel, CheckOptions::default()).await {
+            tracing::warn!(error = %e, "Failed to confirm staged update");
+        }
+    });
+}
 
+/// Download the available update to the staging directory, unless it is
+/// already staged.
+async fn stage_update(app: &AppHandle, allow_downgrade: bool) -> Result<String, String> {
+    let mut download_handle = app.state::<DownloadControl>().begin()?;
+    let channel = update_channel(app);
+    let updater = build_updater(app, channel, allow_downgrade).map_err(|e| e.to_string())?;
     let update = updater
         .check()
         .await
-        .map_err(|e| format!("Failed to check for updates: {}", e))?
+        .map_err(|e| UpdateError::from_updater(&e, update_endpoint(app, channel)).to_string())?
         .ok_or_else(|| "No update available".to_string())?;
 
-    let app_handle = app.clone();
+    // Drops a superseded or stale stage, or confirms this version's
+    reconcile_stage(app, Some(update.clone()));
+    let stage = app.state::<UpdateStage>();
+    if stage.is_ready(&update.version) {
+        return Ok(update.version);
+    }
 
-    // Download with progress tracking
-    let mut downloaded: u64 = 0;
-    let mut last_percentage: u8 = 0;
+    let bytes = tokio::select! {
+        bytes = download(app, &update) => bytes?,
+        _ = download_handle.cancelled() => return Err(download_cancelled(app)),
+    };
+    if download_handle.is_cancelled() {
+        return Err(download_cancelled(app));
+    }
+
+    // Written under a temporary name so a crash can't leave a truncated
+    // package behind
+    let path = staging_dir(app)?.join(staging::package_file_name(&update.version));
+    let partial = path.with_extension(staging::PARTIAL_EXTENSION);
+    std::fs::write(&partial, bytes).map_err(|e| format!("Failed to save update: {}", e))?;
+    std::fs::rename(&partial, &path).map_err(|e| format!("Failed to save update: {}", e))?;
+
+    let marker = StagedUpdate {
+        version: update.version.clone(),
+        path,
+    };
+    let previous = stage.0.lock().unwrap().set(marker.clone(), update);
+    if let Some(previous) = previous {
+        let _ = std::fs::remove_file(&previous.path);
+    }
+    persist_stage(app, Some(&marker));
+    let _ = app.emit("update-staged", &marker.version);
+    Ok(marker.version)
+}
+
+/// Clean up after a cancelled download and tell the frontend.
+///
+/// # Returns
+///
+/// The error the download returns.
+fn download_cancelled(app: &AppHandle) -> String {
+    if let Ok(dir) = staging_dir(app) {
+        staging::remove_partial_packages(&dir);
+    }
+    let _ = app.emit("update-download-cancelled", ());
+    "Update download cancelled".to_string()
+}
+
+/// Download an update package, emitting progress events.
+async fn download(app: &AppHandle, update: &Update) -> Result<Vec<u8>, String> {
+    let started = Instant::now();
+    let mut meter = DownloadMeter::default();
+    let mut last_percentage = None;
 
     update
-        .download_and_install(
+        .download(
             |chunk_length, content_length| {
-                downloaded += chunk_length as u64;
-                if let Some(total) = content_length {
-                    let percentage = ((downloaded as f64 / total as f64) * 100.0) as u8;
-                    // Only emit when percentage changes to avoid flooding
-                    if percentage != last_percentage {
-                        last_percentage = percentage;
-                        let _ = app_handle.emit("update-download-progress", percentage);
+                let Some(progress) = meter.record(chunk_length, content_length, started.elapsed())
+                else {
+                    return;
+                };
+                // The bare percentage, for listeners of the old event
+                if progress.percent != last_percentage {
+                    last_percentage = progress.percent;
+                    if let Some(percentage) = progress.percent {
+                        let _ = app.emit("update-download-progress", percentage);
                     }
                 }
+                let _ = app.emit("update-download-stats", progress);
             },
             || {
-                let _ = app_handle.emit("update-download-finished", ());
+                let _ = app.emit("update-download-finished", ());
             },
         )
         .await
-        .map_err(|e| format!("Failed to download and install update: {}", e))?;
+        .map_err(|e| {
+            UpdateError::from_updater(&e, Some(update.download_url.to_string())).to_string()
+        })
+}
 
-    let _ = app.emit("update-install-started", ());
+/// Install the staged update and forget it, whether or not installing
+/// worked; a failed package is downloaded again next time.
+fn install_staged(app: &AppHandle) -> Result<(), String> {
+    let (marker, update) = app
+        .state::<UpdateStage>()
+        .0
+        .lock()
+        .unwrap()
+        .take_ready()
+        .ok_or_else(|| "No downloaded update is ready to install".to_string())?;
 
+    let result = std::fs::read(&marker.path)
+        .map_err(|e| format!("Failed to read downloaded update: {}", e))
+        .and_then(|bytes| {
+            update
+                .install(bytes)
+                .map_err(|e| UpdateError::from_updater(&e, None).to_string())
+        });
+    let _ = std::fs::remove_file(&marker.path);
+    persist_stage(app, None);
+    result?;
+
+    let _ = app.emit("update-install-started", ());
     Ok(())
 }
 
+/// Bring the staged update in line with a check result, deleting the
+/// package if it's superseded or stale.
+fn reconcile_stage(app: &AppHandle, available: Option<Update>) {
+    let Some(stage) = app.try_state::<UpdateStage>() else {
+        return;
+    };
+    let version = available.as_ref().map(|update| update.version.clone());
+    let discarded = stage
+        .0
+        .lock()
+        .unwrap()
+        .reconcile(version.as_deref().zip(available), env!("CARGO_PKG_VERSION"));
+    if let Some(discarded) = discarded {
+        let _ = std::fs::remove_file(&discarded.path);
+        persist_stage(app, None);
+    }
+}
+
+/// Record the staged update in the settings store.
+fn persist_stage(app: &AppHandle, marker: Option<&StagedUpdate>) {
+    if let Err(e) = app
+        .state::<SettingsManager>()
+        .save_state(staging::STAGED_UPDATE_KEY, &marker)
+    {
+        tracing::warn!(error = %e, "Failed to save staged update");
+    }
+}
+
+/// Resolve (and create) the staging directory.
+fn staging_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = paths::data_dir(app)?.join(staging::STAGING_DIR);
+
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create updates dir: {}", e))?;
+
+    Ok(dir)
+}
+
+/// The staged update, managed as Tauri state.
+#[derive(Default)]
+pub struct UpdateStage(Mutex<Stage<Update>>);
+
+impl UpdateStage {
+    /// Stage restored from the settings store.
+    pub fn restore(marker: Option<StagedUpdate>) -> Self {
+        Self(Mutex::new(Stage::restore(marker)))
+    }
+
+    /// Whether `version` is staged and ready to install.
+    pub fn is_ready(&self, version: &str) -> bool {
+        self.0.lock().unwrap().is_ready(version)
+    }
+}
+
 /// Restart the application to apply the installed update.
 ///
-/// This will close the current application and start the new version.
+/// This will shut down the current application (see [`crate::shutdown`])
+/// and start the new version.
 ///
 /// # Arguments
 ///
 /// * `app` - The Tauri AppHandle
 #[tauri::command]
 pub fn restart_app(app: tauri::AppHandle) {
-    app.restart();
+    shutdown::shutdown(&app, ShutdownReason::Restart);
+}
+
+/// The version change since the release notes were last shown.
+///
+/// # Returns
+///
+/// `(from, to)` after an update (or a downgrade) until
+/// `acknowledge_update_transition` is called; `None` otherwise, including
+/// on a fresh install (see `is_first_run`).
+///
+/// # Frontend Usage
+///
+/// ```typescript
+/// const transition = await invoke<[string, string] | null>('get_update_transition');
+/// if (transition) {
+///   showWhatsNew(await invoke('get_release_notes', { fromVersion: transition[0], toVersion: transition[1] }));
+///   await invoke('acknowledge_update_transition');
+/// }
+/// ```
+#[tauri::command]
+pub fn get_update_transition(settings_manager: State<SettingsManager>) -> Option<(String, String)> {
+    let pending = transition::pending(&*settings_manager).ok().flatten()?;
+    Some((pending.from?, pending.to))
+}
+
+/// Whether this is a fresh install whose onboarding hasn't been
+/// acknowledged yet.
+#[tauri::command]
+pub fn is_first_run(settings_manager: State<SettingsManager>) -> bool {
+    transition::pending(&*settings_manager)
+        .ok()
+        .flatten()
+        .is_some_and(|pending| pending.is_first_run())
+}
+
+/// Clear the pending update transition (or first run) once shown.
+#[tauri::command]
+pub fn acknowledge_update_transition(
+    settings_manager: State<SettingsManager>,
+) -> Result<(), String> {
+    transition::acknowledge(&*settings_manager)
+}
+
+/// Payload of `app-updated`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AppUpdated {
+    pub from: String,
+    pub to: String,
+    /// An older version was installed over a newer one
+    pub downgrade: bool,
+}
+
+/// Record the running version at startup, emitting `app-updated` if it
+/// changed since the last launch.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+pub fn record_launch(app: &AppHandle) {
+    let settings_manager = app.state::<SettingsManager>();
+    match transition::record_launch(&*settings_manager, env!("CARGO_PKG_VERSION")) {
+        Ok(Some(launch)) => {
+            let downgrade = launch.is_downgrade();
+            if let Some(from) = launch.from {
+                telemetry::record(
+                    app,
+                    TelemetryEvent::UpdateInstalled {
+                        version: launch.to.clone(),
+                    },
+                );
+                let _ = app.emit(
+                    "app-updated",
+                    AppUpdated {
+                        from,
+                        to: launch.to,
+                        downgrade,
+                    },
+                );
+            }
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!(error = %e, "Failed to record app version"),
+    }
 }
 
 /// Get the current application version.
@@ -151,3 +859,267 @@ pub fn restart_app(app: tauri::AppHandle) {
 pub fn get_current_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
+
+/// Updater for a channel's endpoint.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+/// * `channel` - Channel to check
+/// * `allow_downgrade` - Offer versions older than the running one
+pub fn build_updater(
+    app: &AppHandle,
+    channel: UpdateChannel,
+    allow_downgrade: bool,
+) -> Result<Updater, UpdateError> {
+    let network = app
+        .state::<SettingsManager>()
+        .snapshot()
+        .map(|settings| settings.network.clone())
+        .unwrap_or_default();
+    let mut builder = app
+        .updater_builder()
+        .version_comparator(move |current, release| {
+            channel::is_offered(&current, &release.version, allow_downgrade)
+        });
+    if let Some(proxy) = network::proxy_url(&network)
+        .map_err(|e| UpdateError::new(UpdateErrorKind::ProxyConfig, e, None, None))?
+    {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(timeout) = network::request_timeout(&network) {
+        builder = builder.timeout(timeout);
+    }
+    let endpoint = channel::endpoint_override(channel)
+        .map_err(|e| UpdateError::new(UpdateErrorKind::Unknown, e, None, None))?;
+    if let Some(endpoint) = endpoint {
+        builder = builder
+            .endpoints(vec![endpoint.clone()])
+            .map_err(|e| UpdateError::from_updater(&e, Some(endpoint.to_string())))?;
+    }
+    builder
+        .build()
+        .map_err(|e| UpdateError::from_updater(&e, update_endpoint(app, channel)))
+}
+
+/// The manifest URL checked for a channel: its override, or the first
+/// endpoint configured in `tauri.conf.json`.
+pub fn update_endpoint(app: &AppHandle, channel: UpdateChannel) -> Option<String> {
+    if let Ok(Some(endpoint)) = channel::endpoint_override(channel) {
+        return Some(endpoint.to_string());
+    }
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater["endpoints"][0].as_str())
+        .map(str::to_string)
+}
+
+/// The `updates.channel` setting.
+pub fn update_channel(app: &AppHandle) -> UpdateChannel {
+    app.state::<SettingsManager>()
+        .snapshot()
+        .map(|settings| settings.updates.channel)
+        .unwrap_or_default()
+}
+
+/// The version the user chose to skip, if any.
+fn skipped_version(app: &AppHandle) -> Option<String> {
+    app.state::<SettingsManager>()
+        .snapshot()
+        .ok()
+        .and_then(|settings| settings.updates.skipped_version.clone())
+}
+
+/// Forget the skipped version.
+async fn clear_skipped_version(app: &AppHandle) -> Result<(), String> {
+    app.state::<SettingsManager>()
+        .update(|settings| {
+            settings.updates.skipped_version = None;
+            Ok(())
+        })
+        .await?;
+    Ok(())
+}
+
+/// Payload of `update-available` from a background check.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateAvailable {
+    pub version: String,
+    /// The `updates.notify` setting, unless do not disturb is holding the
+    /// notification back; the frontend shows a system notification when set
+    pub notify: bool,
+}
+
+/// The background update check task, managed as Tauri state.
+pub struct UpdateScheduler {
+    task: Mutex<Option<JoinHandle<()>>>,
+    /// Settings the running task was started with
+    applied: Mutex<Option<UpdateSettings>>,
+    /// Kept across restarts so changing settings doesn't reset the timing
+    schedule: Arc<Mutex<CheckSchedule>>,
+    /// Outcome of the most recent check
+    last_check: Mutex<Option<LastUpdateCheck>>,
+    /// The check running now, shared by callers asking for the same one
+    in_flight: InFlight<(UpdateChannel, CheckOptions), UpdateCheckResult>,
+}
+
+impl UpdateScheduler {
+    /// Scheduler whose first check is due shortly after `now_ms`.
+    pub fn new(now_ms: i64) -> Self {
+        Self {
+            task: Mutex::new(None),
+            applied: Mutex::new(None),
+            schedule: Arc::new(Mutex::new(CheckSchedule::new(now_ms))),
+            last_check: Mutex::new(None),
+            in_flight: InFlight::default(),
+        }
+    }
+
+    /// The most recent check this session, if any.
+    pub fn last_check(&self) -> Option<LastUpdateCheck> {
+        self.last_check.lock().unwrap().clone()
+    }
+}
+
+/// Start, restart or stop background update checks to match `settings`.
+///
+/// Does nothing if the settings are unchanged since the last call (the
+/// skipped version doesn't count), or if the scheduler isn't managed yet.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri AppHandle
+/// * `settings` - The update settings to follow
+pub fn apply_schedule(app: &AppHandle, settings: &UpdateSettings) {
+    let Some(scheduler) = app.try_state::<UpdateScheduler>() else {
+        return;
+    };
+    // The skipped version is read at check time
+    let settings = &UpdateSettings {
+        skipped_version: None,
+        ..settings.clone()
+    };
+    let mut applied = scheduler.applied.lock().unwrap();
+    if applied.as_ref() == Some(settings) {
+        return;
+    }
+    *applied = Some(settings.clone());
+
+    let mut task = scheduler.task.lock().unwrap();
+    if let Some(task) = task.take() {
+        task.abort();
+    }
+    if settings.auto_check {
+        *task = Some(tauri::async_runtime::spawn(run_checks(
+            app.clone(),
+            settings.clone(),
+            Arc::clone(&scheduler.schedule),
+        )));
+    }
+}
+
+/// Stop background update checks for good, e.g. when shutting down.
+pub fn stop_schedule(app: &AppHandle) {
+    let Some(scheduler) = app.try_state::<UpdateScheduler>() else {
+        return;
+    };
+    let task = scheduler.task.lock().unwrap().take();
+    if let Some(task) = task {
+        task.abort();
+    }
+}
+
+/// Check for updates whenever the schedule says so, until aborted.
+async fn run_checks(app: AppHandle, settings: UpdateSettings, schedule: Arc<Mutex<CheckSchedule>>) {
+    loop {
+        let delay = schedule
+            .lock()
+            .unwrap()
+            .delay_until_due(settings.check_interval_hours, now_ms());
+        tokio::time::sleep(delay).await;
+        if snooze::is_snoozed(&app) {
+            tokio::time::sleep(snooze::RECHECK_INTERVAL).await;
+            continue;
+        }
+
+        let result = check(&app, settings.channel, CheckOptions::default()).await;
+        // Held back during do not disturb, and sent again once it ends
+        let notify = match &result {
+            UpdateCheckResult::Available(info) if settings.notify => {
+                let notification = HeldNotification::UpdateAvailable {
+                    version: info.version.clone(),
+                };
+                notifications::hold_if_quiet(&app, notification)
+                    .await
+                    .is_some()
+            }
+            _ => false,
+        };
+        let mut schedule = schedule.lock().unwrap();
+        match result {
+            UpdateCheckResult::Available(info) => {
+                schedule.record_success(now_ms());
+                let _ = app.emit(
+                    "update-available",
+                    UpdateAvailable {
+                        version: info.version,
+                        notify,
+                    },
+                );
+            }
+            UpdateCheckResult::UpToDate => schedule.record_success(now_ms()),
+            UpdateCheckResult::Error(e) => {
+                tracing::warn!(error = %e.detail, "Background update check failed");
+                schedule.record_failure(now_ms());
+                let _ = app.emit("update-error", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_payload_includes_channel() {
+        let check = UpdateCheck {
+            channel: UpdateChannel::Beta,
+            result: UpdateCheckResult::Available(UpdateInfo {
+                version: "1.3.0-beta.1".to_string(),
+                skipped: false,
+                staged: true,
+                notes_available: true,
+            }),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&check).unwrap(),
+            serde_json::json!({
+                "channel": "beta",
+                "status": "Available",
+                "data": {
+                    "version": "1.3.0-beta.1",
+                    "skipped": false,
+                    "staged": true,
+                    "notes_available": true
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_up_to_date_payload() {
+        let check = UpdateCheck {
+            channel: UpdateChannel::Stable,
+            result: UpdateCheckResult::UpToDate,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&check).unwrap(),
+            serde_json::json!({ "channel": "stable", "status": "UpToDate" })
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Release notes across several versions.
+//!
+//! Update manifests only carry the newest version's notes, so someone going
+//! from 1.2.0 to 1.5.0 would miss 1.3 and 1.4. The notes in between come
+//! from a release feed in the GitHub releases API format
+//! ([`DEFAULT_FEED_URL`], or `updates.release_notes_url`). The feed is
+//! fetched once per session; when it can't be fetched (offline, rate
+//! limited), the notes of the update found by the last check are used
+//! instead.
+//!
+//! Prereleases are only included when updating to a prerelease, so stable
+//! users don't see beta notes that the stable release repeats.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use super::version;
+use crate::network;
+use crate::settings::NetworkSettings;
+
+/// Release feed used when `updates.release_notes_url` isn't set.
+pub const DEFAULT_FEED_URL: &str =
+    "https://api.github.com/repos/LokeshShelva/qwik-ask/releases?per_page=100";
+
+/// How long to wait for the feed.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Notes of one release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReleaseNote {
+    pub version: String,
+    /// Publish date (RFC 3339), if known
+    pub date: Option<String>,
+    /// Markdown
+    pub body: String,
+}
+
+/// A release in the GitHub releases API format.
+#[derive(Debug, Deserialize)]
+struct FeedRelease {
+    tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    draft: bool,
+}
+
+/// Parse a release feed, skipping drafts and tags that aren't versions.
+pub fn parse_feed(json: &str) -> Result<Vec<ReleaseNote>, String> {
+    let releases: Vec<FeedRelease> =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse release feed: {}", e))?;
+    Ok(releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .filter_map(|release| {
+            let version = version::parse(&release.tag_name)?;
+            Some(ReleaseNote {
+                version: version.to_string(),
+                date: release.published_at,
+                body: release.body.unwrap_or_default(),
+            })
+        })
+        .collect())
+}
+
+/// The notes of versions after `from` up to and including `to`, newest
+/// first.
+///
+/// # Arguments
+///
+/// * `notes` - All known release notes
+/// * `from` - Running version
+/// * `to` - Version being updated to
+pub fn notes_between(notes: &[ReleaseNote], from: &str, to: &str) -> Vec<ReleaseNote> {
+    let (Some(from), Some(to)) = (version::parse(from), version::parse(to)) else {
+        return Vec::new();
+    };
+    let include_prereleases = !to.pre.is_empty();
+    let mut between: Vec<(Version, ReleaseNote)> = notes
+        .iter()
+        .filter_map(|note| Some((version::parse(&note.version)?, note.clone())))
+        .filter(|(version, _)| *version > from && *version <= to)
+        .filter(|(version, _)| include_prereleases || version.pre.is_empty())
+        .collect();
+    between.sort_by(|(a, _), (b, _)| b.cmp(a));
+    between.dedup_by(|(a, _), (b, _)| a == b);
+    between.into_iter().map(|(_, note)| note).collect()
+}
+
+/// The release feed and the last update's notes, managed as Tauri state.
+#[derive(Default)]
+pub struct ReleaseNotes {
+    /// Feed fetched this session, with the URL it came from
+    feed: Mutex<Option<(String, Vec<ReleaseNote>)>>,
+    /// Notes of the update found by the last check
+    latest: Mutex<Option<ReleaseNote>>,
+}
+
+impl ReleaseNotes {
+    /// Remember the notes of the update found by a check.
+    pub fn set_latest(&self, note: ReleaseNote) {
+        *self.latest.lock().unwrap() = Some(note);
+    }
+
+    /// Notes of the update found by the last check.
+    pub fn latest(&self) -> Option<ReleaseNote> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    /// Whether there are notes to show for updating from `from` to `to`,
+    /// without fetching anything.
+    pub fn has_notes(&self, from: &str, to: &str) -> bool {
+        let latest = self.latest.lock().unwrap();
+        if latest
+            .as_ref()
+            .is_some_and(|note| note.version == to && !note.body.trim().is_empty())
+        {
+            return true;
+        }
+        self.feed
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|(_, notes)| !notes_between(notes, from, to).is_empty())
+    }
+
+    /// The notes between two versions, newest first.
+    ///
+    /// Falls back to the last update's notes if the feed can't be fetched.
+    ///
+    /// # Arguments
+    ///
+    /// * `network` - Proxy and timeout for fetching the feed
+    /// * `feed_url` - Release feed to fetch
+    /// * `from` - Running version
+    /// * `to` - Version being updated to
+    pub async fn between(
+        &self,
+        network: &NetworkSettings,
+        feed_url: &str,
+        from: &str,
+        to: &str,
+    ) -> Vec<ReleaseNote> {
+        match self.feed(network, feed_url).await {
+            Ok(notes) => {
+                let between = notes_between(&notes, from, to);
+                if !between.is_empty() {
+                    return between;
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to load release notes"),
+        }
+        self.latest
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|note| note.version == to)
+            .cloned()
+            .collect()
+    }
+
+    /// The feed, fetched on first use.
+    async fn feed(&self, network: &NetworkSettings, url: &str) -> Result<Vec<ReleaseNote>, String> {
+        if let Some((cached_url, notes)) = self.feed.lock().unwrap().as_ref() {
+            if cached_url == url {
+                return Ok(notes.clone());
+            }
+        }
+
+        let response = network::http_client(network)?
+            .get(url)
+            .timeout(FETCH_TIMEOUT)
+            .header(
+                reqwest::header::USER_AGENT,
+                concat!("qwik-ask/", env!("CARGO_PKG_VERSION")),
+            )
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch release notes: {}", e))?;
+        if !response.status().is_success() {
+            // 403/429 when rate limited
+            return Err(format!(
+                "Failed to fetch release notes: HTTP {}",
+                response.status()
+            ));
+        }
+        let json = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to fetch release notes: {}", e))?;
+        let notes = parse_feed(&json)?;
+
+        *self.feed.lock().unwrap() = Some((url.to_string(), notes.clone()));
+        Ok(notes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(version: &str) -> ReleaseNote {
+        ReleaseNote {
+            version: version.to_string(),
+            date: None,
+            body: format!("Notes for {}", version),
+        }
+    }
+
+    fn versions(notes: &[ReleaseNote]) -> Vec<&str> {
+        notes.iter().map(|note| note.version.as_str()).collect()
+    }
+
+    // ===== Version ranges =====
+
+    #[test]
+    fn test_range_excludes_from_and_includes_to() {
+        let notes = [note("1.2.0"), note("1.3.0"), note("1.4.0"), note("1.5.0")];
+
+        let between = notes_between(&notes, "1.2.0", "1.5.0");
+
+        assert_eq!(versions(&between), ["1.5.0", "1.4.0", "1.3.0"]);
+    }
+
+    #[test]
+    fn test_range_ignores_newer_and_older() {
+        let notes = [note("1.1.0"), note("1.3.0"), note("1.6.0")];
+
+        let between = notes_between(&notes, "1.2.0", "1.5.0");
+
+        assert_eq!(versions(&between), ["1.3.0"]);
+    }
+
+    #[test]
+    fn test_range_is_semver_not_string_order() {
+        let notes = [note("1.9.0"), note("1.10.0"), note("1.11.0")];
+
+        let between = notes_between(&notes, "1.9.0", "1.11.0");
+
+        assert_eq!(versions(&between), ["1.11.0", "1.10.0"]);
+    }
+
+    #[test]
+    fn test_prereleases_skipped_for_stable_target() {
+        let notes = [note("1.3.0-beta.1"), note("1.3.0-rc.1"), note("1.3.0")];
+
+        let between = notes_between(&notes, "1.2.0", "1.3.0");
+
+        assert_eq!(versions(&between), ["1.3.0"]);
+    }
+
+    #[test]
+    fn test_prerelease_ordering_for_beta_target() {
+        let notes = [
+            note("1.3.0-beta.10"),
+            note("1.3.0-beta.2"),
+            note("1.3.0-alpha.1"),
+            note("1.3.0-rc.1"),
+        ];
+
+        let between = notes_between(&notes, "1.3.0-beta.2", "1.3.0-rc.1");
+
+        assert_eq!(versions(&between), ["1.3.0-rc.1", "1.3.0-beta.10"]);
+    }
+
+    #[test]
+    fn test_invalid_bounds_give_nothing() {
+        let notes = [note("1.3.0")];
+
+        assert!(notes_between(&notes, "nightly", "1.3.0").is_empty());
+        assert!(notes_between(&notes, "1.2.0", "").is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_versions_listed_once() {
+        let notes = [note("1.3.0"), note("v1.3.0")];
+
+        assert_eq!(notes_between(&notes, "1.2.0", "1.3.0").len(), 1);
+    }
+
+    // ===== Feed parsing =====
+
+    #[test]
+    fn test_parse_github_feed() {
+        let json = r#"[
+            {"tag_name": "v1.3.0", "body": "Fixes", "published_at": "2025-03-01T10:00:00Z", "draft": false, "prerelease": false},
+            {"tag_name": "v1.4.0", "body": null, "published_at": null, "draft": true},
+            {"tag_name": "nightly", "body": "Nightly build"}
+        ]"#;
+
+        let notes = parse_feed(json).unwrap();
+
+        assert_eq!(
+            notes,
+            [ReleaseNote {
+                version: "1.3.0".to_string(),
+                date: Some("2025-03-01T10:00:00Z".to_string()),
+                body: "Fixes".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_feed_rejects_error_payload() {
+        // What GitHub returns when rate limited
+        let json = r#"{"message": "API rate limit exceeded"}"#;
+
+        assert!(parse_feed(json).is_err());
+    }
+
+    // ===== Fallback =====
+
+    #[test]
+    fn test_has_notes_from_latest_update() {
+        let notes = ReleaseNotes::default();
+        assert!(!notes.has_notes("1.2.0", "1.5.0"));
+
+        notes.set_latest(note("1.5.0"));
+
+        assert!(notes.has_notes("1.2.0", "1.5.0"));
+        assert!(!notes.has_notes("1.2.0", "1.6.0"));
+    }
+}
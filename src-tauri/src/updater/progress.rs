@@ -0,0 +1,243 @@
+//! Update download progress.
+//!
+//! [`DownloadMeter`] turns the updater's chunk callbacks into
+//! [`DownloadProgress`] reports with a transfer speed averaged over the last
+//! [`SPEED_WINDOW`] (single chunks arrive too irregularly to be useful) and
+//! an ETA. Reports are throttled to one per [`REPORT_INTERVAL`], plus one
+//! when the download completes.
+//!
+//! Times are passed in as the time elapsed since the download started, so
+//! the calculations can be tested without a clock.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Span the transfer speed is averaged over.
+pub const SPEED_WINDOW: Duration = Duration::from_secs(3);
+
+/// Minimum time between reports.
+pub const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Payload of the `update-download-stats` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DownloadProgress {
+    pub downloaded_bytes: u64,
+    /// Size of the package, if the server sent it
+    pub total_bytes: Option<u64>,
+    /// 0-100, if the size is known
+    pub percent: Option<u8>,
+    /// Average over the last few seconds
+    pub bytes_per_sec: u64,
+    /// Seconds left at the current speed, if the size is known and data is
+    /// coming in
+    pub eta_secs: Option<u64>,
+}
+
+/// Tracks a download for progress reports.
+#[derive(Debug)]
+pub struct DownloadMeter {
+    downloaded: u64,
+    total: Option<u64>,
+    /// `(elapsed, downloaded)` samples covering the speed window
+    samples: VecDeque<(Duration, u64)>,
+    last_report: Option<Duration>,
+}
+
+impl Default for DownloadMeter {
+    fn default() -> Self {
+        Self {
+            downloaded: 0,
+            total: None,
+            samples: VecDeque::from([(Duration::ZERO, 0)]),
+            last_report: None,
+        }
+    }
+}
+
+impl DownloadMeter {
+    /// Record a downloaded chunk.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_length` - Bytes in the chunk
+    /// * `content_length` - Package size, as reported by the updater
+    /// * `elapsed` - Time since the download started
+    ///
+    /// # Returns
+    ///
+    /// A report, unless one was made less than [`REPORT_INTERVAL`] ago and
+    /// the download isn't complete.
+    pub fn record(
+        &mut self,
+        chunk_length: usize,
+        content_length: Option<u64>,
+        elapsed: Duration,
+    ) -> Option<DownloadProgress> {
+        self.downloaded += chunk_length as u64;
+        self.total = content_length.or(self.total);
+        self.samples.push_back((elapsed, self.downloaded));
+        // Keep one sample at or before the window start as the baseline
+        while self.samples.len() > 2 && elapsed.saturating_sub(self.samples[1].0) >= SPEED_WINDOW {
+            self.samples.pop_front();
+        }
+
+        let complete = self.total.is_some_and(|total| self.downloaded >= total);
+        let due = self
+            .last_report
+            .is_none_or(|last| elapsed.saturating_sub(last) >= REPORT_INTERVAL);
+        if !due && !complete {
+            return None;
+        }
+        self.last_report = Some(elapsed);
+        Some(self.progress())
+    }
+
+    /// The current progress.
+    pub fn progress(&self) -> DownloadProgress {
+        let bytes_per_sec = self.bytes_per_sec();
+        let percent = self
+            .total
+            .filter(|total| *total > 0)
+            .map(|total| (self.downloaded.min(total) * 100 / total) as u8);
+        let eta_secs = self.total.filter(|_| bytes_per_sec > 0).map(|total| {
+            total
+                .saturating_sub(self.downloaded)
+                .div_ceil(bytes_per_sec)
+        });
+        DownloadProgress {
+            downloaded_bytes: self.downloaded,
+            total_bytes: self.total,
+            percent,
+            bytes_per_sec,
+            eta_secs,
+        }
+    }
+
+    /// Average speed over the samples in the window.
+    fn bytes_per_sec(&self) -> u64 {
+        let (Some(&(start, from)), Some(&(end, to))) = (self.samples.front(), self.samples.back())
+        else {
+            return 0;
+        };
+        let span = end.saturating_sub(start).as_secs_f64();
+        if span <= 0.0 {
+            return 0;
+        }
+        ((to - from) as f64 / span) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: usize = 1_000_000;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    // ===== Speed and ETA =====
+
+    #[test]
+    fn test_steady_download() {
+        let mut meter = DownloadMeter::default();
+
+        for second in 1..=4 {
+            meter.record(MB, Some(10 * MB as u64), ms(second * 1000));
+        }
+
+        let progress = meter.progress();
+        assert_eq!(progress.downloaded_bytes, 4 * MB as u64);
+        assert_eq!(progress.percent, Some(40));
+        assert_eq!(progress.bytes_per_sec, MB as u64);
+        assert_eq!(progress.eta_secs, Some(6));
+    }
+
+    #[test]
+    fn test_speed_follows_recent_chunks() {
+        let mut meter = DownloadMeter::default();
+        // Fast start, then a slowdown lasting longer than the window
+        for second in 1..=2 {
+            meter.record(4 * MB, None, ms(second * 1000));
+        }
+        for second in 3..=8 {
+            meter.record(MB, None, ms(second * 1000));
+        }
+
+        assert_eq!(meter.progress().bytes_per_sec, MB as u64);
+    }
+
+    #[test]
+    fn test_bursty_chunks_are_averaged() {
+        let mut meter = DownloadMeter::default();
+        // 3 MB every 1.5 seconds
+        for i in 1..=4 {
+            meter.record(3 * MB, None, ms(i * 1500));
+        }
+
+        assert_eq!(meter.progress().bytes_per_sec, 2 * MB as u64);
+    }
+
+    #[test]
+    fn test_eta_rounds_up() {
+        let mut meter = DownloadMeter::default();
+
+        meter.record(MB, Some(2 * MB as u64 + 1), ms(1000));
+
+        assert_eq!(meter.progress().eta_secs, Some(2));
+    }
+
+    #[test]
+    fn test_unknown_size() {
+        let mut meter = DownloadMeter::default();
+
+        meter.record(MB, None, ms(1000));
+
+        let progress = meter.progress();
+        assert_eq!(progress.total_bytes, None);
+        assert_eq!(progress.percent, None);
+        assert_eq!(progress.eta_secs, None);
+        assert_eq!(progress.bytes_per_sec, MB as u64);
+    }
+
+    #[test]
+    fn test_no_speed_without_elapsed_time() {
+        let mut meter = DownloadMeter::default();
+
+        meter.record(MB, Some(2 * MB as u64), Duration::ZERO);
+
+        let progress = meter.progress();
+        assert_eq!(progress.bytes_per_sec, 0);
+        assert_eq!(progress.eta_secs, None);
+    }
+
+    // ===== Throttling =====
+
+    #[test]
+    fn test_reports_are_throttled() {
+        let mut meter = DownloadMeter::default();
+        let total = Some(100 * MB as u64);
+
+        let reports = (1..=100)
+            .filter_map(|i| meter.record(1000, total, ms(i * 10)))
+            .count();
+
+        // Every 250 ms over one second, starting with the first chunk
+        assert_eq!(reports, 4);
+    }
+
+    #[test]
+    fn test_completion_is_always_reported() {
+        let mut meter = DownloadMeter::default();
+        let total = Some(2 * MB as u64);
+
+        assert!(meter.record(MB, total, ms(10)).is_some());
+        let last = meter.record(MB, total, ms(20)).unwrap();
+
+        assert_eq!(last.percent, Some(100));
+        assert_eq!(last.eta_secs, Some(0));
+    }
+}
@@ -0,0 +1,164 @@
+//! Background update check scheduling.
+//!
+//! The first check runs [`FIRST_CHECK_DELAY`] after startup so it doesn't
+//! compete with the app starting up; later checks follow
+//! `updates.check_interval_hours`. Failed checks (usually no network) are
+//! retried sooner, backing off from [`RETRY_BASE`] and doubling up to the
+//! regular interval.
+//!
+//! Times are passed in as Unix milliseconds so the decisions can be tested
+//! without a clock.
+
+use std::time::Duration;
+
+/// Delay between startup and the first check.
+pub const FIRST_CHECK_DELAY: Duration = Duration::from_secs(2 * 60);
+
+/// Delay before retrying after the first failed check.
+pub const RETRY_BASE: Duration = Duration::from_secs(5 * 60);
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+
+/// When background update checks happen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckSchedule {
+    /// When the scheduler started (Unix ms)
+    started_ms: i64,
+    /// When the last check finished (Unix ms)
+    last_check_ms: Option<i64>,
+    /// Failed checks in a row
+    failures: u32,
+}
+
+impl CheckSchedule {
+    /// Schedule starting at `now_ms`.
+    pub fn new(now_ms: i64) -> Self {
+        Self {
+            started_ms: now_ms,
+            last_check_ms: None,
+            failures: 0,
+        }
+    }
+
+    /// When the next check is due (Unix ms).
+    ///
+    /// # Arguments
+    ///
+    /// * `interval_hours` - `updates.check_interval_hours`; `0` is treated
+    ///   as 1
+    pub fn next_check_ms(&self, interval_hours: u32) -> i64 {
+        let interval_ms = interval_hours.max(1) as i64 * HOUR_MS;
+        match self.last_check_ms {
+            None => self.started_ms + FIRST_CHECK_DELAY.as_millis() as i64,
+            Some(last) if self.failures > 0 => {
+                let shift = (self.failures - 1).min(16);
+                let backoff_ms = (RETRY_BASE.as_millis() as i64) << shift;
+                last + backoff_ms.min(interval_ms)
+            }
+            Some(last) => last + interval_ms,
+        }
+    }
+
+    /// How long to wait from `now_ms` until the next check.
+    pub fn delay_until_due(&self, interval_hours: u32, now_ms: i64) -> Duration {
+        let wait_ms = self.next_check_ms(interval_hours) - now_ms;
+        Duration::from_millis(wait_ms.max(0) as u64)
+    }
+
+    /// Record a check that reached the update server.
+    pub fn record_success(&mut self, now_ms: i64) {
+        self.last_check_ms = Some(now_ms);
+        self.failures = 0;
+    }
+
+    /// Record a check that failed.
+    pub fn record_failure(&mut self, now_ms: i64) {
+        self.last_check_ms = Some(now_ms);
+        self.failures = self.failures.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: i64 = 1_700_000_000_000;
+    const MINUTE_MS: i64 = 60 * 1000;
+
+    #[test]
+    fn test_first_check_is_delayed() {
+        let schedule = CheckSchedule::new(START);
+
+        assert_eq!(schedule.next_check_ms(24), START + 2 * MINUTE_MS);
+        assert_eq!(
+            schedule.delay_until_due(24, START + MINUTE_MS),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_checks_follow_interval() {
+        let mut schedule = CheckSchedule::new(START);
+
+        schedule.record_success(START + 2 * MINUTE_MS);
+
+        assert_eq!(
+            schedule.next_check_ms(6),
+            START + 2 * MINUTE_MS + 6 * HOUR_MS
+        );
+    }
+
+    #[test]
+    fn test_failures_back_off() {
+        let mut schedule = CheckSchedule::new(START);
+
+        schedule.record_failure(START);
+        assert_eq!(schedule.next_check_ms(24), START + 5 * MINUTE_MS);
+
+        schedule.record_failure(START);
+        assert_eq!(schedule.next_check_ms(24), START + 10 * MINUTE_MS);
+
+        schedule.record_failure(START);
+        assert_eq!(schedule.next_check_ms(24), START + 20 * MINUTE_MS);
+    }
+
+    #[test]
+    fn test_backoff_capped_at_interval() {
+        let mut schedule = CheckSchedule::new(START);
+
+        for _ in 0..40 {
+            schedule.record_failure(START);
+        }
+
+        assert_eq!(schedule.next_check_ms(1), START + HOUR_MS);
+    }
+
+    #[test]
+    fn test_success_resets_backoff() {
+        let mut schedule = CheckSchedule::new(START);
+        schedule.record_failure(START);
+        schedule.record_failure(START);
+
+        schedule.record_success(START);
+
+        assert_eq!(schedule.next_check_ms(24), START + 24 * HOUR_MS);
+    }
+
+    #[test]
+    fn test_zero_interval_treated_as_one_hour() {
+        let mut schedule = CheckSchedule::new(START);
+        schedule.record_success(START);
+
+        assert_eq!(schedule.next_check_ms(0), START + HOUR_MS);
+    }
+
+    #[test]
+    fn test_overdue_check_runs_immediately() {
+        let schedule = CheckSchedule::new(START);
+
+        assert_eq!(
+            schedule.delay_until_due(24, START + HOUR_MS),
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! Staged updates.
+//!
+//! An update can be downloaded ahead of time and installed later, either
+//! on request or when the app quits. The downloaded package is written to
+//! `{app_data_dir}/updates/` and described by a [`StagedUpdate`] marker,
+//! which is persisted in the settings store so the stage survives restarts.
+//!
+//! Installing needs the updater's handle for the release as well as the
+//! bytes. The handle only lives in memory, so after a restart the stage
+//! waits for a check to confirm the staged version is still current before
+//! it can be installed:
+//!
+//! - the same version is still offered: the stage is ready
+//! - a newer version is offered: the stage is superseded and discarded
+//! - nothing (or an older version) is offered, or the app is already at the
+//!   staged version: the stage is stale and discarded
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::version::{self, SkipStatus};
+
+/// Settings store key of the [`StagedUpdate`] marker.
+pub const STAGED_UPDATE_KEY: &str = "staged_update";
+
+/// Directory under the app data dir holding downloaded packages.
+pub const STAGING_DIR: &str = "updates";
+
+/// Extension of packages still being written.
+pub const PARTIAL_EXTENSION: &str = "part";
+
+/// A downloaded update waiting to be installed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StagedUpdate {
+    /// Version of the downloaded package
+    pub version: String,
+    /// Where the package was written
+    pub path: PathBuf,
+}
+
+/// File name of the package for `version`.
+pub fn package_file_name(version: &str) -> String {
+    let version: String = version
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("qwik-ask-{}.update", version)
+}
+
+/// Delete packages left half-written in `dir`.
+pub fn remove_partial_packages(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().is_some_and(|ext| ext == PARTIAL_EXTENSION) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// The staged update and, once confirmed by a check, the updater's handle
+/// to install it with.
+#[derive(Debug)]
+pub struct Stage<U> {
+    staged: Option<(StagedUpdate, Option<U>)>,
+}
+
+impl<U> Default for Stage<U> {
+    fn default() -> Self {
+        Self { staged: None }
+    }
+}
+
+impl<U> Stage<U> {
+    /// Stage restored from the settings store, waiting for a check.
+    pub fn restore(marker: Option<StagedUpdate>) -> Self {
+        Self {
+            staged: marker.map(|marker| (marker, None)),
+        }
+    }
+
+    /// The staged update, if any.
+    pub fn staged(&self) -> Option<&StagedUpdate> {
+        self.staged.as_ref().map(|(marker, _)| marker)
+    }
+
+    /// Whether `version` is staged and can be installed now.
+    pub fn is_ready(&self, version: &str) -> bool {
+        matches!(&self.staged, Some((marker, Some(_))) if marker.version == version)
+    }
+
+    /// Stage a freshly downloaded update.
+    ///
+    /// # Returns
+    ///
+    /// The previously staged update if it was a different package, whose
+    /// file should be deleted.
+    pub fn set(&mut self, marker: StagedUpdate, update: U) -> Option<StagedUpdate> {
+        let previous = self.staged.replace((marker.clone(), Some(update)));
+        previous
+            .map(|(previous, _)| previous)
+            .filter(|previous| previous.path != marker.path)
+    }
+
+    /// Bring the stage in line with the result of an update check.
+    ///
+    /// # Arguments
+    ///
+    /// * `available` - Version offered by the check and its handle, if any
+    /// * `current` - Running app version
+    ///
+    /// # Returns
+    ///
+    /// The discarded stage, whose file should be deleted.
+    pub fn reconcile(
+        &mut self,
+        available: Option<(&str, U)>,
+        current: &str,
+    ) -> Option<StagedUpdate> {
+        let (marker, handle) = self.staged.as_mut()?;
+        let installed =
+            version::skip_status(current, Some(&marker.version)) != SkipStatus::NotSkipped;
+        match available {
+            Some((version, update))
+                if !installed
+                    && version::skip_status(version, Some(&marker.version))
+                        == SkipStatus::Skipped =>
+            {
+                *handle = Some(update);
+                None
+            }
+            _ => self.clear(),
+        }
+    }
+
+    /// Take the staged update for installing, if it is ready.
+    pub fn take_ready(&mut self) -> Option<(StagedUpdate, U)> {
+        match self.staged.take() {
+            Some((marker, Some(update))) => Some((marker, update)),
+            waiting => {
+                self.staged = waiting;
+                None
+            }
+        }
+    }
+
+    /// Forget the staged update.
+    pub fn clear(&mut self) -> Option<StagedUpdate> {
+        self.staged.take().map(|(marker, _)| marker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(version: &str) -> StagedUpdate {
+        StagedUpdate {
+            version: version.to_string(),
+            path: PathBuf::from(package_file_name(version)),
+        }
+    }
+
+    // ===== Staging =====
+
+    #[test]
+    fn test_downloaded_update_is_ready() {
+        let mut stage = Stage::default();
+
+        assert_eq!(stage.set(marker("1.3.0"), "handle"), None);
+
+        assert!(stage.is_ready("1.3.0"));
+        assert!(!stage.is_ready("1.4.0"));
+        assert_eq!(stage.take_ready(), Some((marker("1.3.0"), "handle")));
+        assert_eq!(stage.staged(), None);
+    }
+
+    #[test]
+    fn test_restaging_returns_previous_package() {
+        let mut stage = Stage::default();
+        stage.set(marker("1.3.0"), "old");
+
+        assert_eq!(stage.set(marker("1.3.0"), "again"), None);
+        assert_eq!(stage.set(marker("1.4.0"), "new"), Some(marker("1.3.0")));
+        assert_eq!(stage.staged(), Some(&marker("1.4.0")));
+    }
+
+    #[test]
+    fn test_remove_partial_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let staged = dir.path().join(package_file_name("1.3.0"));
+        let partial = staged.with_extension(PARTIAL_EXTENSION);
+        std::fs::write(&staged, b"package").unwrap();
+        std::fs::write(&partial, b"pack").unwrap();
+
+        remove_partial_packages(dir.path());
+
+        assert!(staged.exists());
+        assert!(!partial.exists());
+    }
+
+    #[test]
+    fn test_package_file_name() {
+        assert_eq!(
+            package_file_name("1.3.0-beta.1"),
+            "qwik-ask-1.3.0-beta.1.update"
+        );
+        assert_eq!(package_file_name("../1.3.0"), "qwik-ask-.._1.3.0.update");
+    }
+
+    // ===== Restored stages =====
+
+    #[test]
+    fn test_restored_stage_waits_for_check() {
+        let mut stage = Stage::<&str>::restore(Some(marker("1.3.0")));
+
+        assert!(!stage.is_ready("1.3.0"));
+        assert_eq!(stage.take_ready(), None);
+        assert_eq!(stage.staged(), Some(&marker("1.3.0")));
+    }
+
+    #[test]
+    fn test_check_confirms_restored_stage() {
+        let mut stage = Stage::restore(Some(marker("1.3.0")));
+
+        assert_eq!(stage.reconcile(Some(("1.3.0", "handle")), "1.2.0"), None);
+
+        assert!(stage.is_ready("1.3.0"));
+    }
+
+    // ===== Reconciling =====
+
+    #[test]
+    fn test_newer_version_supersedes_stage() {
+        let mut stage = Stage::default();
+        stage.set(marker("1.3.0"), "old");
+
+        assert_eq!(
+            stage.reconcile(Some(("1.3.1", "new")), "1.2.0"),
+            Some(marker("1.3.0"))
+        );
+        assert_eq!(stage.staged(), None);
+    }
+
+    #[test]
+    fn test_no_update_makes_stage_stale() {
+        let mut stage = Stage::<&str>::restore(Some(marker("1.3.0")));
+
+        assert_eq!(stage.reconcile(None, "1.2.0"), Some(marker("1.3.0")));
+    }
+
+    #[test]
+    fn test_installed_stage_is_stale() {
+        let mut stage = Stage::restore(Some(marker("1.3.0")));
+
+        assert_eq!(
+            stage.reconcile(Some(("1.3.0", "handle")), "1.3.0"),
+            Some(marker("1.3.0"))
+        );
+    }
+
+    #[test]
+    fn test_older_offer_makes_stage_stale() {
+        // Switched channels after staging a beta
+        let mut stage = Stage::default();
+        stage.set(marker("1.3.0-beta.1"), "beta");
+
+        assert_eq!(
+            stage.reconcile(Some(("1.2.0", "stable")), "1.2.0-beta.3"),
+            Some(marker("1.3.0-beta.1"))
+        );
+    }
+
+    #[test]
+    fn test_reconcile_without_stage_does_nothing() {
+        let mut stage = Stage::default();
+
+        assert_eq!(stage.reconcile(Some(("1.3.0", "handle")), "1.2.0"), None);
+        assert_eq!(stage.staged(), None);
+    }
+}
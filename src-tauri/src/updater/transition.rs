@@ -0,0 +1,219 @@
+//! First run and first run after an update.
+//!
+//! The version seen at each launch is kept in the settings store. A launch
+//! with a different version records a pending [`VersionTransition`], which
+//! the frontend reads to show release notes (or onboarding, on a fresh
+//! install) once, then acknowledges. If it's never acknowledged and the app
+//! updates again, the transition keeps the older `from` so no notes are
+//! missed.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::version;
+use crate::settings::SettingsManager;
+
+/// Settings store key of the version seen at the last launch.
+pub const LAST_SEEN_VERSION_KEY: &str = "last_seen_version";
+
+/// Settings store key of the unacknowledged [`VersionTransition`].
+pub const PENDING_TRANSITION_KEY: &str = "pending_version_transition";
+
+/// A change of app version between launches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionTransition {
+    /// Version last seen; `None` on a fresh install
+    pub from: Option<String>,
+    /// Running version
+    pub to: String,
+}
+
+impl VersionTransition {
+    /// Whether this is the first launch of a fresh install.
+    pub fn is_first_run(&self) -> bool {
+        self.from.is_none()
+    }
+
+    /// Whether an older version was installed over a newer one.
+    pub fn is_downgrade(&self) -> bool {
+        let from = self.from.as_deref().and_then(version::parse);
+        matches!((from, version::parse(&self.to)), (Some(from), Some(to)) if to < from)
+    }
+}
+
+/// Where launch versions are kept; the settings store in the app.
+pub trait StateStore {
+    fn load_state<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String>;
+    fn save_state<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String>;
+}
+
+impl StateStore for SettingsManager {
+    fn load_state<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        SettingsManager::load_state(self, key)
+    }
+
+    fn save_state<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String> {
+        SettingsManager::save_state(self, key, value)
+    }
+}
+
+/// Record the running version at startup.
+///
+/// # Arguments
+///
+/// * `store` - Where launch versions are kept
+/// * `current` - Running app version
+///
+/// # Returns
+///
+/// * `Ok(Some(VersionTransition))` - The version changed (or this is the
+///   first run); the transition is now pending
+/// * `Ok(None)` - Same version as last time
+/// * `Err(String)` - The store couldn't be read or written
+pub fn record_launch(
+    store: &impl StateStore,
+    current: &str,
+) -> Result<Option<VersionTransition>, String> {
+    let last_seen: Option<String> = store.load_state(LAST_SEEN_VERSION_KEY)?;
+    if last_seen.as_deref() == Some(current) {
+        return Ok(None);
+    }
+    store.save_state(LAST_SEEN_VERSION_KEY, &current)?;
+
+    // An unacknowledged transition keeps its starting point
+    let from = match pending(store)? {
+        Some(pending) => pending.from,
+        None => last_seen,
+    };
+    if from.as_deref() == Some(current) {
+        acknowledge(store)?;
+        return Ok(None);
+    }
+    let transition = VersionTransition {
+        from,
+        to: current.to_string(),
+    };
+    store.save_state(PENDING_TRANSITION_KEY, &Some(&transition))?;
+    Ok(Some(transition))
+}
+
+/// The unacknowledged transition, if any.
+pub fn pending(store: &impl StateStore) -> Result<Option<VersionTransition>, String> {
+    Ok(store
+        .load_state::<Option<VersionTransition>>(PENDING_TRANSITION_KEY)?
+        .flatten())
+}
+
+/// Clear the pending transition once the frontend has shown it.
+pub fn acknowledge(store: &impl StateStore) -> Result<(), String> {
+    store.save_state(PENDING_TRANSITION_KEY, &None::<VersionTransition>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// In-memory stand-in for the settings store.
+    #[derive(Default)]
+    struct TempStore(RefCell<HashMap<String, Value>>);
+
+    impl StateStore for TempStore {
+        fn load_state<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+            Ok(self
+                .0
+                .borrow()
+                .get(key)
+                .and_then(|value| serde_json::from_value(value.clone()).ok()))
+        }
+
+        fn save_state<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String> {
+            let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+            self.0.borrow_mut().insert(key.to_string(), value);
+            Ok(())
+        }
+    }
+
+    fn transition(from: Option<&str>, to: &str) -> VersionTransition {
+        VersionTransition {
+            from: from.map(str::to_string),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_fresh_install_is_first_run() {
+        let store = TempStore::default();
+
+        let launch = record_launch(&store, "1.2.0").unwrap().unwrap();
+
+        assert!(launch.is_first_run());
+        assert_eq!(pending(&store).unwrap(), Some(transition(None, "1.2.0")));
+    }
+
+    #[test]
+    fn test_same_version_reports_nothing() {
+        let store = TempStore::default();
+        record_launch(&store, "1.2.0").unwrap();
+        acknowledge(&store).unwrap();
+
+        assert_eq!(record_launch(&store, "1.2.0").unwrap(), None);
+        assert_eq!(pending(&store).unwrap(), None);
+    }
+
+    #[test]
+    fn test_upgrade_is_reported_until_acknowledged() {
+        let store = TempStore::default();
+        record_launch(&store, "1.2.0").unwrap();
+        acknowledge(&store).unwrap();
+
+        let launch = record_launch(&store, "1.3.0").unwrap().unwrap();
+
+        assert_eq!(launch, transition(Some("1.2.0"), "1.3.0"));
+        assert!(!launch.is_first_run());
+        assert!(!launch.is_downgrade());
+        // Still pending on the next launch
+        assert_eq!(record_launch(&store, "1.3.0").unwrap(), None);
+        assert_eq!(pending(&store).unwrap(), Some(launch));
+
+        acknowledge(&store).unwrap();
+        assert_eq!(pending(&store).unwrap(), None);
+    }
+
+    #[test]
+    fn test_downgrade_is_reported() {
+        let store = TempStore::default();
+        record_launch(&store, "1.3.0").unwrap();
+        acknowledge(&store).unwrap();
+
+        let launch = record_launch(&store, "1.2.0").unwrap().unwrap();
+
+        assert_eq!(launch, transition(Some("1.3.0"), "1.2.0"));
+        assert!(launch.is_downgrade());
+    }
+
+    #[test]
+    fn test_unacknowledged_updates_keep_start() {
+        let store = TempStore::default();
+        record_launch(&store, "1.2.0").unwrap();
+        acknowledge(&store).unwrap();
+        record_launch(&store, "1.3.0").unwrap();
+
+        let launch = record_launch(&store, "1.4.0").unwrap().unwrap();
+
+        assert_eq!(launch, transition(Some("1.2.0"), "1.4.0"));
+    }
+
+    #[test]
+    fn test_returning_to_start_clears_pending() {
+        let store = TempStore::default();
+        record_launch(&store, "1.2.0").unwrap();
+        acknowledge(&store).unwrap();
+        record_launch(&store, "1.3.0").unwrap();
+
+        assert_eq!(record_launch(&store, "1.2.0").unwrap(), None);
+        assert_eq!(pending(&store).unwrap(), None);
+    }
+}
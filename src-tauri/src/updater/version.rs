@@ -0,0 +1,101 @@
+//! Skipped update versions.
+//!
+//! Versions are compared as semver, so `1.2.0` supersedes a skipped
+//! `1.2.0-beta.2`. Versions that don't parse only match themselves.
+
+use semver::Version;
+
+/// How an available update relates to the skipped version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipStatus {
+    /// Nothing is skipped, or an older version than the skipped one
+    NotSkipped,
+    /// The available version is the skipped one
+    Skipped,
+    /// The available version is newer; the skip no longer applies and can
+    /// be cleared
+    Superseded,
+}
+
+/// Compare an available update with the skipped version.
+///
+/// # Arguments
+///
+/// * `available` - Version offered by the update server
+/// * `skipped` - `updates.skipped_version`
+pub fn skip_status(available: &str, skipped: Option<&str>) -> SkipStatus {
+    let Some(skipped) = skipped else {
+        return SkipStatus::NotSkipped;
+    };
+    match (parse(available), parse(skipped)) {
+        (Some(available), Some(skipped)) if available == skipped => SkipStatus::Skipped,
+        (Some(available), Some(skipped)) if available > skipped => SkipStatus::Superseded,
+        (Some(_), Some(_)) => SkipStatus::NotSkipped,
+        _ if available.trim() == skipped.trim() => SkipStatus::Skipped,
+        _ => SkipStatus::NotSkipped,
+    }
+}
+
+/// Parse a version, tolerating a leading `v`.
+pub fn parse(version: &str) -> Option<Version> {
+    let version = version.trim();
+    Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nothing_skipped() {
+        assert_eq!(skip_status("1.2.0", None), SkipStatus::NotSkipped);
+    }
+
+    #[test]
+    fn test_same_version_is_skipped() {
+        assert_eq!(skip_status("1.2.0", Some("1.2.0")), SkipStatus::Skipped);
+        assert_eq!(skip_status("v1.2.0", Some("1.2.0")), SkipStatus::Skipped);
+        assert_eq!(
+            skip_status("1.2.0-beta.1", Some("1.2.0-beta.1")),
+            SkipStatus::Skipped
+        );
+    }
+
+    #[test]
+    fn test_newer_version_supersedes_skip() {
+        assert_eq!(skip_status("1.2.1", Some("1.2.0")), SkipStatus::Superseded);
+        assert_eq!(skip_status("2.0.0", Some("1.9.9")), SkipStatus::Superseded);
+    }
+
+    #[test]
+    fn test_prerelease_ordering() {
+        // A release supersedes its prereleases
+        assert_eq!(
+            skip_status("1.2.0", Some("1.2.0-beta.2")),
+            SkipStatus::Superseded
+        );
+        assert_eq!(
+            skip_status("1.2.0-beta.10", Some("1.2.0-beta.2")),
+            SkipStatus::Superseded
+        );
+        // A prerelease of the skipped version is older
+        assert_eq!(
+            skip_status("1.2.0-rc.1", Some("1.2.0")),
+            SkipStatus::NotSkipped
+        );
+    }
+
+    #[test]
+    fn test_older_version_keeps_skip() {
+        assert_eq!(skip_status("1.1.0", Some("1.2.0")), SkipStatus::NotSkipped);
+    }
+
+    #[test]
+    fn test_unparseable_versions_match_exactly() {
+        assert_eq!(skip_status("nightly", Some("nightly")), SkipStatus::Skipped);
+        assert_eq!(
+            skip_status("nightly", Some("1.2.0")),
+            SkipStatus::NotSkipped
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Detached conversation windows.
+//!
+//! A conversation can be opened in its own window, labeled
+//! `conv-<conversation id>`, that stays put while the launcher comes and
+//! goes. Labels are the only bookkeeping: the open detached windows are the
+//! app's windows whose label has the prefix.
+
+/// Label prefix of detached conversation windows.
+pub const DETACHED_PREFIX: &str = "conv-";
+
+/// Label of the detached window for a conversation.
+///
+/// # Arguments
+///
+/// * `conversation_id` - Conversation ID (a UUID)
+///
+/// # Returns
+///
+/// * `Ok(String)` - The window label
+/// * `Err(String)` - The ID has characters not allowed in a label
+pub fn detached_label(conversation_id: &str) -> Result<String, String> {
+    let valid = !conversation_id.is_empty()
+        && conversation_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if !valid {
+        return Err(format!("Invalid conversation ID: '{}'", conversation_id));
+    }
+    Ok(format!("{}{}", DETACHED_PREFIX, conversation_id))
+}
+
+/// Whether `label` belongs to a detached conversation window.
+pub fn is_detached(label: &str) -> bool {
+    label
+        .strip_prefix(DETACHED_PREFIX)
+        .is_some_and(|id| !id.is_empty())
+}
+
+/// Frontend route showing a single conversation.
+pub fn conversation_route(conversation_id: &str) -> String {
+    format!("conversation/{}", conversation_id)
+}
+
+/// The detached window labels among `labels`, sorted.
+pub fn detached_labels<'a>(labels: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut detached: Vec<String> = labels
+        .into_iter()
+        .filter(|label| is_detached(label))
+        .map(str::to_string)
+        .collect();
+    detached.sort();
+    detached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "01920d4e-7b3a-7c41-9a55-3f0e8b1c2d4e";
+
+    #[test]
+    fn test_label_round_trip() {
+        let label = detached_label(ID).unwrap();
+
+        assert_eq!(label, format!("conv-{}", ID));
+        assert!(is_detached(&label));
+    }
+
+    #[test]
+    fn test_invalid_ids_are_rejected() {
+        assert!(detached_label("").is_err());
+        assert!(detached_label("../settings").is_err());
+        assert!(detached_label("a b").is_err());
+        assert!(detached_label("x/y").is_err());
+    }
+
+    #[test]
+    fn test_other_windows_are_not_detached() {
+        assert!(!is_detached("main"));
+        assert!(!is_detached("settings"));
+        assert!(!is_detached("conv-"));
+        assert!(!is_detached("conversation"));
+    }
+
+    #[test]
+    fn test_detached_labels_filters_and_sorts() {
+        let labels = ["main", "conv-b", "settings", "conv-a"];
+
+        assert_eq!(detached_labels(labels), ["conv-a", "conv-b"]);
+    }
+
+    #[test]
+    fn test_route() {
+        assert_eq!(conversation_route(ID), format!("conversation/{}", ID));
+    }
+}
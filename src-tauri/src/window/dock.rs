@@ -0,0 +1,95 @@
+//! The Dock icon on macOS.
+//!
+//! With `general.hide_dock_icon` (the default on macOS) the app runs with
+//! the accessory activation policy: no Dock icon and no entry in the
+//! Cmd+Tab switcher, like other menu-bar apps. It replaces
+//! `launcher.skip_taskbar` there, since macOS windows can't leave the Dock
+//! individually.
+//!
+//! An accessory app isn't activated when one of its windows is shown, so
+//! [`bring_to_front`] activates it explicitly for the settings window.
+//! Elsewhere the setting is ignored and the functions here do nothing.
+
+use tauri::AppHandle;
+
+use crate::settings::GeneralSettings;
+
+/// How the app appears in the Dock and app switcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivationPolicy {
+    /// Dock icon and Cmd+Tab entry
+    Regular,
+    /// Menu bar only
+    Accessory,
+}
+
+/// The activation policy for the settings.
+pub fn activation_policy(general: &GeneralSettings) -> ActivationPolicy {
+    if general.hide_dock_icon {
+        ActivationPolicy::Accessory
+    } else {
+        ActivationPolicy::Regular
+    }
+}
+
+/// Switch the activation policy (macOS only).
+#[cfg(target_os = "macos")]
+pub fn set_activation_policy(app: &AppHandle, policy: ActivationPolicy) -> Result<(), String> {
+    let policy = match policy {
+        ActivationPolicy::Regular => tauri::ActivationPolicy::Regular,
+        ActivationPolicy::Accessory => tauri::ActivationPolicy::Accessory,
+    };
+    app.set_activation_policy(policy)
+        .map_err(|e| format!("Failed to set activation policy: {}", e))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_activation_policy(_app: &AppHandle, _policy: ActivationPolicy) -> Result<(), String> {
+    Ok(())
+}
+
+/// Make the app active so a window it shows comes to the front with
+/// keyboard focus (macOS only).
+#[cfg(target_os = "macos")]
+pub fn bring_to_front() {
+    use objc2_app_kit::{NSApplicationActivationOptions, NSRunningApplication};
+
+    #[allow(deprecated)]
+    NSRunningApplication::currentApplication()
+        .activateWithOptions(NSApplicationActivationOptions::ActivateIgnoringOtherApps);
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn bring_to_front() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(hide_dock_icon: bool) -> ActivationPolicy {
+        activation_policy(&GeneralSettings {
+            hide_dock_icon,
+            ..GeneralSettings::default()
+        })
+    }
+
+    // ===== Policy =====
+
+    #[test]
+    fn test_hidden_dock_icon_is_accessory() {
+        assert_eq!(policy(true), ActivationPolicy::Accessory);
+    }
+
+    #[test]
+    fn test_shown_dock_icon_is_regular() {
+        assert_eq!(policy(false), ActivationPolicy::Regular);
+    }
+
+    #[test]
+    fn test_default_hides_dock_icon_on_macos_only() {
+        assert_eq!(
+            GeneralSettings::default().hide_dock_icon,
+            cfg!(target_os = "macos")
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Escape handling at the window layer.
+//!
+//! The webview only sees Escape when a text field has focus, so the key is
+//! registered as a shortcut while the launcher or settings window is
+//! focused and released as soon as neither is. That way it never reaches
+//! past our own windows, and Escape works wherever focus sits inside them.
+
+use std::sync::Mutex;
+
+use tauri_plugin_global_shortcut::{Code, Shortcut};
+
+use super::SETTINGS_LABEL;
+
+/// Windows that Escape dismisses.
+pub const ESCAPE_WINDOWS: [&str; 2] = ["main", SETTINGS_LABEL];
+
+/// The Escape key, without modifiers.
+pub fn escape_shortcut() -> Shortcut {
+    Shortcut::new(None, Code::Escape)
+}
+
+/// What Escape does to the launcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeAction {
+    /// Clear pin mode and leave the launcher open
+    Unpin,
+    /// Hide the launcher
+    Hide,
+}
+
+/// Decide what Escape does on the launcher.
+///
+/// A pinned launcher is unpinned by the first Escape and hidden by the
+/// second.
+///
+/// # Arguments
+///
+/// * `pinned` - Whether pin mode is on
+pub fn escape_action(pinned: bool) -> EscapeAction {
+    if pinned {
+        EscapeAction::Unpin
+    } else {
+        EscapeAction::Hide
+    }
+}
+
+/// Change to the Escape registration after a focus change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registration {
+    Register,
+    Unregister,
+}
+
+/// Which of our windows currently owns Escape, managed as Tauri state.
+#[derive(Debug, Default)]
+pub struct EscapeKey {
+    owner: Mutex<Option<String>>,
+}
+
+impl EscapeKey {
+    /// Record a window gaining or losing focus.
+    ///
+    /// Focus moving between our windows may report the new window's gain
+    /// before the old window's loss, so a loss only counts for the current
+    /// owner.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Window label
+    /// * `focused` - Whether it gained focus
+    ///
+    /// # Returns
+    ///
+    /// The registration change needed, if any.
+    pub fn focus_changed(&self, label: &str, focused: bool) -> Option<Registration> {
+        let mut owner = self.owner.lock().unwrap();
+        let was_registered = owner.is_some();
+
+        if focused && ESCAPE_WINDOWS.contains(&label) {
+            *owner = Some(label.to_string());
+        } else if owner.as_deref() == Some(label) || focused {
+            *owner = None;
+        }
+
+        match (was_registered, owner.is_some()) {
+            (false, true) => Some(Registration::Register),
+            (true, false) => Some(Registration::Unregister),
+            _ => None,
+        }
+    }
+
+    /// Label of the window Escape applies to.
+    pub fn owner(&self) -> Option<String> {
+        self.owner.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===== Pin mode =====
+
+    #[test]
+    fn test_escape_unpins_then_hides() {
+        assert_eq!(escape_action(true), EscapeAction::Unpin);
+        // Unpinning clears the flag, so the next Escape hides
+        assert_eq!(escape_action(false), EscapeAction::Hide);
+    }
+
+    // ===== Registration =====
+
+    #[test]
+    fn test_registered_while_launcher_focused() {
+        let escape = EscapeKey::default();
+
+        assert_eq!(
+            escape.focus_changed("main", true),
+            Some(Registration::Register)
+        );
+        assert_eq!(escape.owner().as_deref(), Some("main"));
+        assert_eq!(
+            escape.focus_changed("main", false),
+            Some(Registration::Unregister)
+        );
+        assert_eq!(escape.owner(), None);
+    }
+
+    #[test]
+    fn test_focus_moving_between_our_windows_keeps_registration() {
+        let escape = EscapeKey::default();
+        escape.focus_changed("main", true);
+
+        // Gain reported before the loss
+        assert_eq!(escape.focus_changed("settings", true), None);
+        assert_eq!(escape.focus_changed("main", false), None);
+        assert_eq!(escape.owner().as_deref(), Some("settings"));
+
+        // Loss reported before the gain
+        assert_eq!(
+            escape.focus_changed("settings", false),
+            Some(Registration::Unregister)
+        );
+        assert_eq!(
+            escape.focus_changed("main", true),
+            Some(Registration::Register)
+        );
+    }
+
+    #[test]
+    fn test_other_windows_release_escape() {
+        let escape = EscapeKey::default();
+        escape.focus_changed("main", true);
+
+        assert_eq!(
+            escape.focus_changed("detached", true),
+            Some(Registration::Unregister)
+        );
+        assert_eq!(escape.focus_changed("detached", false), None);
+    }
+
+    #[test]
+    fn test_repeated_losses_are_ignored() {
+        let escape = EscapeKey::default();
+
+        assert_eq!(escape.focus_changed("main", false), None);
+        escape.focus_changed("main", true);
+        escape.focus_changed("main", false);
+        assert_eq!(escape.focus_changed("main", false), None);
+    }
+}
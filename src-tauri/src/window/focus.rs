@@ -0,0 +1,261 @@
+//! Give focus back to the app that was active before the launcher.
+//!
+//! The foreground window (HWND on Windows) or frontmost application (pid on
+//! macOS) is captured when the launcher is shown and re-activated when it
+//! is dismissed. Everything is best-effort: a handle that has gone away is
+//! ignored, and other platforms do nothing.
+//!
+//! The OS calls sit behind [`FocusPlatform`] so [`FocusTracker`] can be
+//! tested with a fake.
+
+use std::sync::Mutex;
+
+use super::visibility::VisibilityReason;
+
+/// OS access for capturing and re-activating the foreground app.
+pub trait FocusPlatform: Send + Sync {
+    /// Opaque handle of the current foreground window or app, or `None`
+    /// when it is unknown or belongs to this app.
+    fn foreground(&self) -> Option<i64>;
+
+    /// Bring the window or app back to the front.
+    ///
+    /// # Returns
+    ///
+    /// `false` if it no longer exists or couldn't be activated.
+    fn activate(&self, handle: i64) -> bool;
+}
+
+/// Whether hiding the launcher for `reason` should give focus back.
+///
+/// Explicit dismissals do; after a blur the user already chose where focus
+/// goes, and opening settings focuses the settings window.
+pub fn restores_focus(reason: VisibilityReason) -> bool {
+    matches!(
+        reason,
+        VisibilityReason::Hotkey
+            | VisibilityReason::Command
+            | VisibilityReason::Closed
+            | VisibilityReason::EscapeKey
+    )
+}
+
+/// The window that was in front before the launcher, managed as Tauri state.
+pub struct FocusTracker<P: FocusPlatform = NativeFocus> {
+    platform: P,
+    previous: Mutex<Option<i64>>,
+}
+
+impl FocusTracker {
+    /// Tracker using the current platform's APIs.
+    pub fn native() -> Self {
+        Self::new(NativeFocus)
+    }
+}
+
+impl<P: FocusPlatform> FocusTracker<P> {
+    pub fn new(platform: P) -> Self {
+        Self {
+            platform,
+            previous: Mutex::new(None),
+        }
+    }
+
+    /// Remember the foreground window. Call before showing the launcher.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - The `general.restore_focus` setting; when off, any
+    ///   previously captured window is forgotten
+    pub fn capture(&self, enabled: bool) {
+        let handle = if enabled {
+            self.platform.foreground()
+        } else {
+            None
+        };
+        *self.previous.lock().unwrap() = handle;
+    }
+
+    /// Re-activate the captured window, at most once per capture.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - The `general.restore_focus` setting
+    ///
+    /// # Returns
+    ///
+    /// `true` if a window was re-activated.
+    pub fn restore(&self, enabled: bool) -> bool {
+        let handle = self.previous.lock().unwrap().take();
+        match handle {
+            Some(handle) if enabled => self.platform.activate(handle),
+            _ => false,
+        }
+    }
+}
+
+/// The current platform's [`FocusPlatform`].
+pub struct NativeFocus;
+
+#[cfg(target_os = "windows")]
+impl FocusPlatform for NativeFocus {
+    fn foreground(&self) -> Option<i64> {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            GetForegroundWindow, GetWindowThreadProcessId,
+        };
+
+        // SAFETY: plain Win32 calls; the out pointer is a live local.
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() {
+                return None;
+            }
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(hwnd, &mut pid);
+            (pid != std::process::id()).then_some(hwnd as isize as i64)
+        }
+    }
+
+    fn activate(&self, handle: i64) -> bool {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{IsWindow, SetForegroundWindow};
+
+        let hwnd = handle as isize as windows_sys::Win32::Foundation::HWND;
+        // SAFETY: IsWindow accepts any value and rejects stale handles
+        // before SetForegroundWindow sees them.
+        unsafe { IsWindow(hwnd) != 0 && SetForegroundWindow(hwnd) != 0 }
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl FocusPlatform for NativeFocus {
+    fn foreground(&self) -> Option<i64> {
+        use objc2_app_kit::NSWorkspace;
+
+        let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+        let pid = app.processIdentifier();
+        (pid as u32 != std::process::id()).then_some(pid as i64)
+    }
+
+    fn activate(&self, handle: i64) -> bool {
+        use objc2_app_kit::{NSApplicationActivationOptions, NSRunningApplication};
+
+        NSRunningApplication::runningApplicationWithProcessIdentifier(handle as i32)
+            .map(|app| app.activateWithOptions(NSApplicationActivationOptions::empty()))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+impl FocusPlatform for NativeFocus {
+    fn foreground(&self) -> Option<i64> {
+        None
+    }
+
+    fn activate(&self, _handle: i64) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fake OS with one foreground window that can be closed.
+    #[derive(Default)]
+    struct FakePlatform {
+        foreground: Mutex<Option<i64>>,
+        closed: Mutex<Vec<i64>>,
+        activated: Mutex<Vec<i64>>,
+    }
+
+    impl FakePlatform {
+        fn with_foreground(handle: i64) -> Self {
+            let platform = Self::default();
+            *platform.foreground.lock().unwrap() = Some(handle);
+            platform
+        }
+    }
+
+    impl FocusPlatform for FakePlatform {
+        fn foreground(&self) -> Option<i64> {
+            *self.foreground.lock().unwrap()
+        }
+
+        fn activate(&self, handle: i64) -> bool {
+            if self.closed.lock().unwrap().contains(&handle) {
+                return false;
+            }
+            self.activated.lock().unwrap().push(handle);
+            true
+        }
+    }
+
+    fn activated(tracker: &FocusTracker<FakePlatform>) -> Vec<i64> {
+        tracker.platform.activated.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn test_restore_activates_captured_window() {
+        let tracker = FocusTracker::new(FakePlatform::with_foreground(42));
+
+        tracker.capture(true);
+
+        assert!(tracker.restore(true));
+        assert_eq!(activated(&tracker), [42]);
+    }
+
+    #[test]
+    fn test_restore_happens_once_per_capture() {
+        let tracker = FocusTracker::new(FakePlatform::with_foreground(42));
+
+        tracker.capture(true);
+        tracker.restore(true);
+
+        assert!(!tracker.restore(true));
+        assert_eq!(activated(&tracker), [42]);
+    }
+
+    #[test]
+    fn test_disabled_setting_skips_capture_and_restore() {
+        let tracker = FocusTracker::new(FakePlatform::with_foreground(42));
+
+        tracker.capture(false);
+        assert!(!tracker.restore(true));
+
+        tracker.capture(true);
+        assert!(!tracker.restore(false));
+
+        assert!(activated(&tracker).is_empty());
+    }
+
+    #[test]
+    fn test_closed_window_is_ignored() {
+        let tracker = FocusTracker::new(FakePlatform::with_foreground(42));
+        tracker.capture(true);
+
+        tracker.platform.closed.lock().unwrap().push(42);
+
+        assert!(!tracker.restore(true));
+        assert!(activated(&tracker).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_foreground() {
+        let tracker = FocusTracker::new(FakePlatform::default());
+
+        tracker.capture(true);
+
+        assert!(!tracker.restore(true));
+    }
+
+    #[test]
+    fn test_restores_focus_by_reason() {
+        assert!(restores_focus(VisibilityReason::Hotkey));
+        assert!(restores_focus(VisibilityReason::Command));
+        assert!(restores_focus(VisibilityReason::Closed));
+        assert!(restores_focus(VisibilityReason::EscapeKey));
+        assert!(!restores_focus(VisibilityReason::Blur));
+        assert!(!restores_focus(VisibilityReason::SettingsOpened));
+        assert!(!restores_focus(VisibilityReason::Tray));
+    }
+}
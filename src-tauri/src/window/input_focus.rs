@@ -0,0 +1,241 @@
+//! Focusing the launcher's input once the window really has focus.
+//!
+//! Showing a window and calling `set_focus` doesn't mean keystrokes arrive
+//! yet: on Windows the foreground lock can delay or refuse activation, and
+//! an IME (e.g. Japanese) swallows the first characters typed into an input
+//! focused before the window is active. So the show path asks for focus,
+//! waits for the window to report `Focused(true)`, and only then emits
+//! `focus-input`. If focus doesn't arrive within [`FOCUS_WAIT`] it asks
+//! again, up to [`FOCUS_ATTEMPTS`] times, and emits `focus-input` anyway
+//! (with `focused: false`) so the input isn't left unfocused.
+//!
+//! The window sits behind [`FocusTarget`] so [`focus_and_notify`] can be
+//! tested with a fake.
+//!
+//! ```typescript
+//! await listen<FocusInput>('focus-input', ({ payload }) => {
+//!   input.focus();
+//!   if (payload.reason === 'hotkey') input.select();
+//! });
+//! ```
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{Emitter, WebviewWindow};
+use tokio::sync::Notify;
+
+use super::visibility::VisibilityReason;
+
+/// Event telling the launcher to focus its input.
+pub const FOCUS_INPUT_EVENT: &str = "focus-input";
+
+/// How long to wait for the window to report focus before asking again.
+pub const FOCUS_WAIT: Duration = Duration::from_millis(150);
+
+/// Focus requests made before giving up.
+pub const FOCUS_ATTEMPTS: u32 = 3;
+
+/// Payload of the `focus-input` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FocusInput {
+    /// Why the launcher was shown; the frontend selects existing text for
+    /// some reasons and not others
+    pub reason: VisibilityReason,
+    /// Whether the window reported focus; `false` when every attempt timed
+    /// out
+    pub focused: bool,
+}
+
+/// The window being focused; the launcher in production.
+pub trait FocusTarget {
+    /// Ask the OS to focus the window.
+    fn request_focus(&self);
+
+    /// Whether the window has focus now.
+    fn is_focused(&self) -> bool;
+
+    fn emit_focus_input(&self, payload: FocusInput);
+}
+
+impl FocusTarget for WebviewWindow {
+    fn request_focus(&self) {
+        let _ = self.set_focus();
+    }
+
+    fn is_focused(&self) -> bool {
+        WebviewWindow::is_focused(self).unwrap_or(false)
+    }
+
+    fn emit_focus_input(&self, payload: FocusInput) {
+        let _ = self.emit_to(self.label(), FOCUS_INPUT_EVENT, payload);
+    }
+}
+
+/// Signalled when the launcher reports `Focused(true)`, managed as Tauri
+/// state.
+#[derive(Default)]
+pub struct FocusSignal(Notify);
+
+impl FocusSignal {
+    /// Wake the show sequences waiting for focus.
+    pub fn focus_gained(&self) {
+        self.0.notify_waiters();
+    }
+}
+
+/// Focus `window`, then emit `focus-input` once it has focus.
+///
+/// # Arguments
+///
+/// * `window` - The launcher
+/// * `signal` - Signalled by the window's `Focused(true)` event
+/// * `reason` - Why the launcher is being shown
+///
+/// # Returns
+///
+/// Whether the window got focus.
+pub async fn focus_and_notify(
+    window: &impl FocusTarget,
+    signal: &FocusSignal,
+    reason: VisibilityReason,
+) -> bool {
+    let mut focused = false;
+    for _ in 0..FOCUS_ATTEMPTS {
+        // Listen before asking, so a focus event sent right away isn't missed
+        let notified = signal.0.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        window.request_focus();
+        // Already focused windows get no new event
+        if window.is_focused() || tokio::time::timeout(FOCUS_WAIT, notified).await.is_ok() {
+            focused = true;
+            break;
+        }
+    }
+    window.emit_focus_input(FocusInput { reason, focused });
+    focused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// Window that gains focus on the `gains_on`-th request, reporting it
+    /// through the signal like the real `Focused(true)` event.
+    struct FakeWindow {
+        signal: Arc<FocusSignal>,
+        gains_on: Option<u32>,
+        already_focused: bool,
+        requests: AtomicU32,
+        emitted: Mutex<Vec<FocusInput>>,
+    }
+
+    impl FakeWindow {
+        fn new(signal: &Arc<FocusSignal>, gains_on: Option<u32>) -> Self {
+            Self {
+                signal: signal.clone(),
+                gains_on,
+                already_focused: false,
+                requests: AtomicU32::new(0),
+                emitted: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl FocusTarget for FakeWindow {
+        fn request_focus(&self) {
+            let request = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+            if self.gains_on == Some(request) {
+                // Sent from the event loop shortly after
+                let signal = self.signal.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    signal.focus_gained();
+                });
+            }
+        }
+
+        fn is_focused(&self) -> bool {
+            self.already_focused
+        }
+
+        fn emit_focus_input(&self, payload: FocusInput) {
+            self.emitted.lock().unwrap().push(payload);
+        }
+    }
+
+    fn payload(reason: VisibilityReason, focused: bool) -> FocusInput {
+        FocusInput { reason, focused }
+    }
+
+    #[tokio::test]
+    async fn test_emits_after_focus_event() {
+        let signal = Arc::new(FocusSignal::default());
+        let window = FakeWindow::new(&signal, Some(1));
+
+        let focused = focus_and_notify(&window, &signal, VisibilityReason::Hotkey).await;
+
+        assert!(focused);
+        assert_eq!(window.requests.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            *window.emitted.lock().unwrap(),
+            [payload(VisibilityReason::Hotkey, true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retries_when_focus_is_late() {
+        let signal = Arc::new(FocusSignal::default());
+        let window = FakeWindow::new(&signal, Some(2));
+
+        let focused = focus_and_notify(&window, &signal, VisibilityReason::Tray).await;
+
+        assert!(focused);
+        assert_eq!(window.requests.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            *window.emitted.lock().unwrap(),
+            [payload(VisibilityReason::Tray, true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_emits_unfocused_after_last_attempt() {
+        let signal = Arc::new(FocusSignal::default());
+        let window = FakeWindow::new(&signal, None);
+
+        let focused = focus_and_notify(&window, &signal, VisibilityReason::DeepLink).await;
+
+        assert!(!focused);
+        assert_eq!(window.requests.load(Ordering::SeqCst), FOCUS_ATTEMPTS);
+        assert_eq!(
+            *window.emitted.lock().unwrap(),
+            [payload(VisibilityReason::DeepLink, false)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_already_focused_window_does_not_wait() {
+        let signal = Arc::new(FocusSignal::default());
+        let mut window = FakeWindow::new(&signal, None);
+        window.already_focused = true;
+        let started = std::time::Instant::now();
+
+        let focused = focus_and_notify(&window, &signal, VisibilityReason::Command).await;
+
+        assert!(focused);
+        assert!(started.elapsed() < FOCUS_WAIT);
+        assert_eq!(window.emitted.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_payload_shape() {
+        assert_eq!(
+            serde_json::to_value(payload(VisibilityReason::Hotkey, true)).unwrap(),
+            serde_json::json!({ "reason": "hotkey", "focused": true })
+        );
+    }
+}
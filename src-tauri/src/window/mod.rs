@@ -1,8 +1,232 @@
 //! Window management module.
 //!
-//! Provides Tauri commands for window operations invoked from the frontend.
+//! Provides Tauri commands for window operations invoked from the frontend,
+//! launcher placement for the show path, and the close-to-hide lifecycle.
+//!
+//! # Architecture
+//!
+//! - [`detached`] - Labels of detached conversation windows
+//! - [`dock`] - The macOS Dock icon and activation policy
+//! - [`escape`] - Escape key registration and pin-aware dismissal
+//! - [`focus`] - Refocusing the previously active app
+//! - [`input_focus`] - `focus-input` once the launcher has focus
+//! - [`peek`] - Hold-to-peek press tracking
+//! - [`quit`] - Work-in-flight tracking and quit confirmation
+//! - [`placement`] - Show positions, monitor layout keys and work-area clamping
+//! - [`resize`] - Launcher height fitting and resize debouncing
+//! - [`toggle`] - Shortcut, blur and pin mode decisions
+//! - [`visibility`] - `launcher-shown` / `launcher-hidden` events
+//! - This file - Tauri commands, the launcher show path and geometry persistence
+//!
+//! # Launcher Placement
+//!
+//! `launcher.placement` decides where the launcher appears when shown:
+//! centered or top-centered on the monitor containing the cursor, just
+//! below the cursor, or where it was last left.
+//!
+//! With `remember_last`, the launcher's outer position and size are stored
+//! whenever it is moved, resized or hidden, keyed by the current monitor
+//! layout. Before the launcher is shown the geometry for the current layout
+//! is restored, clamped to a monitor's work area so it never reappears
+//! off-screen after a monitor is unplugged.
+//!
+//! `save_current_position` stores where the launcher is now, and
+//! `lock_launcher_position` sets `launcher.position_locked`. While that is
+//! set, every show puts the launcher at the saved position, clamped the
+//! same way, instead of following `launcher.placement` (see
+//! [`placement::show_positions`]). Locking saves the current position if
+//! none was saved yet. Moving the launcher for a show and saving its
+//! position take turns (see [`LauncherPositioning`]), so a save never
+//! captures a launcher halfway through being placed.
+//!
+//! ```typescript
+//! await invoke('save_current_position');
+//! await invoke('lock_launcher_position');   // or 'unlock_launcher_position'
+//! ```
+//!
+//! # Pin Mode
+//!
+//! A pinned launcher stays on top and doesn't hide when another app takes
+//! focus. The global shortcut focuses it; pressing it again once focused
+//! unpins and hides it. Unpinning leaves the launcher on top if
+//! `launcher.always_on_top` is set.
+//!
+//! # Detached Conversations
+//!
+//! [`detach_conversation`] opens a conversation in its own resizable,
+//! taskbar-visible window that the shortcut, blur and Escape handling never
+//! touch; those only ever act on the `main` window. Closing a detached
+//! window destroys it.
+//!
+//! # Escape
+//!
+//! While the launcher or settings window has focus, Escape is registered
+//! as a shortcut so it works no matter which element inside the webview is
+//! focused. On the launcher the first Escape unpins a pinned launcher and
+//! the next one hides it; on the settings window it hides the window.
+//!
+//! # Peek Mode
+//!
+//! With `shortcuts.peek_mode` on, holding the shortcut shows the launcher
+//! until it is released; a quick tap toggles it as usual.
+//!
+//! # Closing Windows
+//!
+//! The app lives in the tray, so closing the launcher or settings window
+//! (X button, Alt+F4) only hides it; destroying the webview would leave the
+//! tray and shortcut with nothing to show. Quitting goes through [`quit`],
+//! which runs [`crate::shutdown`]; it sets the [`QuitFlag`] so close
+//! requests are let through.
+//!
+//! Quitting from the tray while an LLM request or update download is in
+//! flight goes through [`request_quit`] instead, which shows the settings
+//! window with a `confirm-quit` event and waits for [`confirm_quit`]. A
+//! second quit within ten seconds exits anyway.
+//!
+//! ```typescript
+//! await listen<ConfirmQuit>('confirm-quit', async ({ payload }) => {
+//!   const force = await ask(`${payload.active} task(s) still running. Quit anyway?`);
+//!   await invoke('confirm_quit', { force });
+//! });
+//! ```
+
+pub mod detached;
+pub mod dock;
+pub mod escape;
+pub mod focus;
+pub mod input_focus;
+pub mod peek;
+pub mod placement;
+pub mod quit;
+pub mod resize;
+pub mod toggle;
+pub mod visibility;
 
-use tauri::{AppHandle, Manager};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::webview::PageLoadEvent;
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder,
+};
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+use crate::db::now_ms;
+use crate::history::drafts;
+use crate::settings::{LauncherPlacement, LauncherSettings, SettingsManager};
+use crate::shutdown::{self, ShutdownReason};
+use escape::{EscapeAction, EscapeKey, Registration};
+use focus::FocusTracker;
+use input_focus::FocusSignal;
+use peek::PeekTracker;
+use placement::{MonitorArea, Rect, RememberedGeometry, ShowPosition};
+use quit::{ActivityTracker, ConfirmQuit, QuitDecision, QuitRequests};
+use resize::ResizeDebouncer;
+use toggle::{LauncherPinChanged, LauncherState, ShortcutAction};
+use visibility::VisibilityReason;
+
+/// Store key holding the remembered [`placement::RememberedGeometry`].
+const LAUNCHER_GEOMETRY_KEY: &str = "launcher_geometry";
+
+/// Store key holding the geometry saved with `save_current_position`.
+const SAVED_POSITION_KEY: &str = "launcher_saved_position";
+
+/// Label of the settings window.
+const SETTINGS_LABEL: &str = "settings";
+
+/// Windows that are hidden instead of closed.
+const HIDE_ON_CLOSE: [&str; 2] = ["main", SETTINGS_LABEL];
+
+/// Payload of the `navigate-settings` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct NavigateSettings {
+    /// Section to show, as passed to `open_settings`
+    pub section: String,
+}
+
+/// Set once the app is quitting, so close requests are no longer turned
+/// into hides.
+#[derive(Debug, Default)]
+pub struct QuitFlag(AtomicBool);
+
+impl QuitFlag {
+    /// Mark the app as quitting.
+    pub fn set(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the app is quitting.
+    pub fn is_quitting(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Decide whether a close request should hide the window instead.
+///
+/// # Arguments
+///
+/// * `label` - Label of the window being closed
+/// * `is_quitting` - Whether the app is exiting
+pub fn should_hide_on_close(label: &str, is_quitting: bool) -> bool {
+    !is_quitting && HIDE_ON_CLOSE.contains(&label)
+}
+
+/// Quit the application, letting windows close for real.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle
+pub fn quit(app: &AppHandle) {
+    shutdown::shutdown(app, ShutdownReason::Quit);
+}
+
+/// Quit, unless work is in flight and the user hasn't confirmed yet.
+///
+/// While busy, the first request shows the settings window and sends it
+/// `confirm-quit`; a second request within
+/// [`quit::QUIT_CONFIRM_WINDOW_MS`] or [`confirm_quit`] with `force` quits.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle
+pub fn request_quit(app: &AppHandle) {
+    let activity = app.state::<ActivityTracker>();
+    match app
+        .state::<QuitRequests>()
+        .request(activity.is_busy(), now_ms())
+    {
+        QuitDecision::Exit => quit(app),
+        QuitDecision::Confirm => match open_settings_window(app, None) {
+            // A window created just now gets the event once it has loaded
+            Ok(window) => send_confirm_quit(&window, activity.active()),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to open settings; quitting without confirmation");
+                quit(app);
+            }
+        },
+    }
+}
+
+/// Answer a `confirm-quit` prompt.
+///
+/// # Arguments
+///
+/// * `force` - `true` to quit even though work is in flight, `false` to
+///   keep running
+#[tauri::command]
+pub fn confirm_quit(app: AppHandle, force: bool) {
+    if force {
+        quit(&app);
+    } else {
+        app.state::<QuitRequests>().cancel();
+    }
+}
+
+fn send_confirm_quit(window: &WebviewWindow, active: usize) {
+    let _ = window.emit_to(SETTINGS_LABEL, "confirm-quit", ConfirmQuit { active });
+}
 
 /// Open the settings window and hide the main launcher.
 ///
@@ -12,32 +236,820 @@ use tauri::{AppHandle, Manager};
 /// # Arguments
 ///
 /// * `app` - Tauri AppHandle for window access
+/// * `section` - Settings section to navigate to (`"general"`,
+///   `"shortcuts"`, `"llm"`, `"updates"`, `"history"`); passed through to
+///   the `navigate-settings` event as is
 ///
 /// # Returns
 ///
 /// * `Ok(())` - Settings window opened (and focused)
-/// * `Err(String)` - Settings window not found in configuration
+/// * `Err(String)` - Settings window could not be created
 ///
 /// # Frontend Usage
 ///
 /// ```typescript
 /// import { invoke } from '@tauri-apps/api/core';
+/// import { listen } from '@tauri-apps/api/event';
 ///
 /// async function openSettings() {
-///   await invoke('open_settings');
+///   await invoke('open_settings', { section: 'llm' }); // or null
 /// }
+///
+/// // In the settings window
+/// await listen<NavigateSettings>('navigate-settings', ({ payload }) => {
+///   router.push(`/${payload.section}`);
+/// });
 /// ```
 #[tauri::command]
-pub fn open_settings(app: AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("settings") {
-        // Hide main window when opening settings
-        if let Some(main_window) = app.get_webview_window("main") {
-            let _ = main_window.hide();
+pub fn open_settings(app: AppHandle, section: Option<String>) -> Result<(), String> {
+    open_settings_window(&app, section).map(|_| ())
+}
+
+/// Show and focus the settings window, hiding the launcher.
+///
+/// Shared by the `open_settings` command and the tray. The settings window
+/// is created if it doesn't exist, e.g. because it was destroyed.
+///
+/// When a section is given, `navigate-settings` is sent to the settings
+/// window once it is showing. A window created here has no listeners yet,
+/// so the event is sent when its page has finished loading instead.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window access
+/// * `section` - Settings section to navigate to, if any
+///
+/// # Returns
+///
+/// * `Ok(WebviewWindow)` - The settings window
+/// * `Err(String)` - The window could not be created
+pub fn open_settings_window(
+    app: &AppHandle,
+    section: Option<String>,
+) -> Result<WebviewWindow, String> {
+    let (window, section) = match app.get_webview_window(SETTINGS_LABEL) {
+        Some(window) => (window, section),
+        None => {
+            let window = build_settings_window(app, section)
+                .map_err(|e| format!("Failed to create settings window: {}", e))?;
+            (window, None)
         }
+    };
+
+    hide_launcher(app, VisibilityReason::SettingsOpened);
+    window
+        .show()
+        .map_err(|e| format!("Failed to show settings window: {}", e))?;
+    // Without a Dock icon the app isn't activated by showing a window,
+    // which would leave it behind the frontmost app without keyboard focus
+    dock::bring_to_front();
+    let _ = window.set_focus();
+
+    if let Some(section) = section {
+        navigate_settings(&window, section);
+    }
+    Ok(window)
+}
+
+/// Tell the settings window which section to show.
+fn navigate_settings(window: &WebviewWindow, section: String) {
+    let _ = window.emit_to(
+        SETTINGS_LABEL,
+        "navigate-settings",
+        NavigateSettings { section },
+    );
+}
+
+/// Create the settings window with the same options as `tauri.conf.json`.
+///
+/// # Arguments
+///
+/// * `section` - Sent as `navigate-settings` once the page has loaded
+fn build_settings_window(app: &AppHandle, section: Option<String>) -> tauri::Result<WebviewWindow> {
+    let pending = Mutex::new(section);
+    WebviewWindowBuilder::new(app, SETTINGS_LABEL, WebviewUrl::App("settings.html".into()))
+        .on_page_load(move |window, payload| {
+            if payload.event() != PageLoadEvent::Finished {
+                return;
+            }
+            // Only the first load; a reload keeps whatever the user chose
+            if let Some(section) = pending.lock().ok().and_then(|mut s| s.take()) {
+                navigate_settings(&window, section);
+            }
+            let app = window.app_handle();
+            if app.state::<QuitRequests>().is_pending(now_ms()) {
+                send_confirm_quit(&window, app.state::<ActivityTracker>().active());
+            }
+        })
+        .title("Qwik Ask Settings")
+        .inner_size(580.0, 520.0)
+        .min_inner_size(480.0, 400.0)
+        .resizable(true)
+        .decorations(false)
+        .center()
+        .visible(false)
+        .always_on_top(false)
+        .skip_taskbar(false)
+        .focused(true)
+        .build()
+}
+
+/// Open a conversation in its own window.
+///
+/// The window is labeled `conv-<conversation_id>` and loads the
+/// `/conversation/<id>` route, sized from `launcher.detached_width` and
+/// `launcher.detached_height`. If the conversation is already detached,
+/// its window is focused instead of opening a second one.
+///
+/// Async so the window isn't created on the main thread, which deadlocks
+/// on Windows.
+///
+/// # Arguments
+///
+/// * `conversation_id` - ID of the conversation to show
+///
+/// # Returns
+///
+/// * `Ok(String)` - Label of the detached window
+/// * `Err(String)` - Invalid ID, or the window could not be created
+///
+/// # Frontend Usage
+///
+/// ```typescript
+/// const label = await invoke<string>('detach_conversation', {
+///   conversationId: conversation.id,
+/// });
+/// ```
+#[tauri::command]
+pub async fn detach_conversation(
+    app: AppHandle,
+    conversation_id: String,
+) -> Result<String, String> {
+    let label = detached::detached_label(&conversation_id)?;
+
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
         let _ = window.show();
         let _ = window.set_focus();
-        Ok(())
-    } else {
-        Err("Settings window not found".to_string())
+        return Ok(label);
+    }
+
+    let launcher = launcher_settings(&app);
+    let url = WebviewUrl::App(detached::conversation_route(&conversation_id).into());
+    WebviewWindowBuilder::new(&app, &label, url)
+        .title("Qwik Ask")
+        .inner_size(
+            launcher.detached_width as f64,
+            launcher.detached_height as f64,
+        )
+        .min_inner_size(320.0, 240.0)
+        .resizable(true)
+        .center()
+        .skip_taskbar(false)
+        .focused(true)
+        .build()
+        .map_err(|e| format!("Failed to open conversation window: {}", e))?;
+
+    Ok(label)
+}
+
+/// Close a detached conversation window.
+///
+/// Does nothing if the window is already closed.
+///
+/// # Arguments
+///
+/// * `label` - Label returned by `detach_conversation`
+///
+/// # Returns
+///
+/// * `Ok(())` - Window closed or already gone
+/// * `Err(String)` - `label` isn't a detached window, or it couldn't be closed
+#[tauri::command]
+pub fn close_detached(app: AppHandle, label: String) -> Result<(), String> {
+    if !detached::is_detached(&label) {
+        return Err(format!("Not a detached window: '{}'", label));
+    }
+    match app.get_webview_window(&label) {
+        Some(window) => window
+            .close()
+            .map_err(|e| format!("Failed to close window: {}", e)),
+        None => Ok(()),
+    }
+}
+
+/// List the open detached conversation windows.
+///
+/// # Returns
+///
+/// Window labels (`conv-<conversation id>`), sorted.
+#[tauri::command]
+pub fn list_detached_windows(app: AppHandle) -> Vec<String> {
+    let windows = app.webview_windows();
+    detached::detached_labels(windows.keys().map(String::as_str))
+}
+
+/// The launcher settings, or the defaults when settings can't be read.
+fn launcher_settings(app: &AppHandle) -> LauncherSettings {
+    app.state::<SettingsManager>()
+        .snapshot()
+        .map(|settings| settings.launcher.clone())
+        .unwrap_or_default()
+}
+
+/// Bounds, work areas and scale factors of the connected monitors.
+pub fn monitor_areas(app: &AppHandle) -> Vec<MonitorArea> {
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| {
+            let area = m.work_area();
+            MonitorArea {
+                bounds: Rect::new(
+                    m.position().x,
+                    m.position().y,
+                    m.size().width,
+                    m.size().height,
+                ),
+                work_area: Rect::new(
+                    area.position.x,
+                    area.position.y,
+                    area.size.width,
+                    area.size.height,
+                ),
+                scale_factor: m.scale_factor(),
+            }
+        })
+        .collect()
+}
+
+/// Show and focus the launcher, placed according to `launcher.placement`.
+///
+/// Emits `launcher-shown` with `reason` unless the launcher was already
+/// visible, followed by `draft-restored` if a draft was saved when it was
+/// hidden. `focus-input` follows once the window has focus (see
+/// [`input_focus`]).
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window and settings access
+/// * `reason` - Why it is being shown
+pub fn show_launcher(app: &AppHandle, reason: VisibilityReason) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let was_visible = window.is_visible().unwrap_or(false);
+
+    if !was_visible {
+        app.state::<FocusTracker>()
+            .capture(restore_focus_enabled(app));
+        position_window_for_show(&window);
+    }
+    let _ = window.show();
+
+    if !was_visible {
+        visibility::notify_shown(app, reason, now_ms());
+        drafts::emit_restored(app);
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let signal = app.state::<FocusSignal>();
+        input_focus::focus_and_notify(&window, &signal, reason).await;
+    });
+}
+
+/// Hide the launcher, remembering its geometry first.
+///
+/// When dismissed by the hotkey, a command (Escape) or closing, focus goes
+/// back to the app that was active before it was shown
+/// (`general.restore_focus`).
+///
+/// Emits `launcher-hidden` with `reason` unless the launcher was already
+/// hidden, so the blur that follows hiding it isn't reported twice.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window and settings access
+/// * `reason` - Why it is being hidden
+pub fn hide_launcher(app: &AppHandle, reason: VisibilityReason) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if !window.is_visible().unwrap_or(false) {
+        return;
+    }
+
+    remember_launcher_geometry(app);
+    let _ = window.hide();
+    handle_window_focus(app, "main", false);
+    if focus::restores_focus(reason) {
+        app.state::<FocusTracker>()
+            .restore(restore_focus_enabled(app));
+    }
+    visibility::notify_hidden(app, reason, now_ms());
+}
+
+/// The `general.restore_focus` setting.
+fn restore_focus_enabled(app: &AppHandle) -> bool {
+    app.state::<SettingsManager>()
+        .snapshot()
+        .map(|settings| settings.general.restore_focus)
+        .unwrap_or(true)
+}
+
+/// Show the launcher from the frontend.
+///
+/// Emits `launcher-shown` with reason `command`.
+#[tauri::command]
+pub fn show_main_window(app: AppHandle) {
+    show_launcher(&app, VisibilityReason::Command);
+}
+
+/// Hide the launcher from the frontend.
+///
+/// Emits `launcher-hidden` with reason `command`.
+///
+/// # Frontend Usage
+///
+/// ```typescript
+/// await listen<LauncherVisibilityChanged>('launcher-shown', ({ payload }) => {
+///   if (payload.reason !== 'blur' || Date.now() - hiddenAt > 2000) clearInput();
+/// });
+/// await invoke('hide_main_window'); // instead of getCurrentWindow().hide()
+/// ```
+#[tauri::command]
+pub fn hide_main_window(app: AppHandle) {
+    hide_launcher(&app, VisibilityReason::Command);
+}
+
+/// Handle a press of the global shortcut.
+///
+/// See [`toggle::shortcut_action`] for how visibility, focus, pin mode and
+/// `launcher.toggle_behavior` combine.
+///
+/// # Returns
+///
+/// The action taken, or `None` when there is no launcher window.
+pub fn toggle_launcher(app: &AppHandle) -> Option<ShortcutAction> {
+    let window = app.get_webview_window("main")?;
+    let action = toggle::shortcut_action(
+        window.is_visible().unwrap_or(false),
+        window.is_focused().unwrap_or(false),
+        app.state::<LauncherState>().is_pinned(),
+        launcher_settings(app).toggle_behavior,
+    );
+
+    match action {
+        ShortcutAction::Show => show_launcher(app, VisibilityReason::Hotkey),
+        ShortcutAction::Focus => {
+            let _ = window.set_focus();
+        }
+        ShortcutAction::Hide => hide_launcher(app, VisibilityReason::Hotkey),
+        ShortcutAction::UnpinAndHide => {
+            unpin_launcher(app);
+            hide_launcher(app, VisibilityReason::Hotkey);
+        }
+    }
+
+    app.state::<PeekTracker>()
+        .pressed(now_ms(), action == ShortcutAction::Show);
+    Some(action)
+}
+
+/// Handle the global shortcut being released.
+///
+/// With `shortcuts.peek_mode` on, hides the launcher if the press showed
+/// it and was held for at least `shortcuts.peek_hold_ms`.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window and settings access
+pub fn handle_shortcut_release(app: &AppHandle) {
+    let shortcuts = app
+        .state::<SettingsManager>()
+        .snapshot()
+        .map(|settings| settings.shortcuts.clone())
+        .unwrap_or_default();
+    let peeked = app
+        .state::<PeekTracker>()
+        .released(now_ms(), shortcuts.peek_hold_ms);
+
+    if shortcuts.peek_mode && peeked {
+        hide_launcher(app, VisibilityReason::Hotkey);
+    }
+}
+
+/// Hide the launcher after it lost focus, unless it is pinned or
+/// `launcher.hide_on_blur` is off.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window and settings access
+pub fn handle_launcher_blur(app: &AppHandle) {
+    let pinned = app.state::<LauncherState>().is_pinned();
+    if toggle::should_hide_on_blur(pinned, launcher_settings(app).hide_on_blur) {
+        hide_launcher(app, VisibilityReason::Blur);
+    }
+}
+
+/// Register or release Escape as our windows gain and lose focus.
+///
+/// Also called after hiding a window, in case the platform doesn't report
+/// the focus loss, so Escape is never held while none of our windows has
+/// focus.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for shortcut access
+/// * `label` - Label of the window whose focus changed
+/// * `focused` - Whether it gained focus
+pub fn handle_window_focus(app: &AppHandle, label: &str, focused: bool) {
+    let shortcuts = app.global_shortcut();
+    match app.state::<EscapeKey>().focus_changed(label, focused) {
+        Some(Registration::Register) => {
+            if let Err(e) = shortcuts.register(escape::escape_shortcut()) {
+                tracing::warn!(error = %e, "Failed to register Escape");
+            }
+        }
+        Some(Registration::Unregister) => {
+            let _ = shortcuts.unregister(escape::escape_shortcut());
+        }
+        None => {}
+    }
+}
+
+/// Handle Escape pressed while one of our windows has focus.
+///
+/// On the launcher, see [`escape::escape_action`]; the settings window is
+/// hidden.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window and state access
+pub fn handle_escape(app: &AppHandle) {
+    let owner = app.state::<EscapeKey>().owner();
+    match owner.as_deref() {
+        Some("main") => match escape::escape_action(app.state::<LauncherState>().is_pinned()) {
+            EscapeAction::Unpin => unpin_launcher(app),
+            EscapeAction::Hide => hide_launcher(app, VisibilityReason::EscapeKey),
+        },
+        Some(SETTINGS_LABEL) => {
+            if let Some(window) = app.get_webview_window(SETTINGS_LABEL) {
+                let _ = window.hide();
+            }
+            handle_window_focus(app, SETTINGS_LABEL, false);
+        }
+        _ => {}
+    }
+}
+
+/// Pin or unpin the launcher.
+///
+/// A pinned launcher stays on top and visible when another app takes focus,
+/// and the global shortcut focuses it instead of hiding it. Pin mode is not
+/// persisted. Emits `launcher-pin-changed` when the flag changes.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window access
+/// * `pinned` - Whether to pin the launcher
+///
+/// # Frontend Usage
+///
+/// ```typescript
+/// await invoke('set_launcher_pinned', { pinned: true });
+/// await listen<LauncherPinChanged>('launcher-pin-changed', ({ payload }) => {
+///   pinIcon.active = payload.pinned;
+/// });
+/// ```
+#[tauri::command]
+pub fn set_launcher_pinned(app: AppHandle, pinned: bool) -> Result<(), String> {
+    if !app.state::<LauncherState>().set_pinned(pinned) {
+        return Ok(());
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let always_on_top = launcher_settings(&app).always_on_top;
+        window
+            .set_always_on_top(toggle::keeps_on_top(pinned, always_on_top))
+            .map_err(|e| format!("Failed to update launcher: {}", e))?;
+    }
+    let _ = app.emit("launcher-pin-changed", LauncherPinChanged { pinned });
+    Ok(())
+}
+
+/// Fit the launcher's height to its content.
+///
+/// The height is clamped between a minimum and `launcher.max_height` (and
+/// the monitor's work area), and the top edge stays where it is. Calls made
+/// in quick succession, as while an answer streams in, are coalesced: only
+/// the last one within [`resize::RESIZE_DEBOUNCE`] is applied.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window and settings access
+/// * `height` - Height the content needs, in logical pixels
+///
+/// # Frontend Usage
+///
+/// ```typescript
+/// new ResizeObserver(() => {
+///   invoke('resize_launcher', { height: container.scrollHeight });
+/// }).observe(container);
+/// ```
+#[tauri::command]
+pub fn resize_launcher(app: AppHandle, height: f64) {
+    let ticket = app.state::<ResizeDebouncer>().ticket();
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(resize::RESIZE_DEBOUNCE).await;
+        if !app.state::<ResizeDebouncer>().is_latest(ticket) {
+            return;
+        }
+        if let Err(e) = apply_launcher_height(&app, height) {
+            tracing::warn!(error = %e, "Failed to resize launcher");
+        }
+    });
+}
+
+/// Resize the launcher to `height` logical pixels, keeping its top edge.
+fn apply_launcher_height(app: &AppHandle, height: f64) -> tauri::Result<()> {
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    let scale_factor = window.scale_factor()?;
+    let work_area_height = window
+        .current_monitor()?
+        .map(|m| m.work_area().size.height)
+        .unwrap_or(u32::MAX);
+    let max_height = launcher_settings(app).max_height as f64;
+
+    let position = window.outer_position()?;
+    let width = window.inner_size()?.width;
+    let height = resize::launcher_height(height, max_height, work_area_height, scale_factor);
+
+    window.set_size(PhysicalSize::new(width, height))?;
+    window.set_position(position)
+}
+
+/// Clear pin mode, if set.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window access
+pub fn unpin_launcher(app: &AppHandle) {
+    if let Err(e) = set_launcher_pinned(app.clone(), false) {
+        tracing::warn!(error = %e, "Failed to unpin the launcher");
+    }
+}
+
+/// Moving the launcher for a show and saving its position, one at a time;
+/// managed as Tauri state.
+#[derive(Default)]
+pub struct LauncherPositioning(Mutex<()>);
+
+/// Run `position` while nothing else moves the launcher or saves its
+/// position.
+fn positioning<T>(app: &AppHandle, position: impl FnOnce() -> T) -> T {
+    let positioning = app.state::<LauncherPositioning>();
+    let _turn = positioning.0.lock().unwrap();
+    position()
+}
+
+/// Move a window to where the launcher settings want it before it is shown.
+///
+/// The saved position wins while `launcher.position_locked` is set;
+/// otherwise `launcher.placement` decides (see
+/// [`placement::show_positions`]). Positions are computed in physical
+/// pixels on the monitor containing the cursor, accounting for the window
+/// being rescaled when it moves to a monitor with a different scale
+/// factor. Saved and remembered geometry is restored on its monitor if
+/// still connected; without any, the launcher is centered. The window is
+/// left where it is if the cursor position or monitors can't be queried.
+///
+/// # Arguments
+///
+/// * `window` - Window to move (normally the launcher)
+pub fn position_window_for_show(window: &WebviewWindow) {
+    let app = window.app_handle();
+    positioning(app, || {
+        let settings = launcher_settings(app);
+        let monitors = monitor_areas(app);
+        for position in placement::show_positions(settings.placement, settings.position_locked) {
+            let placed = match position {
+                ShowPosition::Saved => restore_geometry(window, SAVED_POSITION_KEY, &monitors),
+                ShowPosition::Remembered => {
+                    restore_geometry(window, LAUNCHER_GEOMETRY_KEY, &monitors)
+                }
+                ShowPosition::Computed(placement) => {
+                    place_window(window, placement, &monitors);
+                    true
+                }
+            };
+            if placed {
+                return;
+            }
+        }
+    });
+}
+
+/// Move a window to the position [`placement::position_for_show`] computes.
+fn place_window(window: &WebviewWindow, placement: LauncherPlacement, monitors: &[MonitorArea]) {
+    let (Ok(cursor), Ok(size), Ok(scale_factor)) = (
+        window.app_handle().cursor_position(),
+        window.outer_size(),
+        window.scale_factor(),
+    ) else {
+        return;
+    };
+
+    if let Some(rect) = placement::position_for_show(
+        placement,
+        (cursor.x, cursor.y),
+        (size.width, size.height),
+        scale_factor,
+        monitors,
+    ) {
+        let _ = window.set_position(PhysicalPosition::new(rect.x, rect.y));
+    }
+}
+
+/// The launcher's current geometry, relative to its monitor.
+fn launcher_geometry(app: &AppHandle) -> Option<RememberedGeometry> {
+    let window = app.get_webview_window("main")?;
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.outer_size()) else {
+        return None;
+    };
+    let window_rect = Rect::new(position.x, position.y, size.width, size.height);
+    placement::remember(window_rect, &monitor_areas(app))
+}
+
+/// Save the launcher's current position, unless `only_if_unsaved` and one
+/// is saved already.
+fn save_launcher_position(app: &AppHandle, only_if_unsaved: bool) -> Result<(), String> {
+    positioning(app, || {
+        let settings_manager = app.state::<SettingsManager>();
+        if only_if_unsaved
+            && settings_manager
+                .load_state::<RememberedGeometry>(SAVED_POSITION_KEY)?
+                .is_some()
+        {
+            return Ok(());
+        }
+        let geometry = launcher_geometry(app)
+            .ok_or_else(|| "Failed to read the launcher position".to_string())?;
+        settings_manager.save_state(SAVED_POSITION_KEY, &geometry)
+    })
+}
+
+/// Set `launcher.position_locked`, keeping the rest of the current settings.
+async fn set_position_locked(app: &AppHandle, locked: bool) -> Result<(), String> {
+    app.state::<SettingsManager>()
+        .update(|settings| {
+            settings.launcher.position_locked = locked;
+            Ok(())
+        })
+        .await
+        .map(|_| ())
+}
+
+/// Save where the launcher is now as the position it is shown at while
+/// locked.
+///
+/// # Returns
+///
+/// * `Ok(())` - Saved
+/// * `Err(String)` - The position couldn't be read or stored
+#[tauri::command]
+pub fn save_current_position(app: AppHandle) -> Result<(), String> {
+    save_launcher_position(&app, false)
+}
+
+/// Always show the launcher at the saved position, whatever
+/// `launcher.placement` says.
+///
+/// Saves the current position first if none was saved yet.
+#[tauri::command]
+pub async fn lock_launcher_position(app: AppHandle) -> Result<(), String> {
+    save_launcher_position(&app, true)?;
+    set_position_locked(&app, true).await
+}
+
+/// Go back to placing the launcher by `launcher.placement`. The saved
+/// position is kept for the next lock.
+#[tauri::command]
+pub async fn unlock_launcher_position(app: AppHandle) -> Result<(), String> {
+    set_position_locked(&app, false).await
+}
+
+/// Store the launcher's current geometry, relative to its monitor.
+///
+/// Called when the launcher is moved, resized or hidden. Does nothing
+/// unless `launcher.placement` is `remember_last` and the launcher is
+/// visible, so restoring a position before showing doesn't overwrite it.
+///
+/// # Arguments
+///
+/// * `app` - Tauri AppHandle for window and settings access
+pub fn remember_launcher_geometry(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if !window.is_visible().unwrap_or(false)
+        || launcher_settings(app).placement != LauncherPlacement::RememberLast
+    {
+        return;
+    }
+    let Some(geometry) = launcher_geometry(app) else {
+        return;
+    };
+
+    if let Err(e) = app
+        .state::<SettingsManager>()
+        .save_state(LAUNCHER_GEOMETRY_KEY, &geometry)
+    {
+        tracing::warn!(error = %e, "Failed to remember launcher position");
+    }
+}
+
+/// Apply the geometry stored under `key`, translated to another monitor if
+/// its own is gone (see [`placement::restore`]).
+///
+/// # Returns
+///
+/// `false` when nothing was stored.
+fn restore_geometry(window: &WebviewWindow, key: &str, monitors: &[MonitorArea]) -> bool {
+    let saved: Option<RememberedGeometry> = window
+        .state::<SettingsManager>()
+        .load_state(key)
+        .ok()
+        .flatten();
+    let Some(rect) = saved.and_then(|saved| placement::restore(&saved, monitors)) else {
+        return false;
+    };
+
+    // Move first: entering a monitor with another scale factor makes the OS
+    // rescale the window, which would undo a size set before the move
+    let _ = window.set_position(PhysicalPosition::new(rect.x, rect.y));
+    let _ = window.set_size(PhysicalSize::new(rect.width, rect.height));
+    true
+}
+
+/// Keep the launcher on screen after its scale factor changed.
+///
+/// The OS resizes a window around a fixed corner when it moves to a
+/// monitor with another scale factor or the factor is changed in the
+/// display settings, which can push it past the work area's edge.
+///
+/// # Arguments
+///
+/// * `window` - The window whose scale factor changed
+pub fn handle_scale_factor_changed(window: &tauri::Window) {
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.outer_size()) else {
+        return;
+    };
+    let current = Rect::new(position.x, position.y, size.width, size.height);
+    let Some(rect) = placement::reclamp(current, &monitor_areas(window.app_handle())) else {
+        return;
+    };
+    if rect != current {
+        tracing::debug!(?current, ?rect, "Re-clamping launcher after scale change");
+        let _ = window.set_position(PhysicalPosition::new(rect.x, rect.y));
+        let _ = window.set_size(PhysicalSize::new(rect.width, rect.height));
+    }
+    remember_launcher_geometry(window.app_handle());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===== Close Handling =====
+
+    #[test]
+    fn test_app_windows_hide_on_close() {
+        assert!(should_hide_on_close("main", false));
+        assert!(should_hide_on_close("settings", false));
+    }
+
+    #[test]
+    fn test_windows_close_when_quitting() {
+        assert!(!should_hide_on_close("main", true));
+        assert!(!should_hide_on_close("settings", true));
+    }
+
+    #[test]
+    fn test_other_windows_close() {
+        assert!(!should_hide_on_close("updater", false));
+    }
+
+    #[test]
+    fn test_quit_flag() {
+        let flag = QuitFlag::default();
+        assert!(!flag.is_quitting());
+
+        flag.set();
+
+        assert!(flag.is_quitting());
     }
 }
@@ -0,0 +1,107 @@
+//! Hold-to-peek for the global shortcut.
+//!
+//! With `shortcuts.peek_mode` on, holding the shortcut shows the launcher
+//! only for as long as it is held. A press that showed the launcher and is
+//! released after `shortcuts.peek_hold_ms` hides it again; shorter presses
+//! are ordinary toggle taps.
+
+use std::sync::Mutex;
+
+/// Press/release tracking, managed as Tauri state.
+#[derive(Debug, Default)]
+pub struct PeekTracker {
+    /// `(pressed_at_ms, showed_launcher)` of the press being held
+    press: Mutex<Option<(i64, bool)>>,
+}
+
+impl PeekTracker {
+    /// Record a shortcut press.
+    ///
+    /// # Arguments
+    ///
+    /// * `now_ms` - Current Unix timestamp (ms)
+    /// * `showed_launcher` - Whether this press showed the launcher
+    pub fn pressed(&self, now_ms: i64, showed_launcher: bool) {
+        *self.press.lock().unwrap() = Some((now_ms, showed_launcher));
+    }
+
+    /// Record a shortcut release.
+    ///
+    /// # Arguments
+    ///
+    /// * `now_ms` - Current Unix timestamp (ms)
+    /// * `hold_ms` - Minimum hold that counts as a peek
+    ///
+    /// # Returns
+    ///
+    /// `true` if the launcher should be hidden: the matching press showed
+    /// it and was held for at least `hold_ms`.
+    pub fn released(&self, now_ms: i64, hold_ms: u32) -> bool {
+        match self.press.lock().unwrap().take() {
+            Some((pressed_at, true)) => now_ms - pressed_at >= hold_ms as i64,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOLD_MS: u32 = 400;
+
+    #[test]
+    fn test_tap_keeps_launcher_open() {
+        let tracker = PeekTracker::default();
+
+        tracker.pressed(1_000, true);
+
+        assert!(!tracker.released(1_120, HOLD_MS));
+    }
+
+    #[test]
+    fn test_long_hold_hides_on_release() {
+        let tracker = PeekTracker::default();
+
+        tracker.pressed(1_000, true);
+
+        assert!(tracker.released(1_400, HOLD_MS));
+    }
+
+    #[test]
+    fn test_long_hold_that_hid_the_launcher_does_nothing() {
+        let tracker = PeekTracker::default();
+
+        tracker.pressed(1_000, false);
+
+        assert!(!tracker.released(2_000, HOLD_MS));
+    }
+
+    #[test]
+    fn test_rapid_double_tap_toggles() {
+        let tracker = PeekTracker::default();
+
+        // First tap shows, second tap hides through the toggle
+        tracker.pressed(1_000, true);
+        assert!(!tracker.released(1_080, HOLD_MS));
+        tracker.pressed(1_150, false);
+        assert!(!tracker.released(1_230, HOLD_MS));
+    }
+
+    #[test]
+    fn test_release_without_press() {
+        let tracker = PeekTracker::default();
+
+        assert!(!tracker.released(1_000, HOLD_MS));
+    }
+
+    #[test]
+    fn test_release_is_consumed() {
+        let tracker = PeekTracker::default();
+
+        tracker.pressed(1_000, true);
+
+        assert!(tracker.released(1_500, HOLD_MS));
+        assert!(!tracker.released(1_600, HOLD_MS));
+    }
+}
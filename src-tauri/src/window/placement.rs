@@ -0,0 +1,753 @@
+//! Launcher placement math.
+//!
+//! Kept free of Tauri types so it can be tested against synthetic monitor
+//! layouts. All coordinates are physical pixels in the desktop coordinate
+//! space, where monitors left of or above the primary one have negative
+//! positions.
+//!
+//! Monitors can have different scale factors, so logical sizes only mean
+//! something together with the monitor they're on. Conversions go through
+//! [`to_physical`] and [`to_logical`] with that monitor's factor, once per
+//! value: remembered geometry is stored in logical pixels relative to its
+//! monitor ([`RememberedGeometry`]) and turned into physical pixels on the
+//! monitor it's restored to.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::settings::LauncherPlacement;
+
+/// Gap between the cursor and the window for `NearCursor`, in logical pixels.
+pub const CURSOR_OFFSET: f64 = 8.0;
+
+/// Fraction of the work area height above the window for `TopCenter`.
+pub const TOP_CENTER_RATIO: f64 = 0.2;
+
+/// A window or monitor rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn right(&self) -> i64 {
+        self.x as i64 + self.width as i64
+    }
+
+    fn bottom(&self) -> i64 {
+        self.y as i64 + self.height as i64
+    }
+
+    /// Area shared with `other`, in square pixels.
+    fn overlap(&self, other: &Rect) -> i64 {
+        let width = self.right().min(other.right()) - (self.x.max(other.x) as i64);
+        let height = self.bottom().min(other.bottom()) - (self.y.max(other.y) as i64);
+        width.max(0) * height.max(0)
+    }
+
+    /// Squared length of the gap between the edges of two rectangles;
+    /// `0` when they touch or overlap.
+    fn gap_squared(&self, other: &Rect) -> i64 {
+        let dx = (other.x as i64 - self.right())
+            .max(self.x as i64 - other.right())
+            .max(0);
+        let dy = (other.y as i64 - self.bottom())
+            .max(self.y as i64 - other.bottom())
+            .max(0);
+        dx * dx + dy * dy
+    }
+}
+
+/// Logical pixels to physical pixels at `scale_factor`.
+pub fn to_physical(logical: f64, scale_factor: f64) -> i64 {
+    (logical * scale_factor).round() as i64
+}
+
+/// Physical pixels to logical pixels at `scale_factor`.
+pub fn to_logical(physical: i64, scale_factor: f64) -> f64 {
+    physical as f64 / scale_factor
+}
+
+/// A connected monitor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonitorArea {
+    /// Full monitor bounds
+    pub bounds: Rect,
+    /// Bounds minus taskbars, docks and menu bars
+    pub work_area: Rect,
+    /// Physical pixels per logical pixel
+    pub scale_factor: f64,
+}
+
+impl MonitorArea {
+    fn contains(&self, (x, y): (f64, f64)) -> bool {
+        let b = self.bounds;
+        x >= b.x as f64 && x < b.right() as f64 && y >= b.y as f64 && y < b.bottom() as f64
+    }
+}
+
+/// Compute where to show a window for `placement`.
+///
+/// The window is placed on the monitor containing the cursor, or the
+/// nearest one when the cursor is between monitors. Its size is converted
+/// from the scale factor of the monitor it is on now to the target
+/// monitor's, since the OS rescales it when it moves there. `RememberLast`
+/// is treated as `Center`; remembered geometry is handled by the caller.
+///
+/// # Arguments
+///
+/// * `cursor` - Cursor position in physical pixels
+/// * `window_size` - Current outer size in physical pixels
+/// * `window_scale` - Scale factor of the window's current monitor
+/// * `monitors` - Connected monitors
+///
+/// # Returns
+///
+/// The window rectangle on the target monitor, or `None` when there are
+/// no monitors.
+pub fn position_for_show(
+    placement: LauncherPlacement,
+    cursor: (f64, f64),
+    window_size: (u32, u32),
+    window_scale: f64,
+    monitors: &[MonitorArea],
+) -> Option<Rect> {
+    let cursor_rect = Rect::new(cursor.0.floor() as i32, cursor.1.floor() as i32, 1, 1);
+    let monitor = monitors.iter().find(|m| m.contains(cursor)).or_else(|| {
+        monitors
+            .iter()
+            .min_by_key(|m| cursor_rect.gap_squared(&m.bounds))
+    })?;
+
+    let scale = monitor.scale_factor;
+    let width = to_physical(to_logical(window_size.0 as i64, window_scale), scale) as u32;
+    let height = to_physical(to_logical(window_size.1 as i64, window_scale), scale) as u32;
+    let area = monitor.work_area;
+    let centered_x = area.x as i64 + (area.width as i64 - width as i64) / 2;
+
+    let (x, y) = match placement {
+        LauncherPlacement::Center | LauncherPlacement::RememberLast => (
+            centered_x,
+            area.y as i64 + (area.height as i64 - height as i64) / 2,
+        ),
+        LauncherPlacement::TopCenter => (
+            centered_x,
+            area.y as i64 + (area.height as f64 * TOP_CENTER_RATIO).round() as i64,
+        ),
+        LauncherPlacement::NearCursor => {
+            let offset = to_physical(CURSOR_OFFSET, scale);
+            (cursor_rect.x as i64, cursor_rect.y as i64 + offset)
+        }
+    };
+
+    clamp_to_work_area(
+        Rect::new(x as i32, y as i32, width, height),
+        &[monitor.work_area],
+    )
+}
+
+/// A way of placing the launcher when it is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShowPosition {
+    /// The geometry saved with `save_current_position`
+    Saved,
+    /// The geometry remembered for `remember_last`
+    Remembered,
+    /// Computed by [`position_for_show`]
+    Computed(LauncherPlacement),
+}
+
+/// The ways to place the launcher, in order; the first one with geometry
+/// to apply wins.
+///
+/// A locked position comes first, then the placement mode, then `Center`
+/// when nothing was saved or remembered.
+///
+/// # Arguments
+///
+/// * `placement` - The `launcher.placement` setting
+/// * `locked` - The `launcher.position_locked` setting
+pub fn show_positions(placement: LauncherPlacement, locked: bool) -> Vec<ShowPosition> {
+    let mut positions = Vec::new();
+    if locked {
+        positions.push(ShowPosition::Saved);
+    }
+    if placement == LauncherPlacement::RememberLast {
+        positions.push(ShowPosition::Remembered);
+    }
+    positions.push(ShowPosition::Computed(placement));
+    positions
+}
+
+/// Identify a monitor by its position and size.
+///
+/// The same monitor at the same place in the layout always maps to the same
+/// key; moving it in the OS display settings or changing its resolution
+/// gives a new one.
+pub fn monitor_key(bounds: &Rect) -> String {
+    let material = format!(
+        "{},{},{},{}",
+        bounds.x, bounds.y, bounds.width, bounds.height
+    );
+    let digest = Sha256::digest(material.as_bytes());
+    format!("{:x}", digest)[..16].to_string()
+}
+
+/// Launcher geometry remembered for `remember_last`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RememberedGeometry {
+    /// [`monitor_key`] of the monitor the launcher was on
+    pub monitor: String,
+    /// Offset from that monitor's work area origin, logical pixels
+    pub x: f64,
+    pub y: f64,
+    /// Outer size, logical pixels
+    pub width: f64,
+    pub height: f64,
+}
+
+/// The monitor a window is on: the one whose work area it overlaps most, or
+/// the nearest one.
+fn monitor_of<'a>(window: &Rect, monitors: &'a [MonitorArea]) -> Option<&'a MonitorArea> {
+    monitors.iter().max_by_key(|m| {
+        (
+            window.overlap(&m.work_area),
+            -window.gap_squared(&m.work_area),
+        )
+    })
+}
+
+/// Describe `window` relative to the monitor it's on.
+///
+/// # Arguments
+///
+/// * `window` - Outer position and size, physical pixels
+/// * `monitors` - Connected monitors
+///
+/// # Returns
+///
+/// * `None` - No monitors were given
+pub fn remember(window: Rect, monitors: &[MonitorArea]) -> Option<RememberedGeometry> {
+    let monitor = monitor_of(&window, monitors)?;
+    let scale = monitor.scale_factor;
+    let area = monitor.work_area;
+    Some(RememberedGeometry {
+        monitor: monitor_key(&monitor.bounds),
+        x: to_logical(window.x as i64 - area.x as i64, scale),
+        y: to_logical(window.y as i64 - area.y as i64, scale),
+        width: to_logical(window.width as i64, scale),
+        height: to_logical(window.height as i64, scale),
+    })
+}
+
+/// Where to put a window described by `saved` with the current monitors.
+///
+/// On the remembered monitor, the geometry is converted at its current
+/// scale factor. If that monitor is gone or was moved, the same offset and
+/// size are used on the monitor at the desktop origin (the primary on
+/// Windows and macOS), or the first one. The result is clamped to the
+/// monitor's work area.
+///
+/// # Returns
+///
+/// * `None` - No monitors were given
+pub fn restore(saved: &RememberedGeometry, monitors: &[MonitorArea]) -> Option<Rect> {
+    let monitor = monitors
+        .iter()
+        .find(|m| monitor_key(&m.bounds) == saved.monitor)
+        .or_else(|| monitors.iter().find(|m| (m.bounds.x, m.bounds.y) == (0, 0)))
+        .or_else(|| monitors.first())?;
+    let scale = monitor.scale_factor;
+    let area = monitor.work_area;
+    let width = to_physical(saved.width, scale).max(1);
+    let height = to_physical(saved.height, scale).max(1);
+
+    clamp_to_work_area(
+        Rect::new(
+            (area.x as i64 + to_physical(saved.x, scale)) as i32,
+            (area.y as i64 + to_physical(saved.y, scale)) as i32,
+            width as u32,
+            height as u32,
+        ),
+        &[area],
+    )
+}
+
+/// Keep `window` inside the work area of the monitor it's on.
+///
+/// For after a scale factor change, when the OS has resized the window
+/// around a fixed corner and may have pushed it past the edge.
+pub fn reclamp(window: Rect, monitors: &[MonitorArea]) -> Option<Rect> {
+    let monitor = monitor_of(&window, monitors)?;
+    clamp_to_work_area(window, &[monitor.work_area])
+}
+
+/// Move (and if needed shrink) `window` so it lies inside a work area.
+///
+/// The work area the window overlaps most is used; when it overlaps none
+/// (its monitor was unplugged), the nearest one is.
+///
+/// # Returns
+///
+/// * `None` - No work areas were given
+pub fn clamp_to_work_area(window: Rect, work_areas: &[Rect]) -> Option<Rect> {
+    let area = work_areas
+        .iter()
+        .max_by_key(|area| (window.overlap(area), -window.gap_squared(area)))?;
+
+    let width = window.width.min(area.width);
+    let height = window.height.min(area.height);
+    let x = (window.x as i64).clamp(area.x as i64, area.right() - width as i64);
+    let y = (window.y as i64).clamp(area.y as i64, area.bottom() - height as i64);
+
+    Some(Rect::new(x as i32, y as i32, width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1920x1080 primary monitor with a 40px taskbar at the bottom.
+    fn primary() -> Rect {
+        Rect::new(0, 0, 1920, 1040)
+    }
+
+    /// 2560x1440 monitor to the right of the primary, top-aligned.
+    fn right_monitor() -> Rect {
+        Rect::new(1920, 0, 2560, 1440)
+    }
+
+    /// 1280x1024 monitor to the left of the primary.
+    fn left_monitor() -> Rect {
+        Rect::new(-1280, 0, 1280, 1024)
+    }
+
+    // ===== Clamping =====
+
+    #[test]
+    fn test_window_inside_work_area_is_unchanged() {
+        let window = Rect::new(620, 300, 680, 110);
+
+        assert_eq!(clamp_to_work_area(window, &[primary()]), Some(window));
+    }
+
+    #[test]
+    fn test_window_past_the_edge_is_pulled_in() {
+        let window = Rect::new(1500, 1000, 680, 110);
+
+        assert_eq!(
+            clamp_to_work_area(window, &[primary()]),
+            Some(Rect::new(1240, 930, 680, 110))
+        );
+    }
+
+    #[test]
+    fn test_window_on_unplugged_monitor_moves_to_nearest() {
+        // Last left on the right monitor, which is no longer connected
+        let window = Rect::new(3000, 1200, 680, 110);
+
+        assert_eq!(
+            clamp_to_work_area(window, &[left_monitor(), primary()]),
+            Some(Rect::new(1240, 930, 680, 110))
+        );
+    }
+
+    #[test]
+    fn test_window_spanning_monitors_uses_larger_overlap() {
+        // 480px on the left monitor, 200px on the primary
+        let window = Rect::new(-480, 100, 680, 110);
+
+        assert_eq!(
+            clamp_to_work_area(window, &[left_monitor(), primary(), right_monitor()]),
+            Some(Rect::new(-680, 100, 680, 110))
+        );
+    }
+
+    #[test]
+    fn test_window_on_negative_coordinates_stays_put() {
+        let window = Rect::new(-1000, 50, 680, 110);
+
+        assert_eq!(
+            clamp_to_work_area(window, &[left_monitor(), primary()]),
+            Some(window)
+        );
+    }
+
+    #[test]
+    fn test_window_larger_than_work_area_is_shrunk() {
+        let window = Rect::new(-100, -100, 2400, 1600);
+
+        assert_eq!(
+            clamp_to_work_area(window, &[primary()]),
+            Some(Rect::new(0, 0, 1920, 1040))
+        );
+    }
+
+    #[test]
+    fn test_no_work_areas() {
+        assert_eq!(clamp_to_work_area(Rect::new(0, 0, 680, 110), &[]), None);
+    }
+
+    // ===== Show Position =====
+
+    fn monitor(bounds: Rect, work_area: Rect, scale_factor: f64) -> MonitorArea {
+        MonitorArea {
+            bounds,
+            work_area,
+            scale_factor,
+        }
+    }
+
+    /// Primary at 1.0, a 4K monitor at 2.0 to its right, and a 1.5 laptop
+    /// panel to its left.
+    fn three_monitors() -> Vec<MonitorArea> {
+        vec![
+            monitor(Rect::new(0, 0, 1920, 1080), primary(), 1.0),
+            monitor(
+                Rect::new(1920, 0, 3840, 2160),
+                Rect::new(1920, 0, 3840, 2160),
+                2.0,
+            ),
+            monitor(
+                Rect::new(-2256, 0, 2256, 1504),
+                Rect::new(-2256, 0, 2256, 1456),
+                1.5,
+            ),
+        ]
+    }
+
+    fn show(placement: LauncherPlacement, cursor: (f64, f64), scale: f64) -> Option<Rect> {
+        position_for_show(placement, cursor, (680, 110), scale, &three_monitors())
+    }
+
+    #[test]
+    fn test_center_on_cursor_monitor() {
+        assert_eq!(
+            show(LauncherPlacement::Center, (500.0, 500.0), 1.0),
+            Some(Rect::new(620, 465, 680, 110))
+        );
+    }
+
+    #[test]
+    fn test_center_rescales_for_hidpi_monitor() {
+        // 680x110 logical becomes 1360x220 physical at 2.0
+        assert_eq!(
+            show(LauncherPlacement::Center, (3000.0, 100.0), 1.0),
+            Some(Rect::new(3160, 970, 1360, 220))
+        );
+    }
+
+    #[test]
+    fn test_center_rescales_from_hidpi_monitor() {
+        // Currently on the 2.0 monitor at 1360x220 physical
+        let rect = position_for_show(
+            LauncherPlacement::Center,
+            (-1000.0, 700.0),
+            (1360, 220),
+            2.0,
+            &three_monitors(),
+        );
+
+        // 1020x165 physical at 1.5, centered in the laptop's work area
+        assert_eq!(rect, Some(Rect::new(-1638, 645, 1020, 165)));
+    }
+
+    #[test]
+    fn test_top_center() {
+        assert_eq!(
+            show(LauncherPlacement::TopCenter, (500.0, 500.0), 1.0),
+            Some(Rect::new(620, 208, 680, 110))
+        );
+    }
+
+    #[test]
+    fn test_remember_last_falls_back_to_center() {
+        assert_eq!(
+            show(LauncherPlacement::RememberLast, (500.0, 500.0), 1.0),
+            show(LauncherPlacement::Center, (500.0, 500.0), 1.0)
+        );
+    }
+
+    #[test]
+    fn test_near_cursor_offset_is_scaled() {
+        assert_eq!(
+            show(LauncherPlacement::NearCursor, (100.4, 200.6), 1.0),
+            Some(Rect::new(100, 208, 680, 110))
+        );
+        assert_eq!(
+            show(LauncherPlacement::NearCursor, (2000.0, 200.0), 1.0),
+            Some(Rect::new(2000, 216, 1360, 220))
+        );
+    }
+
+    #[test]
+    fn test_near_cursor_is_clamped_inside_monitor() {
+        // Bottom-right corner of the primary, above the taskbar
+        assert_eq!(
+            show(LauncherPlacement::NearCursor, (1900.0, 1030.0), 1.0),
+            Some(Rect::new(1240, 930, 680, 110))
+        );
+    }
+
+    #[test]
+    fn test_cursor_between_monitors_uses_nearest() {
+        // Below the primary, where the taller monitors extend but it doesn't
+        assert_eq!(
+            show(LauncherPlacement::Center, (1000.0, 1300.0), 1.0),
+            show(LauncherPlacement::Center, (100.0, 100.0), 1.0)
+        );
+    }
+
+    #[test]
+    fn test_show_without_monitors() {
+        assert_eq!(
+            position_for_show(LauncherPlacement::Center, (0.0, 0.0), (680, 110), 1.0, &[]),
+            None
+        );
+    }
+
+    // ===== Monitor Keys =====
+
+    #[test]
+    fn test_monitor_key_is_stable() {
+        assert_eq!(monitor_key(&right_monitor()), monitor_key(&right_monitor()));
+        assert_eq!(monitor_key(&primary()).len(), 16);
+    }
+
+    #[test]
+    fn test_monitor_key_changes_with_position_and_size() {
+        let moved = Rect::new(-2560, 0, 2560, 1440);
+        let resized = Rect::new(1920, 0, 1920, 1080);
+
+        assert_ne!(monitor_key(&right_monitor()), monitor_key(&moved));
+        assert_ne!(monitor_key(&right_monitor()), monitor_key(&resized));
+    }
+
+    // ===== Conversion =====
+
+    const FACTORS: [f64; 4] = [1.0, 1.25, 1.5, 2.0];
+
+    #[test]
+    fn test_logical_to_physical() {
+        let physical: Vec<i64> = FACTORS.iter().map(|&f| to_physical(680.0, f)).collect();
+
+        assert_eq!(physical, vec![680, 850, 1020, 1360]);
+        assert_eq!(to_physical(CURSOR_OFFSET, 1.25), 10);
+        assert_eq!(to_physical(-8.0, 1.5), -12);
+    }
+
+    #[test]
+    fn test_physical_to_logical_roundtrip() {
+        for factor in FACTORS {
+            for logical in [0.0, 110.0, 680.0, -300.0] {
+                let physical = to_physical(logical, factor);
+                assert_eq!(to_physical(to_logical(physical, factor), factor), physical);
+            }
+        }
+    }
+
+    // ===== Remembered Geometry =====
+
+    /// 100% laptop panel and a 1.5 external monitor to its right.
+    fn mixed_dpi(external_scale: f64) -> Vec<MonitorArea> {
+        vec![
+            monitor(Rect::new(0, 0, 1920, 1080), primary(), 1.0),
+            monitor(
+                Rect::new(1920, 0, 3840, 2160),
+                Rect::new(1920, 0, 3840, 2100),
+                external_scale,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_remember_stores_logical_offset_on_monitor() {
+        let monitors = mixed_dpi(1.5);
+        let window = Rect::new(1920 + 300, 150, 1020, 165);
+
+        let saved = remember(window, &monitors).unwrap();
+
+        assert_eq!(
+            saved,
+            RememberedGeometry {
+                monitor: monitor_key(&monitors[1].bounds),
+                x: 200.0,
+                y: 100.0,
+                width: 680.0,
+                height: 110.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_restore_roundtrip_at_each_factor() {
+        for factor in FACTORS {
+            let monitors = mixed_dpi(factor);
+            let window = Rect::new(
+                1920 + to_physical(400.0, factor) as i32,
+                to_physical(200.0, factor) as i32,
+                to_physical(680.0, factor) as u32,
+                to_physical(110.0, factor) as u32,
+            );
+
+            let saved = remember(window, &monitors).unwrap();
+
+            assert_eq!(restore(&saved, &monitors), Some(window), "at {}", factor);
+        }
+    }
+
+    #[test]
+    fn test_restore_uses_current_scale_of_monitor() {
+        // Saved at 150%, the monitor has since been set to 200%
+        let saved = remember(Rect::new(1920 + 300, 150, 1020, 165), &mixed_dpi(1.5)).unwrap();
+
+        assert_eq!(
+            restore(&saved, &mixed_dpi(2.0)),
+            Some(Rect::new(1920 + 400, 200, 1360, 220))
+        );
+    }
+
+    #[test]
+    fn test_restore_after_monitor_removed() {
+        // Saved on the 1.5 external monitor, now only the laptop is left
+        let saved = remember(Rect::new(1920 + 300, 150, 1020, 165), &mixed_dpi(1.5)).unwrap();
+        let laptop = [monitor(Rect::new(0, 0, 1920, 1080), primary(), 1.0)];
+
+        assert_eq!(
+            restore(&saved, &laptop),
+            Some(Rect::new(200, 100, 680, 110))
+        );
+    }
+
+    #[test]
+    fn test_restore_after_monitor_removed_is_clamped() {
+        // Far right on a large monitor; the remaining one is smaller
+        let saved = RememberedGeometry {
+            monitor: monitor_key(&right_monitor()),
+            x: 2000.0,
+            y: 1300.0,
+            width: 680.0,
+            height: 110.0,
+        };
+        let laptop = [monitor(Rect::new(0, 0, 1920, 1080), primary(), 1.25)];
+
+        assert_eq!(
+            restore(&saved, &laptop),
+            Some(Rect::new(1070, 902, 850, 138))
+        );
+    }
+
+    #[test]
+    fn test_restore_prefers_monitor_at_origin() {
+        let saved = RememberedGeometry {
+            monitor: "gone".to_string(),
+            x: 10.0,
+            y: 10.0,
+            width: 680.0,
+            height: 110.0,
+        };
+        let monitors = [
+            monitor(left_monitor(), left_monitor(), 1.0),
+            monitor(Rect::new(0, 0, 1920, 1080), primary(), 2.0),
+        ];
+
+        assert_eq!(
+            restore(&saved, &monitors),
+            Some(Rect::new(20, 20, 1360, 220))
+        );
+    }
+
+    #[test]
+    fn test_restore_without_monitors() {
+        let saved = remember(Rect::new(0, 0, 680, 110), &mixed_dpi(1.0)).unwrap();
+
+        assert_eq!(restore(&saved, &[]), None);
+        assert_eq!(remember(Rect::new(0, 0, 680, 110), &[]), None);
+    }
+
+    #[test]
+    fn test_reclamp_after_scale_change() {
+        // Grown from 680x110 to 1020x165 around its top-left corner
+        let window = Rect::new(1920 + 3000, 2000, 1020, 165);
+
+        assert_eq!(
+            reclamp(window, &mixed_dpi(1.5)),
+            Some(Rect::new(1920 + 2820, 1935, 1020, 165))
+        );
+    }
+
+    // ===== Show Order =====
+
+    #[test]
+    fn test_lock_comes_before_placement_mode() {
+        assert_eq!(
+            show_positions(LauncherPlacement::NearCursor, true),
+            [
+                ShowPosition::Saved,
+                ShowPosition::Computed(LauncherPlacement::NearCursor)
+            ]
+        );
+        assert_eq!(
+            show_positions(LauncherPlacement::RememberLast, true),
+            [
+                ShowPosition::Saved,
+                ShowPosition::Remembered,
+                ShowPosition::Computed(LauncherPlacement::RememberLast)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_placement_mode_when_unlocked() {
+        assert_eq!(
+            show_positions(LauncherPlacement::TopCenter, false),
+            [ShowPosition::Computed(LauncherPlacement::TopCenter)]
+        );
+        assert_eq!(
+            show_positions(LauncherPlacement::RememberLast, false),
+            [
+                ShowPosition::Remembered,
+                ShowPosition::Computed(LauncherPlacement::RememberLast)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_when_nothing_is_saved() {
+        // `RememberLast` computes as `Center`
+        let fallback = *show_positions(LauncherPlacement::RememberLast, true)
+            .last()
+            .unwrap();
+        let ShowPosition::Computed(placement) = fallback else {
+            panic!("last position should be computed");
+        };
+        let monitors = [MonitorArea {
+            bounds: Rect::new(0, 0, 1920, 1080),
+            work_area: Rect::new(0, 0, 1920, 1040),
+            scale_factor: 1.0,
+        }];
+
+        assert_eq!(
+            position_for_show(placement, (100.0, 100.0), (680, 110), 1.0, &monitors),
+            position_for_show(
+                LauncherPlacement::Center,
+                (100.0, 100.0),
+                (680, 110),
+                1.0,
+                &monitors
+            )
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Quitting while work is in flight.
+//!
+//! LLM requests and update downloads hold an [`ActivityGuard`] from an
+//! [`ActivityTracker`]. Quitting from the tray while any guard is alive asks
+//! for confirmation first; asking to quit again within
+//! [`QUIT_CONFIRM_WINDOW_MS`] quits regardless.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+/// How long after an unconfirmed quit another quit request exits anyway.
+pub const QUIT_CONFIRM_WINDOW_MS: i64 = 10_000;
+
+struct Activity {
+    active: AtomicUsize,
+    on_change: Box<dyn Fn() + Send + Sync>,
+}
+
+/// Counts work in flight, managed as Tauri state.
+pub struct ActivityTracker {
+    inner: Arc<Activity>,
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self::new(|| {})
+    }
+}
+
+impl ActivityTracker {
+    /// Create a tracker.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_change` - Called after work starts or ends, e.g. to refresh
+    ///   the tray
+    pub fn new(on_change: impl Fn() + Send + Sync + 'static) -> Self {
+        Self {
+            inner: Arc::new(Activity {
+                active: AtomicUsize::new(0),
+                on_change: Box::new(on_change),
+            }),
+        }
+    }
+
+    /// Record the start of some work; it ends when the guard is dropped,
+    /// including when the task holding it panics.
+    pub fn begin(&self) -> ActivityGuard {
+        self.inner.active.fetch_add(1, Ordering::SeqCst);
+        (self.inner.on_change)();
+        ActivityGuard {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+
+    /// Number of guards alive.
+    pub fn active(&self) -> usize {
+        self.inner.active.load(Ordering::SeqCst)
+    }
+
+    /// Whether any work is in flight.
+    pub fn is_busy(&self) -> bool {
+        self.active() > 0
+    }
+}
+
+/// Keeps its [`ActivityTracker`] busy until dropped.
+#[must_use = "the activity ends when the guard is dropped"]
+pub struct ActivityGuard {
+    inner: Arc<Activity>,
+}
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        self.inner.active.fetch_sub(1, Ordering::SeqCst);
+        (self.inner.on_change)();
+    }
+}
+
+/// What a quit request should do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuitDecision {
+    /// Quit now
+    Exit,
+    /// Ask the user first
+    Confirm,
+}
+
+/// Payload of the `confirm-quit` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfirmQuit {
+    /// Requests and downloads in flight
+    pub active: usize,
+}
+
+/// Unconfirmed quit requests, managed as Tauri state.
+#[derive(Debug, Default)]
+pub struct QuitRequests {
+    /// When confirmation was last asked for (Unix ms)
+    pending_since: Mutex<Option<i64>>,
+}
+
+impl QuitRequests {
+    /// Decide what a quit request does.
+    ///
+    /// # Arguments
+    ///
+    /// * `busy` - Whether work is in flight
+    /// * `now_ms` - Current Unix timestamp (ms)
+    pub fn request(&self, busy: bool, now_ms: i64) -> QuitDecision {
+        if !busy || self.is_pending(now_ms) {
+            return QuitDecision::Exit;
+        }
+        *self.pending_since.lock().unwrap() = Some(now_ms);
+        QuitDecision::Confirm
+    }
+
+    /// Whether confirmation was asked for within the last
+    /// [`QUIT_CONFIRM_WINDOW_MS`].
+    pub fn is_pending(&self, now_ms: i64) -> bool {
+        self.pending_since
+            .lock()
+            .unwrap()
+            .is_some_and(|since| now_ms - since <= QUIT_CONFIRM_WINDOW_MS)
+    }
+
+    /// Forget the pending request; the user chose to keep the app running.
+    pub fn cancel(&self) {
+        *self.pending_since.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+
+    // ===== Activity guard =====
+
+    #[test]
+    fn test_guard_tracks_activity() {
+        let tracker = ActivityTracker::default();
+        assert!(!tracker.is_busy());
+
+        let first = tracker.begin();
+        let second = tracker.begin();
+        assert_eq!(tracker.active(), 2);
+
+        drop(first);
+        assert!(tracker.is_busy());
+        drop(second);
+        assert!(!tracker.is_busy());
+    }
+
+    #[test]
+    fn test_guard_released_on_panic() {
+        let tracker = ActivityTracker::default();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = tracker.begin();
+            panic!("request failed");
+        }));
+
+        assert!(result.is_err());
+        assert!(!tracker.is_busy());
+    }
+
+    #[test]
+    fn test_guard_outlives_borrow_of_tracker() {
+        let tracker = ActivityTracker::default();
+        let guard = tracker.begin();
+
+        let handle = std::thread::spawn(move || drop(guard));
+        handle.join().unwrap();
+
+        assert!(!tracker.is_busy());
+    }
+
+    #[test]
+    fn test_changes_are_reported() {
+        let changes = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&changes);
+        let tracker = ActivityTracker::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        drop(tracker.begin());
+
+        assert_eq!(changes.load(Ordering::SeqCst), 2);
+    }
+
+    // ===== Quit requests =====
+
+    #[test]
+    fn test_idle_quit_exits() {
+        let requests = QuitRequests::default();
+
+        assert_eq!(requests.request(false, 1_000), QuitDecision::Exit);
+    }
+
+    #[test]
+    fn test_busy_quit_asks_then_second_click_exits() {
+        let requests = QuitRequests::default();
+
+        assert_eq!(requests.request(true, 1_000), QuitDecision::Confirm);
+        assert_eq!(requests.request(true, 9_000), QuitDecision::Exit);
+    }
+
+    #[test]
+    fn test_second_click_after_window_asks_again() {
+        let requests = QuitRequests::default();
+
+        requests.request(true, 1_000);
+
+        assert_eq!(requests.request(true, 12_000), QuitDecision::Confirm);
+    }
+
+    #[test]
+    fn test_cancel_clears_pending() {
+        let requests = QuitRequests::default();
+        requests.request(true, 1_000);
+
+        requests.cancel();
+
+        assert!(!requests.is_pending(2_000));
+        assert_eq!(requests.request(true, 2_000), QuitDecision::Confirm);
+    }
+}
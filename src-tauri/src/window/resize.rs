@@ -0,0 +1,116 @@
+//! Launcher height fitting.
+//!
+//! The frontend asks for the height its content needs, repeatedly while an
+//! answer streams in. Requests are coalesced: each one takes a ticket, waits
+//! [`RESIZE_DEBOUNCE`], and is only applied if no newer request arrived in
+//! the meantime.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Smallest launcher height in logical pixels (the input row alone).
+pub const MIN_LAUNCHER_HEIGHT: f64 = 110.0;
+
+/// How long a resize waits for a newer request before it is applied.
+pub const RESIZE_DEBOUNCE: Duration = Duration::from_millis(30);
+
+/// Hands out resize tickets; only the newest ticket is applied.
+#[derive(Debug, Default)]
+pub struct ResizeDebouncer {
+    latest: AtomicU64,
+}
+
+impl ResizeDebouncer {
+    /// Take a ticket for a new request, superseding all earlier ones.
+    pub fn ticket(&self) -> u64 {
+        self.latest.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Whether `ticket` is still the newest request.
+    pub fn is_latest(&self, ticket: u64) -> bool {
+        self.latest.load(Ordering::SeqCst) == ticket
+    }
+}
+
+/// Compute the launcher's inner height in physical pixels.
+///
+/// The requested height is clamped to at least [`MIN_LAUNCHER_HEIGHT`] and
+/// at most `max_height` or the monitor's work area, whichever is smaller.
+/// The minimum wins if the limits contradict each other.
+///
+/// # Arguments
+///
+/// * `requested` - Height the content needs, in logical pixels
+/// * `max_height` - `launcher.max_height`, in logical pixels
+/// * `work_area_height` - Work area height of the window's monitor, in
+///   physical pixels
+/// * `scale_factor` - The window's scale factor
+pub fn launcher_height(
+    requested: f64,
+    max_height: f64,
+    work_area_height: u32,
+    scale_factor: f64,
+) -> u32 {
+    let limit = max_height.min(work_area_height as f64 / scale_factor);
+    let logical = requested.min(limit).max(MIN_LAUNCHER_HEIGHT);
+    (logical * scale_factor).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ===== Height =====
+
+    #[test]
+    fn test_height_within_limits() {
+        assert_eq!(launcher_height(300.0, 600.0, 1040, 1.0), 300);
+        assert_eq!(launcher_height(300.0, 600.0, 1560, 1.5), 450);
+        assert_eq!(launcher_height(300.0, 600.0, 2080, 2.0), 600);
+    }
+
+    #[test]
+    fn test_height_clamped_to_minimum() {
+        assert_eq!(launcher_height(20.0, 600.0, 1040, 1.0), 110);
+        assert_eq!(launcher_height(20.0, 600.0, 1560, 1.5), 165);
+        assert_eq!(launcher_height(-5.0, 600.0, 2080, 2.0), 220);
+    }
+
+    #[test]
+    fn test_height_clamped_to_max_setting() {
+        assert_eq!(launcher_height(5000.0, 600.0, 1040, 1.0), 600);
+        assert_eq!(launcher_height(5000.0, 600.0, 1560, 1.5), 900);
+        assert_eq!(launcher_height(5000.0, 600.0, 2080, 2.0), 1200);
+    }
+
+    #[test]
+    fn test_height_clamped_to_work_area() {
+        // 1.5 on a 900px-high work area leaves 600 logical pixels
+        assert_eq!(launcher_height(5000.0, 800.0, 720, 1.0), 720);
+        assert_eq!(launcher_height(5000.0, 800.0, 900, 1.5), 900);
+        assert_eq!(launcher_height(5000.0, 800.0, 1000, 2.0), 1000);
+    }
+
+    #[test]
+    fn test_fractional_heights_round() {
+        assert_eq!(launcher_height(123.3, 600.0, 1560, 1.5), 185);
+    }
+
+    #[test]
+    fn test_minimum_wins_over_tiny_limits() {
+        assert_eq!(launcher_height(300.0, 50.0, 1040, 1.0), 110);
+    }
+
+    // ===== Debounce =====
+
+    #[test]
+    fn test_only_newest_ticket_is_applied() {
+        let debouncer = ResizeDebouncer::default();
+
+        let first = debouncer.ticket();
+        let second = debouncer.ticket();
+
+        assert!(!debouncer.is_latest(first));
+        assert!(debouncer.is_latest(second));
+    }
+}
@@ -0,0 +1,179 @@
+//! Launcher show/hide decisions.
+//!
+//! The global shortcut, focus loss and pin mode interact; the decisions are
+//! kept here as plain functions so the whole matrix can be tested without
+//! a window.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+
+use crate::settings::ToggleBehavior;
+
+/// Whether the launcher is pinned. Not persisted; every launch starts
+/// unpinned.
+#[derive(Debug, Default)]
+pub struct LauncherState {
+    pinned: AtomicBool,
+}
+
+impl LauncherState {
+    /// Whether the launcher is pinned.
+    pub fn is_pinned(&self) -> bool {
+        self.pinned.load(Ordering::SeqCst)
+    }
+
+    /// Set the pin flag.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the flag changed.
+    pub fn set_pinned(&self, pinned: bool) -> bool {
+        self.pinned.swap(pinned, Ordering::SeqCst) != pinned
+    }
+}
+
+/// Payload of the `launcher-pin-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct LauncherPinChanged {
+    pub pinned: bool,
+}
+
+/// What the global shortcut should do to the launcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutAction {
+    /// Place and show the hidden launcher
+    Show,
+    /// Bring the visible launcher to the front
+    Focus,
+    /// Hide the launcher
+    Hide,
+    /// Clear pin mode, then hide the launcher
+    UnpinAndHide,
+}
+
+/// Decide what a shortcut press does.
+///
+/// A pinned launcher is focused first and only hidden (and unpinned) by a
+/// second press once it has focus. Unpinned, `toggle_behavior` decides
+/// whether a visible but unfocused launcher is focused or hidden.
+///
+/// # Arguments
+///
+/// * `visible` - Whether the launcher is showing
+/// * `focused` - Whether the launcher has keyboard focus
+/// * `pinned` - Whether pin mode is on
+/// * `behavior` - The `launcher.toggle_behavior` setting
+pub fn shortcut_action(
+    visible: bool,
+    focused: bool,
+    pinned: bool,
+    behavior: ToggleBehavior,
+) -> ShortcutAction {
+    match (visible, focused, pinned, behavior) {
+        (false, _, _, _) => ShortcutAction::Show,
+        (true, false, true, _) => ShortcutAction::Focus,
+        (true, true, true, _) => ShortcutAction::UnpinAndHide,
+        (true, false, false, ToggleBehavior::FocusFirst) => ShortcutAction::Focus,
+        (true, _, false, _) => ShortcutAction::Hide,
+    }
+}
+
+/// Decide whether the launcher hides when it loses focus.
+///
+/// # Arguments
+///
+/// * `pinned` - Whether pin mode is on
+/// * `hide_on_blur` - The `launcher.hide_on_blur` setting
+pub fn should_hide_on_blur(pinned: bool, hide_on_blur: bool) -> bool {
+    hide_on_blur && !pinned
+}
+
+/// Decide whether the launcher stays above other windows.
+///
+/// Pin mode forces it on top; unpinning falls back to the setting rather
+/// than turning it off.
+///
+/// # Arguments
+///
+/// * `pinned` - Whether pin mode is on
+/// * `always_on_top` - The `launcher.always_on_top` setting
+pub fn keeps_on_top(pinned: bool, always_on_top: bool) -> bool {
+    pinned || always_on_top
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ShortcutAction::*;
+    use ToggleBehavior::{FocusFirst, Toggle};
+
+    // ===== Shortcut =====
+
+    #[test]
+    fn test_hidden_launcher_is_shown() {
+        for pinned in [false, true] {
+            for behavior in [Toggle, FocusFirst] {
+                assert_eq!(shortcut_action(false, false, pinned, behavior), Show);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unpinned_toggle_hides() {
+        assert_eq!(shortcut_action(true, true, false, Toggle), Hide);
+        assert_eq!(shortcut_action(true, false, false, Toggle), Hide);
+    }
+
+    #[test]
+    fn test_unpinned_focus_first() {
+        assert_eq!(shortcut_action(true, false, false, FocusFirst), Focus);
+        assert_eq!(shortcut_action(true, true, false, FocusFirst), Hide);
+    }
+
+    #[test]
+    fn test_pinned_focuses_then_unpins() {
+        for behavior in [Toggle, FocusFirst] {
+            // First press brings it forward, the second one hides it
+            assert_eq!(shortcut_action(true, false, true, behavior), Focus);
+            assert_eq!(shortcut_action(true, true, true, behavior), UnpinAndHide);
+        }
+    }
+
+    // ===== Blur =====
+
+    #[test]
+    fn test_blur_matrix() {
+        assert!(should_hide_on_blur(false, true));
+        assert!(!should_hide_on_blur(true, true));
+        assert!(!should_hide_on_blur(false, false));
+        assert!(!should_hide_on_blur(true, false));
+    }
+
+    // ===== Always on top =====
+
+    #[test]
+    fn test_on_top_matrix() {
+        assert!(keeps_on_top(true, false));
+        assert!(keeps_on_top(true, true));
+        assert!(!keeps_on_top(false, false));
+        // Unpinning keeps the setting
+        assert!(keeps_on_top(false, true));
+    }
+
+    // ===== State =====
+
+    #[test]
+    fn test_set_pinned_reports_changes() {
+        let state = LauncherState::default();
+        assert!(!state.is_pinned());
+
+        assert!(state.set_pinned(true));
+        assert!(!state.set_pinned(true));
+        assert!(state.is_pinned());
+
+        assert!(state.set_pinned(false));
+        assert!(!state.is_pinned());
+    }
+}
@@ -0,0 +1,152 @@
+//! Launcher visibility events.
+//!
+//! Every show and hide of the launcher goes through
+//! [`show_launcher`](super::show_launcher) /
+//! [`hide_launcher`](super::hide_launcher), which report it as
+//! `launcher-shown` / `launcher-hidden` with the reason and time, so the
+//! frontend can tell a fresh summon from coming back after a blur.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Event sent after the launcher is shown.
+pub const SHOWN_EVENT: &str = "launcher-shown";
+
+/// Event sent after the launcher is hidden.
+pub const HIDDEN_EVENT: &str = "launcher-hidden";
+
+/// Why the launcher was shown or hidden.
+///
+/// Serializes to snake_case strings, e.g. `"settings_opened"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisibilityReason {
+    /// The global shortcut
+    Hotkey,
+    /// Another app took focus
+    Blur,
+    /// The tray menu
+    Tray,
+    /// A frontend command
+    Command,
+    /// The settings window was opened
+    SettingsOpened,
+    /// The window's close button or Alt+F4
+    Closed,
+    /// The Escape key
+    EscapeKey,
+    /// A system notification was clicked
+    Notification,
+    /// A `qwikask://` link was opened
+    DeepLink,
+    /// Command-line arguments such as `--query`
+    CommandLine,
+}
+
+/// Payload of `launcher-shown` and `launcher-hidden`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LauncherVisibilityChanged {
+    pub reason: VisibilityReason,
+    /// Unix timestamp (ms)
+    pub timestamp: i64,
+}
+
+/// Destination for visibility events; the app handle in production.
+pub trait VisibilityEmitter {
+    fn emit_visibility(&self, event: &str, payload: LauncherVisibilityChanged);
+}
+
+impl VisibilityEmitter for AppHandle {
+    fn emit_visibility(&self, event: &str, payload: LauncherVisibilityChanged) {
+        let _ = self.emit(event, payload);
+    }
+}
+
+/// Report that the launcher was shown.
+pub fn notify_shown(emitter: &impl VisibilityEmitter, reason: VisibilityReason, now_ms: i64) {
+    emitter.emit_visibility(
+        SHOWN_EVENT,
+        LauncherVisibilityChanged {
+            reason,
+            timestamp: now_ms,
+        },
+    );
+}
+
+/// Report that the launcher was hidden.
+pub fn notify_hidden(emitter: &impl VisibilityEmitter, reason: VisibilityReason, now_ms: i64) {
+    emitter.emit_visibility(
+        HIDDEN_EVENT,
+        LauncherVisibilityChanged {
+            reason,
+            timestamp: now_ms,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records emitted events instead of sending them.
+    #[derive(Default)]
+    struct RecordingEmitter {
+        events: Mutex<Vec<(String, LauncherVisibilityChanged)>>,
+    }
+
+    impl VisibilityEmitter for RecordingEmitter {
+        fn emit_visibility(&self, event: &str, payload: LauncherVisibilityChanged) {
+            self.events
+                .lock()
+                .unwrap()
+                .push((event.to_string(), payload));
+        }
+    }
+
+    fn changed(reason: VisibilityReason, timestamp: i64) -> LauncherVisibilityChanged {
+        LauncherVisibilityChanged { reason, timestamp }
+    }
+
+    #[test]
+    fn test_events_carry_reason_and_time() {
+        let emitter = RecordingEmitter::default();
+
+        notify_shown(&emitter, VisibilityReason::Hotkey, 1_000);
+        notify_hidden(&emitter, VisibilityReason::Blur, 3_000);
+        notify_shown(&emitter, VisibilityReason::Tray, 4_000);
+        notify_hidden(&emitter, VisibilityReason::SettingsOpened, 5_000);
+
+        assert_eq!(
+            *emitter.events.lock().unwrap(),
+            [
+                (
+                    "launcher-shown".to_string(),
+                    changed(VisibilityReason::Hotkey, 1_000)
+                ),
+                (
+                    "launcher-hidden".to_string(),
+                    changed(VisibilityReason::Blur, 3_000)
+                ),
+                (
+                    "launcher-shown".to_string(),
+                    changed(VisibilityReason::Tray, 4_000)
+                ),
+                (
+                    "launcher-hidden".to_string(),
+                    changed(VisibilityReason::SettingsOpened, 5_000)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reason_serializes_to_snake_case() {
+        let json = serde_json::to_value(changed(VisibilityReason::SettingsOpened, 7)).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({ "reason": "settings_opened", "timestamp": 7 })
+        );
+    }
+}
@@ -27,7 +27,7 @@
 import { ref, computed } from 'vue';
 import type { Message } from '../types/chat';
 import type { HistoryMessage } from '../types/history';
-import type { LlmProvider } from '../types/settings';
+import type { AzureSettings, LlmProvider } from '../types/settings';
 import { getProvider, generateTitle, type LlmConfig } from '../services/llm';
 import * as historyDb from '../services/historyDb';
 
@@ -73,6 +73,7 @@ export interface ChatLlmConfig {
     apiKey: string;
     model: string;
     baseUrl?: string;
+    azure?: AzureSettings;
 }
 
 /**
@@ -153,6 +154,7 @@ export function useChat() {
             apiKey: llmConfig.apiKey,
             model: llmConfig.model,
             baseUrl: llmConfig.baseUrl,
+            azure: llmConfig.azure,
         };
 
         await provider.streamChat(config, messages.value.slice(0, -1), {
@@ -0,0 +1,130 @@
+/**
+ * @fileoverview Azure OpenAI service for LLM interactions.
+ *
+ * Request and response bodies, streamed chunks included, are OpenAI's
+ * (see ./openai); only addressing and auth differ:
+ *
+ * - The URL names the deployment instead of the model:
+ *   `{endpoint}/openai/deployments/{deployment}/chat/completions?api-version=…`
+ * - The key is sent in an `api-key` header instead of a bearer token
+ *
+ * @module services/llm/azure
+ */
+
+import type { Message } from '../../types/chat';
+import { DEFAULT_AZURE_API_VERSION } from '../../types/settings';
+import type { LlmConfig, StreamCallbacks } from './types';
+import { convertToOpenAIMessages, readChatStream } from './openai';
+
+/**
+ * Chat completions URL for the configured deployment, or null if the
+ * resource (or base URL) or deployment is missing.
+ *
+ * @param config - LLM configuration with `azure` set
+ */
+export function chatCompletionsUrl(config: LlmConfig): string | null {
+    const azure = config.azure;
+    if (!azure?.deployment || (!azure.resource && !config.baseUrl)) {
+        return null;
+    }
+
+    const endpoint = config.baseUrl
+        ? config.baseUrl.replace(/\/+$/, '')
+        : `https://${azure.resource}.openai.azure.com`;
+    const apiVersion = azure.api_version || DEFAULT_AZURE_API_VERSION;
+    return `${endpoint}/openai/deployments/${encodeURIComponent(azure.deployment)}/chat/completions?api-version=${encodeURIComponent(apiVersion)}`;
+}
+
+function headers(config: LlmConfig): Record<string, string> {
+    return {
+        'Content-Type': 'application/json',
+        'api-key': config.apiKey,
+    };
+}
+
+/**
+ * Stream a chat completion from an Azure OpenAI deployment.
+ *
+ * @param config - LLM configuration (apiKey, azure, optional baseUrl)
+ * @param messages - Conversation history
+ * @param callbacks - Event handlers for streaming
+ * @param systemPrompt - Optional system instructions
+ */
+export async function streamChat(
+    config: LlmConfig,
+    messages: Message[],
+    callbacks: StreamCallbacks,
+    systemPrompt?: string
+): Promise<void> {
+    if (!config.apiKey) {
+        callbacks.onError('API key is not configured. Please add your API key in Settings.');
+        return;
+    }
+
+    const url = chatCompletionsUrl(config);
+    if (!url) {
+        callbacks.onError('Azure OpenAI resource and deployment are not configured. Please add them in Settings.');
+        return;
+    }
+
+    try {
+        const response = await fetch(url, {
+            method: 'POST',
+            headers: headers(config),
+            body: JSON.stringify({
+                model: config.model,
+                messages: convertToOpenAIMessages(messages, systemPrompt),
+                stream: true,
+                temperature: 0.7,
+                max_tokens: 8192,
+            }),
+        });
+
+        await readChatStream(response, callbacks);
+    } catch (error) {
+        const message = error instanceof Error ? error.message : 'Unknown error occurred';
+        callbacks.onError(message);
+    }
+}
+
+/**
+ * Perform a simple, non-streaming completion.
+ * Returns just the text response for a given prompt.
+ *
+ * @param config - LLM configuration
+ * @param prompt - The prompt to send
+ * @returns The text response or empty string on error
+ * @internal Used by the shared generateTitle utility
+ */
+export async function simpleCompletion(
+    config: LlmConfig,
+    prompt: string
+): Promise<string> {
+    const url = chatCompletionsUrl(config);
+    if (!url) {
+        return "";
+    }
+
+    try {
+        const response = await fetch(url, {
+            method: 'POST',
+            headers: headers(config),
+            body: JSON.stringify({
+                model: config.model,
+                messages: [{ role: 'user', content: prompt }],
+                temperature: 0.7,
+                max_tokens: 50,
+            }),
+        });
+
+        if (!response.ok) {
+            return "";
+        }
+
+        const data = await response.json();
+        return data.choices?.[0]?.message?.content?.trim() || "";
+    } catch (e) {
+        console.error('Simple completion failed:', e);
+        return "";
+    }
+}
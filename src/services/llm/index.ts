@@ -21,6 +21,7 @@
 import * as gemini from './gemini';
 import * as openai from './openai';
 import * as anthropic from './anthropic';
+import * as azure from './azure';
 import type { LlmProvider } from '../../types/settings';
 import type { LlmConfig, StreamCallbacks } from './types';
 import type { Message } from '../../types/chat';
@@ -47,7 +48,7 @@ export interface Provider {
 /**
  * Get the LLM provider service for a given provider type.
  * 
- * @param provider - The provider type ('gemini', 'openai', 'anthropic', 'custom')
+ * @param provider - The provider type ('gemini', 'openai', 'anthropic', 'azureopenai', 'custom')
  * @returns The provider service module
  * @throws Error if provider is unknown
  */
@@ -59,6 +60,8 @@ export function getProvider(provider: LlmProvider): Provider {
             return openai;
         case 'anthropic':
             return anthropic;
+        case 'azureopenai':
+            return azure;
         case 'custom':
             // Custom uses OpenAI-compatible format
             return openai;
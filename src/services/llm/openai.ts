@@ -25,7 +25,7 @@ interface OpenAIMessage {
 /**
  * Convert our Message format to OpenAI's expected format.
  */
-function convertToOpenAIMessages(messages: Message[], systemPrompt?: string): OpenAIMessage[] {
+export function convertToOpenAIMessages(messages: Message[], systemPrompt?: string): OpenAIMessage[] {
     const result: OpenAIMessage[] = [];
 
     if (systemPrompt) {
@@ -42,6 +42,79 @@ function convertToOpenAIMessages(messages: Message[], systemPrompt?: string): Op
     return result;
 }
 
+/**
+ * Read a streamed chat completion response (Server-Sent Events carrying
+ * `chat.completion.chunk` objects) into the callbacks.
+ *
+ * Shared with Azure OpenAI, which streams the same chunks.
+ *
+ * @param response - Response to a request sent with `stream: true`
+ * @param callbacks - Event handlers for streaming
+ */
+export async function readChatStream(
+    response: Response,
+    callbacks: StreamCallbacks
+): Promise<void> {
+    if (!response.ok) {
+        const errorData = await response.json().catch(() => ({}));
+        const errorMessage = errorData?.error?.message || `HTTP error ${response.status}`;
+        callbacks.onError(errorMessage);
+        return;
+    }
+
+    if (!response.body) {
+        callbacks.onError('No response body received');
+        return;
+    }
+
+    const reader = response.body.getReader();
+    const decoder = new TextDecoder();
+    let buffer = '';
+
+    while (true) {
+        const { done, value } = await reader.read();
+
+        if (done) {
+            callbacks.onComplete();
+            break;
+        }
+
+        buffer += decoder.decode(value, { stream: true });
+
+        const lines = buffer.split('\n');
+        buffer = lines.pop() || '';
+
+        for (const line of lines) {
+            const trimmedLine = line.trim();
+
+            if (trimmedLine === '' || trimmedLine === 'data: [DONE]') {
+                continue;
+            }
+
+            if (trimmedLine.startsWith('data: ')) {
+                const jsonStr = trimmedLine.slice(6);
+
+                try {
+                    const data = JSON.parse(jsonStr);
+
+                    if (data.error) {
+                        callbacks.onError(data.error.message || 'Unknown error');
+                        return;
+                    }
+
+                    const content = data.choices?.[0]?.delta?.content;
+                    if (content) {
+                        callbacks.onToken(content);
+                    }
+                } catch (e) {
+                    // Skip malformed JSON
+                    console.warn('Failed to parse SSE data:', jsonStr);
+                }
+            }
+        }
+    }
+}
+
 /**
  * Stream a chat completion from OpenAI API (or compatible endpoint).
  * 
@@ -89,64 +162,7 @@ export async function streamChat(
             }),
         });
 
-        if (!response.ok) {
-            const errorData = await response.json().catch(() => ({}));
-            const errorMessage = errorData?.error?.message || `HTTP error ${response.status}`;
-            callbacks.onError(errorMessage);
-            return;
-        }
-
-        if (!response.body) {
-            callbacks.onError('No response body received');
-            return;
-        }
-
-        const reader = response.body.getReader();
-        const decoder = new TextDecoder();
-        let buffer = '';
-
-        while (true) {
-            const { done, value } = await reader.read();
-
-            if (done) {
-                callbacks.onComplete();
-                break;
-            }
-
-            buffer += decoder.decode(value, { stream: true });
-
-            const lines = buffer.split('\n');
-            buffer = lines.pop() || '';
-
-            for (const line of lines) {
-                const trimmedLine = line.trim();
-
-                if (trimmedLine === '' || trimmedLine === 'data: [DONE]') {
-                    continue;
-                }
-
-                if (trimmedLine.startsWith('data: ')) {
-                    const jsonStr = trimmedLine.slice(6);
-
-                    try {
-                        const data = JSON.parse(jsonStr);
-
-                        if (data.error) {
-                            callbacks.onError(data.error.message || 'Unknown error');
-                            return;
-                        }
-
-                        const content = data.choices?.[0]?.delta?.content;
-                        if (content) {
-                            callbacks.onToken(content);
-                        }
-                    } catch (e) {
-                        // Skip malformed JSON
-                        console.warn('Failed to parse SSE data:', jsonStr);
-                    }
-                }
-            }
-        }
+        await readChatStream(response, callbacks);
     } catch (error) {
         const message = error instanceof Error ? error.message : 'Unknown error occurred';
         callbacks.onError(message);
@@ -7,6 +7,7 @@
  */
 
 import type { Message } from '../../types/chat';
+import type { AzureSettings } from '../../types/settings';
 
 /**
  * Callbacks for handling streaming response events.
@@ -30,6 +31,8 @@ export interface LlmConfig {
     model: string;
     /** Base URL for API (used for custom OpenAI-compatible endpoints) */
     baseUrl?: string;
+    /** Azure OpenAI deployment (used by the azureopenai provider) */
+    azure?: AzureSettings;
 }
 
 /**
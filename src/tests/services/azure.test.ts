@@ -0,0 +1,114 @@
+/**
+ * Tests for Azure OpenAI service.
+ */
+
+import { describe, it, expect, vi, beforeEach } from 'vitest';
+import { chatCompletionsUrl, simpleCompletion, streamChat } from '../../services/llm/azure';
+import type { LlmConfig } from '../../services/llm/types';
+
+// Mock fetch globally
+const mockFetch = vi.fn();
+vi.stubGlobal('fetch', mockFetch);
+
+const config: LlmConfig = {
+    apiKey: 'test-api-key',
+    model: 'gpt-4o',
+    azure: { resource: 'contoso', deployment: 'chat', api_version: '2024-10-21' },
+};
+
+function sseBody(lines: string[]): ReadableStream<Uint8Array> {
+    const encoder = new TextEncoder();
+    return new ReadableStream({
+        start(controller) {
+            for (const line of lines) {
+                controller.enqueue(encoder.encode(`${line}\n\n`));
+            }
+            controller.close();
+        },
+    });
+}
+
+describe('azure service', () => {
+    beforeEach(() => {
+        mockFetch.mockReset();
+    });
+
+    describe('chatCompletionsUrl', () => {
+        it('should address the deployment on the resource', () => {
+            expect(chatCompletionsUrl(config)).toBe(
+                'https://contoso.openai.azure.com/openai/deployments/chat/chat/completions?api-version=2024-10-21'
+            );
+        });
+
+        it('should prefer the base URL over the resource', () => {
+            const url = chatCompletionsUrl({ ...config, baseUrl: 'https://proxy.example.com/' });
+            expect(url).toBe(
+                'https://proxy.example.com/openai/deployments/chat/chat/completions?api-version=2024-10-21'
+            );
+        });
+
+        it('should return null without a deployment', () => {
+            const url = chatCompletionsUrl({ ...config, azure: { resource: 'contoso', deployment: '', api_version: '' } });
+            expect(url).toBeNull();
+        });
+    });
+
+    describe('simpleCompletion', () => {
+        it('should send the key in the api-key header', async () => {
+            mockFetch.mockResolvedValueOnce({
+                ok: true,
+                json: () => Promise.resolve({
+                    choices: [{ message: { content: ' TypeScript Basics ' } }],
+                }),
+            });
+
+            const result = await simpleCompletion(config, 'Generate a title');
+
+            expect(result).toBe('TypeScript Basics');
+            const [, init] = mockFetch.mock.calls[0];
+            expect(init.headers['api-key']).toBe('test-api-key');
+            expect(init.headers.Authorization).toBeUndefined();
+        });
+
+        it('should return empty string on API error', async () => {
+            mockFetch.mockResolvedValueOnce({ ok: false, status: 401 });
+
+            const result = await simpleCompletion(config, 'Generate a title');
+
+            expect(result).toBe('');
+        });
+    });
+
+    describe('streamChat', () => {
+        it('should stream OpenAI chunks, skipping prompt filter results', async () => {
+            mockFetch.mockResolvedValueOnce({
+                ok: true,
+                body: sseBody([
+                    'data: {"choices":[],"prompt_filter_results":[]}',
+                    'data: {"choices":[{"delta":{"content":"Hello"}}]}',
+                    'data: {"choices":[{"delta":{"content":" there"}}]}',
+                    'data: [DONE]',
+                ]),
+            });
+
+            const chunks: string[] = [];
+            const onComplete = vi.fn();
+            const onError = vi.fn();
+            await streamChat(config, [], { onToken: (t) => chunks.push(t), onComplete, onError });
+
+            expect(chunks.join('')).toBe('Hello there');
+            expect(onComplete).toHaveBeenCalledOnce();
+            expect(onError).not.toHaveBeenCalled();
+            const [, init] = mockFetch.mock.calls[0];
+            expect(JSON.parse(init.body).stream).toBe(true);
+        });
+
+        it('should report a missing deployment without calling fetch', async () => {
+            const onError = vi.fn();
+            await streamChat({ ...config, azure: undefined }, [], { onToken: vi.fn(), onComplete: vi.fn(), onError });
+
+            expect(onError).toHaveBeenCalledOnce();
+            expect(mockFetch).not.toHaveBeenCalled();
+        });
+    });
+});
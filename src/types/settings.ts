@@ -1,5 +1,5 @@
 export type Theme = 'dark' | 'light' | 'system';
-export type LlmProvider = 'gemini' | 'openai' | 'anthropic' | 'custom';
+export type LlmProvider = 'gemini' | 'openai' | 'anthropic' | 'azureopenai' | 'custom';
 
 export interface GeneralSettings {
     auto_startup: boolean;
@@ -19,11 +19,27 @@ Guidelines:
 - If a question is ambiguous, give the most likely answer first, then briefly mention alternatives
 - Avoid unnecessary pleasantries - get straight to the point`;
 
+/**
+ * Azure OpenAI deployment, used by the `azureopenai` provider.
+ *
+ * Requests go to `https://{resource}.openai.azure.com`, or to `base_url`
+ * when one is set.
+ */
+export interface AzureSettings {
+    resource: string;
+    deployment: string;
+    api_version: string;
+}
+
+/** Azure OpenAI API version used when none is configured. */
+export const DEFAULT_AZURE_API_VERSION = '2024-10-21';
+
 export interface LlmSettings {
     provider: LlmProvider;
     api_key: string;
     model: string;
     base_url?: string;
+    azure?: AzureSettings;
     system_prompt: string;
 }
 
@@ -55,6 +71,8 @@ export const PROVIDER_MODELS: Record<LlmProvider, { id: string; name: string }[]
         { id: 'claude-sonnet-4-5', name: 'Claude 4.5 Sonnet (Balanced)' },
         { id: 'claude-opus-4-5', name: 'Claude 4.5 Opus (Smart)' },
     ],
+    // The deployment picks the model
+    azureopenai: [],
     custom: [],
 };
 